@@ -81,14 +81,56 @@ export default defineConfig({
           { text: "add", link: "/reference/commands/add" },
           { text: "merge", link: "/reference/commands/merge" },
           { text: "remove", link: "/reference/commands/remove" },
+          { text: "prune", link: "/reference/commands/prune" },
+          { text: "gc", link: "/reference/commands/gc" },
+          { text: "sync", link: "/reference/commands/sync" },
+          { text: "grep", link: "/reference/commands/grep" },
+          { text: "exec", link: "/reference/commands/exec" },
           { text: "list", link: "/reference/commands/list" },
+          { text: "history", link: "/reference/commands/history" },
+          { text: "activity", link: "/reference/commands/activity" },
+          { text: "report", link: "/reference/commands/report" },
           { text: "open", link: "/reference/commands/open" },
+          { text: "switch", link: "/reference/commands/switch" },
           { text: "close", link: "/reference/commands/close" },
+          { text: "apply", link: "/reference/commands/apply" },
+          { text: "apply-patch", link: "/reference/commands/apply-patch" },
+          { text: "export", link: "/reference/commands/export" },
+          { text: "import-work", link: "/reference/commands/import-work" },
+          { text: "archive", link: "/reference/commands/archive" },
+          { text: "restore", link: "/reference/commands/restore" },
+          {
+            text: "review-with-agent",
+            link: "/reference/commands/review-with-agent",
+          },
+          { text: "diff", link: "/reference/commands/diff" },
+          { text: "summarize", link: "/reference/commands/summarize" },
+          { text: "tidy", link: "/reference/commands/tidy" },
+          {
+            text: "split-commits",
+            link: "/reference/commands/split-commits",
+          },
           { text: "path", link: "/reference/commands/path" },
+          { text: "edit", link: "/reference/commands/edit" },
+          { text: "which", link: "/reference/commands/which" },
+          { text: "status", link: "/reference/commands/status" },
+          { text: "state", link: "/reference/commands/state" },
+          { text: "pin / unpin", link: "/reference/commands/pin" },
+          { text: "layout", link: "/reference/commands/layout" },
           { text: "dashboard", link: "/reference/commands/dashboard" },
+          { text: "menu", link: "/reference/commands/menu" },
+          { text: "respond", link: "/reference/commands/respond" },
+          { text: "reprompt", link: "/reference/commands/reprompt" },
           { text: "init", link: "/reference/commands/init" },
+          { text: "config", link: "/reference/commands/config" },
+          { text: "agents", link: "/reference/commands/agents" },
           { text: "claude prune", link: "/reference/commands/claude" },
           { text: "completions", link: "/reference/commands/completions" },
+          { text: "version", link: "/reference/commands/version" },
+          { text: "doctor", link: "/reference/commands/doctor" },
+          { text: "hooks", link: "/reference/commands/hooks" },
+          { text: "selftest", link: "/reference/commands/selftest" },
+          { text: "upgrade", link: "/reference/commands/upgrade" },
           { text: "docs", link: "/reference/commands/docs" },
         ],
       },
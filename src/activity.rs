@@ -0,0 +1,184 @@
+//! Append-only feed of agent status changes, shown by `workmux activity`.
+//!
+//! Each line of `activity.log` in the state directory is one JSON entry,
+//! written by `set-window-status` when an agent's status changes. Once the
+//! log outgrows [`MAX_LOG_BYTES`], its older half is dropped. Like the audit
+//! log, recording is best-effort.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::config::StatusIcons;
+use crate::{dry_run, git, logger};
+
+const ACTIVITY_FILE: &str = "activity.log";
+
+/// Size past which the oldest entries are dropped
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AgentStatus {
+    Working,
+    Waiting,
+    Done,
+}
+
+impl AgentStatus {
+    /// The status a window's icon stands for, with the configured icons
+    pub fn from_icon(icon: &str, icons: &StatusIcons) -> Option<Self> {
+        if icon == icons.working() {
+            Some(Self::Working)
+        } else if icon == icons.waiting() {
+            Some(Self::Waiting)
+        } else if icon == icons.done() {
+            Some(Self::Done)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Working => "working",
+            Self::Waiting => "waiting",
+            Self::Done => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    /// Worktree the agent runs in
+    pub handle: String,
+    /// Status before the change, None if there was none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<AgentStatus>,
+    /// Status after the change, None if it was cleared
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<AgentStatus>,
+    /// Main worktree of the repository the worktree belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+}
+
+impl ActivityEntry {
+    /// The change, e.g. `working → waiting`
+    pub fn transition(&self) -> String {
+        let label = |status: Option<AgentStatus>| status.map_or("-", |status| status.label());
+        format!("{} → {}", label(self.from), label(self.to))
+    }
+}
+
+/// Append a status change to the feed, logging (not returning) any failure.
+/// Changes to the same status are not recorded.
+pub fn record(handle: &str, from: Option<AgentStatus>, to: Option<AgentStatus>) {
+    if from == to || dry_run::is_enabled() {
+        return;
+    }
+    let entry = ActivityEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        handle: handle.to_string(),
+        from,
+        to,
+        repo: git::get_main_worktree_root().ok(),
+    };
+    if let Err(e) = append(&entry) {
+        warn!(error = %e, handle, "activity:failed to record status change");
+    }
+}
+
+/// The raw feed, one JSON entry per line, empty if nothing was recorded yet
+pub fn read_log() -> Result<String> {
+    let path = log_path()?;
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to read activity log '{}'", path.display()))
+        }
+    }
+}
+
+pub fn log_path() -> Result<PathBuf> {
+    Ok(logger::state_dir()?.join(ACTIVITY_FILE))
+}
+
+fn append(entry: &ActivityEntry) -> Result<()> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory {}", parent.display()))?;
+    }
+
+    if fs::metadata(&path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        let content = fs::read_to_string(&path)?;
+        let staged = path.with_extension("log.tmp");
+        fs::write(&staged, newer_half(&content))?;
+        fs::rename(&staged, &path)
+            .with_context(|| format!("Failed to trim activity log '{}'", path.display()))?;
+    }
+
+    // A single write per entry keeps lines whole when hooks run concurrently
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write activity log '{}'", path.display()))
+}
+
+/// The lines in the second half of the log
+fn newer_half(content: &str) -> &str {
+    let middle = content.len() / 2;
+    content.as_bytes()[middle..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or("", |newline| &content[middle + newline + 1..])
+}
+
+/// Parse the log, skipping lines that are not valid entries (e.g. cut short by a crash).
+pub fn parse_entries(content: &str) -> Vec<ActivityEntry> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_and_trim_to_whole_lines() {
+        let entry = ActivityEntry {
+            timestamp: 1_700_000_000,
+            handle: "auth".to_string(),
+            from: Some(AgentStatus::Working),
+            to: Some(AgentStatus::Waiting),
+            repo: Some(PathBuf::from("/src/app")),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        let content = format!("{}\n{{\"timest\n{}\n", line, line);
+        assert_eq!(parse_entries(&content), vec![entry.clone(), entry.clone()]);
+        assert_eq!(entry.transition(), "working → waiting");
+
+        let cleared = ActivityEntry { to: None, ..entry };
+        assert_eq!(cleared.transition(), "working → -");
+
+        assert_eq!(newer_half("one\ntwo\nthree\nfour\n"), "four\n");
+        assert_eq!(newer_half("one\n"), "");
+    }
+}
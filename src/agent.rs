@@ -0,0 +1,355 @@
+//! What workmux knows about agent CLIs.
+//!
+//! Built-in profiles say how the agents workmux knows take the prompt they
+//! start with and how their status reaches the tmux window list. The `agents`
+//! config section defines more agents by name, or changes a built-in one; any
+//! other command gets the prompt after `--`.
+
+use std::path::Path;
+
+use crate::config::{self, Config, PromptInput, StatusTracking};
+
+#[derive(Debug)]
+pub struct AgentProfile {
+    pub name: &'static str,
+    pub prompt: PromptInput,
+    /// Argument put before the prompt text or file
+    pub prompt_flag: &'static str,
+    pub status: StatusTracking,
+}
+
+/// Built-in agent profiles
+pub const PROFILES: &[AgentProfile] = &[
+    AgentProfile {
+        name: "aider",
+        prompt: PromptInput::File,
+        prompt_flag: "--message-file",
+        status: StatusTracking::Exit,
+    },
+    AgentProfile {
+        name: "claude",
+        prompt: PromptInput::Arg,
+        prompt_flag: "--",
+        status: StatusTracking::Hooks,
+    },
+    AgentProfile {
+        name: "codex",
+        prompt: PromptInput::Arg,
+        prompt_flag: "--",
+        status: StatusTracking::None,
+    },
+    AgentProfile {
+        name: "copilot",
+        prompt: PromptInput::Arg,
+        prompt_flag: "-i",
+        status: StatusTracking::Hooks,
+    },
+    AgentProfile {
+        name: "gemini",
+        prompt: PromptInput::Arg,
+        prompt_flag: "-i",
+        status: StatusTracking::Hooks,
+    },
+    AgentProfile {
+        name: "goose",
+        prompt: PromptInput::File,
+        // Stays in the session once the instructions are done
+        prompt_flag: "run --interactive --instructions",
+        status: StatusTracking::None,
+    },
+    AgentProfile {
+        name: "opencode",
+        prompt: PromptInput::Arg,
+        prompt_flag: "--prompt",
+        status: StatusTracking::Hooks,
+    },
+];
+
+/// Prompt argument for agents without a profile
+const DEFAULT_PROMPT_FLAG: &str = "--";
+
+/// The profile for an executable name, e.g. "claude" or "gemini"
+pub fn profile(name: &str) -> Option<&'static AgentProfile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
+
+/// An agent as workmux runs it: its `agents` definition over its built-in
+/// profile, or the defaults for an agent that has neither
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agent {
+    pub name: String,
+    /// Command that starts the agent
+    pub command: String,
+    pub prompt: PromptInput,
+    pub prompt_flag: String,
+    /// None when neither a profile nor a definition says
+    pub status: Option<StatusTracking>,
+    /// Whether workmux has a built-in profile for the agent
+    pub built_in: bool,
+    /// Whether the `agents` config section defines the agent
+    pub defined: bool,
+}
+
+impl Agent {
+    /// An agent workmux knows nothing about, run as `name`
+    pub fn unknown(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            command: name.to_string(),
+            prompt: PromptInput::Arg,
+            prompt_flag: DEFAULT_PROMPT_FLAG.to_string(),
+            status: None,
+            built_in: false,
+            defined: false,
+        }
+    }
+
+    /// Arguments that give the agent the prompt in `prompt_path`, appended to
+    /// its command, e.g. ` -- "$(cat PROMPT.md)"`
+    pub fn prompt_args(&self, prompt_path: &str) -> String {
+        let flag = if self.prompt_flag.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", self.prompt_flag)
+        };
+        match self.prompt {
+            PromptInput::Arg => format!("{} \"$(cat {})\"", flag, prompt_path),
+            PromptInput::File => format!("{} {}", flag, prompt_path),
+            PromptInput::Stdin => format!(" < {}", prompt_path),
+        }
+    }
+}
+
+/// The agent named `name`, if the `agents` config section or a built-in
+/// profile knows it
+pub fn find(config: &Config, name: &str) -> Option<Agent> {
+    let definition = config.agents.as_ref().and_then(|agents| agents.get(name));
+    let profile = profile(name);
+    if definition.is_none() && profile.is_none() {
+        return None;
+    }
+    let definition = definition.cloned().unwrap_or_default();
+    let prompt = definition
+        .prompt
+        .or(profile.map(|profile| profile.prompt))
+        .unwrap_or_default();
+    // A profile's flag only fits the way the profile passes the prompt
+    let profile_flag = profile
+        .filter(|profile| profile.prompt == prompt)
+        .map(|profile| profile.prompt_flag.to_string());
+    let prompt_flag = definition
+        .prompt_flag
+        .or(profile_flag)
+        .unwrap_or_else(|| match prompt {
+            PromptInput::Arg => DEFAULT_PROMPT_FLAG.to_string(),
+            PromptInput::File | PromptInput::Stdin => String::new(),
+        });
+    Some(Agent {
+        name: name.to_string(),
+        command: definition.command.unwrap_or_else(|| name.to_string()),
+        prompt,
+        prompt_flag,
+        status: definition.status.or(profile.map(|profile| profile.status)),
+        built_in: profile.is_some(),
+        defined: config
+            .agents
+            .as_ref()
+            .is_some_and(|agents| agents.contains_key(name)),
+    })
+}
+
+/// The agent a command runs, by its executable name: a defined agent of that
+/// name or whose command runs it, else the built-in profile of that name
+pub fn for_command(config: &Config, command: &str) -> Option<Agent> {
+    let executable = name(command)?;
+    let defined = config.agents.iter().flatten().find(|(agent, definition)| {
+        **agent == executable
+            || definition.command.as_deref().and_then(name).as_deref() == Some(&executable)
+    });
+    match defined {
+        Some((agent, _)) => find(config, agent),
+        None => find(config, &executable),
+    }
+}
+
+/// The command that runs `agent`, which may be an agent's name or already a
+/// command, as in `agent` or `--agent`
+pub fn command(config: &Config, agent: &str) -> String {
+    find(config, agent.trim()).map_or_else(|| agent.to_string(), |agent| agent.command)
+}
+
+/// `command` run so that the window shows the agent working until it exits,
+/// for agents whose status is tracked by [`StatusTracking::Exit`]
+pub fn track_until_exit(command: &str) -> String {
+    format!(
+        " workmux set-window-status working; {}; workmux set-window-status done",
+        command.trim_start()
+    )
+}
+
+/// Executable name of an agent command, e.g. "claude" for
+/// "/usr/local/bin/claude --verbose"
+pub fn name(command: &str) -> Option<String> {
+    let (executable, _) = config::split_first_token(command)?;
+    Path::new(executable)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+}
+
+/// Where the configuration runs an agent command
+#[derive(Debug, PartialEq)]
+pub struct AgentUsage {
+    /// The agent command, with arguments
+    pub command: String,
+    /// What runs it, e.g. "default agent" or "team 'default' (reviewer)"
+    pub used_by: String,
+}
+
+/// Every agent command the configuration runs: the default agent and the
+/// panes that run it, review agents, team members, and panes of the top-level
+/// config or a template that run a known agent directly.
+pub fn usages(config: &Config) -> Vec<AgentUsage> {
+    let default_agent = config.agent.as_deref().unwrap_or("claude");
+    let mut usages = Vec::new();
+    let mut add = |command: &str, used_by: String| {
+        let usage = AgentUsage {
+            command: command.trim().to_string(),
+            used_by,
+        };
+        if !usage.command.is_empty() && !usages.contains(&usage) {
+            usages.push(usage);
+        }
+    };
+
+    add(default_agent, "default agent".to_string());
+
+    let mut pane_sets = vec![("panes".to_string(), config.panes.as_deref())];
+    for (name, template) in config.templates.iter().flatten() {
+        pane_sets.push((format!("template '{}'", name), template.panes.as_deref()));
+    }
+    for (source, panes) in pane_sets {
+        for command in panes
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.command.as_deref())
+        {
+            let command = match command.trim().strip_prefix("<agent>") {
+                Some(args) => format!("{}{}", default_agent, args),
+                None => command.to_string(),
+            };
+            let is_agent = config::is_agent_command(&command, default_agent)
+                || for_command(config, &command).is_some();
+            if is_agent {
+                add(&command, source.clone());
+            }
+        }
+    }
+
+    if let Some(agent) = config.review.as_ref().and_then(|r| r.agent.as_deref()) {
+        add(&command(config, agent), "review".to_string());
+    }
+    for (team, members) in config.team.iter().flatten() {
+        for member in members {
+            let agent = member
+                .agent
+                .as_deref()
+                .map_or_else(|| default_agent.to_string(), |agent| command(config, agent));
+            add(&agent, format!("team '{}' ({})", team, member.role));
+        }
+    }
+
+    usages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_flags_follow_the_profile() {
+        let config = Config::default();
+        let prompt = |command: &str| {
+            for_command(&config, command)
+                .unwrap_or_else(|| Agent::unknown(command))
+                .prompt_args("PROMPT.md")
+        };
+        assert_eq!(prompt("gemini"), " -i \"$(cat PROMPT.md)\"");
+        assert_eq!(prompt("opencode"), " --prompt \"$(cat PROMPT.md)\"");
+        assert_eq!(prompt("my-agent"), " -- \"$(cat PROMPT.md)\"");
+        assert_eq!(prompt("aider"), " --message-file PROMPT.md");
+        assert_eq!(name("/opt/bin/claude --verbose").as_deref(), Some("claude"));
+    }
+
+    #[test]
+    fn definitions_name_agents_and_change_built_in_ones() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+agents:
+  fast:
+    command: codex --full-auto
+    prompt: stdin
+    status: exit
+  gemini:
+    prompt: file
+  aider:
+    command: aider --no-auto-commits
+"#,
+        )
+        .unwrap();
+        assert_eq!(command(&config, "fast"), "codex --full-auto");
+        assert_eq!(command(&config, "claude --verbose"), "claude --verbose");
+
+        let fast = for_command(&config, "codex --full-auto").unwrap();
+        assert_eq!(fast.name, "fast");
+        assert_eq!(fast.prompt_args("PROMPT.md"), " < PROMPT.md");
+        assert_eq!(fast.status, Some(StatusTracking::Exit));
+        assert!(fast.defined && !fast.built_in);
+
+        // The built-in `-i` is an argument for the prompt text, not a file
+        let gemini = find(&config, "gemini").unwrap();
+        assert_eq!(gemini.prompt_args("PROMPT.md"), " PROMPT.md");
+        assert_eq!(gemini.status, Some(StatusTracking::Hooks));
+
+        let aider = find(&config, "aider").unwrap();
+        assert_eq!(aider.command, "aider --no-auto-commits");
+        assert_eq!(aider.prompt_args("P.md"), " --message-file P.md");
+        assert!(find(&config, "my-agent").is_none());
+    }
+
+    #[test]
+    fn usages_cover_default_agent_panes_review_and_teams() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+agent: claude
+panes:
+  - command: <agent> --verbose
+  - command: npm run dev
+  - command: codex
+review:
+  agent: gemini
+team:
+  pair:
+    - role: implementer
+    - role: reviewer
+      agent: gemini
+"#,
+        )
+        .unwrap();
+        let usages: Vec<(String, String)> = usages(&config)
+            .into_iter()
+            .map(|usage| (usage.command, usage.used_by))
+            .collect();
+        let expected = [
+            ("claude", "default agent"),
+            ("claude --verbose", "panes"),
+            ("codex", "panes"),
+            ("gemini", "review"),
+            ("claude", "team 'pair' (implementer)"),
+            ("gemini", "team 'pair' (reviewer)"),
+        ];
+        assert_eq!(
+            usages,
+            expected.map(|(command, used_by)| (command.to_string(), used_by.to_string()))
+        );
+    }
+}
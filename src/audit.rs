@@ -0,0 +1,153 @@
+//! Append-only audit log of destructive actions, shown by `workmux history`.
+//!
+//! Each line of `audit.log` in the state directory is one JSON entry. Recording
+//! is best-effort: failing to write the log never aborts the action itself.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::cmd::shell_quote;
+use crate::{dry_run, git, logger};
+
+const AUDIT_FILE: &str = "audit.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditAction {
+    /// A local branch was deleted
+    BranchDeleted,
+    /// A branch was deleted on a remote
+    RemoteBranchDeleted,
+    /// A worktree directory was removed
+    WorktreeRemoved,
+    /// A tmux window was killed
+    WindowKilled,
+}
+
+impl AuditAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::BranchDeleted => "branch deleted",
+            Self::RemoteBranchDeleted => "remote branch deleted",
+            Self::WorktreeRemoved => "worktree removed",
+            Self::WindowKilled => "window killed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    pub action: AuditAction,
+    /// Branch name, worktree path or window name
+    pub target: String,
+    /// Extra context, such as the commit a deleted branch pointed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Main worktree of the repository the action happened in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+    /// The workmux invocation that performed the action
+    pub command: String,
+}
+
+/// Append an action to the audit log, logging (not returning) any failure.
+pub fn record(action: AuditAction, target: &str, detail: Option<&str>) {
+    if dry_run::is_enabled() {
+        return;
+    }
+    let entry = AuditEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        action,
+        target: target.to_string(),
+        detail: detail.map(str::to_string),
+        repo: git::get_main_worktree_root().ok(),
+        command: invocation(),
+    };
+    if let Err(e) = append(&entry) {
+        warn!(error = %e, action = ?action, target, "audit:failed to record action");
+    }
+}
+
+/// Read every entry in the audit log, oldest first.
+pub fn read_entries() -> Result<Vec<AuditEntry>> {
+    let path = log_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read audit log '{}'", path.display()));
+        }
+    };
+    Ok(parse_entries(&content))
+}
+
+pub fn log_path() -> Result<PathBuf> {
+    Ok(logger::state_dir()?.join(AUDIT_FILE))
+}
+
+fn append(entry: &AuditEntry) -> Result<()> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory {}", parent.display()))?;
+    }
+
+    // A single write per entry keeps lines whole when commands run concurrently
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write audit log '{}'", path.display()))
+}
+
+/// Parse the log, skipping lines that are not valid entries (e.g. cut short by a crash).
+fn parse_entries(content: &str) -> Vec<AuditEntry> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// The current command line, with the binary shown as `workmux`.
+pub fn invocation() -> String {
+    std::iter::once("workmux".to_string())
+        .chain(std::env::args().skip(1).map(|arg| shell_quote(&arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entries_round_trips_and_skips_broken_lines() {
+        let entry = AuditEntry {
+            timestamp: 1_700_000_000,
+            action: AuditAction::BranchDeleted,
+            target: "feature/auth".to_string(),
+            detail: Some("9c0a9a2".to_string()),
+            repo: Some(PathBuf::from("/src/app")),
+            command: "workmux rm feature/auth".to_string(),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(line.contains(r#""action":"branch-deleted""#));
+
+        let content = format!("{line}\n{{\"timestamp\":17\n{line}\n");
+        assert_eq!(parse_entries(&content), vec![entry.clone(), entry]);
+    }
+}
@@ -3,6 +3,8 @@ use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::dry_run;
+
 /// Get the path to the Claude Code configuration file
 fn get_config_path() -> Option<PathBuf> {
     home::home_dir().map(|h| h.join(".claude.json"))
@@ -60,6 +62,9 @@ pub fn prune_stale_entries() -> Result<usize> {
         projects.remove(path_str);
     }
 
+    if removed_count > 0 && dry_run::skip(format_args!("write {}", config_path.display())) {
+        return Ok(removed_count);
+    }
     if removed_count > 0 {
         // Create a backup
         let backup_path = config_path.with_extension("json.bak");
@@ -1,8 +1,12 @@
-use crate::command::args::{MultiArgs, PromptArgs, RescueArgs, SetupFlags};
-use crate::{claude, command, git};
-use anyhow::{Context, Result};
-use clap::{CommandFactory, Parser, Subcommand};
+use crate::command::args::{MatchArgs, MultiArgs, PromptArgs, RescueArgs, SetupFlags};
+use crate::config::NestedRepoPolicy;
+use crate::{claude, command, dry_run, git};
+use anyhow::{Context, Result, anyhow};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
 use clap_complete::{Shell, generate};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tracing::info;
 
 #[derive(Clone, Debug)]
 struct WorktreeBranchParser;
@@ -24,7 +28,8 @@ impl WorktreeBranchParser {
             Err(_) => return Vec::new(),
         };
 
-        let main_branch = git::get_default_branch().ok();
+        let config = crate::config::Config::load(None).unwrap_or_default();
+        let main_branch = git::get_default_branch(config.default_remote()).ok();
 
         worktrees
             .into_iter()
@@ -61,6 +66,8 @@ impl clap::builder::TypedValueParser for WorktreeBranchParser {
 }
 
 /// Parser for worktree handles (directory names), used for open/path/remove commands.
+/// Positional arguments using it are also marked `ValueHint::Other`, which
+/// puts their command in [`handle_commands`].
 #[derive(Clone, Debug)]
 struct WorktreeHandleParser;
 
@@ -168,9 +175,33 @@ impl clap::builder::TypedValueParser for GitBranchParser {
 #[command(name = "workmux")]
 #[command(about = "An opinionated workflow tool that orchestrates git worktrees and tmux")]
 #[command(after_help = "Run 'workmux docs' for detailed documentation.")]
+// Flags from the config's `defaults` come first, so ones given later win
+#[command(args_override_self = true)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Skip all network operations (git fetch, GitHub PR lookups, LLM calls)
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Run git and tmux on a remote machine over ssh (`<ssh-host>[:<repo-path>]`)
+    #[arg(long, global = true, value_name = "HOST")]
+    host: Option<String>,
+
+    /// Ignore the default flags set for commands in the config
+    #[arg(long, global = true)]
+    no_defaults: bool,
+
+    /// Print the git and tmux commands, hooks and file operations that would
+    /// run, without running them
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Print the log to stderr: -v for workflow steps, -vv for every command run.
+    /// Given before the command, so commands keep -v for their own flags.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
 }
 
 #[derive(Subcommand)]
@@ -186,6 +217,24 @@ enum Commands {
         #[arg(long, conflicts_with_all = ["base", "auto_name"])]
         pr: Option<u32>,
 
+        /// With --pr, add the PR's description and unresolved review comments to the prompt
+        #[arg(long, requires = "pr")]
+        with_context: bool,
+
+        /// With --pr, check out the PR to review it: detached, without a local branch,
+        /// in the `pr.review_panes` layout (the PR's diff and the tests by default)
+        #[arg(long, requires = "pr", conflicts_with = "team")]
+        review: bool,
+
+        /// With --review, check out a local branch of this name instead of detaching
+        #[arg(
+            long = "branch",
+            value_name = "NAME",
+            requires = "review",
+            conflicts_with = "branch_name"
+        )]
+        review_branch: Option<String>,
+
         /// Generate branch name from prompt using LLM
         #[arg(short = 'A', long = "auto-name", conflicts_with = "pr")]
         auto_name: bool,
@@ -198,6 +247,11 @@ enum Commands {
         #[arg(long)]
         name: Option<String>,
 
+        /// Worktree template from the `templates` config (sparse paths, panes, hooks).
+        /// Defaults to the template whose `branches` match the branch name.
+        #[arg(short = 't', long)]
+        template: Option<String>,
+
         #[command(flatten)]
         prompt: PromptArgs,
 
@@ -208,17 +262,21 @@ enum Commands {
         rescue: RescueArgs,
 
         #[command(flatten)]
-        multi: MultiArgs,
+        multi: Box<MultiArgs>,
 
         /// Block until the created tmux window is closed
         #[arg(short = 'W', long)]
         wait: bool,
+
+        /// Don't fetch remote branches, regardless of the `fetch` config
+        #[arg(long)]
+        no_fetch: bool,
     },
 
     /// Open a tmux window for an existing worktree
     Open {
         /// Worktree name (directory name, visible in tmux window)
-        #[arg(value_parser = WorktreeHandleParser::new())]
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
         name: String,
 
         /// Re-run post-create hooks (e.g., pnpm install)
@@ -233,21 +291,41 @@ enum Commands {
         #[arg(long, short = 'n')]
         new: bool,
 
+        /// Turn the current tmux window into the worktree's window instead of opening one
+        #[arg(long, conflicts_with_all = ["new", "prompt", "prompt_file", "prompt_editor"])]
+        here: bool,
+
+        /// Start the agent with the prompt the worktree was created with again,
+        /// or `resume_prompt` rendered with it
+        #[arg(long, conflicts_with_all = ["prompt", "prompt_file", "prompt_editor", "here"])]
+        resume_prompt: bool,
+
         #[command(flatten)]
         prompt: PromptArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// Jump to a worktree's tmux window by fuzzy-matching its name or branch,
+    /// opening the window if it's closed
+    Switch {
+        /// Characters of the worktree name or branch, in order (asks when omitted)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        query: Option<String>,
     },
 
     /// Close a worktree's tmux window (keeps the worktree and branch)
     Close {
         /// Worktree name (defaults to current directory if omitted)
-        #[arg(value_parser = WorktreeHandleParser::new())]
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
         name: Option<String>,
     },
 
     /// Merge a branch, then clean up the worktree and tmux window
     Merge {
         /// Worktree name or branch (defaults to current directory)
-        #[arg(value_parser = WorktreeHandleParser::new())]
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
         name: Option<String>,
 
         /// The target branch to merge into (defaults to main_branch from config)
@@ -266,6 +344,32 @@ enum Commands {
         #[arg(long, group = "merge_strategy")]
         squash: bool,
 
+        /// Only fast-forward the main branch, failing if the branch has diverged from it
+        #[arg(long, group = "merge_strategy")]
+        ff_only: bool,
+
+        /// Finish a merge stopped on conflicts, once they are resolved and staged
+        #[arg(long = "continue", conflicts_with_all = ["into", "ignore_uncommitted", "merge_strategy", "keep", "no_verify", "abort", "interactive", "delete_remote", "yes"])]
+        continue_: bool,
+
+        /// Undo a merge stopped on conflicts
+        #[arg(long, conflicts_with_all = ["into", "ignore_uncommitted", "merge_strategy", "keep", "no_verify", "interactive", "delete_remote", "yes"])]
+        abort: bool,
+
+        /// Ask about each step (committing staged changes, rebasing, squashing,
+        /// hooks, deleting the remote branch, cleanup) with a preview, instead
+        /// of taking them from flags
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Delete the branch on its remote after merging
+        #[arg(long)]
+        delete_remote: bool,
+
+        /// Merge without the preflight report and confirmation
+        #[arg(short, long, conflicts_with = "interactive")]
+        yes: bool,
+
         /// Keep the worktree, window, and branch after merging (skip cleanup)
         #[arg(short = 'k', long)]
         keep: bool,
@@ -273,23 +377,107 @@ enum Commands {
         /// Skip running pre-merge hooks
         #[arg(short = 'n', long)]
         no_verify: bool,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// Fetch, then rebase (or merge) worktrees onto the main branch
+    Sync {
+        /// Worktree names (defaults to all worktrees)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other, num_args = 0..)]
+        names: Vec<String>,
+
+        /// Rebase each branch onto the main branch (the default)
+        #[arg(long, group = "sync_strategy")]
+        rebase: bool,
+
+        /// Merge the main branch into each branch
+        #[arg(long, group = "sync_strategy")]
+        merge: bool,
+
+        /// Stash uncommitted changes around the update instead of skipping the worktree
+        #[arg(long)]
+        autostash: bool,
+
+        /// Use the existing remote-tracking refs instead of fetching
+        #[arg(long)]
+        no_fetch: bool,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// Search the files of all worktrees, prefixing each match with its worktree
+    Grep {
+        /// Regular expression to search for
+        pattern: String,
+
+        /// Only search these worktrees (repeatable; defaults to all but the main one)
+        #[arg(long = "handle", value_name = "NAME", value_parser = WorktreeHandleParser::new())]
+        handles: Vec<String>,
+
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+
+        /// Only match whole words
+        #[arg(short, long)]
+        word_regexp: bool,
+
+        /// Print only the names of files with matches
+        #[arg(short = 'l', long)]
+        files_with_matches: bool,
+    },
+
+    /// Run a shell command in every worktree, with each line of its output
+    /// prefixed by the worktree
+    Exec {
+        /// Only run in these worktrees (repeatable; defaults to all but the
+        /// main one, leaving out pinned worktrees)
+        #[arg(long = "handle", value_name = "NAME", value_parser = WorktreeHandleParser::new())]
+        handles: Vec<String>,
+
+        /// How many worktrees to run the command in at once
+        #[arg(short = 'j', long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: u16,
+
+        /// The command. A single argument runs as a shell script, e.g. "make lint && make test"
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
     },
 
     /// Remove a worktree, tmux window, and branch without merging
-    #[command(visible_alias = "rm")]
+    #[command(visible_alias = "rm", group(clap::ArgGroup::new("fetching").args(["gone", "prs"])))]
     Remove {
         /// Worktree names (defaults to current directory name if empty)
-        #[arg(value_parser = WorktreeHandleParser::new(), conflicts_with_all = ["gone", "all"], num_args = 0..)]
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other, conflicts_with_all = ["gone", "prs", "all", "merged", "older_than"], num_args = 0..)]
         names: Vec<String>,
 
         /// Remove worktrees whose upstream remote branch has been deleted (e.g., after PR merge)
-        #[arg(long, conflicts_with = "all")]
+        #[arg(long, conflicts_with_all = ["prs", "all", "merged", "older_than"])]
         gone: bool,
 
+        /// Remove worktrees checked out with `add --pr` whose PR was merged or closed
+        #[arg(long, conflicts_with_all = ["all", "merged", "older_than"])]
+        prs: bool,
+
         /// Remove all worktrees (except the main worktree)
         #[arg(long)]
         all: bool,
 
+        /// Remove worktrees whose branch is merged into its base
+        #[arg(long)]
+        merged: bool,
+
+        /// Remove worktrees created longer ago than this, e.g. 12h, 7d or 2w
+        #[arg(long, value_name = "DURATION", value_parser = command::remove::parse_age)]
+        older_than: Option<std::time::Duration>,
+
         /// Skip confirmation and ignore uncommitted changes
         #[arg(short, long)]
         force: bool,
@@ -297,32 +485,431 @@ enum Commands {
         /// Keep the local branch (only remove worktree and tmux window)
         #[arg(short = 'k', long)]
         keep_branch: bool,
+
+        /// With --gone or --prs, use the existing remote-tracking refs instead of fetching
+        #[arg(long, requires = "fetching")]
+        no_fetch: bool,
+
+        #[command(flatten)]
+        matching: MatchArgs,
     },
 
+    /// Close windows, prune git metadata and delete directories left over from
+    /// worktrees removed without workmux
+    Prune,
+
+    /// Remove log entries, trash and saved progress older than `retention` keeps them
+    Gc,
+
     /// List all worktrees
     #[command(visible_alias = "ls")]
     List {
         /// Show PR status for each worktree (requires gh CLI)
         #[arg(long)]
         pr: bool,
+
+        /// Show each branch's description
+        #[arg(short, long)]
+        long: bool,
+
+        /// Print worktrees as JSON, with uncommitted changes and commits
+        /// ahead/behind the main branch
+        #[arg(long)]
+        json: bool,
+
+        /// Show what each agent waiting for input last printed
+        #[arg(long)]
+        why: bool,
+    },
+
+    /// Show the log of removed worktrees, deleted branches and killed windows
+    History {
+        /// Number of most recent entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+
+        /// Show entries from every repository, not just the current one
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show a feed of agent status changes across worktrees
+    Activity {
+        /// Keep printing status changes as they happen
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of most recent changes to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+
+        /// Show changes from every repository, not just the current one
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Summarize what agents did recently, e.g. from cron for a morning digest
+    Report {
+        /// How far back to look: a number followed by m, h, d or w
+        #[arg(long, value_name = "DURATION", default_value = "24h", value_parser = command::remove::parse_age)]
+        since: std::time::Duration,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "md")]
+        format: command::report::ReportFormat,
+
+        /// Run the pre_merge commands in each worktree with activity and
+        /// include whether they passed
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Create or open the worktrees listed in a manifest file
+    Apply {
+        /// Path to the manifest (e.g. team-setup.yaml)
+        manifest: PathBuf,
+
+        /// Also remove worktrees that are not listed in the manifest
+        #[arg(long)]
+        prune: bool,
+
+        /// Skip confirmation and prune worktrees with uncommitted or unmerged changes
+        #[arg(short, long, requires = "prune")]
+        force: bool,
+    },
+
+    /// Apply a unified diff to a worktree, falling back to a 3-way merge
+    ApplyPatch {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Patch file to apply, or "-" to read from stdin
+        patch: PathBuf,
+
+        /// Only check whether the patch applies cleanly
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Export a worktree's commits as a patch series or bundle
+    Export {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "patch")]
+        format: command::export::ExportFormat,
+
+        /// Directory to write the export to (defaults to the current directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Branch/commit to export from (defaults to the branch's base)
+        #[arg(long)]
+        base: Option<String>,
+    },
+
+    /// Create a worktree from a patch series or bundle made with `export`
+    ImportWork {
+        /// Bundle file, patch/mbox file, or directory of .patch files
+        file: PathBuf,
+
+        /// Branch to create (required for patches; defaults to the bundle's branch)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Branch/commit to apply patches on top of (defaults to the current branch)
+        #[arg(long)]
+        base: Option<String>,
+
+        #[command(flatten)]
+        setup: SetupFlags,
+    },
+
+    /// Save a worktree's commits, uncommitted changes and state to an archive,
+    /// then remove the worktree and its branch
+    Archive {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// File to write (defaults to <handle>.tar in the repository's archive directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Recreate a worktree from an archive, or list the archived worktrees
+    Restore {
+        /// Archive file, or the handle of an archived worktree
+        archive: Option<String>,
+
+        /// Keep the archive after restoring from the archive directory
+        #[arg(long)]
+        keep: bool,
+
+        #[command(flatten)]
+        setup: SetupFlags,
+    },
+
+    /// Launch a reviewer agent on a worktree's changes against its base branch
+    ReviewWithAgent {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Reviewer agent to run (overrides the `review.agent` config)
+        #[arg(short, long)]
+        agent: Option<String>,
+
+        /// Branch/commit to diff against (defaults to the branch's base)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// File the reviewer writes findings to, relative to the worktree
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Run the reviewer in a split pane of the worktree's window
+        #[arg(long, conflicts_with = "window")]
+        pane: bool,
+
+        /// Run the reviewer in a separate window
+        #[arg(long)]
+        window: bool,
+    },
+
+    /// Show a worktree's changes since it left its base branch, committed and
+    /// uncommitted together
+    Diff {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+
+        /// Branch/commit to diff against (defaults to the branch's base)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Count commits and uncommitted changes, then show the diffstat (the default)
+        #[arg(long, group = "diff_mode")]
+        stat: bool,
+
+        /// Show the full diff
+        #[arg(long, group = "diff_mode")]
+        patch: bool,
+
+        /// List the changed files, untracked ones included
+        #[arg(long, group = "diff_mode")]
+        files: bool,
+    },
+
+    /// Summarize a worktree's changes against its base branch with the `llm` CLI
+    Summarize {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Branch/commit to diff against (defaults to the branch's base)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Model passed to `llm` (defaults to the `auto_name.model` config)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Save the summary to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Open an interactive rebase of a worktree's branch in a new pane, to tidy its commits
+    Tidy {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Branch/commit the branch diverged from (defaults to the branch's base)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Start the rebase with a fixup/squash plan suggested by the `llm` CLI
+        #[arg(short, long)]
+        suggest: bool,
+
+        /// Model passed to `llm` (defaults to the `auto_name.model` config)
+        #[arg(short, long, requires = "suggest")]
+        model: Option<String>,
+    },
+
+    /// Split a worktree's uncommitted changes, or its last commit, into several commits
+    SplitCommits {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Group the files with the `llm` CLI instead of by directory
+        #[arg(short, long)]
+        suggest: bool,
+
+        /// Model passed to `llm` (defaults to the `auto_name.model` config)
+        #[arg(short, long, requires = "suggest")]
+        model: Option<String>,
+
+        /// Make the commits without asking
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// Get the filesystem path of a worktree
     Path {
         /// Worktree name (directory name)
-        #[arg(value_parser = WorktreeHandleParser::new())]
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// Pin a worktree, keeping it out of batch removals and syncs
+    Pin {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+    },
+
+    /// Unpin a worktree pinned with `pin`
+    Unpin {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+    },
+
+    /// Save the pane layout of a worktree's window, restored when it is opened
+    Layout {
+        #[command(subcommand)]
+        command: LayoutCommands,
+    },
+
+    /// Open a file of a worktree in your editor, by its path in that worktree
+    Edit {
+        /// Worktree name or branch
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: String,
+
+        /// Path of the file, relative to the worktree's root
+        path: PathBuf,
+
+        /// Open the editor in a split pane of the current tmux window
+        #[arg(long)]
+        pane: bool,
+    },
+
+    /// Show what a worktree was created from: base, prompt, agent and PR
+    Status {
+        /// Worktree name (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+    },
+
+    /// Unlock, lock or encrypt the prompts and agent output that worktree
+    /// state records, with `state_encryption` configured
+    State {
+        #[command(subcommand)]
+        command: StateCommands,
+    },
+
+    /// Answer an agent waiting for input: jump to its pane, or send it text
+    Respond {
+        /// Worktree whose waiting agent to answer
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other, required_unless_present = "all")]
+        name: Option<String>,
+
+        /// Text to send, followed by Enter (e.g. "yes"). Without it, jumps to the agent's pane
+        #[arg(conflicts_with = "all")]
+        text: Option<String>,
+
+        /// Send this text to every agent waiting for input
+        #[arg(long, value_name = "TEXT", conflicts_with = "name")]
+        all: Option<String>,
+    },
+
+    /// Start a worktree's agent over on the prompt it was created with
+    Reprompt {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+
+        /// Edit the prompt before sending it; the edit is kept for later attempts
+        #[arg(long)]
+        edit: bool,
+    },
+
+    /// Explain how a name resolves to a worktree, window and branch
+    Which {
+        /// Worktree handle (directory name) or branch name
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
         name: String,
     },
 
     /// Generate example .workmux.yaml configuration file
     Init,
 
+    /// Inspect the configuration in effect here
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
     /// Show detailed documentation (renders README.md)
     Docs,
 
+    /// List configured and built-in agents, or describe one: how it gets its
+    /// prompt, status hook support, and whether its binary is installed
+    Agents {
+        /// Agent to describe, e.g. claude
+        name: Option<String>,
+    },
+
+    /// Show the workmux version, and with --verbose the environment it runs in
+    Version {
+        /// Also show tool versions, modes, and config and state paths
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Check git, tmux, the config, agents, optional tools and hook scripts,
+    /// and suggest fixes for anything missing or misconfigured
+    Doctor,
+
+    /// Check the tmux hooks and options workmux set on windows, and repair
+    /// stale ones or ones that clash with your tmux config
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+
+    /// Check that workmux works here by adding, opening, merging and removing
+    /// worktrees in a throwaway repository and tmux server
+    Selftest {
+        /// Keep the throwaway repository and command output afterwards
+        #[arg(long)]
+        keep: bool,
+    },
+
+    /// Upgrade workmux to the latest GitHub release
+    Upgrade {
+        /// Only report whether a newer version is available
+        #[arg(long)]
+        check: bool,
+    },
+
     /// Show a TUI dashboard of all active workmux agents across all sessions
     Dashboard,
 
+    /// Open a tmux menu of workmux windows with actions for each
+    Menu,
+
     /// Claude Code integration commands
     Claude {
         #[command(subcommand)]
@@ -332,8 +919,16 @@ enum Commands {
     /// Set agent status for the current tmux window (used by hooks)
     #[command(hide = true)]
     SetWindowStatus {
-        #[arg(value_enum)]
-        command: command::set_window_status::SetWindowStatusCommand,
+        #[arg(value_enum, required_unless_present = "hook")]
+        command: Option<command::set_window_status::SetWindowStatusCommand>,
+
+        /// Set the status from a hook event of this agent, read as JSON from stdin
+        #[arg(long, value_enum, conflicts_with = "command")]
+        hook: Option<command::set_window_status::HookAgent>,
+
+        /// Name of the hook event, for agents that don't include it in the event JSON
+        #[arg(long, requires = "hook")]
+        event: Option<String>,
     },
 
     /// Generate shell completions
@@ -354,6 +949,64 @@ enum Commands {
     /// Output git branches for shell completion (internal use)
     #[command(hide = true, name = "_complete-git-branches")]
     CompleteGitBranches,
+
+    /// Output local branches, checked out ones included, for shell completion (internal use)
+    #[command(hide = true, name = "_complete-local-branches")]
+    CompleteLocalBranches,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Create a .workmux.yaml by answering a few questions
+    Init,
+
+    /// Print the effective configuration, merged from all config files
+    Show {
+        /// Mark each setting with the file it came from
+        #[arg(long)]
+        origin: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommands {
+    /// Read the age identity from the key file or keyring, so encrypted
+    /// state is shown until `state lock`
+    Unlock,
+
+    /// Forget the identity read by `state unlock`
+    Lock,
+
+    /// Encrypt state recorded before encryption was configured
+    Encrypt,
+}
+
+#[derive(Subcommand)]
+enum LayoutCommands {
+    /// Save the current arrangement of the window's panes
+    Save {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+    },
+
+    /// Forget the saved layout, going back to `panes.layout`
+    Clear {
+        /// Worktree name or branch (defaults to current directory)
+        #[arg(value_parser = WorktreeHandleParser::new(), value_hint = ValueHint::Other)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksCommands {
+    /// List the hooks and options workmux set on windows, flagging stale or
+    /// conflicting ones
+    Status,
+
+    /// Remove stale hooks and options, and keep your global hooks running
+    /// alongside workmux's
+    Repair,
 }
 
 #[derive(Subcommand)]
@@ -364,39 +1017,99 @@ enum ClaudeCommands {
 
 // --- Public Entry Point ---
 pub fn run() -> Result<()> {
-    let cli = Cli::parse();
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let cli = Cli::parse_from(&args);
+    crate::logger::set_verbosity(cli.verbose);
+    crate::help::register(command_summaries);
 
-    match cli.command {
+    if cli.offline {
+        crate::offline::enable();
+    }
+    if cli.dry_run {
+        dry_run::enable();
+    }
+    if let Some(host) = &cli.host {
+        crate::remote::enable(host)?;
+    }
+
+    if cli.command.uses_current_repo() {
+        check_nested_repo()?;
+    }
+    // Commands run by hooks and completion skip this, as they run often and
+    // must be quick. A broken config is reported by the command itself.
+    let config = cli
+        .command
+        .takes_default_flags()
+        .then(|| crate::config::Config::load(None).ok())
+        .flatten();
+    if let Some(path) = config
+        .as_ref()
+        .and_then(|config| config.log_file.as_deref())
+        && let Err(e) = crate::logger::log_json_to(path)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+    if let Some(config) = &config {
+        crate::tmux::set_layout(config.tmux_layout());
+    }
+    let cli = with_default_flags(cli, &args, config.as_ref())?;
+    if let Some(config) = &config {
+        crate::retention::enforce_if_due(config);
+    }
+
+    let result = match cli.command {
         Commands::Add {
             branch_name,
             pr,
+            with_context,
+            review,
+            review_branch,
             auto_name,
             base,
             name,
+            template,
             prompt,
             setup,
             rescue,
             multi,
             wait,
+            no_fetch,
         } => command::add::run(
-            branch_name.as_deref(),
+            branch_name.or(review_branch).as_deref(),
             pr,
+            with_context,
+            review,
             auto_name,
             base.as_deref(),
             name,
+            template.as_deref(),
             prompt,
             setup,
             rescue,
-            multi,
+            *multi,
             wait,
+            no_fetch,
         ),
         Commands::Open {
             name,
             run_hooks,
             force_files,
             new,
+            here,
+            resume_prompt,
+            prompt,
+            matching,
+        } => command::open::run(
+            &name,
+            run_hooks,
+            force_files,
+            new,
+            here,
+            resume_prompt,
             prompt,
-        } => command::open::run(&name, run_hooks, force_files, new, prompt),
+            matching,
+        ),
+        Commands::Switch { query } => command::switch::run(query.as_deref()),
         Commands::Close { name } => command::close::run(name.as_deref()),
         Commands::Merge {
             name,
@@ -404,33 +1117,228 @@ pub fn run() -> Result<()> {
             ignore_uncommitted,
             rebase,
             squash,
+            ff_only,
+            continue_,
+            abort,
             keep,
             no_verify,
+            interactive,
+            delete_remote,
+            yes,
+            matching,
         } => command::merge::run(
             name.as_deref(),
             into.as_deref(),
             ignore_uncommitted,
             rebase,
             squash,
+            ff_only,
+            continue_,
+            abort,
             keep,
             no_verify,
+            interactive,
+            delete_remote,
+            yes,
+            matching,
+        ),
+        Commands::Sync {
+            names,
+            rebase,
+            merge,
+            autostash,
+            no_fetch,
+            matching,
+        } => command::sync::run(names, rebase, merge, autostash, no_fetch, matching),
+        Commands::Grep {
+            pattern,
+            handles,
+            ignore_case,
+            fixed_strings,
+            word_regexp,
+            files_with_matches,
+        } => command::grep::run(
+            &pattern,
+            &handles,
+            command::grep::GrepOptions {
+                ignore_case,
+                fixed_strings,
+                word_regexp,
+                files_with_matches,
+            },
         ),
+        Commands::Exec {
+            handles,
+            parallel,
+            command,
+        } => command::exec::run(&command, &handles, parallel.into()),
         Commands::Remove {
             names,
             gone,
+            prs,
+            all,
+            merged,
+            older_than,
+            force,
+            keep_branch,
+            no_fetch,
+            matching,
+        } => command::remove::run(
+            names,
+            gone,
+            prs,
             all,
+            command::remove::BatchFilter { merged, older_than },
             force,
             keep_branch,
-        } => command::remove::run(names, gone, all, force, keep_branch),
-        Commands::List { pr } => command::list::run(pr),
-        Commands::Path { name } => command::path::run(&name),
+            no_fetch,
+            matching,
+        ),
+        Commands::Prune => command::prune::run(dry_run::is_enabled()),
+        Commands::Gc => command::gc::run(),
+        Commands::List {
+            pr,
+            long,
+            json,
+            why,
+        } => command::list::run(pr, long, json, why),
+        Commands::History { limit, all } => command::history::run(limit, all),
+        Commands::Activity { follow, limit, all } => command::activity::run(follow, limit, all),
+        Commands::Report {
+            since,
+            format,
+            verify,
+        } => command::report::run(since, format, verify),
+        Commands::Apply {
+            manifest,
+            prune,
+            force,
+        } => command::apply::run(&manifest, prune, dry_run::is_enabled(), force),
+        Commands::ApplyPatch { name, patch, check } => {
+            command::apply_patch::run(&name, &patch, check)
+        }
+        Commands::Export {
+            name,
+            format,
+            out,
+            base,
+        } => command::export::run(&name, format, out.as_deref(), base.as_deref()),
+        Commands::ImportWork {
+            file,
+            branch,
+            base,
+            setup,
+        } => command::import_work::run(&file, branch.as_deref(), base.as_deref(), setup),
+        Commands::Archive { name, output } => command::archive::run(&name, output.as_deref()),
+        Commands::Restore {
+            archive,
+            keep,
+            setup,
+        } => command::archive::restore(archive.as_deref(), keep, setup),
+        Commands::ReviewWithAgent {
+            name,
+            agent,
+            base,
+            output,
+            pane,
+            window,
+        } => command::review::run(
+            &name,
+            agent.as_deref(),
+            base.as_deref(),
+            output.as_deref(),
+            pane,
+            window,
+        ),
+        Commands::Diff {
+            name,
+            base,
+            stat: _,
+            patch,
+            files,
+        } => command::diff::run(
+            name.as_deref(),
+            base.as_deref(),
+            if patch {
+                command::diff::DiffMode::Patch
+            } else if files {
+                command::diff::DiffMode::Files
+            } else {
+                command::diff::DiffMode::Stat
+            },
+        ),
+        Commands::Summarize {
+            name,
+            base,
+            model,
+            output,
+        } => command::summarize::run(&name, base.as_deref(), model.as_deref(), output.as_deref()),
+        Commands::Tidy {
+            name,
+            base,
+            suggest,
+            model,
+        } => command::tidy::run(&name, base.as_deref(), suggest, model.as_deref()),
+        Commands::SplitCommits {
+            name,
+            suggest,
+            model,
+            yes,
+        } => command::split_commits::run(
+            &name,
+            suggest,
+            model.as_deref(),
+            dry_run::is_enabled(),
+            yes,
+        ),
+        Commands::Path { name, matching } => command::path::run(&name, matching),
+        Commands::Pin { name } => command::pin::run(name.as_deref()),
+        Commands::Unpin { name } => command::pin::unpin(name.as_deref()),
+        Commands::Layout { command } => match command {
+            LayoutCommands::Save { name } => command::layout::save(name.as_deref()),
+            LayoutCommands::Clear { name } => command::layout::clear(name.as_deref()),
+        },
+        Commands::Edit { name, path, pane } => command::edit::run(&name, &path, pane),
+        Commands::Respond { name, text, all } => {
+            command::respond::run(name.as_deref(), text.as_deref(), all.as_deref())
+        }
+        Commands::Reprompt { name, edit } => command::reprompt::run(name.as_deref(), edit),
+        Commands::Status { name } => command::status::run(name.as_deref()),
+        Commands::State { command } => match command {
+            StateCommands::Unlock => command::state::unlock(),
+            StateCommands::Lock => command::state::lock(),
+            StateCommands::Encrypt => command::state::encrypt(),
+        },
+        Commands::Which { name } => command::which::run(&name),
         Commands::Init => crate::config::Config::init(),
+        Commands::Config { command } => match command {
+            ConfigCommands::Init => command::config::init(),
+            ConfigCommands::Show { origin } => command::config::show(origin),
+        },
         Commands::Docs => command::docs::run(),
+        Commands::Agents { name } => command::agents::run(name.as_deref()),
+        Commands::Version { verbose } => command::version::run(verbose),
+        Commands::Doctor => command::doctor::run(),
+        Commands::Hooks { command } => match command {
+            HooksCommands::Status => command::hooks::status(),
+            HooksCommands::Repair => command::hooks::repair(),
+        },
+        Commands::Selftest { keep } => command::selftest::run(keep),
+        Commands::Upgrade { check } => command::upgrade::run(check),
         Commands::Dashboard => command::dashboard::run(),
+        Commands::Menu => command::menu::run(),
         Commands::Claude { command } => match command {
             ClaudeCommands::Prune => prune_claude_config(),
         },
-        Commands::SetWindowStatus { command } => command::set_window_status::run(command),
+        Commands::SetWindowStatus {
+            command,
+            hook,
+            event,
+        } => match (command, hook) {
+            (_, Some(agent)) => command::set_window_status::run_hook(agent, event),
+            (Some(command), None) => command::set_window_status::run(command),
+            (None, None) => unreachable!("clap requires a command or --hook"),
+        },
         Commands::Completions { shell } => {
             generate_completions(shell);
             Ok(())
@@ -453,6 +1361,215 @@ pub fn run() -> Result<()> {
             }
             Ok(())
         }
+        Commands::CompleteLocalBranches => {
+            // Fail silently on completion; don't disrupt the user's shell.
+            if git::is_git_repo().unwrap_or(false) {
+                for branch in git::list_local_branches().unwrap_or_default() {
+                    println!("{branch}");
+                }
+            }
+            Ok(())
+        }
+    };
+
+    // Windows switched on a remote host aren't visible until we attach to it
+    result.and_then(|()| crate::remote::attach_if_requested())
+}
+
+impl Commands {
+    /// Whether the command works on the repository of the current directory,
+    /// so must not be run on a submodule by mistake
+    fn uses_current_repo(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Docs
+                | Commands::Agents { .. }
+                | Commands::Version { .. }
+                | Commands::Doctor
+                | Commands::Hooks { .. }
+                | Commands::Selftest { .. }
+                | Commands::Upgrade { .. }
+                | Commands::Dashboard
+                | Commands::Menu
+                | Commands::Claude { .. }
+                | Commands::SetWindowStatus { .. }
+                | Commands::Completions { .. }
+                | Commands::CompleteBranches
+                | Commands::CompleteHandles
+                | Commands::CompleteGitBranches
+                | Commands::CompleteLocalBranches
+        )
+    }
+
+    /// Whether the config's `defaults` apply, which they don't to commands
+    /// run by hooks and shell completion
+    fn takes_default_flags(&self) -> bool {
+        !matches!(
+            self,
+            Commands::SetWindowStatus { .. }
+                | Commands::Completions { .. }
+                | Commands::CompleteBranches
+                | Commands::CompleteHandles
+                | Commands::CompleteGitBranches
+                | Commands::CompleteLocalBranches
+        )
+    }
+}
+
+/// `cli` parsed again with the config's default flags for its command, which
+/// go right after the command name so flags given on the command line win
+fn with_default_flags(
+    cli: Cli,
+    args: &[OsString],
+    config: Option<&crate::config::Config>,
+) -> Result<Cli> {
+    let Some(config) = config.filter(|_| !cli.no_defaults) else {
+        return Ok(cli);
+    };
+    let Some(index) = subcommand_index(args) else {
+        return Ok(cli);
+    };
+    let Some(name) = args[index].to_str().and_then(|name| {
+        Cli::command()
+            .find_subcommand(name)
+            .map(|c| c.get_name().to_string())
+    }) else {
+        return Ok(cli);
+    };
+    let flags = defaults_not_overridden(&name, config.default_flags(&name), args);
+    if flags.is_empty() {
+        return Ok(cli);
+    }
+
+    let mut args = args.to_vec();
+    args.splice(index + 1..index + 1, flags.iter().map(OsString::from));
+    Cli::try_parse_from(&args).with_context(|| {
+        format!(
+            "Invalid default flags for '{}' in the config: {}",
+            name,
+            flags.join(" ")
+        )
+    })
+}
+
+/// The default `flags` of the command `name`, less those that conflict with a
+/// flag given in `args`, as a default `--rebase` does with `merge --squash`
+fn defaults_not_overridden(name: &str, flags: &[String], args: &[OsString]) -> Vec<String> {
+    let mut cli = Cli::command();
+    cli.build();
+    let Some(command) = cli.find_subcommand(name) else {
+        return flags.to_vec();
+    };
+    let Some(matches) = cli
+        .clone()
+        .try_get_matches_from(args)
+        .ok()
+        .and_then(|matches| matches.subcommand_matches(name).cloned())
+    else {
+        return flags.to_vec();
+    };
+    let given: Vec<&clap::Arg> = command
+        .get_arguments()
+        .filter(|arg| {
+            !arg.is_global_set()
+                && matches.value_source(arg.get_id().as_str())
+                    == Some(clap::parser::ValueSource::CommandLine)
+        })
+        .collect();
+    let conflicts = |default: &clap::Arg, given: &clap::Arg| {
+        let exclusive_group = command.get_groups().any(|group| {
+            let members: Vec<_> = group.get_args().collect();
+            !group.clone().is_multiple()
+                && members.contains(&default.get_id())
+                && members.contains(&given.get_id())
+        });
+        exclusive_group
+            || command
+                .get_arg_conflicts_with(default)
+                .iter()
+                .any(|arg| arg.get_id() == given.get_id())
+            || command
+                .get_arg_conflicts_with(given)
+                .iter()
+                .any(|arg| arg.get_id() == default.get_id())
+    };
+
+    // Each flag keeps the values that follow it
+    let mut kept = Vec::new();
+    let mut keep = true;
+    for flag in flags {
+        if flag.starts_with('-') {
+            let default = command
+                .get_arguments()
+                .find(|arg| match flag.strip_prefix("--") {
+                    Some(long) => arg.get_long() == long.split('=').next(),
+                    None => flag[1..].chars().next() == arg.get_short(),
+                });
+            // Unknown flags are kept, to be reported
+            keep = default.is_none_or(|default| {
+                default.is_global_set() || !given.iter().any(|given| conflicts(default, given))
+            });
+        }
+        if keep {
+            kept.push(flag.clone());
+        }
+    }
+    kept
+}
+
+/// Where the command name is in `args`, after any global options
+fn subcommand_index(args: &[OsString]) -> Option<usize> {
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        match arg.to_str()? {
+            "--offline" | "--no-defaults" | "--dry-run" | "--verbose" => index += 1,
+            arg if arg
+                .strip_prefix('-')
+                .is_some_and(|flags| !flags.is_empty() && flags.bytes().all(|b| b == b'v')) =>
+            {
+                index += 1
+            }
+            "--host" => index += 2,
+            arg if arg.starts_with("--host=") => index += 1,
+            arg if arg.starts_with('-') => return None,
+            _ => return Some(index),
+        }
+    }
+    None
+}
+
+/// Stop workmux from creating worktrees of a submodule, or of a repository
+/// nested in another, when the superproject was meant. With `nested_repo:
+/// superproject`, moves to the superproject's root instead. The superproject's
+/// config (or the global one) decides, since the nested repository's may not
+/// know about it. Left alone on a `--host`.
+fn check_nested_repo() -> Result<()> {
+    if crate::remote::get().is_some() {
+        return Ok(());
+    }
+    let Some(superproject) = git::get_superproject_root() else {
+        return Ok(());
+    };
+    let nested = git::get_repo_root()?;
+    let current_dir = std::env::current_dir()?;
+    std::env::set_current_dir(&superproject)
+        .with_context(|| format!("Failed to enter '{}'", superproject.display()))?;
+    let policy = crate::config::Config::load(None)
+        .map(|config| config.nested_repo())
+        .unwrap_or_default();
+    match policy {
+        NestedRepoPolicy::Error => Err(anyhow!(
+            "'{}' is a repository nested in '{}'. Run workmux from the superproject, \
+             or set `nested_repo` to `superproject` or `allow` in its config.",
+            nested.display(),
+            superproject.display()
+        )),
+        NestedRepoPolicy::Superproject => {
+            info!(superproject = %superproject.display(), "cli:using superproject");
+            Ok(())
+        }
+        NestedRepoPolicy::Allow => std::env::set_current_dir(&current_dir)
+            .with_context(|| format!("Failed to return to '{}'", current_dir.display())),
     }
 }
 
@@ -461,6 +1578,29 @@ fn prune_claude_config() -> Result<()> {
     Ok(())
 }
 
+/// Commands whose positional arguments are worktrees, completed with
+/// `_complete-handles` by the dynamic completion scripts: those with, or
+/// with subcommands with, a positional argument marked `ValueHint::Other`
+/// next to its [`WorktreeHandleParser`], and their visible aliases
+fn handle_commands() -> Vec<String> {
+    fn takes_handle(command: &clap::Command) -> bool {
+        command
+            .get_positionals()
+            .any(|arg| arg.get_value_hint() == ValueHint::Other)
+            || command.get_subcommands().any(takes_handle)
+    }
+    Cli::command()
+        .get_subcommands()
+        .filter(|command| takes_handle(command))
+        .flat_map(|command| {
+            std::iter::once(command.get_name())
+                .chain(command.get_visible_aliases())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 fn generate_completions(shell: Shell) {
     let mut cmd = Cli::command();
     let name = cmd.get_name().to_string();
@@ -483,13 +1623,127 @@ fn generate_completions(shell: Shell) {
 }
 
 fn print_zsh_dynamic_completion() {
-    print!("{}", include_str!("scripts/completions/zsh_dynamic.zsh"));
+    print!(
+        "{}",
+        with_handle_commands(include_str!("scripts/completions/zsh_dynamic.zsh"), "|")
+    );
 }
 
 fn print_bash_dynamic_completion() {
-    print!("{}", include_str!("scripts/completions/bash_dynamic.bash"));
+    print!(
+        "{}",
+        with_handle_commands(include_str!("scripts/completions/bash_dynamic.bash"), "|")
+    );
 }
 
 fn print_fish_dynamic_completion() {
-    print!("{}", include_str!("scripts/completions/fish_dynamic.fish"));
+    print!(
+        "{}",
+        with_handle_commands(include_str!("scripts/completions/fish_dynamic.fish"), " ")
+    );
+}
+
+/// Fill in the script's list of [`handle_commands`], joined as the shell
+/// lists alternatives
+fn with_handle_commands(script: &str, separator: &str) -> String {
+    script.replace("@HANDLE_COMMANDS@", &handle_commands().join(separator))
+}
+
+/// Name and description of each command `workmux --help` lists
+fn command_summaries() -> Vec<(String, String)> {
+    Cli::command()
+        .get_subcommands()
+        .filter(|command| !command.is_hide_set())
+        .map(|command| {
+            let about = command.get_about().map(|about| about.to_string());
+            (command.get_name().to_string(), about.unwrap_or_default())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Cli, command_summaries, defaults_not_overridden, handle_commands, subcommand_index,
+    };
+    use clap::Parser;
+
+    #[test]
+    fn handle_commands_are_commands_taking_a_worktree() {
+        let commands = handle_commands();
+        for name in [
+            "open", "remove", "rm", "edit", "pin", "unpin", "layout", "respond", "which",
+        ] {
+            assert!(commands.iter().any(|c| c == name), "'{}' is missing", name);
+        }
+        // Only a `--handle` flag, or no worktree at all
+        for name in ["grep", "exec", "add", "list", "restore"] {
+            assert!(!commands.iter().any(|c| c == name), "'{}' is listed", name);
+        }
+    }
+
+    #[test]
+    fn command_summaries_leave_out_hidden_commands() {
+        let summaries = command_summaries();
+        let add = summaries.iter().find(|(name, _)| name == "add").unwrap();
+        assert_eq!(add.1, "Create a new worktree and tmux window");
+        assert!(
+            !summaries
+                .iter()
+                .any(|(name, _)| name.starts_with('_') || name == "set-window-status")
+        );
+    }
+
+    #[test]
+    fn subcommand_index_skips_global_options() {
+        let args =
+            |line: &str| -> Vec<std::ffi::OsString> { line.split(' ').map(Into::into).collect() };
+        assert_eq!(subcommand_index(&args("workmux add feat")), Some(1));
+        assert_eq!(
+            subcommand_index(&args("workmux --offline --host box:/src/app rm feat")),
+            Some(4)
+        );
+        assert_eq!(subcommand_index(&args("workmux --host=box ls")), Some(2));
+        assert_eq!(
+            subcommand_index(&args("workmux -vv --verbose add x")),
+            Some(3)
+        );
+        assert_eq!(subcommand_index(&args("workmux --help")), None);
+    }
+
+    #[test]
+    fn later_flags_override_earlier_ones() {
+        // Default flags are inserted before the ones given on the command line
+        assert!(
+            Cli::try_parse_from(["workmux", "merge", "--into", "main", "--into", "develop"])
+                .is_ok()
+        );
+        assert!(
+            Cli::try_parse_from(["workmux", "add", "--background", "--background", "feat"]).is_ok()
+        );
+    }
+
+    #[test]
+    fn flags_given_drop_the_defaults_they_conflict_with() {
+        let kept = |defaults: &[&str], line: &str| {
+            let defaults: Vec<String> = defaults.iter().map(|f| f.to_string()).collect();
+            let args: Vec<std::ffi::OsString> = line.split(' ').map(Into::into).collect();
+            defaults_not_overridden("merge", &defaults, &args)
+        };
+        // Another strategy replaces the default one, and leaves the rest
+        assert_eq!(
+            kept(
+                &["--rebase", "--into", "develop"],
+                "workmux merge --squash feat"
+            ),
+            ["--into", "develop"]
+        );
+        assert_eq!(
+            kept(&["--rebase"], "workmux merge --keep feat"),
+            ["--rebase"]
+        );
+        // Flags that conflict outright, rather than through a group
+        assert!(kept(&["--keep", "--into=develop"], "workmux merge --continue").is_empty());
+        assert_eq!(kept(&["--bogus"], "workmux merge feat"), ["--bogus"]);
+    }
 }
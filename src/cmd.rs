@@ -1,13 +1,21 @@
 use anyhow::{Context, Result, anyhow};
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
-use std::process::{Command, Output};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::{debug, trace};
 
+use crate::{dry_run, git, interrupt, remote};
+
 /// A builder for executing shell commands with unified error handling
 pub struct Cmd<'a> {
     command: &'a str,
     args: Vec<&'a str>,
     workdir: Option<&'a Path>,
+    env: Vec<(&'a str, &'a str)>,
 }
 
 impl<'a> Cmd<'a> {
@@ -17,6 +25,7 @@ impl<'a> Cmd<'a> {
             command,
             args: Vec::new(),
             workdir: None,
+            env: Vec::new(),
         }
     }
 
@@ -38,32 +47,62 @@ impl<'a> Cmd<'a> {
         self
     }
 
+    /// Set an environment variable for the command
+    pub fn env(mut self, key: &'a str, value: &'a str) -> Self {
+        self.env.push((key, value));
+        self
+    }
+
+    /// Build the process without running it, for commands that need to be
+    /// spawned or attached to the terminal. Set `tty` for interactive commands.
+    pub fn into_command(self, tty: bool) -> Command {
+        if self.skip_in_dry_run() {
+            return Command::new("true");
+        }
+        build(self.command, &self.args, self.workdir, &self.env, tty)
+    }
+
+    /// In dry-run mode, print the command instead of running it, unless it
+    /// only reads state. Returns whether it was skipped.
+    fn skip_in_dry_run(&self) -> bool {
+        dry_run::is_enabled()
+            && !is_read_only(self.command, &self.args)
+            // Nothing but a throwaway index is written to
+            && !self.env.iter().any(|(key, _)| *key == "GIT_INDEX_FILE")
+            && dry_run::skip(display(self.command, &self.args, self.workdir))
+    }
+
     /// Execute the command and return the output
     /// Returns an error if the command fails (non-zero exit code)
     pub fn run(self) -> Result<Output> {
+        if self.skip_in_dry_run() {
+            return Ok(skipped_output(self.command, &self.args));
+        }
         let Cmd {
             command,
             args,
             workdir,
+            env,
         } = self;
         let workdir_display = workdir.map(|p| p.display().to_string());
 
         trace!(command, args = ?args, workdir = ?workdir_display, "cmd:run start");
 
-        let mut cmd = Command::new(command);
-        if let Some(dir) = workdir {
-            cmd.current_dir(dir);
-        }
-        let output = cmd.args(&args).output().with_context(|| {
-            format!("Failed to execute command: {} {}", command, args.join(" "))
-        })?;
+        let started = Instant::now();
+        let output = build(command, &args, workdir, &env, false)
+            .output()
+            .with_context(|| {
+                format!("Failed to execute command: {} {}", command, args.join(" "))
+            })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             debug!(
                 command,
                 args = ?args,
+                workdir = ?workdir_display,
                 status = ?output.status.code(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
                 stderr = %stderr.trim(),
                 "cmd:run failure"
             );
@@ -74,7 +113,13 @@ impl<'a> Cmd<'a> {
                 stderr.trim()
             ));
         }
-        trace!(command, "cmd:run success");
+        debug!(
+            command,
+            args = ?args,
+            workdir = ?workdir_display,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "cmd:run success"
+        );
         Ok(output)
     }
 
@@ -87,45 +132,306 @@ impl<'a> Cmd<'a> {
     /// Execute the command, returning Ok(true) if it succeeds, Ok(false) if it fails
     /// This is useful for commands that are used as checks (e.g., git rev-parse --verify)
     pub fn run_as_check(self) -> Result<bool> {
+        if self.skip_in_dry_run() {
+            return Ok(true);
+        }
         let Cmd {
             command,
             args,
             workdir,
+            env,
         } = self;
         let workdir_display = workdir.map(|p| p.display().to_string());
         trace!(command, args = ?args, workdir = ?workdir_display, "cmd:check start");
 
-        let mut cmd = Command::new(command);
-        if let Some(dir) = workdir {
-            cmd.current_dir(dir);
-        }
-        let output = cmd.args(&args).output().with_context(|| {
-            format!("Failed to execute command: {} {}", command, args.join(" "))
-        })?;
+        let started = Instant::now();
+        let output = build(command, &args, workdir, &env, false)
+            .output()
+            .with_context(|| {
+                format!("Failed to execute command: {} {}", command, args.join(" "))
+            })?;
 
         let success = output.status.success();
-        trace!(command, success, "cmd:check result");
+        debug!(
+            command,
+            args = ?args,
+            workdir = ?workdir_display,
+            success,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "cmd:check result"
+        );
         Ok(success)
     }
 }
 
-/// Helper to create a shell command with additional environment variables
+/// The command line as it would be typed, run in `workdir` if given
+pub fn display(command: &str, args: &[&str], workdir: Option<&Path>) -> String {
+    let mut line = match workdir {
+        Some(dir) => format!("cd {} && {}", shell_quote(&dir.to_string_lossy()), command),
+        None => command.to_string(),
+    };
+    for arg in args {
+        line.push(' ');
+        line.push_str(&shell_quote(arg));
+    }
+    line
+}
+
+/// What a command skipped in a dry run returns: success, with a placeholder
+/// pane ID for tmux commands asked to print the pane they create
+fn skipped_output(command: &str, args: &[&str]) -> Output {
+    let stdout = if command == "tmux" && args.contains(&"-P") {
+        b"%dry-run".to_vec()
+    } else {
+        Vec::new()
+    };
+    Output {
+        status: ExitStatus::from_raw(0),
+        stdout,
+        stderr: Vec::new(),
+    }
+}
+
+/// Whether running `command` with `args` leaves everything as it was. A dry
+/// run still runs these, as workflows decide what to do from their output.
+fn is_read_only(command: &str, args: &[&str]) -> bool {
+    // Any tool asked for its version, as `workmux version` and `doctor` do
+    if matches!(args, ["--version" | "-V"]) {
+        return true;
+    }
+    match command {
+        "git" => is_read_only_git(args),
+        "tmux" => match args.first().copied() {
+            Some("display-message") => args.contains(&"-p"),
+            Some(subcommand) => {
+                subcommand.starts_with("list-")
+                    || subcommand.starts_with("show-")
+                    || matches!(subcommand, "has-session" | "capture-pane")
+            }
+            None => false,
+        },
+        "test" | "ls" => true,
+        _ => false,
+    }
+}
+
+fn is_read_only_git(args: &[&str]) -> bool {
+    // Skip options given before the subcommand, such as `-c key=value`
+    let mut args = args;
+    while let [option, rest @ ..] = args
+        && option.starts_with('-')
+    {
+        args = match *option {
+            "-c" | "-C" => rest.get(1..).unwrap_or_default(),
+            _ => rest,
+        };
+    }
+    let [subcommand, args @ ..] = args else {
+        return false;
+    };
+    let has = |flags: &[&str]| {
+        args.iter()
+            .any(|arg| flags.iter().any(|flag| arg.split('=').next() == Some(flag)))
+    };
+    let positional: Vec<&str> = args
+        .iter()
+        .copied()
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    match *subcommand {
+        "rev-parse" | "rev-list" | "log" | "show" | "show-ref" | "diff" | "diff-tree"
+        | "diff-index" | "status" | "for-each-ref" | "merge-base" | "merge-tree" | "ls-files"
+        | "ls-tree" | "ls-remote" | "cat-file" | "name-rev" | "describe" | "check-ignore"
+        | "var" | "grep" | "shortlog" | "cherry" => true,
+        "worktree" => positional.first() == Some(&"list"),
+        "stash" => matches!(positional.first(), Some(&("list" | "show"))),
+        "bundle" => matches!(positional.first(), Some(&("list-heads" | "verify"))),
+        "apply" => has(&["--check", "--numstat", "--stat"]),
+        "remote" => matches!(positional.first(), None | Some(&("get-url" | "show"))),
+        "symbolic-ref" => positional.len() <= 1,
+        "config" => {
+            has(&["--get", "--get-all", "--get-regexp", "--list", "-l"])
+                || (positional.len() <= 1 && !has(&["--unset", "--unset-all", "--add"]))
+        }
+        "branch" => {
+            let writes = has(&[
+                "-d",
+                "-D",
+                "--delete",
+                "-m",
+                "-M",
+                "--move",
+                "-c",
+                "-C",
+                "--copy",
+                "-u",
+                "--set-upstream-to",
+                "--unset-upstream",
+                "--edit-description",
+                "-f",
+                "--force",
+            ]);
+            let lists = has(&[
+                "--list",
+                "-l",
+                "--show-current",
+                "--merged",
+                "--no-merged",
+                "--contains",
+                "--points-at",
+                "--format",
+                "-r",
+                "-a",
+                "--all",
+            ]);
+            !writes && (lists || positional.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Create the process for `command`, running it over ssh when `--host` is set
+fn build(
+    command: &str,
+    args: &[&str],
+    workdir: Option<&Path>,
+    env: &[(&str, &str)],
+    tty: bool,
+) -> Command {
+    if let Some(remote) = remote::get() {
+        return remote.command(command, args, workdir, env, tty);
+    }
+    let mut cmd = Command::new(command);
+    if let Some(dir) = workdir {
+        cmd.current_dir(dir);
+    }
+    if command == "git" {
+        git::apply_repo_context(&mut cmd, workdir);
+    }
+    cmd.envs(env.iter().copied());
+    cmd.args(args);
+    cmd
+}
+
+/// How long a cancelled hook gets to exit after SIGTERM before it is killed
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Run a hook command through `sh -c` with additional environment variables.
+///
+/// The command reads `input` on stdin, or workmux's own stdin without it.
+/// If it outlives `timeout`, or Ctrl-C is pressed, it is terminated and
+/// reaped. Commands with a timeout run in their own process group, which is
+/// terminated as a whole so no processes they started are left running.
 pub fn shell_command_with_env(
     command: &str,
     workdir: &Path,
     env_vars: &[(&str, &str)],
+    input: Option<&str>,
+    timeout: Option<Duration>,
 ) -> Result<()> {
-    let mut cmd = Command::new("sh");
-    cmd.arg("-c").arg(command).current_dir(workdir);
+    run_shell(command, workdir, env_vars, input, timeout, false)
+}
 
-    for (key, value) in env_vars {
-        cmd.env(key, value);
+/// Like [`shell_command_with_env`], with the command's output discarded
+pub fn shell_command_quietly(
+    command: &str,
+    workdir: &Path,
+    env_vars: &[(&str, &str)],
+    input: Option<&str>,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    run_shell(command, workdir, env_vars, input, timeout, true)
+}
+
+fn run_shell(
+    command: &str,
+    workdir: &Path,
+    env_vars: &[(&str, &str)],
+    input: Option<&str>,
+    timeout: Option<Duration>,
+    quiet: bool,
+) -> Result<()> {
+    if dry_run::skip(display("sh", &["-c", command], Some(workdir))) {
+        return Ok(());
+    }
+    let mut cmd = match remote::get() {
+        Some(remote) => remote.command("sh", &["-c", command], Some(workdir), env_vars, false),
+        None => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(command).current_dir(workdir);
+            for (key, value) in env_vars {
+                cmd.env(key, value);
+            }
+            cmd
+        }
+    };
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+    // Hooks that can run out of time, or whose output nobody sees, aren't
+    // interactive: they get a group of their own so everything they started
+    // can be killed with them. Others stay in the terminal's foreground group,
+    // where they can use it and get Ctrl-C themselves.
+    let own_group = timeout.is_some() || quiet;
+    if own_group {
+        cmd.process_group(0);
+    }
+    if quiet {
+        cmd.stdout(Stdio::null()).stderr(Stdio::null());
     }
 
-    let status = cmd
-        .status()
+    let _guard = interrupt::Guard::new()?;
+    let mut child = cmd
+        .spawn()
         .with_context(|| format!("Failed to execute shell command: {}", command))?;
+    // Written from a thread so a command that doesn't read it can't block us.
+    // It's closed once written; a command that exits first just misses it.
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        let input = input.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let started = Instant::now();
+
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed to wait for shell command: {}", command))?
+        {
+            break status;
+        }
+        if interrupt::is_interrupted() {
+            terminate(&mut child, own_group);
+            return Err(anyhow!(interrupt::Interrupted))
+                .with_context(|| format!("Cancelled shell command: {}", command));
+        }
+        if let Some(limit) = timeout
+            && started.elapsed() >= limit
+        {
+            terminate(&mut child, own_group);
+            return Err(anyhow!(
+                "Shell command timed out after {}s: {}",
+                limit.as_secs(),
+                command
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
 
+    debug!(
+        command,
+        workdir = %workdir.display(),
+        status = ?status.code(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "cmd:shell finished"
+    );
+    // A hook in the terminal's foreground group gets Ctrl-C itself, and
+    // usually exits on it before the loop above notices
+    if !status.success() && interrupt::is_interrupted() {
+        return Err(anyhow!(interrupt::Interrupted))
+            .with_context(|| format!("Cancelled shell command: {}", command));
+    }
     if !status.success() {
         return Err(anyhow!(
             "Shell command failed with exit code {}: {}",
@@ -135,3 +441,97 @@ pub fn shell_command_with_env(
     }
     Ok(())
 }
+
+/// Terminate a child started in its own process group, along with everything
+/// it spawned: SIGTERM first, SIGKILL for whatever is left after a grace period.
+pub fn terminate_group(child: &mut Child) {
+    terminate(child, true);
+}
+
+/// Terminate a child, and with `group` the process group it leads
+fn terminate(child: &mut Child, group: bool) {
+    let pid = child.id() as libc::pid_t;
+    let target = if group { -pid } else { pid };
+    debug!(pid, group, "cmd:terminating child");
+    // SAFETY: kill(2) has no memory-safety preconditions
+    unsafe { libc::kill(target, libc::SIGTERM) };
+
+    let deadline = Instant::now() + KILL_GRACE;
+    while Instant::now() < deadline && matches!(child.try_wait(), Ok(None)) {
+        thread::sleep(Duration::from_millis(50));
+    }
+    // For a group, also catches children that outlived the shell
+    unsafe { libc::kill(target, libc::SIGKILL) };
+    let _ = child.wait();
+}
+
+/// Quote a word for a POSIX shell, leaving plain words as they are
+pub fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', r#"'\''"#))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_commands_that_read_state_count_as_read_only() {
+        let git = |args: &str| is_read_only("git", &args.split(' ').collect::<Vec<_>>());
+        assert!(git("worktree list --porcelain"));
+        assert!(!git("worktree add -b feat ../feat main"));
+        assert!(git("config --get branch.feat.remote"));
+        assert!(git("config --bool extensions.worktreeConfig"));
+        assert!(!git("config --worktree user.name Ada"));
+        assert!(git("branch --show-current"));
+        assert!(git("branch --format=%(refname:short) --merged main"));
+        assert!(!git("branch -D feat"));
+        assert!(!git("branch feat main"));
+        assert!(git("-c core.editor=true rev-parse HEAD"));
+        assert!(!git("-c core.editor=true rebase --continue"));
+        assert!(!git("merge --no-edit feat"));
+
+        assert!(is_read_only(
+            "tmux",
+            &["list-windows", "-F", "#{window_name}"]
+        ));
+        assert!(is_read_only("tmux", &["display-message", "-p", "#S"]));
+        assert!(!is_read_only("tmux", &["display-message", "hello"]));
+        assert!(!is_read_only(
+            "tmux",
+            &["new-window", "-P", "-F", "#{pane_id}"]
+        ));
+        assert!(!is_read_only("rm", &["-f", "state.json"]));
+        // How workflows look at files on the remote host with --host
+        assert!(is_read_only("test", &["-e", "/srv/app"]));
+        assert!(is_read_only("ls", &["-A", "/srv/app"]));
+        assert!(!is_read_only("mv", &["/srv/app", "/srv/.trash"]));
+        // Version probes, however the tool spells them
+        assert!(git("--version"));
+        assert!(is_read_only("tmux", &["-V"]));
+        assert!(is_read_only("claude", &["--version"]));
+        assert!(!is_read_only("claude", &["--version", "-p", "hi"]));
+    }
+
+    #[test]
+    fn skipped_commands_are_shown_as_typed() {
+        assert_eq!(
+            display(
+                "git",
+                &["commit", "-m", "Add it"],
+                Some(Path::new("/src/my app"))
+            ),
+            "cd '/src/my app' && git commit -m 'Add it'"
+        );
+        assert_eq!(
+            skipped_output("tmux", &["split-window", "-P"]).stdout,
+            b"%dry-run"
+        );
+    }
+}
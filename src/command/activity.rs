@@ -0,0 +1,123 @@
+use crate::activity::{self, ActivityEntry};
+use crate::git;
+use anyhow::Result;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// How often `--follow` checks the feed for new entries
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn run(follow: bool, limit: usize, all: bool) -> Result<()> {
+    // Outside a repository there is nothing to scope to, so show everything
+    let repo = if all {
+        None
+    } else {
+        git::get_main_worktree_root().ok()
+    };
+    let content = activity::read_log()?;
+    let entries = in_repo(activity::parse_entries(&content), repo.as_deref());
+
+    if entries.is_empty() && !follow {
+        if repo.is_some() {
+            println!("No status changes recorded for this repository (use --all for others)");
+        } else {
+            println!("No status changes recorded");
+        }
+        return Ok(());
+    }
+
+    let skip = entries.len().saturating_sub(limit);
+    let width = entries
+        .iter()
+        .map(|entry| label(entry, repo.is_none()).len())
+        .max()
+        .unwrap_or(0)
+        .max(12);
+    for entry in entries.iter().skip(skip) {
+        print_entry(entry, width, repo.is_none());
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    // Print what is appended from now on. The log may be trimmed meanwhile;
+    // pick up again after the last line seen.
+    let mut seen = whole_lines(&content).to_string();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let content = activity::read_log()?;
+        // A line still being written is picked up once it is complete
+        let content = whole_lines(&content);
+        let new = match content.strip_prefix(seen.as_str()) {
+            Some(new) => new,
+            None => after_last_line(content, &seen),
+        };
+        for entry in in_repo(activity::parse_entries(new), repo.as_deref()) {
+            print_entry(&entry, width, repo.is_none());
+        }
+        seen = content.to_string();
+    }
+}
+
+fn in_repo(mut entries: Vec<ActivityEntry>, repo: Option<&Path>) -> Vec<ActivityEntry> {
+    if let Some(repo) = repo {
+        entries.retain(|entry| entry.repo.as_deref() == Some(repo));
+    }
+    entries
+}
+
+/// `content` up to and including its last newline
+fn whole_lines(content: &str) -> &str {
+    &content[..content.rfind('\n').map_or(0, |newline| newline + 1)]
+}
+
+/// The part of `content` after the last line of `seen`, or all of it if that
+/// line is gone
+fn after_last_line<'a>(content: &'a str, seen: &str) -> &'a str {
+    let Some(last) = seen.lines().next_back() else {
+        return content;
+    };
+    match content.rfind(&format!("{}\n", last)) {
+        Some(start) => &content[start + last.len() + 1..],
+        None => content,
+    }
+}
+
+/// The worktree the entry is about, prefixed with its repository when
+/// showing every repository
+fn label(entry: &ActivityEntry, with_repo: bool) -> String {
+    let repo = entry
+        .repo
+        .as_ref()
+        .and_then(|repo| repo.file_name())
+        .filter(|_| with_repo);
+    match repo {
+        Some(repo) => format!("{}/{}", repo.to_string_lossy(), entry.handle),
+        None => entry.handle.clone(),
+    }
+}
+
+fn print_entry(entry: &ActivityEntry, width: usize, with_repo: bool) {
+    println!(
+        "{}  {:<width$}  {}",
+        super::history::format_timestamp(entry.timestamp),
+        label(entry, with_repo),
+        entry.transition(),
+        width = width
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{after_last_line, whole_lines};
+
+    #[test]
+    fn follow_resumes_after_the_last_line_seen() {
+        let seen = "a\nb\nc\n";
+        assert_eq!(after_last_line("b\nc\nd\n", seen), "d\n");
+        assert_eq!(after_last_line("x\ny\n", seen), "x\ny\n");
+        assert_eq!(after_last_line("a\n", ""), "a\n");
+        assert_eq!(whole_lines("a\nb\n{\"time"), "a\nb\n");
+    }
+}
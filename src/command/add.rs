@@ -1,17 +1,21 @@
-use crate::prompt::{Prompt, PromptDocument, foreach_from_frontmatter};
+use crate::prompt::{Prompt, PromptDocument, PromptMetadata, foreach_from_frontmatter};
 use crate::spinner;
 use crate::template::{
-    TemplateEnv, WorktreeSpec, create_template_env, generate_worktree_specs, parse_foreach_matrix,
-    render_prompt_body, validate_template_variables,
+    PR_BASE_VAR, PR_CONTEXT_VAR, TemplateEnv, WorktreeSpec, add_template_variables,
+    create_template_env, generate_worktree_specs, missing_template_variables, parse_foreach_matrix,
+    render_prompt_body, team_member_context, validate_template_variables,
 };
-use crate::workflow::SetupOptions;
 use crate::workflow::pr::detect_remote_branch;
 use crate::workflow::prompt_loader::{PromptLoadArgs, load_prompt, parse_prompt_with_frontmatter};
-use crate::{config, git, tmux, workflow};
+use crate::workflow::{ChangesTarget, FileFilter, SetupOptions};
+use crate::{config, dry_run, git, interrupt, resume, tmux, workflow};
 use anyhow::{Context, Result, anyhow};
 use serde_json::Value;
 use std::collections::BTreeMap;
 use std::io::{IsTerminal, Read};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 // Re-export the arg types that are used by the CLI
 pub use super::args::{MultiArgs, PromptArgs, RescueArgs, SetupFlags};
@@ -19,6 +23,10 @@ pub use super::args::{MultiArgs, PromptArgs, RescueArgs, SetupFlags};
 /// Variable name exposed to templates for stdin input lines
 const STDIN_INPUT_VAR: &str = "input";
 
+/// Prompt used with `--with-context` when none is given
+const PR_CONTEXT_PROMPT: &str = "Address the unresolved review comments on this pull request, \
+described below: change the code where they ask for changes, and run the tests.";
+
 /// Maximum stdin size to read (10MB) to prevent OOM from infinite streams
 const STDIN_MAX_BYTES: u64 = 10 * 1024 * 1024;
 
@@ -103,21 +111,30 @@ fn check_preconditions() -> Result<()> {
 pub fn run(
     branch_name: Option<&str>,
     pr: Option<u32>,
+    with_context: bool,
+    review: bool,
     auto_name: bool,
     base: Option<&str>,
     name: Option<String>,
+    template: Option<&str>,
     prompt_args: PromptArgs,
     setup: SetupFlags,
     rescue: RescueArgs,
     multi: MultiArgs,
     wait: bool,
+    no_fetch: bool,
 ) -> Result<()> {
     // Ensure preconditions are met (git repo and tmux session)
     check_preconditions()?;
 
+    // Remote that PR checkouts and fork remotes are resolved against
+    let repo_config = config::Config::load(None)?;
+    let default_remote = repo_config.default_remote().to_string();
+
     // Construct setup options from flags
     let mut options = SetupOptions::new(!setup.no_hooks, !setup.no_file_ops, !setup.no_pane_cmds);
     options.focus_window = !setup.background;
+    options.file_filter = FileFilter::new(&setup.skip_file, &setup.only_file)?;
 
     // Detect stdin input early
     let stdin_lines = read_stdin_lines()?;
@@ -127,6 +144,14 @@ pub fn run(
     let is_explicit_multi =
         has_stdin || multi.foreach.is_some() || multi.count.is_some() || multi.agent.len() > 1;
 
+    // The PR's description and review comments, with --with-context
+    let mut pr_context = None;
+    let mut pr_title = None;
+    // The branch the PR merges into, for the diff of --review
+    let mut pr_base = None;
+    // Without a branch name to check it out as, a PR is reviewed detached
+    let detach = review && branch_name.is_none();
+
     // Handle auto-name: load prompt first, generate branch name
     // In multi-worktree mode with auto-name, we defer LLM generation to the loop
     let (final_branch_name, preloaded_prompt, remote_branch_for_pr, deferred_auto_name) =
@@ -166,7 +191,30 @@ pub fn run(
             }
         } else if let Some(pr_number) = pr {
             // Handle PR checkout if --pr flag is provided
-            let result = workflow::pr::resolve_pr_ref(pr_number, branch_name)?;
+            let provider = workflow::pr::provider(repo_config.pr.as_ref(), &default_remote);
+            let result =
+                workflow::pr::resolve_pr_ref(pr_number, branch_name, &default_remote, provider)?;
+            if with_context {
+                let label = provider.label(pr_number);
+                let context = spinner::with_spinner(&format!("Fetching {} reviews", label), || {
+                    workflow::pr::review_context(provider, pr_number)
+                })
+                .with_context(|| format!("Failed to fetch review comments of {}", label))?;
+                println!(
+                    "Adding the description and {} unresolved review thread(s) to the prompt",
+                    context.threads.len()
+                );
+                pr_context = Some(context.render(&label));
+            }
+            pr_title = Some(result.title);
+            if review {
+                pr_base = Some(result.base_branch.ok_or_else(|| {
+                    anyhow!(
+                        "Could not tell which branch {} merges into",
+                        provider.label(pr_number)
+                    )
+                })?);
+            }
             (result.local_branch, None, Some(result.remote_branch), false)
         } else {
             // Normal flow: use provided branch name
@@ -188,10 +236,14 @@ pub fn run(
         base
     };
 
+    // The task shown in tmux: the explicit name, the PR's title, or else the
+    // prompt's first line
+    options.task = name.clone().or(pr_title);
+
     // Validate --with-changes compatibility
-    if rescue.with_changes && multi.agent.len() > 1 {
+    if rescue.with_changes && deferred_auto_name {
         return Err(anyhow!(
-            "--with-changes cannot be used with multiple --agent flags. Use zero or one --agent."
+            "--auto-name cannot be used with --with-changes for several worktrees. Give a branch name instead."
         ));
     }
 
@@ -209,19 +261,36 @@ pub fn run(
 
     // Handle rescue flow early if requested
     if rescue.with_changes {
-        let rescue_config = config::Config::load(multi.agent.first().map(|s| s.as_str()))?;
-        let rescue_context = workflow::WorkflowContext::new(rescue_config)?;
-        // Derive handle for rescue flow (uses config for naming strategy/prefix)
-        let handle =
-            crate::naming::derive_handle(branch_name, name.as_deref(), &rescue_context.config)?;
-        if handle_rescue_flow(
+        if multi.count.is_some() && multi.agent.len() > 1 {
+            return Err(anyhow!(
+                "--count can only be used with zero or one --agent, but {} were provided",
+                multi.agent.len()
+            ));
+        }
+        // One worktree per --agent or --count, each getting a copy of the changes
+        let specs = generate_worktree_specs(
             branch_name,
-            &handle,
-            &rescue,
-            &rescue_context,
-            options.clone(),
-            wait,
-        )? {
+            &multi.agent,
+            multi.count,
+            None,
+            &create_template_env(),
+            &multi.branch_template,
+        )?;
+        let mut targets = Vec::new();
+        for spec in specs {
+            let mut rescue_config = config::Config::load(spec.agent.as_deref())?;
+            apply_template(&mut rescue_config, template, &spec.branch_name)?;
+            let context = workflow::WorkflowContext::new(rescue_config)?;
+            // Derive handle for rescue flow (uses config for naming strategy/prefix)
+            let handle =
+                crate::naming::derive_handle(&spec.branch_name, name.as_deref(), &context.config)?;
+            targets.push(ChangesTarget {
+                branch_name: spec.branch_name,
+                handle,
+                context,
+            });
+        }
+        if handle_rescue_flow(&targets, &rescue, options.clone(), wait)? {
             return Ok(());
         }
     }
@@ -253,6 +322,12 @@ pub fn run(
         None
     };
 
+    // The PR's context follows the prompt, or makes up the whole prompt without one
+    let prompt_doc = match pr_context {
+        Some(_) => Some(with_pr_context(prompt_doc)),
+        None => prompt_doc,
+    };
+
     // Validate multi-worktree arguments
     if multi.count.is_some() && multi.agent.len() > 1 {
         return Err(anyhow!(
@@ -281,7 +356,7 @@ pub fn run(
     let (remote_branch, template_base_name) = if let Some(ref pr_remote) = remote_branch_for_pr {
         (Some(pr_remote.clone()), branch_name.to_string())
     } else {
-        detect_remote_branch(branch_name, base)?
+        detect_remote_branch(branch_name, base, &default_remote)?
     };
     let resolved_base = if remote_branch.is_some() { None } else { base };
 
@@ -290,7 +365,7 @@ pub fn run(
         determine_foreach_matrix(&multi, prompt_doc.as_ref(), stdin_lines)?;
 
     // Generate worktree specifications
-    let specs = generate_worktree_specs(
+    let mut specs = generate_worktree_specs(
         &template_base_name,
         &multi.agent,
         multi.count,
@@ -303,20 +378,82 @@ pub fn run(
         return Err(anyhow!("No worktree specifications were generated"));
     }
 
+    // Passed as a variable so braces in comments aren't read as template syntax
+    if let Some(context) = pr_context {
+        let vars = BTreeMap::from([(PR_CONTEXT_VAR.to_string(), context)]);
+        for spec in &mut specs {
+            add_template_variables(&mut spec.template_context, &vars);
+        }
+    }
+    if let Some(base) = pr_base {
+        let vars = BTreeMap::from([(PR_BASE_VAR.to_string(), base)]);
+        for spec in &mut specs {
+            add_template_variables(&mut spec.template_context, &vars);
+        }
+    }
+
+    // Resolve the team layout up front so a typo fails before anything is created
+    let team = match multi.team.as_deref() {
+        Some(team_name) => Some(config::Config::load(None)?.team_panes(team_name)?),
+        None => None,
+    };
+
+    // Fail on an unknown template before anything is created
+    if let Some(name) = template {
+        config::Config::load(None)?.select_template(Some(name), "")?;
+    }
+
     // Validate prompt template variables before proceeding to create worktrees.
     // We use the context from the first spec (variable schema is consistent across specs).
     if let Some(doc) = &prompt_doc
         && let Some(first_spec) = specs.first()
     {
-        validate_template_variables(&env, &doc.body, &first_spec.template_context)
-            .context("Prompt template uses undefined variables")?;
+        let mut context = match team.as_ref().and_then(|members| members.first()) {
+            Some((role, pane)) => team_member_context(
+                &first_spec.template_context,
+                role,
+                pane.command.as_deref().unwrap_or_default(),
+            ),
+            None => first_spec.template_context.clone(),
+        };
+        let mut vars: BTreeMap<String, String> = multi.vars.iter().cloned().collect();
+        add_template_variables(&mut context, &vars);
+
+        // Ask for the rest rather than sending literal placeholders to the agent
+        if std::io::stdin().is_terminal() {
+            for name in missing_template_variables(&env, &doc.body, &context)? {
+                // Attribute lookups like `{{ issue.title }}` can't be typed in
+                if name.contains('.') {
+                    continue;
+                }
+                let value = super::ask(&format!("Value for '{}':", name))?;
+                vars.insert(name, value);
+            }
+            add_template_variables(&mut context, &vars);
+        }
+
+        validate_template_variables(&env, &doc.body, &context)
+            .context("Prompt template uses undefined variables (set them with --var name=value)")?;
+        for spec in &mut specs {
+            add_template_variables(&mut spec.template_context, &vars);
+        }
+    }
+
+    // Generated names come one at a time, so auto-naming creates sequentially
+    let parallel = multi.parallel.or(repo_config.add_parallel).unwrap_or(1) as usize;
+    if parallel > 1 && deferred_auto_name && multi.parallel.is_some() {
+        eprintln!("Note: --auto-name creates worktrees one at a time; ignoring --parallel");
     }
+    let parallel = if deferred_auto_name { 1 } else { parallel };
 
     // Create worktrees from specs
     let plan = CreationPlan {
         specs: &specs,
         resolved_base,
         remote_branch: remote_branch.as_deref(),
+        pr,
+        review,
+        detach,
         prompt_doc: prompt_doc.as_ref(),
         options,
         env: &env,
@@ -324,17 +461,46 @@ pub fn run(
         wait,
         deferred_auto_name,
         max_concurrent: multi.max_concurrent,
+        parallel,
+        no_fetch,
+        team: team.as_deref(),
+        template,
     };
     plan.execute()
 }
 
+/// Make room for the PR's context in the prompt: after it, unless the prompt
+/// places `{{ pr_context }}` itself
+fn with_pr_context(prompt_doc: Option<PromptDocument>) -> PromptDocument {
+    let mut doc = prompt_doc.unwrap_or_else(|| PromptDocument {
+        body: PR_CONTEXT_PROMPT.to_string(),
+        meta: PromptMetadata::default(),
+    });
+    if !doc.body.contains(PR_CONTEXT_VAR) {
+        doc.body = format!("{}\n\n{{{{ {} }}}}\n", doc.body.trim_end(), PR_CONTEXT_VAR);
+    }
+    doc
+}
+
+/// Apply the template given with --template, or else the one whose `branches`
+/// match the branch, to the config for a new worktree
+fn apply_template(
+    config: &mut config::Config,
+    requested: Option<&str>,
+    branch_name: &str,
+) -> Result<()> {
+    if let Some(name) = config.select_template(requested, branch_name)? {
+        println!("Using template '{}'", name);
+        config.apply_template(&name)?;
+    }
+    Ok(())
+}
+
 /// Handle the rescue flow (--with-changes).
 /// Returns Ok(true) if rescue flow was handled, Ok(false) if normal flow should continue.
 fn handle_rescue_flow(
-    branch_name: &str,
-    handle: &str,
+    targets: &[ChangesTarget],
     rescue: &RescueArgs,
-    context: &workflow::WorkflowContext,
     options: SetupOptions,
     wait: bool,
 ) -> Result<bool> {
@@ -342,25 +508,37 @@ fn handle_rescue_flow(
         return Ok(false);
     }
 
-    let result = workflow::create_with_changes(
-        branch_name,
-        handle,
-        rescue.include_untracked,
-        rescue.patch,
-        context,
-        options,
-    )
-    .context("Failed to move uncommitted changes")?;
+    let results =
+        workflow::create_with_changes(targets, rescue.include_untracked, rescue.patch, options)
+            .context("Failed to move uncommitted changes")?;
 
-    println!(
-        "✓ Moved uncommitted changes to new worktree for branch '{}'\n  Worktree: {}\n  Original worktree is now clean",
-        result.branch_name,
-        result.worktree_path.display()
-    );
+    if let [result] = results.as_slice() {
+        println!(
+            "✓ Moved uncommitted changes to new worktree for branch '{}'\n  Worktree: {}\n  Original worktree is now clean",
+            result.branch_name,
+            result.worktree_path.display()
+        );
+    } else {
+        println!(
+            "✓ Copied uncommitted changes to {} new worktrees:",
+            results.len()
+        );
+        for result in &results {
+            println!(
+                "  {}: {}",
+                result.branch_name,
+                result.worktree_path.display()
+            );
+        }
+        println!("  Original worktree is now clean");
+    }
 
     if wait {
-        let full_window_name = tmux::prefixed(&context.prefix, handle);
-        tmux::wait_until_windows_closed(&[full_window_name])?;
+        let window_names: Vec<String> = targets
+            .iter()
+            .map(|target| tmux::prefixed(&target.context.prefix, &target.handle))
+            .collect();
+        tmux::wait_until_windows_closed(&window_names)?;
     }
 
     Ok(true)
@@ -442,6 +620,12 @@ struct CreationPlan<'a> {
     specs: &'a [WorktreeSpec],
     resolved_base: Option<&'a str>,
     remote_branch: Option<&'a str>,
+    /// Pull or merge request being checked out, from `--pr`
+    pr: Option<u32>,
+    /// Checked out to review the pull request, from `--review`
+    review: bool,
+    /// Checked out detached, for `--review` without a branch name
+    detach: bool,
     prompt_doc: Option<&'a PromptDocument>,
     options: SetupOptions,
     env: &'a TemplateEnv,
@@ -449,6 +633,13 @@ struct CreationPlan<'a> {
     wait: bool,
     deferred_auto_name: bool,
     max_concurrent: Option<u32>,
+    /// How many worktrees to create at once
+    parallel: usize,
+    no_fetch: bool,
+    /// Agent team members as (role, pane) pairs, from `--team`
+    team: Option<&'a [(String, config::PaneConfig)]>,
+    /// Worktree template from `--template`
+    template: Option<&'a str>,
 }
 
 impl<'a> CreationPlan<'a> {
@@ -458,16 +649,63 @@ impl<'a> CreationPlan<'a> {
     }
 
     fn create_worktrees(&self) -> Result<()> {
-        if self.specs.len() > 1 {
-            println!("Preparing to create {} worktrees...", self.specs.len());
+        let total = self.specs.len();
+        if total > 1 {
+            println!("Preparing to create {} worktrees...", total);
+        }
+
+        // Pick up after the worktrees an interrupted run of this command created
+        let resumed = if total > 1 {
+            resume::load_for_current_command()
+                .filter(|state| state.completed.len() + state.remaining.len() == total)
+        } else {
+            None
+        };
+        let completed = resumed
+            .as_ref()
+            .map(|state| state.completed.clone())
+            .unwrap_or_default();
+        if !completed.is_empty() {
+            println!(
+                "Resuming: skipping {} worktree(s) created before the last run stopped",
+                completed.len()
+            );
         }
 
+        // Ctrl-C stops between worktrees rather than killing workmux mid-creation
+        let guard = interrupt::Guard::new()?;
+        let created_windows = if self.parallel > 1 && total > 1 {
+            self.create_parallel(completed)?
+        } else {
+            self.create_sequential(completed)?
+        };
+        drop(guard);
+
+        if resumed.is_some() {
+            resume::clear();
+        }
+
+        if self.wait && !created_windows.is_empty() {
+            tmux::wait_until_windows_closed(&created_windows)?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the specs not yet in `completed` one at a time, returning the
+    /// windows to wait for.
+    fn create_sequential(&self, mut completed: Vec<String>) -> Result<Vec<String>> {
+        let total = self.specs.len();
         // Track windows for --wait (all created windows)
         let mut created_windows = Vec::new();
         // Track currently active windows for --max-concurrent
         let mut active_windows: Vec<String> = Vec::new();
 
-        for (i, spec) in self.specs.iter().enumerate() {
+        let pending: Vec<usize> = (0..self.specs.len())
+            .filter(|&i| !self.is_completed(i, &completed))
+            .collect();
+        for i in pending {
+            let spec = &self.specs[i];
             // Concurrency control: wait for a slot if at limit
             if let Some(limit) = self.max_concurrent {
                 let limit = limit as usize;
@@ -475,103 +713,306 @@ impl<'a> CreationPlan<'a> {
                 if active_windows.len() >= limit {
                     loop {
                         active_windows = tmux::filter_active_windows(&active_windows)?;
-                        if active_windows.len() < limit {
+                        if active_windows.len() < limit || interrupt::is_interrupted() {
                             break;
                         }
                         std::thread::sleep(std::time::Duration::from_millis(WORKER_POOL_POLL_MS));
                     }
                 }
             }
-            // Load config for this specific agent to ensure correct agent resolution
-            let config = config::Config::load(spec.agent.as_deref())?;
-
-            // Render prompt first (needed for deferred auto-name)
-            let rendered_prompt = if let Some(doc) = self.prompt_doc {
-                Some(
-                    render_prompt_body(&doc.body, self.env, &spec.template_context)
-                        .with_context(|| format!("Failed to render prompt for spec index {}", i))?,
-                )
-            } else {
-                None
-            };
 
-            // If auto-name was deferred, run it now using the rendered prompt
-            let final_branch_name = if self.deferred_auto_name {
-                generate_branch_name_with_spinner(rendered_prompt.as_deref(), &config)?
-            } else {
-                spec.branch_name.clone()
-            };
+            if interrupt::is_interrupted() {
+                return self.stop_interrupted(&completed);
+            }
+            match self.create_spec(i, spec) {
+                Ok((branch, window)) => {
+                    completed.push(branch);
+                    if self.wait {
+                        created_windows.push(window.clone());
+                    }
+                    // Track for concurrency control
+                    if self.max_concurrent.is_some() {
+                        active_windows.push(window);
+                    }
+                }
+                Err(e) if total > 1 && interrupt::is_interrupted() => {
+                    eprintln!("{:#}", e);
+                    return self.stop_interrupted(&completed);
+                }
+                Err(e) => {
+                    if total > 1 {
+                        self.save_progress(&completed);
+                    }
+                    return Err(e);
+                }
+            }
+        }
 
-            if self.specs.len() > 1 {
-                println!(
-                    "\n--- [{}/{}] Creating worktree: {} ---",
-                    i + 1,
-                    self.specs.len(),
-                    final_branch_name
-                );
+        Ok(created_windows)
+    }
+
+    /// Create the specs not yet in `completed` on up to `parallel` threads. A
+    /// failing spec doesn't stop the others; failures are reported together
+    /// once every spec has been tried. Returns the windows to wait for.
+    fn create_parallel(&self, mut completed: Vec<String>) -> Result<Vec<String>> {
+        let pending: Vec<usize> = (0..self.specs.len())
+            .filter(|&i| !completed.contains(&self.specs[i].branch_name))
+            .collect();
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..self.parallel.min(pending.len()) {
+                scope.spawn(|| {
+                    while !interrupt::is_interrupted() {
+                        let Some(&i) = pending.get(next.fetch_add(1, Ordering::SeqCst)) else {
+                            break;
+                        };
+                        let result = self.create_spec(i, &self.specs[i]);
+                        results.lock().unwrap().push((i, result));
+                    }
+                });
             }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(i, _)| *i);
 
-            // Derive handle from branch name, optional explicit name, and config
-            // For single specs, explicit_name overrides; for multi-specs, it's None (disallowed)
-            let handle =
-                crate::naming::derive_handle(&final_branch_name, self.explicit_name, &config)?;
+        let mut created_windows = Vec::new();
+        let mut failures = Vec::new();
+        for (i, result) in results {
+            match result {
+                Ok((branch, window)) => {
+                    completed.push(branch);
+                    if self.wait {
+                        created_windows.push(window);
+                    }
+                }
+                Err(e) => failures.push((i, e)),
+            }
+        }
 
-            let prompt_for_spec = rendered_prompt.map(Prompt::Inline);
+        if interrupt::is_interrupted() {
+            for (_, e) in &failures {
+                eprintln!("{:#}", e);
+            }
+            return self.stop_interrupted(&completed);
+        }
 
-            super::announce_hooks(&config, Some(&self.options), super::HookPhase::PostCreate);
+        if !failures.is_empty() {
+            eprintln!(
+                "\nFailed to create {} of {} worktree(s):",
+                failures.len(),
+                pending.len()
+            );
+            for (i, e) in &failures {
+                eprintln!("  {}: {:#}", self.specs[*i].branch_name, e);
+            }
+            self.save_progress(&completed);
+            return Err(anyhow!(
+                "{} worktree(s) could not be created",
+                failures.len()
+            ));
+        }
 
-            // Create a WorkflowContext for this spec's config
-            let context = workflow::WorkflowContext::new(config)?;
+        Ok(created_windows)
+    }
 
-            // Calculate window name for tracking
-            let full_window_name = tmux::prefixed(&context.prefix, &handle);
+    /// Whether the spec at `i` is among the `completed` branches. Auto-named
+    /// branches only get their name once created, one at a time in order, so
+    /// those are matched by position.
+    fn is_completed(&self, i: usize, completed: &[String]) -> bool {
+        if self.deferred_auto_name {
+            i < completed.len()
+        } else {
+            completed.contains(&self.specs[i].branch_name)
+        }
+    }
 
-            if self.wait {
-                created_windows.push(full_window_name.clone());
-            }
+    /// The specs not among the `completed` branches, as shown to the user
+    fn remaining(&self, completed: &[String]) -> Vec<String> {
+        (0..self.specs.len())
+            .filter(|&i| !self.is_completed(i, completed))
+            .map(|i| {
+                if self.deferred_auto_name {
+                    format!("worktree {} (auto-named)", i + 1)
+                } else {
+                    self.specs[i].branch_name.clone()
+                }
+            })
+            .collect()
+    }
 
-            // Track for concurrency control
-            if self.max_concurrent.is_some() {
-                active_windows.push(full_window_name);
-            }
+    /// Report a run stopped by Ctrl-C with the `completed` branches created
+    fn stop_interrupted<T>(&self, completed: &[String]) -> Result<T> {
+        super::report_interrupted(
+            completed,
+            &self.remaining(completed),
+            "Run the same command again to create the rest.",
+        );
+        Err(interrupt::Interrupted.into())
+    }
 
-            let result = workflow::create(
-                &context,
-                workflow::CreateArgs {
-                    branch_name: &final_branch_name,
-                    handle: &handle,
-                    base_branch: self.resolved_base,
-                    remote_branch: self.remote_branch,
-                    prompt: prompt_for_spec.as_ref(),
-                    options: self.options.clone(),
-                    agent: spec.agent.as_deref(),
-                },
+    /// Record the `completed` branches of a run stopped by a failure, so
+    /// running it again after fixing the cause creates only the rest
+    fn save_progress(&self, completed: &[String]) {
+        if completed.is_empty() {
+            return;
+        }
+        if let Some(path) = resume::save(completed, &self.remaining(completed)) {
+            eprintln!(
+                "Progress saved to {}. Once what failed is fixed, and any worktree it left removed with 'workmux rm', run the same command again to create the rest.",
+                path.display()
+            );
+        }
+    }
+
+    /// Create the worktree and window for one spec, returning the branch name
+    /// and the full window name.
+    fn create_spec(&self, i: usize, spec: &WorktreeSpec) -> Result<(String, String)> {
+        // Load config for this specific agent to ensure correct agent resolution
+        let mut config = config::Config::load(spec.agent.as_deref())?;
+        if self.no_fetch {
+            config.fetch = Some(config::FetchPolicy::Never);
+        }
+
+        // Render prompt first (needed for deferred auto-name)
+        let rendered_prompt = if let Some(doc) = self.prompt_doc {
+            Some(
+                render_prompt_body(&doc.body, self.env, &spec.template_context)
+                    .with_context(|| format!("Failed to render prompt for spec index {}", i))?,
             )
-            .with_context(|| {
-                format!(
-                    "Failed to create worktree environment for branch '{}'",
-                    final_branch_name
-                )
-            })?;
-
-            if result.post_create_hooks_run > 0 {
-                println!("✓ Setup complete");
-            }
+        } else {
+            None
+        };
+
+        // If auto-name was deferred, run it now using the rendered prompt
+        let final_branch_name = if self.deferred_auto_name {
+            generate_branch_name_with_spinner(rendered_prompt.as_deref(), &config)?
+        } else {
+            spec.branch_name.clone()
+        };
 
+        if self.specs.len() > 1 {
             println!(
-                "✓ Successfully created worktree and tmux window for '{}'",
-                result.branch_name
+                "\n--- [{}/{}] Creating worktree: {} ---",
+                i + 1,
+                self.specs.len(),
+                final_branch_name
             );
-            if let Some(ref base) = result.base_branch {
-                println!("  Base: {}", base);
+        }
+
+        // Derive handle from branch name, optional explicit name, and config
+        // For single specs, explicit_name overrides; for multi-specs, it's None (disallowed)
+        let handle = crate::naming::derive_handle(&final_branch_name, self.explicit_name, &config)?;
+
+        apply_template(&mut config, self.template, &final_branch_name)?;
+        // A template given with --template arranges reviews its own way
+        if self.review && self.template.is_none() {
+            config.panes = Some(config.review_panes()?);
+        }
+
+        // Agent teams get one pane per member, each with its own rendered prompt
+        let mut options = self.options.clone();
+        options.template_context = Some(spec.template_context.clone());
+        let prompt_for_spec = if let Some(team) = self.team {
+            config.panes = Some(team.iter().map(|(_, pane)| pane.clone()).collect());
+            if let Some(doc) = self.prompt_doc {
+                options.pane_prompt_paths = team
+                    .iter()
+                    .map(|(role, pane)| {
+                        let context = team_member_context(
+                            &spec.template_context,
+                            role,
+                            pane.command.as_deref().unwrap_or_default(),
+                        );
+                        let prompt = render_prompt_body(&doc.body, self.env, &context)
+                            .with_context(|| {
+                                format!("Failed to render prompt for role '{}'", role)
+                            })?;
+                        workflow::write_prompt_file(
+                            &format!("{}-{}", handle, role),
+                            &Prompt::Inline(prompt),
+                        )
+                        .map(Some)
+                    })
+                    .collect::<Result<_>>()?;
             }
-            println!("  Worktree: {}", result.worktree_path.display());
+            None
+        } else {
+            rendered_prompt.map(Prompt::Inline)
+        };
+
+        // The worktree doesn't exist yet, so `exists` conditions are checked
+        // in the main worktree, as pre-create hooks run there
+        let main_worktree_root = git::get_main_worktree_root()?;
+        let target = config::WhenTarget {
+            branch: &final_branch_name,
+            agent: spec.agent.as_deref().or(config.agent.as_deref()),
+            worktree_path: &main_worktree_root,
+        };
+        super::announce_hooks(
+            &config,
+            Some(&options),
+            super::HookPhase::PreCreate,
+            &target,
+        );
+        super::announce_hooks(
+            &config,
+            Some(&options),
+            super::HookPhase::PostCreate,
+            &target,
+        );
+
+        // Create a WorkflowContext for this spec's config
+        let context = workflow::WorkflowContext::new(config)?;
+
+        // Calculate window name for tracking
+        let full_window_name = tmux::prefixed(&context.prefix, &handle);
+
+        let result = workflow::create(
+            &context,
+            workflow::CreateArgs {
+                branch_name: &final_branch_name,
+                handle: &handle,
+                base_branch: self.resolved_base,
+                remote_branch: self.remote_branch,
+                prompt: prompt_for_spec.as_ref(),
+                options,
+                agent: spec.agent.as_deref(),
+                pr: self.pr,
+                review: self.review,
+                detach: self.detach,
+            },
+        )
+        .with_context(|| {
+            format!(
+                "Failed to create worktree environment for branch '{}'",
+                final_branch_name
+            )
+        })?;
+
+        if result.post_create_hooks_run > 0 {
+            println!("✓ Setup complete");
         }
 
-        if self.wait && !created_windows.is_empty() {
-            tmux::wait_until_windows_closed(&created_windows)?;
+        println!(
+            "{} worktree and tmux window for '{}'",
+            dry_run::outcome("✓ Successfully created", "[dry-run] Would create"),
+            result.branch_name
+        );
+        if let Some(ref base) = result.base_branch {
+            println!("  Base: {}", base);
+        }
+        println!("  Worktree: {}", result.worktree_path.display());
+        if let Some(team) = self.team {
+            let members: Vec<String> = team
+                .iter()
+                .map(|(role, pane)| {
+                    format!("{} ({})", role, pane.command.as_deref().unwrap_or_default())
+                })
+                .collect();
+            println!("  Team: {}", members.join(", "));
         }
 
-        Ok(())
+        Ok((result.branch_name, full_window_name))
     }
 }
@@ -0,0 +1,208 @@
+use crate::agent::{self, Agent, AgentUsage};
+use crate::config::{self, Config, PromptInput, StatusTracking};
+use anyhow::Result;
+use std::path::Path;
+use tabled::{
+    Table, Tabled,
+    settings::{Padding, Style, object::Columns},
+};
+
+/// One agent with everything that runs it
+struct AgentEntry {
+    agent: Agent,
+    usages: Vec<AgentUsage>,
+    binary: Option<String>,
+}
+
+#[derive(Tabled)]
+struct AgentRow {
+    #[tabled(rename = "AGENT")]
+    name: String,
+    #[tabled(rename = "PROMPT")]
+    prompt: String,
+    #[tabled(rename = "STATUS")]
+    status: String,
+    #[tabled(rename = "BINARY")]
+    binary: String,
+    #[tabled(rename = "USED BY")]
+    used_by: String,
+}
+
+pub fn run(name: Option<&str>) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let entries = entries(&config, agent::usages(&config));
+
+    match name {
+        Some(name) => {
+            let entry = entries
+                .into_iter()
+                .find(|entry| entry.agent.name == name)
+                .unwrap_or_else(|| entry(known(&config, name), Vec::new()));
+            describe(&entry);
+        }
+        None => list(entries),
+    }
+    Ok(())
+}
+
+/// Agents in the order the config uses them, then the defined agents and
+/// built-in profiles nothing uses
+fn entries(config: &Config, usages: Vec<AgentUsage>) -> Vec<AgentEntry> {
+    let mut grouped: Vec<(Agent, Vec<AgentUsage>)> = Vec::new();
+    for usage in usages {
+        let Some(agent) = agent::for_command(config, &usage.command)
+            .or_else(|| agent::name(&usage.command).map(|name| Agent::unknown(&name)))
+        else {
+            continue;
+        };
+        match grouped
+            .iter_mut()
+            .find(|(existing, _)| existing.name == agent.name)
+        {
+            Some((_, usages)) => usages.push(usage),
+            None => grouped.push((agent, vec![usage])),
+        }
+    }
+    let defined = config
+        .agents
+        .iter()
+        .flatten()
+        .map(|(name, _)| name.as_str());
+    let built_in = agent::PROFILES.iter().map(|profile| profile.name);
+    for name in defined.chain(built_in) {
+        if !grouped.iter().any(|(agent, _)| agent.name == name) {
+            grouped.push((known(config, name), Vec::new()));
+        }
+    }
+    grouped
+        .into_iter()
+        .map(|(agent, usages)| entry(agent, usages))
+        .collect()
+}
+
+/// The agent called `name`, or one workmux knows nothing about
+fn known(config: &Config, name: &str) -> Agent {
+    agent::find(config, name).unwrap_or_else(|| Agent::unknown(name))
+}
+
+fn entry(agent: Agent, usages: Vec<AgentUsage>) -> AgentEntry {
+    // Run what the config runs, so a configured path is checked rather than PATH
+    let command = usages
+        .first()
+        .map_or(&agent.command, |usage| &usage.command);
+    let executable = config::split_first_token(command)
+        .map_or(agent.name.clone(), |(executable, _)| executable.to_string());
+    let binary =
+        config::resolve_executable_path(&executable).filter(|path| Path::new(path).exists());
+    AgentEntry {
+        agent,
+        usages,
+        binary,
+    }
+}
+
+/// How the agent is given a prompt, with the prompt shown as `prompt`
+fn prompt_usage(agent: &Agent, prompt: &str) -> String {
+    let flag = if agent.prompt_flag.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", agent.prompt_flag)
+    };
+    match agent.prompt {
+        PromptInput::Arg | PromptInput::File => format!("{}{}", flag, prompt),
+        PromptInput::Stdin => format!("< {}", prompt),
+    }
+}
+
+fn list(entries: Vec<AgentEntry>) {
+    let rows: Vec<AgentRow> = entries
+        .into_iter()
+        .map(|entry| {
+            let mut used_by: Vec<&str> = entry.usages.iter().map(|u| u.used_by.as_str()).collect();
+            used_by.dedup();
+            AgentRow {
+                prompt: prompt_usage(
+                    &entry.agent,
+                    match entry.agent.prompt {
+                        PromptInput::Arg => "<prompt>",
+                        PromptInput::File | PromptInput::Stdin => "<file>",
+                    },
+                ),
+                status: status(entry.agent.status, false),
+                binary: entry.binary.unwrap_or_else(|| "not found".to_string()),
+                used_by: if used_by.is_empty() {
+                    "-".to_string()
+                } else {
+                    used_by.join(", ")
+                },
+                name: entry.agent.name,
+            }
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table
+        .with(Style::blank())
+        .modify(Columns::new(0..5), Padding::new(0, 1, 0, 0));
+    println!("{table}");
+}
+
+fn describe(entry: &AgentEntry) {
+    let agent = &entry.agent;
+    println!("{}", agent.name);
+    println!(
+        "  {:<15}{}",
+        "profile",
+        match (agent.defined, agent.built_in) {
+            (true, true) => "built-in, changed by the agents config",
+            (true, false) => "agents config",
+            (false, true) => "built-in",
+            (false, false) => "none (prompt passed after --)",
+        }
+    );
+    println!("  {:<15}{}", "command", agent.command);
+    println!(
+        "  {:<15}{}",
+        "binary",
+        entry.binary.as_deref().unwrap_or("not found on PATH")
+    );
+    let prompt = match agent.prompt {
+        PromptInput::Arg => "\"$(cat PROMPT.md)\"",
+        PromptInput::File | PromptInput::Stdin => "PROMPT.md",
+    };
+    println!(
+        "  {:<15}{} {}",
+        "prompt",
+        agent.command,
+        prompt_usage(agent, prompt)
+    );
+    println!("  {:<15}{}", "status", status(agent.status, true));
+
+    if entry.usages.is_empty() {
+        println!("  {:<15}not used by the configuration", "used by");
+    } else {
+        println!("  used by");
+        for usage in &entry.usages {
+            println!("    {:<24}{}", usage.used_by, usage.command);
+        }
+    }
+}
+
+fn status(status: Option<StatusTracking>, long: bool) -> String {
+    match status {
+        Some(StatusTracking::Hooks) if long => {
+            "hooks, see https://workmux.raine.dev/guide/status-tracking".to_string()
+        }
+        Some(StatusTracking::Hooks) => "hooks".to_string(),
+        Some(StatusTracking::Exit) if long => {
+            "working while the agent runs, done once it exits".to_string()
+        }
+        Some(StatusTracking::Exit) => "exit".to_string(),
+        Some(StatusTracking::None) if long => {
+            "not supported, the window shows no agent status".to_string()
+        }
+        Some(StatusTracking::None) => "-".to_string(),
+        None if long => "unknown".to_string(),
+        None => "?".to_string(),
+    }
+}
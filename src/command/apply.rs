@@ -0,0 +1,180 @@
+use crate::manifest::Manifest;
+use crate::workflow::{self, ApplyAction, ApplyStep, WorkflowContext};
+use crate::{config, git, worktree_state};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// A worktree not listed in the manifest, considered for removal with --prune
+struct PruneStep {
+    path: PathBuf,
+    branch: String,
+    handle: String,
+    /// Why the worktree is kept despite --prune (None if it will be removed)
+    skip_reason: Option<String>,
+}
+
+fn describe(step: &ApplyStep) -> String {
+    match step.action {
+        ApplyAction::Create => match &step.entry.base {
+            Some(base) => format!("  + {} (create from {})", step.handle, base),
+            None => format!("  + {} (create)", step.handle),
+        },
+        ApplyAction::Open => format!("  ~ {} (open window)", step.handle),
+        ApplyAction::Unchanged => format!("  = {} (up to date)", step.handle),
+    }
+}
+
+fn describe_prune(step: &PruneStep) -> String {
+    match &step.skip_reason {
+        Some(reason) => format!("  ! {} (not listed, kept: {})", step.handle, reason),
+        None => format!("  - {} (remove worktree and branch)", step.handle),
+    }
+}
+
+/// Find worktrees missing from the manifest and check whether they are safe to remove.
+fn plan_prune(manifest: &Manifest, force: bool) -> Result<Vec<PruneStep>> {
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+    let remote = context.config.default_remote();
+
+    workflow::unlisted_worktrees(manifest, &context)?
+        .into_iter()
+        .map(|(path, branch)| {
+            let handle = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(&branch)
+                .to_string();
+
+            let skip_reason = if worktree_state::is_pinned(&handle) {
+                Some("pinned".to_string())
+            } else if force {
+                None
+            } else if let Some(operation) = git::in_progress_operation(&path) {
+                Some(format!("{} in progress", operation.label()))
+            } else if path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
+                Some("uncommitted changes".to_string())
+            } else {
+                super::remove::is_unmerged(&handle, &branch, remote)?
+                    .map(|base| format!("commits not merged into {}", base))
+            };
+
+            Ok(PruneStep {
+                path,
+                branch,
+                handle,
+                skip_reason,
+            })
+        })
+        .collect()
+}
+
+pub fn run(manifest_path: &Path, prune: bool, dry_run: bool, force: bool) -> Result<()> {
+    let manifest = Manifest::load(manifest_path)?;
+
+    let steps = workflow::plan(&manifest)?;
+    let prune_steps = if prune {
+        plan_prune(&manifest, force)?
+    } else {
+        Vec::new()
+    };
+
+    if steps.is_empty() && prune_steps.is_empty() {
+        println!("Nothing to do for {}", manifest_path.display());
+        return Ok(());
+    }
+
+    println!("Plan for {}:", manifest_path.display());
+    for step in &steps {
+        println!("{}", describe(step));
+    }
+    for step in &prune_steps {
+        println!("{}", describe_prune(step));
+    }
+
+    let to_remove: Vec<&PruneStep> = prune_steps
+        .iter()
+        .filter(|step| step.skip_reason.is_none())
+        .collect();
+
+    if prune_steps.len() > to_remove.len() {
+        println!("\nUse --force to also remove the worktrees marked with '!'.");
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    // Removing worktrees is destructive, so confirm according to the policy
+    let confirm = config::Config::load(None)?.confirm;
+    let policy = confirm.removal(confirm.clean(), force);
+    if !to_remove.is_empty() {
+        let question = format!(
+            "\nRemove {} worktree(s) not listed in the manifest?",
+            to_remove.len()
+        );
+        if !super::confirm(&question, policy)? {
+            return Ok(());
+        }
+    }
+
+    let (mut created, mut opened) = (0, 0);
+    for step in &steps {
+        let Some(result) = workflow::apply(step)? else {
+            continue;
+        };
+
+        match step.action {
+            ApplyAction::Create => {
+                created += 1;
+                println!(
+                    "✓ Created worktree for '{}'\n  Worktree: {}",
+                    result.branch_name,
+                    result.worktree_path.display()
+                );
+            }
+            ApplyAction::Open => {
+                opened += 1;
+                println!(
+                    "✓ Opened tmux window for '{}'\n  Worktree: {}",
+                    result.branch_name,
+                    result.worktree_path.display()
+                );
+            }
+            ApplyAction::Unchanged => {}
+        }
+    }
+
+    let mut removed = 0;
+    let mut failed: Vec<(String, String)> = Vec::new();
+    for step in to_remove {
+        // force=true because we already checked/prompted
+        match super::remove::remove_worktree(&step.handle, &step.path, true, false, policy) {
+            Ok(true) => removed += 1,
+            Ok(false) => {}
+            Err(e) => failed.push((step.branch.clone(), e.to_string())),
+        }
+    }
+
+    let unchanged = steps.len() - created - opened;
+    if prune {
+        println!(
+            "\nApplied: {} created, {} opened, {} unchanged, {} removed",
+            created, opened, unchanged, removed
+        );
+    } else {
+        println!(
+            "\nApplied: {} created, {} opened, {} unchanged",
+            created, opened, unchanged
+        );
+    }
+
+    if !failed.is_empty() {
+        eprintln!("\nFailed to remove {} worktree(s):", failed.len());
+        for (branch, error) in &failed {
+            eprintln!("  - {}: {}", branch, error);
+        }
+        return Err(anyhow!("Some worktrees could not be removed"));
+    }
+
+    Ok(())
+}
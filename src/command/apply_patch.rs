@@ -0,0 +1,65 @@
+use crate::workflow::{self, PatchOutcome};
+use anyhow::{Context, Result, anyhow};
+use std::io::Read;
+use std::path::Path;
+
+/// Read the patch from a file, or from stdin when the path is "-".
+fn read_patch(patch: &Path) -> Result<String> {
+    if patch == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .context("Failed to read patch from stdin")?;
+        Ok(content)
+    } else {
+        std::fs::read_to_string(patch)
+            .with_context(|| format!("Failed to read patch '{}'", patch.display()))
+    }
+}
+
+pub fn run(name: &str, patch: &Path, check: bool) -> Result<()> {
+    let content = read_patch(patch)?;
+
+    if check {
+        let (files, clean) = workflow::check_patch(name, &content)?;
+        if clean {
+            println!("✓ Patch applies cleanly to '{}'", name);
+        } else {
+            println!(
+                "Patch does not apply cleanly to '{}'; applying it would fall back to a 3-way merge",
+                name
+            );
+        }
+        for file in &files {
+            println!("  {}", file);
+        }
+        return Ok(());
+    }
+
+    let result = workflow::apply_patch(name, &content)?;
+
+    match &result.outcome {
+        PatchOutcome::Clean => println!("✓ Applied patch to '{}'", name),
+        PatchOutcome::Merged => println!("✓ Applied patch to '{}' with a 3-way merge", name),
+        PatchOutcome::Conflicts(_) => {
+            println!("Applied patch to '{}' with conflicts", name)
+        }
+    }
+    println!("  Worktree: {}", result.worktree_path.display());
+    for file in &result.files {
+        println!("  {}", file);
+    }
+
+    if let PatchOutcome::Conflicts(conflicts) = &result.outcome {
+        eprintln!("\nResolve the conflict markers in:");
+        for file in conflicts {
+            eprintln!("  - {}", file);
+        }
+        return Err(anyhow!(
+            "Patch applied with {} conflict(s)",
+            conflicts.len()
+        ));
+    }
+
+    Ok(())
+}
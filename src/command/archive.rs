@@ -0,0 +1,174 @@
+use crate::command::args::SetupFlags;
+use crate::command::history::format_timestamp;
+use crate::workflow::{self, FileFilter, SetupOptions, WorkflowContext};
+use crate::{config, dry_run, git, remote, worktree_state};
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Archive a worktree's branch, uncommitted changes and state to a file, then
+/// remove the worktree and its branch
+pub fn run(name: &str, output: Option<&Path>) -> Result<()> {
+    // The archive is written here, but the worktree is on the other machine
+    if remote::get().is_some() {
+        return Err(anyhow!("archive is not supported with --host"));
+    }
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+    let (worktree_path, _) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    // Archiving and removing look the worktree up by handle again
+    git::pin_worktree(&handle, &worktree_path);
+    if worktree_state::is_pinned(&handle) {
+        return Err(anyhow!(
+            "Worktree '{}' is pinned. Run 'workmux unpin {}' first.",
+            handle,
+            handle
+        ));
+    }
+
+    let out = match output {
+        Some(path) => path.to_path_buf(),
+        None => workflow::archive_dir()?.join(format!("{}.tar", handle)),
+    };
+    if out.exists() {
+        return Err(anyhow!(
+            "'{}' already exists. Restore or delete it, or pick another file with --output.",
+            out.display()
+        ));
+    }
+    if dry_run::skip(format_args!(
+        "archive '{}' to {} and remove it",
+        handle,
+        out.display()
+    )) {
+        return Ok(());
+    }
+
+    let meta = workflow::archive(&handle, &out, &context)
+        .with_context(|| format!("Failed to archive '{}'", handle))?;
+    println!(
+        "✓ Archived '{}' to {}\n  {} commit(s){}",
+        handle,
+        out.display(),
+        meta.commits,
+        if meta.uncommitted {
+            ", uncommitted changes"
+        } else {
+            ""
+        }
+    );
+
+    // Everything uncommitted is in the archive, so nothing is lost by forcing
+    super::announce_hooks(
+        &context.config.for_branch(&meta.branch),
+        None,
+        super::HookPhase::PreRemove,
+        &config::WhenTarget {
+            branch: &meta.branch,
+            agent: context.config.agent.as_deref(),
+            worktree_path: &worktree_path,
+        },
+    );
+    workflow::remove(&handle, true, false, &context)
+        .with_context(|| format!("Archived '{}', but failed to remove the worktree", handle))?;
+    println!(
+        "✓ Removed worktree '{}' and branch '{}'",
+        handle, meta.branch
+    );
+    println!(
+        "  Restore it with: workmux restore {}",
+        restore_arg(&handle, output)
+    );
+    Ok(())
+}
+
+/// Recreate an archived worktree. With no archive given, list the ones in
+/// the repository's archive directory.
+pub fn restore(archive: Option<&str>, keep: bool, setup: SetupFlags) -> Result<()> {
+    if remote::get().is_some() {
+        return Err(anyhow!("restore is not supported with --host"));
+    }
+    let Some(archive) = archive else {
+        return list();
+    };
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+    let path = workflow::resolve_archive(archive)?;
+
+    let mut options = SetupOptions::new(!setup.no_hooks, !setup.no_file_ops, !setup.no_pane_cmds);
+    options.focus_window = !setup.background;
+    options.file_filter = FileFilter::new(&setup.skip_file, &setup.only_file)?;
+
+    if dry_run::skip(format_args!("restore {}", path.display())) {
+        return Ok(());
+    }
+    context.ensure_tmux_running()?;
+
+    let (meta, result) = workflow::restore(&path, &context, options)
+        .with_context(|| format!("Failed to restore '{}'", path.display()))?;
+
+    if result.post_create_hooks_run > 0 {
+        println!("✓ Setup complete");
+    }
+    println!(
+        "✓ Restored '{}' on branch '{}'\n  {} commit(s){}",
+        meta.handle,
+        meta.branch,
+        meta.commits,
+        if meta.uncommitted {
+            ", uncommitted changes"
+        } else {
+            ""
+        }
+    );
+    println!("  Worktree: {}", result.worktree_path.display());
+    if meta
+        .state
+        .as_ref()
+        .is_some_and(|state| state.prompt.is_some())
+    {
+        println!(
+            "  Start the agent on its prompt with: workmux reprompt {}",
+            meta.handle
+        );
+    }
+
+    // Archives outside the archive directory are the user's to keep
+    if !keep && path.parent() == Some(workflow::archive_dir()?.as_path()) {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove '{}'", path.display()))?;
+    }
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let archives = workflow::list_archives()?;
+    if archives.is_empty() {
+        println!("No archived worktrees");
+        return Ok(());
+    }
+    for path in archives {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let archived = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|age| format_timestamp(age.as_secs()))
+            .unwrap_or_default();
+        println!("{:<24} {}", name, archived);
+    }
+    Ok(())
+}
+
+/// What to pass `restore` to get back an archive written for `handle`
+fn restore_arg(handle: &str, output: Option<&Path>) -> String {
+    output.map_or(handle.to_string(), |path| {
+        let path: PathBuf = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        path.display().to_string()
+    })
+}
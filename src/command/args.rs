@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::git::WorktreeMatch;
+
 #[derive(clap::Args, Debug)]
 pub struct PromptArgs {
     /// Inline prompt text to store in the new worktree
@@ -30,6 +32,22 @@ pub struct SetupFlags {
     #[arg(short = 'F', long)]
     pub no_file_ops: bool,
 
+    /// Skip file operations whose pattern or path matches this glob (repeatable)
+    #[arg(
+        long = "skip-file",
+        value_name = "GLOB",
+        conflicts_with = "no_file_ops"
+    )]
+    pub skip_file: Vec<String>,
+
+    /// Only run file operations whose pattern or path matches this glob (repeatable)
+    #[arg(
+        long = "only-file",
+        value_name = "GLOB",
+        conflicts_with = "no_file_ops"
+    )]
+    pub only_file: Vec<String>,
+
     /// Skip executing pane commands (panes open with plain shells)
     #[arg(short = 'C', long)]
     pub no_pane_cmds: bool,
@@ -69,16 +87,37 @@ pub struct MultiArgs {
     )]
     pub branch_template: String,
 
+    /// Set a prompt template variable (repeatable). Variables the prompt still
+    /// leaves undefined are asked for interactively.
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = crate::template::parse_template_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Run an agent team from the `team` config: one pane per role, each given
+    /// the prompt rendered with its `{{ role }}`.
+    #[arg(long, conflicts_with_all = ["agent", "with_changes"])]
+    pub team: Option<String>,
+
     /// Maximum number of worktrees to run concurrently.
     /// When set, waits for a slot to open before creating new worktrees.
     #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
     pub max_concurrent: Option<u32>,
+
+    /// Create up to N worktrees at once. A failing worktree doesn't stop the
+    /// others; failures are listed at the end. Defaults to `add_parallel`.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with = "max_concurrent"
+    )]
+    pub parallel: Option<u32>,
 }
 
 #[derive(clap::Args, Debug)]
 pub struct RescueArgs {
-    /// Move uncommitted changes from the current worktree to the new worktree
-    #[arg(short = 'w', long, conflicts_with_all = ["count", "foreach"])]
+    /// Move uncommitted changes from the current worktree to the new worktree.
+    /// With several --agent or --count, each new worktree gets a copy.
+    #[arg(short = 'w', long, conflicts_with = "foreach")]
     pub with_changes: bool,
 
     /// Interactively select which changes to move (only applies with --with-changes)
@@ -89,3 +128,26 @@ pub struct RescueArgs {
     #[arg(short = 'u', long, requires = "with_changes")]
     pub include_untracked: bool,
 }
+
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub struct MatchArgs {
+    /// Only match names against branch names, never worktree directory names
+    #[arg(long, conflicts_with = "by_handle")]
+    pub by_branch: bool,
+
+    /// Only match names against worktree directory names (handles)
+    #[arg(long)]
+    pub by_handle: bool,
+}
+
+impl MatchArgs {
+    pub fn mode(&self) -> WorktreeMatch {
+        if self.by_branch {
+            WorktreeMatch::Branch
+        } else if self.by_handle {
+            WorktreeMatch::Handle
+        } else {
+            WorktreeMatch::Any
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::audit::{self, AuditAction};
 use crate::{config, git, tmux};
 use anyhow::{Context, Result, anyhow};
 
@@ -8,15 +9,16 @@ pub fn run(name: Option<&str>) -> Result<()> {
     // When no name is provided, prefer the current tmux window name
     // This handles duplicate windows (e.g., wm:feature-2) correctly
     let (full_window_name, is_current_window) = match name {
-        Some(handle) => {
-            // Explicit name provided - validate the worktree exists
-            git::find_worktree(handle).with_context(|| {
-                format!(
-                    "No worktree found with name '{}'. Use 'workmux list' to see available worktrees.",
-                    handle
-                )
-            })?;
-            let prefixed = tmux::prefixed(prefix, handle);
+        Some(name) => {
+            // Explicit name provided - validate the worktree exists. The window
+            // is named after its directory, which may differ from the name given
+            // (a branch name or partial handle)
+            let (worktree_path, _) = git::find_worktree(name)?;
+            let handle = worktree_path
+                .file_name()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?;
+            let prefixed = tmux::prefixed(prefix, &handle);
             let current_window = tmux::current_window_name()?;
             let is_current = current_window.as_deref() == Some(&prefixed);
             (prefixed, is_current)
@@ -59,6 +61,7 @@ pub fn run(name: Option<&str>) -> Result<()> {
         tmux::kill_window_by_full_name(&full_window_name).context("Failed to close tmux window")?;
         println!("✓ Closed window '{}' (worktree kept)", full_window_name);
     }
+    audit::record(AuditAction::WindowKilled, &full_window_name, None);
 
     Ok(())
 }
@@ -0,0 +1,399 @@
+use crate::cmd::Cmd;
+use crate::config::{self, Config, ConfigFile};
+use crate::{agent, dry_run, git, preset};
+use anyhow::{Context, Result, anyhow};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+pub fn show(origin: bool) -> Result<()> {
+    let config = Config::load(None)?;
+    let files = config::config_files()?;
+    print!("{}", render(&config, &files, origin)?);
+    Ok(())
+}
+
+/// `config` as YAML, leaving out unset settings. With `origin`, each
+/// top-level key is preceded by a comment naming the file that set it.
+fn render(config: &Config, files: &[ConfigFile], origin: bool) -> Result<String> {
+    let mut value = serde_yaml::to_value(config).context("Failed to serialize config")?;
+    prune(&mut value);
+    let Value::Mapping(settings) = value else {
+        return Ok(String::new());
+    };
+    if !origin {
+        return Ok(serde_yaml::to_string(&settings)?);
+    }
+
+    // Unparseable files were already reported by Config::load
+    let file_keys: Vec<Mapping> = files
+        .iter()
+        .map(|file| {
+            serde_yaml::from_str::<Value>(&file.contents)
+                .ok()
+                .and_then(|value| value.as_mapping().cloned())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut output = String::new();
+    for (key, value) in settings {
+        let defined_in: Vec<String> = files
+            .iter()
+            .zip(&file_keys)
+            .filter(|(_, keys)| keys.contains_key(&key))
+            .map(|(file, _)| file.path.display().to_string())
+            .collect();
+        let source = match defined_in.split_last() {
+            None => "default".to_string(),
+            Some((last, [])) => last.clone(),
+            // Lower files also set it: overridden or merged, depending on the key
+            Some((last, earlier)) => format!("{} (over {})", last, earlier.join(", ")),
+        };
+        output.push_str(&format!("# {}\n", source));
+        let mut entry = Mapping::new();
+        entry.insert(key, value);
+        output.push_str(&serde_yaml::to_string(&entry)?);
+    }
+    Ok(output)
+}
+
+/// The pane layouts `config init` offers
+#[derive(Debug, PartialEq)]
+enum Layout {
+    Agent,
+    AgentAndShell,
+    AgentAndCommand(String),
+}
+
+/// What `config init` asked for, with what it found installed
+#[derive(Debug)]
+struct InitAnswers {
+    main_branch: String,
+    agent: String,
+    layout: Layout,
+    copy: Vec<String>,
+    symlink: Vec<String>,
+    post_create: Vec<String>,
+    pre_merge: Vec<String>,
+    has_llm: bool,
+    has_gh: bool,
+}
+
+/// Ask about the basics and write them to a commented `.workmux.yaml` in the
+/// repository root. Answers default to what is found in the repository and on
+/// PATH, so pressing Enter throughout gives a working config.
+pub fn init() -> Result<()> {
+    let root = git::get_repo_root().context("`config init` needs a git repository")?;
+    let path = root.join(".workmux.yaml");
+    if path.exists() {
+        return Err(anyhow!(
+            "{} already exists. Remove it first if you want to regenerate it.",
+            path.display()
+        ));
+    }
+
+    println!("Answer each question, or press Enter for the value in brackets.");
+    println!("Type 'none' to leave a setting out.\n");
+
+    // The global config may name another remote
+    let remote = Config::load(None)
+        .map(|config| config.default_remote().to_string())
+        .unwrap_or_else(|_| "origin".to_string());
+    let main_branch = ask_or(
+        "Main branch",
+        &git::get_default_branch(&remote)
+            .or_else(|_| git::get_current_branch())
+            .unwrap_or_else(|_| "main".to_string()),
+    )?;
+
+    let installed = installed_agents(|name| which::which(name).is_ok());
+    let agent_question = if installed.is_empty() {
+        "Agent (none of the known agents is installed)".to_string()
+    } else {
+        format!("Agent (installed: {})", installed.join(", "))
+    };
+    let agent = ask_or(
+        &agent_question,
+        installed.first().copied().unwrap_or("claude"),
+    )?;
+
+    let preset = preset::resolve(None, &root).ok().flatten();
+    let dev = preset.and_then(|preset| preset.dev_command(&root));
+    let layout = loop {
+        let default = if dev.is_some() { "3" } else { "2" };
+        let answer = ask_or(
+            "Panes: 1) agent only, 2) agent and a shell, 3) agent and a command",
+            default,
+        )?;
+        match answer.as_str() {
+            "1" => break Layout::Agent,
+            "2" => break Layout::AgentAndShell,
+            "3" => {
+                let command = ask_or("Command for the second pane", dev.unwrap_or(""))?;
+                break if command.is_empty() {
+                    Layout::AgentAndShell
+                } else {
+                    Layout::AgentAndCommand(command)
+                };
+            }
+            _ => println!("Choose 1, 2 or 3."),
+        }
+    };
+
+    let copy = words(&ask_or(
+        "Files to copy into new worktrees (globs, space-separated)",
+        &untracked_env_files(&root).join(" "),
+    )?);
+    let symlink = words(&ask_or(
+        "Files or directories to symlink into new worktrees",
+        "",
+    )?);
+    let post_create = ask_or(
+        "Command to run in each new worktree",
+        preset.map_or("", |preset| preset.install),
+    )?;
+    let pre_merge = ask_or("Command that must pass before merging", "")?;
+
+    let answers = InitAnswers {
+        main_branch,
+        agent,
+        layout,
+        copy,
+        symlink,
+        post_create: Vec::from_iter((!post_create.is_empty()).then_some(post_create)),
+        pre_merge: Vec::from_iter((!pre_merge.is_empty()).then_some(pre_merge)),
+        has_llm: which::which("llm").is_ok(),
+        has_gh: which::which("gh").is_ok(),
+    };
+    if dry_run::skip(format_args!("write {}", path.display())) {
+        return Ok(());
+    }
+    fs::write(&path, render_init(&answers))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("\n✓ Wrote {}", path.display());
+    Ok(())
+}
+
+/// Ask `question`, taking `default` for an empty answer and nothing for `none`
+fn ask_or(question: &str, default: &str) -> Result<String> {
+    let prompt = if default.is_empty() {
+        format!("{}:", question)
+    } else {
+        format!("{} [{}]:", question, default)
+    };
+    let answer = super::ask(&prompt)?;
+    Ok(match answer.as_str() {
+        "" => default.to_string(),
+        "none" => String::new(),
+        _ => answer,
+    })
+}
+
+fn words(answer: &str) -> Vec<String> {
+    answer.split_whitespace().map(str::to_string).collect()
+}
+
+/// Agents with a built-in profile that are on PATH, Claude Code first
+fn installed_agents(is_installed: impl Fn(&str) -> bool) -> Vec<&'static str> {
+    let mut names: Vec<&str> = agent::PROFILES
+        .iter()
+        .map(|profile| profile.name)
+        .filter(|name| is_installed(name))
+        .collect();
+    names.sort_by_key(|name| *name != "claude");
+    names
+}
+
+/// `.env` files in `root` that git doesn't track, which new worktrees won't
+/// have unless they are copied
+fn untracked_env_files(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(".env"))
+        .filter(|name| {
+            !Cmd::new("git")
+                .args(&["ls-files", "--error-unmatch", name])
+                .workdir(root)
+                .run_as_check()
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// `value` as a YAML scalar, quoted when it needs to be
+fn scalar(value: &str) -> String {
+    serde_yaml::to_string(value)
+        .map(|yaml| yaml.trim_end().to_string())
+        .unwrap_or_else(|_| format!("{:?}", value))
+}
+
+fn list(out: &mut String, indent: &str, items: &[String]) {
+    for item in items {
+        out.push_str(&format!("{}  - {}\n", indent, scalar(item)));
+    }
+}
+
+/// The commented `.workmux.yaml` for `answers`
+fn render_init(answers: &InitAnswers) -> String {
+    let mut out = String::from(
+        "# workmux project configuration, written by `workmux config init`.\n\
+         # Personal settings go in ~/.config/workmux/config.yaml. See the\n\
+         # configuration guide (`workmux docs`) for every option.\n",
+    );
+
+    if !answers.main_branch.is_empty() {
+        out.push_str("\n# Branch that worktrees are merged into\n");
+        out.push_str(&format!("main_branch: {}\n", scalar(&answers.main_branch)));
+    }
+    if !answers.agent.is_empty() {
+        out.push_str("\n# Agent started in the first pane, as <agent>\n");
+        out.push_str(&format!("agent: {}\n", scalar(&answers.agent)));
+    }
+
+    out.push_str(
+        "\n# Panes of each worktree's tmux window\npanes:\n  - command: <agent>\n    focus: true\n",
+    );
+    match &answers.layout {
+        Layout::Agent => {}
+        Layout::AgentAndShell => out.push_str("  - split: horizontal # A shell\n"),
+        Layout::AgentAndCommand(command) => out.push_str(&format!(
+            "  - command: {}\n    split: horizontal\n",
+            scalar(command)
+        )),
+    }
+
+    if !answers.copy.is_empty() || !answers.symlink.is_empty() {
+        out.push_str("\n# Files that new worktrees get from the main worktree\nfiles:\n");
+        if !answers.copy.is_empty() {
+            out.push_str("  copy:\n");
+            list(&mut out, "  ", &answers.copy);
+        }
+        if !answers.symlink.is_empty() {
+            out.push_str("  symlink:\n");
+            list(&mut out, "  ", &answers.symlink);
+        }
+    }
+
+    if !answers.post_create.is_empty() {
+        out.push_str("\n# Run in each new worktree before its window opens\npost_create:\n");
+        list(&mut out, "", &answers.post_create);
+    }
+    if !answers.pre_merge.is_empty() {
+        out.push_str("\n# Run before `workmux merge`; a failure stops the merge\npre_merge:\n");
+        list(&mut out, "", &answers.pre_merge);
+    }
+
+    if !answers.has_llm {
+        out.push_str(
+            "\n# llm isn't installed, so branch descriptions are cut from the prompt\n\
+             # instead of summarized. Install it and remove this to get summaries.\n\
+             branch_description: truncate\n",
+        );
+    }
+    if !answers.has_gh {
+        out.push_str("\n# `add --pr` and `list --pr` need the gh CLI, which isn't installed.\n");
+    }
+    out
+}
+
+/// Drop nulls and the mappings left empty without them
+fn prune(value: &mut Value) {
+    match value {
+        Value::Mapping(map) => {
+            for value in map.values_mut() {
+                prune(value);
+            }
+            map.retain(|_, value| {
+                !value.is_null() && value.as_mapping().is_none_or(|map| !map.is_empty())
+            });
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(prune),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(path: &str, contents: &str) -> ConfigFile {
+        ConfigFile {
+            path: PathBuf::from(path),
+            contents: contents.to_string(),
+        }
+    }
+
+    #[test]
+    fn init_writes_a_config_that_parses() {
+        let answers = InitAnswers {
+            main_branch: "develop".to_string(),
+            agent: "aider".to_string(),
+            layout: Layout::AgentAndCommand("pnpm run dev".to_string()),
+            copy: vec![".env".to_string(), ".env.local".to_string()],
+            symlink: Vec::new(),
+            post_create: vec!["pnpm install".to_string()],
+            pre_merge: vec!["just check: all".to_string()],
+            has_llm: false,
+            has_gh: true,
+        };
+        let yaml = render_init(&answers);
+        let config: Config = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(config.main_branch.as_deref(), Some("develop"));
+        assert_eq!(config.agent.as_deref(), Some("aider"));
+        let panes = config.panes.unwrap();
+        assert_eq!(panes[0].command.as_deref(), Some("<agent>"));
+        assert_eq!(panes[1].command.as_deref(), Some("pnpm run dev"));
+        assert_eq!(config.files.copy.unwrap().len(), 2);
+        assert!(config.files.symlink.is_none());
+        assert_eq!(config.pre_merge.unwrap()[0].command(), "just check: all");
+        assert_eq!(
+            config.branch_description,
+            Some(config::BranchDescription::Truncate)
+        );
+        assert!(!yaml.contains("gh CLI"));
+    }
+
+    #[test]
+    fn installed_agents_put_claude_first() {
+        assert_eq!(
+            installed_agents(|name| ["aider", "claude", "codex"].contains(&name)),
+            ["claude", "aider", "codex"]
+        );
+        assert!(installed_agents(|_| false).is_empty());
+    }
+
+    #[test]
+    fn origin_names_the_files_that_set_each_key() {
+        let files = [
+            file(
+                "/home/me/.config/workmux/config.yaml",
+                "agent: codex\nmerge_strategy: rebase\n",
+            ),
+            file("/src/app/.workmux.yaml", "merge_strategy: squash\n"),
+        ];
+        let config = Config {
+            agent: Some("codex".to_string()),
+            merge_strategy: Some(config::MergeStrategy::Squash),
+            window_prefix: Some("wm-".to_string()),
+            ..Config::default()
+        };
+
+        let output = render(&config, &files, true).unwrap();
+        assert!(output.contains("# /home/me/.config/workmux/config.yaml\nagent: codex\n"));
+        assert!(output.contains("# default\nwindow_prefix: wm-\n"));
+        assert!(output.contains(
+            "# /src/app/.workmux.yaml (over /home/me/.config/workmux/config.yaml)\nmerge_strategy: squash\n"
+        ));
+        assert!(!output.contains("null"));
+    }
+}
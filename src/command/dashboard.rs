@@ -15,13 +15,16 @@ use ratatui::{
     text::{Line, Span, Text},
     widgets::{Block, Cell, Paragraph, Row, Table, TableState},
 };
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use crate::cmd::Cmd;
 use crate::config::Config;
+use crate::git::{self, CommitSummary, Head};
 use crate::tmux::{self, AgentPane};
+use crate::worktree_state;
 
 /// Available sort modes for the agent list
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -102,6 +105,18 @@ impl SortMode {
 /// Number of lines to capture from the agent's terminal for preview (scrollable history)
 const PREVIEW_LINES: u16 = 200;
 
+/// How long the branches' newest commits are shown before being read again,
+/// unless an agent's worktree switches to a branch they weren't read for
+const COMMITS_REFRESH: Duration = Duration::from_secs(30);
+
+/// The newest commit of each branch of a repository, read in one git call
+struct BranchCommits {
+    read_at: Instant,
+    /// What the agents' worktrees had checked out when they were read
+    heads: HashSet<Head>,
+    commits: HashMap<String, CommitSummary>,
+}
+
 /// App state for the TUI
 struct App {
     agents: Vec<AgentPane>,
@@ -123,6 +138,15 @@ struct App {
     preview_line_count: u16,
     /// Height of the preview area (updated during rendering)
     preview_height: u16,
+    /// Summary of what each waiting agent is asking, by pane, with the status
+    /// timestamp it was read for
+    waiting_reasons: HashMap<String, (Option<u64>, Option<String>)>,
+    /// Newest commit of each agent's worktree, by pane
+    last_commits: HashMap<String, CommitSummary>,
+    /// Branches' newest commits, by repository (its common git directory)
+    branch_commits: HashMap<PathBuf, BranchCommits>,
+    /// Commits of detached worktrees, which never change, by hash
+    detached_commits: HashMap<String, Option<CommitSummary>>,
 }
 
 impl App {
@@ -142,6 +166,10 @@ impl App {
             preview_scroll: None,
             preview_line_count: 0,
             preview_height: 0,
+            waiting_reasons: HashMap::new(),
+            last_commits: HashMap::new(),
+            branch_commits: HashMap::new(),
+            detached_commits: HashMap::new(),
         };
         app.refresh();
         // Select first item if available
@@ -156,6 +184,8 @@ impl App {
     fn refresh(&mut self) {
         self.agents = tmux::get_all_agent_panes().unwrap_or_default();
         self.sort_agents();
+        self.refresh_waiting_reasons();
+        self.refresh_last_commits();
 
         // Adjust selection if it's now out of bounds
         if let Some(selected) = self.table_state.selected()
@@ -172,6 +202,95 @@ impl App {
         self.update_preview();
     }
 
+    /// Read what newly waiting agents are asking from their worktree's state,
+    /// once per time they start waiting
+    fn refresh_waiting_reasons(&mut self) {
+        let waiting = self.config.status_icons.waiting();
+        let waiting_agents: Vec<&AgentPane> = self
+            .agents
+            .iter()
+            .filter(|agent| agent.status.as_deref() == Some(waiting))
+            .collect();
+        self.waiting_reasons
+            .retain(|pane_id, _| waiting_agents.iter().any(|a| &a.pane_id == pane_id));
+        for agent in waiting_agents {
+            if self
+                .waiting_reasons
+                .get(&agent.pane_id)
+                .is_some_and(|(ts, _)| *ts == agent.status_ts)
+            {
+                continue;
+            }
+            let summary = worktree_state::load_for_path(&agent.path)
+                .and_then(|state| state.waiting)
+                .map(worktree_state::WaitingReason::revealed)
+                .and_then(|reason| reason.summary().map(str::to_string));
+            self.waiting_reasons
+                .insert(agent.pane_id.clone(), (agent.status_ts, summary));
+        }
+    }
+
+    /// Look up the newest commit of each agent's worktree, reading each
+    /// repository's branches at most every [`COMMITS_REFRESH`]
+    fn refresh_last_commits(&mut self) {
+        let heads: Vec<(String, PathBuf, Head)> = self
+            .agents
+            .iter()
+            .filter_map(|agent| {
+                let (git_dir, head) = git::read_head(&agent.path)?;
+                Some((agent.pane_id.clone(), git_dir, head))
+            })
+            .collect();
+        self.branch_commits
+            .retain(|dir, _| heads.iter().any(|(_, git_dir, _)| git_dir == dir));
+
+        for (_, git_dir, head) in &heads {
+            match head {
+                Head::Branch(_) => {
+                    let fresh = self.branch_commits.get(git_dir).is_some_and(|read| {
+                        read.read_at.elapsed() < COMMITS_REFRESH && read.heads.contains(head)
+                    });
+                    if !fresh {
+                        let read_heads = heads
+                            .iter()
+                            .filter(|(_, dir, _)| dir == git_dir)
+                            .map(|(_, _, head)| head.clone())
+                            .collect();
+                        self.branch_commits.insert(
+                            git_dir.clone(),
+                            BranchCommits {
+                                read_at: Instant::now(),
+                                heads: read_heads,
+                                commits: git::last_commits_in(git_dir),
+                            },
+                        );
+                    }
+                }
+                Head::Detached(commit) => {
+                    self.detached_commits
+                        .entry(commit.clone())
+                        .or_insert_with(|| git::commit_summary(git_dir, commit));
+                }
+            }
+        }
+
+        self.last_commits = heads
+            .into_iter()
+            .filter_map(|(pane_id, git_dir, head)| {
+                let commit = match &head {
+                    Head::Branch(branch) => self
+                        .branch_commits
+                        .get(&git_dir)
+                        .and_then(|read| read.commits.get(branch)),
+                    Head::Detached(commit) => {
+                        self.detached_commits.get(commit).and_then(Option::as_ref)
+                    }
+                };
+                Some((pane_id, commit?.clone()))
+            })
+            .collect();
+    }
+
     /// Update the preview for the currently selected agent.
     /// Only fetches if the selection has changed or preview is stale.
     fn update_preview(&mut self) {
@@ -565,6 +684,7 @@ pub fn run() -> Result<()> {
                     KeyCode::Enter => app.jump_to_selected(),
                     KeyCode::Char('p') => app.peek_selected(),
                     KeyCode::Char('s') => app.cycle_sort_mode(),
+                    #[allow(clippy::collapsible_match)]
                     KeyCode::Char('i') => {
                         // Enter input mode if an agent is selected
                         if app.table_state.selected().is_some() && !app.agents.is_empty() {
@@ -673,9 +793,17 @@ fn ui(f: &mut Frame, app: &mut App) {
 }
 
 fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
-    let header_cells = ["#", "Project", "Agent", "Status", "Time", "Title"]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Cyan).bold()));
+    let header_cells = [
+        "#",
+        "Project",
+        "Agent",
+        "Status",
+        "Time",
+        "Last commit",
+        "Title",
+    ]
+    .iter()
+    .map(|h| Cell::from(*h).style(Style::default().fg(Color::Cyan).bold()));
     let header = Row::new(header_cells).height(1);
 
     // Group agents by (session, window_name) to detect multi-pane windows
@@ -692,6 +820,11 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
         .map(|(key, _)| key.clone())
         .collect();
 
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
     // Track position within each window group for pane numbering
     let mut window_positions: BTreeMap<(String, String), usize> = BTreeMap::new();
 
@@ -720,16 +853,36 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
 
             let project = App::extract_project_name(agent);
             let agent_name = format!("{}{}", app.extract_agent_name(agent), pane_suffix);
-            let title = agent
-                .pane_title
-                .as_ref()
-                .map(|t| t.strip_prefix("✳ ").unwrap_or(t).to_string())
+            // A waiting agent shows what it is asking instead of its title
+            let title = app
+                .waiting_reasons
+                .get(&agent.pane_id)
+                .and_then(|(_, summary)| summary.clone())
+                .or_else(|| {
+                    agent
+                        .pane_title
+                        .as_ref()
+                        .map(|t| t.strip_prefix("✳ ").unwrap_or(t).to_string())
+                })
                 .unwrap_or_default();
             let (status_text, status_color) = app.get_status_display(agent);
             let duration = app
                 .get_elapsed(agent)
                 .map(|d| app.format_duration(d))
                 .unwrap_or_else(|| "-".to_string());
+            // How long ago first, so a narrow column cuts the subject instead
+            let last_commit = app
+                .last_commits
+                .get(&agent.pane_id)
+                .map(|commit| {
+                    let age = now.saturating_sub(commit.timestamp);
+                    format!(
+                        "{} ago {}",
+                        super::remove::format_age(Duration::from_secs(age)),
+                        commit.subject
+                    )
+                })
+                .unwrap_or_else(|| "-".to_string());
 
             (
                 jump_key,
@@ -738,6 +891,7 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
                 status_text,
                 status_color,
                 duration,
+                last_commit,
                 title,
             )
         })
@@ -746,7 +900,7 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     // Calculate max project name width (with padding, capped)
     let max_project_width = row_data
         .iter()
-        .map(|(_, project, _, _, _, _, _)| project.len())
+        .map(|(_, project, _, _, _, _, _, _)| project.len())
         .max()
         .unwrap_or(5)
         .clamp(5, 20) // min 5, max 20
@@ -755,7 +909,7 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     // Calculate max agent name width (with padding, capped)
     let max_agent_width = row_data
         .iter()
-        .map(|(_, _, agent_name, _, _, _, _)| agent_name.len())
+        .map(|(_, _, agent_name, _, _, _, _, _)| agent_name.len())
         .max()
         .unwrap_or(5)
         .clamp(5, 24) // min 5, max 24
@@ -764,13 +918,23 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     let rows: Vec<Row> = row_data
         .into_iter()
         .map(
-            |(jump_key, project, agent_name, status_text, status_color, duration, title)| {
+            |(
+                jump_key,
+                project,
+                agent_name,
+                status_text,
+                status_color,
+                duration,
+                last_commit,
+                title,
+            )| {
                 Row::new(vec![
                     Cell::from(jump_key).style(Style::default().fg(Color::Yellow)),
                     Cell::from(project),
                     Cell::from(agent_name),
                     Cell::from(status_text).style(Style::default().fg(status_color)),
                     Cell::from(duration),
+                    Cell::from(last_commit),
                     Cell::from(title),
                 ])
             },
@@ -785,6 +949,7 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
             Constraint::Length(max_agent_width as u16),   // Agent: auto-sized
             Constraint::Length(8),                        // Status: fixed (icons)
             Constraint::Length(10),                       // Time: HH:MM:SS + padding
+            Constraint::Length(30),                       // Last commit: age and subject
             Constraint::Fill(1),                          // Title: takes remaining space
         ],
     )
@@ -0,0 +1,205 @@
+use crate::config::Config;
+use crate::git::{self, DiffSize, UntrackedIndex};
+use crate::workflow::WorkflowContext;
+use crate::worktree_state;
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+
+/// What `diff` shows of a worktree's changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffMode {
+    /// Commits and uncommitted changes counted apart, then the combined diffstat
+    Stat,
+    /// The combined diff itself
+    Patch,
+    /// The changed files
+    Files,
+}
+
+/// Show what a worktree changed since its branch left `base` (the branch's
+/// base by default): its commits and uncommitted changes together
+pub fn run(name: Option<&str>, base: Option<&str>, mode: DiffMode) -> Result<()> {
+    let config = Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+    let name = super::resolve_name(name)?;
+    let (worktree_path, branch) = git::find_worktree(&name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone()),
+    };
+    // The base's upstream, if it has one, is where the branch is merged
+    let base = git::get_merge_base(&base, context.config.default_remote())?;
+    let merge_base = git::get_merge_base_in_worktree(&worktree_path, &base)?;
+
+    // Untracked files count as new ones in every mode
+    let index = UntrackedIndex::new(&worktree_path)?;
+    match mode {
+        DiffMode::Stat => print_stat(&worktree_path, &index, &handle, &base, &merge_base),
+        DiffMode::Patch => show_patch(&index, &merge_base),
+        DiffMode::Files => {
+            for file in index.changed_files_since(&merge_base)? {
+                println!("{}", file);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_stat(
+    worktree_path: &Path,
+    index: &UntrackedIndex,
+    handle: &str,
+    base: &str,
+    merge_base: &str,
+) -> Result<()> {
+    print!("{}", stat(worktree_path, index, handle, base, merge_base)?);
+    Ok(())
+}
+
+/// The `--stat` summary: commits and uncommitted changes apart, then the
+/// diffstat of both
+fn stat(
+    worktree_path: &Path,
+    index: &UntrackedIndex,
+    handle: &str,
+    base: &str,
+    merge_base: &str,
+) -> Result<String> {
+    let commits = git::get_commits_from_base(worktree_path, merge_base)?;
+    let committed = git::committed_diff_size(worktree_path, merge_base)?;
+    let uncommitted = git::diff_size(worktree_path, "HEAD")?;
+    let untracked = git::list_untracked_files(worktree_path)?.len();
+
+    let mut out = format!(
+        "'{}' since it left {} ({})\n\n",
+        handle,
+        base,
+        &merge_base[..merge_base.len().min(7)]
+    );
+    out.push_str(&format!(
+        "  committed    {} commit{}, {}\n",
+        commits.len(),
+        if commits.len() == 1 { "" } else { "s" },
+        size(committed)
+    ));
+    let mut line = size(uncommitted);
+    if untracked > 0 {
+        line.push_str(&format!(
+            ", {} untracked file{}",
+            untracked,
+            if untracked == 1 { "" } else { "s" }
+        ));
+    }
+    out.push_str(&format!("  uncommitted  {}\n", line));
+
+    let stat = index.diff_stat_since(merge_base)?;
+    if !stat.is_empty() {
+        out.push_str(&format!("\n{}\n", stat));
+    }
+    Ok(out)
+}
+
+/// The diff on the terminal, so git pages and colors it as configured
+fn show_patch(index: &UntrackedIndex, merge_base: &str) -> Result<()> {
+    let status = index
+        .git(&["diff", merge_base])
+        .into_command(true)
+        .status()
+        .context("Failed to run git diff")?;
+    if !status.success() {
+        return Err(anyhow!("git diff exited with {}", status));
+    }
+    Ok(())
+}
+
+fn size(diff: DiffSize) -> String {
+    if diff.files == 0 {
+        return "no changes".to_string();
+    }
+    format!(
+        "{} file{} changed, +{} −{}",
+        diff.files,
+        if diff.files == 1 { "" } else { "s" },
+        diff.insertions,
+        diff.deletions
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn all_modes_cover_committed_modified_and_untracked_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "--quiet", "-b", "main"]);
+        fs::write(repo.join("README.md"), "one\ntwo\n").unwrap();
+        git(&["add", "README.md"]);
+        git(&["commit", "--quiet", "-m", "base"]);
+        let merge_base = git(&["rev-parse", "HEAD"]);
+
+        git(&["switch", "--quiet", "-c", "feature"]);
+        fs::write(repo.join("login.rs"), "fn login() {}\n").unwrap();
+        git(&["add", "login.rs"]);
+        git(&["commit", "--quiet", "-m", "Add login"]);
+        fs::write(repo.join("README.md"), "one\n").unwrap();
+        fs::write(repo.join("notes.txt"), "todo\n").unwrap();
+
+        let index = UntrackedIndex::new(repo).unwrap();
+        let summary = stat(repo, &index, "login", "main", &merge_base).unwrap();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                format!("'login' since it left main ({})", &merge_base[..7]).as_str(),
+                "",
+                "  committed    1 commit, 1 file changed, +1 −0",
+                "  uncommitted  1 file changed, +0 −1, 1 untracked file",
+            ]
+        );
+        // Every mode spans the commits, the modified file and the untracked one
+        let files = ["README.md", "login.rs", "notes.txt"];
+        let diffstat = lines[5..].join("\n");
+        for file in files {
+            assert!(diffstat.contains(file), "{}", diffstat);
+        }
+        assert!(diffstat.contains("3 files changed"), "{}", diffstat);
+        assert_eq!(index.changed_files_since(&merge_base).unwrap(), files);
+        let patch = index.git(&["diff", &merge_base]).run().unwrap();
+        let patch = String::from_utf8_lossy(&patch.stdout);
+        for file in files {
+            assert!(patch.contains(&format!("+++ b/{}", file)), "{}", patch);
+        }
+
+        // The worktree's own index is left alone, and the temporary one removed
+        assert_eq!(git(&["status", "--porcelain"]), "M README.md\n?? notes.txt");
+        drop(index);
+        assert!(!fs::read_dir(repo.join(".git")).unwrap().any(|entry| {
+            entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("workmux-index")
+        }));
+    }
+}
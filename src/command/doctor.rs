@@ -0,0 +1,409 @@
+use crate::cmd::Cmd;
+use crate::config::{self, Config};
+use crate::{agent, git, remote, state_encryption, tmux};
+use anyhow::{Result, anyhow};
+use console::style;
+use std::path::Path;
+
+use super::version::tool_version;
+
+/// Oldest git with everything workmux runs (`git switch`, cone-mode sparse
+/// checkout, `git config --worktree`)
+const MIN_GIT: (u32, u32) = (2, 25);
+/// Oldest git that can predict merge conflicts with `git merge-tree`
+const CONFLICT_PREVIEW_GIT: (u32, u32) = (2, 38);
+/// Oldest tmux with `display-menu`
+const MIN_TMUX: (u32, u32) = (3, 0);
+/// Oldest tmux with `display-popup`, which `workmux menu` opens
+const POPUP_TMUX: (u32, u32) = (3, 2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Ok,
+    Warning,
+    Problem,
+}
+
+/// The outcome of one check, with what to do about it unless it passed
+struct Check {
+    level: Level,
+    summary: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(summary: impl Into<String>) -> Self {
+        Self {
+            level: Level::Ok,
+            summary: summary.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(summary: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warning,
+            summary: summary.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn problem(summary: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            level: Level::Problem,
+            summary: summary.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+pub fn run() -> Result<()> {
+    let mut checks = vec![check_git(), check_git_identity()];
+    checks.extend(check_tmux());
+
+    let loaded = Config::load(None);
+    checks.push(match &loaded {
+        Ok(_) => Check::ok(format!("config ({})", config_files())),
+        Err(e) => Check::problem(
+            format!("config does not load: {:#}", e),
+            "Fix the file named above; 'workmux config show' prints the result once it loads",
+        ),
+    });
+    let config = loaded.unwrap_or_default();
+    checks.extend(check_status_format(&config));
+    checks.extend(check_agents(&config));
+    checks.extend(check_optional_tools());
+    checks.extend(check_state_encryption(&config));
+    checks.extend(check_hook_scripts(&config));
+
+    for check in &checks {
+        let mark = match check.level {
+            Level::Ok => style("✓").green(),
+            Level::Warning => style("!").yellow(),
+            Level::Problem => style("✗").red(),
+        };
+        println!("{} {}", mark, check.summary);
+        if let Some(fix) = &check.fix {
+            println!("    {}", style(fix).dim());
+        }
+    }
+
+    let count = |level| checks.iter().filter(|check| check.level == level).count();
+    let (problems, warnings) = (count(Level::Problem), count(Level::Warning));
+    println!();
+    if problems > 0 {
+        return Err(anyhow!(
+            "{} problem(s) and {} warning(s) found",
+            problems,
+            warnings
+        ));
+    }
+    if warnings > 0 {
+        println!("No problems, {} warning(s)", warnings);
+    } else {
+        println!("✓ Your environment is ready for workmux");
+    }
+    Ok(())
+}
+
+fn check_git() -> Check {
+    let Some(line) = tool_version("git", &["--version"], true) else {
+        return Check::problem("git not found", "Install git 2.25 or later");
+    };
+    let version = line.trim_start_matches("git version ");
+    match parse_version(version) {
+        Some(found) if found < MIN_GIT => Check::problem(
+            format!("git {} is too old", version),
+            format!("Upgrade to git {}.{} or later", MIN_GIT.0, MIN_GIT.1),
+        ),
+        Some(found) if found < CONFLICT_PREVIEW_GIT => Check::warning(
+            format!(
+                "git {}: merge previews can't predict conflicts before git {}.{}",
+                version, CONFLICT_PREVIEW_GIT.0, CONFLICT_PREVIEW_GIT.1
+            ),
+            "Upgrade git to see expected conflicts before merging",
+        ),
+        _ => Check::ok(format!("git {}", version)),
+    }
+}
+
+/// Merge and squash commits need a committer identity
+fn check_git_identity() -> Check {
+    let get = |key| {
+        Cmd::new("git")
+            .args(&["config", key])
+            .run_and_capture_stdout()
+            .ok()
+            .filter(|value| !value.is_empty())
+    };
+    match (get("user.name"), get("user.email")) {
+        (Some(name), Some(email)) => Check::ok(format!("git identity {} <{}>", name, email)),
+        _ => Check::warning(
+            "git has no user.name or user.email, so merge and squash commits fail",
+            "git config --global user.name 'Your Name' && git config --global user.email you@example.com",
+        ),
+    }
+}
+
+fn check_tmux() -> Vec<Check> {
+    let Some(line) = tool_version("tmux", &["-V"], true) else {
+        return vec![Check::problem(
+            "tmux not found",
+            "Install tmux 3.2 or later",
+        )];
+    };
+    let version = line.trim_start_matches("tmux ");
+    let mut checks = vec![match parse_version(version) {
+        Some(found) if found < MIN_TMUX => Check::problem(
+            format!("tmux {} is too old", version),
+            format!("Upgrade to tmux {}.{} or later", POPUP_TMUX.0, POPUP_TMUX.1),
+        ),
+        Some(found) if found < POPUP_TMUX => Check::warning(
+            format!(
+                "tmux {}: 'workmux menu' needs popups, from tmux {}.{}",
+                version, POPUP_TMUX.0, POPUP_TMUX.1
+            ),
+            format!("Upgrade to tmux {}.{} or later", POPUP_TMUX.0, POPUP_TMUX.1),
+        ),
+        _ => Check::ok(format!("tmux {}", version)),
+    }];
+
+    if !tmux::is_running().unwrap_or(false) {
+        checks.push(Check::warning(
+            "no tmux server is running",
+            "Start tmux (e.g. 'tmux new -s work') and run workmux inside it",
+        ));
+    } else if std::env::var_os("TMUX").is_none() && remote::get().is_none() {
+        checks.push(Check::warning(
+            "not running inside tmux",
+            "Windows open in the running server; attach to it to see them",
+        ));
+    }
+    checks
+}
+
+/// With `status_format: false`, agent status only shows if the user's own
+/// window format has it
+fn check_status_format(config: &Config) -> Option<Check> {
+    if config.status_format.unwrap_or(true) || !tmux::is_running().unwrap_or(false) {
+        return None;
+    }
+    let format = Cmd::new("tmux")
+        .args(&["show-option", "-gv", "window-status-format"])
+        .run_and_capture_stdout()
+        .unwrap_or_default();
+    if format.contains("@workmux_status") {
+        return Some(Check::ok("tmux window-status-format shows agent status"));
+    }
+    Some(Check::warning(
+        "status_format is off and window-status-format doesn't show agent status",
+        "Add #{?@workmux_status, #{@workmux_status},} to window-status-format in tmux.conf",
+    ))
+}
+
+/// Every agent the config runs has a binary
+fn check_agents(config: &Config) -> Vec<Check> {
+    let mut checks: Vec<Check> = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for usage in agent::usages(config) {
+        let Some((executable, _)) = config::split_first_token(&usage.command) else {
+            continue;
+        };
+        if seen.iter().any(|name| name == executable) {
+            continue;
+        }
+        seen.push(executable.to_string());
+        let found = if remote::get().is_some() {
+            Cmd::new("sh")
+                .args(&[
+                    "-c",
+                    &format!("command -v {}", crate::cmd::shell_quote(executable)),
+                ])
+                .run_and_capture_stdout()
+                .ok()
+        } else {
+            config::resolve_executable_path(executable).filter(|path| Path::new(path).exists())
+        };
+        checks.push(match found {
+            Some(path) => Check::ok(format!("agent {} ({})", executable, path)),
+            None => Check::problem(
+                format!("agent {} not found ({})", executable, usage.used_by),
+                format!(
+                    "Install {}, or point 'agent' or the agents config at one you have",
+                    executable
+                ),
+            ),
+        });
+    }
+    checks
+}
+
+/// `gh` and `llm` run on this machine, and only some commands need them
+fn check_optional_tools() -> Vec<Check> {
+    let llm = match tool_version("llm", &["--version"], false) {
+        Some(_) => Check::ok("llm"),
+        None => Check::warning(
+            "llm not found: 'add -A', branch descriptions from prompts and 'summarize' need it",
+            "Install it (e.g. 'pipx install llm') and set a model with 'llm models default'",
+        ),
+    };
+    let gh = match tool_version("gh", &["--version"], false) {
+        Some(_) => Check::ok("gh"),
+        None => Check::warning(
+            "gh not found: 'add --pr' and PR status in 'list --pr' need it",
+            "Install the GitHub CLI and run 'gh auth login'",
+        ),
+    };
+    vec![llm, gh]
+}
+
+/// With state encryption configured, `age` writes the state and an unlocked
+/// identity reads it
+fn check_state_encryption(config: &Config) -> Option<Check> {
+    config
+        .state_encryption
+        .as_ref()
+        .filter(|settings| !settings.recipients().is_empty())?;
+    Some(if tool_version("age", &["--version"], false).is_none() {
+        Check::problem(
+            "age not found, and state_encryption needs it to record worktree state",
+            "Install age (https://age-encryption.org)",
+        )
+    } else if !state_encryption::is_unlocked() {
+        Check::warning(
+            "worktree state is encrypted and locked, so prompts and agent output are hidden",
+            "workmux state unlock",
+        )
+    } else {
+        Check::ok("worktree state encryption (unlocked)")
+    })
+}
+
+/// Hooks that run a script by path need it to exist and be executable
+fn check_hook_scripts(config: &Config) -> Vec<Check> {
+    // The scripts are on the --host machine
+    if remote::get().is_some() {
+        return Vec::new();
+    }
+    let Ok(root) = git::get_main_worktree_root() else {
+        return Vec::new();
+    };
+    let hooks = [
+        ("pre_create", &config.pre_create),
+        ("post_create", &config.post_create),
+        ("pre_open", &config.pre_open),
+        ("pre_merge", &config.pre_merge),
+        ("post_merge", &config.post_merge),
+        ("pre_remove", &config.pre_remove),
+        ("post_remove", &config.post_remove),
+    ];
+    let commands = hooks
+        .iter()
+        .flat_map(|(name, hooks)| {
+            hooks
+                .iter()
+                .flatten()
+                .map(move |hook| (*name, hook.command()))
+        })
+        .chain(
+            [
+                ("on_enter", &config.on_enter),
+                ("on_leave", &config.on_leave),
+            ]
+            .into_iter()
+            .flat_map(|(name, commands)| {
+                commands
+                    .iter()
+                    .flatten()
+                    .map(move |command| (name, command.as_str()))
+            }),
+        );
+
+    let mut checks = Vec::new();
+    for (hook, command) in commands {
+        let Some((script, _)) = config::split_first_token(command) else {
+            continue;
+        };
+        if !script.contains('/') || script.starts_with('$') || script.contains("{{") {
+            continue;
+        }
+        let path = if let Some(rest) = script.strip_prefix("~/") {
+            match home::home_dir() {
+                Some(home) => home.join(rest),
+                None => continue,
+            }
+        } else {
+            root.join(script)
+        };
+        if !path.exists() {
+            checks.push(Check::problem(
+                format!("{} hook script {} does not exist", hook, script),
+                format!("Create it, or fix the {} command in the config", hook),
+            ));
+        } else if !is_executable(&path) {
+            checks.push(Check::problem(
+                format!("{} hook script {} is not executable", hook, script),
+                format!("chmod +x {}", script),
+            ));
+        } else {
+            checks.push(Check::ok(format!("{} hook script {}", hook, script)));
+        }
+    }
+    checks
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The config files in effect, for the summary line
+fn config_files() -> String {
+    let files: Vec<String> = [config::global_config_path(), config::project_config_path()]
+        .into_iter()
+        .flatten()
+        .map(|path| path.display().to_string())
+        .collect();
+    if files.is_empty() {
+        "defaults, no config file".to_string()
+    } else {
+        files.join(", ")
+    }
+}
+
+/// Major and minor version from strings like "2.43.0", "3.3a" or "next-3.4"
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = version[start..].split('.').map(|part| {
+        part.chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>()
+            .parse::<u32>()
+    });
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().and_then(|part| part.ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_handles_git_and_tmux_styles() {
+        assert_eq!(parse_version("2.43.0"), Some((2, 43)));
+        assert_eq!(parse_version("2.39.3 (Apple Git-146)"), Some((2, 39)));
+        assert_eq!(parse_version("3.3a"), Some((3, 3)));
+        assert_eq!(parse_version("next-3.4"), Some((3, 4)));
+        assert_eq!(parse_version("3"), Some((3, 0)));
+        assert_eq!(parse_version("master"), None);
+        assert!(parse_version("3.1c").unwrap() < POPUP_TMUX);
+    }
+}
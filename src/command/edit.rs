@@ -0,0 +1,85 @@
+use crate::cmd::{Cmd, shell_quote};
+use crate::config::SplitDirection;
+use crate::{git, tmux};
+use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// `path` in the worktree at `root`. Paths are taken relative to the
+/// worktree's root, whatever directory workmux runs in.
+fn resolve(root: &Path, path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Err(anyhow!(
+            "'{}' is absolute; give the path relative to the worktree's root",
+            path.display()
+        ));
+    }
+    Ok(root.join(path))
+}
+
+pub fn run(name: &str, path: &Path, pane: bool) -> Result<()> {
+    let (worktree_path, _branch) = git::find_worktree(name)?;
+    let file = resolve(&worktree_path, path)?;
+    let file_str = file.to_string_lossy();
+    if !Cmd::new("test").args(&["-e", &file_str]).run_as_check()? {
+        return Err(anyhow!(
+            "'{}' doesn't exist in worktree '{}'",
+            path.display(),
+            name
+        ));
+    }
+
+    // Resolved here, as git does ($GIT_EDITOR, core.editor, $VISUAL, $EDITOR),
+    // since a new pane doesn't see this shell's environment. The editor is a
+    // shell command, run the way git runs it.
+    let editor = Cmd::new("git")
+        .args(&["var", "GIT_EDITOR"])
+        .workdir(&worktree_path)
+        .run_and_capture_stdout()
+        .context("Failed to find an editor")?;
+    let script = format!("{} \"$@\"", editor);
+
+    if pane {
+        let current =
+            tmux::current_pane_id().ok_or_else(|| anyhow!("--pane only works inside tmux"))?;
+        let command = format!(
+            "sh -c {} sh {}",
+            shell_quote(&script),
+            shell_quote(&file_str)
+        );
+        tmux::split_pane_with_command(
+            &current,
+            &SplitDirection::Horizontal,
+            &worktree_path,
+            None,
+            None,
+            Some(&command),
+        )?;
+        return Ok(());
+    }
+
+    let status = Cmd::new("sh")
+        .args(&["-c", &script, "sh", &file_str])
+        .workdir(&worktree_path)
+        .into_command(true)
+        .status()
+        .context("Failed to start the editor")?;
+    if !status.success() {
+        return Err(anyhow!("The editor exited with {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_resolved_against_the_worktree_root() {
+        let root = Path::new("/src/app__worktrees/login");
+        assert_eq!(
+            resolve(root, Path::new("src/lib.rs")).unwrap(),
+            Path::new("/src/app__worktrees/login/src/lib.rs")
+        );
+        assert!(resolve(root, Path::new("/etc/hosts")).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::cmd::{self, shell_quote, terminate_group};
+use crate::workflow::HookEnv;
+use crate::{dry_run, git, interrupt, remote, worktree_state};
+use anyhow::{Context, Result, anyhow};
+use console::style;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What running the command did in one worktree
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Exited {
+        code: i32,
+        elapsed: Duration,
+    },
+    /// Killed by a signal, or stopped with Ctrl-C
+    Killed,
+    Skipped(String),
+    Failed(String),
+}
+
+impl Outcome {
+    fn line(&self, handle: &str) -> String {
+        match self {
+            Self::Exited { code: 0, elapsed } => {
+                format!("✓ {}: exit 0 ({})", handle, format_elapsed(*elapsed))
+            }
+            Self::Exited { code, elapsed } => {
+                format!("✗ {}: exit {} ({})", handle, code, format_elapsed(*elapsed))
+            }
+            Self::Killed => format!("✗ {}: killed", handle),
+            Self::Skipped(reason) => format!("- {}: skipped, {}", handle, reason),
+            Self::Failed(error) => format!("✗ {}: {}", handle, error),
+        }
+    }
+
+    fn is_failure(&self) -> bool {
+        !matches!(self, Self::Exited { code: 0, .. } | Self::Skipped(_))
+    }
+}
+
+/// Seconds with one decimal below a minute, else minutes and seconds
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// The shell script to run: a single argument as it is, so it can use pipes
+/// and `&&`, several as one quoted command line
+fn script(command: &[String]) -> String {
+    match command {
+        [script] => script.clone(),
+        words => words
+            .iter()
+            .map(|word| shell_quote(word))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+pub fn run(command: &[String], handles: &[String], parallel: usize) -> Result<()> {
+    let main_worktree_root = git::get_main_worktree_root()?;
+    let named = !handles.is_empty();
+    let targets: Vec<(PathBuf, String)> = if named {
+        handles
+            .iter()
+            .map(|name| git::find_worktree(name))
+            .collect::<Result<_>>()?
+    } else {
+        git::list_worktrees()?
+            .into_iter()
+            .filter(|(path, _)| *path != main_worktree_root)
+            .collect()
+    };
+    if targets.is_empty() {
+        println!("No worktrees to run in.");
+        return Ok(());
+    }
+    let targets: Vec<(String, PathBuf, String)> = targets
+        .into_iter()
+        .map(|(path, branch)| {
+            let handle = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| branch.clone());
+            (handle, path, branch)
+        })
+        .collect();
+
+    let script = script(command);
+    let width = targets
+        .iter()
+        .map(|(handle, _, _)| handle.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let guard = interrupt::Guard::new()?;
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Option<Outcome>>> = Mutex::new(targets.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..parallel.min(targets.len()) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((handle, path, branch)) = targets.get(i) else {
+                        break;
+                    };
+                    let outcome = if interrupt::is_interrupted() {
+                        Outcome::Skipped("interrupted".to_string())
+                    } else if !named && worktree_state::is_pinned(handle) {
+                        Outcome::Skipped("pinned".to_string())
+                    } else {
+                        let env = HookEnv::new(handle, branch, path, &main_worktree_root);
+                        let prefix = format!("{:<width$} │", handle, width = width);
+                        run_in(path, &script, &env.vars(), &prefix)
+                            .unwrap_or_else(|e| Outcome::Failed(format!("{:#}", e)))
+                    };
+                    outcomes.lock().unwrap()[i] = Some(outcome);
+                }
+            });
+        }
+    });
+    drop(guard);
+
+    let outcomes = outcomes.into_inner().unwrap();
+    let mut failures = 0;
+    println!();
+    for ((handle, _, _), outcome) in targets.iter().zip(outcomes) {
+        let outcome = outcome.unwrap_or(Outcome::Skipped("not started".to_string()));
+        if outcome.is_failure() {
+            failures += 1;
+        }
+        println!("{}", outcome.line(handle));
+    }
+
+    if interrupt::is_interrupted() {
+        return Err(interrupt::Interrupted.into());
+    }
+    if failures > 0 {
+        return Err(anyhow!(
+            "The command failed in {} of {} worktree(s)",
+            failures,
+            targets.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Run `script` in the worktree at `path`, printing each line it writes to
+/// stdout or stderr there, after `prefix`
+fn run_in(path: &Path, script: &str, vars: &[(&str, &str)], prefix: &str) -> Result<Outcome> {
+    if dry_run::skip(cmd::display("sh", &["-c", script], Some(path))) {
+        return Ok(Outcome::Skipped("dry run".to_string()));
+    }
+    let mut cmd = match remote::get() {
+        Some(remote) => remote.command("sh", &["-c", script], Some(path), vars, false),
+        None => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(script).current_dir(path);
+            for (key, value) in vars {
+                cmd.env(key, value);
+            }
+            cmd
+        }
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0);
+
+    let started = Instant::now();
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", script))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let prefix = style(prefix).magenta().to_string();
+
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| stream(stdout, |line| println!("{} {}", prefix, line)));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream(stderr, |line| eprintln!("{} {}", prefix, line)));
+        }
+        let status = loop {
+            if let Some(status) = child.try_wait().context("Failed to wait for the command")? {
+                break status;
+            }
+            if interrupt::is_interrupted() {
+                terminate_group(&mut child);
+                return Ok(Outcome::Killed);
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+        Ok(match status.code() {
+            Some(code) => Outcome::Exited {
+                code,
+                elapsed: started.elapsed(),
+            },
+            None => Outcome::Killed,
+        })
+    })
+}
+
+/// Call `print` with each line read from `reader`, invalid UTF-8 replaced
+fn stream(reader: impl Read, print: impl Fn(&str)) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    while let Ok(read) = reader.read_until(b'\n', &mut line) {
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        print(text.trim_end_matches(['\n', '\r']));
+        line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_argument_is_a_script_and_several_are_quoted() {
+        assert_eq!(
+            script(&["cargo test && cargo clippy".to_string()]),
+            "cargo test && cargo clippy"
+        );
+        assert_eq!(
+            script(&[
+                "git".to_string(),
+                "log".to_string(),
+                "-1 --oneline".to_string()
+            ]),
+            "git log '-1 --oneline'"
+        );
+    }
+
+    #[test]
+    fn summary_lines_show_exit_codes() {
+        let exited = |code| Outcome::Exited {
+            code,
+            elapsed: Duration::from_millis(3200),
+        };
+        assert_eq!(exited(0).line("api"), "✓ api: exit 0 (3.2s)");
+        assert_eq!(exited(2).line("api"), "✗ api: exit 2 (3.2s)");
+        assert!(exited(2).is_failure());
+        assert!(!Outcome::Skipped("pinned".to_string()).is_failure());
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+    }
+}
@@ -0,0 +1,82 @@
+use crate::workflow::WorkflowContext;
+use crate::{config, dry_run, git, worktree_state};
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use std::path::Path;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// A `git format-patch` series, one file per commit
+    Patch,
+    /// A single `git bundle` file that can be fetched from
+    Bundle,
+}
+
+pub fn run(name: &str, format: ExportFormat, out: Option<&Path>, base: Option<&str>) -> Result<()> {
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone()),
+    };
+
+    let commits = git::count_commits_since(&base, &branch)?;
+    if commits == 0 {
+        return Err(anyhow!(
+            "Branch '{}' has no commits on top of '{}' to export",
+            branch,
+            base
+        ));
+    }
+
+    if git::has_uncommitted_changes(&worktree_path)? {
+        eprintln!(
+            "Warning: '{}' has uncommitted changes, which are not included in the export.",
+            handle
+        );
+    }
+
+    // git runs in the worktree, so relative paths are resolved here
+    let out_dir = std::path::absolute(out.unwrap_or_else(|| Path::new(".")))
+        .context("Failed to resolve the output directory")?;
+    let out_dir = out_dir.as_path();
+    if !dry_run::skip(format_args!("mkdir -p {}", out_dir.display())) {
+        std::fs::create_dir_all(out_dir).with_context(|| {
+            format!("Failed to create output directory '{}'", out_dir.display())
+        })?;
+    }
+
+    match format {
+        ExportFormat::Patch => {
+            let patches = git::format_patch(&worktree_path, &base, &branch, out_dir)?;
+            println!(
+                "✓ Exported {} commit(s) from '{}' (since '{}') as patches:",
+                commits, branch, base
+            );
+            for patch in &patches {
+                println!("  {}", patch.display());
+            }
+        }
+        ExportFormat::Bundle => {
+            let bundle_path = out_dir.join(format!("{}.bundle", handle));
+            git::create_bundle(&worktree_path, &base, &branch, &bundle_path)?;
+            println!(
+                "✓ Exported {} commit(s) from '{}' (since '{}') as a bundle:\n  {}",
+                commits,
+                branch,
+                base,
+                bundle_path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use crate::config::Config;
+use crate::retention;
+use anyhow::Result;
+
+/// Enforce `retention` now, rather than waiting for the next daily check
+pub fn run() -> Result<()> {
+    let config = Config::load(None)?;
+    let collected = retention::enforce(&config)?;
+
+    if collected.log_entries > 0 {
+        println!("✓ Removed {} old log entries", collected.log_entries);
+    }
+    for dir in &collected.trash_dirs {
+        println!("✓ Removed trash {}", dir.display());
+    }
+    if collected.checkpoint {
+        println!("✓ Removed saved progress of an interrupted command");
+    }
+    if collected.log_entries == 0 && collected.trash_dirs.is_empty() && !collected.checkpoint {
+        println!("Nothing to remove");
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use crate::cmd::Cmd;
+use crate::{git, remote};
+use anyhow::{Result, anyhow};
+use console::style;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// How to match the pattern, as both ripgrep and git grep take it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    pub fixed_strings: bool,
+    pub word_regexp: bool,
+    pub files_with_matches: bool,
+}
+
+/// Searches files with ripgrep when it is installed, else with git grep
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tool {
+    Ripgrep,
+    GitGrep,
+}
+
+impl Tool {
+    fn detect() -> Self {
+        // On a --host the local ripgrep says nothing about the remote one
+        if remote::get().is_none() && which::which("rg").is_ok() {
+            Self::Ripgrep
+        } else {
+            Self::GitGrep
+        }
+    }
+
+    fn args(self, pattern: &str, options: GrepOptions) -> Vec<&str> {
+        let mut args = match self {
+            Self::Ripgrep => vec!["--line-number", "--no-heading", "--color", "never"],
+            // Untracked files are searched too, since they may be an agent's new work
+            Self::GitGrep => vec!["grep", "--line-number", "-I", "--untracked"],
+        };
+        for (enabled, flag) in [
+            (options.ignore_case, "--ignore-case"),
+            (options.fixed_strings, "--fixed-strings"),
+            (options.word_regexp, "--word-regexp"),
+            (options.files_with_matches, "--files-with-matches"),
+        ] {
+            if enabled {
+                args.push(flag);
+            }
+        }
+        args.extend(["-e", pattern]);
+        args
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Ripgrep => "rg",
+            Self::GitGrep => "git",
+        }
+    }
+}
+
+/// What searching one worktree found
+enum Search {
+    Matches(String),
+    NoMatches,
+    Failed(String),
+}
+
+fn search(tool: Tool, path: &Path, pattern: &str, options: GrepOptions) -> Search {
+    let args = tool.args(pattern, options);
+    let output = Cmd::new(tool.program())
+        .args(&args)
+        .workdir(path)
+        .into_command(false)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            Search::Matches(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        // Both exit with 1 when nothing matched
+        Ok(output) if output.status.code() == Some(1) && output.stderr.is_empty() => {
+            Search::NoMatches
+        }
+        Ok(output) => Search::Failed(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Search::Failed(e.to_string()),
+    }
+}
+
+/// `output` with each line prefixed by the worktree it was found in
+fn prefixed(handle: &str, output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| format!("{}:{}", style(handle).magenta(), line))
+        .collect()
+}
+
+pub fn run(pattern: &str, handles: &[String], options: GrepOptions) -> Result<()> {
+    let main_worktree_root = git::get_main_worktree_root()?;
+    let targets: Vec<(PathBuf, String)> = if handles.is_empty() {
+        git::list_worktrees()?
+            .into_iter()
+            .filter(|(path, _)| *path != main_worktree_root)
+            .collect()
+    } else {
+        handles
+            .iter()
+            .map(|name| git::find_worktree(name))
+            .collect::<Result<_>>()?
+    };
+    if targets.is_empty() {
+        println!("No worktrees to search.");
+        return Ok(());
+    }
+
+    let tool = Tool::detect();
+    let results: Vec<Search> = thread::scope(|scope| {
+        let searches: Vec<_> = targets
+            .iter()
+            .map(|(path, _)| scope.spawn(move || search(tool, path, pattern, options)))
+            .collect();
+        searches
+            .into_iter()
+            .map(|search| {
+                search
+                    .join()
+                    .unwrap_or_else(|_| Search::Failed("search panicked".to_string()))
+            })
+            .collect()
+    });
+
+    let mut matched = false;
+    let mut failures = 0;
+    for ((path, branch), result) in targets.iter().zip(results) {
+        let handle = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| branch.clone());
+        match result {
+            Search::Matches(output) => {
+                matched = true;
+                for line in prefixed(&handle, &output) {
+                    println!("{}", line);
+                }
+            }
+            Search::NoMatches => {}
+            Search::Failed(error) => {
+                failures += 1;
+                eprintln!("✗ {}: {}", handle, error);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} worktree(s) could not be searched", failures));
+    }
+    if !matched {
+        return Err(anyhow!("No matches for '{}'", pattern));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_tools_get_the_same_matching_flags() {
+        let options = GrepOptions {
+            ignore_case: true,
+            files_with_matches: true,
+            ..GrepOptions::default()
+        };
+        assert_eq!(
+            Tool::Ripgrep.args("TODO", options),
+            [
+                "--line-number",
+                "--no-heading",
+                "--color",
+                "never",
+                "--ignore-case",
+                "--files-with-matches",
+                "-e",
+                "TODO"
+            ]
+        );
+        assert_eq!(
+            Tool::GitGrep.args("-x", GrepOptions::default()),
+            ["grep", "--line-number", "-I", "--untracked", "-e", "-x"]
+        );
+    }
+
+    #[test]
+    fn lines_are_prefixed_with_the_handle() {
+        let lines: Vec<String> = prefixed("login", "src/a.rs:3:// TODO\nsrc/b.rs:9:// TODO\n")
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            ["login:src/a.rs:3:// TODO", "login:src/b.rs:9:// TODO"]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::audit::{self, AuditEntry};
+use crate::git;
+use anyhow::Result;
+use tabled::{
+    Table, Tabled,
+    settings::{Padding, Style, object::Columns},
+};
+
+#[derive(Tabled)]
+struct HistoryRow {
+    #[tabled(rename = "TIME (UTC)")]
+    time: String,
+    #[tabled(rename = "ACTION")]
+    action: String,
+    #[tabled(rename = "TARGET")]
+    target: String,
+    #[tabled(rename = "COMMAND")]
+    command: String,
+}
+
+pub fn run(limit: usize, all: bool) -> Result<()> {
+    let mut entries = audit::read_entries()?;
+
+    // Outside a repository there is nothing to scope to, so show everything
+    let repo = if all {
+        None
+    } else {
+        git::get_main_worktree_root().ok()
+    };
+    if let Some(repo) = &repo {
+        entries.retain(|entry| entry.repo.as_ref() == Some(repo));
+    }
+
+    if entries.is_empty() {
+        if repo.is_some() {
+            println!("No destructive actions recorded for this repository (use --all for others)");
+        } else {
+            println!("No destructive actions recorded");
+        }
+        return Ok(());
+    }
+
+    let skip = entries.len().saturating_sub(limit);
+    let rows: Vec<HistoryRow> = entries.into_iter().skip(skip).map(to_row).collect();
+
+    let mut table = Table::new(rows);
+    table
+        .with(Style::blank())
+        .modify(Columns::new(0..3), Padding::new(0, 1, 0, 0));
+    println!("{table}");
+
+    Ok(())
+}
+
+fn to_row(entry: AuditEntry) -> HistoryRow {
+    let target = match entry.detail {
+        Some(detail) => format!("{} ({})", entry.target, detail),
+        None => entry.target,
+    };
+    HistoryRow {
+        time: format_timestamp(entry.timestamp),
+        action: entry.action.label().to_string(),
+        target,
+        command: entry.command,
+    }
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_timestamp;
+
+    #[test]
+    fn format_timestamp_as_utc_date_time() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20");
+    }
+}
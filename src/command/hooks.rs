@@ -0,0 +1,321 @@
+use crate::remote;
+use crate::tmux::{self, MarkedWindow, WorktreeTag};
+use anyhow::{Result, anyhow};
+use console::style;
+use tabled::{
+    Table, Tabled,
+    settings::{Padding, Style, object::Columns},
+};
+
+/// What the scripts of workmux's focus hooks have in common: they export the
+/// worktree's handle before running `on_enter`/`on_leave`
+const HOOK_MARKER: &str = "WORKMUX_HANDLE=";
+
+/// Something workmux set on a window that is stale or clashes with the tmux
+/// config
+#[derive(Debug, PartialEq)]
+enum Issue {
+    /// The window belongs to a worktree that is gone
+    WorktreeGone(WorktreeTag),
+    /// A focus hook of workmux on a window no worktree owns
+    OrphanedHook(&'static str),
+    /// A status no pane of the window reports any more
+    StaleStatus(String),
+    /// The window's hook hides these commands of the global hook of the
+    /// same event
+    HidesGlobalHook(&'static str, Vec<String>),
+    /// The window shows the status in a format made from an older global one
+    StaleFormat(&'static str),
+}
+
+impl Issue {
+    fn describe(&self) -> String {
+        match self {
+            Issue::WorktreeGone(tag) => {
+                format!("belongs to {}, which no longer exists", tag.path.display())
+            }
+            Issue::OrphanedHook(event) => {
+                format!("has a workmux {} hook but no worktree", event)
+            }
+            Issue::StaleStatus(icon) => format!("shows {} but no pane reports a status", icon),
+            Issue::HidesGlobalHook(event, commands) => format!(
+                "{} hook hides {} command(s) of your global hook: {}",
+                event,
+                commands.len(),
+                commands.join("; ")
+            ),
+            Issue::StaleFormat(option) => {
+                format!("{} was made from an older global format", option)
+            }
+        }
+    }
+
+    fn repair(&self, window_id: &str) -> Result<String> {
+        Ok(match self {
+            Issue::WorktreeGone(tag) => {
+                tmux::clear_window_state(&[window_id.to_string()], &tag.handle);
+                "cleared workmux's status, tag and hooks".to_string()
+            }
+            Issue::OrphanedHook(event) => {
+                tmux::unset_window_hook(window_id, event)?;
+                format!("removed the {} hook", event)
+            }
+            Issue::StaleStatus(_) => {
+                tmux::clear_window_status(window_id)?;
+                "cleared the status".to_string()
+            }
+            Issue::HidesGlobalHook(event, commands) => {
+                tmux::append_window_hook(window_id, event, commands)?;
+                format!("added your global {} hook after workmux's", event)
+            }
+            Issue::StaleFormat(option) => {
+                tmux::reset_status_format(window_id, option)?;
+                format!("remade {} from the global format", option)
+            }
+        })
+    }
+}
+
+/// A window with something of workmux's on it
+struct Inspected {
+    window: MarkedWindow,
+    /// Events workmux hooks the window for
+    hooks: Vec<&'static str>,
+    issues: Vec<Issue>,
+}
+
+#[derive(Tabled)]
+struct WindowRow {
+    #[tabled(rename = "WINDOW")]
+    name: String,
+    #[tabled(rename = "WORKTREE")]
+    worktree: String,
+    #[tabled(rename = "STATUS")]
+    status: String,
+    #[tabled(rename = "HOOKS")]
+    hooks: String,
+    #[tabled(rename = "ISSUES")]
+    issues: usize,
+}
+
+/// List what workmux set on tmux windows, flagging what is stale or clashes
+/// with the tmux config
+pub fn status() -> Result<()> {
+    let (windows, focus_events) = inspect()?;
+    if windows.is_empty() {
+        println!("No tmux windows have workmux hooks or options");
+        return Ok(());
+    }
+
+    let rows: Vec<WindowRow> = windows
+        .iter()
+        .map(|inspected| WindowRow {
+            name: inspected.window.name.clone(),
+            worktree: inspected
+                .window
+                .worktree
+                .as_ref()
+                .and_then(|tag| tag.path.file_name())
+                .map_or("-".to_string(), |name| name.to_string_lossy().to_string()),
+            status: inspected.window.status.clone().unwrap_or("-".to_string()),
+            hooks: if inspected.hooks.is_empty() {
+                "-".to_string()
+            } else {
+                inspected.hooks.join(", ")
+            },
+            issues: inspected.issues.len(),
+        })
+        .collect();
+    let mut table = Table::new(rows);
+    table
+        .with(Style::blank())
+        .modify(Columns::new(0..5), Padding::new(0, 1, 0, 0));
+    println!("{table}");
+
+    let mut count = 0;
+    for inspected in &windows {
+        for issue in &inspected.issues {
+            if count == 0 {
+                println!();
+            }
+            count += 1;
+            println!(
+                "{} {}: {}",
+                style("!").yellow(),
+                inspected.window.name,
+                issue.describe()
+            );
+        }
+    }
+    if !focus_events {
+        count += 1;
+        println!(
+            "{} focus-events is off, so focus hooks never run",
+            style("!").yellow()
+        );
+    }
+
+    println!();
+    if count > 0 {
+        println!(
+            "{} issue(s) found; run 'workmux hooks repair' to fix them",
+            count
+        );
+    } else {
+        println!("✓ No stale or conflicting hooks");
+    }
+    Ok(())
+}
+
+/// Fix what `status` flags
+pub fn repair() -> Result<()> {
+    let (windows, focus_events) = inspect()?;
+    let mut repaired = 0;
+    for inspected in &windows {
+        let window = &inspected.window;
+        for issue in &inspected.issues {
+            match issue.repair(&window.window_id) {
+                Ok(done) => {
+                    repaired += 1;
+                    println!("✓ {}: {}", window.name, done);
+                }
+                Err(e) => eprintln!("✗ {}: {:#}", window.name, e),
+            }
+        }
+    }
+    if !focus_events {
+        tmux::enable_focus_events()?;
+        repaired += 1;
+        println!("✓ Turned on focus-events");
+    }
+    if repaired == 0 {
+        println!("Nothing to repair");
+    }
+    Ok(())
+}
+
+/// The windows with something of workmux's on them, and whether focus events
+/// are on as far as the focus hooks among them need
+fn inspect() -> Result<(Vec<Inspected>, bool)> {
+    if !tmux::is_running().unwrap_or(false) {
+        return Err(anyhow!("tmux is not running"));
+    }
+    let global: Vec<(&'static str, Vec<String>)> = tmux::WINDOW_HOOKS
+        .into_iter()
+        .map(|event| (event, tmux::global_hook_commands(event)))
+        .collect();
+
+    let mut inspected = Vec::new();
+    for window in tmux::marked_windows()? {
+        let mut hooks = Vec::new();
+        let mut issues = Vec::new();
+        if let Some(tag) = &window.worktree
+            && remote::get().is_none()
+            && !tag.path.exists()
+        {
+            issues.push(Issue::WorktreeGone(tag.clone()));
+        }
+        for (event, global) in &global {
+            let commands = tmux::window_hook_commands(&window.window_id, event);
+            if !commands.iter().any(|command| command.contains(HOOK_MARKER)) {
+                continue;
+            }
+            hooks.push(*event);
+            issues.extend(hook_issues(&window, event, &commands, global));
+        }
+        if let Some(status) = &window.status
+            && !window.agent_pane
+        {
+            issues.push(Issue::StaleStatus(status.clone()));
+        }
+        for option in tmux::STATUS_FORMAT_OPTIONS {
+            if tmux::stale_status_format(&window.window_id, option).is_some() {
+                issues.push(Issue::StaleFormat(option));
+            }
+        }
+
+        // Clearing everything from a window of a removed worktree is all
+        // there is to do for it
+        if matches!(issues.first(), Some(Issue::WorktreeGone(_))) {
+            issues.truncate(1);
+        }
+        if window.worktree.is_some()
+            || window.status.is_some()
+            || !hooks.is_empty()
+            || !issues.is_empty()
+        {
+            inspected.push(Inspected {
+                window,
+                hooks,
+                issues,
+            });
+        }
+    }
+
+    let hooked = inspected.iter().any(|window| !window.hooks.is_empty());
+    Ok((inspected, !hooked || tmux::focus_events_enabled()))
+}
+
+/// What is wrong with the `commands` a window runs for `event`, given the
+/// `global` hook's commands for it
+fn hook_issues(
+    window: &MarkedWindow,
+    event: &'static str,
+    commands: &[String],
+    global: &[String],
+) -> Vec<Issue> {
+    if window.worktree.is_none() {
+        return vec![Issue::OrphanedHook(event)];
+    }
+    let hidden: Vec<String> = global
+        .iter()
+        .filter(|command| !commands.contains(command))
+        .cloned()
+        .collect();
+    if hidden.is_empty() {
+        Vec::new()
+    } else {
+        vec![Issue::HidesGlobalHook(event, hidden)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn window(worktree: Option<&str>) -> MarkedWindow {
+        MarkedWindow {
+            window_id: "@1".to_string(),
+            name: "main:wm-api".to_string(),
+            worktree: worktree.map(|path| WorktreeTag {
+                handle: "api".to_string(),
+                path: PathBuf::from(path),
+            }),
+            status: None,
+            agent_pane: false,
+        }
+    }
+
+    #[test]
+    fn hooks_are_checked_against_the_global_ones() {
+        let ours = "run-shell -b \"cd /w/api || exit 1\nexport WORKMUX_HANDLE=api\"".to_string();
+        let global = vec!["run-shell true".to_string()];
+        let issues = |worktree, commands: &[String], global: &[String]| {
+            hook_issues(&window(worktree), "pane-focus-in", commands, global)
+        };
+
+        let alone = vec![ours.clone()];
+        assert_eq!(
+            issues(None, &alone, &global),
+            vec![Issue::OrphanedHook("pane-focus-in")]
+        );
+        assert_eq!(
+            issues(Some("/w/api"), &alone, &global),
+            vec![Issue::HidesGlobalHook("pane-focus-in", global.clone())]
+        );
+        assert!(issues(Some("/w/api"), &alone, &[]).is_empty());
+        // After a repair, the global hook runs after ours
+        assert!(issues(Some("/w/api"), &[ours, global[0].clone()], &global).is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+use crate::command::args::SetupFlags;
+use crate::config;
+use crate::workflow::{self, FileFilter, ImportSource, SetupOptions, WorkflowContext};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+pub fn run(file: &Path, branch: Option<&str>, base: Option<&str>, setup: SetupFlags) -> Result<()> {
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+    let source = workflow::detect_source(file)?;
+
+    let mut options = SetupOptions::new(!setup.no_hooks, !setup.no_file_ops, !setup.no_pane_cmds);
+    options.focus_window = !setup.background;
+    options.file_filter = FileFilter::new(&setup.skip_file, &setup.only_file)?;
+
+    context.ensure_tmux_running()?;
+
+    let result = workflow::import_work(&context, &source, branch, base, options)
+        .with_context(|| format!("Failed to import '{}'", file.display()))?;
+
+    if result.post_create_hooks_run > 0 {
+        println!("✓ Setup complete");
+    }
+
+    match &source {
+        ImportSource::Bundle(_) => {
+            println!("✓ Imported bundle into branch '{}'", result.branch_name)
+        }
+        ImportSource::Patches(patches) => println!(
+            "✓ Applied {} patch file(s) to new branch '{}'",
+            patches.len(),
+            result.branch_name
+        ),
+    }
+    if let Some(ref base) = result.base_branch {
+        println!("  Base: {}", base);
+    }
+    println!("  Worktree: {}", result.worktree_path.display());
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::config::Config;
+use crate::{git, tmux, worktree_state};
+use anyhow::{Context, Result, anyhow};
+
+/// Save the pane layout of a worktree's window, so its window is arranged the
+/// same way whenever it is opened again
+pub fn save(name: Option<&str>) -> Result<()> {
+    let config = Config::load(None)?;
+    let (handle, branch) = resolve(name)?;
+
+    // Without a name, the window this runs in, which may be a duplicate
+    // opened with `open --new`
+    let pane_id = match (name, tmux::current_pane_id()) {
+        (None, Some(pane_id)) => pane_id,
+        _ => tmux::active_pane_id(config.window_prefix(), &handle)
+            .with_context(|| format!("No open window for '{}'", handle))?,
+    };
+    let layout = tmux::window_layout(&pane_id)?;
+
+    worktree_state::set_layout(&handle, &branch, Some(layout.clone()), &config)
+        .context("Failed to record the pane layout")?;
+    println!("✓ Saved the pane layout of '{}'", handle);
+    println!(
+        "  To arrange every worktree this way, set in .workmux.yaml:\n\n  panes:\n    layout: \"{}\"",
+        layout
+    );
+    Ok(())
+}
+
+/// Forget the layout saved with `save`
+pub fn clear(name: Option<&str>) -> Result<()> {
+    let config = Config::load(None)?;
+    let (handle, branch) = resolve(name)?;
+    worktree_state::set_layout(&handle, &branch, None, &config)
+        .context("Failed to forget the pane layout")?;
+    println!("✓ Cleared the saved pane layout of '{}'", handle);
+    Ok(())
+}
+
+fn resolve(name: Option<&str>) -> Result<(String, String)> {
+    let name = super::resolve_name(name)?;
+    let (path, branch) = git::find_worktree(&name)?;
+    let handle = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    Ok((handle, branch))
+}
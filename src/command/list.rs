@@ -1,6 +1,12 @@
-use crate::{config, workflow};
+use crate::git::CommitSummary;
+use crate::workflow::WorktreeInfo;
+use crate::worktree_state::WaitingReason;
+use crate::{config, offline, workflow};
 use anyhow::Result;
 use pathdiff::diff_paths;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tabled::{
     Table, Tabled,
     settings::{Padding, Style, disable::Remove, object::Columns},
@@ -10,6 +16,8 @@ use tabled::{
 struct WorktreeRow {
     #[tabled(rename = "BRANCH")]
     branch: String,
+    #[tabled(rename = "STATE")]
+    state: String,
     #[tabled(rename = "PR")]
     pr_status: String,
     #[tabled(rename = "TMUX")]
@@ -18,6 +26,98 @@ struct WorktreeRow {
     unmerged_status: String,
     #[tabled(rename = "PATH")]
     path_str: String,
+    #[tabled(rename = "LAST COMMIT")]
+    last_commit: String,
+    #[tabled(rename = "DESCRIPTION")]
+    description: String,
+}
+
+/// Longest description shown in the table
+const DESCRIPTION_WIDTH: usize = 60;
+
+/// Longest commit subject shown in the table
+const SUBJECT_WIDTH: usize = 50;
+
+/// A worktree in `list --json` output
+#[derive(Serialize)]
+struct WorktreeJson {
+    branch: String,
+    handle: String,
+    path: PathBuf,
+    window: String,
+    window_open: bool,
+    unmerged: bool,
+    /// `branch.<name>.description`
+    description: Option<String>,
+    /// The branch's newest commit, null for detached worktrees
+    last_commit: Option<CommitJson>,
+    dirty: bool,
+    /// Commits relative to the main branch, null when there is none
+    ahead: Option<usize>,
+    behind: Option<usize>,
+    /// Unfinished rebase, merge, etc.
+    state: Option<&'static str>,
+    pinned: bool,
+    /// Only with --pr, null when the branch has no PR
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pr: Option<Option<PrJson>>,
+    /// Only with --why, null when the agent isn't waiting for input or
+    /// nothing was recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    waiting: Option<Option<WaitingReason>>,
+}
+
+#[derive(Serialize)]
+struct CommitJson {
+    sha: String,
+    subject: String,
+    author: String,
+    /// Unix timestamp of when it was committed
+    timestamp: u64,
+}
+
+#[derive(Serialize)]
+struct PrJson {
+    number: u32,
+    title: String,
+    state: String,
+    draft: bool,
+}
+
+impl WorktreeJson {
+    fn new(wt: WorktreeInfo, show_pr: bool, waiting: Option<Option<WaitingReason>>) -> Self {
+        let status = wt.status.as_ref();
+        let ahead_behind = status.and_then(|status| status.ahead_behind);
+        Self {
+            branch: wt.branch,
+            handle: wt.handle,
+            path: wt.path,
+            window: wt.window,
+            window_open: wt.has_tmux,
+            unmerged: wt.has_unmerged,
+            description: wt.description,
+            last_commit: wt.last_commit.map(|commit| CommitJson {
+                sha: commit.sha,
+                subject: commit.subject,
+                author: commit.author,
+                timestamp: commit.timestamp,
+            }),
+            dirty: status.is_some_and(|status| status.dirty),
+            ahead: ahead_behind.map(|(ahead, _)| ahead),
+            behind: ahead_behind.map(|(_, behind)| behind),
+            state: wt.operation.map(|op| op.state()),
+            pinned: wt.pinned,
+            pr: show_pr.then(|| {
+                wt.pr_info.map(|pr| PrJson {
+                    number: pr.number,
+                    title: pr.title,
+                    state: pr.state,
+                    draft: pr.is_draft,
+                })
+            }),
+            waiting,
+        }
+    }
 }
 
 fn format_pr_status(pr_info: Option<crate::github::PrSummary>) -> String {
@@ -37,9 +137,65 @@ fn format_pr_status(pr_info: Option<crate::github::PrSummary>) -> String {
         .unwrap_or_else(|| "-".to_string())
 }
 
-pub fn run(show_pr: bool) -> Result<()> {
+/// First line of a description, cut to fit the table
+fn short_description(description: &str) -> String {
+    let first_line = description.lines().next().unwrap_or_default();
+    truncate(first_line, DESCRIPTION_WIDTH)
+}
+
+/// `text` cut to `width` characters, ending in an ellipsis if it was longer
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(width - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// A commit as the table shows it: hash, subject, author and how long ago
+fn short_commit(commit: &CommitSummary, now: u64) -> String {
+    let age = Duration::from_secs(now.saturating_sub(commit.timestamp));
+    format!(
+        "{} {} ({}, {} ago)",
+        commit.sha,
+        truncate(&commit.subject, SUBJECT_WIDTH),
+        commit.author,
+        super::remove::format_age(age)
+    )
+}
+
+pub fn run(show_pr: bool, long: bool, json: bool, why: bool) -> Result<()> {
     let config = config::Config::load(None)?;
-    let worktrees = workflow::list(&config, show_pr)?;
+
+    let show_pr = if show_pr && offline::is_enabled() {
+        eprintln!("Offline: skipping PR status.");
+        false
+    } else {
+        show_pr
+    };
+    let worktrees = workflow::list(&config, show_pr, json)?;
+    let mut waiting = if why {
+        workflow::waiting(&config)?
+    } else {
+        Vec::new()
+    };
+
+    if json {
+        let entries: Vec<WorktreeJson> = worktrees
+            .into_iter()
+            .map(|wt| {
+                let reason = why.then(|| {
+                    waiting
+                        .iter_mut()
+                        .find(|(handle, _)| *handle == wt.handle)
+                        .and_then(|(_, reason)| reason.take())
+                });
+                WorktreeJson::new(wt, show_pr, reason)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
 
     if worktrees.is_empty() {
         println!("No worktrees found");
@@ -47,6 +203,13 @@ pub fn run(show_pr: bool) -> Result<()> {
     }
 
     let current_dir = std::env::current_dir()?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let any_state = worktrees
+        .iter()
+        .any(|wt| wt.operation.is_some() || wt.pinned);
 
     let display_data: Vec<WorktreeRow> = worktrees
         .into_iter()
@@ -64,6 +227,11 @@ pub fn run(show_pr: bool) -> Result<()> {
 
             WorktreeRow {
                 branch: wt.branch,
+                state: match (wt.operation, wt.pinned) {
+                    (Some(op), _) => op.state().to_string(),
+                    (None, true) => "pinned".to_string(),
+                    (None, false) => "-".to_string(),
+                },
                 pr_status: format_pr_status(wt.pr_info),
                 path_str,
                 tmux_status: if wt.has_tmux {
@@ -76,6 +244,16 @@ pub fn run(show_pr: bool) -> Result<()> {
                 } else {
                     "-".to_string()
                 },
+                last_commit: wt
+                    .last_commit
+                    .as_ref()
+                    .map(|commit| short_commit(commit, now))
+                    .unwrap_or_else(|| "-".to_string()),
+                description: wt
+                    .description
+                    .as_deref()
+                    .map(short_description)
+                    .unwrap_or_else(|| "-".to_string()),
             }
         })
         .collect();
@@ -83,14 +261,152 @@ pub fn run(show_pr: bool) -> Result<()> {
     let mut table = Table::new(display_data);
     table
         .with(Style::blank())
-        .modify(Columns::new(0..4), Padding::new(0, 1, 0, 0));
+        .modify(Columns::new(0..7), Padding::new(0, 1, 0, 0));
 
+    // Remove columns right to left so the indices stay valid
+    if !long {
+        table.with(Remove::column(Columns::new(6..8)));
+    }
     // Hide PR column if --pr flag not used
     if !show_pr {
+        table.with(Remove::column(Columns::new(2..3)));
+    }
+    // Only show STATE when a worktree is pinned or mid-rebase, mid-merge, etc.
+    if !any_state {
         table.with(Remove::column(Columns::new(1..2)));
     }
 
     println!("{table}");
 
+    if why {
+        print_waiting(&waiting);
+    }
+
     Ok(())
 }
+
+/// What each waiting agent last printed, indented under its worktree
+fn print_waiting(waiting: &[(String, Option<WaitingReason>)]) {
+    if waiting.is_empty() {
+        println!("\nNo agents are waiting for input.");
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    for (handle, reason) in waiting {
+        match reason {
+            Some(reason) => {
+                let age = Duration::from_secs(now.saturating_sub(reason.since));
+                println!(
+                    "\n{} is waiting (for {}):",
+                    handle,
+                    super::remove::format_age(age)
+                );
+                for line in reason.output.lines() {
+                    println!("    {}", line);
+                }
+            }
+            None => println!("\n{} is waiting (nothing recorded)", handle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::types::WorktreeStatus;
+
+    #[test]
+    fn commits_show_hash_subject_author_and_age() {
+        let commit = CommitSummary {
+            sha: "e4f5a6b".to_string(),
+            subject: "Add the login form, with validation of every field and a remember-me box"
+                .to_string(),
+            author: "Ada".to_string(),
+            timestamp: 1_700_000_000,
+        };
+        assert_eq!(
+            short_commit(&commit, 1_700_000_000 + 3 * 3600),
+            "e4f5a6b Add the login form, with validation of every fiel… (Ada, 3h ago)"
+        );
+    }
+
+    #[test]
+    fn json_reports_status_counts_and_pr() {
+        let worktree = |status, pr_info| WorktreeInfo {
+            branch: "feature/login".to_string(),
+            handle: "login".to_string(),
+            path: PathBuf::from("/repo__worktrees/login"),
+            window: "wm-login".to_string(),
+            has_tmux: true,
+            has_unmerged: false,
+            operation: Some(crate::git::InProgressOperation::Rebase),
+            pinned: false,
+            pr_info,
+            description: None,
+            last_commit: None,
+            status,
+        };
+        let json =
+            |wt, show_pr| serde_json::to_value(WorktreeJson::new(wt, show_pr, None)).unwrap();
+
+        let listed = json(
+            worktree(
+                Some(WorktreeStatus {
+                    dirty: true,
+                    ahead_behind: Some((2, 5)),
+                }),
+                None,
+            ),
+            false,
+        );
+        assert_eq!(
+            listed,
+            serde_json::json!({
+                "branch": "feature/login",
+                "handle": "login",
+                "path": "/repo__worktrees/login",
+                "window": "wm-login",
+                "window_open": true,
+                "unmerged": false,
+                "description": null,
+                "last_commit": null,
+                "dirty": true,
+                "ahead": 2,
+                "behind": 5,
+                "state": "rebasing",
+                "pinned": false,
+            })
+        );
+
+        // With no main branch to compare to, the counts are null
+        let detached = json(
+            worktree(
+                Some(WorktreeStatus {
+                    dirty: false,
+                    ahead_behind: None,
+                }),
+                None,
+            ),
+            true,
+        );
+        assert_eq!(detached["ahead"], serde_json::Value::Null);
+        assert_eq!(detached["behind"], serde_json::Value::Null);
+        // --pr shows a branch without one as null
+        assert_eq!(detached["pr"], serde_json::Value::Null);
+        assert!(detached.as_object().unwrap().contains_key("pr"));
+
+        let pr = crate::github::PrSummary {
+            number: 42,
+            title: "Fix login".to_string(),
+            state: "OPEN".to_string(),
+            is_draft: true,
+        };
+        assert_eq!(
+            json(worktree(None, Some(pr)), true)["pr"],
+            serde_json::json!({"number": 42, "title": "Fix login", "state": "OPEN", "draft": true})
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::cmd::shell_quote;
+use crate::config::{Config, StatusIcons};
+use crate::tmux::{self, MenuItem, WorkmuxWindow};
+use anyhow::{Context, Result, anyhow};
+
+/// Shortcut keys for the windows in the menu, in order
+const WINDOW_KEYS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Everything a window's actions need besides the window itself
+struct MenuContext<'a> {
+    /// Path to this workmux binary, for actions that call back into it
+    exe: &'a str,
+    shell: &'a str,
+    icons: &'a StatusIcons,
+}
+
+pub fn run() -> Result<()> {
+    let config = Config::load(None)?;
+    let windows = tmux::list_workmux_windows(config.window_prefix())?;
+    if windows.is_empty() {
+        return Err(anyhow!("No workmux windows are open"));
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate the workmux executable")?;
+    let shell = tmux::get_default_shell()?;
+    let ctx = MenuContext {
+        exe: &exe.to_string_lossy(),
+        shell: &shell,
+        icons: &config.status_icons,
+    };
+
+    tmux::display_menu("workmux", &window_items(&windows, &ctx))
+}
+
+/// Top-level menu: one entry per window, opening that window's actions
+fn window_items(windows: &[WorkmuxWindow], ctx: &MenuContext) -> Vec<MenuItem> {
+    windows
+        .iter()
+        .enumerate()
+        .map(|(idx, window)| {
+            let handle = escape_format(&window.handle);
+            MenuItem::Action {
+                label: format!("{} {}", window.status.as_deref().unwrap_or(" "), handle),
+                key: WINDOW_KEYS
+                    .chars()
+                    .nth(idx)
+                    .map(String::from)
+                    .unwrap_or_default(),
+                command: tmux::menu_command(&handle, &window_actions(window, ctx)),
+            }
+        })
+        .collect()
+}
+
+/// Actions for one window. Agent actions only appear while an agent has a
+/// status, and "mark done" only while it isn't done yet.
+fn window_actions(window: &WorkmuxWindow, ctx: &MenuContext) -> Vec<MenuItem> {
+    let path = shell_quote(&window.path.to_string_lossy());
+    let handle = shell_quote(&window.handle);
+    let exe = shell_quote(ctx.exe);
+
+    let mut items = vec![MenuItem::Action {
+        label: "Switch".to_string(),
+        key: "s".to_string(),
+        command: format!("switch-client -t {}", window.window_id),
+    }];
+
+    if let Some(pane) = &window.agent_pane {
+        if window.status.as_deref() != Some(ctx.icons.done()) {
+            let script = format!(
+                "cd {} && TMUX_PANE={} {} set-window-status done",
+                path, pane, exe
+            );
+            items.push(MenuItem::Action {
+                label: "Mark done".to_string(),
+                key: "d".to_string(),
+                command: format!("run-shell -b {}", shell_quote(&script)),
+            });
+        }
+
+        // Replace the agent with a fresh shell and drop its stale status icon
+        let kill = format!(
+            "respawn-pane -k -t {pane} -c {path} {shell} ; set-option -uw -t {pane} @workmux_status",
+            shell = shell_quote(ctx.shell),
+        );
+        let prompt = format!("Kill the agent in {}? (y/n)", escape_format(&window.handle));
+        items.push(MenuItem::Action {
+            label: "Kill agent".to_string(),
+            key: "k".to_string(),
+            command: format!(
+                "confirm-before -p {} {}",
+                shell_quote(&prompt),
+                shell_quote(&kill)
+            ),
+        });
+    } else {
+        items.push(MenuItem::Disabled("No agent running".to_string()));
+    }
+
+    // remove may ask for confirmation, so give it a terminal
+    items.push(MenuItem::Separator);
+    items.push(MenuItem::Action {
+        label: "Remove worktree".to_string(),
+        key: "r".to_string(),
+        command: format!(
+            "display-popup -E -d {} {}",
+            path,
+            shell_quote(&format!("{} remove {}", exe, handle))
+        ),
+    });
+
+    items
+}
+
+/// Menu labels and prompts are expanded as tmux formats
+fn escape_format(text: &str) -> String {
+    text.replace('#', "##")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn window(status: Option<&str>, agent_pane: Option<&str>) -> WorkmuxWindow {
+        WorkmuxWindow {
+            window_id: "@3".to_string(),
+            handle: "feature".to_string(),
+            path: PathBuf::from("/work/feature"),
+            status: status.map(String::from),
+            agent_pane: agent_pane.map(String::from),
+        }
+    }
+
+    fn labels(items: &[MenuItem]) -> Vec<&str> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                MenuItem::Action { label, .. } => Some(label.as_str()),
+                MenuItem::Disabled(label) => Some(label.as_str()),
+                MenuItem::Separator => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn window_actions_depend_on_agent_status() {
+        let icons = StatusIcons::default();
+        let ctx = MenuContext {
+            exe: "/usr/bin/workmux",
+            shell: "/bin/zsh",
+            icons: &icons,
+        };
+
+        let working = window_actions(&window(Some(icons.working()), Some("%5")), &ctx);
+        assert_eq!(
+            labels(&working),
+            ["Switch", "Mark done", "Kill agent", "Remove worktree"]
+        );
+
+        let done = window_actions(&window(Some(icons.done()), Some("%5")), &ctx);
+        assert_eq!(labels(&done), ["Switch", "Kill agent", "Remove worktree"]);
+
+        let idle = window_actions(&window(None, None), &ctx);
+        assert_eq!(
+            labels(&idle),
+            ["Switch", "No agent running", "Remove worktree"]
+        );
+    }
+
+    #[test]
+    fn window_items_nest_action_menus() {
+        let icons = StatusIcons::default();
+        let ctx = MenuContext {
+            exe: "/usr/bin/workmux",
+            shell: "/bin/zsh",
+            icons: &icons,
+        };
+        let items = window_items(&[window(Some(icons.waiting()), Some("%5"))], &ctx);
+
+        let MenuItem::Action {
+            label,
+            key,
+            command,
+        } = &items[0]
+        else {
+            panic!("expected an action");
+        };
+        assert_eq!(label, &format!("{} feature", icons.waiting()));
+        assert_eq!(key, "1");
+        assert!(command.starts_with("display-menu -T feature Switch s "));
+    }
+}
@@ -1,77 +1,604 @@
-use crate::config::MergeStrategy;
-use crate::workflow::WorkflowContext;
-use crate::{config, workflow};
-use anyhow::{Context, Result};
+use crate::audit::{self, AuditAction};
+use crate::cmd::Cmd;
+use crate::command::args::MatchArgs;
+use crate::config::{ConfirmPolicy, MergeStrategy};
+use crate::workflow::{MergePreflight, MergeResult, WorkflowContext};
+use crate::{config, dry_run, git, interrupt, offline, workflow, worktree_state};
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
 
+/// How a merge is made, from flags or asked step by step with `--interactive`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MergePlan {
+    ignore_uncommitted: bool,
+    strategy: MergeStrategy,
+    keep: bool,
+    no_verify: bool,
+    delete_remote: bool,
+}
+
+impl MergePlan {
+    /// One line per choice, for the summary before an interactive merge
+    fn describe(&self, branch: &str, target: &str, remote: Option<&str>) -> Vec<String> {
+        let verb = match self.strategy {
+            MergeStrategy::Merge => "Merge",
+            MergeStrategy::Rebase => "Rebase and fast-forward",
+            MergeStrategy::Squash => "Squash",
+            MergeStrategy::FfOnly => "Fast-forward",
+        };
+        let mut lines = vec![format!("{} '{}' into '{}'", verb, branch, target)];
+        if self.ignore_uncommitted {
+            lines.push("Leave uncommitted changes out".to_string());
+        }
+        if self.no_verify {
+            lines.push("Skip the pre-merge hooks".to_string());
+        }
+        if self.delete_remote
+            && let Some(remote) = remote
+        {
+            lines.push(format!("Delete '{}'", remote));
+        }
+        lines.push(if self.keep {
+            "Keep the worktree, window and branch".to_string()
+        } else {
+            "Remove the worktree, window and branch".to_string()
+        });
+        lines
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     name: Option<&str>,
     into_branch: Option<&str>,
     ignore_uncommitted: bool,
-    mut rebase: bool,
-    mut squash: bool,
+    rebase: bool,
+    squash: bool,
+    ff_only: bool,
+    continue_: bool,
+    abort: bool,
     keep: bool,
     no_verify: bool,
+    interactive: bool,
+    delete_remote: bool,
+    yes: bool,
+    matching: MatchArgs,
 ) -> Result<()> {
     let config = config::Config::load(None)?;
 
-    // Apply default strategy from config if no CLI flags are provided
-    if !rebase
-        && !squash
-        && let Some(strategy) = config.merge_strategy
-    {
-        match strategy {
-            MergeStrategy::Rebase => rebase = true,
-            MergeStrategy::Squash => squash = true,
-            MergeStrategy::Merge => {}
-        }
-    }
+    // CLI flags override the default strategy from config
+    let strategy = if rebase {
+        MergeStrategy::Rebase
+    } else if squash {
+        MergeStrategy::Squash
+    } else if ff_only {
+        MergeStrategy::FfOnly
+    } else {
+        config.merge_strategy.unwrap_or_default()
+    };
 
     // Resolve name from argument or current directory
     // Note: Must be done BEFORE creating WorkflowContext (which may change CWD)
-    let name_to_merge = super::resolve_name(name)?;
+    // --by-branch/--by-handle only apply to an explicitly given name
+    let name_to_merge = match name {
+        Some(name) => super::resolve_match(name, matching.mode())?,
+        None if continue_ || abort => stopped_merge_name()?,
+        None => super::resolve_name(None)?,
+    };
 
     let context = WorkflowContext::new(config)?;
 
-    // Announce pre-merge hooks if any (unless --no-verify is passed)
-    if !no_verify {
-        super::announce_hooks(&context.config, None, super::HookPhase::PreMerge);
+    if continue_ {
+        return continue_merge(&name_to_merge, &context);
+    }
+    if abort {
+        let branch =
+            workflow::abort_merge(&name_to_merge, &context).context("Failed to abort merge")?;
+        println!(
+            "✓ Aborted merging '{}'; both branches are as they were",
+            branch
+        );
+        return Ok(());
+    }
+
+    let target = into_branch.unwrap_or(&context.main_branch);
+    let worktree = git::find_worktree(&name_to_merge).ok();
+    // Looked up before the merge, which deletes the local branch and its upstream
+    let remote_branch = worktree.as_ref().and_then(|(_, branch)| {
+        git::remote_branch_of(branch, context.config.default_remote()).map(|(remote, branch)| {
+            let name = format!("{}/{}", remote, branch);
+            (remote, branch, name)
+        })
+    });
+    let mut plan = MergePlan {
+        ignore_uncommitted,
+        strategy,
+        keep,
+        no_verify,
+        delete_remote,
+    };
+
+    if interactive {
+        let Some((worktree_path, branch)) = &worktree else {
+            return Err(anyhow!("No worktree found for '{}'", name_to_merge));
+        };
+        let remote = remote_branch.as_ref().map(|(_, _, name)| name.as_str());
+        match ask_plan(branch, worktree_path, target, remote, &context, plan)? {
+            Some(asked) => plan = asked,
+            None => return Ok(()),
+        }
+    } else if !yes {
+        let preflight = workflow::merge_preflight(&name_to_merge, into_branch, &context)?;
+        let remote = remote_branch.as_ref().map(|(_, _, name)| name.as_str());
+        for line in preflight_report(&preflight, &plan, remote) {
+            println!("{}", line);
+        }
+        let question = format!("Merge '{}' into '{}'?", name_to_merge, target);
+        if !super::confirm(&question, context.config.confirm.merge())? {
+            return Ok(());
+        }
+    }
+    if !interactive && delete_remote && remote_branch.is_none() {
+        println!("No remote branch of '{}' to delete", name_to_merge);
+    }
+    let MergePlan {
+        ignore_uncommitted,
+        strategy,
+        keep,
+        no_verify,
+        delete_remote,
+    } = plan;
+
+    // Cleanup deletes the untracked files too; say how much before merging,
+    // asking unless --yes answered for everything
+    let size_policy = if yes {
+        ConfirmPolicy::Skip
+    } else {
+        ConfirmPolicy::Ask
+    };
+    if !keep
+        && let Some((worktree_path, _)) = &worktree
+        && !super::check_untracked_size(
+            &context.config,
+            &name_to_merge,
+            worktree_path,
+            size_policy,
+        )?
+    {
+        return Ok(());
     }
 
-    // Only announce pre-remove hooks if we're actually going to run cleanup
-    if !keep {
-        super::announce_hooks(&context.config, None, super::HookPhase::PreRemove);
+    // The worktree's template may bring its own hooks
+    let hook_config = match &worktree {
+        Some((_, branch)) => context.config.for_branch(branch),
+        None => context.config.clone(),
+    };
+
+    if let Some((worktree_path, branch)) = &worktree {
+        let target = config::WhenTarget {
+            branch,
+            agent: context.config.agent.as_deref(),
+            worktree_path,
+        };
+        // Announce pre-merge hooks if any (unless --no-verify is passed)
+        if !no_verify {
+            super::announce_hooks(&hook_config, None, super::HookPhase::PreMerge, &target);
+        }
+
+        // Only announce pre-remove hooks if we're actually going to run cleanup
+        if !keep {
+            super::announce_hooks(&hook_config, None, super::HookPhase::PreRemove, &target);
+        }
     }
 
+    // Ctrl-C stops at the next safe point instead of mid-merge
+    let guard = interrupt::Guard::new()?;
     let result = workflow::merge(
         &name_to_merge,
         into_branch,
         ignore_uncommitted,
-        rebase,
-        squash,
+        strategy,
         keep,
         no_verify,
         &context,
     )
     .context("Failed to merge worktree")?;
+    drop(guard);
 
-    if result.had_staged_changes {
+    if result.had_staged_changes && !ignore_uncommitted {
         println!("✓ Committed staged changes");
     }
 
-    println!(
-        "Merging '{}' into '{}'...",
-        result.branch_merged, result.main_branch
+    let stopped = result.conflicts.is_some();
+    report_merged(result)?;
+
+    if delete_remote && let Some((remote, branch, name)) = &remote_branch {
+        if stopped {
+            println!(
+                "'{}' was not deleted; once the merge is done, run 'git push {} --delete {}'",
+                name, remote, branch
+            );
+        } else if offline::is_enabled() {
+            println!("Skipped deleting '{}' (offline)", name);
+        } else {
+            match git::delete_remote_branch(remote, branch) {
+                Ok(commit) => {
+                    audit::record(AuditAction::RemoteBranchDeleted, name, commit.as_deref());
+                    println!("✓ Deleted remote branch '{}'", name)
+                }
+                Err(e) => eprintln!("✗ {:#}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Walk through the choices of merging `branch`, showing what each one
+/// affects. None if the user backs out.
+fn ask_plan(
+    branch: &str,
+    worktree_path: &Path,
+    target: &str,
+    remote: Option<&str>,
+    context: &WorkflowContext,
+    mut plan: MergePlan,
+) -> Result<Option<MergePlan>> {
+    let ask = super::ask_yes_no;
+
+    // Uncommitted work
+    let dirty =
+        git::has_unstaged_changes(worktree_path)? || git::has_untracked_files(worktree_path)?;
+    if dirty {
+        println!("Uncommitted changes in '{}':", branch);
+        print_indented(&git_output(worktree_path, &["status", "--short"]));
+        if !ask("Merge only the committed work, leaving these out?", false)? {
+            println!("Aborted.");
+            return Ok(None);
+        }
+        plan.ignore_uncommitted = true;
+    } else if git::has_staged_changes(worktree_path)? {
+        println!("Staged changes in '{}':", branch);
+        print_indented(&git_output(worktree_path, &["diff", "--cached", "--stat"]));
+        plan.ignore_uncommitted = !ask("Commit them before merging?", true)?;
+    }
+
+    // Strategy
+    let commits = git::get_commit_subjects(target, branch)?;
+    println!("\n{} commit(s) on '{}' to merge:", commits.len(), branch);
+    print_indented(&commits.join("\n"));
+    let default = plan.strategy;
+    plan.strategy = if ask(
+        &format!("Rebase onto '{}' first, for a linear history?", target),
+        default == MergeStrategy::Rebase,
+    )? {
+        MergeStrategy::Rebase
+    } else if commits.len() > 1
+        && ask(
+            &format!("Squash the {} commits into one?", commits.len()),
+            default == MergeStrategy::Squash,
+        )?
+    {
+        MergeStrategy::Squash
+    } else {
+        match default {
+            MergeStrategy::Rebase => MergeStrategy::Merge,
+            MergeStrategy::Squash if commits.len() > 1 => MergeStrategy::Merge,
+            other => other,
+        }
+    };
+
+    // Pre-merge hooks, from the worktree's template if it has one
+    let branch_config = context.config.for_branch(branch);
+    let hooks = config::hooks_for(
+        &branch_config.pre_merge,
+        &config::WhenTarget {
+            branch,
+            agent: context.config.agent.as_deref(),
+            worktree_path,
+        },
+    );
+    if !hooks.is_empty() {
+        println!("\nPre-merge hooks:");
+        print_indented(
+            &hooks
+                .iter()
+                .map(|hook| hook.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        plan.no_verify = !ask("Run them?", !plan.no_verify)?;
+    }
+
+    // Cleanup
+    println!();
+    if let Some(remote) = remote {
+        if offline::is_enabled() {
+            println!("'{}' is kept while offline", remote);
+            plan.delete_remote = false;
+        } else {
+            plan.delete_remote = ask(
+                &format!("Delete the remote branch '{}' after merging?", remote),
+                plan.delete_remote,
+            )?;
+        }
+    }
+    plan.keep = !ask(
+        &format!(
+            "Remove the worktree ({}), its window and branch after merging?",
+            worktree_path.display()
+        ),
+        !plan.keep,
+    )?;
+
+    println!();
+    for line in plan.describe(branch, target, remote) {
+        println!("  - {}", line);
+    }
+    if !ask("Go ahead?", true)? {
+        println!("Aborted.");
+        return Ok(None);
+    }
+    Ok(Some(plan))
+}
+
+/// What a merge is about to do: its commits, the files they touch, expected
+/// conflicts, the state of the worktree, and what is removed afterwards
+fn preflight_report(
+    preflight: &MergePreflight,
+    plan: &MergePlan,
+    remote: Option<&str>,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Preflight for merging '{}' into '{}':",
+        preflight.branch, preflight.target
+    )];
+    lines.push(format!("  Commits ({}):", preflight.commits.len()));
+    lines.extend(preflight.commits.iter().map(|c| format!("    {}", c)));
+    if !preflight.diff_stat.is_empty() {
+        lines.push("  Files:".to_string());
+        lines.extend(
+            preflight
+                .diff_stat
+                .lines()
+                .map(|line| format!("    {}", line.trim_start())),
+        );
+    }
+    lines.push(match &preflight.conflicts {
+        Some(files) if files.is_empty() => "  Conflicts: none expected".to_string(),
+        Some(files) => format!("  Conflicts: expected in {}", files.join(", ")),
+        None => "  Conflicts: unknown (needs git 2.38 or later)".to_string(),
+    });
+
+    let mut uncommitted = Vec::new();
+    if preflight.has_staged {
+        uncommitted.push(if plan.ignore_uncommitted {
+            "staged changes, left out"
+        } else {
+            "staged changes, committed first"
+        });
+    }
+    if preflight.has_unstaged {
+        uncommitted.push("unstaged changes");
+    }
+    if preflight.has_untracked {
+        uncommitted.push("untracked files");
+    }
+    let state = if uncommitted.is_empty() {
+        "clean".to_string()
+    } else {
+        uncommitted.join("; ")
+    };
+    lines.push(format!(
+        "  Worktree ({}): {}",
+        preflight.worktree_path.display(),
+        state
+    ));
+    if (preflight.has_unstaged || preflight.has_untracked) && !plan.keep {
+        lines.push(if plan.ignore_uncommitted {
+            "    (deleted with the worktree)".to_string()
+        } else {
+            "    (the merge stops unless --ignore-uncommitted is given)".to_string()
+        });
+    }
+
+    lines.push("  Plan:".to_string());
+    lines.extend(
+        plan.describe(&preflight.branch, &preflight.target, remote)
+            .into_iter()
+            .map(|line| format!("    - {}", line)),
     );
-    println!("✓ Merged '{}'", result.branch_merged);
+    lines
+}
+
+/// Output of a git command in `worktree_path`, for previews. Empty if it fails.
+fn git_output(worktree_path: &Path, args: &[&str]) -> String {
+    Cmd::new("git")
+        .args(args)
+        .workdir(worktree_path)
+        .run_and_capture_stdout()
+        .unwrap_or_default()
+}
+
+fn print_indented(text: &str) {
+    for line in text.lines() {
+        println!("  {}", line);
+    }
+}
+
+/// The worktree `--continue` or `--abort` refers to without a name: the
+/// current one, unless it has no stopped merge and exactly one other worktree
+/// does. Merges and squashes stop in the target's worktree, where the
+/// conflicts are resolved.
+fn stopped_merge_name() -> Result<String> {
+    let current = super::resolve_name(None)?;
+    let mut stopped: Vec<String> = git::list_worktrees()?
+        .into_iter()
+        .filter_map(|(path, _)| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .filter(|handle| {
+            worktree_state::load(handle)
+                .ok()
+                .flatten()
+                .is_some_and(|state| state.merge.is_some())
+        })
+        .collect();
+    if stopped.contains(&current) {
+        return Ok(current);
+    }
+    match stopped.len() {
+        0 => Ok(current),
+        1 => Ok(stopped.remove(0)),
+        _ => Err(anyhow!(
+            "Merges of several worktrees are stopped on conflicts ({}); name the one to continue or abort",
+            stopped.join(", ")
+        )),
+    }
+}
+
+/// Finish a merge stopped on conflicts
+fn continue_merge(name: &str, context: &WorkflowContext) -> Result<()> {
+    let guard = interrupt::Guard::new()?;
+    let result = workflow::continue_merge(name, context).context("Failed to continue merge")?;
+    drop(guard);
+    report_merged(result)
+}
+
+fn report_merged(result: MergeResult) -> Result<()> {
+    if let Some(conflicts) = &result.conflicts {
+        println!("✗ Stopped on conflicts in {}", conflicts.path.display());
+        for file in &conflicts.files {
+            println!("  - {}", file);
+        }
+        if conflicts.pane_opened {
+            println!("Opened a pane to resolve them.");
+        }
+        println!(
+            "Resolve and stage them, then run 'workmux merge --continue {}' (or '--abort').",
+            result.branch_merged
+        );
+        return Ok(());
+    }
+
+    if dry_run::is_enabled() {
+        println!(
+            "[dry-run] Would merge '{}' into '{}'",
+            result.branch_merged, result.main_branch
+        );
+    } else {
+        println!(
+            "Merging '{}' into '{}'...",
+            result.branch_merged, result.main_branch
+        );
+        println!("✓ Merged '{}'", result.branch_merged);
+    }
+
+    if result.cleanup_interrupted {
+        super::report_interrupted(
+            &[format!(
+                "merge '{}' into '{}'",
+                result.branch_merged, result.main_branch
+            )],
+            &[format!(
+                "remove the worktree, window, and branch '{}'",
+                result.branch_merged
+            )],
+            &format!(
+                "Run 'workmux rm {}' to finish cleaning up.",
+                result.branch_merged
+            ),
+        );
+        return Err(interrupt::Interrupted.into());
+    }
 
-    if keep {
-        println!("Worktree, window, and branch kept");
+    if result.kept {
+        println!(
+            "{}",
+            dry_run::outcome(
+                "Worktree, window, and branch kept",
+                "[dry-run] Would keep the worktree, window, and branch"
+            )
+        );
     } else {
         println!(
-            "✓ Successfully merged and cleaned up '{}'",
+            "{} '{}'",
+            dry_run::outcome(
+                "✓ Successfully merged and cleaned up",
+                "[dry-run] Would merge and clean up"
+            ),
             result.branch_merged
         );
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_summary_lists_each_choice() {
+        let plan = MergePlan {
+            ignore_uncommitted: false,
+            strategy: MergeStrategy::Squash,
+            keep: false,
+            no_verify: true,
+            delete_remote: true,
+        };
+        assert_eq!(
+            plan.describe("login", "main", Some("origin/login")),
+            [
+                "Squash 'login' into 'main'",
+                "Skip the pre-merge hooks",
+                "Delete 'origin/login'",
+                "Remove the worktree, window and branch",
+            ]
+        );
+        // Nothing to delete without a remote branch
+        assert_eq!(plan.describe("login", "main", None).len(), 3);
+    }
+
+    #[test]
+    fn preflight_report_shows_conflicts_and_what_the_merge_leaves_out() {
+        let preflight = MergePreflight {
+            branch: "login".to_string(),
+            target: "main".to_string(),
+            worktree_path: std::path::PathBuf::from("/src/app__worktrees/login"),
+            commits: vec!["Add form".to_string(), "Fix typo".to_string()],
+            diff_stat: "form.rs | 4 ++--\n 1 file changed, 2 insertions(+), 2 deletions(-)"
+                .to_string(),
+            conflicts: Some(vec!["form.rs".to_string()]),
+            has_staged: true,
+            has_unstaged: false,
+            has_untracked: true,
+        };
+        let plan = MergePlan {
+            ignore_uncommitted: true,
+            strategy: MergeStrategy::Merge,
+            keep: false,
+            no_verify: false,
+            delete_remote: false,
+        };
+        assert_eq!(
+            preflight_report(&preflight, &plan, None),
+            [
+                "Preflight for merging 'login' into 'main':",
+                "  Commits (2):",
+                "    Add form",
+                "    Fix typo",
+                "  Files:",
+                "    form.rs | 4 ++--",
+                "    1 file changed, 2 insertions(+), 2 deletions(-)",
+                "  Conflicts: expected in form.rs",
+                "  Worktree (/src/app__worktrees/login): staged changes, left out; untracked files",
+                "    (deleted with the worktree)",
+                "  Plan:",
+                "    - Merge 'login' into 'main'",
+                "    - Leave uncommitted changes out",
+                "    - Remove the worktree, window and branch",
+            ]
+        );
+    }
+}
@@ -1,55 +1,229 @@
+pub mod activity;
 pub mod add;
+pub mod agents;
+pub mod apply;
+pub mod apply_patch;
+pub mod archive;
 pub mod args;
 pub mod close;
+pub mod config;
 pub mod dashboard;
+pub mod diff;
 pub mod docs;
+pub mod doctor;
+pub mod edit;
+pub mod exec;
+pub mod export;
+pub mod gc;
+pub mod grep;
+pub mod history;
+pub mod hooks;
+pub mod import_work;
+pub mod layout;
 pub mod list;
+pub mod menu;
 pub mod merge;
 pub mod open;
 pub mod path;
+pub mod pin;
+pub mod prune;
 pub mod remove;
+pub mod report;
+pub mod reprompt;
+pub mod respond;
+pub mod review;
+pub mod selftest;
 pub mod set_window_status;
+pub mod split_commits;
+pub mod state;
+pub mod status;
+pub mod summarize;
+pub mod switch;
+pub mod sync;
+pub mod tidy;
+pub mod upgrade;
+pub mod version;
+pub mod which;
 
 use anyhow::{Context, Result, anyhow};
+use std::io::{self, Write};
+use std::path::Path;
+use tracing::warn;
 
-use crate::{config::Config, workflow::SetupOptions};
+use crate::config::{ByteSize, Config, ConfirmPolicy, WhenTarget, hooks_for};
+use crate::git::{self, WorktreeMatch};
+use crate::workflow::{self, SetupOptions};
+use crate::{dry_run, resume};
 
 /// Represents the different phases where hooks can be executed
 pub enum HookPhase {
+    PreCreate,
     PostCreate,
+    PreOpen,
     PreMerge,
     PreRemove,
 }
 
-/// Announce that hooks are about to run, if applicable.
+/// Announce that hooks are about to run, if applicable: if any of the
+/// phase's hooks has its `when` condition hold for `target`.
 /// Returns true if the announcement was printed (hooks will run).
-pub fn announce_hooks(config: &Config, options: Option<&SetupOptions>, phase: HookPhase) -> bool {
-    match phase {
-        HookPhase::PostCreate => {
-            let should_run = options.is_some_and(|opts| opts.run_hooks)
-                && config.post_create.as_ref().is_some_and(|v| !v.is_empty());
-
-            if should_run {
-                println!("Running setup commands...");
-            }
-            should_run
-        }
-        HookPhase::PreMerge => {
-            let should_run = config.pre_merge.as_ref().is_some_and(|v| !v.is_empty());
+pub fn announce_hooks(
+    config: &Config,
+    options: Option<&SetupOptions>,
+    phase: HookPhase,
+    target: &WhenTarget,
+) -> bool {
+    let run_hooks = options.is_some_and(|opts| opts.run_hooks);
+    let (hooks, enabled, message) = match phase {
+        HookPhase::PreCreate => (
+            &config.pre_create,
+            run_hooks,
+            "Running pre-create commands...",
+        ),
+        HookPhase::PostCreate => (&config.post_create, run_hooks, "Running setup commands..."),
+        HookPhase::PreOpen => (&config.pre_open, true, "Running pre-open commands..."),
+        HookPhase::PreMerge => (&config.pre_merge, true, "Running pre-merge commands..."),
+        HookPhase::PreRemove => (&config.pre_remove, true, "Running pre-remove commands..."),
+    };
+    let should_run = enabled && !hooks_for(hooks, target).is_empty();
+    if should_run {
+        println!("{}", message);
+    }
+    should_run
+}
 
-            if should_run {
-                println!("Running pre-merge commands...");
-            }
-            should_run
+/// After Ctrl-C stopped a multi-step command, print which steps finished and
+/// which did not, and save that so the command can pick up where it stopped.
+pub fn report_interrupted(completed: &[String], remaining: &[String], next_step: &str) {
+    eprintln!("\nInterrupted.");
+    if !completed.is_empty() {
+        eprintln!("Completed:");
+        for step in completed {
+            eprintln!("  ✓ {}", step);
+        }
+    }
+    if !remaining.is_empty() {
+        eprintln!("Not done:");
+        for step in remaining {
+            eprintln!("  - {}", step);
         }
-        HookPhase::PreRemove => {
-            let should_run = config.pre_remove.as_ref().is_some_and(|v| !v.is_empty());
+    }
+    if let Some(path) = resume::save(completed, remaining) {
+        eprintln!("Progress saved to {}", path.display());
+    }
+    eprintln!("{}", next_step);
+}
 
-            if should_run {
-                println!("Running pre-remove commands...");
-            }
-            should_run
+/// Before a worktree is removed, list the large untracked files that go with
+/// it, and ask first, following `policy`, if they add up to more than
+/// `size_guard.confirm_above`. Returns false if the user chose to keep the
+/// worktree.
+pub fn check_untracked_size(
+    config: &Config,
+    handle: &str,
+    worktree_path: &Path,
+    policy: ConfirmPolicy,
+) -> Result<bool> {
+    let size_guard = &config.size_guard;
+    if !size_guard.enabled() || !worktree_path.exists() {
+        return Ok(true);
+    }
+    let untracked = match workflow::measure_untracked(worktree_path, size_guard.min_size().0) {
+        Ok(untracked) => untracked,
+        Err(e) => {
+            warn!(error = %e, handle, "size_guard:failed to measure untracked files");
+            return Ok(true);
         }
+    };
+
+    let over_limit = size_guard
+        .confirm_above
+        .is_some_and(|limit| untracked.total > limit.0);
+    if untracked.large.is_empty() && !over_limit {
+        return Ok(true);
+    }
+
+    println!(
+        "Removing '{}' deletes {} of untracked files:",
+        handle,
+        ByteSize(untracked.total)
+    );
+    for (path, size) in &untracked.large {
+        println!("  {:>9}  {}", ByteSize(*size).to_string(), path.display());
+    }
+    if !over_limit {
+        return Ok(true);
+    }
+    confirm(
+        &format!(
+            "Delete {} of untracked files with '{}'?",
+            ByteSize(untracked.total),
+            handle
+        ),
+        policy,
+    )
+}
+
+/// Ask the user to confirm an action, following its configured policy.
+/// Returns true if the action should go ahead; prints "Aborted." otherwise.
+pub fn confirm(question: &str, policy: ConfirmPolicy) -> Result<bool> {
+    let hint = match policy {
+        ConfirmPolicy::Skip => return Ok(true),
+        ConfirmPolicy::Ask => "[y/N]",
+        ConfirmPolicy::AskDefaultYes => "[Y/n]",
+    };
+    // Nothing is changed in a dry run, so there is nothing to confirm
+    if dry_run::is_enabled() {
+        println!("{} [dry-run: assuming yes]", question);
+        return Ok(true);
+    }
+    print!("{} {} ", question, hint);
+    io::stdout().flush().context("Failed to flush stdout")?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read user input")?;
+
+    let accepted = is_accepted(&input, policy);
+    if !accepted {
+        println!("Aborted.");
+    }
+    Ok(accepted)
+}
+
+/// Ask for a line of input, returning it without surrounding whitespace.
+/// Closed stdin gives an empty answer.
+pub fn ask(question: &str) -> Result<String> {
+    print!("{} ", question);
+    io::stdout().flush().context("Failed to flush stdout")?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read user input")?;
+    Ok(input.trim().to_string())
+}
+
+/// Ask a yes/no question; an empty answer (including closed stdin) takes
+/// `default`
+pub fn ask_yes_no(question: &str, default: bool) -> Result<bool> {
+    let (hint, policy) = if default {
+        ("[Y/n]", ConfirmPolicy::AskDefaultYes)
+    } else {
+        ("[y/N]", ConfirmPolicy::Ask)
+    };
+    let answer = ask(&format!("{} {}", question, hint))?;
+    Ok(is_accepted(&answer, policy))
+}
+
+/// Interpret an answer to a confirmation prompt. An empty answer (including
+/// closed stdin) takes the policy's default.
+fn is_accepted(input: &str, policy: ConfirmPolicy) -> bool {
+    match input.trim().to_lowercase().as_str() {
+        "" => policy != ConfirmPolicy::Ask,
+        "y" | "yes" => true,
+        _ => false,
     }
 }
 
@@ -67,6 +241,32 @@ pub fn resolve_name(arg: Option<&str>) -> Result<String> {
     }
 }
 
+/// Resolve a name given on the command line using only the lookup `by` allows.
+///
+/// Returns the matched worktree's handle, pinned so the lookups done by the
+/// workflows find the same worktree. `WorktreeMatch::Any` returns the name
+/// unchanged.
+pub fn resolve_match(name: &str, by: WorktreeMatch) -> Result<String> {
+    let what = match by {
+        WorktreeMatch::Any => return Ok(name.to_string()),
+        WorktreeMatch::Handle => "directory name",
+        WorktreeMatch::Branch => "branch",
+    };
+    let (path, _) = git::find_worktree_by(name, by).map_err(|e| {
+        if e.is::<git::WorktreeNotFound>() {
+            anyhow!("No worktree found with {} '{}'", what, name)
+        } else {
+            e
+        }
+    })?;
+    let handle = path
+        .file_name()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?;
+    git::pin_worktree(&handle, &path);
+    Ok(handle)
+}
+
 /// Internal function to resolve worktree name from a path.
 /// Separated for testability.
 ///
@@ -107,6 +307,41 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn test_is_accepted_uses_policy_default_for_empty_answer() {
+        assert!(!is_accepted("\n", ConfirmPolicy::Ask));
+        assert!(is_accepted("\n", ConfirmPolicy::AskDefaultYes));
+        assert!(is_accepted("Y\n", ConfirmPolicy::Ask));
+        assert!(is_accepted("yes\n", ConfirmPolicy::Ask));
+        assert!(!is_accepted("n\n", ConfirmPolicy::AskDefaultYes));
+        assert!(!is_accepted("sure\n", ConfirmPolicy::AskDefaultYes));
+    }
+
+    #[test]
+    fn hooks_are_announced_only_if_one_applies() {
+        use crate::config::{Condition, HookCommand};
+        let config = Config {
+            pre_remove: Some(vec![HookCommand::WithOptions {
+                command: "docker compose down".to_string(),
+                timeout: None,
+                when: Some(Condition {
+                    branch: Some(vec!["feat/*".to_string()]),
+                    ..Condition::default()
+                }),
+            }]),
+            ..Config::default()
+        };
+        let target = |branch| WhenTarget {
+            branch,
+            agent: None,
+            worktree_path: Path::new("/nonexistent"),
+        };
+        let announce =
+            |branch| announce_hooks(&config, None, HookPhase::PreRemove, &target(branch));
+        assert!(announce("feat/login"));
+        assert!(!announce("fix/typo"));
+    }
+
     #[test]
     fn test_resolve_name_with_explicit_arg() {
         assert_eq!(resolve_name(Some("my-feature")).unwrap(), "my-feature");
@@ -1,25 +1,49 @@
-use crate::command::args::PromptArgs;
+use crate::command::args::{MatchArgs, PromptArgs};
+use crate::prompt::Prompt;
 use crate::workflow::prompt_loader::{PromptLoadArgs, load_prompt};
 use crate::workflow::{SetupOptions, WorkflowContext};
-use crate::{config, workflow};
-use anyhow::{Context, Result};
+use crate::worktree_state::{self, WorktreeState};
+use crate::{config, git, state_encryption, template, workflow};
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     name: &str,
     run_hooks: bool,
     force_files: bool,
     new_window: bool,
+    here: bool,
+    resume_prompt: bool,
     prompt_args: PromptArgs,
+    matching: MatchArgs,
 ) -> Result<()> {
-    let config = config::Config::load(None)?;
+    let name = &super::resolve_match(name, matching.mode())?;
+    let mut config = config::Config::load(None)?;
+    // A review worktree opens the way `add --pr --review` set it up
+    if git::find_worktree(name)
+        .ok()
+        .and_then(|(path, _)| Some(path.file_name()?.to_string_lossy().to_string()))
+        .is_some_and(|handle| worktree_state::is_review(&handle))
+    {
+        config.panes = Some(config.review_panes()?);
+    }
     let context = WorkflowContext::new(config)?;
 
+    if here {
+        return run_here(name, run_hooks, force_files, &context);
+    }
+
     // Load prompt if any prompt argument is provided
-    let prompt = load_prompt(&PromptLoadArgs {
-        prompt_editor: prompt_args.prompt_editor,
-        prompt_inline: prompt_args.prompt.as_deref(),
-        prompt_file: prompt_args.prompt_file.as_ref(),
-    })?;
+    let prompt = if resume_prompt {
+        Some(original_prompt(name, &context.config)?)
+    } else {
+        load_prompt(&PromptLoadArgs {
+            prompt_editor: prompt_args.prompt_editor,
+            prompt_inline: prompt_args.prompt.as_deref(),
+            prompt_file: prompt_args.prompt_file.as_ref(),
+        })?
+    };
 
     // Write prompt to temp file if provided
     // Use unique filename with timestamp to prevent race condition when opening multiple duplicates
@@ -43,11 +67,7 @@ pub fn run(
 
     // Only announce hooks if we're forcing a new window (otherwise we might just switch)
     if new_window {
-        super::announce_hooks(
-            &context.config,
-            Some(&options),
-            super::HookPhase::PostCreate,
-        );
+        announce_open_hooks(name, &options, &context)?;
     }
 
     let result = workflow::open(name, &context, options, new_window)
@@ -59,6 +79,12 @@ pub fn run(
             name,
             result.worktree_path.display()
         );
+        if resume_prompt {
+            println!(
+                "  The window was already open, so its agent wasn't given the prompt again \
+                 (use --new for another window)"
+            );
+        }
     } else {
         if result.post_create_hooks_run > 0 {
             println!("✓ Setup complete");
@@ -73,3 +99,134 @@ pub fn run(
 
     Ok(())
 }
+
+/// The prompt the worktree `name` was created with, for `--resume-prompt`:
+/// as it was (or as edited in the worktree, if saved there with
+/// `save_prompt`), or rendered into the `resume_prompt` template
+fn original_prompt(name: &str, config: &config::Config) -> Result<Prompt> {
+    let (path, branch) = git::find_worktree(name)?;
+    let handle = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    let state = worktree_state::load(&handle)?.unwrap_or_default();
+    let prompt = stored_prompt(&path, &handle, &state)?;
+
+    let Some(template) = &config.resume_prompt else {
+        return Ok(Prompt::Inline(prompt));
+    };
+    let context = serde_json::json!({
+        "prompt": prompt,
+        "branch": branch,
+        "handle": handle,
+        "task": state.task.unwrap_or_default(),
+    });
+    render_resume_prompt(template, &context).map(Prompt::Inline)
+}
+
+/// The prompt the worktree `handle` at `path` was created with, as it was or
+/// as edited in the worktree, if saved there with `save_prompt`
+pub(super) fn stored_prompt(path: &Path, handle: &str, state: &WorktreeState) -> Result<String> {
+    let saved = state
+        .prompt_file
+        .as_ref()
+        .and_then(|file| std::fs::read_to_string(path.join(file)).ok())
+        .filter(|prompt| !prompt.trim().is_empty());
+    if let Some(prompt) = saved {
+        return Ok(prompt);
+    }
+    let prompt = state
+        .prompt
+        .as_ref()
+        .filter(|prompt| !prompt.trim().is_empty())
+        .ok_or_else(|| anyhow!("No prompt is recorded for '{}'", handle))?;
+    state_encryption::decrypted(prompt)
+        .with_context(|| format!("The prompt of '{}' is encrypted", handle))
+}
+
+fn render_resume_prompt(template: &str, context: &serde_json::Value) -> Result<String> {
+    let env = template::create_template_env();
+    template::validate_template_variables(&env, template, context)
+        .context("Invalid resume_prompt")?;
+    env.render_str(template, context)
+        .context("Failed to render resume_prompt")
+}
+
+/// Announce the pre-open and post-create hooks that apply to the worktree
+/// `name`, as the worktree's template sets them up
+fn announce_open_hooks(
+    name: &str,
+    options: &SetupOptions,
+    context: &WorkflowContext,
+) -> Result<()> {
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    let target = config::WhenTarget {
+        branch: &branch,
+        agent: context.config.agent.as_deref(),
+        worktree_path: &worktree_path,
+    };
+    super::announce_hooks(
+        &context.config.for_branch(&branch),
+        Some(options),
+        super::HookPhase::PreOpen,
+        &target,
+    );
+    super::announce_hooks(
+        &context.config,
+        Some(options),
+        super::HookPhase::PostCreate,
+        &target,
+    );
+    Ok(())
+}
+
+fn run_here(
+    name: &str,
+    run_hooks: bool,
+    force_files: bool,
+    context: &WorkflowContext,
+) -> Result<()> {
+    // No panes are created, so there are no pane commands to run
+    let options = SetupOptions::new(run_hooks, force_files, false);
+    announce_open_hooks(name, &options, context)?;
+
+    let result = workflow::open_here(name, context, options)
+        .context("Failed to open worktree in the current window")?;
+
+    if result.post_create_hooks_run > 0 {
+        println!("✓ Setup complete");
+    }
+    println!(
+        "✓ Turned this window into the window for '{}'\n  Worktree: {}",
+        name,
+        result.worktree_path.display()
+    );
+    for (pane_id, command) in &result.skipped_panes {
+        println!(
+            "  Pane {} is running '{}', so it stays in its directory",
+            pane_id, command
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_resume_prompt;
+
+    #[test]
+    fn resume_prompt_wraps_the_original() {
+        let context = serde_json::json!({
+            "prompt": "Fix the login page",
+            "branch": "fix-login",
+            "handle": "fix-login",
+            "task": "Fix the login page",
+        });
+        let rendered =
+            render_resume_prompt("Continue on {{ branch }}:\n\n{{ prompt }}", &context).unwrap();
+        assert_eq!(rendered, "Continue on fix-login:\n\nFix the login page");
+        assert!(render_resume_prompt("{{ ticket }}", &context).is_err());
+    }
+}
@@ -1,14 +1,10 @@
+use crate::command::args::MatchArgs;
 use crate::git;
-use anyhow::{Context, Result};
+use anyhow::Result;
 
-pub fn run(name: &str) -> Result<()> {
-    // Smart resolution: try handle first, then branch name
-    let (path, _branch) = git::find_worktree(name).with_context(|| {
-        format!(
-            "No worktree found with name '{}'. Use 'workmux list' to see available worktrees.",
-            name
-        )
-    })?;
+pub fn run(name: &str, matching: MatchArgs) -> Result<()> {
+    // Smart resolution (handle first, then branch name) unless restricted by flags
+    let (path, _branch) = git::find_worktree_by(name, matching.mode())?;
     println!("{}", path.display());
     Ok(())
 }
@@ -0,0 +1,41 @@
+use crate::config::Config;
+use crate::{git, worktree_state};
+use anyhow::{Context, Result, anyhow};
+
+/// Reason `git worktree list` shows for worktrees pinned with workmux
+const LOCK_REASON: &str = "pinned with workmux";
+
+/// Pin a worktree: lock it in git and record it, so batch removals, syncs and
+/// `apply --prune` leave it alone, and `remove` and `merge` refuse to delete it
+pub fn run(name: Option<&str>) -> Result<()> {
+    let (handle, path, branch) = resolve(name)?;
+    git::lock_worktree(&path, LOCK_REASON)?;
+    worktree_state::set_pinned(&handle, &branch, true, &Config::load(None)?)
+        .context("Failed to record the worktree as pinned")?;
+    println!("✓ Pinned '{}'", handle);
+    Ok(())
+}
+
+/// Undo `pin`
+pub fn unpin(name: Option<&str>) -> Result<()> {
+    let (handle, path, branch) = resolve(name)?;
+    git::unlock_worktree(&path)?;
+    worktree_state::set_pinned(&handle, &branch, false, &Config::load(None)?)
+        .context("Failed to record the worktree as unpinned")?;
+    println!("✓ Unpinned '{}'", handle);
+    Ok(())
+}
+
+fn resolve(name: Option<&str>) -> Result<(String, std::path::PathBuf, String)> {
+    let name = super::resolve_name(name)?;
+    let (path, branch) = git::find_worktree(&name)?;
+    if path == git::get_main_worktree_root()? {
+        return Err(anyhow!("The main worktree can't be pinned or unpinned"));
+    }
+    let handle = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    Ok((handle, path, branch))
+}
@@ -0,0 +1,59 @@
+use crate::config;
+use crate::workflow::{self, WorkflowContext};
+use anyhow::{Context, Result};
+
+pub fn run(dry_run: bool) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+
+    let plan = workflow::plan_prune(&context).context("Failed to look for orphaned resources")?;
+    if let Some(reason) = &plan.directories_unchecked {
+        eprintln!("Skipping leftover directories: {}", reason);
+    }
+    if plan.is_empty() {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+
+    println!("Orphaned resources:");
+    for window in &plan.windows {
+        println!(
+            "  - {}{} (close window: its worktree is gone)",
+            context.prefix, window.handle
+        );
+    }
+    for (path, reason) in &plan.stale_worktrees {
+        println!(
+            "  - {} (prune worktree metadata: {})",
+            path.display(),
+            reason
+        );
+    }
+    for directory in &plan.directories {
+        match &directory.skip_reason {
+            Some(reason) => println!("  ! {} (kept: {})", directory.path.display(), reason),
+            None => println!("  - {} (delete directory)", directory.path.display()),
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let removals = plan.windows.len()
+        + plan.stale_worktrees.len()
+        + plan
+            .directories
+            .iter()
+            .filter(|d| d.skip_reason.is_none())
+            .count();
+    if removals == 0 {
+        return Ok(());
+    }
+    if !super::confirm("Prune these?", context.config.confirm.clean())? {
+        return Ok(());
+    }
+    workflow::apply_prune(&plan)?;
+    println!("✓ Pruned {} orphaned resource(s)", removals);
+
+    Ok(())
+}
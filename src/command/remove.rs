@@ -1,44 +1,66 @@
+use crate::command::args::MatchArgs;
+use crate::config::{ConfirmConfig, ConfirmPolicy};
 use crate::workflow::WorkflowContext;
-use crate::{config, git, spinner, workflow};
+use crate::{config, dry_run, git, interrupt, offline, spinner, workflow, worktree_state};
 use anyhow::{Context, Result, anyhow};
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     names: Vec<String>,
     gone: bool,
+    prs: bool,
     all: bool,
+    filter: BatchFilter,
     force: bool,
     keep_branch: bool,
+    no_fetch: bool,
+    matching: MatchArgs,
 ) -> Result<()> {
-    if all {
-        return run_all(force, keep_branch);
+    let config = config::Config::load(None)?;
+    let remote = config.default_remote();
+
+    if all || !filter.is_empty() {
+        return run_batch(&filter, force, keep_branch, remote, &config.confirm);
+    }
+
+    let fetch =
+        !no_fetch && !offline::is_enabled() && config.should_fetch(config::FetchTrigger::Merge);
+    if prs {
+        return run_prs(force, keep_branch, fetch, &config);
     }
 
     if gone {
-        return run_gone(force, keep_branch);
+        return run_gone(force, keep_branch, remote, fetch, &config.confirm);
     }
 
-    run_specified(names, force, keep_branch)
+    run_specified(names, force, keep_branch, remote, &config.confirm, matching)
 }
 
 /// Remove specific worktrees provided by user (or current if empty)
-fn run_specified(names: Vec<String>, force: bool, keep_branch: bool) -> Result<()> {
+fn run_specified(
+    names: Vec<String>,
+    force: bool,
+    keep_branch: bool,
+    remote: &str,
+    confirm: &ConfirmConfig,
+    matching: MatchArgs,
+) -> Result<()> {
     // Normalize all inputs (handles "." and other special cases)
     let resolved_names: Vec<String> = if names.is_empty() {
         vec![super::resolve_name(None)?]
     } else {
         names
             .iter()
-            .map(|n| super::resolve_name(Some(n)))
+            .map(|n| super::resolve_match(n, matching.mode()))
             .collect::<Result<Vec<_>>>()?
     };
 
     // 2. Resolve all targets and validate they exist
     let mut candidates: Vec<(String, PathBuf, String)> = Vec::new();
     for name in resolved_names {
-        let (worktree_path, branch_name) = git::find_worktree(&name)
-            .with_context(|| format!("No worktree found with name '{}'", name))?;
+        let (worktree_path, branch_name) = git::find_worktree(&name)?;
 
         let handle = worktree_path
             .file_name()
@@ -54,12 +76,33 @@ fn run_specified(names: Vec<String>, force: bool, keep_branch: bool) -> Result<(
         candidates.push((handle, worktree_path, branch_name));
     }
 
+    // Pinned worktrees are kept even with --force
+    let pinned: Vec<&str> = candidates
+        .iter()
+        .map(|(handle, _, _)| handle.as_str())
+        .filter(|handle| worktree_state::is_pinned(handle))
+        .collect();
+    if !pinned.is_empty() {
+        return Err(anyhow!(
+            "Cannot remove pinned worktrees: {}. Run 'workmux unpin' on them first.",
+            pinned.join(", ")
+        ));
+    }
+
     // 3. If forced, skip all checks and remove
     if force {
+        let question = format!(
+            "Force-remove {} worktree(s), discarding uncommitted changes and unmerged commits?",
+            candidates.len()
+        );
+        if !super::confirm(&question, confirm.force())? {
+            return Ok(());
+        }
+
         let mut failed: Vec<(String, String)> = Vec::new();
 
-        for (handle, _, _) in candidates {
-            if let Err(e) = remove_worktree(&handle, true, keep_branch) {
+        for (handle, path, _) in candidates {
+            if let Err(e) = remove_worktree(&handle, &path, true, keep_branch, confirm.force()) {
                 failed.push((handle, e.to_string()));
             }
         }
@@ -76,27 +119,58 @@ fn run_specified(names: Vec<String>, force: bool, keep_branch: bool) -> Result<(
     }
 
     // 4. Safety checks: categorize candidates
+    let mut in_progress: Vec<(String, git::InProgressOperation)> = Vec::new();
     let mut uncommitted: Vec<String> = Vec::new();
-    let mut unmerged: Vec<(String, String, String)> = Vec::new(); // (handle, branch, base)
-    let mut safe: Vec<String> = Vec::new();
+    let mut detached_commits: Vec<(String, usize)> = Vec::new();
+    let mut unmerged: Vec<(String, PathBuf, String, String)> = Vec::new(); // (handle, path, branch, base)
+    let mut safe: Vec<(String, PathBuf)> = Vec::new();
 
     for (handle, path, branch) in candidates {
+        // Check for an unfinished rebase, merge, etc. (blocking)
+        if let Some(operation) = git::in_progress_operation(&path) {
+            in_progress.push((handle, operation));
+            continue;
+        }
+
         // Check uncommitted (blocking)
         if path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
             uncommitted.push(handle);
             continue;
         }
 
+        // Check commits made on a detached HEAD, which no branch keeps (blocking)
+        if branch == "(detached)" {
+            match git::unreferenced_commits(&path) {
+                Ok(0) | Err(_) => safe.push((handle, path)),
+                Ok(count) => detached_commits.push((handle, count)),
+            }
+            continue;
+        }
+
         // Check unmerged (promptable), only if we're deleting the branch
-        if !keep_branch && let Some(base) = is_unmerged(&branch)? {
-            unmerged.push((handle, branch, base));
+        if !keep_branch && let Some(base) = is_unmerged(&handle, &branch, remote)? {
+            unmerged.push((handle, path, branch, base));
             continue;
         }
 
-        safe.push(handle);
+        safe.push((handle, path));
     }
 
-    // 5. Handle blocking issues (uncommitted changes)
+    // 5. Handle blocking issues (unfinished operations, uncommitted changes)
+    if !in_progress.is_empty() {
+        eprintln!("The following worktrees are in the middle of a git operation:");
+        for (handle, operation) in &in_progress {
+            eprintln!(
+                "  - {} ({}; '{}' abandons it)",
+                handle,
+                operation.state(),
+                operation.abort_command()
+            );
+        }
+        return Err(anyhow!(
+            "Cannot remove worktrees with an unfinished git operation. Use --force to override."
+        ));
+    }
     if !uncommitted.is_empty() {
         eprintln!("The following worktrees have uncommitted changes:");
         for handle in &uncommitted {
@@ -106,55 +180,57 @@ fn run_specified(names: Vec<String>, force: bool, keep_branch: bool) -> Result<(
             "Cannot remove worktrees with uncommitted changes. Use --force to override."
         ));
     }
+    if !detached_commits.is_empty() {
+        eprintln!("The following worktrees have commits on no branch:");
+        for (handle, count) in &detached_commits {
+            eprintln!(
+                "  - {} ({} commit(s); 'git switch -c <name>' there keeps them)",
+                handle, count
+            );
+        }
+        return Err(anyhow!(
+            "Cannot remove worktrees whose commits would be lost. Use --force to override."
+        ));
+    }
 
     // 6. Handle warnings (unmerged branches)
     if !unmerged.is_empty() {
         println!("The following branches have commits not merged into their base:");
-        for (_, branch, base) in &unmerged {
+        for (_, _, branch, base) in &unmerged {
             println!("  - {} (base: {})", branch, base);
         }
         println!("\nThis will delete the worktree, tmux window, and local branch.");
-        print!("Are you sure you want to continue? [y/N] ");
-        io::stdout().flush().context("Failed to flush stdout")?;
-
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .context("Failed to read input")?;
-
-        if input.trim().to_lowercase() != "y" {
-            println!("Aborted.");
+        if !super::confirm("Are you sure you want to continue?", confirm.remove())? {
             return Ok(());
         }
 
         // Add unmerged candidates to safe list for processing
-        for (handle, _, _) in unmerged {
-            safe.push(handle);
+        for (handle, path, _, _) in unmerged {
+            safe.push((handle, path));
         }
     }
 
     // 7. Execute removal
-    for handle in safe {
+    for (handle, path) in safe {
         // force=true because we already checked/prompted
-        remove_worktree(&handle, true, keep_branch)?;
+        remove_worktree(&handle, &path, true, keep_branch, confirm.remove())?;
     }
 
     Ok(())
 }
 
-/// Check if a branch has unmerged commits. Returns Some(base) if unmerged, None otherwise.
-fn is_unmerged(branch: &str) -> Result<Option<String>> {
-    let main_branch = git::get_default_branch().unwrap_or_else(|_| "main".to_string());
+/// Check if the branch of worktree `handle` has unmerged commits. Returns
+/// Some(base) if unmerged, None otherwise.
+pub fn is_unmerged(handle: &str, branch: &str, remote: &str) -> Result<Option<String>> {
+    let main_branch = git::get_default_branch(remote).unwrap_or_else(|_| "main".to_string());
 
-    let base = git::get_branch_base(branch)
-        .ok()
-        .unwrap_or_else(|| main_branch.clone());
+    let base = worktree_state::base_branch(handle, branch).unwrap_or_else(|| main_branch.clone());
 
-    let base_commit = match git::get_merge_base(&base) {
+    let base_commit = match git::get_merge_base(&base, remote) {
         Ok(b) => b,
         Err(_) => {
             // If we can't determine base, try falling back to main
-            match git::get_merge_base(&main_branch) {
+            match git::get_merge_base(&main_branch, remote) {
                 Ok(b) => b,
                 Err(_) => return Ok(None), // Can't determine, assume safe
             }
@@ -169,15 +245,39 @@ fn is_unmerged(branch: &str) -> Result<Option<String>> {
     }
 }
 
-/// Remove all managed worktrees (except main)
-fn run_all(force: bool, keep_branch: bool) -> Result<()> {
+/// Which worktrees `--all`, `--merged` and `--older-than` remove. With no
+/// filter set, all of them are.
+#[derive(Debug, Default)]
+pub struct BatchFilter {
+    /// Only worktrees whose branch has no commits missing from its base
+    pub merged: bool,
+    /// Only worktrees created longer ago than this
+    pub older_than: Option<Duration>,
+}
+
+impl BatchFilter {
+    fn is_empty(&self) -> bool {
+        !self.merged && self.older_than.is_none()
+    }
+}
+
+/// Remove all managed worktrees (except main) that pass `filter`
+fn run_batch(
+    filter: &BatchFilter,
+    force: bool,
+    keep_branch: bool,
+    remote: &str,
+    confirm: &ConfirmConfig,
+) -> Result<()> {
     let worktrees = git::list_worktrees()?;
-    let main_branch = git::get_default_branch()?;
+    let main_branch = git::get_default_branch(remote)?;
     let main_worktree_root = git::get_main_worktree_root()?;
 
     let mut to_remove: Vec<(PathBuf, String, String)> = Vec::new();
+    let mut ages: Vec<Option<Duration>> = Vec::new();
     let mut skipped_uncommitted: Vec<String> = Vec::new();
     let mut skipped_unmerged: Vec<String> = Vec::new();
+    let mut skipped_pinned: Vec<String> = Vec::new();
 
     for (path, branch) in worktrees {
         // Skip main branch/worktree and detached HEAD
@@ -190,37 +290,61 @@ fn run_all(force: bool, keep_branch: bool) -> Result<()> {
             continue;
         }
 
-        // Check for uncommitted changes
+        let handle = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&branch)
+            .to_string();
+
+        // Worktrees of unknown age are never old enough
+        let age = worktree_age(&handle, &path);
+        if let Some(min_age) = filter.older_than
+            && age.is_none_or(|age| age < min_age)
+        {
+            continue;
+        }
+
+        // None if the merge base can't be determined
+        let unmerged = {
+            let base = worktree_state::base_branch(&handle, &branch)
+                .unwrap_or_else(|| main_branch.clone());
+            git::get_merge_base(&base, remote)
+                .and_then(|merge_base| git::get_unmerged_branches(&merge_base))
+                .ok()
+                .map(|unmerged_branches| unmerged_branches.contains(&branch))
+        };
+        if filter.merged && unmerged != Some(false) {
+            continue;
+        }
+
+        if worktree_state::is_pinned(&handle) {
+            skipped_pinned.push(branch);
+            continue;
+        }
+
+        // Check for uncommitted changes or an unfinished rebase, merge, etc.
+        if !force && let Some(operation) = git::in_progress_operation(&path) {
+            skipped_uncommitted.push(format!("{} ({})", branch, operation.state()));
+            continue;
+        }
         if !force && path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
             skipped_uncommitted.push(branch);
             continue;
         }
 
         // Check for unmerged commits (only when deleting the branch)
-        if !force && !keep_branch {
-            let base = git::get_branch_base(&branch)
-                .ok()
-                .unwrap_or_else(|| main_branch.clone());
-            if let Ok(merge_base) = git::get_merge_base(&base)
-                && let Ok(unmerged_branches) = git::get_unmerged_branches(&merge_base)
-                && unmerged_branches.contains(&branch)
-            {
-                skipped_unmerged.push(branch);
-                continue;
-            }
+        if !force && !keep_branch && unmerged == Some(true) {
+            skipped_unmerged.push(branch);
+            continue;
         }
 
-        let handle = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(&branch)
-            .to_string();
-
         to_remove.push((path, branch, handle));
+        ages.push(age);
     }
 
     if to_remove.is_empty() && skipped_uncommitted.is_empty() && skipped_unmerged.is_empty() {
         println!("No worktrees to remove.");
+        print_skipped_pinned(&skipped_pinned);
         return Ok(());
     }
 
@@ -228,7 +352,7 @@ fn run_all(force: bool, keep_branch: bool) -> Result<()> {
         println!("No removable worktrees found.");
         if !skipped_uncommitted.is_empty() {
             println!(
-                "\nSkipped {} worktree(s) with uncommitted changes:",
+                "\nSkipped {} worktree(s) with uncommitted changes or unfinished operations:",
                 skipped_uncommitted.len()
             );
             for branch in &skipped_uncommitted {
@@ -245,18 +369,26 @@ fn run_all(force: bool, keep_branch: bool) -> Result<()> {
             }
         }
         println!("\nUse --force to remove these anyway.");
+        print_skipped_pinned(&skipped_pinned);
         return Ok(());
     }
 
     // Show what will be removed
-    println!("The following worktrees will be removed:");
-    for (_, branch, _) in &to_remove {
-        println!("  - {}", branch);
+    if filter.merged {
+        println!("The following merged worktrees will be removed:");
+    } else {
+        println!("The following worktrees will be removed:");
+    }
+    for ((_, branch, _), age) in to_remove.iter().zip(&ages) {
+        match age {
+            Some(age) => println!("  - {} (created {} ago)", branch, format_age(*age)),
+            None => println!("  - {}", branch),
+        }
     }
 
     if !skipped_uncommitted.is_empty() {
         println!(
-            "\nSkipping {} worktree(s) with uncommitted changes:",
+            "\nSkipping {} worktree(s) with uncommitted changes or unfinished operations:",
             skipped_uncommitted.len()
         );
         for branch in &skipped_uncommitted {
@@ -273,59 +405,116 @@ fn run_all(force: bool, keep_branch: bool) -> Result<()> {
             println!("  - {}", branch);
         }
     }
+    print_skipped_pinned(&skipped_pinned);
 
-    // Confirm with user unless --force
-    if !force {
-        print!(
-            "\nAre you sure you want to remove ALL {} worktree(s)? [y/N] ",
+    let question = if filter.is_empty() {
+        format!(
+            "\nAre you sure you want to remove ALL {} worktree(s)?",
             to_remove.len()
-        );
-        io::stdout().flush().context("Failed to flush stdout")?;
-
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .context("Failed to read user input")?;
-
-        if input.trim().to_lowercase() != "y" {
-            println!("Aborted.");
-            return Ok(());
-        }
+        )
+    } else {
+        format!(
+            "\nAre you sure you want to remove {} worktree(s)?",
+            to_remove.len()
+        )
+    };
+    let policy = confirm.removal(confirm.clean(), force);
+    if !super::confirm(&question, policy)? {
+        return Ok(());
     }
 
-    // Execute removal
-    let mut success_count = 0;
-    let mut failed: Vec<(String, String)> = Vec::new();
-
-    for (_, branch, handle) in to_remove {
-        match remove_worktree(&handle, true, keep_branch) {
-            Ok(()) => success_count += 1,
-            Err(e) => failed.push((branch, e.to_string())),
-        }
-    }
+    remove_batch(to_remove, keep_branch, policy)
+}
 
-    // Report results
-    if success_count > 0 {
-        println!("\n✓ Successfully removed {} worktree(s)", success_count);
-    }
+/// How long ago the worktree was created: the recorded creation time, else
+/// when git wrote the worktree's `.git` file
+fn worktree_age(handle: &str, path: &Path) -> Option<Duration> {
+    let created_at = worktree_state::load(handle)
+        .ok()
+        .flatten()
+        .and_then(|state| state.created_at);
+    let created = match created_at {
+        Some(created_at) => UNIX_EPOCH + Duration::from_secs(created_at),
+        None => std::fs::metadata(path.join(".git"))
+            .and_then(|m| m.modified())
+            .ok()?,
+    };
+    SystemTime::now().duration_since(created).ok()
+}
 
-    if !failed.is_empty() {
-        eprintln!("\nFailed to remove {} worktree(s):", failed.len());
-        for (branch, error) in &failed {
-            eprintln!("  - {}: {}", branch, error);
+/// Parse a `--older-than` duration: a number followed by `m`, `h`, `d` or `w`
+pub fn parse_age(input: &str) -> Result<Duration, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number.parse().map_err(|_| {
+        format!(
+            "expected a number followed by m, h, d or w, got '{}'",
+            input
+        )
+    })?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => {
+            return Err(format!(
+                "unknown unit in '{}', expected m, h, d or w (e.g. 7d)",
+                input
+            ));
         }
-    }
+    };
+    number
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("'{}' is too long an age", input))
+}
 
-    Ok(())
+/// Largest whole unit of `age`, e.g. "3d" or "5h"
+pub(super) fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        s if s >= 86_400 => format!("{}d", s / 86_400),
+        s if s >= 3600 => format!("{}h", s / 3600),
+        s => format!("{}m", s / 60),
+    }
 }
 
 /// Remove worktrees whose upstream remote branch has been deleted
-fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
-    // Fetch with prune to update remote-tracking refs
-    spinner::with_spinner("Fetching from remote", git::fetch_prune)?;
-
+fn run_gone(
+    force: bool,
+    keep_branch: bool,
+    remote: &str,
+    fetch: bool,
+    confirm: &ConfirmConfig,
+) -> Result<()> {
     let worktrees = git::list_worktrees()?;
-    let main_branch = git::get_default_branch()?;
+
+    // Fetch with prune to update remote-tracking refs. Branches may track other
+    // remotes than the default one (e.g. a fork), so fetch each of those too.
+    if fetch {
+        let mut remotes = vec![remote.to_string()];
+        for (_, branch) in &worktrees {
+            if let Some(branch_remote) = git::get_branch_remote(branch)
+                && !remotes.contains(&branch_remote)
+            {
+                remotes.push(branch_remote);
+            }
+        }
+        for remote in &remotes {
+            spinner::with_spinner(&format!("Fetching from '{}'", remote), || {
+                git::fetch_prune(remote)
+            })?;
+        }
+    } else if offline::is_enabled() {
+        println!("Offline: not fetching; using existing remote-tracking refs.");
+    } else {
+        println!("Skipping fetch; using existing remote-tracking refs.");
+    }
+
+    let main_branch = git::get_default_branch(remote)?;
     let main_worktree_root = git::get_main_worktree_root()?;
 
     let gone_branches = git::get_gone_branches().unwrap_or_default();
@@ -333,6 +522,7 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
     // Find worktrees whose upstream is gone
     let mut to_remove: Vec<(PathBuf, String, String)> = Vec::new();
     let mut skipped_uncommitted: Vec<String> = Vec::new();
+    let mut skipped_pinned: Vec<String> = Vec::new();
 
     for (path, branch) in worktrees {
         // Skip main branch/worktree and detached HEAD
@@ -350,23 +540,33 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
             continue;
         }
 
-        // Check for uncommitted changes
-        if !force && path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
-            skipped_uncommitted.push(branch);
-            continue;
-        }
-
         let handle = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or(&branch)
             .to_string();
 
+        if worktree_state::is_pinned(&handle) {
+            skipped_pinned.push(branch);
+            continue;
+        }
+
+        // Check for uncommitted changes or an unfinished rebase, merge, etc.
+        if !force && let Some(operation) = git::in_progress_operation(&path) {
+            skipped_uncommitted.push(format!("{} ({})", branch, operation.state()));
+            continue;
+        }
+        if !force && path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
+            skipped_uncommitted.push(branch);
+            continue;
+        }
+
         to_remove.push((path, branch, handle));
     }
 
     if to_remove.is_empty() && skipped_uncommitted.is_empty() {
         println!("No worktrees with gone upstreams found.");
+        print_skipped_pinned(&skipped_pinned);
         return Ok(());
     }
 
@@ -374,7 +574,7 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
         println!("No worktrees to remove.");
         if !skipped_uncommitted.is_empty() {
             println!(
-                "\nSkipped {} worktree(s) with uncommitted changes:",
+                "\nSkipped {} worktree(s) with uncommitted changes or unfinished operations:",
                 skipped_uncommitted.len()
             );
             for branch in &skipped_uncommitted {
@@ -382,6 +582,7 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
             }
             println!("\nUse --force to remove these anyway.");
         }
+        print_skipped_pinned(&skipped_pinned);
         return Ok(());
     }
 
@@ -390,10 +591,11 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
     for (_, branch, _) in &to_remove {
         println!("  - {}", branch);
     }
+    print_skipped_pinned(&skipped_pinned);
 
     if !skipped_uncommitted.is_empty() {
         println!(
-            "\nSkipping {} worktree(s) with uncommitted changes:",
+            "\nSkipping {} worktree(s) with uncommitted changes or unfinished operations:",
             skipped_uncommitted.len()
         );
         for branch in &skipped_uncommitted {
@@ -401,39 +603,246 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
         }
     }
 
-    // Confirm with user unless --force
-    if !force {
-        print!(
-            "\nAre you sure you want to remove {} worktree(s)? [y/N] ",
-            to_remove.len()
-        );
-        io::stdout().flush().context("Failed to flush stdout")?;
+    let question = format!(
+        "\nAre you sure you want to remove {} worktree(s)?",
+        to_remove.len()
+    );
+    let policy = confirm.removal(confirm.clean(), force);
+    if !super::confirm(&question, policy)? {
+        return Ok(());
+    }
 
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .context("Failed to read user input")?;
+    remove_batch(to_remove, keep_branch, policy)
+}
 
-        if input.trim().to_lowercase() != "y" {
-            println!("Aborted.");
-            return Ok(());
+/// Remove worktrees checked out with `add --pr` whose pull or merge request
+/// was merged or closed. The branches of closed ones are kept out unless
+/// `force` or `keep_branch` if they have commits their base doesn't.
+fn run_prs(force: bool, keep_branch: bool, fetch: bool, config: &config::Config) -> Result<()> {
+    if offline::is_enabled() {
+        return Err(anyhow!("Cannot check pull request states in offline mode"));
+    }
+    let remote = config.default_remote();
+    let provider = workflow::pr::provider(config.pr.as_ref(), remote);
+    let main_worktree_root = git::get_main_worktree_root()?;
+
+    let mut to_remove: Vec<(PathBuf, String, String)> = Vec::new();
+    let mut reasons: Vec<String> = Vec::new();
+    let mut skipped_uncommitted: Vec<String> = Vec::new();
+    let mut skipped_unmerged: Vec<String> = Vec::new();
+    let mut unchecked: Vec<(String, String)> = Vec::new();
+    let mut skipped_pinned: Vec<String> = Vec::new();
+    let mut fetched = !fetch;
+
+    for (path, branch) in git::list_worktrees()? {
+        if path == main_worktree_root {
+            continue;
+        }
+        let handle = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&branch)
+            .to_string();
+        let Some(state) = worktree_state::load(&handle).ok().flatten() else {
+            continue;
+        };
+        let Some(number) = state.pr else {
+            continue;
+        };
+        if state.pinned {
+            skipped_pinned.push(branch);
+            continue;
+        }
+
+        let label = provider.label(number);
+        let state = spinner::with_spinner(&format!("Checking {}", label), || {
+            workflow::pr::change_state(provider, number)
+        });
+        let state = match state {
+            Ok(workflow::pr::ChangeState::Open) => continue,
+            Ok(state) => state,
+            Err(e) => {
+                unchecked.push((branch, format!("{:#}", e)));
+                continue;
+            }
+        };
+
+        // Check for uncommitted changes or an unfinished rebase, merge, etc.
+        if !force && let Some(operation) = git::in_progress_operation(&path) {
+            skipped_uncommitted.push(format!("{} ({})", branch, operation.state()));
+            continue;
+        }
+        if !force && path.exists() && git::has_uncommitted_changes(&path).unwrap_or(false) {
+            skipped_uncommitted.push(branch);
+            continue;
         }
+
+        // Commits made on a review worktree's detached HEAD are on no branch
+        if !force
+            && branch == "(detached)"
+            && let Ok(count @ 1..) = git::unreferenced_commits(&path)
+        {
+            skipped_uncommitted.push(format!("{} ({} commit(s) on no branch)", handle, count));
+            continue;
+        }
+
+        // A closed PR's commits may be nowhere else; a merged one's are in
+        // its base, even when squashed
+        if state == workflow::pr::ChangeState::Closed
+            && !force
+            && !keep_branch
+            && branch != "(detached)"
+        {
+            if !fetched {
+                spinner::with_spinner(&format!("Fetching from '{}'", remote), || {
+                    git::fetch_prune(remote)
+                })?;
+                fetched = true;
+            }
+            if let Some(base) = is_unmerged(&handle, &branch, remote)? {
+                skipped_unmerged.push(format!("{} (not merged into '{}')", branch, base));
+                continue;
+            }
+        }
+
+        reasons.push(format!("{} ({} {})", branch, label, state.label()));
+        to_remove.push((path, branch, handle));
     }
 
-    // Execute removal
-    let mut success_count = 0;
+    if !unchecked.is_empty() {
+        eprintln!("Could not check {} worktree(s):", unchecked.len());
+        for (branch, error) in &unchecked {
+            eprintln!("  - {}: {}", branch, error);
+        }
+    }
+
+    if to_remove.is_empty() {
+        if skipped_uncommitted.is_empty() && skipped_unmerged.is_empty() {
+            println!("No worktrees with merged or closed pull requests found.");
+            print_skipped_pinned(&skipped_pinned);
+        } else if skipped_uncommitted.is_empty() {
+            print_skipped_unmerged(&skipped_unmerged);
+            print_skipped_pinned(&skipped_pinned);
+        } else {
+            println!(
+                "Skipped {} worktree(s) with uncommitted changes, unfinished operations or commits on no branch:",
+                skipped_uncommitted.len()
+            );
+            for branch in &skipped_uncommitted {
+                println!("  - {}", branch);
+            }
+            println!("\nUse --force to remove these anyway.");
+            print_skipped_unmerged(&skipped_unmerged);
+            print_skipped_pinned(&skipped_pinned);
+        }
+        return Ok(());
+    }
+
+    // Show what will be removed
+    println!("The following worktrees have merged or closed pull requests and will be removed:");
+    for reason in &reasons {
+        println!("  - {}", reason);
+    }
+    print_skipped_pinned(&skipped_pinned);
+
+    if !skipped_uncommitted.is_empty() {
+        println!(
+            "\nSkipping {} worktree(s) with uncommitted changes, unfinished operations or commits on no branch:",
+            skipped_uncommitted.len()
+        );
+        for branch in &skipped_uncommitted {
+            println!("  - {}", branch);
+        }
+    }
+    print_skipped_unmerged(&skipped_unmerged);
+
+    let question = format!(
+        "\nAre you sure you want to remove {} worktree(s)?",
+        to_remove.len()
+    );
+    let policy = config.confirm.removal(config.confirm.clean(), force);
+    if !super::confirm(&question, policy)? {
+        return Ok(());
+    }
+
+    remove_batch(to_remove, keep_branch, policy)
+}
+
+/// List the worktrees of closed pull requests `--prs` left out because their
+/// branch would lose commits
+fn print_skipped_unmerged(branches: &[String]) {
+    if branches.is_empty() {
+        return;
+    }
+    println!(
+        "\nSkipping {} worktree(s) of closed pull requests with unmerged commits:",
+        branches.len()
+    );
+    for branch in branches {
+        println!("  - {}", branch);
+    }
+    println!("Use --keep-branch to remove them but keep their branch, or --force to delete both.");
+}
+
+/// List the worktrees a batch removal left out because they are pinned
+fn print_skipped_pinned(branches: &[String]) {
+    if branches.is_empty() {
+        return;
+    }
+    println!(
+        "\nSkipping {} pinned worktree(s) ('workmux unpin' to include them):",
+        branches.len()
+    );
+    for branch in branches {
+        println!("  - {}", branch);
+    }
+}
+
+/// Remove worktrees one by one, stopping between two of them on Ctrl-C.
+/// `policy` is the batch's, for the size of each one's untracked files.
+fn remove_batch(
+    to_remove: Vec<(PathBuf, String, String)>,
+    keep_branch: bool,
+    policy: ConfirmPolicy,
+) -> Result<()> {
+    let guard = interrupt::Guard::new()?;
+    let mut removed: Vec<String> = Vec::new();
+    let mut kept: Vec<String> = Vec::new();
     let mut failed: Vec<(String, String)> = Vec::new();
+    let mut stopped_at = None;
 
-    for (_, branch, handle) in to_remove {
-        match remove_worktree(&handle, true, keep_branch) {
-            Ok(()) => success_count += 1,
-            Err(e) => failed.push((branch, e.to_string())),
+    for (i, (path, branch, handle)) in to_remove.iter().enumerate() {
+        if interrupt::is_interrupted() {
+            stopped_at = Some(i);
+            break;
+        }
+        match remove_worktree(handle, path, true, keep_branch, policy) {
+            Ok(true) => removed.push(branch.clone()),
+            Ok(false) => kept.push(branch.clone()),
+            Err(e) if interrupt::is_interrupted() => {
+                eprintln!("{:#}", e);
+                stopped_at = Some(i);
+                break;
+            }
+            Err(e) => failed.push((branch.clone(), e.to_string())),
         }
     }
+    drop(guard);
 
     // Report results
-    if success_count > 0 {
-        println!("\n✓ Successfully removed {} worktree(s)", success_count);
+    if !removed.is_empty() {
+        println!(
+            "\n{} {} worktree(s)",
+            dry_run::outcome("✓ Successfully removed", "[dry-run] Would remove"),
+            removed.len()
+        );
+    }
+
+    if !kept.is_empty() {
+        println!("\nKept {} worktree(s):", kept.len());
+        for branch in &kept {
+            println!("  - {}", branch);
+        }
     }
 
     if !failed.is_empty() {
@@ -443,30 +852,104 @@ fn run_gone(force: bool, keep_branch: bool) -> Result<()> {
         }
     }
 
+    if let Some(i) = stopped_at {
+        let remaining: Vec<String> = to_remove[i..]
+            .iter()
+            .map(|(_, branch, _)| branch.clone())
+            .collect();
+        super::report_interrupted(
+            &removed,
+            &remaining,
+            "Run the same command again to remove the rest.",
+        );
+        return Err(interrupt::Interrupted.into());
+    }
+
     Ok(())
 }
 
-/// Execute the actual worktree removal
-fn remove_worktree(handle: &str, force: bool, keep_branch: bool) -> Result<()> {
+/// Remove the worktree `handle` at `worktree_path`. Returns false if the user
+/// chose to keep it after seeing the size of its untracked files, asked
+/// following `size_policy`.
+pub fn remove_worktree(
+    handle: &str,
+    worktree_path: &Path,
+    force: bool,
+    keep_branch: bool,
+    size_policy: ConfirmPolicy,
+) -> Result<bool> {
     let config = config::Config::load(None)?;
     let context = WorkflowContext::new(config)?;
 
-    super::announce_hooks(&context.config, None, super::HookPhase::PreRemove);
+    // The handle may also be the branch of another worktree, which would
+    // make looking it up again ambiguous
+    git::pin_worktree(handle, worktree_path);
+    let worktree = git::find_worktree(handle).ok();
+    if let Some((worktree_path, _)) = &worktree
+        && !super::check_untracked_size(&context.config, handle, worktree_path, size_policy)?
+    {
+        return Ok(false);
+    }
+
+    // The worktree's template may bring its own pre-remove hooks
+    let hook_config = match &worktree {
+        Some((_, branch)) => context.config.for_branch(branch),
+        None => context.config.clone(),
+    };
+    // Pre-remove hooks run in the worktree, so only if it is still there
+    if let Some((worktree_path, branch)) = &worktree
+        && worktree_path.exists()
+    {
+        let target = config::WhenTarget {
+            branch,
+            agent: context.config.agent.as_deref(),
+            worktree_path,
+        };
+        super::announce_hooks(&hook_config, None, super::HookPhase::PreRemove, &target);
+    }
 
     let result = workflow::remove(handle, force, keep_branch, &context)
         .context("Failed to remove worktree")?;
 
-    if keep_branch {
+    let removed = dry_run::outcome("✓ Removed", "[dry-run] Would remove");
+    if result.branch_removed == "(detached)" {
+        println!("{} worktree '{}'", removed, handle);
+    } else if keep_branch {
         println!(
-            "✓ Removed worktree '{}' (branch '{}' kept)",
-            handle, result.branch_removed
+            "{} worktree '{}' (branch '{}' kept)",
+            removed, handle, result.branch_removed
         );
     } else {
         println!(
-            "✓ Removed worktree '{}' and branch '{}'",
-            handle, result.branch_removed
+            "{} worktree '{}' and branch '{}'",
+            removed, handle, result.branch_removed
         );
     }
 
-    Ok(())
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_age, parse_age};
+    use std::time::Duration;
+
+    #[test]
+    fn ages_parse_with_a_unit() {
+        assert_eq!(parse_age("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(7 * 86_400)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7 days").is_err());
+        assert!(parse_age("99999999999999999w").is_err());
+    }
+
+    #[test]
+    fn ages_format_in_their_largest_unit() {
+        assert_eq!(format_age(Duration::from_secs(90)), "1m");
+        assert_eq!(format_age(Duration::from_secs(5 * 3600 + 59)), "5h");
+        assert_eq!(format_age(Duration::from_secs(3 * 86_400 + 7200)), "3d");
+    }
 }
@@ -0,0 +1,439 @@
+use crate::activity::{self, ActivityEntry, AgentStatus};
+use crate::git::{self, CommitSummary, DiffSize};
+use crate::workflow::{HookEnv, WorkflowContext};
+use crate::{cmd, config, worktree_state};
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::history::format_timestamp;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    /// Markdown
+    Md,
+    /// A standalone HTML page
+    Html,
+}
+
+/// What happened in one worktree during the reported period
+struct WorktreeReport {
+    handle: String,
+    /// None once the worktree is gone
+    branch: Option<String>,
+    /// Status changes, oldest first
+    transitions: Vec<ActivityEntry>,
+    /// Commits made during the period, newest first
+    commits: Vec<CommitSummary>,
+    /// The branch's changes since it left its base, uncommitted ones included
+    diff: Option<DiffSize>,
+    dirty: bool,
+    /// Outcome of the `pre_merge` checks, with `--verify`
+    checks: Option<Result<(), String>>,
+}
+
+impl WorktreeReport {
+    /// The agent's status at the end of the period
+    fn status(&self) -> Option<AgentStatus> {
+        self.transitions.last().and_then(|entry| entry.to)
+    }
+}
+
+struct Report {
+    repo: String,
+    since: u64,
+    now: u64,
+    active: Vec<WorktreeReport>,
+    /// Worktrees with nothing to report
+    quiet: Vec<String>,
+}
+
+pub fn run(since: Duration, format: ReportFormat, verify: bool) -> Result<()> {
+    let context = WorkflowContext::new(config::Config::load(None)?)?;
+    if verify && context.config.pre_merge.as_ref().is_none_or(Vec::is_empty) {
+        return Err(anyhow!(
+            "No pre_merge commands are configured to verify worktrees with"
+        ));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let cutoff = now.saturating_sub(since.as_secs());
+    let mut transitions: Vec<ActivityEntry> = activity::parse_entries(&activity::read_log()?)
+        .into_iter()
+        .filter(|entry| {
+            entry.timestamp >= cutoff
+                && entry.repo.as_deref() == Some(context.main_worktree_root.as_path())
+        })
+        .collect();
+
+    let mut active = Vec::new();
+    let mut quiet = Vec::new();
+    for (path, branch) in git::list_worktrees()? {
+        if path == context.main_worktree_root {
+            continue;
+        }
+        let Some(handle) = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        let (own, rest): (Vec<_>, Vec<_>) = transitions
+            .into_iter()
+            .partition(|entry| entry.handle == handle);
+        transitions = rest;
+
+        let base = worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone());
+        let commits = git::commits_since(&base, &branch, cutoff).unwrap_or_default();
+        if own.is_empty() && commits.is_empty() {
+            quiet.push(handle);
+            continue;
+        }
+
+        let diff = git::get_merge_base_in_worktree(&path, &base)
+            .and_then(|merge_base| git::diff_size(&path, &merge_base))
+            .ok();
+        let checks = verify.then(|| run_checks(&context, &handle, &branch, &path));
+        active.push(WorktreeReport {
+            dirty: git::has_uncommitted_changes(&path).unwrap_or(false),
+            handle,
+            branch: Some(branch),
+            transitions: own,
+            commits,
+            diff,
+            checks,
+        });
+    }
+
+    // Agents whose worktree was merged or removed during the period
+    while let Some(first) = transitions.first() {
+        let handle = first.handle.clone();
+        let (own, rest): (Vec<_>, Vec<_>) = transitions
+            .into_iter()
+            .partition(|entry| entry.handle == handle);
+        transitions = rest;
+        active.push(WorktreeReport {
+            handle,
+            branch: None,
+            transitions: own,
+            commits: Vec::new(),
+            diff: None,
+            dirty: false,
+            checks: None,
+        });
+    }
+
+    let report = Report {
+        repo: context
+            .main_worktree_root
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string()),
+        since: cutoff,
+        now,
+        active,
+        quiet,
+    };
+    let output = match format {
+        ReportFormat::Md => markdown(&report),
+        ReportFormat::Html => html(&report),
+    };
+    print!("{}", output);
+    Ok(())
+}
+
+/// Run the `pre_merge` commands in a worktree with their output discarded,
+/// stopping at the first that fails
+fn run_checks(
+    context: &WorkflowContext,
+    handle: &str,
+    branch: &str,
+    path: &Path,
+) -> Result<(), String> {
+    if std::io::stderr().is_terminal() {
+        eprintln!("Verifying {}...", handle);
+    }
+    let branch_config = context.config.for_branch(branch);
+    let hooks = config::hooks_for(
+        &branch_config.pre_merge,
+        &config::WhenTarget {
+            branch,
+            agent: context.config.agent.as_deref(),
+            worktree_path: path,
+        },
+    );
+    let env = HookEnv::new(handle, branch, path, &context.main_worktree_root)
+        .with_target_branch(&context.main_branch);
+    let input = env.input("pre_merge");
+    for hook in hooks {
+        let command = env.render(hook.command()).map_err(|e| format!("{:#}", e))?;
+        cmd::shell_command_quietly(&command, path, &env.vars(), Some(&input), hook.timeout())
+            .map_err(|_| format!("`{}` failed", hook))?;
+    }
+    Ok(())
+}
+
+/// One line per status change, e.g. `2026-10-16 03:12:40 working → waiting`
+fn transition_lines(report: &WorktreeReport) -> Vec<String> {
+    report
+        .transitions
+        .iter()
+        .map(|entry| {
+            format!(
+                "{} {}",
+                format_timestamp(entry.timestamp),
+                entry.transition()
+            )
+        })
+        .collect()
+}
+
+fn commit_line(commit: &CommitSummary) -> (String, String) {
+    (
+        commit.sha.clone(),
+        format!(
+            "{} ({}, {})",
+            commit.subject,
+            commit.author,
+            format_timestamp(commit.timestamp)
+        ),
+    )
+}
+
+fn diff_line(report: &WorktreeReport) -> Option<String> {
+    let diff = report.diff?;
+    if diff.files == 0 {
+        return Some("no changes".to_string());
+    }
+    let mut line = format!(
+        "{} file{} changed, +{} −{}",
+        diff.files,
+        if diff.files == 1 { "" } else { "s" },
+        diff.insertions,
+        diff.deletions
+    );
+    if report.dirty {
+        line.push_str(", some of it uncommitted");
+    }
+    Some(line)
+}
+
+fn summary_line(report: &Report) -> String {
+    format!(
+        "From {} to {} UTC: {} worktree(s) with activity, {} without.",
+        format_timestamp(report.since),
+        format_timestamp(report.now),
+        report.active.len(),
+        report.quiet.len()
+    )
+}
+
+fn markdown(report: &Report) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# workmux report: {}\n", report.repo);
+    let _ = writeln!(out, "{}", summary_line(report));
+
+    for worktree in &report.active {
+        match &worktree.branch {
+            Some(branch) => {
+                let _ = writeln!(out, "\n## {} (`{}`)\n", worktree.handle, branch);
+            }
+            None => {
+                let _ = writeln!(out, "\n## {} (removed)\n", worktree.handle);
+            }
+        }
+        if let Some(status) = worktree.status() {
+            let _ = writeln!(out, "- **Status:** {}", status.label());
+        }
+        if !worktree.transitions.is_empty() {
+            let _ = writeln!(out, "- **Status changes:** {}", worktree.transitions.len());
+            for line in transition_lines(worktree) {
+                let _ = writeln!(out, "  - {}", line);
+            }
+        }
+        if worktree.branch.is_some() {
+            let _ = writeln!(out, "- **Commits:** {}", worktree.commits.len());
+            for commit in &worktree.commits {
+                let (sha, rest) = commit_line(commit);
+                let _ = writeln!(out, "  - `{}` {}", sha, rest);
+            }
+        }
+        if let Some(line) = diff_line(worktree) {
+            let _ = writeln!(out, "- **Diff:** {}", line);
+        }
+        match &worktree.checks {
+            Some(Ok(())) => {
+                let _ = writeln!(out, "- **Checks:** ✓ passed");
+            }
+            Some(Err(error)) => {
+                let _ = writeln!(out, "- **Checks:** ✗ {}", error);
+            }
+            None => {}
+        }
+    }
+
+    if !report.quiet.is_empty() {
+        let _ = writeln!(out, "\n## No activity\n");
+        let _ = writeln!(out, "{}", report.quiet.join(", "));
+    }
+    out
+}
+
+fn html(report: &Report) -> String {
+    let mut out = String::new();
+    let title = format!("workmux report: {}", escape(&report.repo));
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>",
+        title
+    );
+    let _ = writeln!(out, "<h1>{}</h1>", title);
+    let _ = writeln!(out, "<p>{}</p>", escape(&summary_line(report)));
+
+    for worktree in &report.active {
+        match &worktree.branch {
+            Some(branch) => {
+                let _ = writeln!(
+                    out,
+                    "<h2>{} (<code>{}</code>)</h2>",
+                    escape(&worktree.handle),
+                    escape(branch)
+                );
+            }
+            None => {
+                let _ = writeln!(out, "<h2>{} (removed)</h2>", escape(&worktree.handle));
+            }
+        }
+        let _ = writeln!(out, "<ul>");
+        if let Some(status) = worktree.status() {
+            let _ = writeln!(out, "<li><b>Status:</b> {}</li>", status.label());
+        }
+        if !worktree.transitions.is_empty() {
+            let _ = writeln!(
+                out,
+                "<li><b>Status changes:</b> {}\n<ul>",
+                worktree.transitions.len()
+            );
+            for line in transition_lines(worktree) {
+                let _ = writeln!(out, "<li>{}</li>", escape(&line));
+            }
+            let _ = writeln!(out, "</ul></li>");
+        }
+        if worktree.branch.is_some() {
+            let _ = write!(out, "<li><b>Commits:</b> {}", worktree.commits.len());
+            if !worktree.commits.is_empty() {
+                let _ = writeln!(out, "\n<ul>");
+                for commit in &worktree.commits {
+                    let (sha, rest) = commit_line(commit);
+                    let _ = writeln!(
+                        out,
+                        "<li><code>{}</code> {}</li>",
+                        escape(&sha),
+                        escape(&rest)
+                    );
+                }
+                let _ = write!(out, "</ul>");
+            }
+            let _ = writeln!(out, "</li>");
+        }
+        if let Some(line) = diff_line(worktree) {
+            let _ = writeln!(out, "<li><b>Diff:</b> {}</li>", escape(&line));
+        }
+        match &worktree.checks {
+            Some(Ok(())) => {
+                let _ = writeln!(out, "<li><b>Checks:</b> ✓ passed</li>");
+            }
+            Some(Err(error)) => {
+                let _ = writeln!(out, "<li><b>Checks:</b> ✗ {}</li>", escape(error));
+            }
+            None => {}
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.quiet.is_empty() {
+        let _ = writeln!(out, "<h2>No activity</h2>");
+        let _ = writeln!(out, "<p>{}</p>", escape(&report.quiet.join(", ")));
+    }
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Report {
+        let entry = |timestamp, from, to| ActivityEntry {
+            timestamp,
+            handle: "auth".to_string(),
+            from,
+            to,
+            repo: None,
+        };
+        Report {
+            repo: "app".to_string(),
+            since: 1_700_000_000,
+            now: 1_700_043_200,
+            active: vec![WorktreeReport {
+                handle: "auth".to_string(),
+                branch: Some("feat/<auth>".to_string()),
+                transitions: vec![
+                    entry(1_700_000_100, None, Some(AgentStatus::Working)),
+                    entry(
+                        1_700_003_700,
+                        Some(AgentStatus::Working),
+                        Some(AgentStatus::Done),
+                    ),
+                ],
+                commits: vec![CommitSummary {
+                    sha: "a1b2c3d".to_string(),
+                    subject: "Add login form".to_string(),
+                    author: "Ada".to_string(),
+                    timestamp: 1_700_003_600,
+                }],
+                diff: Some(DiffSize {
+                    files: 3,
+                    insertions: 40,
+                    deletions: 2,
+                }),
+                dirty: true,
+                checks: Some(Err("`cargo test` failed".to_string())),
+            }],
+            quiet: vec!["docs".to_string(), "api".to_string()],
+        }
+    }
+
+    #[test]
+    fn markdown_lists_each_worktree_with_activity() {
+        let output = markdown(&sample());
+        assert!(output.starts_with("# workmux report: app\n\n"));
+        assert!(output.contains("1 worktree(s) with activity, 2 without."));
+        assert!(output.contains("## auth (`feat/<auth>`)\n\n- **Status:** done\n"));
+        assert!(output.contains("  - 2023-11-14 23:15:00 working → done\n"));
+        assert!(output.contains("  - `a1b2c3d` Add login form (Ada, 2023-11-14 23:13:20)\n"));
+        assert!(output.contains("- **Diff:** 3 files changed, +40 −2, some of it uncommitted\n"));
+        assert!(output.contains("- **Checks:** ✗ `cargo test` failed\n"));
+        assert!(output.ends_with("## No activity\n\ndocs, api\n"));
+    }
+
+    #[test]
+    fn html_escapes_what_it_shows() {
+        let output = html(&sample());
+        assert!(output.contains("<h2>auth (<code>feat/&lt;auth&gt;</code>)</h2>"));
+        assert!(output.trim_end().ends_with("</html>"));
+    }
+}
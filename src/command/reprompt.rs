@@ -0,0 +1,145 @@
+use crate::config::{Config, PaneConfig, SplitDirection};
+use crate::prompt::Prompt;
+use crate::workflow::prompt_loader::edit_prompt;
+use crate::workflow::{self, SetupOptions, WorkflowContext};
+use crate::{git, tmux, worktree_state};
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use tracing::debug;
+
+/// Start the worktree's agent over on the prompt it was created with, edited
+/// first with `edit`: in place of the running agent, in a new pane of the
+/// worktree's window if no agent runs there, or in a newly opened window
+pub fn run(name: Option<&str>, edit: bool) -> Result<()> {
+    let name = super::resolve_name(name)?;
+    let (worktree_path, _) = git::find_worktree(&name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    let mut state = worktree_state::load(&handle)?.unwrap_or_default();
+    let mut prompt = super::open::stored_prompt(&worktree_path, &handle, &state)?;
+    // The agent the worktree was created with, not the configured one
+    let config = Config::load(state.agent.as_deref())?;
+
+    if edit {
+        let edited = edit_prompt(&prompt)?;
+        if edited != prompt.trim() {
+            // Later attempts, and `open --resume-prompt`, start from the edit
+            if let Some(file) = &state.prompt_file {
+                workflow::save_prompt(&worktree_path, file, &edited, &config)?;
+            }
+            state.prompt = Some(edited.clone());
+            worktree_state::save(&handle, &state, &config)
+                .context("Failed to record the prompt")?;
+        }
+        prompt = edited;
+    }
+
+    let context = WorkflowContext::new(config)?;
+    context.ensure_tmux_running()?;
+    let prompt_file_path =
+        workflow::write_prompt_file(&format!("{}-reprompt", handle), &Prompt::Inline(prompt))?;
+
+    if !tmux::window_exists(&context.prefix, &handle)? {
+        let mut options = SetupOptions::new(false, false, true);
+        options.prompt_file_path = Some(prompt_file_path);
+        workflow::open(&handle, &context, options, false)
+            .context("Failed to open worktree environment")?;
+        println!(
+            "✓ Opened a window for '{}' with its agent on the original prompt",
+            handle
+        );
+        return Ok(());
+    }
+
+    let agent_pane = PaneConfig {
+        command: Some("<agent>".to_string()),
+        focus: true,
+        split: None,
+        size: None,
+        percentage: None,
+        target: None,
+    };
+    let agent_command = state
+        .agent
+        .as_deref()
+        .or(context.config.agent.as_deref())
+        .map(|agent| crate::agent::command(&context.config, agent));
+    let target = tmux::window_target(&tmux::prefixed(&context.prefix, &handle));
+    let running = tmux::agent_panes(&target, agent_command.as_deref())?;
+    let setup = |pane_id: &str, panes: &[PaneConfig]| {
+        tmux::setup_panes(
+            pane_id,
+            panes,
+            &worktree_path,
+            tmux::PaneSetupOptions {
+                run_commands: true,
+                prompt_file_path: Some(&prompt_file_path),
+                pane_prompt_paths: &[],
+            },
+            &context.config,
+            state.agent.as_deref(),
+        )
+        .context("Failed to start the agent")
+    };
+
+    if running.is_empty() {
+        // An empty first pane leaves the window's active pane untouched
+        let existing_pane = PaneConfig {
+            command: None,
+            focus: false,
+            ..agent_pane.clone()
+        };
+        let split_pane = PaneConfig {
+            split: Some(SplitDirection::Horizontal),
+            ..agent_pane
+        };
+        setup(
+            &tmux::active_pane_id(&context.prefix, &handle)?,
+            &[existing_pane, split_pane],
+        )?;
+        forget_waiting(&worktree_path, &context.config);
+        println!(
+            "✓ Started an agent for '{}' on the original prompt in a new pane",
+            handle
+        );
+        return Ok(());
+    }
+
+    for (idx, pane_id) in running.iter().enumerate() {
+        // Focus stays where it was put first
+        let pane = PaneConfig {
+            focus: idx == 0,
+            ..agent_pane.clone()
+        };
+        setup(pane_id, &[pane])?;
+    }
+    forget_waiting(&worktree_path, &context.config);
+    // The old agents' status shows until the new ones report their own
+    if let Err(e) = tmux::pane_window_id(&running[0]).and_then(|id| tmux::clear_window_status(&id))
+    {
+        debug!(error = %e, "reprompt:failed to clear status");
+    }
+    if running.len() == 1 {
+        println!(
+            "✓ Restarted the agent of '{}' on the original prompt",
+            handle
+        );
+    } else {
+        println!(
+            "✓ Restarted the {} agents of '{}' on the original prompt",
+            running.len(),
+            handle
+        );
+    }
+    Ok(())
+}
+
+/// What the previous agent was waiting for doesn't apply to the new one
+fn forget_waiting(worktree_path: &Path, config: &Config) {
+    if let Err(e) = worktree_state::set_waiting(worktree_path, None, config) {
+        debug!(error = %e, "reprompt:failed to clear waiting reason");
+    }
+}
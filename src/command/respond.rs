@@ -0,0 +1,135 @@
+use crate::config::Config;
+use crate::git;
+use crate::tmux::{self, AgentPane};
+use anyhow::{Result, anyhow};
+
+pub fn run(name: Option<&str>, text: Option<&str>, all: Option<&str>) -> Result<()> {
+    let config = Config::load(None)?;
+    let agents = tmux::get_all_agent_panes()?;
+    let waiting = waiting_panes(
+        &agents,
+        config.window_prefix(),
+        config.status_icons.waiting(),
+    );
+
+    if let Some(text) = all {
+        if waiting.is_empty() {
+            return Err(anyhow!("No agents are waiting for input"));
+        }
+        for (_, agent) in &waiting {
+            respond(agent, text, &config)?;
+        }
+        let handles: Vec<&str> = waiting.iter().map(|(handle, _)| handle.as_str()).collect();
+        println!(
+            "✓ Sent '{}' to {} waiting agent(s): {}",
+            text,
+            waiting.len(),
+            handles.join(", ")
+        );
+        return Ok(());
+    }
+
+    let name = name.ok_or_else(|| anyhow!("Pass a worktree name or --all <text>"))?;
+    let agent = find_waiting(&waiting, name)?;
+    match text {
+        Some(text) => {
+            respond(agent, text, &config)?;
+            println!("✓ Sent '{}' to '{}'", text, name);
+        }
+        None => tmux::switch_to_pane(&agent.pane_id)?,
+    }
+    Ok(())
+}
+
+/// Type `text` into the agent's pane and show it as working again
+fn respond(agent: &AgentPane, text: &str, config: &Config) -> Result<()> {
+    tmux::send_keys(&agent.pane_id, text)?;
+    super::set_window_status::mark_working(&agent.pane_id, &agent.path, config)
+}
+
+/// The waiting agent pane of the worktree `name`, given as a window handle
+/// or anything else that resolves to a worktree
+fn find_waiting<'a>(waiting: &[(String, &'a AgentPane)], name: &str) -> Result<&'a AgentPane> {
+    let pane_of = |handle: &str| {
+        waiting
+            .iter()
+            .find(|(waiting_handle, _)| waiting_handle == handle)
+            .map(|(_, agent)| *agent)
+    };
+    if let Some(pane) = pane_of(name) {
+        return Ok(pane);
+    }
+    let (path, _) = git::find_worktree(name)?;
+    let handle = path
+        .file_name()
+        .map(|handle| handle.to_string_lossy().to_string())
+        .unwrap_or_default();
+    pane_of(&handle).ok_or_else(|| {
+        if waiting.is_empty() {
+            anyhow!("No agent in '{}' is waiting for input", handle)
+        } else {
+            let handles: Vec<&str> = waiting.iter().map(|(handle, _)| handle.as_str()).collect();
+            anyhow!(
+                "No agent in '{}' is waiting for input. Waiting: {}",
+                handle,
+                handles.join(", ")
+            )
+        }
+    })
+}
+
+/// Agent panes of workmux windows waiting for input, with their window's handle
+fn waiting_panes<'a>(
+    agents: &'a [AgentPane],
+    prefix: &str,
+    waiting_icon: &str,
+) -> Vec<(String, &'a AgentPane)> {
+    agents
+        .iter()
+        .filter(|agent| agent.status.as_deref() == Some(waiting_icon))
+        .filter_map(|agent| {
+            let handle = agent.window_name.strip_prefix(prefix)?;
+            Some((handle.to_string(), agent))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::waiting_panes;
+    use crate::tmux::AgentPane;
+    use std::path::PathBuf;
+
+    fn agent(window_name: &str, pane_id: &str, status: &str) -> AgentPane {
+        AgentPane {
+            session: "main".to_string(),
+            window_name: window_name.to_string(),
+            pane_id: pane_id.to_string(),
+            path: PathBuf::new(),
+            pane_title: None,
+            status: Some(status.to_string()),
+            status_ts: None,
+        }
+    }
+
+    #[test]
+    fn only_waiting_agents_of_workmux_windows_are_picked() {
+        let agents = [
+            agent("wm-auth", "%1", "💬"),
+            agent("wm-search", "%2", "🤖"),
+            agent("scratch", "%3", "💬"),
+            agent("wm-billing", "%4", "💬"),
+        ];
+        let waiting: Vec<(String, String)> = waiting_panes(&agents, "wm-", "💬")
+            .into_iter()
+            .map(|(handle, agent)| (handle, agent.pane_id.clone()))
+            .collect();
+        assert_eq!(
+            waiting,
+            [
+                ("auth".to_string(), "%1".to_string()),
+                ("billing".to_string(), "%4".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::config::{self, ReviewTarget};
+use crate::workflow::{self, ReviewArgs, WorkflowContext};
+use anyhow::{Context, Result};
+
+pub fn run(
+    name: &str,
+    agent: Option<&str>,
+    base: Option<&str>,
+    output: Option<&str>,
+    pane: bool,
+    window: bool,
+) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+
+    let target = if window {
+        Some(ReviewTarget::Window)
+    } else if pane {
+        Some(ReviewTarget::Pane)
+    } else {
+        None
+    };
+
+    let result = workflow::review(
+        name,
+        &context,
+        ReviewArgs {
+            agent,
+            base,
+            output,
+            target,
+        },
+    )
+    .context("Failed to start reviewer agent")?;
+
+    println!(
+        "✓ Started {} reviewing '{}' against '{}'\n  Findings: {}",
+        result.agent,
+        result.branch,
+        result.base,
+        result.output_path.display()
+    );
+
+    Ok(())
+}
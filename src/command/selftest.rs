@@ -0,0 +1,315 @@
+use crate::{dry_run, remote};
+use anyhow::{Context, Result, anyhow};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long one workmux, git or tmux command may take before the step fails
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Lines of a failed command's output shown under the step
+const OUTPUT_TAIL: usize = 15;
+
+/// A throwaway repository with its own tmux server, so the test leaves the
+/// user's sessions and worktrees alone
+struct Sandbox {
+    root: PathBuf,
+    repo: PathBuf,
+    /// Numbers the output files of the commands run
+    runs: usize,
+}
+
+type Step = fn(&mut Sandbox) -> Result<()>;
+
+const STEPS: &[(&str, Step)] = &[
+    ("create a git repository", create_repo),
+    ("start a tmux server", start_tmux),
+    ("add a worktree", add),
+    ("reopen its window", reopen),
+    ("merge it", merge),
+    ("remove a worktree", remove),
+];
+
+pub fn run(keep: bool) -> Result<()> {
+    if remote::get().is_some() {
+        return Err(anyhow!("selftest runs on this machine; drop --host"));
+    }
+    if dry_run::is_enabled() {
+        return Err(anyhow!(
+            "selftest only changes its own throwaway repository; drop --dry-run"
+        ));
+    }
+
+    let root = std::env::temp_dir().join(format!("workmux-selftest-{}", process::id()));
+    fs::create_dir_all(root.join("tmux"))
+        .with_context(|| format!("Failed to create {}", root.display()))?;
+    let mut sandbox = Sandbox {
+        repo: root.join("repo"),
+        root,
+        runs: 0,
+    };
+    println!("Testing workmux in {}", sandbox.root.display());
+
+    let mut failed = None;
+    for (name, step) in STEPS {
+        if failed.is_some() {
+            println!("  - {}: skipped", name);
+            continue;
+        }
+        match step(&mut sandbox) {
+            Ok(()) => println!("  ✓ {}", name),
+            Err(e) => {
+                println!("  ✗ {}: {:#}", name, e);
+                failed = Some(*name);
+            }
+        }
+    }
+
+    // Stops the agent-less panes along with the server
+    let _ = sandbox
+        .command("tmux")
+        .arg("kill-server")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    if keep || failed.is_some() {
+        println!("\nKept {} for inspection.", sandbox.root.display());
+    } else {
+        let _ = fs::remove_dir_all(&sandbox.root);
+    }
+
+    match failed {
+        Some(name) => Err(anyhow!("The self-test failed at '{}'", name)),
+        None => {
+            println!("\n✓ workmux works in this environment");
+            Ok(())
+        }
+    }
+}
+
+fn create_repo(sandbox: &mut Sandbox) -> Result<()> {
+    fs::create_dir_all(&sandbox.repo)?;
+    sandbox.git(&["init", "-q"])?;
+    // `init -b` needs git 2.28
+    sandbox.git(&["symbolic-ref", "HEAD", "refs/heads/main"])?;
+    // A plain shell in place of the agent, so nothing needs to be installed
+    fs::write(
+        sandbox.repo.join(".workmux.yaml"),
+        "main_branch: main\npanes:\n  - command: \"true\"\n",
+    )?;
+    sandbox.commit(&sandbox.repo.clone(), "Initial commit")
+}
+
+fn start_tmux(sandbox: &mut Sandbox) -> Result<()> {
+    let repo = sandbox.repo.to_string_lossy().to_string();
+    sandbox.tmux(&["new-session", "-d", "-s", "selftest", "-c", &repo])?;
+    Ok(())
+}
+
+fn add(sandbox: &mut Sandbox) -> Result<()> {
+    sandbox.workmux(&["add", "selftest-add"])?;
+    let path = sandbox
+        .worktree_path("selftest-add")?
+        .ok_or_else(|| anyhow!("git has no worktree for 'selftest-add'"))?;
+    if !path.join(".workmux.yaml").exists() {
+        return Err(anyhow!("{} was not checked out", path.display()));
+    }
+    sandbox.expect_window("selftest-add", true)
+}
+
+fn reopen(sandbox: &mut Sandbox) -> Result<()> {
+    sandbox.workmux(&["close", "selftest-add"])?;
+    sandbox.expect_window("selftest-add", false)?;
+    sandbox.workmux(&["open", "selftest-add"])?;
+    sandbox.expect_window("selftest-add", true)
+}
+
+fn merge(sandbox: &mut Sandbox) -> Result<()> {
+    let path = sandbox
+        .worktree_path("selftest-add")?
+        .ok_or_else(|| anyhow!("git has no worktree for 'selftest-add'"))?;
+    fs::write(path.join("selftest.txt"), "merged\n")?;
+    sandbox.commit(&path, "Add selftest.txt")?;
+
+    sandbox.workmux(&["merge", "selftest-add"])?;
+    if sandbox
+        .git(&["cat-file", "-e", "main:selftest.txt"])
+        .is_err()
+    {
+        return Err(anyhow!("main does not have the merged commit"));
+    }
+    sandbox.expect_gone("selftest-add", &path)
+}
+
+fn remove(sandbox: &mut Sandbox) -> Result<()> {
+    sandbox.workmux(&["add", "selftest-remove"])?;
+    let path = sandbox
+        .worktree_path("selftest-remove")?
+        .ok_or_else(|| anyhow!("git has no worktree for 'selftest-remove'"))?;
+    sandbox.workmux(&["remove", "selftest-remove", "--force"])?;
+    sandbox.expect_gone("selftest-remove", &path)
+}
+
+impl Sandbox {
+    /// `program` set up to use the sandbox's tmux server and state directory,
+    /// run from the repository
+    fn command(&self, program: impl AsRef<OsStr>) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.current_dir(&self.repo)
+            .env("TMUX_TMPDIR", self.root.join("tmux"))
+            .env("XDG_STATE_HOME", self.root.join("state"))
+            .env_remove("TMUX")
+            .env_remove("TMUX_PANE")
+            .stdin(Stdio::null());
+        cmd
+    }
+
+    /// Run `cmd`, failing with the end of its output if it fails or takes
+    /// longer than `COMMAND_TIMEOUT`. Returns its output.
+    fn run(&mut self, mut cmd: Command, what: &str) -> Result<String> {
+        self.runs += 1;
+        let log = self.root.join(format!("{:02}.log", self.runs));
+        let file = File::create(&log)?;
+        cmd.stdout(file.try_clone()?).stderr(file);
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to run '{}'", what))?;
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if started.elapsed() >= COMMAND_TIMEOUT {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(anyhow!(
+                    "'{}' did not finish within {}s{}",
+                    what,
+                    COMMAND_TIMEOUT.as_secs(),
+                    tail(&log)
+                ));
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+        if !status.success() {
+            return Err(anyhow!("'{}' failed{}", what, tail(&log)));
+        }
+        Ok(fs::read_to_string(&log).unwrap_or_default())
+    }
+
+    /// Run this workmux, without network access or the user's default flags
+    fn workmux(&mut self, args: &[&str]) -> Result<String> {
+        let exe = std::env::current_exe().context("Failed to locate the workmux executable")?;
+        let mut cmd = self.command(exe);
+        cmd.args(["--offline", "--no-defaults"]).args(args);
+        self.run(cmd, &format!("workmux {}", args.join(" ")))
+    }
+
+    fn git(&mut self, args: &[&str]) -> Result<String> {
+        let mut cmd = self.command("git");
+        cmd.args(args);
+        self.run(cmd, &format!("git {}", args.join(" ")))
+    }
+
+    fn tmux(&mut self, args: &[&str]) -> Result<String> {
+        let mut cmd = self.command("tmux");
+        cmd.args(args);
+        self.run(cmd, &format!("tmux {}", args.join(" ")))
+    }
+
+    /// Commit everything in `dir`, as a fixed identity so a missing
+    /// user.name doesn't stop the test before workmux is involved
+    fn commit(&mut self, dir: &Path, message: &str) -> Result<()> {
+        let dir = dir.to_string_lossy().to_string();
+        self.git(&["-C", &dir, "add", "-A"])?;
+        self.git(&[
+            "-C",
+            &dir,
+            "-c",
+            "user.name=workmux selftest",
+            "-c",
+            "user.email=selftest@workmux.invalid",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ])?;
+        Ok(())
+    }
+
+    /// Path of the worktree with `branch` checked out, if there is one
+    fn worktree_path(&mut self, branch: &str) -> Result<Option<PathBuf>> {
+        let list = self.git(&["worktree", "list", "--porcelain"])?;
+        Ok(worktree_for_branch(&list, branch))
+    }
+
+    fn expect_window(&mut self, handle: &str, open: bool) -> Result<()> {
+        let windows = self.tmux(&["list-windows", "-a", "-F", "#{window_name}"])?;
+        let found = windows.lines().any(|name| name.ends_with(handle));
+        match (open, found) {
+            (true, false) => Err(anyhow!("no tmux window for '{}'", handle)),
+            (false, true) => Err(anyhow!("the tmux window for '{}' is still open", handle)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the worktree, window and branch of `handle` are all gone
+    fn expect_gone(&mut self, handle: &str, path: &Path) -> Result<()> {
+        if self.worktree_path(handle)?.is_some() || path.exists() {
+            return Err(anyhow!("the worktree {} is still there", path.display()));
+        }
+        let branches = self.git(&["branch", "--list", handle])?;
+        if !branches.trim().is_empty() {
+            return Err(anyhow!("the branch '{}' was not deleted", handle));
+        }
+        self.expect_window(handle, false)
+    }
+}
+
+/// From `git worktree list --porcelain`, the worktree with `branch` checked out
+fn worktree_for_branch(list: &str, branch: &str) -> Option<PathBuf> {
+    let wanted = format!("branch refs/heads/{}", branch);
+    list.split("\n\n").find_map(|entry| {
+        let mut lines = entry.lines();
+        let path = lines.next()?.strip_prefix("worktree ")?;
+        lines
+            .any(|line| line == wanted)
+            .then(|| PathBuf::from(path))
+    })
+}
+
+/// The last lines of the output in `log`, indented to go under the step
+fn tail(log: &Path) -> String {
+    let output = fs::read_to_string(log).unwrap_or_default();
+    let lines: Vec<&str> = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let shown = &lines[lines.len().saturating_sub(OUTPUT_TAIL)..];
+    shown
+        .iter()
+        .map(|line| format!("\n      {}", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worktree_for_branch_reads_porcelain_entries() {
+        let list = "worktree /tmp/repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                    worktree /tmp/repo__worktrees/feat\nHEAD def\nbranch refs/heads/feat\n\n\
+                    worktree /tmp/repo__worktrees/detached\nHEAD 123\ndetached\n";
+        assert_eq!(
+            worktree_for_branch(list, "feat"),
+            Some(PathBuf::from("/tmp/repo__worktrees/feat"))
+        );
+        assert_eq!(worktree_for_branch(list, "fea"), None);
+        assert_eq!(worktree_for_branch(list, "detached"), None);
+    }
+}
@@ -1,12 +1,25 @@
 use anyhow::Result;
 use clap::ValueEnum;
+use serde_json::Value;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
 
+use crate::activity::{self, AgentStatus};
 use crate::cmd::Cmd;
 use crate::config::Config;
-use crate::tmux;
+use crate::worktree_state::{self, WaitingReason};
+use crate::{git, tmux};
 
-#[derive(ValueEnum, Debug, Clone)]
+/// Lines of the agent's pane kept as the reason it is waiting
+const WAITING_LINES: u16 = 15;
+
+/// How often an unchanged status has its timestamps refreshed, which the
+/// dashboard uses to tell live agents from stale ones
+const TIMESTAMP_REFRESH_SECS: u64 = 30;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetWindowStatusCommand {
     /// Set status to "working" (agent is processing)
     Working,
@@ -18,6 +31,64 @@ pub enum SetWindowStatusCommand {
     Clear,
 }
 
+/// Agents whose hook events `--hook` understands
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum HookAgent {
+    /// Gemini CLI hooks (`hooks` in `settings.json`)
+    Gemini,
+    /// GitHub Copilot CLI hooks (`.github/hooks/*.json`)
+    Copilot,
+}
+
+/// Set the status from an agent hook event. The event JSON is read from
+/// stdin; its name comes from `--event` or the event's `hook_event_name`.
+/// Events that don't change the status are ignored.
+pub fn run_hook(agent: HookAgent, event: Option<String>) -> Result<()> {
+    let payload = read_payload();
+    let event = event.or_else(|| payload["hook_event_name"].as_str().map(str::to_string));
+    match event.and_then(|event| status_for_event(agent, &event, &payload)) {
+        Some(cmd) => run(cmd),
+        None => Ok(()),
+    }
+}
+
+/// The hook event JSON on stdin, or null if there is none
+fn read_payload() -> Value {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Value::Null;
+    }
+    let mut input = String::new();
+    if stdin.read_to_string(&mut input).is_err() {
+        return Value::Null;
+    }
+    serde_json::from_str(&input).unwrap_or(Value::Null)
+}
+
+/// The status an agent's hook event stands for
+fn status_for_event(
+    agent: HookAgent,
+    event: &str,
+    payload: &Value,
+) -> Option<SetWindowStatusCommand> {
+    use SetWindowStatusCommand::*;
+    match agent {
+        HookAgent::Gemini => match event {
+            "BeforeAgent" | "AfterTool" => Some(Working),
+            "Notification" if payload["notification_type"] == "ToolPermission" => Some(Waiting),
+            "AfterAgent" => Some(Done),
+            "SessionEnd" => Some(Clear),
+            _ => None,
+        },
+        HookAgent::Copilot => match event {
+            "userPromptSubmitted" | "postToolUse" => Some(Working),
+            "agentStop" | "errorOccurred" => Some(Done),
+            "sessionEnd" => Some(Clear),
+            _ => None,
+        },
+    }
+}
+
 pub fn run(cmd: SetWindowStatusCommand) -> Result<()> {
     // Fail silently if not in tmux to avoid polluting non-tmux shells
     let Ok(pane) = std::env::var("TMUX_PANE") else {
@@ -25,104 +96,424 @@ pub fn run(cmd: SetWindowStatusCommand) -> Result<()> {
     };
 
     let config = Config::load(None)?;
+    let root = git::get_repo_root().ok();
+    let icon = match cmd {
+        SetWindowStatusCommand::Working => Some(config.status_icons.working()),
+        SetWindowStatusCommand::Waiting => Some(config.status_icons.waiting()),
+        SetWindowStatusCommand::Done => Some(config.status_icons.done()),
+        SetWindowStatusCommand::Clear => None,
+    };
 
-    // Ensure the status format is applied so the icon actually shows up
-    // Skip for Clear since there's nothing to display
-    if config.status_format.unwrap_or(true) && !matches!(cmd, SetWindowStatusCommand::Clear) {
-        let _ = tmux::ensure_status_format(&pane);
+    // Agents report the same status many times a minute (every tool call
+    // while working). One that is already shown only needs its timestamps
+    // kept fresh, now and then.
+    let shown = Shown::read(&pane, root.as_deref());
+    let window = shown
+        .as_ref()
+        .map_or_else(|| pane.clone(), |shown| shown.window.clone());
+    if let Some(shown) = shown.as_ref().filter(|shown| shown.is(icon)) {
+        debug!(pane, status = ?cmd, "set_window_status:unchanged");
+        if icon.is_some() && shown.is_due(now()) {
+            refresh_timestamps(&pane, &window);
+        }
+        return Ok(());
     }
 
-    match cmd {
-        SetWindowStatusCommand::Working => set_status(&pane, config.status_icons.working()),
-        SetWindowStatusCommand::Waiting => set_status(&pane, config.status_icons.waiting()),
-        SetWindowStatusCommand::Done => set_status(&pane, config.status_icons.done()),
-        SetWindowStatusCommand::Clear => clear_status(&pane),
+    if let Some(root) = &root {
+        let from = shown
+            .as_ref()
+            .and_then(|shown| shown.pane_status.as_deref());
+        record_change(root, from, cmd, &config);
     }
-}
 
-fn set_status(pane: &str, icon: &str) -> Result<()> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    let now_str = now.to_string();
+    // Ensure the status format is applied so the icon actually shows up.
+    // Once the window shows a status, an earlier call already did.
+    if config.status_format.unwrap_or(true)
+        && icon.is_some()
+        && shown
+            .as_ref()
+            .is_none_or(|shown| shown.window_status.is_none())
+    {
+        let _ = tmux::ensure_status_format(&window);
+    }
 
-    // 1. Set Window Option (for tmux status bar display)
-    // "Last write wins" behavior for the window icon
-    if let Err(e) = Cmd::new("tmux")
-        .args(&["set-option", "-w", "-t", pane, "@workmux_status", icon])
-        .run()
+    // Keep what the agent is waiting for until it moves on
+    let waiting = matches!(cmd, SetWindowStatusCommand::Waiting)
+        .then(|| tmux::capture_pane(&pane, WAITING_LINES))
+        .flatten()
+        .map(|output| WaitingReason::new(waiting_output(&output)));
+    if let Ok(cwd) = std::env::current_dir()
+        && let Err(e) = worktree_state::set_waiting(&cwd, waiting, &config)
     {
-        eprintln!("workmux: failed to set window status: {}", e);
+        debug!(error = %e, "set_window_status:failed to record waiting reason");
     }
-    let _ = Cmd::new("tmux")
+
+    let current_command = shown.map(|shown| shown.current_command);
+    match icon {
+        Some(icon) => set_status(&pane, &window, icon, current_command),
+        None => clear_status(&pane, &window),
+    }
+}
+
+/// The status a pane and the window to show it on show, read in a single tmux
+/// call
+#[derive(Debug, PartialEq)]
+struct Shown {
+    /// The window to show the status on: the window workmux created for the
+    /// worktree the agent runs in, wherever that window or the agent's pane
+    /// has since been moved. Falls back to the pane's own window.
+    window: String,
+    /// When the pane's status was last set or refreshed
+    status_ts: Option<u64>,
+    pane_status: Option<String>,
+    window_status: Option<String>,
+    /// The pane's foreground command when its status was set
+    stored_command: Option<String>,
+    current_command: String,
+}
+
+impl Shown {
+    /// Read the status of `pane`, which runs in `worktree`, if known
+    fn read(pane: &str, worktree: Option<&Path>) -> Option<Self> {
+        let tag = worktree
+            .and_then(Path::file_name)
+            .map(|handle| tmux::worktree_tag_format(&handle.to_string_lossy()))
+            .unwrap_or_default();
+        let windows = format!("#{{window_id}}|#{{@workmux_status}}|{}", tag);
+        let output = Cmd::new("tmux")
+            .args(&[
+                "display-message",
+                "-p",
+                "-t",
+                pane,
+                "#{@workmux_pane_status_ts}|#{@workmux_pane_status}|#{@workmux_pane_command}|#{window_id}|#{pane_current_command}",
+                ";",
+                "list-windows",
+                "-a",
+                "-F",
+                &windows,
+            ])
+            .run_and_capture_stdout()
+            .ok()?;
+        Self::parse(&output, worktree.map(tmux::tag_path).as_deref())
+    }
+
+    /// Parse the pane's line, then a `window_id|status|tag` line per window
+    fn parse(output: &str, worktree: Option<&Path>) -> Option<Self> {
+        let mut lines = output.lines();
+        let mut pane = lines.next()?.split('|');
+        let field = |value: Option<&str>| value.filter(|value| !value.is_empty()).map(String::from);
+        let status_ts = pane.next().and_then(|ts| ts.parse().ok());
+        let pane_status = field(pane.next());
+        let stored_command = field(pane.next());
+        let own = pane.next().unwrap_or_default();
+        let current_command = pane.next().unwrap_or_default().to_string();
+
+        let windows: Vec<(&str, &str, &str)> = lines
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '|');
+                Some((fields.next()?, fields.next()?, fields.next()?))
+            })
+            .collect();
+        let tagged = |(_, _, tag): &&(&str, &str, &str)| {
+            worktree.is_some_and(|worktree| Path::new(tag) == worktree)
+        };
+        let (window, window_status) = windows
+            .iter()
+            .filter(tagged)
+            .find(|(window_id, _, _)| *window_id == own)
+            .or_else(|| windows.iter().find(tagged))
+            .or_else(|| windows.iter().find(|(window_id, _, _)| *window_id == own))
+            .map(|(window_id, status, _)| (window_id.to_string(), field(Some(status))))?;
+        Some(Self {
+            window,
+            status_ts,
+            pane_status,
+            window_status,
+            stored_command,
+            current_command,
+        })
+    }
+
+    /// Whether setting `icon` (None to clear) would change nothing
+    fn is(&self, icon: Option<&str>) -> bool {
+        self.pane_status.as_deref() == icon
+            && self.window_status.as_deref() == icon
+            && (icon.is_none() || self.stored_command.as_deref() == Some(&self.current_command))
+    }
+
+    /// Whether the timestamps of the status are old enough to refresh at `now`
+    fn is_due(&self, now: u64) -> bool {
+        self.status_ts
+            .is_none_or(|ts| now.saturating_sub(ts) >= TIMESTAMP_REFRESH_SECS)
+    }
+}
+
+/// The last lines of a pane's output, without colors or trailing blank lines
+fn waiting_output(captured: &str) -> String {
+    let plain = console::strip_ansi_codes(captured);
+    let lines: Vec<&str> = plain.trim_end().lines().map(str::trim_end).collect();
+    lines[lines.len().saturating_sub(WAITING_LINES as usize)..].join("\n")
+}
+
+/// Show an agent as working again after workmux answered its prompt, until
+/// the agent's own hooks report its status.
+pub(super) fn mark_working(pane: &str, worktree: &Path, config: &Config) -> Result<()> {
+    if let Err(e) = worktree_state::set_waiting(worktree, None, config) {
+        debug!(error = %e, "set_window_status:failed to clear waiting reason");
+    }
+    let shown = Shown::read(pane, Some(worktree));
+    let window = shown
+        .as_ref()
+        .map_or_else(|| pane.to_string(), |shown| shown.window.clone());
+    let from = shown.and_then(|shown| shown.pane_status);
+    record_change(
+        worktree,
+        from.as_deref(),
+        SetWindowStatusCommand::Working,
+        config,
+    );
+    set_status(pane, &window, config.status_icons.working(), None)
+}
+
+/// Add the change from the pane's status, shown as `from`, to the activity feed
+fn record_change(
+    worktree: &Path,
+    from: Option<&str>,
+    cmd: SetWindowStatusCommand,
+    config: &Config,
+) {
+    let Some(handle) = worktree.file_name() else {
+        return;
+    };
+    let from = from.and_then(|icon| AgentStatus::from_icon(icon, &config.status_icons));
+    let to = match cmd {
+        SetWindowStatusCommand::Working => Some(AgentStatus::Working),
+        SetWindowStatusCommand::Waiting => Some(AgentStatus::Waiting),
+        SetWindowStatusCommand::Done => Some(AgentStatus::Done),
+        SetWindowStatusCommand::Clear => None,
+    };
+    activity::record(&handle.to_string_lossy(), from, to);
+}
+
+/// Show `icon` on the window (for the status bar) and the pane (for the
+/// dashboard), in one tmux call. `current_command` is the pane's foreground
+/// command if already known.
+fn set_status(pane: &str, window: &str, icon: &str, current_command: Option<String>) -> Result<()> {
+    let now = now().to_string();
+
+    // The pane option uses a DISTINCT key to avoid inheritance issues in list-panes
+    let mut args = vec![
+        "set-option",
+        "-w",
+        "-t",
+        window,
+        "@workmux_status",
+        icon,
+        ";",
+        "set-option",
+        "-w",
+        "-t",
+        window,
+        "@workmux_status_ts",
+        &now,
+        ";",
+        "set-option",
+        "-p",
+        "-t",
+        pane,
+        "@workmux_pane_status",
+        icon,
+        ";",
+        "set-option",
+        "-p",
+        "-t",
+        pane,
+        "@workmux_pane_status_ts",
+        &now,
+    ];
+
+    // Store the current foreground command for agent exit detection
+    // When the command changes (e.g., from "node" to "zsh"), we know the agent exited
+    let current_command =
+        current_command.unwrap_or_else(|| tmux::get_pane_current_command(pane).unwrap_or_default());
+    if !current_command.is_empty() {
+        args.extend([";", "set-option", "-p", "-t", pane, "@workmux_pane_command"]);
+        args.push(&current_command);
+    }
+
+    if let Err(e) = Cmd::new("tmux").args(&args).run() {
+        eprintln!("workmux: failed to set status: {}", e);
+    }
+    Ok(())
+}
+
+/// Mark the status the window and pane show as current, in one tmux call
+fn refresh_timestamps(pane: &str, window: &str) {
+    let now = now().to_string();
+    if let Err(e) = Cmd::new("tmux")
         .args(&[
             "set-option",
             "-w",
             "-t",
-            pane,
+            window,
             "@workmux_status_ts",
-            &now_str,
+            &now,
+            ";",
+            "set-option",
+            "-p",
+            "-t",
+            pane,
+            "@workmux_pane_status_ts",
+            &now,
         ])
-        .run();
-
-    // 2. Set Pane Option (for dashboard tracking)
-    // Use a DISTINCT key to avoid inheritance issues in list-panes
-    if let Err(e) = Cmd::new("tmux")
-        .args(&["set-option", "-p", "-t", pane, "@workmux_pane_status", icon])
         .run()
     {
-        eprintln!("workmux: failed to set pane status: {}", e);
+        debug!(error = %e, "set_window_status:failed to refresh timestamps");
     }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn clear_status(pane: &str, window: &str) -> Result<()> {
     let _ = Cmd::new("tmux")
         .args(&[
             "set-option",
-            "-p",
+            "-uw",
+            "-t",
+            window,
+            "@workmux_status",
+            ";",
+            "set-option",
+            "-uw",
+            "-t",
+            window,
+            "@workmux_status_ts",
+            ";",
+            "set-option",
+            "-up",
+            "-t",
+            pane,
+            "@workmux_pane_status",
+            ";",
+            "set-option",
+            "-up",
             "-t",
             pane,
             "@workmux_pane_status_ts",
-            &now_str,
+            ";",
+            "set-option",
+            "-up",
+            "-t",
+            pane,
+            "@workmux_pane_command",
         ])
         .run();
+    Ok(())
+}
 
-    // 3. Store the current foreground command for agent exit detection
-    // When the command changes (e.g., from "node" to "zsh"), we know the agent exited
-    let current_cmd = tmux::get_pane_current_command(pane).unwrap_or_default();
-    if !current_cmd.is_empty() {
-        let _ = Cmd::new("tmux")
-            .args(&[
-                "set-option",
-                "-p",
-                "-t",
-                pane,
-                "@workmux_pane_command",
-                &current_cmd,
-            ])
-            .run();
+#[cfg(test)]
+mod tests {
+    use super::{HookAgent, SetWindowStatusCommand::*, Shown, status_for_event};
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn gemini_events_map_to_statuses() {
+        let status = |event, payload| status_for_event(HookAgent::Gemini, event, &payload);
+        assert_eq!(status("BeforeAgent", json!({})), Some(Working));
+        assert_eq!(status("AfterTool", json!({})), Some(Working));
+        assert_eq!(
+            status(
+                "Notification",
+                json!({"notification_type": "ToolPermission"})
+            ),
+            Some(Waiting)
+        );
+        assert_eq!(status("Notification", json!({})), None);
+        assert_eq!(status("AfterAgent", json!({})), Some(Done));
+        assert_eq!(status("SessionEnd", json!({})), Some(Clear));
+        assert_eq!(status("BeforeTool", json!({})), None);
     }
 
-    Ok(())
-}
+    #[test]
+    fn copilot_events_map_to_statuses() {
+        let status = |event| status_for_event(HookAgent::Copilot, event, &json!({}));
+        assert_eq!(status("userPromptSubmitted"), Some(Working));
+        assert_eq!(status("postToolUse"), Some(Working));
+        assert_eq!(status("errorOccurred"), Some(Done));
+        assert_eq!(status("agentStop"), Some(Done));
+        assert_eq!(status("sessionEnd"), Some(Clear));
+        assert_eq!(status("preToolUse"), None);
+        // Event names are matched as the agent sends them
+        assert_eq!(status("AgentStop"), None);
+    }
 
-fn clear_status(pane: &str) -> Result<()> {
-    // Clear Window Options
-    let _ = Cmd::new("tmux")
-        .args(&["set-option", "-uw", "-t", pane, "@workmux_status"])
-        .run();
-    let _ = Cmd::new("tmux")
-        .args(&["set-option", "-uw", "-t", pane, "@workmux_status_ts"])
-        .run();
+    #[test]
+    fn unchanged_status_is_recognized() {
+        let shown = Shown::parse("100|🤖|node|@1|node\n@1|🤖|\n", None).unwrap();
+        assert_eq!(shown.current_command, "node");
+        assert!(shown.is(Some("🤖")));
+        assert!(!shown.is(Some("💬")));
+        assert!(!shown.is(None));
 
-    // Clear Pane Options
-    let _ = Cmd::new("tmux")
-        .args(&["set-option", "-up", "-t", pane, "@workmux_pane_status"])
-        .run();
-    let _ = Cmd::new("tmux")
-        .args(&["set-option", "-up", "-t", pane, "@workmux_pane_status_ts"])
-        .run();
-    let _ = Cmd::new("tmux")
-        .args(&["set-option", "-up", "-t", pane, "@workmux_pane_command"])
-        .run();
+        // The agent exited since, so the command needs storing again
+        assert!(
+            !Shown::parse("100|🤖|node|@1|zsh\n@1|🤖|\n", None)
+                .unwrap()
+                .is(Some("🤖"))
+        );
+        // Another pane of the window set the window's status
+        assert!(
+            !Shown::parse("|||@1|zsh\n@1|🤖|\n", None)
+                .unwrap()
+                .is(Some("🤖"))
+        );
+        assert!(Shown::parse("|||@1|zsh\n@1||\n", None).unwrap().is(None));
+        assert_eq!(Shown::parse("", None), None);
+    }
 
-    Ok(())
+    #[test]
+    fn unchanged_status_timestamps_are_refreshed_now_and_then() {
+        let shown = Shown::parse("1000|🤖|node|@1|node\n@1|🤖|\n", None).unwrap();
+        assert_eq!(shown.status_ts, Some(1000));
+        assert!(!shown.is_due(1000));
+        assert!(!shown.is_due(1029));
+        assert!(shown.is_due(1030));
+        // A status set before timestamps were recorded gets one
+        assert!(
+            Shown::parse("|🤖|node|@1|node\n@1|🤖|\n", None)
+                .unwrap()
+                .is_due(0)
+        );
+    }
+
+    #[test]
+    fn status_is_shown_on_the_window_of_the_worktree() {
+        let worktree = Some(Path::new("/w/api"));
+        let window = |output| Shown::parse(output, worktree).unwrap().window;
+        // The pane was moved out of the worktree's window
+        assert_eq!(window("|||@1|zsh\n@1||\n@2|🤖|/w/api\n@3||/w/web\n"), "@2");
+        assert_eq!(
+            Shown::parse("|||@1|zsh\n@1||\n@2|🤖|/w/api\n", worktree)
+                .unwrap()
+                .window_status,
+            Some("🤖".to_string())
+        );
+        // Of several windows of the worktree, the pane's own is used
+        assert_eq!(window("|||@3|zsh\n@2||/w/api\n@3||/w/api\n"), "@3");
+        // An untagged window shows the status of its panes
+        assert_eq!(window("|||@1|zsh\n@1||\n@2||/w/web\n"), "@1");
+        assert_eq!(
+            Shown::parse("|||@1|zsh\n@1||\n@2||/w/api\n", None)
+                .unwrap()
+                .window,
+            "@1"
+        );
+    }
 }
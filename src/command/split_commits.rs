@@ -0,0 +1,67 @@
+use crate::config::{self, ConfirmPolicy};
+use crate::workflow::{self, SplitSource, WorkflowContext};
+use anyhow::{Context, Result};
+
+pub fn run(name: &str, suggest: bool, model: Option<&str>, dry_run: bool, yes: bool) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+
+    let plan =
+        workflow::plan_split(name, &context, suggest, model).context("Failed to plan the split")?;
+
+    if let Some(error) = &plan.plan_error {
+        eprintln!(
+            "Not using the suggested split ({}); grouping by directory instead",
+            error
+        );
+    }
+    let what = match &plan.source {
+        SplitSource::Uncommitted => "the uncommitted changes".to_string(),
+        SplitSource::LastCommit { hash, subject } => {
+            format!("the last commit ({} {})", hash, subject)
+        }
+    };
+    if plan.groups.len() < 2 {
+        println!(
+            "Nothing to split: {} on '{}' would make a single commit{}",
+            what,
+            plan.branch,
+            if suggest { "" } else { " (try --suggest)" }
+        );
+        return Ok(());
+    }
+
+    println!(
+        "Splitting {} on '{}' into {} commits:",
+        what,
+        plan.branch,
+        plan.groups.len()
+    );
+    for (index, group) in plan.groups.iter().enumerate() {
+        println!("  {}. {}", index + 1, group.message);
+        for file in &group.files {
+            println!("       {}", file);
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let policy = if yes {
+        ConfirmPolicy::Skip
+    } else {
+        ConfirmPolicy::Ask
+    };
+    if !super::confirm("Make these commits?", policy)? {
+        return Ok(());
+    }
+    workflow::apply_split(&plan)?;
+    println!(
+        "✓ Made {} commits on '{}'. Run 'workmux tidy {}' to reword or reorder them.",
+        plan.groups.len(),
+        plan.branch,
+        name
+    );
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use crate::config::Config;
+use crate::{git, state_encryption, worktree_state};
+use anyhow::{Context, Result, anyhow};
+
+/// Read the identity that decrypts worktree state, so `status`, `list --why`
+/// and the dashboard show prompts and agent output again
+pub fn unlock() -> Result<()> {
+    let config = Config::load(None)?;
+    let settings = config
+        .state_encryption
+        .ok_or_else(|| anyhow!("state_encryption is not configured"))?;
+    let path = state_encryption::unlock(&settings)?;
+    println!(
+        "✓ Unlocked worktree state until 'workmux state lock' ({})",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Forget the identity read by `unlock`
+pub fn lock() -> Result<()> {
+    if state_encryption::lock()? {
+        println!("✓ Locked worktree state");
+    } else {
+        println!("Worktree state is not unlocked");
+    }
+    Ok(())
+}
+
+/// Encrypt what worktrees recorded before encryption was turned on
+pub fn encrypt() -> Result<()> {
+    let config = Config::load(None)?;
+    if state_encryption::settings(&config).is_none() {
+        return Err(anyhow!(
+            "Set state_encryption.recipients to the age keys to encrypt to first"
+        ));
+    }
+    let main_root = git::get_main_worktree_root()?;
+    let mut encrypted = 0;
+    for (path, _) in git::list_worktrees()? {
+        if path == main_root {
+            continue;
+        }
+        let Some(handle) = path.file_name().map(|name| name.to_string_lossy()) else {
+            continue;
+        };
+        if worktree_state::encrypt_existing(&handle, &config)
+            .with_context(|| format!("Failed to encrypt the state of '{}'", handle))?
+        {
+            println!("  encrypted {}", handle);
+            encrypted += 1;
+        }
+    }
+    if encrypted == 0 {
+        println!("No worktree state left to encrypt");
+    } else {
+        println!("✓ Encrypted the state of {} worktree(s)", encrypted);
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::worktree_state::WorktreeState;
+use crate::{git, worktree_state};
+use anyhow::{Result, anyhow};
+
+pub fn run(name: Option<&str>) -> Result<()> {
+    let name = super::resolve_name(name)?;
+    let (path, branch) = git::find_worktree(&name)?;
+    let handle = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    println!("{}", handle);
+    print_row("branch", &branch);
+    print_row("path", &path.display().to_string());
+    // An unfinished rebase or merge shows whatever state is recorded
+    if let Some(operation) = git::in_progress_operation(&path) {
+        print_row(
+            "state",
+            &format!(
+                "{} ('{}' abandons it)",
+                operation.state(),
+                operation.abort_command()
+            ),
+        );
+    }
+
+    let Some(state) = worktree_state::load(&handle)?.map(WorktreeState::revealed) else {
+        // Worktrees created before state was recorded still have their base
+        if let Ok(base) = git::get_branch_base(&branch) {
+            print_row("base", &base);
+        }
+        println!("\nNo state recorded for '{}'.", handle);
+        return Ok(());
+    };
+
+    if let Some(task) = &state.task {
+        print_row("task", task);
+    }
+    if let Some(base) = &state.base {
+        print_row("base", base);
+    }
+    if let Some(agent) = &state.agent {
+        print_row("agent", agent);
+    }
+    if let Some(pr) = state.pr {
+        print_row("pr", &format!("#{}", pr));
+    }
+    if let Some(prompt_file) = &state.prompt_file {
+        print_row("prompt", prompt_file);
+    }
+    if let Some(created_at) = state.created_at {
+        print_row(
+            "created",
+            &format!("{} UTC", super::history::format_timestamp(created_at)),
+        );
+    }
+    if state.pinned {
+        print_row("pinned", "yes (workmux unpin to release)");
+    }
+    if let Some(prompt) = &state.prompt {
+        println!("\nPrompt:");
+        for line in prompt.trim_end().lines() {
+            println!("  {}", line);
+        }
+    }
+    if let Some(waiting) = &state.waiting {
+        println!(
+            "\nWaiting for input since {} UTC:",
+            super::history::format_timestamp(waiting.since)
+        );
+        for line in waiting.output.lines() {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn print_row(label: &str, value: &str) {
+    println!("  {:<10}{}", label, value);
+}
@@ -0,0 +1,35 @@
+use crate::workflow::{self, WorkflowContext};
+use crate::{config, dry_run};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+pub fn run(
+    name: &str,
+    base: Option<&str>,
+    model: Option<&str>,
+    output: Option<&Path>,
+) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+
+    let result =
+        workflow::summarize(name, &context, base, model).context("Failed to summarize worktree")?;
+
+    match output {
+        Some(path) if dry_run::skip(format_args!("write the summary to {}", path.display())) => {}
+        Some(path) => {
+            fs::write(path, format!("{}\n", result.summary))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "✓ Summarized '{}' against '{}'\n  Summary: {}",
+                result.branch,
+                result.base,
+                path.display()
+            );
+        }
+        None => println!("{}", result.summary),
+    }
+
+    Ok(())
+}
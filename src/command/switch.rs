@@ -0,0 +1,89 @@
+use crate::config;
+use crate::workflow::{self, SetupOptions, SwitchCandidate, WorkflowContext};
+use anyhow::{Context, Result, anyhow};
+use std::io::IsTerminal;
+
+pub fn run(query: Option<&str>) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+    context.ensure_tmux_running()?;
+
+    let candidates = workflow::switch_candidates(&context)?;
+    if candidates.is_empty() {
+        return Err(anyhow!("No worktrees to switch to"));
+    }
+
+    let query = query.unwrap_or_default();
+    let target = match workflow::best_match(query, &candidates) {
+        Some(candidate) => candidate,
+        None => {
+            let ranked = workflow::fuzzy_rank(query, &candidates);
+            if ranked.is_empty() {
+                return Err(anyhow!("No worktree matches '{}'", query));
+            }
+            choose(query, &ranked)?
+        }
+    };
+
+    // Pane commands run if the window has to be created
+    let options = SetupOptions::new(false, false, true);
+    let result = workflow::open(&target.handle, &context, options, false)
+        .context("Failed to open worktree environment")?;
+
+    let action = if result.did_switch {
+        "Switched to"
+    } else {
+        "Opened"
+    };
+    println!(
+        "✓ {} '{}' ({})",
+        action,
+        target.handle,
+        result.worktree_path.display()
+    );
+    Ok(())
+}
+
+/// Ask which of several matches to switch to
+fn choose<'a>(query: &str, ranked: &[&'a SwitchCandidate]) -> Result<&'a SwitchCandidate> {
+    if !std::io::stdin().is_terminal() {
+        let handles: Vec<&str> = ranked.iter().map(|c| c.handle.as_str()).collect();
+        if query.is_empty() {
+            return Err(anyhow!(
+                "Pass a query to pick a worktree: {}",
+                handles.join(", ")
+            ));
+        }
+        return Err(anyhow!(
+            "'{}' matches several worktrees: {}",
+            query,
+            handles.join(", ")
+        ));
+    }
+
+    for (i, candidate) in ranked.iter().enumerate() {
+        let window = if candidate.has_window {
+            ""
+        } else {
+            "  (no window)"
+        };
+        if candidate.branch == candidate.handle {
+            println!("{:>3}) {}{}", i + 1, candidate.handle, window);
+        } else {
+            println!(
+                "{:>3}) {} [{}]{}",
+                i + 1,
+                candidate.handle,
+                candidate.branch,
+                window
+            );
+        }
+    }
+    let answer = super::ask(&format!("Switch to [1-{}]:", ranked.len()))?;
+    answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| ranked.get(i).copied())
+        .ok_or_else(|| anyhow!("No worktree selected"))
+}
@@ -0,0 +1,199 @@
+use crate::command::args::MatchArgs;
+use crate::config::{self, SyncStrategy};
+use crate::{git, offline, spinner, worktree_state};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// What syncing did to one worktree
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// Brought in this many new commits from the main branch
+    Updated(usize),
+    UpToDate,
+    /// Stopped on conflicts in these files, and was aborted
+    Conflict(Vec<String>),
+    Skipped(String),
+    Failed(String),
+}
+
+impl Outcome {
+    fn line(&self, handle: &str, onto: &str, strategy: SyncStrategy) -> String {
+        let (verb, aborted) = match strategy {
+            SyncStrategy::Rebase => ("rebased onto", "rebase aborted"),
+            SyncStrategy::Merge => ("merged", "merge aborted"),
+        };
+        match self {
+            Self::Updated(commits) => format!(
+                "✓ {}: {} {} ({} new commit{})",
+                handle,
+                verb,
+                onto,
+                commits,
+                if *commits == 1 { "" } else { "s" }
+            ),
+            Self::UpToDate => format!("- {}: up to date", handle),
+            Self::Conflict(files) if files.is_empty() => {
+                format!("✗ {}: conflicts ({})", handle, aborted)
+            }
+            Self::Conflict(files) => format!(
+                "✗ {}: conflicts in {} ({})",
+                handle,
+                files.join(", "),
+                aborted
+            ),
+            Self::Skipped(reason) => format!("- {}: skipped, {}", handle, reason),
+            Self::Failed(error) => format!("✗ {}: {}", handle, error),
+        }
+    }
+
+    fn is_failure(&self) -> bool {
+        matches!(self, Self::Conflict(_) | Self::Failed(_))
+    }
+}
+
+pub fn run(
+    names: Vec<String>,
+    rebase: bool,
+    merge: bool,
+    autostash: bool,
+    no_fetch: bool,
+    matching: MatchArgs,
+) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let remote = config.default_remote();
+    let strategy = if rebase {
+        SyncStrategy::Rebase
+    } else if merge {
+        SyncStrategy::Merge
+    } else {
+        config.sync_strategy.unwrap_or_default()
+    };
+
+    let main_branch = git::get_default_branch(remote)?;
+    let main_worktree_root = git::get_main_worktree_root()?;
+    let all = names.is_empty();
+    let targets: Vec<(PathBuf, String)> = if all {
+        git::list_worktrees()?
+            .into_iter()
+            .filter(|(path, branch)| {
+                *path != main_worktree_root && *branch != main_branch && branch != "(detached)"
+            })
+            .collect()
+    } else {
+        names
+            .iter()
+            .map(|name| git::find_worktree(&super::resolve_match(name, matching.mode())?))
+            .collect::<Result<_>>()?
+    };
+    if targets.is_empty() {
+        println!("No worktrees to sync.");
+        return Ok(());
+    }
+
+    if no_fetch {
+        println!("Skipping fetch; using existing remote-tracking refs.");
+    } else if offline::is_enabled() {
+        println!("Offline: not fetching; using existing remote-tracking refs.");
+    } else if config.should_fetch(config::FetchTrigger::Sync) && git::remote_exists(remote)? {
+        spinner::with_spinner(&format!("Fetching from '{}'", remote), || {
+            git::fetch_remote(remote)
+        })?;
+    }
+
+    // The remote-tracking main branch when there is one, as `list` compares with
+    let onto = git::get_merge_base(&main_branch, remote)?;
+    println!("Syncing {} worktree(s) with {}:", targets.len(), onto);
+
+    let mut failures = 0;
+    for (path, branch) in targets {
+        let handle = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| branch.clone());
+        let outcome = if path == main_worktree_root || branch == main_branch {
+            Outcome::Skipped("it is the main worktree".to_string())
+        } else if branch == "(detached)" {
+            Outcome::Skipped("detached HEAD".to_string())
+        } else if all && worktree_state::is_pinned(&handle) {
+            Outcome::Skipped("pinned".to_string())
+        } else {
+            sync_worktree(&path, &branch, &onto, strategy, autostash)
+        };
+        if outcome.is_failure() {
+            failures += 1;
+        }
+        println!("  {}", outcome.line(&handle, &onto, strategy));
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("{} worktree(s) could not be synced", failures));
+    }
+    Ok(())
+}
+
+fn sync_worktree(
+    path: &Path,
+    branch: &str,
+    onto: &str,
+    strategy: SyncStrategy,
+    autostash: bool,
+) -> Outcome {
+    if let Some(operation) = git::in_progress_operation(path) {
+        return Outcome::Skipped(format!("{} in progress", operation.state()));
+    }
+    // Untracked files don't stop a rebase or merge, unless they conflict
+    if !autostash && git::has_tracked_changes(path).unwrap_or(false) {
+        return Outcome::Skipped("uncommitted changes (use --autostash)".to_string());
+    }
+    let behind = match git::ahead_behind(onto, branch) {
+        Ok((_, 0)) => return Outcome::UpToDate,
+        Ok((_, behind)) => behind,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let rebase = strategy == SyncStrategy::Rebase;
+    match git::update_worktree_from(path, onto, rebase, autostash) {
+        Ok(()) => Outcome::Updated(behind),
+        // Stopped on conflicts: leave the worktree as it was, so its agent
+        // can keep working
+        Err(_) if git::in_progress_operation(path).is_some() => {
+            let files = git::get_conflicted_files(path).unwrap_or_default();
+            let aborted = if rebase {
+                git::abort_rebase_in_worktree(path)
+            } else {
+                git::abort_merge_in_worktree(path)
+            };
+            match aborted {
+                Ok(()) => Outcome::Conflict(files),
+                Err(e) => Outcome::Failed(format!("conflicts, and {:#}", e)),
+            }
+        }
+        Err(e) => Outcome::Failed(format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, SyncStrategy};
+
+    #[test]
+    fn outcome_lines_name_the_strategy() {
+        let line = |outcome: Outcome, strategy| outcome.line("api", "origin/main", strategy);
+        assert_eq!(
+            line(Outcome::Updated(1), SyncStrategy::Rebase),
+            "✓ api: rebased onto origin/main (1 new commit)"
+        );
+        assert_eq!(
+            line(Outcome::Updated(3), SyncStrategy::Merge),
+            "✓ api: merged origin/main (3 new commits)"
+        );
+        assert_eq!(
+            line(
+                Outcome::Conflict(vec!["src/lib.rs".to_string()]),
+                SyncStrategy::Rebase
+            ),
+            "✗ api: conflicts in src/lib.rs (rebase aborted)"
+        );
+        assert!(!Outcome::Skipped("detached HEAD".to_string()).is_failure());
+    }
+}
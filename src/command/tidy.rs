@@ -0,0 +1,27 @@
+use crate::config;
+use crate::workflow::{self, WorkflowContext};
+use anyhow::{Context, Result};
+
+pub fn run(name: &str, base: Option<&str>, suggest: bool, model: Option<&str>) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let context = WorkflowContext::new(config)?;
+
+    let result = workflow::tidy(name, &context, base, suggest, model)
+        .context("Failed to start tidying worktree")?;
+
+    if let Some(error) = &result.plan_error {
+        eprintln!("Not using the suggested plan: {}", error);
+    }
+    println!(
+        "✓ Opened an interactive rebase of {} commit(s) on '{}' since '{}'",
+        result.commits, result.branch, result.base
+    );
+    if let Some(plan) = &result.plan {
+        println!("  Suggested plan, to review in the editor:");
+        for line in plan.lines() {
+            println!("    {}", line);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::{dry_run, release, spinner};
+use anyhow::{Context, Result, anyhow};
+use std::{env, fs, process};
+
+pub fn run(check: bool) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = spinner::with_spinner("Checking for updates", release::latest)?;
+
+    if !release::is_newer(latest.version(), current) {
+        println!("✓ workmux {} is the latest version", current);
+        return Ok(());
+    }
+    if check {
+        println!(
+            "workmux {} is available (installed: {})",
+            latest.version(),
+            current
+        );
+        println!("Run 'workmux upgrade' to install it.");
+        return Ok(());
+    }
+
+    let exe = env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .context("Failed to locate the workmux executable")?;
+    if let Some(command) = release::package_manager(&exe) {
+        return Err(anyhow!(
+            "{} is managed by a package manager. Run '{}' instead.",
+            exe.display(),
+            command
+        ));
+    }
+
+    if dry_run::skip(format_args!(
+        "replace {} with workmux {}",
+        exe.display(),
+        latest.version()
+    )) {
+        return Ok(());
+    }
+    let dir = env::temp_dir().join(format!("workmux-upgrade-{}", process::id()));
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let result =
+        spinner::with_spinner(&format!("Downloading workmux {}", latest.version()), || {
+            release::download(&latest, &dir)
+        })
+        .and_then(|binary| release::install(&binary, &exe));
+    let _ = fs::remove_dir_all(&dir);
+    result?;
+
+    println!(
+        "✓ Upgraded workmux {} -> {} ({})",
+        current,
+        latest.version(),
+        exe.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::{cmd, config, logger, offline, remote};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Command;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn run(verbose: bool) -> Result<()> {
+    if !verbose {
+        println!("workmux {}", VERSION);
+        return Ok(());
+    }
+
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    println!(
+        "workmux {} ({}-{}, {} build)",
+        VERSION,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        profile
+    );
+
+    // git, tmux and the agent run on the --host machine, gh and llm locally
+    let loaded = config::Config::load(None);
+    let config = loaded.as_ref().cloned().unwrap_or_default();
+    let agent = config.agent.as_deref().unwrap_or("claude");
+    let agent_executable = config::split_first_token(agent).map_or(agent, |(first, _)| first);
+    println!("\nTools:");
+    print_row(
+        "git",
+        tool_version("git", &["--version"], true),
+        "git version ",
+    );
+    print_row("tmux", tool_version("tmux", &["-V"], true), "tmux ");
+    print_row(
+        "gh",
+        tool_version("gh", &["--version"], false),
+        "gh version ",
+    );
+    print_row(
+        "llm",
+        tool_version("llm", &["--version"], false),
+        "llm, version ",
+    );
+    print_row(
+        &format!("agent ({})", agent_executable),
+        tool_version(agent_executable, &["--version"], true),
+        "",
+    );
+
+    println!("\nModes:");
+    println!(
+        "  {:<16}{}",
+        "offline",
+        if offline::is_enabled() { "on" } else { "off" }
+    );
+    match remote::get() {
+        Some(remote) => println!("  {:<16}{}:{}", "host", remote.host, remote.dir.display()),
+        None => println!("  {:<16}local", "host"),
+    }
+
+    println!("\nPaths:");
+    print_path("global config", config::global_config_path());
+    print_path("project config", config::project_config_path());
+    print_path("local config", config::local_config_path());
+    print_path("log file", logger::log_path().ok());
+    print_path("state dir", logger::state_dir().ok());
+    if let Err(e) = loaded {
+        println!("\nConfig error: {:#}", e);
+    }
+
+    Ok(())
+}
+
+/// First line of `<tool> <args>`, or None if the tool can't be run
+pub(super) fn tool_version(tool: &str, args: &[&str], on_host: bool) -> Option<String> {
+    let stdout = if on_host {
+        cmd::Cmd::new(tool)
+            .args(args)
+            .run_and_capture_stdout()
+            .ok()?
+    } else {
+        let output = Command::new(tool).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    stdout.lines().next().map(|line| line.trim().to_string())
+}
+
+fn print_row(label: &str, version: Option<String>, prefix: &str) {
+    let value = version.map_or_else(
+        || "not found".to_string(),
+        |version| version.trim_start_matches(prefix).to_string(),
+    );
+    println!("  {:<16}{}", label, value);
+}
+
+fn print_path(label: &str, path: Option<PathBuf>) {
+    let value = path.map_or_else(|| "none".to_string(), |path| path.display().to_string());
+    println!("  {:<16}{}", label, value);
+}
@@ -0,0 +1,120 @@
+use crate::config::{self, HandleMatching, WorktreeNaming};
+use crate::workflow::{self, NameMatch};
+use crate::{git, tmux};
+use anyhow::{Result, anyhow};
+
+pub fn run(name: &str) -> Result<()> {
+    let config = config::Config::load(None)?;
+    let resolution = workflow::which(name, &config)?;
+
+    println!("Resolving '{}':", name);
+    match resolution.handle_matches.as_slice() {
+        [] => println!("  handle: no worktree directory is named '{}'", name),
+        [(path, branch), rest @ ..] => {
+            println!("  handle: {} (branch '{}')", path.display(), branch);
+            for (path, _) in rest {
+                println!("          {} (same directory name)", path.display());
+            }
+        }
+    }
+    match &resolution.branch_match {
+        None => println!("  branch: no worktree has '{}' checked out", name),
+        Some((path, _)) => {
+            println!("  branch: {}", path.display());
+        }
+    }
+    let partial = match config.handle_matching() {
+        HandleMatching::Exact => None,
+        HandleMatching::Prefix => Some("prefix"),
+        HandleMatching::Substring => Some("substring"),
+    };
+    if let Some(mode) = partial
+        && resolution.handle_matches.is_empty()
+        && resolution.branch_match.is_none()
+    {
+        match resolution.partial_matches.as_slice() {
+            [] => println!("  {}: no handle matches '{}'", mode, name),
+            [(path, _), rest @ ..] => {
+                println!("  {}: {}", mode, path.display());
+                for (path, _) in rest {
+                    println!("  {}  {}", " ".repeat(mode.len()), path.display());
+                }
+            }
+        }
+    }
+
+    let derivation = &resolution.derivation;
+    let naming = match derivation.naming {
+        WorktreeNaming::Full => "full",
+        WorktreeNaming::Basename => "basename",
+    };
+    println!("\nHandle for a new worktree on branch '{}':", name);
+    println!("  worktree_naming: {} -> '{}'", naming, derivation.named);
+    match &derivation.prefix {
+        Some(prefix) => println!(
+            "  worktree_prefix: '{}' -> '{}{}'",
+            prefix, prefix, derivation.named
+        ),
+        None => println!("  worktree_prefix: none"),
+    }
+    match &derivation.handle {
+        Some(handle) => println!("  handle (slugified): '{}'", handle),
+        None => println!("  handle: not a valid handle"),
+    }
+
+    if resolution.is_ambiguous() {
+        let hint = if resolution.partial_matches.is_empty() {
+            "Use --by-handle or --by-branch to pick one."
+        } else {
+            "Give more of the handle to pick one."
+        };
+        println!(
+            "\n'{}' is ambiguous: commands will ask which worktree you mean, or fail \
+             when not run from a terminal. {}",
+            name, hint
+        );
+        return Err(anyhow!("'{}' matches more than one worktree", name));
+    }
+
+    let Some((path, branch, matched)) = resolution.resolved() else {
+        if let Some((path, _)) = &resolution.derived_match {
+            println!(
+                "\nNo match for '{}', but the derived handle is a worktree: {}",
+                name,
+                path.display()
+            );
+        }
+        return Err(anyhow!(git::WorktreeNotFound(name.to_string())));
+    };
+
+    let handle = path
+        .file_name()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let prefix = config.window_prefix();
+    let window_state = if !tmux::is_running().unwrap_or(false) {
+        "tmux not running"
+    } else if tmux::window_exists(prefix, &handle)? {
+        "open"
+    } else {
+        "not open"
+    };
+
+    println!(
+        "\nResolved by {}:",
+        match matched {
+            NameMatch::Handle => "handle",
+            NameMatch::Branch => "branch name",
+            NameMatch::Partial => "partial handle",
+        }
+    );
+    println!("  worktree: {}", path.display());
+    println!("  branch:   {}", branch);
+    println!(
+        "  window:   {} ({})",
+        tmux::prefixed(prefix, &handle),
+        window_state
+    );
+
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::debug;
 
-use crate::{cmd, git};
+use crate::{cmd, git, preset, remote};
 use which::{which, which_in};
 
 /// Default script for cleaning up node_modules directories before worktree deletion.
@@ -17,11 +19,166 @@ const NODE_MODULES_CLEANUP_SCRIPT: &str = include_str!("scripts/cleanup_node_mod
 pub struct FileConfig {
     /// Glob patterns for files to copy from the repo root to the new worktree
     #[serde(default)]
-    pub copy: Option<Vec<String>>,
+    pub copy: Option<Vec<FileOp>>,
 
     /// Glob patterns for files to symlink from the repo root into the new worktree
     #[serde(default)]
-    pub symlink: Option<Vec<String>>,
+    pub symlink: Option<Vec<FileOp>>,
+
+    /// Templates rendered into the new worktree with its variables
+    #[serde(default)]
+    pub render: Option<Vec<RenderOp>>,
+
+    /// Glob patterns for paths `copy` and `symlink` leave out, including
+    /// files inside copied directories
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+}
+
+impl FileConfig {
+    /// The operations whose `when` condition holds for the new worktree
+    pub fn for_worktree(&self, target: &WhenTarget) -> FileConfig {
+        let select = |ops: &Option<Vec<FileOp>>| {
+            ops.as_ref().map(|ops| {
+                ops.iter()
+                    .filter(|op| op.applies_to(target))
+                    .cloned()
+                    .collect()
+            })
+        };
+        FileConfig {
+            copy: select(&self.copy),
+            symlink: select(&self.symlink),
+            render: self.render.as_ref().map(|ops| {
+                ops.iter()
+                    .filter(|op| op.when.as_ref().is_none_or(|when| when.matches(target)))
+                    .cloned()
+                    .collect()
+            }),
+            exclude: self.exclude.clone(),
+        }
+    }
+}
+
+/// A `files.render` entry: a template in the repository, rendered with the
+/// new worktree's variables (branch, prompt, `--foreach` values) into it
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RenderOp {
+    /// Template file, relative to the repository root
+    pub template: String,
+    /// File to write, relative to the worktree (defaults to `template`)
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub when: Option<Condition>,
+}
+
+impl RenderOp {
+    pub fn destination(&self) -> &str {
+        self.to.as_deref().unwrap_or(&self.template)
+    }
+}
+
+/// A `files.copy` or `files.symlink` entry: a glob pattern, or a pattern that
+/// only applies `when` the new worktree matches
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FileOp {
+    Pattern(String),
+    Conditional { pattern: String, when: Condition },
+}
+
+impl FileOp {
+    pub fn pattern(&self) -> &str {
+        match self {
+            Self::Pattern(pattern) | Self::Conditional { pattern, .. } => pattern,
+        }
+    }
+
+    pub fn applies_to(&self, target: &WhenTarget) -> bool {
+        match self {
+            Self::Pattern(_) => true,
+            Self::Conditional { when, .. } => when.matches(target),
+        }
+    }
+}
+
+/// A `when` condition on a hook or file operation. Every condition given must
+/// hold; each takes one value or a list, and a list matches if any entry does.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// Agents the worktree runs, matched by executable name like `panes`
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub agent: Option<Vec<String>>,
+    /// Glob patterns for the branch name (`feat/*`)
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub branch: Option<Vec<String>>,
+    /// Glob patterns for files in the worktree (`package.json`)
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub exists: Option<Vec<String>>,
+}
+
+/// The worktree a `when` condition is checked against
+pub struct WhenTarget<'a> {
+    pub branch: &'a str,
+    pub agent: Option<&'a str>,
+    pub worktree_path: &'a Path,
+}
+
+impl Condition {
+    pub fn matches(&self, target: &WhenTarget) -> bool {
+        let agent_matches = self.agent.as_ref().is_none_or(|agents| {
+            target
+                .agent
+                .is_some_and(|agent| agents.iter().any(|want| is_agent_command(want, agent)))
+        });
+        let branch_matches = self.branch.as_ref().is_none_or(|globs| {
+            globs.iter().any(|glob| {
+                glob::Pattern::new(glob).is_ok_and(|pattern| pattern.matches(target.branch))
+            })
+        });
+        let exists_matches = self.exists.as_ref().is_none_or(|globs| {
+            globs.iter().any(|glob| {
+                let pattern = target.worktree_path.join(glob);
+                glob::glob(&pattern.to_string_lossy())
+                    .is_ok_and(|mut paths| paths.any(|path| path.is_ok()))
+            })
+        });
+        agent_matches && branch_matches && exists_matches
+    }
+}
+
+/// Accept a single string where a list of strings is expected
+fn one_or_many<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(
+        Option::<OneOrMany>::deserialize(deserializer)?.map(|value| match value {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        }),
+    )
+}
+
+impl AsRef<str> for FileOp {
+    fn as_ref(&self) -> &str {
+        self.pattern()
+    }
+}
+
+impl std::fmt::Display for FileOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.pattern())
+    }
 }
 
 /// Configuration for agent status icons displayed in tmux window bar
@@ -61,6 +218,490 @@ pub struct AutoNameConfig {
     pub system_prompt: Option<String>,
 }
 
+/// Configuration for resolving `workmux add --pr <number>`
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct PrConfig {
+    /// Code host the number refers to (defaults to auto)
+    #[serde(default)]
+    pub provider: Option<PrProvider>,
+
+    /// Hosts of self-hosted GitLab instances, for auto-detection when the
+    /// host name doesn't contain "gitlab"
+    #[serde(default)]
+    pub gitlab_hosts: Option<Vec<String>>,
+
+    /// Panes of worktrees checked out with `add --pr --review`, instead of
+    /// `panes`
+    #[serde(default)]
+    pub review_panes: Option<Panes>,
+}
+
+impl PrConfig {
+    pub fn provider(&self) -> PrProvider {
+        self.provider.unwrap_or_default()
+    }
+
+    pub fn gitlab_hosts(&self) -> &[String] {
+        self.gitlab_hosts.as_deref().unwrap_or_default()
+    }
+}
+
+/// Encrypting the prompt and captured agent output in worktree state
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct StateEncryptionConfig {
+    /// age recipients (`age1...` or SSH public keys) the state is encrypted
+    /// to. Encryption is off while this is empty.
+    pub recipients: Option<Vec<String>>,
+    /// Where `workmux state unlock` reads the identity that decrypts it: an
+    /// age key file, passphrase-protected or not, or `keyring`
+    pub identity: Option<String>,
+}
+
+impl StateEncryptionConfig {
+    pub fn recipients(&self) -> &[String] {
+        self.recipients.as_deref().unwrap_or_default()
+    }
+}
+
+/// How workmux uses tmux
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TmuxConfig {
+    /// Where worktrees open. Default: windows
+    pub layout: Option<TmuxLayout>,
+}
+
+/// Where workmux opens each worktree
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TmuxLayout {
+    /// A window in the current session
+    #[default]
+    Windows,
+    /// A session of its own, named like the window would be
+    Sessions,
+}
+
+/// Code host that `add --pr` looks numbers up on
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PrProvider {
+    /// Detect from the default remote's URL
+    #[default]
+    Auto,
+    /// GitHub pull requests, via `gh`
+    Github,
+    /// GitLab merge requests, via `glab`
+    Gitlab,
+}
+
+/// Configuration for reviewer agents launched by `workmux review-with-agent`
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ReviewConfig {
+    /// Agent command for the reviewer (defaults to the configured agent)
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// File the reviewer writes its findings to, relative to the worktree.
+    /// Defaults to "REVIEW.md".
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Where to run the reviewer: a split pane in the worktree's window
+    /// (default) or a separate window
+    #[serde(default)]
+    pub open_in: Option<ReviewTarget>,
+
+    /// Split direction when running in a pane (defaults to horizontal)
+    #[serde(default)]
+    pub split: Option<SplitDirection>,
+}
+
+impl ReviewConfig {
+    /// Get the findings file name, defaulting to "REVIEW.md"
+    pub fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or("REVIEW.md")
+    }
+}
+
+/// Where `workmux review-with-agent` runs the reviewer agent
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewTarget {
+    /// Split the worktree's window and run the reviewer beside the author
+    #[default]
+    Pane,
+    /// Open a separate `<handle>-review` window
+    Window,
+}
+
+/// An agent defined under `agents:`. Its name can be used wherever an agent
+/// command can (`agent`, `--agent`, team members, `review.agent`), and a
+/// definition named like a built-in agent changes how workmux runs that agent.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AgentDefinition {
+    /// Command that starts the agent, with any options (defaults to the name)
+    #[serde(default)]
+    pub command: Option<String>,
+
+    /// How the agent is given the prompt (defaults to `arg`)
+    #[serde(default)]
+    pub prompt: Option<PromptInput>,
+
+    /// Argument put before the prompt text or file. Defaults to `--` for
+    /// `arg` and nothing for `file`.
+    #[serde(default)]
+    pub prompt_flag: Option<String>,
+
+    /// How the agent's status reaches the tmux window list
+    #[serde(default)]
+    pub status: Option<StatusTracking>,
+}
+
+/// How an agent is given the prompt it starts with
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptInput {
+    /// The prompt text as an argument: `agent <flag> "$(cat PROMPT.md)"`
+    #[default]
+    Arg,
+    /// The prompt file's path as an argument: `agent <flag> PROMPT.md`
+    File,
+    /// The prompt file on standard input: `agent < PROMPT.md`
+    Stdin,
+}
+
+/// How an agent's status reaches the tmux window list
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusTracking {
+    /// The agent's hooks run `workmux set-window-status` (see status tracking)
+    Hooks,
+    /// Working while the agent command runs, done once it exits
+    Exit,
+    /// No status is shown
+    None,
+}
+
+/// Whether an action asks for confirmation, and what pressing Enter means
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmPolicy {
+    /// Ask first; an empty answer aborts (`[y/N]`)
+    #[default]
+    Ask,
+    /// Ask first; an empty answer proceeds (`[Y/n]`)
+    AskDefaultYes,
+    /// Proceed without asking
+    Skip,
+}
+
+/// Which destructive actions ask for confirmation before running
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ConfirmConfig {
+    /// Removing worktrees whose branches have unmerged commits. Default: ask
+    pub remove: Option<ConfirmPolicy>,
+    /// Merging a worktree with `workmux merge`. Default: skip
+    pub merge: Option<ConfirmPolicy>,
+    /// Bulk removals: `remove --all`, `--merged`, `--older-than` and `--gone`,
+    /// `apply --prune` and `prune`. Default: ask
+    pub clean: Option<ConfirmPolicy>,
+    /// Removals with `--force`, which replaces the prompts above and skips
+    /// the uncommitted and unmerged checks. Default: skip
+    pub force: Option<ConfirmPolicy>,
+}
+
+impl ConfirmConfig {
+    pub fn remove(&self) -> ConfirmPolicy {
+        self.remove.unwrap_or(ConfirmPolicy::Ask)
+    }
+
+    pub fn merge(&self) -> ConfirmPolicy {
+        self.merge.unwrap_or(ConfirmPolicy::Skip)
+    }
+
+    pub fn clean(&self) -> ConfirmPolicy {
+        self.clean.unwrap_or(ConfirmPolicy::Ask)
+    }
+
+    pub fn force(&self) -> ConfirmPolicy {
+        self.force.unwrap_or(ConfirmPolicy::Skip)
+    }
+
+    /// Policy for a removal: `--force` swaps the usual prompt for the `force` one
+    pub fn removal(&self, policy: ConfirmPolicy, force: bool) -> ConfirmPolicy {
+        if force { self.force() } else { policy }
+    }
+}
+
+/// Installing a new worktree's dependencies in a pane of its window, while
+/// the agent and other panes start
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BootstrapConfig {
+    /// Commands run one after the other (`npm ci`, `cargo fetch`)
+    pub commands: Option<Vec<String>>,
+    /// Paths in each worktree linked to the same path in the cache directory,
+    /// so every worktree shares them (`target`, `.pnpm-store`)
+    pub cache: Option<Vec<String>>,
+    /// Where shared paths live. Can use `{{ repo }}`. Default:
+    /// `~/.cache/workmux/<repo>`
+    pub cache_dir: Option<String>,
+}
+
+impl BootstrapConfig {
+    pub fn commands(&self) -> &[String] {
+        self.commands.as_deref().unwrap_or_default()
+    }
+
+    pub fn cache(&self) -> &[String] {
+        self.cache.as_deref().unwrap_or_default()
+    }
+}
+
+/// Reporting the untracked files (build output, dependencies) that removing a
+/// worktree deletes along with it
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SizeGuardConfig {
+    /// Measure untracked files before removing a worktree. Default: true
+    pub enabled: Option<bool>,
+    /// Untracked files and directories at least this large are listed.
+    /// Default: 100MB
+    pub min_size: Option<ByteSize>,
+    /// Ask before removing a worktree whose untracked files add up to more
+    /// than this. Default: never ask
+    pub confirm_above: Option<ByteSize>,
+}
+
+impl SizeGuardConfig {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn min_size(&self) -> ByteSize {
+        self.min_size.unwrap_or(ByteSize(100 * 1024 * 1024))
+    }
+}
+
+/// How long workmux keeps what builds up as it is used, enforced once a day
+/// when a command starts, and by `workmux gc`
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Entries of workmux's logs: `workmux.log`, `audit.log`, `activity.log`
+    /// and `log_file`. Default: 30d
+    pub logs: Option<Retention>,
+    /// Worktree directories `remove` renamed but couldn't delete. Default: 14d
+    pub trash: Option<Retention>,
+    /// Progress saved by interrupted commands to resume from. Default: 7d
+    pub checkpoints: Option<Retention>,
+}
+
+impl RetentionConfig {
+    pub fn logs(&self) -> Option<Duration> {
+        Retention::period(self.logs, 30)
+    }
+
+    pub fn trash(&self) -> Option<Duration> {
+        Retention::period(self.trash, 14)
+    }
+
+    pub fn checkpoints(&self) -> Option<Duration> {
+        Retention::period(self.checkpoints, 7)
+    }
+}
+
+/// How long to keep something: a duration like `--older-than` takes (`30d`,
+/// `12h`, `2w`), or `forever`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Retention {
+    Forever,
+    For(Duration),
+}
+
+impl Retention {
+    /// How long `value` keeps things, None for forever
+    fn period(value: Option<Self>, default_days: u64) -> Option<Duration> {
+        match value.unwrap_or(Self::For(Duration::from_secs(default_days * 86_400))) {
+            Self::Forever => None,
+            Self::For(period) => Some(period),
+        }
+    }
+}
+
+impl TryFrom<String> for Retention {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == "forever" {
+            return Ok(Self::Forever);
+        }
+        crate::command::remove::parse_age(&value).map(Self::For)
+    }
+}
+
+impl From<Retention> for String {
+    fn from(value: Retention) -> Self {
+        let secs = match value {
+            Retention::Forever => return "forever".to_string(),
+            Retention::For(period) => period.as_secs(),
+        };
+        match secs {
+            s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+            s if s % 3600 == 0 => format!("{}h", s / 3600),
+            s => format!("{}m", s / 60),
+        }
+    }
+}
+
+/// A size in bytes: a plain number, or one with a unit (`500MB`, `1.5 GB`).
+/// Units are powers of 1024.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "ByteSizeValue", into = "u64")]
+pub struct ByteSize(pub u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ByteSizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+const SIZE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid size '{}'", s))?;
+        let unit = unit.trim().to_uppercase();
+        let power = SIZE_UNITS
+            .iter()
+            .position(|u| *u == unit || (unit.len() == 1 && u.starts_with(unit.as_str())))
+            .or_else(|| unit.is_empty().then_some(0))
+            .ok_or_else(|| format!("unknown unit in size '{}' (use B, KB, MB, GB or TB)", s))?;
+        Ok(Self((number * 1024f64.powi(power as i32)) as u64))
+    }
+}
+
+impl TryFrom<ByteSizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: ByteSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            ByteSizeValue::Bytes(bytes) => Ok(Self(bytes)),
+            ByteSizeValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl std::fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{:.1} {}", value, SIZE_UNITS[unit])
+        }
+    }
+}
+
+/// Where new worktrees are created
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum WorktreeDir {
+    /// `sibling`: `<project>__worktrees` next to the main worktree
+    Sibling,
+    /// `subdir:<path>`: a directory inside the main worktree
+    Subdir(String),
+    /// `global:<path>`: a template for the worktree's path, with `repo` and
+    /// `handle`. Without `handle`, the handle is appended.
+    Global(String),
+    /// Any other value: a directory, relative to the main worktree or absolute
+    Path(String),
+}
+
+impl std::str::FromStr for WorktreeDir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "sibling" {
+            return Ok(Self::Sibling);
+        }
+        if let Some(path) = s.strip_prefix("subdir:") {
+            let path = path.trim();
+            if path.is_empty() || std::path::Path::new(path).is_absolute() {
+                return Err(format!(
+                    "'{}' needs a path relative to the repository (e.g. subdir:.worktrees)",
+                    s
+                ));
+            }
+            return Ok(Self::Subdir(path.to_string()));
+        }
+        if let Some(template) = s.strip_prefix("global:") {
+            let template = template.trim();
+            if template.is_empty() {
+                return Err(format!(
+                    "'{}' needs a path (e.g. global:~/worktrees/{{{{ repo }}}}/{{{{ handle }}}})",
+                    s
+                ));
+            }
+            return Ok(Self::Global(template.to_string()));
+        }
+        if s.is_empty() {
+            return Err("worktree_dir is empty".to_string());
+        }
+        Ok(Self::Path(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for WorktreeDir {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<WorktreeDir> for String {
+    fn from(dir: WorktreeDir) -> Self {
+        dir.to_string()
+    }
+}
+
+impl std::fmt::Display for WorktreeDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sibling => write!(f, "sibling"),
+            Self::Subdir(path) => write!(f, "subdir:{}", path),
+            Self::Global(template) => write!(f, "global:{}", template),
+            Self::Path(path) => write!(f, "{}", path),
+        }
+    }
+}
+
 /// Configuration for the workmux tool, read from .workmux.yaml
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct Config {
@@ -68,38 +709,148 @@ pub struct Config {
     #[serde(default)]
     pub main_branch: Option<String>,
 
-    /// Directory where worktrees should be created (optional, defaults to <project>__worktrees pattern)
-    /// Can be relative to repo root or absolute path
+    /// The git remote to treat as upstream (optional, defaults to "origin").
+    /// Used for default branch detection, merge checks and PR checkouts.
+    #[serde(default)]
+    pub default_remote: Option<String>,
+
+    /// When workmux is allowed to run `git fetch` (optional, defaults to always)
+    #[serde(default)]
+    pub fetch: Option<FetchPolicy>,
+
+    /// Minimum minutes between fetches when `fetch: interval` (defaults to 15)
+    #[serde(default)]
+    pub fetch_interval: Option<u64>,
+
+    /// Where worktrees are created: `sibling` (the default,
+    /// `<project>__worktrees`), `subdir:<path>`, `global:<path template>`, or
+    /// a directory relative to the repo root or absolute
+    #[serde(default)]
+    pub worktree_dir: Option<WorktreeDir>,
+
+    /// Prefix for tmux window names (optional, defaults to "wm-")
+    #[serde(default)]
+    pub window_prefix: Option<String>,
+
+    /// Tmux pane configuration, optionally with the layout to arrange the
+    /// panes in
+    #[serde(default)]
+    pub panes: Option<Panes>,
+
+    /// Which window to focus after `remove` or `merge` closes a worktree window
+    #[serde(default)]
+    pub after_close: Option<AfterClose>,
+
+    /// Named agent teams for `add --team`, each a list of roles with their own pane
+    #[serde(default)]
+    pub team: Option<BTreeMap<String, Vec<TeamMember>>>,
+
+    /// Named worktree templates for `add --template`, each bundling sparse
+    /// paths, panes and hooks for one area of the project
+    #[serde(default)]
+    pub templates: Option<BTreeMap<String, WorktreeTemplate>>,
+
+    /// Directories to check out in new worktrees, using sparse checkout.
+    /// Usually set by a template.
+    #[serde(default)]
+    pub sparse: Option<Vec<String>>,
+
+    /// The template applied to this config, recorded for the new branch
+    #[serde(skip)]
+    pub applied_template: Option<String>,
+
+    /// Commands to run in the main worktree before creating a worktree
+    #[serde(default)]
+    pub pre_create: Option<Vec<HookCommand>>,
+
+    /// Commands to run after creating the worktree
+    #[serde(default)]
+    pub post_create: Option<Vec<HookCommand>>,
+
+    /// Commands to run before opening a window for an existing worktree
+    #[serde(default)]
+    pub pre_open: Option<Vec<HookCommand>>,
+
+    /// Commands to run before merging (e.g., linting, tests)
+    #[serde(default)]
+    pub pre_merge: Option<Vec<HookCommand>>,
+
+    /// Commands to run in the target worktree after merging
+    #[serde(default)]
+    pub post_merge: Option<Vec<HookCommand>>,
+
+    /// Commands to run before removing the worktree (e.g., for backups)
+    #[serde(default)]
+    pub pre_remove: Option<Vec<HookCommand>>,
+
+    /// Commands to run in the main worktree after removing a worktree
+    #[serde(default)]
+    pub post_remove: Option<Vec<HookCommand>>,
+
+    /// Commands to run when the worktree's tmux window gains focus
+    #[serde(default)]
+    pub on_enter: Option<Vec<String>>,
+
+    /// Commands to run when the worktree's tmux window loses focus
+    #[serde(default)]
+    pub on_leave: Option<Vec<String>>,
+
+    /// The agent command to use (e.g., "claude", "gemini"), or the name of an
+    /// agent defined under `agents`
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// Agents by name, each with its command and how it takes a prompt and
+    /// reports its status
+    #[serde(default)]
+    pub agents: Option<BTreeMap<String, AgentDefinition>>,
+
+    /// Add a section on using workmux to new worktrees' CLAUDE.md or AGENTS.md
+    #[serde(default)]
+    pub agent_instructions: Option<bool>,
+
+    /// Default merge strategy for `workmux merge`
+    #[serde(default)]
+    pub merge_strategy: Option<MergeStrategy>,
+
+    /// Template for the commit message of merge and squash commits
+    #[serde(default)]
+    pub merge_message: Option<String>,
+
+    /// Command opened in a pane when `workmux merge` stops on conflicts
     #[serde(default)]
-    pub worktree_dir: Option<String>,
+    pub conflict_command: Option<String>,
 
-    /// Prefix for tmux window names (optional, defaults to "wm-")
+    /// Template for the prompt `open --resume-prompt` starts the agent with,
+    /// given the worktree's original `prompt`
     #[serde(default)]
-    pub window_prefix: Option<String>,
+    pub resume_prompt: Option<String>,
 
-    /// Tmux pane configuration
+    /// Path in each new worktree, relative to its root, that its rendered
+    /// prompt is written to, so the task travels with the worktree
     #[serde(default)]
-    pub panes: Option<Vec<PaneConfig>>,
+    pub save_prompt: Option<String>,
 
-    /// Commands to run after creating the worktree
+    /// How `workmux sync` brings worktrees up to date with the main branch
     #[serde(default)]
-    pub post_create: Option<Vec<String>>,
+    pub sync_strategy: Option<SyncStrategy>,
 
-    /// Commands to run before merging (e.g., linting, tests)
+    /// How many worktrees a multi-worktree `workmux add` creates at once
     #[serde(default)]
-    pub pre_merge: Option<Vec<String>>,
+    pub add_parallel: Option<u32>,
 
-    /// Commands to run before removing the worktree (e.g., for backups)
+    /// File to append JSON logs of every command run and workflow step to.
+    /// Only read from the global config.
     #[serde(default)]
-    pub pre_remove: Option<Vec<String>>,
+    pub log_file: Option<String>,
 
-    /// The agent command to use (e.g., "claude", "gemini")
+    /// Built-in setup preset: `auto` (default), `none`, or a preset name
     #[serde(default)]
-    pub agent: Option<String>,
+    pub preset: Option<String>,
 
-    /// Default merge strategy for `workmux merge`
+    /// Git config applied with `git config --worktree` to each new worktree
     #[serde(default)]
-    pub merge_strategy: Option<MergeStrategy>,
+    pub git_config: Option<BTreeMap<String, GitConfigValue>>,
 
     /// Strategy for deriving worktree/window names from branch names
     #[serde(default)]
@@ -109,6 +860,19 @@ pub struct Config {
     #[serde(default)]
     pub worktree_prefix: Option<String>,
 
+    /// How loosely worktree names given to commands may match handles
+    #[serde(default)]
+    pub handle_matching: Option<HandleMatching>,
+
+    /// What to do when run inside a submodule or a repository nested in another
+    #[serde(default)]
+    pub nested_repo: Option<NestedRepoPolicy>,
+
+    /// Flags added to every run of a command, by command name
+    /// (e.g. `add: ["--background"]`)
+    #[serde(default)]
+    pub defaults: Option<BTreeMap<String, Vec<String>>>,
+
     /// File operations to perform after creating the worktree
     #[serde(default)]
     pub files: FileConfig,
@@ -122,9 +886,45 @@ pub struct Config {
     #[serde(default)]
     pub status_icons: StatusIcons,
 
+    /// Which destructive actions ask for confirmation
+    #[serde(default)]
+    pub confirm: ConfirmConfig,
+
+    /// Listing of large untracked files before a worktree is removed
+    #[serde(default)]
+    pub size_guard: SizeGuardConfig,
+
+    /// How long logs, trash and saved progress are kept
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Dependency setup run in a pane after a worktree is created
+    #[serde(default)]
+    pub bootstrap: BootstrapConfig,
+
     /// Configuration for LLM-based branch name generation
     #[serde(default)]
     pub auto_name: Option<AutoNameConfig>,
+
+    /// How `add` describes a branch created with a prompt
+    #[serde(default)]
+    pub branch_description: Option<BranchDescription>,
+
+    /// Configuration for `workmux review-with-agent`
+    #[serde(default)]
+    pub review: Option<ReviewConfig>,
+
+    /// Configuration for `workmux add --pr`
+    #[serde(default)]
+    pub pr: Option<PrConfig>,
+
+    /// Encryption of prompts and agent output in worktree state
+    #[serde(default)]
+    pub state_encryption: Option<StateEncryptionConfig>,
+
+    /// tmux layout of worktrees
+    #[serde(default)]
+    pub tmux: Option<TmuxConfig>,
 }
 
 /// Configuration for a single tmux pane
@@ -161,6 +961,191 @@ pub struct PaneConfig {
     pub target: Option<usize>,
 }
 
+/// Shows the changes of a pull request checked out for review, from where
+/// it branched off its base
+const REVIEW_DIFF_COMMAND: &str = "git diff {{ pr_base }}...HEAD";
+
+/// Layouts tmux arranges panes in by name
+pub const LAYOUT_PRESETS: &[&str] = &[
+    "even-horizontal",
+    "even-vertical",
+    "main-horizontal",
+    "main-horizontal-mirrored",
+    "main-vertical",
+    "main-vertical-mirrored",
+    "tiled",
+];
+
+/// The `panes` setting: either a list of panes, or a map with the list under
+/// `list` and a tmux `layout` to arrange them in once they are created
+#[derive(Debug, Clone, Default)]
+pub struct Panes {
+    /// A preset from [`LAYOUT_PRESETS`] or a layout string as printed by
+    /// `tmux display -p '#{window_layout}'`
+    pub layout: Option<String>,
+    list: Option<Vec<PaneConfig>>,
+}
+
+impl Panes {
+    /// Whether a list of panes was given, rather than only a layout
+    pub fn has_list(&self) -> bool {
+        self.list.is_some()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        validate_panes_config(self)?;
+        if let Some(layout) = &self.layout {
+            validate_layout(layout)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<PaneConfig>> for Panes {
+    fn from(list: Vec<PaneConfig>) -> Self {
+        Self {
+            layout: None,
+            list: Some(list),
+        }
+    }
+}
+
+impl FromIterator<PaneConfig> for Panes {
+    fn from_iter<I: IntoIterator<Item = PaneConfig>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl std::ops::Deref for Panes {
+    type Target = [PaneConfig];
+
+    fn deref(&self) -> &[PaneConfig] {
+        self.list.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PanesMap {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    list: Option<Vec<PaneConfig>>,
+}
+
+impl<'de> Deserialize<'de> for Panes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+
+        // A visitor rather than an untagged enum, so that mistakes in a pane
+        // are reported as such
+        struct PanesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PanesVisitor {
+            type Value = Panes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a list of panes, or a map with `layout` and `list`")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Panes, A::Error> {
+                Vec::deserialize(SeqAccessDeserializer::new(seq)).map(Panes::from)
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Panes, A::Error> {
+                let map = PanesMap::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Panes {
+                    layout: map.layout,
+                    list: map.list,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(PanesVisitor)
+    }
+}
+
+impl Serialize for Panes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (&self.layout, &self.list) {
+            (None, Some(list)) => list.serialize(serializer),
+            _ => PanesMap {
+                layout: self.layout.clone(),
+                list: self.list.clone(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+/// Settings for worktrees in one area of the project. Each setting given
+/// replaces the configured one.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct WorktreeTemplate {
+    /// Branch globs that select this template when `--template` is not given
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub branches: Option<Vec<String>>,
+
+    /// Directories to check out, using sparse checkout
+    #[serde(default)]
+    pub sparse: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub panes: Option<Panes>,
+
+    #[serde(default)]
+    pub pre_create: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub post_create: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub pre_open: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub pre_merge: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub post_merge: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub pre_remove: Option<Vec<HookCommand>>,
+
+    #[serde(default)]
+    pub post_remove: Option<Vec<HookCommand>>,
+}
+
+/// A role in an agent team. Each member runs its agent in its own pane and
+/// receives the prompt rendered with `role` set to the member's role.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TeamMember {
+    /// Role name, available in prompt templates as `{{ role }}`
+    pub role: String,
+
+    /// Agent command for this role (defaults to the configured agent)
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// Whether this member's pane should receive focus after creation
+    #[serde(default)]
+    pub focus: bool,
+
+    /// Split direction from the previous pane (defaults to horizontal)
+    #[serde(default)]
+    pub split: Option<SplitDirection>,
+
+    /// The size of the pane in lines or cells. Mutually exclusive with `percentage`.
+    #[serde(default)]
+    pub size: Option<u16>,
+
+    /// The size of the pane as a percentage of the available space.
+    #[serde(default)]
+    pub percentage: Option<u8>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SplitDirection {
@@ -175,6 +1160,204 @@ pub enum MergeStrategy {
     Merge,
     Rebase,
     Squash,
+    /// Fast-forward only, refusing branches that have diverged
+    #[serde(rename = "ff-only")]
+    FfOnly,
+}
+
+/// How `workmux sync` updates a worktree's branch from the main branch
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStrategy {
+    #[default]
+    Rebase,
+    Merge,
+}
+
+/// How `add` sets `branch.<name>.description` from the prompt
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchDescription {
+    /// The prompt, or an LLM summary of it when it is long
+    #[default]
+    Summarize,
+    /// The prompt's first line, shortened when it is long
+    Truncate,
+    /// Don't set a description
+    Off,
+}
+
+/// Controls when workmux runs `git fetch`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FetchPolicy {
+    /// Fetch whenever a command needs up-to-date remote refs
+    #[default]
+    Always,
+    /// Never fetch; rely on existing remote-tracking refs
+    Never,
+    /// Only fetch when adding worktrees (`add --pr`, remote branches)
+    OnAdd,
+    /// Only fetch when cleaning up merged branches (`remove --gone`)
+    OnMerge,
+    /// Fetch at most once per `fetch_interval` minutes
+    Interval,
+}
+
+/// The operation that wants to fetch, checked against the `FetchPolicy`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchTrigger {
+    Add,
+    Merge,
+    Sync,
+}
+
+impl FetchPolicy {
+    /// Whether this policy allows fetching for `trigger`, given the time since the last fetch.
+    pub fn allows(
+        self,
+        trigger: FetchTrigger,
+        last_fetch: Option<std::time::Duration>,
+        interval: std::time::Duration,
+    ) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::OnAdd => trigger == FetchTrigger::Add,
+            Self::OnMerge => trigger == FetchTrigger::Merge,
+            Self::Interval => last_fetch.is_none_or(|age| age >= interval),
+        }
+    }
+}
+
+/// Where to navigate after a worktree's tmux window is closed
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterClose {
+    /// Switch to the main branch window (or the merge target)
+    #[default]
+    Main,
+    /// Switch to the window before the closed one
+    Previous,
+    /// Switch to the window after the closed one
+    Next,
+    /// Don't navigate; leave focus to tmux (or where it already is)
+    Stay,
+}
+
+/// A lifecycle hook (`pre_create`, `post_create`, `pre_open`, `pre_merge`,
+/// `post_merge`, `pre_remove`, `post_remove`): a command, or
+/// a command with a time limit or a condition
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum HookCommand {
+    Command(String),
+    WithOptions {
+        command: String,
+        /// Seconds before the hook and everything it started are killed
+        #[serde(default)]
+        timeout: Option<u64>,
+        /// Run only in worktrees this matches
+        #[serde(default)]
+        when: Option<Condition>,
+    },
+}
+
+impl HookCommand {
+    pub fn command(&self) -> &str {
+        match self {
+            Self::Command(command) | Self::WithOptions { command, .. } => command,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::Command(_) => None,
+            Self::WithOptions { timeout, .. } => timeout.map(std::time::Duration::from_secs),
+        }
+    }
+
+    pub fn applies_to(&self, target: &WhenTarget) -> bool {
+        match self {
+            Self::WithOptions {
+                when: Some(when), ..
+            } => when.matches(target),
+            _ => true,
+        }
+    }
+}
+
+/// The hooks of a phase that apply to `target`, in order
+pub fn hooks_for<'a>(
+    hooks: &'a Option<Vec<HookCommand>>,
+    target: &WhenTarget,
+) -> Vec<&'a HookCommand> {
+    hooks
+        .iter()
+        .flatten()
+        .filter(|hook| hook.applies_to(target))
+        .collect()
+}
+
+impl AsRef<str> for HookCommand {
+    fn as_ref(&self) -> &str {
+        self.command()
+    }
+}
+
+impl std::fmt::Display for HookCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.command())
+    }
+}
+
+/// A `git_config` value. YAML booleans and numbers are accepted as-is so
+/// `commit.gpgsign: false` needs no quotes.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum GitConfigValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl std::fmt::Display for GitConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// How a name given to a command is matched against worktree handles
+/// when no handle or branch matches it exactly
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HandleMatching {
+    /// Only exact handle or branch names
+    #[default]
+    Exact,
+    /// Also a prefix of a single handle (`auth` finds `auth-oauth`)
+    Prefix,
+    /// Also a prefix, then any part of a single handle
+    /// (`auth` finds `web-feature-auth-oauth`)
+    Substring,
+}
+
+/// What workmux does when the current directory is in a submodule, or in a
+/// repository the enclosing one tracks
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedRepoPolicy {
+    /// Refuse to run, naming the superproject
+    #[default]
+    Error,
+    /// Run on the superproject, as if started in its root
+    Superproject,
+    /// Run on the nested repository itself
+    Allow,
 }
 
 /// Strategy for deriving worktree/window names from branch names
@@ -255,21 +1438,48 @@ pub fn validate_panes_config(panes: &[PaneConfig]) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Check that `layout` is a tmux preset, or a layout string whose checksum
+/// matches, as tmux would otherwise only reject it once the panes exist
+pub fn validate_layout(layout: &str) -> anyhow::Result<()> {
+    if LAYOUT_PRESETS.contains(&layout) {
+        return Ok(());
+    }
+    let valid = layout.split_once(',').is_some_and(|(checksum, body)| {
+        u16::from_str_radix(checksum, 16).ok() == Some(layout_checksum(body))
+    });
+    if !valid {
+        anyhow::bail!(
+            "Invalid pane layout '{}'. Use one of {} or a layout saved with 'workmux layout save'.",
+            layout,
+            LAYOUT_PRESETS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The checksum tmux puts in front of a window layout
+fn layout_checksum(body: &str) -> u16 {
+    body.bytes().fold(0u16, |sum, byte| {
+        sum.rotate_right(1).wrapping_add(u16::from(byte))
+    })
+}
+
 impl Config {
-    /// Load and merge global and project configurations.
+    /// Load and merge the global, project and worktree-local configurations.
     pub fn load(cli_agent: Option<&str>) -> anyhow::Result<Self> {
         debug!("config:loading");
-        let global_config = Self::load_global()?.unwrap_or_default();
-        let project_config = Self::load_project()?.unwrap_or_default();
+        let mut config = Self::default();
+        for file in config_files()? {
+            config = config.merge(Self::parse(&file.path, &file.contents)?);
+        }
 
         let final_agent = cli_agent
             .map(|s| s.to_string())
-            .or_else(|| project_config.agent.clone())
-            .or_else(|| global_config.agent.clone())
+            .or_else(|| config.agent.clone())
             .unwrap_or_else(|| "claude".to_string());
 
-        let mut config = global_config.merge(project_config);
-        config.agent = Some(final_agent);
+        // An agent given by name starts with its defined command
+        config.agent = Some(crate::agent::command(&config, &final_agent));
 
         // After merging, apply sensible defaults for any values that are not configured.
         if let Ok(repo_root) = git::get_repo_root() {
@@ -278,23 +1488,44 @@ impl Config {
                 || repo_root.join("package-lock.json").exists()
                 || repo_root.join("yarn.lock").exists();
 
+            // Built-in setup for the project type, unless hooks or bootstrap
+            // commands are configured
+            let preset = preset::resolve(config.preset.as_deref(), &repo_root)?;
+            if let Some(preset) = preset {
+                config.apply_preset(preset);
+            }
+
             // Default panes based on project type
-            if config.panes.is_none() {
-                if repo_root.join("CLAUDE.md").exists() {
-                    config.panes = Some(Self::claude_default_panes());
+            // A `panes` map with only a layout arranges the default panes
+            if !config.panes.as_ref().is_some_and(Panes::has_list) {
+                let mut panes = if repo_root.join("CLAUDE.md").exists() {
+                    Self::claude_default_panes()
                 } else {
-                    config.panes = Some(Self::default_panes());
+                    Self::default_panes()
+                };
+                // The preset's dev server takes the second pane
+                if let Some(dev) = preset.and_then(|preset| preset.dev_command(&repo_root)) {
+                    panes[1].command = Some(dev.to_string());
                 }
+                config.panes = Some(Panes {
+                    layout: config.panes.take().and_then(|panes| panes.layout),
+                    list: Some(panes),
+                });
             }
 
             // Default pre_remove hook for Node.js projects
             if config.pre_remove.is_none() && has_node_modules {
-                config.pre_remove = Some(vec![NODE_MODULES_CLEANUP_SCRIPT.to_string()]);
+                config.pre_remove = Some(vec![HookCommand::Command(
+                    NODE_MODULES_CLEANUP_SCRIPT.to_string(),
+                )]);
             }
         } else {
             // Apply fallback defaults for when not in a git repo (e.g., `workmux init`).
-            if config.panes.is_none() {
-                config.panes = Some(Self::default_panes());
+            if !config.panes.as_ref().is_some_and(Panes::has_list) {
+                config.panes = Some(Panes {
+                    layout: config.panes.take().and_then(|panes| panes.layout),
+                    list: Some(Self::default_panes()),
+                });
             }
         }
 
@@ -306,85 +1537,28 @@ impl Config {
         Ok(config)
     }
 
-    /// Load configuration from a specific path.
-    fn load_from_path(path: &Path) -> anyhow::Result<Option<Self>> {
-        if !path.exists() {
-            return Ok(None);
-        }
-        debug!(path = %path.display(), "config:reading file");
-        let contents = fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&contents)
-            .map_err(|e| anyhow::anyhow!("Failed to parse config at {}: {}", path.display(), e))?;
-        Ok(Some(config))
-    }
-
-    /// Load the global configuration file from the XDG config directory.
-    fn load_global() -> anyhow::Result<Option<Self>> {
-        // Check ~/.config/workmux (XDG convention, works cross-platform)
-        if let Some(home_dir) = home::home_dir() {
-            let xdg_config_path = home_dir.join(".config/workmux/config.yaml");
-            if xdg_config_path.exists() {
-                return Self::load_from_path(&xdg_config_path);
-            }
-            let xdg_config_path_yml = home_dir.join(".config/workmux/config.yml");
-            if xdg_config_path_yml.exists() {
-                return Self::load_from_path(&xdg_config_path_yml);
-            }
-        }
-        Ok(None)
-    }
-
-    /// Load the project-specific configuration file.
-    ///
-    /// Searches for `.workmux.yaml` or `.workmux.yml` in the following order:
-    /// 1. Current worktree root (allows branch-specific config overrides)
-    /// 2. Main worktree root (shared config across all worktrees)
-    /// 3. Falls back gracefully when not in a git repository
-    fn load_project() -> anyhow::Result<Option<Self>> {
-        let config_names = [".workmux.yaml", ".workmux.yml"];
-
-        // Build list of directories to search
-        let mut search_dirs = Vec::new();
-        if let Ok(repo_root) = git::get_repo_root() {
-            search_dirs.push(repo_root.clone());
-            // Also check main worktree root if different from current worktree
-            if let Ok(main_root) = git::get_main_worktree_root()
-                && main_root != repo_root
-            {
-                search_dirs.push(main_root);
-            }
-        }
-
-        // Search for config in each directory
-        for dir in search_dirs {
-            for name in &config_names {
-                let config_path = dir.join(name);
-                if config_path.exists() {
-                    debug!(path = %config_path.display(), "config:found project config");
-                    return Self::load_from_path(&config_path);
-                }
-            }
-        }
-
-        Ok(None)
+    fn parse(path: &Path, contents: &str) -> anyhow::Result<Self> {
+        serde_yaml::from_str(contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config at {}: {}", path.display(), e))
     }
 
-    /// Merge a project config into a global config.
-    /// Project config takes precedence. For lists, "<global>" placeholder expands to global items.
+    /// Merge a project config into a global config, or a local config into
+    /// both. The later config takes precedence. For lists, "<global>"
+    /// placeholder expands to the earlier config's items.
     fn merge(self, project: Self) -> Self {
         /// Merge vectors with "<global>" placeholder expansion.
         /// When project contains "<global>", it expands to global items at that position.
-        fn merge_vec_with_placeholder(
-            global: Option<Vec<String>>,
-            project: Option<Vec<String>>,
-        ) -> Option<Vec<String>> {
+        fn merge_vec_with_placeholder<T: AsRef<str> + Clone>(
+            global: Option<Vec<T>>,
+            project: Option<Vec<T>>,
+        ) -> Option<Vec<T>> {
             match (global, project) {
                 (Some(global_items), Some(project_items)) => {
-                    let has_placeholder = project_items.iter().any(|s| s == "<global>");
+                    let has_placeholder = project_items.iter().any(|s| s.as_ref() == "<global>");
                     if has_placeholder {
                         let mut result = Vec::new();
                         for item in project_items {
-                            if item == "<global>" {
+                            if item.as_ref() == "<global>" {
                                 result.extend(global_items.clone());
                             } else {
                                 result.push(item);
@@ -415,14 +1589,34 @@ impl Config {
             self,
             project,
             main_branch,
+            default_remote,
+            fetch,
+            fetch_interval,
             worktree_dir,
             window_prefix,
             agent,
+            agent_instructions,
             merge_strategy,
+            merge_message,
+            conflict_command,
+            resume_prompt,
+            save_prompt,
+            sync_strategy,
+            add_parallel,
+            preset,
             worktree_prefix,
+            handle_matching,
+            nested_repo,
             panes,
+            after_close,
             status_format,
             auto_name,
+            branch_description,
+            review,
+            pr,
+            state_encryption,
+            tmux,
+            sparse,
         );
 
         // Special case: worktree_naming (project wins if not default)
@@ -433,31 +1627,159 @@ impl Config {
         };
 
         // List values with "<global>" placeholder support
+        merged.pre_create = merge_vec_with_placeholder(self.pre_create, project.pre_create);
         merged.post_create = merge_vec_with_placeholder(self.post_create, project.post_create);
+        merged.pre_open = merge_vec_with_placeholder(self.pre_open, project.pre_open);
         merged.pre_merge = merge_vec_with_placeholder(self.pre_merge, project.pre_merge);
+        merged.post_merge = merge_vec_with_placeholder(self.post_merge, project.post_merge);
         merged.pre_remove = merge_vec_with_placeholder(self.pre_remove, project.pre_remove);
+        merged.post_remove = merge_vec_with_placeholder(self.post_remove, project.post_remove);
+        merged.on_enter = merge_vec_with_placeholder(self.on_enter, project.on_enter);
+        merged.on_leave = merge_vec_with_placeholder(self.on_leave, project.on_leave);
 
         // File config with placeholder support
         merged.files = FileConfig {
             copy: merge_vec_with_placeholder(self.files.copy, project.files.copy),
             symlink: merge_vec_with_placeholder(self.files.symlink, project.files.symlink),
+            render: project.files.render.or(self.files.render),
+            exclude: merge_vec_with_placeholder(self.files.exclude, project.files.exclude),
+        };
+
+        // Teams: project definitions replace global ones with the same name
+        merged.team = match (self.team, project.team) {
+            (Some(mut global_teams), Some(project_teams)) => {
+                global_teams.extend(project_teams);
+                Some(global_teams)
+            }
+            (global, project) => project.or(global),
+        };
+
+        // Agents: project definitions replace global ones with the same name
+        merged.agents = match (self.agents, project.agents) {
+            (Some(mut global_agents), Some(project_agents)) => {
+                global_agents.extend(project_agents);
+                Some(global_agents)
+            }
+            (global, project) => project.or(global),
+        };
+
+        // Default flags: project flags replace global ones for the same command
+        merged.defaults = match (self.defaults, project.defaults) {
+            (Some(mut global_defaults), Some(project_defaults)) => {
+                global_defaults.extend(project_defaults);
+                Some(global_defaults)
+            }
+            (global, project) => project.or(global),
+        };
+
+        // Templates: project definitions replace global ones with the same name
+        merged.templates = match (self.templates, project.templates) {
+            (Some(mut global_templates), Some(project_templates)) => {
+                global_templates.extend(project_templates);
+                Some(global_templates)
+            }
+            (global, project) => project.or(global),
+        };
+
+        // Git config: project values override global ones per key
+        merged.git_config = match (self.git_config, project.git_config) {
+            (Some(mut global_values), Some(project_values)) => {
+                global_values.extend(project_values);
+                Some(global_values)
+            }
+            (global, project) => project.or(global),
+        };
+
+        // Status icons: per-field override
+        merged.status_icons = StatusIcons {
+            working: project.status_icons.working.or(self.status_icons.working),
+            waiting: project.status_icons.waiting.or(self.status_icons.waiting),
+            done: project.status_icons.done.or(self.status_icons.done),
+        };
+
+        // A repository's config can't make workmux write files elsewhere
+        merged.log_file = self.log_file;
+
+        // Confirmation prompts: per-action override
+        merged.confirm = ConfirmConfig {
+            remove: project.confirm.remove.or(self.confirm.remove),
+            merge: project.confirm.merge.or(self.confirm.merge),
+            clean: project.confirm.clean.or(self.confirm.clean),
+            force: project.confirm.force.or(self.confirm.force),
+        };
+
+        // Bootstrap: per-field override
+        merged.bootstrap = BootstrapConfig {
+            commands: project.bootstrap.commands.or(self.bootstrap.commands),
+            cache: project.bootstrap.cache.or(self.bootstrap.cache),
+            cache_dir: project.bootstrap.cache_dir.or(self.bootstrap.cache_dir),
+        };
+
+        // Size guard: per-field override
+        merged.size_guard = SizeGuardConfig {
+            enabled: project.size_guard.enabled.or(self.size_guard.enabled),
+            min_size: project.size_guard.min_size.or(self.size_guard.min_size),
+            confirm_above: project
+                .size_guard
+                .confirm_above
+                .or(self.size_guard.confirm_above),
+        };
+
+        // Retention: per-field override
+        merged.retention = RetentionConfig {
+            logs: project.retention.logs.or(self.retention.logs),
+            trash: project.retention.trash.or(self.retention.trash),
+            checkpoints: project.retention.checkpoints.or(self.retention.checkpoints),
         };
 
-        // Status icons: per-field override
-        merged.status_icons = StatusIcons {
-            working: project.status_icons.working.or(self.status_icons.working),
-            waiting: project.status_icons.waiting.or(self.status_icons.waiting),
-            done: project.status_icons.done.or(self.status_icons.done),
-        };
+        merged
+    }
+
+    /// Get default panes.
+    fn default_panes() -> Vec<PaneConfig> {
+        vec![
+            PaneConfig {
+                command: None, // Default shell
+                focus: true,
+                split: None,
+                size: None,
+                percentage: None,
+                target: None,
+            },
+            PaneConfig {
+                command: Some("clear".to_string()),
+                focus: false,
+                split: Some(SplitDirection::Horizontal),
+                size: None,
+                percentage: None,
+                target: None, // Splits most recent (pane 0)
+            },
+        ]
+    }
+
+    /// Whether worktree state is encrypted: `state_encryption` has recipients
+    pub fn encrypts_state(&self) -> bool {
+        self.state_encryption
+            .as_ref()
+            .is_some_and(|settings| !settings.recipients().is_empty())
+    }
 
-        merged
+    /// Panes of worktrees checked out to review a pull request: the
+    /// configured `pr.review_panes`, else the PR's diff and a pane running
+    /// the tests of the repository's preset
+    pub fn review_panes(&self) -> anyhow::Result<Panes> {
+        if let Some(panes) = self.pr.as_ref().and_then(|pr| pr.review_panes.clone()) {
+            return Ok(panes);
+        }
+        let repo_root = git::get_repo_root()?;
+        let test = preset::resolve(self.preset.as_deref(), &repo_root)?.map(|preset| preset.test);
+        Ok(Self::review_default_panes(test).into())
     }
 
-    /// Get default panes.
-    fn default_panes() -> Vec<PaneConfig> {
+    fn review_default_panes(test: Option<&str>) -> Vec<PaneConfig> {
         vec![
             PaneConfig {
-                command: None, // Default shell
+                command: Some(REVIEW_DIFF_COMMAND.to_string()),
                 focus: true,
                 split: None,
                 size: None,
@@ -465,16 +1787,26 @@ impl Config {
                 target: None,
             },
             PaneConfig {
-                command: Some("clear".to_string()),
+                command: test.map(str::to_string),
                 focus: false,
                 split: Some(SplitDirection::Horizontal),
                 size: None,
                 percentage: None,
-                target: None, // Splits most recent (pane 0)
+                target: None,
             },
         ]
     }
 
+    /// Install dependencies with `preset` in new worktrees, unless hooks or
+    /// bootstrap commands are configured
+    fn apply_preset(&mut self, preset: &preset::Preset) {
+        if self.post_create.is_some() || self.bootstrap.commands.is_some() {
+            return;
+        }
+        debug!(preset = preset.name, "config:applying preset");
+        self.post_create = Some(vec![HookCommand::Command(preset.install.to_string())]);
+    }
+
     /// Get default panes for a Claude project.
     fn claude_default_panes() -> Vec<PaneConfig> {
         vec![
@@ -497,11 +1829,207 @@ impl Config {
         ]
     }
 
+    /// Whether the fetch policy allows `git fetch` for the given operation
+    pub fn should_fetch(&self, trigger: FetchTrigger) -> bool {
+        let policy = self.fetch.unwrap_or_default();
+        let interval = std::time::Duration::from_secs(self.fetch_interval.unwrap_or(15) * 60);
+        let last_fetch = if policy == FetchPolicy::Interval {
+            git::last_fetch_age()
+        } else {
+            None
+        };
+        policy.allows(trigger, last_fetch, interval)
+    }
+
+    /// Get the upstream remote to use, defaulting to "origin" if not configured
+    pub fn default_remote(&self) -> &str {
+        self.default_remote.as_deref().unwrap_or("origin")
+    }
+
     /// Get the window prefix to use, defaulting to "wm-" if not configured
     pub fn window_prefix(&self) -> &str {
         self.window_prefix.as_deref().unwrap_or("wm-")
     }
 
+    /// Whether worktrees open as windows or sessions
+    pub fn tmux_layout(&self) -> TmuxLayout {
+        self.tmux
+            .as_ref()
+            .and_then(|tmux| tmux.layout)
+            .unwrap_or_default()
+    }
+
+    /// How to describe branches created with a prompt
+    pub fn branch_description(&self) -> BranchDescription {
+        self.branch_description.unwrap_or_default()
+    }
+
+    /// Get the handle matching mode, defaulting to exact matches only
+    pub fn handle_matching(&self) -> HandleMatching {
+        self.handle_matching.unwrap_or_default()
+    }
+
+    /// What to do inside a nested repository, defaulting to refusing to run
+    pub fn nested_repo(&self) -> NestedRepoPolicy {
+        self.nested_repo.unwrap_or_default()
+    }
+
+    /// The flags configured for every run of `command`
+    pub fn default_flags(&self, command: &str) -> &[String] {
+        self.defaults
+            .as_ref()
+            .and_then(|defaults| defaults.get(command))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The template for a new worktree on `branch`: the one asked for, or else
+    /// the one whose `branches` globs match. None if no template applies.
+    pub fn select_template(
+        &self,
+        requested: Option<&str>,
+        branch: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let templates = self.templates.as_ref().filter(|t| !t.is_empty());
+        if let Some(name) = requested {
+            if templates.is_some_and(|t| t.contains_key(name)) {
+                return Ok(Some(name.to_string()));
+            }
+            let available = templates
+                .map(|t| t.keys().cloned().collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            if available.is_empty() {
+                anyhow::bail!("Template '{}' not found: no templates are configured", name);
+            }
+            anyhow::bail!(
+                "Template '{}' not found. Available templates: {}",
+                name,
+                available
+            );
+        }
+
+        let matching: Vec<&String> = templates
+            .into_iter()
+            .flatten()
+            .filter(|(_, template)| {
+                template.branches.iter().flatten().any(|glob| {
+                    glob::Pattern::new(glob).is_ok_and(|pattern| pattern.matches(branch))
+                })
+            })
+            .map(|(name, _)| name)
+            .collect();
+        match matching.as_slice() {
+            [] => Ok(None),
+            [name] => Ok(Some(name.to_string())),
+            names => anyhow::bail!(
+                "Branch '{}' matches several templates ({}); pick one with --template",
+                branch,
+                names
+                    .iter()
+                    .map(|n| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Apply a template: each setting it has replaces the configured one
+    pub fn apply_template(&mut self, name: &str) -> anyhow::Result<()> {
+        let template = self
+            .templates
+            .as_ref()
+            .and_then(|t| t.get(name))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", name))?;
+        debug!(template = name, "config:applying template");
+        self.sparse = template.sparse.or(self.sparse.take());
+        self.panes = template.panes.or(self.panes.take());
+        self.pre_create = template.pre_create.or(self.pre_create.take());
+        self.post_create = template.post_create.or(self.post_create.take());
+        self.pre_open = template.pre_open.or(self.pre_open.take());
+        self.pre_merge = template.pre_merge.or(self.pre_merge.take());
+        self.post_merge = template.post_merge.or(self.post_merge.take());
+        self.pre_remove = template.pre_remove.or(self.pre_remove.take());
+        self.post_remove = template.post_remove.or(self.post_remove.take());
+        self.applied_template = Some(name.to_string());
+        Ok(())
+    }
+
+    /// This config as it applies to an existing branch: with the template its
+    /// worktree was created from, if that template is still configured
+    pub fn for_branch(&self, branch: &str) -> Config {
+        let mut config = self.clone();
+        if let Some(name) = git::get_branch_template(branch)
+            && let Err(e) = config.apply_template(&name)
+        {
+            debug!(branch, error = %e, "config:recorded template not applied");
+        }
+        config
+    }
+
+    /// Look up a team and build its pane layout, one pane per member running the
+    /// member's agent. Returns the members' roles alongside the panes.
+    pub fn team_panes(&self, name: &str) -> anyhow::Result<Vec<(String, PaneConfig)>> {
+        let teams = self.team.as_ref().filter(|teams| !teams.is_empty());
+        let members = teams.and_then(|teams| teams.get(name)).ok_or_else(|| {
+            let available = teams
+                .map(|teams| teams.keys().cloned().collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            if available.is_empty() {
+                anyhow::anyhow!("Team '{}' not found: no teams are configured", name)
+            } else {
+                anyhow::anyhow!("Team '{}' not found. Available teams: {}", name, available)
+            }
+        })?;
+
+        if members.is_empty() {
+            anyhow::bail!("Team '{}' has no members", name);
+        }
+
+        let mut roles = std::collections::HashSet::new();
+        let mut panes = Vec::with_capacity(members.len());
+        for (i, member) in members.iter().enumerate() {
+            if member.role.trim().is_empty() {
+                anyhow::bail!("Team '{}' member {} has an empty 'role'", name, i);
+            }
+            if !roles.insert(member.role.as_str()) {
+                anyhow::bail!(
+                    "Team '{}' lists role '{}' more than once",
+                    name,
+                    member.role
+                );
+            }
+
+            let agent = match &member.agent {
+                Some(agent) => crate::agent::command(self, agent),
+                None => self.agent.clone().unwrap_or_else(|| "claude".to_string()),
+            };
+            // Members after the first are split off side by side unless configured
+            let split = if i == 0 {
+                member.split.clone()
+            } else {
+                Some(member.split.clone().unwrap_or(SplitDirection::Horizontal))
+            };
+
+            panes.push((
+                member.role.clone(),
+                PaneConfig {
+                    command: Some(agent),
+                    focus: member.focus,
+                    split,
+                    size: member.size,
+                    percentage: member.percentage,
+                    target: None,
+                },
+            ));
+        }
+
+        let pane_configs: Vec<PaneConfig> = panes.iter().map(|(_, pane)| pane.clone()).collect();
+        validate_panes_config(&pane_configs)
+            .map_err(|e| anyhow::anyhow!("Invalid layout for team '{}': {}", name, e))?;
+
+        Ok(panes)
+    }
+
     /// Create an example .workmux.yaml configuration file
     pub fn init() -> anyhow::Result<()> {
         use std::path::PathBuf;
@@ -526,19 +2054,123 @@ impl Config {
 # Default: Auto-detected from remote HEAD, falls back to main/master.
 # main_branch: main
 
+# Remote that PRs target and the main branch is tracked on.
+# Useful for fork workflows where 'origin' is your fork.
+# Default: origin
+# default_remote: upstream
+
+# When workmux may run `git fetch`.
+# Options: always (default), never, on-add, on-merge, interval
+# on-add fetches for `add --pr`/remote branches, on-merge for `remove --gone`.
+# `--no-fetch` on those commands skips fetching regardless.
+# fetch: interval
+# fetch_interval: 15 # minutes, used with `fetch: interval`
+
 # Default merge strategy for `workmux merge`.
-# Options: merge (default), rebase, squash
-# CLI flags (--rebase, --squash) always override this.
+# Options: merge (default), rebase, squash, ff-only
+# CLI flags (--rebase, --squash, --ff-only) always override this.
 # merge_strategy: rebase
 
+# Commit message for merge and squash commits, instead of git's default
+# (merge) or the editor (squash). Variables: branch, target, handle, commits
+# (subjects of the branch's commits, oldest first).
+# merge_message: "Merge {{ branch }} into {{ target }}"
+
+# `workmux merge` pauses on conflicts, to finish with `workmux merge --continue`
+# or give up with `workmux merge --abort`. This command is opened in a pane
+# beside them in the conflicted worktree.
+# Use <agent> for the configured agent, which is told what to resolve.
+# conflict_command: git mergetool
+
+# Prompt `workmux open --resume-prompt` starts the agent with in a reopened
+# window, instead of the worktree's original prompt as it was. Variables:
+# prompt (the original), branch, handle, task.
+# resume_prompt: |
+#   You were working on the task below and the session was restarted.
+#   Check the worktree's state and continue where you left off.
+#
+#   {{ prompt }}
+
+# Write the prompt of each worktree created with one into the worktree, so
+# it can be reread, resent with `open --resume-prompt` after editing, or
+# used by hooks (as `prompt_file` in their JSON input). It is added to
+# .git/info/exclude, and not written while state_encryption is on.
+# save_prompt: .workmux/prompt.md
+
+# How `workmux sync` updates worktrees from the main branch.
+# Options: rebase (default), merge
+# CLI flags (--rebase, --merge) always override this.
+# sync_strategy: merge
+
+# How many worktrees `workmux add` creates at once with -n, --agent or
+# --foreach. Defaults to 1 (one after another); --parallel overrides this.
+# add_parallel: 4
+
+# Append a JSON line for every git/tmux command run and workflow step to this
+# file, for debugging failed setups. Use an absolute path or one starting with ~.
+# Only read from the global config.
+# log_file: ~/.local/state/workmux/debug.jsonl
+
+# Built-in setup when no post_create hooks are configured: installs
+# dependencies in each new worktree, and runs the dev server in the second
+# default pane if package.json has a `dev` script.
+# Options: auto (default, detected from lockfiles), none, node-pnpm, node-npm,
+# rust, python-uv, go
+# preset: none
+
+# Which destructive actions ask for confirmation first.
+# Options: ask (Enter means no), ask-default-yes (Enter means yes), skip
+# confirm:
+#   remove: ask    # removing branches with unmerged commits
+#   merge: skip    # `workmux merge`
+#   clean: ask     # `remove --all`, `remove --gone`, `apply --prune`, `prune`
+#   force: skip    # any removal with --force
+
+# Untracked files (node_modules, target, .venv, ...) that removing a worktree
+# deletes are measured first; those at least `min_size` are listed with sizes.
+# Sizes take B, KB, MB, GB or TB (powers of 1024).
+# size_guard:
+#   min_size: 100MB       # default
+#   confirm_above: 5GB    # ask before deleting more than this
+#   enabled: false        # skip measuring
+
+# How long workmux keeps its logs, worktree directories `remove` couldn't
+# delete, and progress saved by interrupted commands. Enforced once a day when
+# a command starts, or now with `workmux gc`. Durations take m, h, d or w.
+# retention:
+#   logs: 30d             # default
+#   trash: 14d            # default
+#   checkpoints: 7d       # default; or `forever` for any of them
+
+# Dependency setup run in a pane of each new worktree's window, so the agent
+# doesn't wait for it. The pane closes once every command succeeds.
+# `cache` paths are linked to a directory shared by all worktrees of the
+# project (default: ~/.cache/workmux/<repo>), e.g. build output or a store.
+# bootstrap:
+#   commands:
+#     - npm ci
+#   cache:
+#     - .pnpm-store
+#   cache_dir: ~/.cache/workmux/{{ repo }}
+
+# Git config set in each new worktree only, with `git config --worktree`.
+# Enables `extensions.worktreeConfig` for the repository. Handy for giving
+# agent worktrees a different identity or hooks than your main checkout.
+# git_config:
+#   user.email: agent@example.com
+#   commit.gpgsign: false
+#   core.hooksPath: .githooks/agent
+
 #-------------------------------------------------------------------------------
 # Naming & Paths
 #-------------------------------------------------------------------------------
 
-# Directory where worktrees are created.
-# Can be relative to repo root or absolute.
-# Default: Sibling directory '<project>__worktrees'.
-# worktree_dir: .worktrees
+# Where worktrees are created:
+#   sibling            - '<project>__worktrees' next to the repo (default)
+#   subdir:<path>      - a directory inside the repo, e.g. subdir:.worktrees
+#   global:<template>  - one place for all projects, with {{ repo }} and {{ handle }}
+#   <path>             - a directory, relative to the repo root or absolute
+# worktree_dir: global:~/worktrees/{{ repo }}/{{ handle }}
 
 # Strategy for deriving names from branch names.
 # Options: full (default), basename (part after last '/').
@@ -547,6 +2179,23 @@ impl Config {
 # Prefix added to worktree directories and tmux window names.
 # worktree_prefix: ""
 
+# How names given to commands like `open` and `remove` match worktrees when
+# no handle or branch matches exactly.
+# Options: exact (default), prefix, substring
+# A partial name must match a single handle; handles starting with the name
+# are preferred over ones merely containing it. With substring,
+# `workmux open auth` finds `web-feature-auth-oauth`.
+# handle_matching: substring
+
+# What to do when run inside a submodule or a repository this one tracks.
+# Options: error (default), superproject, allow
+# nested_repo: superproject
+
+# Flags added to every run of a command, by its full name.
+# defaults:
+#   add: ["--background"]
+#   remove: ["--keep-branch"]
+
 # Prefix for tmux window names.
 # Default: "wm-"
 # window_prefix: "wm-"
@@ -564,6 +2213,18 @@ impl Config {
 #   - command: clear
 #     split: vertical
 #     size: 5
+#
+# To arrange the panes in a tmux layout (main-vertical, tiled, or a string
+# saved with `workmux layout save`), put the list under `list`:
+# panes:
+#   layout: main-vertical
+#   list:
+#     - command: <agent>
+#     - split: horizontal
+
+# Window to focus after `remove`/`merge` closes a worktree window.
+# Options: main (default), previous, next, stay
+# after_close: previous
 
 # Auto-apply agent status icons to tmux window format.
 # Default: true
@@ -579,29 +2240,133 @@ impl Config {
 # Agent & AI
 #-------------------------------------------------------------------------------
 
-# Agent command for '<agent>' placeholder in pane commands.
+# Agent command for '<agent>' placeholder in pane commands, or the name of an
+# agent under `agents`.
 # Default: "claude"
 # agent: claude
 
+# Agents by name, for `agent`, `--agent`, team members and `review.agent`. A
+# definition named like a built-in agent (aider, claude, codex, copilot,
+# gemini, goose, opencode) changes how that agent is run.
+# agents:
+#   fast:
+#     command: codex --full-auto
+#     prompt: arg          # arg (default), file or stdin
+#     prompt_flag: "--"    # Before the prompt text or file
+#     status: none         # hooks, exit (working until it exits) or none
+
+# Add a section on using workmux (its commands, reporting status, starting a
+# sibling worktree) for the agents of new worktrees: to CLAUDE.local.md for
+# Claude, or to AGENTS.md unless git tracks it. Created files are excluded
+# from git.
+# Default: false
+# agent_instructions: true
+
 # LLM-based branch name generation (`workmux add -a`).
 # auto_name:
 #   model: "gpt-4o-mini"
 #   system_prompt: "Generate a kebab-case git branch name."
 
+# Description stored in `branch.<name>.description` for branches created with
+# a prompt, shown by `workmux list --long`.
+# Options: summarize (default, long prompts are summarized with the auto_name
+# model), truncate (the prompt's first line), off
+# branch_description: truncate
+
+# Reviewer agent for `workmux review-with-agent`.
+# review:
+#   agent: gemini        # Default: the configured agent
+#   output: REVIEW.md    # Findings file, relative to the worktree
+#   open_in: pane        # pane (split the worktree window) or window
+#   split: horizontal
+
+# Code host for `workmux add --pr <number>`. auto (default) picks GitLab when
+# the default remote's host contains "gitlab" or is listed in gitlab_hosts.
+# pr:
+#   provider: gitlab     # auto, github (uses gh), gitlab (uses glab)
+#   gitlab_hosts:
+#     - git.example.com
+#   # Panes of `add --pr <number> --review` worktrees. {{ pr_base }} is the
+#   # branch the PR merges into. Default: its diff, and the preset's tests.
+#   review_panes:
+#     - command: git diff {{ pr_base }}...HEAD
+#       focus: true
+#     - command: cargo test
+#       split: horizontal
+
+# Open each worktree in a tmux session of its own, named like its window
+# (wm-<handle>), instead of a window in the current session.
+# tmux:
+#   layout: sessions     # windows (default) or sessions
+
+# Encrypt the prompt and captured agent output that worktree state records,
+# with age. `workmux state unlock` reads the identity to show them again.
+# state_encryption:
+#   recipients:
+#     - age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p
+#   identity: ~/.config/workmux/state-key.age   # or: keyring
+
+# Agent teams for `workmux add --team <name>`. Each member gets its own pane
+# and the prompt rendered with `{{ role }}` set to the member's role.
+# team:
+#   default:
+#     - role: implementer
+#       agent: claude
+#       focus: true
+#     - role: reviewer
+#       agent: gemini
+#       split: horizontal
+
+# Worktree templates for `workmux add --template <name>`, also picked when the
+# branch matches `branches`. Each option a template sets (sparse, panes, and
+# the hooks below) replaces the configured one.
+# templates:
+#   frontend:
+#     branches: ["web/*"]
+#     sparse: [web/, packages/ui/]   # sparse checkout of these directories
+#     post_create:
+#       - pnpm --dir web install
+
 #-------------------------------------------------------------------------------
 # Hooks
 #-------------------------------------------------------------------------------
 
+# Besides the environment variables below, hooks (except on_enter/on_leave)
+# get a JSON document on stdin with the hook, handle, branch, worktree_path,
+# project_root, base, agent, prompt_file, pr and target_branch, e.g.
+#   jq -r .base
+
+# Commands to run in the main worktree before a new worktree is created.
+# Aborts `add` if any command fails. `when` conditions are checked against
+# the main worktree. WM_WORKTREE_PATH is where the worktree will be created.
+# Skipped with `add --no-hooks`.
+# pre_create:
+#   - git fetch origin
+
 # Commands to run in new worktree before tmux window opens.
 # These block window creation - use for short tasks only.
 # Use "<global>" to inherit from global config.
 # Set to empty list to disable: `post_create: []`
+# Ctrl-C cancels a running hook and removes the half-created worktree.
 # post_create:
 #   - "<global>"
 #   - mise use
+#   # Kill the hook (and anything it started) after 300 seconds
+#   - command: pnpm install
+#     timeout: 300
+#   # Run only where the condition holds (branch glob, agent, file in worktree)
+#   - command: cargo fetch
+#     when:
+#       exists: Cargo.toml
+
+# Commands to run in the worktree before `workmux open` creates its window.
+# Aborts the open if any command fails. Unlike post_create, they run on every
+# open, not only with `--run-hooks`.
+# pre_open:
+#   - docker compose up -d
 
 # Commands to run before merging (e.g., linting, tests).
-# Aborts the merge if any command fails.
+# Aborts the merge if any command fails or exceeds its `timeout` (seconds).
 # Use "<global>" to inherit from global config.
 # Environment variables available:
 #   - WM_BRANCH_NAME: The name of the branch being merged
@@ -614,18 +2379,41 @@ impl Config {
 #   - cargo test
 #   - cargo clippy -- -D warnings
 
+# Commands to run in the target worktree after a merge, and after the merged
+# worktree is cleaned up. Same environment variables as pre_merge.
+# post_merge:
+#   - cargo build --release
+
 # Commands to run before worktree removal (during merge or remove).
 # Useful for backing up gitignored files before cleanup.
 # Default: Auto-detects Node.js projects and fast-deletes node_modules.
 # Set to empty list to disable: `pre_remove: []`
 # Environment variables available:
 #   - WM_HANDLE: The worktree handle (directory name)
+#   - WM_BRANCH_NAME: The worktree's branch
 #   - WM_WORKTREE_PATH: Absolute path of the worktree being deleted
 #   - WM_PROJECT_ROOT: Absolute path of the main project directory
 # pre_remove:
 #   - mkdir -p "$WM_PROJECT_ROOT/artifacts/$WM_HANDLE"
 #   - cp -r test-results/ "$WM_PROJECT_ROOT/artifacts/$WM_HANDLE/"
 
+# Commands to run in the main worktree after a worktree is removed (during
+# merge or remove), e.g. to drop resources named after it.
+# Same environment variables as pre_remove.
+# post_remove:
+#   - docker volume rm "db-$WM_HANDLE"
+
+# Commands to run in the background when a worktree's tmux window gains
+# (on_enter) or loses (on_leave) focus, e.g. to pause file watchers.
+# Installed as tmux hooks when the window is created; turns on tmux's
+# `focus-events` option. Moving between panes of the window doesn't run them.
+# Use "<global>" to inherit from global config.
+# Same environment variables as pre_remove.
+# on_enter:
+#   - docker compose unpause
+# on_leave:
+#   - docker compose pause
+
 #-------------------------------------------------------------------------------
 # Files
 #-------------------------------------------------------------------------------
@@ -642,6 +2430,24 @@ impl Config {
 #   symlink:
 #     - "<global>"
 #     - node_modules
+#
+#   # Paths copy and symlink leave out, also inside copied directories.
+#   exclude:
+#     - .env.example
+#
+#   # Templates rendered into the worktree with its variables: branch, handle,
+#   # agent, prompt, prompt_summary, num, --foreach values and --var variables.
+#   render:
+#     - template: .workmux/AGENTS.md.j2
+#       to: AGENTS.md
+#
+#   # Any entry can apply only to some worktrees, by agent and/or branch glob.
+#   # `add --skip-file <glob>` and `--only-file <glob>` narrow the list per run.
+#   # copy:
+#   #   - pattern: .claude/settings.local.json
+#   #     when:
+#   #       agent: [claude]
+#   #       branch: ["feature/*"]
 "#;
 
         fs::write(&config_path, example_config)?;
@@ -690,6 +2496,106 @@ pub fn resolve_executable_path(executable: &str) -> Option<String> {
     None
 }
 
+/// The global config file in effect, if there is one
+pub fn global_config_path() -> Option<PathBuf> {
+    // ~/.config/workmux (XDG convention, works cross-platform)
+    let config_dir = home::home_dir()?.join(".config/workmux");
+    ["config.yaml", "config.yml"]
+        .into_iter()
+        .map(|name| config_dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// The project config file in effect, if there is one on this machine
+pub fn project_config_path() -> Option<PathBuf> {
+    project_config_candidates()
+        .into_iter()
+        .find(|path| path.exists())
+}
+
+/// The current worktree's local config file, if there is one on this machine
+pub fn local_config_path() -> Option<PathBuf> {
+    local_config_candidates()
+        .into_iter()
+        .find(|path| path.exists())
+}
+
+/// A config file that applies here, read but not yet parsed
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// The config files that apply here, from lowest to highest precedence: the
+/// global config, the project's `.workmux.yaml` and the current worktree's
+/// `.workmux.local.yaml`. Under `--host`, the last two are read on the host.
+pub fn config_files() -> anyhow::Result<Vec<ConfigFile>> {
+    let mut files = Vec::new();
+    if let Some(path) = global_config_path() {
+        debug!(path = %path.display(), "config:found global config");
+        let contents = fs::read_to_string(&path)?;
+        files.push(ConfigFile { path, contents });
+    }
+    for candidates in [project_config_candidates(), local_config_candidates()] {
+        if let Some(file) = read_first(&candidates)? {
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
+
+/// The first of `candidates` that exists
+fn read_first(candidates: &[PathBuf]) -> anyhow::Result<Option<ConfigFile>> {
+    for path in candidates {
+        let contents = match remote::get() {
+            // The repository lives on the remote host
+            Some(remote) => remote.read_file(path)?,
+            None if path.exists() => Some(fs::read_to_string(path)?),
+            None => None,
+        };
+        if let Some(contents) = contents {
+            debug!(path = %path.display(), "config:found config file");
+            return Ok(Some(ConfigFile {
+                path: path.clone(),
+                contents,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Where the current worktree's uncommitted overrides may be
+fn local_config_candidates() -> Vec<PathBuf> {
+    let Ok(repo_root) = git::get_repo_root() else {
+        return Vec::new();
+    };
+    [".workmux.local.yaml", ".workmux.local.yml"]
+        .iter()
+        .map(|name| repo_root.join(name))
+        .collect()
+}
+
+/// Where a project config may be, in order of precedence
+fn project_config_candidates() -> Vec<PathBuf> {
+    let config_names = [".workmux.yaml", ".workmux.yml"];
+
+    let mut search_dirs = Vec::new();
+    if let Ok(repo_root) = git::get_repo_root() {
+        search_dirs.push(repo_root.clone());
+        // Also check main worktree root if different from current worktree
+        if let Ok(main_root) = git::get_main_worktree_root()
+            && main_root != repo_root
+        {
+            search_dirs.push(main_root);
+        }
+    }
+
+    search_dirs
+        .iter()
+        .flat_map(|dir| config_names.iter().map(|name| dir.join(name)))
+        .collect()
+}
+
 pub fn tmux_global_path() -> Option<String> {
     let output = cmd::Cmd::new("tmux")
         .args(&["show-environment", "-g", "PATH"])
@@ -744,7 +2650,13 @@ pub fn is_agent_command(command_line: &str, agent_command: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{is_agent_command, split_first_token};
+    use super::{
+        ByteSize, Config, ConfirmPolicy, FetchPolicy, FetchTrigger, FileOp, HookCommand,
+        MergeStrategy, SplitDirection, WhenTarget, WorktreeDir, hooks_for, is_agent_command,
+        split_first_token, validate_layout,
+    };
+    use std::path::Path;
+    use std::time::Duration;
 
     #[test]
     fn split_first_token_single_word() {
@@ -818,4 +2730,494 @@ mod tests {
         assert!(!is_agent_command("", "claude"));
         assert!(!is_agent_command("   ", "claude"));
     }
+
+    #[test]
+    fn fetch_policy_triggers() {
+        let interval = Duration::from_secs(60);
+        assert!(FetchPolicy::Always.allows(FetchTrigger::Merge, None, interval));
+        assert!(!FetchPolicy::Never.allows(FetchTrigger::Add, None, interval));
+        assert!(FetchPolicy::OnAdd.allows(FetchTrigger::Add, None, interval));
+        assert!(!FetchPolicy::OnAdd.allows(FetchTrigger::Merge, None, interval));
+        assert!(FetchPolicy::OnMerge.allows(FetchTrigger::Merge, None, interval));
+        assert!(!FetchPolicy::OnMerge.allows(FetchTrigger::Add, None, interval));
+        assert!(FetchPolicy::Always.allows(FetchTrigger::Sync, None, interval));
+        assert!(!FetchPolicy::OnMerge.allows(FetchTrigger::Sync, None, interval));
+    }
+
+    #[test]
+    fn fetch_policy_interval() {
+        let interval = Duration::from_secs(60);
+        let policy = FetchPolicy::Interval;
+        assert!(policy.allows(FetchTrigger::Add, None, interval));
+        assert!(policy.allows(FetchTrigger::Add, Some(Duration::from_secs(61)), interval));
+        assert!(!policy.allows(FetchTrigger::Merge, Some(Duration::from_secs(10)), interval));
+    }
+
+    #[test]
+    fn fetch_policy_parses_kebab_case() {
+        let policy: FetchPolicy = serde_yaml::from_str("on-add").unwrap();
+        assert_eq!(policy, FetchPolicy::OnAdd);
+    }
+
+    #[test]
+    fn merge_strategy_and_message_merge_from_project() {
+        let global: Config =
+            serde_yaml::from_str("merge_strategy: ff-only\nmerge_message: \"{{ branch }}\"\n")
+                .unwrap();
+        let project: Config = serde_yaml::from_str("merge_strategy: squash\n").unwrap();
+        assert_eq!(global.merge_strategy, Some(MergeStrategy::FfOnly));
+        let merged = global.merge(project);
+        assert_eq!(merged.merge_strategy, Some(MergeStrategy::Squash));
+        assert_eq!(merged.merge_message.as_deref(), Some("{{ branch }}"));
+    }
+
+    #[test]
+    fn presets_apply_only_without_hooks_of_the_repository() {
+        let rust = &crate::preset::PRESETS
+            .iter()
+            .find(|preset| preset.name == "rust")
+            .unwrap();
+        let install = Some(vec![HookCommand::Command(rust.install.to_string())]);
+
+        let mut config = Config::default();
+        config.apply_preset(rust);
+        assert_eq!(config.post_create, install);
+
+        let mut hooks: Config = serde_yaml::from_str("post_create: [make setup]\n").unwrap();
+        hooks.apply_preset(rust);
+        assert_eq!(
+            hooks.post_create,
+            Some(vec![HookCommand::Command("make setup".to_string())])
+        );
+        let mut bootstrap: Config =
+            serde_yaml::from_str("bootstrap:\n  commands: [make setup]\n").unwrap();
+        bootstrap.apply_preset(rust);
+        assert_eq!(bootstrap.post_create, None);
+    }
+
+    #[test]
+    fn default_remote_is_origin_unless_configured() {
+        assert_eq!(Config::default().default_remote(), "origin");
+
+        let global: Config = serde_yaml::from_str("default_remote: upstream\n").unwrap();
+        assert_eq!(global.default_remote(), "upstream");
+        let project: Config = serde_yaml::from_str("default_remote: company\n").unwrap();
+        assert_eq!(global.clone().merge(project).default_remote(), "company");
+        assert_eq!(global.merge(Config::default()).default_remote(), "upstream");
+    }
+
+    #[test]
+    fn log_file_comes_only_from_the_global_config() {
+        let project: Config = serde_yaml::from_str("log_file: /tmp/log.jsonl\n").unwrap();
+        assert_eq!(Config::default().merge(project).log_file, None);
+
+        let global: Config = serde_yaml::from_str("log_file: ~/debug.jsonl\n").unwrap();
+        let project: Config = serde_yaml::from_str("log_file: /tmp/log.jsonl\n").unwrap();
+        assert_eq!(
+            global.merge(project).log_file.as_deref(),
+            Some("~/debug.jsonl")
+        );
+    }
+
+    #[test]
+    fn confirm_policies_merge_per_action_with_defaults() {
+        let global: Config =
+            serde_yaml::from_str("confirm:\n  merge: ask\n  clean: skip\n").unwrap();
+        let project: Config = serde_yaml::from_str("confirm:\n  clean: ask-default-yes\n").unwrap();
+        let confirm = global.merge(project).confirm;
+
+        assert_eq!(confirm.remove(), ConfirmPolicy::Ask);
+        assert_eq!(confirm.merge(), ConfirmPolicy::Ask);
+        assert_eq!(confirm.clean(), ConfirmPolicy::AskDefaultYes);
+        assert_eq!(confirm.force(), ConfirmPolicy::Skip);
+        assert_eq!(confirm.removal(confirm.clean(), true), ConfirmPolicy::Skip);
+    }
+
+    #[test]
+    fn hooks_accept_plain_commands_and_timeouts() {
+        let global: Config = serde_yaml::from_str("post_create:\n  - mise install\n").unwrap();
+        let project: Config = serde_yaml::from_str(
+            "post_create:\n  - \"<global>\"\n  - command: pnpm install\n    timeout: 300\n",
+        )
+        .unwrap();
+        let hooks = global.merge(project).post_create.unwrap();
+
+        assert_eq!(
+            hooks,
+            vec![
+                HookCommand::Command("mise install".to_string()),
+                HookCommand::WithOptions {
+                    command: "pnpm install".to_string(),
+                    timeout: Some(300),
+                    when: None,
+                },
+            ]
+        );
+        assert_eq!(hooks[0].timeout(), None);
+        assert_eq!(hooks[1].command(), "pnpm install");
+        assert_eq!(hooks[1].timeout(), Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn local_config_layers_over_global_and_project() {
+        let global: Config =
+            serde_yaml::from_str("post_create:\n  - mise install\nwindow_prefix: g-\n").unwrap();
+        let project: Config =
+            serde_yaml::from_str("post_create:\n  - \"<global>\"\n  - pnpm install\n").unwrap();
+        let local: Config = serde_yaml::from_str(
+            "post_create:\n  - \"<global>\"\n  - pnpm db:seed\nwindow_prefix: l-\n",
+        )
+        .unwrap();
+        let config = Config::default().merge(global).merge(project).merge(local);
+
+        let hooks: Vec<&str> = config
+            .post_create
+            .iter()
+            .flatten()
+            .map(|hook| hook.command())
+            .collect();
+        assert_eq!(hooks, ["mise install", "pnpm install", "pnpm db:seed"]);
+        assert_eq!(config.window_prefix(), "l-");
+    }
+
+    #[test]
+    fn size_guard_parses_sizes_and_merges_per_field() {
+        let global: Config =
+            serde_yaml::from_str("size_guard:\n  min_size: 50MB\n  confirm_above: 2 GB\n").unwrap();
+        let project: Config = serde_yaml::from_str("size_guard:\n  min_size: 1.5k\n").unwrap();
+        let merged = global.merge(project);
+
+        assert!(merged.size_guard.enabled());
+        assert_eq!(merged.size_guard.min_size(), ByteSize(1536));
+        assert_eq!(
+            merged.size_guard.confirm_above,
+            Some(ByteSize(2 * 1024 * 1024 * 1024))
+        );
+        assert_eq!(
+            Config::default().size_guard.min_size(),
+            ByteSize(100 * 1024 * 1024)
+        );
+        assert_eq!("4096".parse(), Ok(ByteSize(4096)));
+        assert!("12 parsecs".parse::<ByteSize>().is_err());
+        assert!(serde_yaml::from_str::<Config>("size_guard:\n  min_size: lots\n").is_err());
+    }
+
+    #[test]
+    fn byte_size_displays_in_the_largest_unit() {
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KB");
+        assert_eq!(ByteSize(4_509_715_660).to_string(), "4.2 GB");
+    }
+
+    #[test]
+    fn worktree_dir_parses_strategies_and_plain_paths() {
+        let parse = |yaml: &str| serde_yaml::from_str::<Config>(yaml).map(|c| c.worktree_dir);
+        assert_eq!(
+            parse("worktree_dir: sibling").unwrap(),
+            Some(WorktreeDir::Sibling)
+        );
+        assert_eq!(
+            parse("worktree_dir: subdir:.worktrees").unwrap(),
+            Some(WorktreeDir::Subdir(".worktrees".to_string()))
+        );
+        assert_eq!(
+            parse("worktree_dir: \"global:~/wt/{{ repo }}/{{ handle }}\"").unwrap(),
+            Some(WorktreeDir::Global(
+                "~/wt/{{ repo }}/{{ handle }}".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("worktree_dir: ../trees").unwrap(),
+            Some(WorktreeDir::Path("../trees".to_string()))
+        );
+        assert!(parse("worktree_dir: subdir:/abs").is_err());
+        assert_eq!(
+            WorktreeDir::Subdir(".wt".to_string()).to_string(),
+            "subdir:.wt"
+        );
+    }
+
+    #[test]
+    fn file_ops_apply_when_agent_and_branch_match() {
+        let global: Config =
+            serde_yaml::from_str("files:\n  symlink:\n    - node_modules\n").unwrap();
+        let project: Config = serde_yaml::from_str(
+            "files:\n  symlink:\n    - \"<global>\"\n    - pattern: .claude\n      when:\n        agent: [claude]\n        branch: [\"feature/*\"]\n",
+        )
+        .unwrap();
+        let files = global.merge(project).files;
+
+        let patterns = |branch: &str, agent: Option<&str>| -> Vec<String> {
+            let target = WhenTarget {
+                branch,
+                agent,
+                worktree_path: Path::new("/nonexistent"),
+            };
+            files
+                .for_worktree(&target)
+                .symlink
+                .unwrap()
+                .iter()
+                .map(FileOp::to_string)
+                .collect()
+        };
+        assert_eq!(
+            patterns("feature/login", Some("claude --verbose")),
+            vec!["node_modules", ".claude"]
+        );
+        assert_eq!(patterns("fix/login", Some("claude")), vec!["node_modules"]);
+        assert_eq!(
+            patterns("feature/login", Some("gemini")),
+            vec!["node_modules"]
+        );
+        assert_eq!(patterns("feature/login", None), vec!["node_modules"]);
+
+        let typo = "files:\n  copy:\n    - pattern: .env\n      when:\n        agents: [claude]\n";
+        assert!(serde_yaml::from_str::<Config>(typo).is_err());
+    }
+
+    #[test]
+    fn hooks_run_when_branch_agent_and_files_match() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        let config: Config = serde_yaml::from_str(
+            "post_create:\n  - mise install\n  - command: pnpm install\n    when:\n      exists: package.json\n  - command: cargo fetch\n    when:\n      exists: Cargo.toml\n  - command: make claude-settings\n    when:\n      branch: 'feat/*'\n      agent: claude\n",
+        )
+        .unwrap();
+
+        let commands = |branch: &str, agent: Option<&str>| -> Vec<String> {
+            let target = WhenTarget {
+                branch,
+                agent,
+                worktree_path: dir.path(),
+            };
+            hooks_for(&config.post_create, &target)
+                .iter()
+                .map(|hook| hook.command().to_string())
+                .collect()
+        };
+        assert_eq!(
+            commands("feat/login", Some("claude")),
+            vec!["mise install", "pnpm install", "make claude-settings"]
+        );
+        assert_eq!(
+            commands("fix/login", Some("claude")),
+            vec!["mise install", "pnpm install"]
+        );
+        assert_eq!(
+            commands("feat/login", Some("codex")),
+            vec!["mise install", "pnpm install"]
+        );
+    }
+
+    #[test]
+    fn templates_are_picked_by_name_or_branch_and_replace_settings() {
+        let global: Config = serde_yaml::from_str(
+            "post_create:\n  - mise install\ntemplates:\n  docs:\n    branches: 'docs/*'\n    sparse: [docs/]\n",
+        )
+        .unwrap();
+        let project: Config = serde_yaml::from_str(
+            "templates:\n  frontend:\n    branches: ['web/*', 'ui-*']\n    sparse: [web/, packages/ui/]\n    post_create:\n      - pnpm install\n",
+        )
+        .unwrap();
+        let config = global.merge(project);
+
+        assert_eq!(
+            config
+                .select_template(None, "web/login")
+                .unwrap()
+                .as_deref(),
+            Some("frontend")
+        );
+        assert_eq!(
+            config
+                .select_template(None, "docs/intro")
+                .unwrap()
+                .as_deref(),
+            Some("docs")
+        );
+        assert_eq!(config.select_template(None, "fix/login").unwrap(), None);
+        assert_eq!(
+            config
+                .select_template(Some("frontend"), "fix/login")
+                .unwrap()
+                .as_deref(),
+            Some("frontend")
+        );
+        let err = config.select_template(Some("backend"), "x").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Available templates: docs, frontend")
+        );
+
+        let mut frontend = config.clone();
+        frontend.apply_template("frontend").unwrap();
+        assert_eq!(
+            frontend.sparse,
+            Some(vec!["web/".to_string(), "packages/ui/".to_string()])
+        );
+        assert_eq!(
+            frontend.post_create,
+            Some(vec![HookCommand::Command("pnpm install".to_string())])
+        );
+        assert_eq!(frontend.applied_template.as_deref(), Some("frontend"));
+
+        let mut docs = config.clone();
+        docs.apply_template("docs").unwrap();
+        assert_eq!(
+            docs.post_create,
+            Some(vec![HookCommand::Command("mise install".to_string())])
+        );
+
+        let typo = "templates:\n  web:\n    hooks: [pnpm install]\n";
+        assert!(serde_yaml::from_str::<Config>(typo).is_err());
+    }
+
+    #[test]
+    fn git_config_accepts_yaml_scalars_and_merges_per_key() {
+        let global: Config = serde_yaml::from_str(
+            "git_config:\n  user.email: me@example.com\n  commit.gpgsign: true\n",
+        )
+        .unwrap();
+        let project: Config =
+            serde_yaml::from_str("git_config:\n  commit.gpgsign: false\n  gc.auto: 0\n").unwrap();
+        let values: Vec<(String, String)> = global
+            .merge(project)
+            .git_config
+            .unwrap()
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                ("commit.gpgsign".to_string(), "false".to_string()),
+                ("gc.auto".to_string(), "0".to_string()),
+                ("user.email".to_string(), "me@example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn retention_takes_durations_or_forever() {
+        let global: Config =
+            serde_yaml::from_str("retention:\n  logs: 2w\n  trash: forever\n").unwrap();
+        let project: Config = serde_yaml::from_str("retention:\n  logs: 12h\n").unwrap();
+        let merged = global.merge(project);
+        assert_eq!(
+            merged.retention.logs(),
+            Some(Duration::from_secs(12 * 3600))
+        );
+        assert_eq!(merged.retention.trash(), None);
+        assert_eq!(
+            merged.retention.checkpoints(),
+            Some(Duration::from_secs(7 * 86_400))
+        );
+        assert!(serde_yaml::from_str::<Config>("retention:\n  logs: 30 days\n").is_err());
+    }
+
+    #[test]
+    fn panes_take_a_list_or_a_layout() {
+        let config: Config =
+            serde_yaml::from_str("panes:\n  - command: vim\n  - split: horizontal\n").unwrap();
+        let panes = config.panes.unwrap();
+        assert_eq!(panes.len(), 2);
+        assert!(panes.layout.is_none());
+
+        let config: Config =
+            serde_yaml::from_str("panes:\n  layout: main-vertical\n  list:\n    - command: vim\n")
+                .unwrap();
+        let panes = config.panes.unwrap();
+        assert_eq!(panes.layout.as_deref(), Some("main-vertical"));
+        assert_eq!(panes[0].command.as_deref(), Some("vim"));
+        panes.validate().unwrap();
+
+        // A layout alone leaves the list to the defaults
+        let config: Config = serde_yaml::from_str("panes:\n  layout: tiled\n").unwrap();
+        assert!(!config.panes.unwrap().has_list());
+
+        let err = serde_yaml::from_str::<Config>("panes:\n  - split: diagonal\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("diagonal"), "{}", err);
+    }
+
+    #[test]
+    fn review_panes_show_the_pr_diff_unless_configured() {
+        let config: Config =
+            serde_yaml::from_str("pr:\n  review_panes:\n    - command: tig\n").unwrap();
+        let panes = config.review_panes().unwrap();
+        assert_eq!(panes.len(), 1);
+        assert_eq!(panes[0].command.as_deref(), Some("tig"));
+
+        let panes = Config::review_default_panes(Some("cargo test"));
+        let diff = crate::template::render_command(
+            panes[0].command.as_deref().unwrap(),
+            &serde_json::json!({ "pr_base": "origin/main" }),
+        )
+        .unwrap();
+        assert_eq!(diff, "git diff origin/main...HEAD");
+        assert!(panes[0].focus);
+        assert_eq!(panes[1].command.as_deref(), Some("cargo test"));
+        assert_eq!(panes[1].split, Some(SplitDirection::Horizontal));
+    }
+
+    #[test]
+    fn layout_strings_need_a_matching_checksum() {
+        validate_layout("tiled").unwrap();
+        validate_layout("95e4,120x40,0,0{60x40,0,0,0,59x40,61,0[59x20,61,0,1,59x19,61,21,2]}")
+            .unwrap();
+        validate_layout("95e4,120x40,0,0{61x40,0,0,0,58x40,62,0[58x20,62,0,1,58x19,62,21,2]}")
+            .unwrap_err();
+        validate_layout("main-sideways").unwrap_err();
+    }
+
+    #[test]
+    fn team_panes_builds_one_pane_per_member() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+agent: codex
+team:
+  default:
+    - role: implementer
+      focus: true
+    - role: reviewer
+      agent: gemini
+      split: vertical
+    - role: tester
+      agent: claude
+"#,
+        )
+        .unwrap();
+
+        let panes = config.team_panes("default").unwrap();
+        let roles: Vec<&str> = panes.iter().map(|(role, _)| role.as_str()).collect();
+        assert_eq!(roles, ["implementer", "reviewer", "tester"]);
+
+        assert_eq!(panes[0].1.command.as_deref(), Some("codex"));
+        assert!(panes[0].1.focus);
+        assert!(panes[0].1.split.is_none());
+        assert_eq!(panes[1].1.command.as_deref(), Some("gemini"));
+        assert_eq!(panes[1].1.split, Some(SplitDirection::Vertical));
+        assert_eq!(panes[2].1.split, Some(SplitDirection::Horizontal));
+    }
+
+    #[test]
+    fn team_panes_rejects_unknown_team_and_duplicate_roles() {
+        let config: Config =
+            serde_yaml::from_str("team:\n  pair:\n    - role: a\n    - role: a\n").unwrap();
+
+        let err = config.team_panes("default").unwrap_err().to_string();
+        assert!(err.contains("Available teams: pair"), "{}", err);
+
+        let err = config.team_panes("pair").unwrap_err().to_string();
+        assert!(err.contains("more than once"), "{}", err);
+
+        let err = Config::default()
+            .team_panes("pair")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no teams are configured"), "{}", err);
+    }
 }
@@ -0,0 +1,35 @@
+//! Process-wide dry-run mode, enabled with the global `--dry-run` flag.
+//!
+//! When enabled, [`crate::cmd`] still runs git and tmux commands that only
+//! read state, so workflows make the same decisions, but prints every other
+//! command instead of running it. Hooks and file operations are printed the
+//! same way.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Turn on dry-run mode for the rest of the process
+pub fn enable() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+/// Whether changes should be printed rather than made
+pub fn is_enabled() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// In dry-run mode, print `action` in place of doing it and return true
+pub fn skip(action: impl Display) -> bool {
+    if is_enabled() {
+        println!("[dry-run] {}", action);
+    }
+    is_enabled()
+}
+
+/// `done` to report a finished change, or `would` in dry-run mode, where it
+/// was only printed
+pub fn outcome<'a>(done: &'a str, would: &'a str) -> &'a str {
+    if is_enabled() { would } else { done }
+}
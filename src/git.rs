@@ -1,12 +1,17 @@
 use anyhow::{Context, Result, anyhow};
 use git_url_parse::GitUrl;
 use git_url_parse::types::provider::GenericProvider;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
 use tracing::{debug, info};
 
 use crate::cmd::Cmd;
+use crate::config::{Config, GitConfigValue, HandleMatching};
 
 #[derive(Debug, Clone)]
 pub struct RemoteBranchSpec {
@@ -25,6 +30,100 @@ pub struct ForkBranchSpec {
 #[error("Worktree not found: {0}")]
 pub struct WorktreeNotFound(pub String);
 
+/// The repository selected with `GIT_DIR`/`GIT_WORK_TREE` by whatever ran
+/// workmux, e.g. a wrapper tool or a repository with a separate git dir.
+///
+/// The variables are taken out of the environment at startup, so that git
+/// commands in linked worktrees, hooks and panes find their own repository.
+/// Git commands acting on this repository get them back as `--git-dir` and
+/// `--work-tree`.
+#[derive(Debug)]
+pub struct RepoContext {
+    pub git_dir: Option<PathBuf>,
+    /// None for a bare repository
+    pub work_tree: Option<PathBuf>,
+}
+
+static REPO_CONTEXT: OnceLock<RepoContext> = OnceLock::new();
+
+/// Capture `GIT_DIR` and `GIT_WORK_TREE` into the [`RepoContext`] and remove
+/// them from the environment. Must run before any other thread starts.
+pub fn take_repo_env() {
+    let git_dir = std::env::var_os("GIT_DIR");
+    let work_tree = std::env::var_os("GIT_WORK_TREE");
+    if git_dir.is_none() && work_tree.is_none() {
+        return;
+    }
+
+    // Resolve to absolute paths while the variables still apply, so they stay
+    // valid after workmux changes directory. This also picks up core.worktree.
+    let resolve = |flag: &str| {
+        let output = Command::new("git")
+            .args(["rev-parse", flag])
+            .output()
+            .ok()?;
+        let path = String::from_utf8(output.stdout).ok()?;
+        (output.status.success() && !path.trim().is_empty()).then(|| PathBuf::from(path.trim()))
+    };
+    let absolute = |value: Option<std::ffi::OsString>| {
+        value.and_then(|value| std::path::absolute(PathBuf::from(value)).ok())
+    };
+    let context = RepoContext {
+        git_dir: resolve("--absolute-git-dir").or_else(|| absolute(git_dir)),
+        work_tree: resolve("--show-toplevel").or_else(|| absolute(work_tree)),
+    };
+
+    // SAFETY: called at the start of main, before any threads exist
+    unsafe {
+        std::env::remove_var("GIT_DIR");
+        std::env::remove_var("GIT_WORK_TREE");
+    }
+    let _ = REPO_CONTEXT.set(context);
+}
+
+/// The repository selected through the environment, if any
+pub fn repo_context() -> Option<&'static RepoContext> {
+    REPO_CONTEXT.get()
+}
+
+impl RepoContext {
+    /// Whether a git command run in `workdir` (the current directory if None)
+    /// acts on this repository rather than on a linked worktree.
+    fn applies_to(&self, workdir: Option<&Path>) -> bool {
+        let Some(dir) = workdir else {
+            return true;
+        };
+        let Some(work_tree) = &self.work_tree else {
+            return false;
+        };
+        // A linked worktree inside the work tree has its own `.git` file
+        dir.starts_with(work_tree)
+            && !dir
+                .ancestors()
+                .take_while(|ancestor| ancestor != work_tree)
+                .any(|ancestor| ancestor.join(".git").exists())
+    }
+}
+
+/// Point a git command at the [`RepoContext`] repository when it acts on it
+pub fn apply_repo_context(cmd: &mut Command, workdir: Option<&Path>) {
+    let Some(context) = repo_context().filter(|context| context.applies_to(workdir)) else {
+        return;
+    };
+    if let Some(git_dir) = &context.git_dir {
+        cmd.arg(concat_flag("--git-dir=", git_dir));
+    }
+    if let Some(work_tree) = &context.work_tree {
+        cmd.arg(concat_flag("--work-tree=", work_tree));
+    }
+}
+
+fn concat_flag(flag: &str, path: &Path) -> std::ffi::OsString {
+    let mut arg = OsStr::new(flag).to_os_string();
+    arg.push(path);
+    arg
+}
+
 /// Check if we're in a git repository
 pub fn is_git_repo() -> Result<bool> {
     Cmd::new("git")
@@ -47,6 +146,41 @@ pub fn get_repo_root() -> Result<PathBuf> {
     Ok(PathBuf::from(path))
 }
 
+/// The root of the repository containing the current one, when the current
+/// one is its submodule or a repository it tracks. None otherwise, and
+/// outside a repository.
+pub fn get_superproject_root() -> Option<PathBuf> {
+    Cmd::new("git")
+        .args(&["rev-parse", "--show-superproject-working-tree"])
+        .run_and_capture_stdout()
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// How long ago the repository was last fetched, based on FETCH_HEAD's mtime.
+/// Returns None if the repository has never been fetched.
+pub fn last_fetch_age() -> Option<Duration> {
+    // May be relative to the current directory, which is fine for metadata()
+    let common_dir = Cmd::new("git")
+        .args(&["rev-parse", "--git-common-dir"])
+        .run_and_capture_stdout()
+        .ok()?;
+    let modified = std::fs::metadata(Path::new(&common_dir).join("FETCH_HEAD"))
+        .and_then(|m| m.modified())
+        .ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+/// The repository's common git directory, shared by all of its worktrees
+pub fn get_git_common_dir() -> Result<PathBuf> {
+    let path = Cmd::new("git")
+        .args(&["rev-parse", "--path-format=absolute", "--git-common-dir"])
+        .run_and_capture_stdout()
+        .context("Failed to locate the git directory")?;
+    Ok(PathBuf::from(path))
+}
+
 /// Get the main worktree root directory (not a linked worktree)
 pub fn get_main_worktree_root() -> Result<PathBuf> {
     // Get all worktrees
@@ -66,12 +200,13 @@ pub fn get_main_worktree_root() -> Result<PathBuf> {
 }
 
 /// Get the default branch (main or master)
-pub fn get_default_branch() -> Result<String> {
+pub fn get_default_branch(remote: &str) -> Result<String> {
     // Try to get the default branch from the remote
+    let remote_prefix = format!("refs/remotes/{}/", remote);
     if let Ok(ref_name) = Cmd::new("git")
-        .args(&["symbolic-ref", "refs/remotes/origin/HEAD"])
+        .args(&["symbolic-ref", &format!("{}HEAD", remote_prefix)])
         .run_and_capture_stdout()
-        && let Some(branch) = ref_name.strip_prefix("refs/remotes/origin/")
+        && let Some(branch) = ref_name.strip_prefix(&remote_prefix)
     {
         debug!(branch = branch, "git:default branch from remote HEAD");
         return Ok(branch.to_string());
@@ -180,6 +315,52 @@ pub fn fetch_remote(remote: &str) -> Result<()> {
     Ok(())
 }
 
+/// Prefix of the remote-tracking refs merge requests are fetched to by
+/// [`fetch_merge_request`]
+const MERGE_REQUEST_PREFIX: &str = "merge-requests/";
+
+/// Fetch GitLab merge request `number` from `remote`, where GitLab keeps the
+/// head of every merge request, forks included. Returns the remote-tracking
+/// branch it was fetched to.
+pub fn fetch_merge_request(remote: &str, number: u32) -> Result<String> {
+    let branch = format!("{}{}", MERGE_REQUEST_PREFIX, number);
+    let refspec = format!(
+        "+refs/merge-requests/{}/head:refs/remotes/{}/{}",
+        number, remote, branch
+    );
+    Cmd::new("git")
+        .args(&["fetch", "--quiet", remote, &refspec])
+        .run()
+        .with_context(|| {
+            format!(
+                "Failed to fetch merge request !{} from '{}'",
+                number, remote
+            )
+        })?;
+    Ok(format!("{}/{}", remote, branch))
+}
+
+/// The ref on the remote that remote branch `branch`, as named by
+/// [`fetch_merge_request`], was fetched from
+pub fn merge_request_head(branch: &str) -> Option<String> {
+    let number: u32 = branch.strip_prefix(MERGE_REQUEST_PREFIX)?.parse().ok()?;
+    Some(format!("refs/merge-requests/{}/head", number))
+}
+
+/// Make `branch_name` pull from `merge_ref` on its remote, a ref no remote
+/// branch maps to
+pub fn set_branch_merge_ref(branch_name: &str, merge_ref: &str) -> Result<()> {
+    Cmd::new("git")
+        .args(&[
+            "config",
+            &format!("branch.{}.merge", branch_name),
+            merge_ref,
+        ])
+        .run()
+        .with_context(|| format!("Failed to set the upstream of '{}'", branch_name))?;
+    Ok(())
+}
+
 /// Add a git remote if it doesn't exist
 pub fn add_remote(name: &str, url: &str) -> Result<()> {
     Cmd::new("git")
@@ -211,22 +392,23 @@ pub fn get_remote_url(remote: &str) -> Result<String> {
 
 /// Ensure a remote exists for a specific fork owner.
 /// Returns the name of the remote (e.g., "origin" or "fork-username").
-/// If the remote needs to be created, it constructs the URL based on the origin URL's scheme.
-pub fn ensure_fork_remote(fork_owner: &str) -> Result<String> {
-    // If the fork owner is the same as the origin owner, just use origin
-    let current_owner = get_repo_owner().unwrap_or_default();
+/// If the remote needs to be created, it constructs the URL based on the URL scheme of
+/// `base_remote` (the repository PRs are opened against).
+pub fn ensure_fork_remote(fork_owner: &str, base_remote: &str) -> Result<String> {
+    // If the fork owner is the same as the base remote's owner, just use that remote
+    let current_owner = get_repo_owner(base_remote).unwrap_or_default();
     if !current_owner.is_empty() && fork_owner == current_owner {
-        return Ok("origin".to_string());
+        return Ok(base_remote.to_string());
     }
 
     let remote_name = format!("fork-{}", fork_owner);
 
-    // Construct fork URL based on origin URL format, preserving host and protocol
-    let origin_url = get_remote_url("origin")?;
-    let parsed_url = GitUrl::parse(&origin_url).with_context(|| {
+    // Construct fork URL based on the base remote's URL format, preserving host and protocol
+    let base_url = get_remote_url(base_remote)?;
+    let parsed_url = GitUrl::parse(&base_url).with_context(|| {
         format!(
-            "Failed to parse origin URL for fork remote construction: {}",
-            origin_url
+            "Failed to parse {} URL for fork remote construction: {}",
+            base_remote, base_url
         )
     })?;
 
@@ -235,7 +417,7 @@ pub fn ensure_fork_remote(fork_owner: &str) -> Result<String> {
 
     let provider: GenericProvider = parsed_url
         .provider_info()
-        .with_context(|| "Failed to extract provider info from origin URL")?;
+        .with_context(|| format!("Failed to extract provider info from {} URL", base_remote))?;
     let repo_name = provider.repo();
 
     let fork_url = match scheme {
@@ -283,12 +465,18 @@ fn parse_owner_from_git_url(url: &str) -> Option<&str> {
     }
 }
 
-/// Get the repository owner from the origin remote URL
-pub fn get_repo_owner() -> Result<String> {
-    let url = get_remote_url("origin")?;
+/// Get the repository owner from the given remote's URL
+pub fn get_repo_owner(remote: &str) -> Result<String> {
+    let url = get_remote_url(remote)?;
 
     parse_owner_from_git_url(&url)
-        .ok_or_else(|| anyhow!("Could not parse repository owner from origin URL: {}", url))
+        .ok_or_else(|| {
+            anyhow!(
+                "Could not parse repository owner from {} URL: {}",
+                remote,
+                url
+            )
+        })
         .map(|s| s.to_string())
 }
 
@@ -314,12 +502,16 @@ pub fn create_worktree(
     create_branch: bool,
     base_branch: Option<&str>,
     track_upstream: bool,
+    sparse: &[String],
 ) -> Result<()> {
     let path_str = worktree_path
         .to_str()
         .ok_or_else(|| anyhow!("Invalid worktree path"))?;
 
     let mut cmd = Cmd::new("git").arg("worktree").arg("add");
+    if !sparse.is_empty() {
+        cmd = cmd.arg("--no-checkout");
+    }
 
     if create_branch {
         cmd = cmd.arg("-b").arg(branch_name).arg(path_str);
@@ -332,6 +524,10 @@ pub fn create_worktree(
 
     cmd.run().context("Failed to create worktree")?;
 
+    if !sparse.is_empty() {
+        checkout_sparse(worktree_path, sparse)?;
+    }
+
     // When creating a new branch from a remote tracking branch (e.g., origin/main),
     // git automatically sets up tracking for the new branch. This is desirable when
     // opening a remote branch locally, but we unset the upstream when the new branch
@@ -343,6 +539,89 @@ pub fn create_worktree(
     Ok(())
 }
 
+/// Create a worktree at `worktree_path` with `commit` checked out detached,
+/// so no local branch is created or needed
+pub fn create_detached_worktree(
+    worktree_path: &Path,
+    commit: &str,
+    sparse: &[String],
+) -> Result<()> {
+    let path_str = worktree_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    let mut args = vec!["worktree", "add", "--detach"];
+    if !sparse.is_empty() {
+        args.push("--no-checkout");
+    }
+    args.extend([path_str, commit]);
+    Cmd::new("git")
+        .args(&args)
+        .run()
+        .context("Failed to create worktree")?;
+
+    if !sparse.is_empty() {
+        checkout_sparse(worktree_path, sparse)?;
+    }
+    Ok(())
+}
+
+/// Sets up a review worktree's own hooks directory, printing its path
+const REVIEW_HOOKS_SCRIPT: &str = include_str!("scripts/review_hooks.sh");
+
+/// Make commits fail in the detached worktree at `worktree_path` until a
+/// branch is checked out there, since they would be on no branch. The
+/// worktree's `core.hooksPath` points to hooks of its own, which run the
+/// repository's hooks otherwise.
+pub fn refuse_detached_commits(worktree_path: &Path) -> Result<()> {
+    let hooks = Cmd::new("sh")
+        .workdir(worktree_path)
+        .args(&["-c", REVIEW_HOOKS_SCRIPT])
+        .run_and_capture_stdout()
+        .context("Failed to set up the worktree's hooks")?;
+    let values = BTreeMap::from([("core.hooksPath".to_string(), GitConfigValue::String(hooks))]);
+    set_worktree_config(worktree_path, &values)
+}
+
+/// Number of commits checked out in `worktree_path` that no branch, tag or
+/// remote branch has, which removing a detached worktree loses
+pub fn unreferenced_commits(worktree_path: &Path) -> Result<usize> {
+    let count = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&[
+            "rev-list",
+            "--count",
+            "HEAD",
+            "--not",
+            "--branches",
+            "--tags",
+            "--remotes",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to count the worktree's commits")?;
+    count
+        .parse()
+        .with_context(|| format!("Unexpected commit count '{}'", count))
+}
+
+/// Check out a worktree added with `--no-checkout`, limited to the `sparse`
+/// directories (plus files at the top level). The sparse settings are stored
+/// per worktree, so the main checkout is unaffected.
+fn checkout_sparse(worktree_path: &Path, sparse: &[String]) -> Result<()> {
+    let mut args = vec!["sparse-checkout", "set", "--cone", "--"];
+    args.extend(sparse.iter().map(|path| path.trim_end_matches('/')));
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&args)
+        .run()
+        .context("Failed to set up sparse checkout")?;
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .arg("checkout")
+        .run()
+        .context("Failed to check out sparse worktree")?;
+    Ok(())
+}
+
 /// Unset the upstream tracking for a branch
 pub fn unset_branch_upstream(branch_name: &str) -> Result<()> {
     if !branch_has_upstream(branch_name)? {
@@ -374,6 +653,110 @@ fn branch_has_upstream(branch_name: &str) -> Result<bool> {
         .run_as_check()
 }
 
+/// The remote and remote branch `branch_name` was pushed to: its upstream,
+/// or else a branch of the same name on `default_remote`
+pub fn remote_branch_of(branch_name: &str, default_remote: &str) -> Option<(String, String)> {
+    let config = |key: &str| {
+        Cmd::new("git")
+            .args(&[
+                "config",
+                "--get",
+                &format!("branch.{}.{}", branch_name, key),
+            ])
+            .run_and_capture_stdout()
+            .ok()
+    };
+    // A remote of "." is a local upstream
+    if let Some(remote) = config("remote").filter(|remote| remote != ".")
+        && let Some(branch) = config("merge")
+            .as_deref()
+            .and_then(|merge| merge.strip_prefix("refs/heads/"))
+    {
+        return Some((remote, branch.to_string()));
+    }
+    let remote_ref = format!("refs/remotes/{}/{}", default_remote, branch_name);
+    Cmd::new("git")
+        .args(&["show-ref", "--verify", "--quiet", &remote_ref])
+        .run_as_check()
+        .unwrap_or(false)
+        .then(|| (default_remote.to_string(), branch_name.to_string()))
+}
+
+/// Delete `branch` on `remote`. Returns the commit it was last fetched at,
+/// if it was fetched.
+pub fn delete_remote_branch(remote: &str, branch: &str) -> Result<Option<String>> {
+    let commit = Cmd::new("git")
+        .args(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/remotes/{}/{}", remote, branch),
+        ])
+        .run_and_capture_stdout()
+        .ok();
+    Cmd::new("git")
+        .args(&["push", remote, "--delete", branch])
+        .run()
+        .with_context(|| format!("Failed to delete '{}' on '{}'", branch, remote))?;
+    Ok(commit)
+}
+
+/// Remove a worktree and its directory, discarding any changes in it
+pub fn remove_worktree(worktree_path: &Path) -> Result<()> {
+    let main_worktree_root = get_main_worktree_root()?;
+    let path_str = worktree_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    Cmd::new("git")
+        .workdir(&main_worktree_root)
+        .args(&["worktree", "remove", "--force", path_str])
+        .run()
+        .context("Failed to remove worktree")?;
+    Ok(())
+}
+
+/// Lock a worktree, so git's `worktree prune` and `worktree remove` leave
+/// it alone. A worktree already locked is left as it is.
+pub fn lock_worktree(worktree_path: &Path, reason: &str) -> Result<()> {
+    let path_str = worktree_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    if is_worktree_locked(worktree_path)? {
+        return Ok(());
+    }
+    Cmd::new("git")
+        .args(&["worktree", "lock", "--reason", reason, path_str])
+        .run()
+        .context("Failed to lock worktree")?;
+    Ok(())
+}
+
+/// Unlock a worktree. A worktree that isn't locked is left as it is.
+pub fn unlock_worktree(worktree_path: &Path) -> Result<()> {
+    let path_str = worktree_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    if !is_worktree_locked(worktree_path)? {
+        return Ok(());
+    }
+    Cmd::new("git")
+        .args(&["worktree", "unlock", path_str])
+        .run()
+        .context("Failed to unlock worktree")?;
+    Ok(())
+}
+
+/// Whether git has the worktree at `worktree_path` locked
+pub fn is_worktree_locked(worktree_path: &Path) -> Result<bool> {
+    let list = Cmd::new("git")
+        .args(&["worktree", "list", "--porcelain"])
+        .run_and_capture_stdout()
+        .context("Failed to list worktrees")?;
+    Ok(parse_locked_worktrees(&list)
+        .iter()
+        .any(|path| path == worktree_path))
+}
+
 /// Prune stale worktree metadata
 pub fn prune_worktrees() -> Result<()> {
     // Ensure this command always runs from a valid git directory.
@@ -395,11 +778,16 @@ fn parse_worktree_list_porcelain(output: &str) -> Result<Vec<(PathBuf, String)>>
 
         for line in block.lines() {
             if let Some(p) = line.strip_prefix("worktree ") {
-                path = Some(PathBuf::from(p));
+                path = Some(listed_worktree_path(p));
             } else if let Some(b) = line.strip_prefix("branch refs/heads/") {
                 branch = Some(b.to_string());
             } else if line.trim() == "detached" {
-                branch = Some("(detached)".to_string());
+                // A rebase detaches HEAD from the branch until it finishes
+                let rebasing = path
+                    .as_deref()
+                    .and_then(worktree_git_dir)
+                    .and_then(|git_dir| rebasing_branch(&git_dir));
+                branch = Some(rebasing.unwrap_or_else(|| "(detached)".to_string()));
             }
         }
 
@@ -410,6 +798,56 @@ fn parse_worktree_list_porcelain(output: &str) -> Result<Vec<(PathBuf, String)>>
     Ok(worktrees)
 }
 
+/// Worktrees in the output of `git worktree list --porcelain` that git marks
+/// prunable, with the reason it gives
+fn parse_prunable_worktrees(output: &str) -> Vec<(PathBuf, String)> {
+    output
+        .trim()
+        .split("\n\n")
+        .filter_map(|block| {
+            let path = block
+                .lines()
+                .find_map(|line| line.strip_prefix("worktree "))?;
+            let reason = block.lines().find_map(|line| {
+                line.strip_prefix("prunable")
+                    .map(|reason| reason.trim().to_string())
+            })?;
+            Some((listed_worktree_path(path), reason))
+        })
+        .collect()
+}
+
+/// Worktrees in the output of `git worktree list --porcelain` that git marks
+/// locked
+fn parse_locked_worktrees(output: &str) -> Vec<PathBuf> {
+    output
+        .trim()
+        .split("\n\n")
+        .filter_map(|block| {
+            let path = block
+                .lines()
+                .find_map(|line| line.strip_prefix("worktree "))?;
+            block
+                .lines()
+                .any(|line| line == "locked" || line.starts_with("locked "))
+                .then(|| listed_worktree_path(path))
+        })
+        .collect()
+}
+
+/// `git worktree list` shows a work tree set with core.worktree or
+/// `GIT_WORK_TREE` as its git directory
+fn listed_worktree_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    match repo_context() {
+        Some(RepoContext {
+            git_dir: Some(git_dir),
+            work_tree: Some(work_tree),
+        }) if *git_dir == path => work_tree.clone(),
+        _ => path,
+    }
+}
+
 /// Get the path to a worktree for a given branch
 pub fn get_worktree_path(branch_name: &str) -> Result<PathBuf> {
     let list_str = Cmd::new("git")
@@ -428,34 +866,254 @@ pub fn get_worktree_path(branch_name: &str) -> Result<PathBuf> {
     Err(WorktreeNotFound(branch_name.to_string()).into())
 }
 
+/// What a worktree name is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WorktreeMatch {
+    /// Handle (directory name) first, then branch name
+    #[default]
+    Any,
+    /// Only the handle
+    Handle,
+    /// Only the branch checked out in the worktree
+    Branch,
+}
+
+/// A name that matches more than one worktree
+#[derive(Debug, thiserror::Error)]
+#[error("{}", describe_ambiguity(.name, .candidates))]
+pub struct AmbiguousWorktree {
+    pub name: String,
+    pub candidates: Vec<WorktreeCandidate>,
+}
+
+/// A worktree matched by name, and how it matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorktreeCandidate {
+    pub path: PathBuf,
+    pub branch: String,
+    /// `Handle` or `Branch`
+    pub matched_by: WorktreeMatch,
+}
+
+impl WorktreeCandidate {
+    fn describe(&self) -> String {
+        match self.matched_by {
+            WorktreeMatch::Branch => format!("{} (branch '{}')", self.path.display(), self.branch),
+            _ => format!("{} (directory name)", self.path.display()),
+        }
+    }
+}
+
+fn describe_ambiguity(name: &str, candidates: &[WorktreeCandidate]) -> String {
+    let mut message = format!("'{}' matches more than one worktree:", name);
+    for candidate in candidates {
+        message.push_str(&format!("\n  - {}", candidate.describe()));
+    }
+    if candidates
+        .iter()
+        .any(|c| c.matched_by == WorktreeMatch::Branch)
+    {
+        message.push_str("\nUse --by-handle or --by-branch to pick one.");
+    }
+    message
+}
+
+/// Names resolved interactively or by strict matching earlier in this process,
+/// so later lookups of the same name find the same worktree without asking again.
+static PINNED: Mutex<Vec<(String, PathBuf)>> = Mutex::new(Vec::new());
+
+/// Make later lookups of `name` resolve to the worktree at `path`.
+pub fn pin_worktree(name: &str, path: &Path) {
+    if let Ok(mut pinned) = PINNED.lock() {
+        pinned.retain(|(pinned_name, _)| pinned_name != name);
+        pinned.push((name.to_string(), path.to_path_buf()));
+    }
+}
+
+fn pinned_worktree(name: &str) -> Option<PathBuf> {
+    let pinned = PINNED.lock().ok()?;
+    pinned
+        .iter()
+        .find(|(pinned_name, _)| pinned_name == name)
+        .map(|(_, path)| path.clone())
+}
+
 /// Find a worktree by handle (directory name) or branch name.
-/// Tries handle first, then falls back to branch lookup.
 /// Returns both the path and the branch name checked out in that worktree.
+///
+/// With no exact match, the `handle_matching` setting may allow a unique
+/// prefix or substring of a handle instead.
+///
+/// A name that matches several worktrees (a handle of one and the branch of
+/// another, duplicate directory names, or several partial matches) is an
+/// error, unless stdin is a terminal, in which case the user picks one.
 pub fn find_worktree(name: &str) -> Result<(PathBuf, String)> {
+    find_worktree_by(name, WorktreeMatch::Any)
+}
+
+/// Find a worktree, matching the name only in the ways `by` allows.
+pub fn find_worktree_by(name: &str, by: WorktreeMatch) -> Result<(PathBuf, String)> {
     let list_str = Cmd::new("git")
         .args(&["worktree", "list", "--porcelain"])
         .run_and_capture_stdout()
         .context("Failed to list worktrees")?;
 
     let worktrees = parse_worktree_list_porcelain(&list_str)?;
+    resolve_worktree(&worktrees, name, by)
+}
 
-    // First: try to match by handle (directory name)
-    for (path, branch) in &worktrees {
-        if let Some(dir_name) = path.file_name()
-            && dir_name.to_string_lossy() == name
-        {
-            return Ok((path.clone(), branch.clone()));
+/// Of `worktrees`, the one `name` refers to, as [`find_worktree_by`] finds it
+fn resolve_worktree(
+    worktrees: &[(PathBuf, String)],
+    name: &str,
+    by: WorktreeMatch,
+) -> Result<(PathBuf, String)> {
+    if let Some(path) = pinned_worktree(name)
+        && let Some(worktree) = worktrees.iter().find(|(p, _)| *p == path)
+    {
+        return Ok(worktree.clone());
+    }
+
+    let mut candidates = match_worktree(worktrees, name, by);
+    let mut partial = false;
+    if candidates.is_empty() && by == WorktreeMatch::Any {
+        candidates = match_handle_partially(worktrees, name, configured_handle_matching())
+            .into_iter()
+            .map(|(path, branch)| WorktreeCandidate {
+                path,
+                branch,
+                matched_by: WorktreeMatch::Handle,
+            })
+            .collect();
+        partial = true;
+    }
+
+    match candidates.len() {
+        0 => Err(anyhow!(WorktreeNotFound(name.to_string())).context(format!(
+            "No worktree found with name '{}'. Use 'workmux list' to see available worktrees.",
+            name
+        ))),
+        1 => {
+            let candidate = candidates.remove(0);
+            if partial {
+                // Later lookups in this process skip reloading the config
+                pin_worktree(name, &candidate.path);
+            }
+            Ok((candidate.path, candidate.branch))
+        }
+        _ => {
+            let ambiguous = AmbiguousWorktree {
+                name: name.to_string(),
+                candidates,
+            };
+            if !(io::stdin().is_terminal() && io::stderr().is_terminal()) {
+                return Err(ambiguous.into());
+            }
+            let candidate = choose_worktree(ambiguous)?;
+            pin_worktree(name, &candidate.path);
+            Ok((candidate.path, candidate.branch))
         }
     }
+}
 
-    // Fallback: try to match by branch name
-    for (path, branch) in worktrees {
-        if branch == name {
-            return Ok((path, branch));
+/// Ask the user which of several matching worktrees they meant.
+/// Prompts on stderr so commands like `path` keep stdout clean.
+fn choose_worktree(ambiguous: AmbiguousWorktree) -> Result<WorktreeCandidate> {
+    eprintln!("'{}' matches more than one worktree:", ambiguous.name);
+    for (i, candidate) in ambiguous.candidates.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, candidate.describe());
+    }
+    eprint!("Which one? [1-{}] ", ambiguous.candidates.len());
+    io::stderr().flush().context("Failed to flush stderr")?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read user input")?;
+
+    match input.trim().parse::<usize>() {
+        Ok(choice) if (1..=ambiguous.candidates.len()).contains(&choice) => {
+            Ok(ambiguous.candidates[choice - 1].clone())
+        }
+        _ => Err(ambiguous.into()),
+    }
+}
+
+/// The `handle_matching` setting, read only when a name has no exact match.
+fn configured_handle_matching() -> HandleMatching {
+    Config::load(None)
+        .map(|config| config.handle_matching())
+        .unwrap_or_default()
+}
+
+/// Worktrees whose handle (directory name) partially matches `name`: those
+/// starting with it, or if there are none and `mode` allows, those containing it.
+pub fn match_handle_partially(
+    worktrees: &[(PathBuf, String)],
+    name: &str,
+    mode: HandleMatching,
+) -> Vec<(PathBuf, String)> {
+    if name.is_empty() || mode == HandleMatching::Exact {
+        return Vec::new();
+    }
+    let handle_of = |path: &Path| {
+        path.file_name()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let prefixed: Vec<_> = worktrees
+        .iter()
+        .filter(|(path, _)| handle_of(path).starts_with(name))
+        .cloned()
+        .collect();
+    if !prefixed.is_empty() || mode == HandleMatching::Prefix {
+        return prefixed;
+    }
+    worktrees
+        .iter()
+        .filter(|(path, _)| handle_of(path).contains(name))
+        .cloned()
+        .collect()
+}
+
+/// Every worktree `name` matches under `by`. A worktree matched both by handle
+/// and by branch is listed once.
+fn match_worktree(
+    worktrees: &[(PathBuf, String)],
+    name: &str,
+    by: WorktreeMatch,
+) -> Vec<WorktreeCandidate> {
+    let mut candidates: Vec<WorktreeCandidate> = Vec::new();
+
+    if by != WorktreeMatch::Branch {
+        for (path, branch) in worktrees {
+            if path
+                .file_name()
+                .is_some_and(|dir| dir.to_string_lossy() == name)
+            {
+                candidates.push(WorktreeCandidate {
+                    path: path.clone(),
+                    branch: branch.clone(),
+                    matched_by: WorktreeMatch::Handle,
+                });
+            }
+        }
+    }
+
+    if by != WorktreeMatch::Handle {
+        for (path, branch) in worktrees {
+            if branch == name && !candidates.iter().any(|c| c.path == *path) {
+                candidates.push(WorktreeCandidate {
+                    path: path.clone(),
+                    branch: branch.clone(),
+                    matched_by: WorktreeMatch::Branch,
+                });
+            }
         }
     }
 
-    Err(WorktreeNotFound(name.to_string()).into())
+    candidates
 }
 
 /// List all worktrees with their branches
@@ -467,6 +1125,122 @@ pub fn list_worktrees() -> Result<Vec<(PathBuf, String)>> {
     parse_worktree_list_porcelain(&list)
 }
 
+/// Worktrees whose metadata `git worktree prune` would remove, typically
+/// because their directory is gone, with the reason git gives
+pub fn prunable_worktrees() -> Result<Vec<(PathBuf, String)>> {
+    let list = Cmd::new("git")
+        .args(&["worktree", "list", "--porcelain"])
+        .run_and_capture_stdout()
+        .context("Failed to list worktrees")?;
+    Ok(parse_prunable_worktrees(&list))
+}
+
+/// A git operation left unfinished in a worktree, e.g. a rebase stopped on a
+/// conflict
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InProgressOperation {
+    Rebase,
+    /// `git am` applying patches
+    Am,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl InProgressOperation {
+    /// Name of the operation, as in "in the middle of a rebase"
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rebase => "rebase",
+            Self::Am => "git am",
+            Self::Merge => "merge",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+            Self::Bisect => "bisect",
+        }
+    }
+
+    /// Short state shown in `workmux list`
+    pub fn state(&self) -> &'static str {
+        match self {
+            Self::Rebase => "rebasing",
+            Self::Am => "applying patches",
+            Self::Merge => "merging",
+            Self::CherryPick => "cherry-picking",
+            Self::Revert => "reverting",
+            Self::Bisect => "bisecting",
+        }
+    }
+
+    /// The command that abandons the operation
+    pub fn abort_command(&self) -> &'static str {
+        match self {
+            Self::Rebase => "git rebase --abort",
+            Self::Am => "git am --abort",
+            Self::Merge => "git merge --abort",
+            Self::CherryPick => "git cherry-pick --abort",
+            Self::Revert => "git revert --abort",
+            Self::Bisect => "git bisect reset",
+        }
+    }
+}
+
+/// Detect an unfinished rebase, merge, cherry-pick, revert or bisect in a
+/// worktree from the state files git keeps in its git directory.
+pub fn in_progress_operation(worktree_path: &Path) -> Option<InProgressOperation> {
+    let git_dir = worktree_git_dir(worktree_path)?;
+    let rebase_apply = git_dir.join("rebase-apply");
+
+    if git_dir.join("rebase-merge").is_dir() {
+        Some(InProgressOperation::Rebase)
+    } else if rebase_apply.join("applying").exists() {
+        Some(InProgressOperation::Am)
+    } else if rebase_apply.is_dir() {
+        Some(InProgressOperation::Rebase)
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Some(InProgressOperation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(InProgressOperation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(InProgressOperation::Revert)
+    } else if git_dir.join("BISECT_LOG").exists() {
+        Some(InProgressOperation::Bisect)
+    } else {
+        None
+    }
+}
+
+/// The branch a rebase in progress in `git_dir` is rebasing
+fn rebasing_branch(git_dir: &Path) -> Option<String> {
+    ["rebase-merge", "rebase-apply"].iter().find_map(|dir| {
+        let head_name = std::fs::read_to_string(git_dir.join(dir).join("head-name")).ok()?;
+        head_name
+            .trim()
+            .strip_prefix("refs/heads/")
+            .map(str::to_string)
+    })
+}
+
+/// The git directory of a worktree: `.git` itself in the main worktree, or
+/// the directory a linked worktree's `.git` file points to.
+fn worktree_git_dir(worktree_path: &Path) -> Option<PathBuf> {
+    // A core.worktree or GIT_WORK_TREE work tree has no `.git` of its own
+    if let Some(context) = repo_context()
+        && context.work_tree.as_deref() == Some(worktree_path)
+    {
+        return context.git_dir.clone();
+    }
+    let dot_git = worktree_path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let git_dir = content.trim().strip_prefix("gitdir:")?.trim();
+    // A relative path is relative to the worktree; joining keeps absolute ones
+    Some(worktree_path.join(git_dir))
+}
+
 /// Check if the worktree has uncommitted changes
 pub fn has_uncommitted_changes(worktree_path: &Path) -> Result<bool> {
     let output = Cmd::new("git")
@@ -510,23 +1284,82 @@ pub fn has_untracked_files(worktree_path: &Path) -> Result<bool> {
     Ok(false)
 }
 
-/// Check if the worktree has staged changes
-pub fn has_staged_changes(worktree_path: &Path) -> Result<bool> {
-    // Exit code 0 = no changes, 1 = has changes
-    // So we invert the result of run_as_check
-    let no_changes = Cmd::new("git")
+/// Untracked files in the worktree, ignored ones included, relative to its
+/// root. A directory holding nothing tracked is listed once, with a trailing
+/// slash, rather than file by file.
+pub fn list_untracked_paths(worktree_path: &Path) -> Result<Vec<PathBuf>> {
+    let output = Cmd::new("git")
         .workdir(worktree_path)
-        .args(&["diff", "--cached", "--quiet"])
-        .run_as_check()?;
-    Ok(!no_changes)
+        .args(&["ls-files", "--others", "--directory", "-z"])
+        .run()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
+        .map(PathBuf::from)
+        .collect())
 }
 
-/// Check if the worktree has unstaged changes
-pub fn has_unstaged_changes(worktree_path: &Path) -> Result<bool> {
-    // Exit code 0 = no changes, 1 = has changes
-    // So we invert the result of run_as_check
-    let no_changes = Cmd::new("git")
-        .workdir(worktree_path)
+/// Untracked files `git stash --include-untracked` would take, i.e. not
+/// ignored, relative to the root of the worktree `worktree_root`
+pub fn list_untracked_files(worktree_root: &Path) -> Result<Vec<PathBuf>> {
+    Ok(untracked(worktree_root, &[])?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Like [`list_untracked_files`], limited to the directory `dir`
+pub fn list_untracked_files_in(worktree_root: &Path, dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(untracked(worktree_root, &["--", &dir.to_string_lossy()])?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Like [`list_untracked_files`], with a directory holding nothing tracked
+/// listed as itself rather than file by file. Directories come with `true`.
+pub fn list_untracked_entries(worktree_root: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    Ok(untracked(worktree_root, &["--directory"])?
+        .into_iter()
+        .map(|entry| match entry.strip_suffix('/') {
+            Some(dir) => (PathBuf::from(dir), true),
+            None => (PathBuf::from(entry), false),
+        })
+        .collect())
+}
+
+fn untracked(worktree_root: &Path, args: &[&str]) -> Result<Vec<String>> {
+    let output = Cmd::new("git")
+        .workdir(worktree_root)
+        .args(&["ls-files", "--others", "--exclude-standard", "-z"])
+        .args(args)
+        .run()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Check if the worktree has staged changes
+pub fn has_staged_changes(worktree_path: &Path) -> Result<bool> {
+    // Exit code 0 = no changes, 1 = has changes
+    // So we invert the result of run_as_check
+    let no_changes = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--cached", "--quiet"])
+        .run_as_check()?;
+    Ok(!no_changes)
+}
+
+/// Check if the worktree has unstaged changes
+pub fn has_unstaged_changes(worktree_path: &Path) -> Result<bool> {
+    // Exit code 0 = no changes, 1 = has changes
+    // So we invert the result of run_as_check
+    let no_changes = Cmd::new("git")
+        .workdir(worktree_path)
         .args(&["diff", "--quiet"])
         .run_as_check()?;
     Ok(!no_changes)
@@ -534,9 +1367,10 @@ pub fn has_unstaged_changes(worktree_path: &Path) -> Result<bool> {
 
 /// Commit staged changes in a worktree using the user's editor
 pub fn commit_with_editor(worktree_path: &Path) -> Result<()> {
-    let status = Command::new("git")
-        .current_dir(worktree_path)
+    let status = Cmd::new("git")
+        .workdir(worktree_path)
         .arg("commit")
+        .into_command(true)
         .status()
         .context("Failed to run git commit")?;
 
@@ -548,7 +1382,7 @@ pub fn commit_with_editor(worktree_path: &Path) -> Result<()> {
 }
 
 /// Get the base branch for merge checks, preferring remote tracking branch
-pub fn get_merge_base(main_branch: &str) -> Result<String> {
+pub fn get_merge_base(main_branch: &str, remote: &str) -> Result<String> {
     // Try to get the configured upstream tracking branch
     let upstream_arg = format!("{}@{{upstream}}", main_branch);
     if let Ok(upstream) = Cmd::new("git")
@@ -559,8 +1393,8 @@ pub fn get_merge_base(main_branch: &str) -> Result<String> {
         return Ok(upstream);
     }
 
-    // Fallback: check if origin/<main_branch> exists
-    let remote_main = format!("origin/{}", main_branch);
+    // Fallback: check if <remote>/<main_branch> exists
+    let remote_main = format!("{}/{}", remote, main_branch);
     if branch_exists(&remote_main)? {
         Ok(remote_main)
     } else {
@@ -599,11 +1433,11 @@ pub fn get_unmerged_branches(base_branch: &str) -> Result<HashSet<String>> {
 }
 
 /// Fetch from remote with prune to update remote-tracking refs
-pub fn fetch_prune() -> Result<()> {
+pub fn fetch_prune(remote: &str) -> Result<()> {
     Cmd::new("git")
-        .args(&["fetch", "--prune"])
+        .args(&["fetch", "--prune", remote])
         .run()
-        .context("Failed to fetch with prune")?;
+        .with_context(|| format!("Failed to fetch with prune from remote '{}'", remote))?;
     Ok(())
 }
 
@@ -638,6 +1472,32 @@ pub fn merge_in_worktree(worktree_path: &Path, branch_name: &str) -> Result<()>
     Ok(())
 }
 
+/// Merge a branch in a worktree, committing with `message` if a merge
+/// commit is made
+pub fn merge_with_message_in_worktree(
+    worktree_path: &Path,
+    branch_name: &str,
+    message: &str,
+) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["merge", "-m", message, branch_name])
+        .run()
+        .context("Failed to merge")?;
+    Ok(())
+}
+
+/// Fast-forward a worktree's branch to `branch_name`, failing if the two
+/// have diverged
+pub fn merge_ff_only_in_worktree(worktree_path: &Path, branch_name: &str) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["merge", "--ff-only", branch_name])
+        .run()
+        .context("Failed to fast-forward")?;
+    Ok(())
+}
+
 /// Rebase the current branch in a worktree onto a base branch
 pub fn rebase_branch_onto_base(worktree_path: &Path, base_branch: &str) -> Result<()> {
     Cmd::new("git")
@@ -648,6 +1508,61 @@ pub fn rebase_branch_onto_base(worktree_path: &Path, base_branch: &str) -> Resul
     Ok(())
 }
 
+/// Rebase a worktree's branch onto `base`, or merge `base` into it. With
+/// `autostash`, uncommitted changes are stashed first and restored after.
+pub fn update_worktree_from(
+    worktree_path: &Path,
+    base: &str,
+    rebase: bool,
+    autostash: bool,
+) -> Result<()> {
+    let mut args = vec![if rebase { "rebase" } else { "merge" }];
+    if autostash {
+        args.push("--autostash");
+    }
+    if !rebase {
+        args.push("--no-edit");
+    }
+    args.push(base);
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&args)
+        .run()
+        .with_context(|| format!("Failed to update from '{}'", base))?;
+    Ok(())
+}
+
+/// Abort a rebase in progress in a specific worktree
+pub fn abort_rebase_in_worktree(worktree_path: &Path) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["rebase", "--abort"])
+        .run()
+        .context("Failed to abort rebase")?;
+    Ok(())
+}
+
+/// Continue a rebase in a worktree once its conflicts are resolved, keeping
+/// each commit's message
+pub fn continue_rebase_in_worktree(worktree_path: &Path) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["-c", "core.editor=true", "rebase", "--continue"])
+        .run()
+        .context("Failed to continue rebase")?;
+    Ok(())
+}
+
+/// Conclude a merge in a worktree once its conflicts are resolved
+pub fn conclude_merge_in_worktree(worktree_path: &Path) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["commit", "--no-edit"])
+        .run()
+        .context("Failed to commit merge")?;
+    Ok(())
+}
+
 /// Perform a squash merge in a specific worktree (does not commit)
 pub fn merge_squash_in_worktree(worktree_path: &Path, branch_name: &str) -> Result<()> {
     Cmd::new("git")
@@ -658,6 +1573,26 @@ pub fn merge_squash_in_worktree(worktree_path: &Path, branch_name: &str) -> Resu
     Ok(())
 }
 
+/// Commit the staged changes in a worktree with `message`
+pub fn commit_with_message(worktree_path: &Path, message: &str) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["commit", "-m", message])
+        .run()
+        .context("Failed to commit")?;
+    Ok(())
+}
+
+/// Subjects of the commits on `branch` that `base` doesn't have, oldest first
+pub fn get_commit_subjects(base: &str, branch: &str) -> Result<Vec<String>> {
+    let range = format!("{}..{}", base, branch);
+    let output = Cmd::new("git")
+        .args(&["log", "--reverse", "--format=%s", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to read commit log for '{}'", range))?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
 /// Switch to a different branch in a specific worktree
 pub fn switch_branch_in_worktree(worktree_path: &Path, branch_name: &str) -> Result<()> {
     Cmd::new("git")
@@ -681,6 +1616,15 @@ pub fn get_current_branch() -> Result<String> {
         .run_and_capture_stdout()
 }
 
+/// List all local branches, checked out or not, for shell completion
+pub fn list_local_branches() -> Result<Vec<String>> {
+    let output = Cmd::new("git")
+        .args(&["for-each-ref", "--format=%(refname:short)", "refs/heads/"])
+        .run_and_capture_stdout()
+        .context("Failed to list git branches")?;
+    Ok(output.lines().map(String::from).collect())
+}
+
 /// List all checkout-able branches (local and remote) for shell completion.
 /// Excludes branches that are already checked out in existing worktrees.
 pub fn list_checkout_branches() -> Result<Vec<String>> {
@@ -710,6 +1654,19 @@ pub fn list_checkout_branches() -> Result<Vec<String>> {
         .collect())
 }
 
+/// Get the commit a local branch points to
+pub fn get_branch_commit(branch_name: &str) -> Result<String> {
+    Cmd::new("git")
+        .args(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch_name),
+        ])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to resolve branch '{}'", branch_name))
+}
+
 /// Delete a local branch
 pub fn delete_branch(branch_name: &str, force: bool) -> Result<()> {
     // Run from main worktree root to avoid issues when deleting from within a worktree
@@ -731,13 +1688,17 @@ pub fn delete_branch(branch_name: &str, force: bool) -> Result<()> {
 }
 
 /// Stash uncommitted changes, optionally including untracked files or using patch mode.
-pub fn stash_push(message: &str, include_untracked: bool, patch: bool) -> Result<()> {
-    use std::process::Command;
-
+pub fn stash_push(
+    message: &str,
+    include_untracked: bool,
+    patch: bool,
+    exclude: &[PathBuf],
+) -> Result<()> {
     if patch {
         // For --patch mode, we need an interactive terminal
-        let status = Command::new("git")
-            .args(["stash", "push", "-m", message, "--patch"])
+        let status = Cmd::new("git")
+            .args(&["stash", "push", "-m", message, "--patch"])
+            .into_command(true)
             .status()
             .context("Failed to run interactive git stash")?;
 
@@ -752,6 +1713,18 @@ pub fn stash_push(message: &str, include_untracked: bool, patch: bool) -> Result
         if include_untracked {
             cmd = cmd.arg("--include-untracked");
         }
+        // Everything but `exclude`, files or whole directories given relative
+        // to the worktree root
+        let pathspecs: Vec<String> = exclude
+            .iter()
+            .map(|path| format!(":(top,exclude,literal){}", path.display()))
+            .collect();
+        if !pathspecs.is_empty() {
+            cmd = cmd.args(&["--", ":/"]);
+            for pathspec in &pathspecs {
+                cmd = cmd.arg(pathspec);
+            }
+        }
 
         cmd.run().context("Failed to stash changes")?;
     }
@@ -768,6 +1741,25 @@ pub fn stash_pop(worktree_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Apply the latest stash in a specific worktree, keeping it in the stash list.
+pub fn stash_apply(worktree_path: &Path) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["stash", "apply"])
+        .run()
+        .context("Failed to apply stashed changes. Conflicts may have occurred.")?;
+    Ok(())
+}
+
+/// Drop the latest stash
+pub fn stash_drop() -> Result<()> {
+    Cmd::new("git")
+        .args(&["stash", "drop"])
+        .run()
+        .context("Failed to drop stash")?;
+    Ok(())
+}
+
 /// Reset the worktree to HEAD, discarding all local changes.
 pub fn reset_hard(worktree_path: &Path) -> Result<()> {
     Cmd::new("git")
@@ -788,6 +1780,121 @@ pub fn abort_merge_in_worktree(worktree_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Set config values for a single worktree with `git config --worktree`.
+/// Enables `extensions.worktreeConfig` for the repository first, which
+/// `--worktree` requires, moving the settings that only apply to the main
+/// worktree out of the shared config as git asks for.
+pub fn set_worktree_config(
+    worktree_path: &Path,
+    values: &BTreeMap<String, GitConfigValue>,
+) -> Result<()> {
+    let enabled = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["config", "--bool", "extensions.worktreeConfig"])
+        .run_and_capture_stdout()
+        .is_ok_and(|value| value == "true");
+    if !enabled {
+        move_main_worktree_settings(worktree_path)?;
+        Cmd::new("git")
+            .workdir(worktree_path)
+            .args(&["config", "extensions.worktreeConfig", "true"])
+            .run()
+            .context("Failed to enable extensions.worktreeConfig")?;
+    }
+
+    for (key, value) in values {
+        Cmd::new("git")
+            .workdir(worktree_path)
+            .args(&["config", "--worktree", key, &value.to_string()])
+            .run()
+            .with_context(|| format!("Failed to set worktree git config '{}'", key))?;
+    }
+    Ok(())
+}
+
+/// Move `core.bare = true` and `core.worktree` from the repository's shared
+/// config to the main worktree's `config.worktree`. Once worktree config is
+/// enabled, linked worktrees would otherwise read them too, and take
+/// themselves for bare or for the main worktree.
+fn move_main_worktree_settings(worktree_path: &Path) -> Result<()> {
+    let common_dir = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["rev-parse", "--path-format=absolute", "--git-common-dir"])
+        .run_and_capture_stdout()
+        .context("Failed to locate the git directory")?;
+    let shared = format!("{}/config", common_dir);
+    let main = format!("{}/config.worktree", common_dir);
+
+    for (key, as_bool) in [("core.bare", true), ("core.worktree", false)] {
+        let mut args = vec!["config", "--file", &shared];
+        if as_bool {
+            args.push("--bool");
+        }
+        args.extend(["--get", key]);
+        let Ok(value) = Cmd::new("git").args(&args).run_and_capture_stdout() else {
+            continue;
+        };
+        // Every worktree is non-bare unless told otherwise
+        if as_bool && value != "true" {
+            continue;
+        }
+        Cmd::new("git")
+            .args(&["config", "--file", &main, key, &value])
+            .run()
+            .with_context(|| format!("Failed to move '{}' to the main worktree's config", key))?;
+        Cmd::new("git")
+            .args(&["config", "--file", &shared, "--unset", key])
+            .run()
+            .with_context(|| format!("Failed to move '{}' to the main worktree's config", key))?;
+    }
+    Ok(())
+}
+
+/// Add `paths` of a worktree, relative to its root, to the repository's
+/// `info/exclude`, so git leaves them out of its untracked files without
+/// touching `.gitignore`. Paths that are already there are skipped.
+pub fn exclude_paths(worktree_path: &Path, paths: &[&str]) -> Result<()> {
+    let common_dir = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["rev-parse", "--path-format=absolute", "--git-common-dir"])
+        .run_and_capture_stdout()
+        .context("Failed to locate the git directory")?;
+    let file = PathBuf::from(common_dir).join("info").join("exclude");
+    let file_str = file.to_string_lossy();
+    let mut content = match crate::remote::get() {
+        Some(_) => Cmd::new("cat")
+            .arg(&file_str)
+            .run_and_capture_stdout()
+            .unwrap_or_default(),
+        None => std::fs::read_to_string(&file).unwrap_or_default(),
+    };
+
+    // Anchored, and without a trailing slash so links to directories match
+    let missing: Vec<String> = paths
+        .iter()
+        .map(|path| format!("/{}", path.trim_start_matches("./").trim_matches('/')))
+        .filter(|pattern| !content.lines().any(|line| line.trim() == pattern))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for pattern in missing {
+        content.push_str(&pattern);
+        content.push('\n');
+    }
+    if let Some(remote) = crate::remote::get() {
+        return remote.write_file(&file, &content);
+    }
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    }
+    std::fs::write(&file, content).with_context(|| format!("Failed to update '{}'", file.display()))
+}
+
 /// Store the base branch/commit that a branch was created from
 pub fn set_branch_base(branch: &str, base: &str) -> Result<()> {
     Cmd::new("git")
@@ -802,70 +1909,1264 @@ pub fn set_branch_base(branch: &str, base: &str) -> Result<()> {
     Ok(())
 }
 
-/// Retrieve the base branch/commit that a branch was created from
-pub fn get_branch_base(branch: &str) -> Result<String> {
+/// Record the worktree template a branch was created from
+pub fn set_branch_template(branch: &str, template: &str) -> Result<()> {
+    Cmd::new("git")
+        .args(&[
+            "config",
+            "--local",
+            &format!("branch.{}.workmux-template", branch),
+            template,
+        ])
+        .run()
+        .context("Failed to set workmux-template config")?;
+    Ok(())
+}
+
+/// The worktree template a branch was created from, if any
+pub fn get_branch_template(branch: &str) -> Option<String> {
+    Cmd::new("git")
+        .args(&[
+            "config",
+            "--local",
+            &format!("branch.{}.workmux-template", branch),
+        ])
+        .run_and_capture_stdout()
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+/// Set a branch's description, the one `git branch --edit-description` edits
+pub fn set_branch_description(branch: &str, description: &str) -> Result<()> {
+    Cmd::new("git")
+        .args(&[
+            "config",
+            "--local",
+            &format!("branch.{}.description", branch),
+            description,
+        ])
+        .run()
+        .context("Failed to set branch description")?;
+    Ok(())
+}
+
+/// Descriptions of all branches that have one, by branch name
+pub fn branch_descriptions() -> HashMap<String, String> {
+    // -z separates entries with NUL and key from value with a newline, so
+    // multi-line descriptions survive
     let output = Cmd::new("git")
         .args(&[
             "config",
             "--local",
-            &format!("branch.{}.workmux-base", branch),
+            "-z",
+            "--get-regexp",
+            r"^branch\..*\.description$",
+        ])
+        .run();
+    let Ok(output) = output else {
+        return HashMap::new();
+    };
+    parse_branch_descriptions(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The newest commit of a branch
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitSummary {
+    /// Abbreviated hash
+    pub sha: String,
+    pub subject: String,
+    pub author: String,
+    /// Unix timestamp of when it was committed
+    pub timestamp: u64,
+}
+
+/// The newest commit of every local branch, by branch name, read in one go
+pub fn last_commits() -> HashMap<String, CommitSummary> {
+    last_commits_cmd(Cmd::new("git"))
+}
+
+/// [`last_commits`] of the repository whose git directory is `git_dir`
+pub fn last_commits_in(git_dir: &Path) -> HashMap<String, CommitSummary> {
+    last_commits_cmd(Cmd::new("git").workdir(git_dir))
+}
+
+fn last_commits_cmd(git: Cmd<'_>) -> HashMap<String, CommitSummary> {
+    let output = git
+        .args(&[
+            "for-each-ref",
+            "--format=%(refname:lstrip=2)%00%(objectname:short)%00%(authorname)%00%(committerdate:unix)%00%(contents:subject)",
+            "refs/heads/",
+        ])
+        .run();
+    let Ok(output) = output else {
+        return HashMap::new();
+    };
+    parse_last_commits(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Commit `rev` of the repository whose git directory is `git_dir`
+pub fn commit_summary(git_dir: &Path, rev: &str) -> Option<CommitSummary> {
+    let output = Cmd::new("git")
+        .workdir(git_dir)
+        .args(&["log", "-1", "--format=%h%x00%an%x00%ct%x00%s", rev, "--"])
+        .run_and_capture_stdout()
+        .ok()?;
+    parse_commit(&output)
+}
+
+/// What a worktree has checked out
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Head {
+    Branch(String),
+    /// A commit, by its full hash
+    Detached(String),
+}
+
+/// The git directory shared by the worktrees of the repository containing
+/// `path`, and what the worktree has checked out. Read from the files git
+/// keeps them in, without running git, so it's cheap enough to poll.
+pub fn read_head(path: &Path) -> Option<(PathBuf, Head)> {
+    let worktree_path = path.ancestors().find(|dir| dir.join(".git").exists())?;
+    let git_dir = worktree_git_dir(worktree_path)?;
+    let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.clone(),
+    };
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = match head.trim().strip_prefix("ref:") {
+        Some(reference) => Head::Branch(reference.trim().strip_prefix("refs/heads/")?.to_string()),
+        None => match rebasing_branch(&git_dir) {
+            Some(branch) => Head::Branch(branch),
+            None => Head::Detached(head.trim().to_string()),
+        },
+    };
+    Some((
+        std::fs::canonicalize(&common_dir).unwrap_or(common_dir),
+        head,
+    ))
+}
+
+/// Commits on `branch` that `base` lacks, made at or after `since` (a Unix
+/// timestamp), newest first
+pub fn commits_since(base: &str, branch: &str, since: u64) -> Result<Vec<CommitSummary>> {
+    let range = format!("{}..{}", base, branch);
+    let output = Cmd::new("git")
+        .args(&[
+            "log",
+            "--format=%h%x00%an%x00%ct%x00%s",
+            &format!("--since=@{}", since),
+            &range,
         ])
         .run_and_capture_stdout()
-        .context("Failed to get workmux-base config")?;
+        .with_context(|| format!("Failed to read commit log for '{}'", range))?;
+    Ok(output.lines().filter_map(parse_commit).collect())
+}
 
-    if output.is_empty() {
-        return Err(anyhow!("No workmux-base found for branch '{}'", branch));
-    }
+/// Files changed and lines added and removed, as `git diff --shortstat` counts
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiffSize {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
 
-    Ok(output)
+/// Size of a worktree's changes since `from`, uncommitted ones included
+pub fn diff_size(worktree_path: &Path, from: &str) -> Result<DiffSize> {
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--shortstat", from])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to diff against '{}'", from))?;
+    Ok(parse_shortstat(&output))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::parse_owner_from_git_url;
+/// Size of the changes of a worktree's commits since `from`, without
+/// uncommitted ones
+pub fn committed_diff_size(worktree_path: &Path, from: &str) -> Result<DiffSize> {
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--shortstat", from, "HEAD"])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to diff '{}' against HEAD", from))?;
+    Ok(parse_shortstat(&output))
+}
 
-    #[test]
-    fn test_parse_repo_owner_https_github_com() {
-        assert_eq!(
-            parse_owner_from_git_url("https://github.com/owner/repo.git"),
-            Some("owner")
-        );
-    }
+/// A throwaway index of a worktree: HEAD's files, with every other file of
+/// the worktree added as intent-to-add. Diffs taken against the worktree with
+/// it show new files, staged or untracked, alike, while the worktree's own
+/// index is left alone. Deleted when dropped.
+pub struct UntrackedIndex {
+    worktree_path: PathBuf,
+    path: String,
+}
 
-    #[test]
-    fn test_parse_repo_owner_https_github_com_no_git_suffix() {
-        assert_eq!(
-            parse_owner_from_git_url("https://github.com/owner/repo"),
-            Some("owner")
-        );
+impl UntrackedIndex {
+    pub fn new(worktree_path: &Path) -> Result<Self> {
+        let name = format!("workmux-index-{}", std::process::id());
+        let path = Cmd::new("git")
+            .workdir(worktree_path)
+            .args(&["rev-parse", "--path-format=absolute", "--git-path", &name])
+            .run_and_capture_stdout()
+            .context("Failed to locate the git directory")?;
+        let index = Self {
+            worktree_path: worktree_path.to_path_buf(),
+            path,
+        };
+        index
+            .git(&["read-tree", "HEAD"])
+            .run()
+            .context("Failed to read HEAD into a temporary index")?;
+        // Ignored files are left out, as by `git add`
+        index
+            .git(&["add", "--intent-to-add", "--all"])
+            .run()
+            .context("Failed to add untracked files to a temporary index")?;
+        Ok(index)
     }
 
-    #[test]
-    fn test_parse_repo_owner_http_github_com() {
-        assert_eq!(
-            parse_owner_from_git_url("http://github.com/owner/repo.git"),
-            Some("owner")
-        );
+    /// `git` with `args`, run in the worktree with this index
+    pub fn git<'a>(&'a self, args: &[&'a str]) -> Cmd<'a> {
+        Cmd::new("git")
+            .workdir(&self.worktree_path)
+            .env("GIT_INDEX_FILE", &self.path)
+            .args(args)
     }
 
-    #[test]
-    fn test_parse_repo_owner_ssh_github_com() {
-        assert_eq!(
-            parse_owner_from_git_url("git@github.com:owner/repo.git"),
-            Some("owner")
-        );
+    /// The diffstat of the worktree's changes since `from`
+    pub fn diff_stat_since(&self, from: &str) -> Result<String> {
+        let output = self
+            .git(&["diff", "--stat", from])
+            .run()
+            .with_context(|| format!("Failed to diff against '{}'", from))?;
+        // Not trimmed at the start, where the first file name is indented
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
     }
 
-    #[test]
-    fn test_parse_repo_owner_ssh_github_com_no_git_suffix() {
-        assert_eq!(
-            parse_owner_from_git_url("git@github.com:owner/repo"),
-            Some("owner")
-        );
+    /// The files the worktree changed since `from`, sorted
+    pub fn changed_files_since(&self, from: &str) -> Result<Vec<String>> {
+        let output = self
+            .git(&["diff", "--name-only", "--no-renames", "-z", from])
+            .run()
+            .with_context(|| format!("Failed to list changes since '{}'", from))?;
+        let mut files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect();
+        files.sort();
+        Ok(files)
     }
+}
 
-    #[test]
-    fn test_parse_repo_owner_https_github_enterprise() {
+impl Drop for UntrackedIndex {
+    fn drop(&mut self) {
+        match crate::remote::get() {
+            Some(_) => {
+                let _ = Cmd::new("rm").args(&["-f", &self.path]).run();
+            }
+            None => {
+                let _ = std::fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+/// Parse e.g. ` 3 files changed, 10 insertions(+), 2 deletions(-)`, where
+/// parts with a count of zero are left out
+fn parse_shortstat(output: &str) -> DiffSize {
+    let mut size = DiffSize::default();
+    for part in output.split(',') {
+        let mut words = part.split_whitespace();
+        let (Some(Ok(count)), Some(what)) = (words.next().map(str::parse), words.next()) else {
+            continue;
+        };
+        if what.starts_with("file") {
+            size.files = count;
+        } else if what.starts_with("insertion") {
+            size.insertions = count;
+        } else if what.starts_with("deletion") {
+            size.deletions = count;
+        }
+    }
+    size
+}
+
+fn parse_last_commits(output: &str) -> HashMap<String, CommitSummary> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (branch, commit) = line.split_once('\0')?;
+            Some((branch.to_string(), parse_commit(commit)?))
+        })
+        .collect()
+}
+
+/// A commit's hash, author, commit time and subject, separated by NULs
+fn parse_commit(fields: &str) -> Option<CommitSummary> {
+    let mut fields = fields.splitn(4, '\0');
+    Some(CommitSummary {
+        sha: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        timestamp: fields.next()?.parse().ok()?,
+        subject: fields.next()?.to_string(),
+    })
+}
+
+fn parse_branch_descriptions(output: &str) -> HashMap<String, String> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('\n')?;
+            let branch = key.strip_prefix("branch.")?.strip_suffix(".description")?;
+            Some((branch.to_string(), value.trim().to_string()))
+        })
+        .filter(|(_, description)| !description.is_empty())
+        .collect()
+}
+
+/// Diff a worktree (committed and uncommitted changes) against the point
+/// where its branch diverged from `base`
+pub fn get_diff_from_base(worktree_path: &Path, base: &str) -> Result<String> {
+    let merge_base = get_merge_base_in_worktree(worktree_path, base)?;
+
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", &merge_base])
+        .run_and_capture_stdout()
+        .context("Failed to diff worktree")
+}
+
+/// The commit where a worktree's branch diverged from `base`
+pub fn get_merge_base_in_worktree(worktree_path: &Path, base: &str) -> Result<String> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["merge-base", base, "HEAD"])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to find merge base with '{}'", base))
+}
+
+/// Abbreviated hashes and subjects of a worktree's commits since `base`,
+/// oldest first
+pub fn get_commits_from_base(worktree_path: &Path, base: &str) -> Result<Vec<(String, String)>> {
+    let range = format!("{}..HEAD", base);
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["log", "--reverse", "--format=%h %s", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to read commit log for '{}'", range))?;
+    Ok(output
+        .lines()
+        .map(|line| {
+            let (hash, subject) = line.split_once(' ').unwrap_or((line, ""));
+            (hash.to_string(), subject.to_string())
+        })
+        .collect())
+}
+
+/// Files changed in a worktree since `from`, untracked ones included,
+/// relative to its root. Renames are listed as their two paths.
+pub fn get_changed_files(worktree_path: &Path, from: &str) -> Result<Vec<String>> {
+    let changed = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--name-only", "--no-renames", "-z", from])
+        .run()
+        .with_context(|| format!("Failed to list changes since '{}'", from))?;
+    let untracked = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["ls-files", "--others", "--exclude-standard", "-z"])
+        .run()
+        .context("Failed to list untracked files")?;
+
+    let mut files: Vec<String> = [changed.stdout, untracked.stdout]
+        .iter()
+        .flat_map(|output| {
+            String::from_utf8_lossy(output)
+                .split('\0')
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Diff of a worktree's tracked files against `from`
+pub fn get_diff_since(worktree_path: &Path, from: &str) -> Result<String> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", from])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to diff against '{}'", from))
+}
+
+/// Number of parents of `commit`
+pub fn get_parent_count(worktree_path: &Path, commit: &str) -> Result<usize> {
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["rev-list", "--parents", "-n", "1", commit])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to read parents of '{}'", commit))?;
+    Ok(output.split_whitespace().count().saturating_sub(1))
+}
+
+/// Move a worktree's branch and index to `commit`, keeping its files as they are
+pub fn reset_mixed(worktree_path: &Path, commit: &str) -> Result<()> {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["reset", "-q", commit])
+        .run()
+        .with_context(|| format!("Failed to reset to '{}'", commit))?;
+    Ok(())
+}
+
+/// Stage the current content of `paths`, deletions included
+pub fn stage_paths(worktree_path: &Path, paths: &[String]) -> Result<()> {
+    let mut args = vec!["--literal-pathspecs", "add", "-A", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&args)
+        .run()
+        .context("Failed to stage files")?;
+    Ok(())
+}
+
+/// Whether git tracks `file` in the worktree
+pub fn is_tracked(worktree_path: &Path, file: &str) -> bool {
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["ls-files", "--error-unmatch", "--", file])
+        .run_as_check()
+        .unwrap_or(false)
+}
+
+/// Like [`get_log_from_base`], with the files each commit changes
+pub fn get_log_stat_from_base(worktree_path: &Path, base: &str) -> Result<String> {
+    let range = format!("{}..HEAD", base);
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["log", "--reverse", "--stat", "--format=%h %s%n%b", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to read commit log for '{}'", range))
+}
+
+/// Subjects and bodies of a worktree's commits since it diverged from
+/// `base`, oldest first
+pub fn get_log_from_base(worktree_path: &Path, base: &str) -> Result<String> {
+    let range = format!("{}..HEAD", base);
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["log", "--reverse", "--format=%h %s%n%b", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to read commit log for '{}'", range))
+}
+
+/// Check whether a patch applies cleanly to a worktree
+pub fn patch_applies(worktree_path: &Path, patch_path: &Path) -> Result<bool> {
+    let patch = patch_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Patch path contains non-UTF8 characters"))?;
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["apply", "--check", patch])
+        .run_as_check()
+}
+
+/// List the files a patch touches, without applying it
+pub fn get_patch_files(worktree_path: &Path, patch_path: &Path) -> Result<Vec<String>> {
+    let patch = patch_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Patch path contains non-UTF8 characters"))?;
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["apply", "--numstat", patch])
+        .run_and_capture_stdout()
+        .context("Failed to read patch")?;
+
+    // Each line is "<added>\t<deleted>\t<path>"
+    Ok(output
+        .lines()
+        .filter_map(|line| line.splitn(3, '\t').nth(2))
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// Apply a patch to a worktree. With `three_way`, falls back to a 3-way merge
+/// that stages the result and leaves conflict markers where hunks don't fit.
+pub fn apply_patch(worktree_path: &Path, patch_path: &Path, three_way: bool) -> Result<()> {
+    let patch = patch_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Patch path contains non-UTF8 characters"))?;
+    let mut cmd = Cmd::new("git").workdir(worktree_path).arg("apply");
+    if three_way {
+        cmd = cmd.arg("--3way");
+    }
+    cmd.arg(patch).run().context("Failed to apply patch")?;
+    Ok(())
+}
+
+/// List files with unresolved merge conflicts in a worktree
+pub fn get_conflicted_files(worktree_path: &Path) -> Result<Vec<String>> {
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--name-only", "--diff-filter=U"])
+        .run_and_capture_stdout()
+        .context("Failed to list conflicted files")?;
+
+    Ok(output.lines().map(|line| line.to_string()).collect())
+}
+
+/// Files that merging `branch` into `base` would leave conflicted, found with
+/// `git merge-tree` without touching any worktree. None if git can't tell
+/// (it needs git 2.38 or later).
+pub fn predict_conflicts(base: &str, branch: &str) -> Option<Vec<String>> {
+    let output = Cmd::new("git")
+        .args(&[
+            "merge-tree",
+            "--write-tree",
+            "--name-only",
+            "--no-messages",
+            base,
+            branch,
+        ])
+        .into_command(false)
+        .output()
+        .ok()?;
+    match output.status.code() {
+        // Clean: just the tree
+        Some(0) => Some(Vec::new()),
+        // Conflicts: the tree, then one conflicted file per line
+        Some(1) => Some(parse_conflicted_names(&String::from_utf8_lossy(
+            &output.stdout,
+        ))),
+        _ => None,
+    }
+}
+
+fn parse_conflicted_names(output: &str) -> Vec<String> {
+    let mut files: Vec<String> = output
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    files.dedup();
+    files
+}
+
+/// `git diff --stat` of what `branch` changed since it left `base`
+pub fn diff_stat(base: &str, branch: &str) -> Result<String> {
+    let range = format!("{}...{}", base, branch);
+    Cmd::new("git")
+        .args(&["diff", "--stat", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to diff '{}'", range))
+}
+
+/// Count the commits on `branch` that aren't reachable from `base`
+pub fn count_commits_since(base: &str, branch: &str) -> Result<usize> {
+    let range = format!("{}..{}", base, branch);
+    let output = Cmd::new("git")
+        .args(&["rev-list", "--count", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to count commits in '{}'", range))?;
+    output
+        .parse()
+        .with_context(|| format!("Unexpected rev-list output: '{}'", output))
+}
+
+/// Count the commits on `branch` that `base` lacks (ahead) and the commits on
+/// `base` that `branch` lacks (behind)
+pub fn ahead_behind(base: &str, branch: &str) -> Result<(usize, usize)> {
+    let range = format!("{}...{}", base, branch);
+    let output = Cmd::new("git")
+        .args(&["rev-list", "--left-right", "--count", &range])
+        .run_and_capture_stdout()
+        .with_context(|| format!("Failed to count commits in '{}'", range))?;
+    let counts: Vec<usize> = output
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Unexpected rev-list output: '{}'", output))?;
+    match counts[..] {
+        [behind, ahead] => Ok((ahead, behind)),
+        _ => Err(anyhow!("Unexpected rev-list output: '{}'", output)),
+    }
+}
+
+/// Write the commits on `branch` since `base` in the repository at `repo` as
+/// a `git format-patch` series. Returns the paths of the patch files.
+pub fn format_patch(repo: &Path, base: &str, branch: &str, out_dir: &Path) -> Result<Vec<PathBuf>> {
+    let out = out_dir
+        .to_str()
+        .ok_or_else(|| anyhow!("Output path contains non-UTF8 characters"))?;
+    let range = format!("{}..{}", base, branch);
+    let output = Cmd::new("git")
+        .workdir(repo)
+        .args(&["format-patch", "-o", out, &range])
+        .run_and_capture_stdout()
+        .context("Failed to create patch series")?;
+
+    // format-patch prints the path of each file it writes
+    Ok(output.lines().map(PathBuf::from).collect())
+}
+
+/// Create a bundle with the commits on `branch` since `base`, in the
+/// repository at `repo`
+pub fn create_bundle(repo: &Path, base: &str, branch: &str, bundle_path: &Path) -> Result<()> {
+    let path = bundle_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Output path contains non-UTF8 characters"))?;
+    let exclude_base = format!("^{}", base);
+    Cmd::new("git")
+        .workdir(repo)
+        .args(&["bundle", "create", "--quiet", path, branch, &exclude_base])
+        .run()
+        .context("Failed to create bundle")?;
+    Ok(())
+}
+
+/// Everything uncommitted in a worktree as a patch against HEAD, untracked
+/// files included (marked with `git add -N` to be), with binary changes in a
+/// form `git apply` can recreate
+pub fn uncommitted_patch(worktree_path: &Path) -> Result<Vec<u8>> {
+    if !list_untracked_files(worktree_path)?.is_empty() {
+        Cmd::new("git")
+            .workdir(worktree_path)
+            .args(&["add", "--intent-to-add", "--all"])
+            .run()
+            .context("Failed to mark untracked files")?;
+    }
+    let output = Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["diff", "--binary", "HEAD"])
+        .run()
+        .context("Failed to diff uncommitted changes")?;
+    Ok(output.stdout)
+}
+
+/// Check whether a file is a git bundle, based on its header
+pub fn is_bundle(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && (header.starts_with(b"# v2 git bundle") || header.starts_with(b"# v3 git bundle"))
+}
+
+/// List the branches contained in a bundle
+pub fn get_bundle_branches(bundle_path: &Path) -> Result<Vec<String>> {
+    let path = bundle_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Bundle path contains non-UTF8 characters"))?;
+    let output = Cmd::new("git")
+        .args(&["bundle", "list-heads", path])
+        .run_and_capture_stdout()
+        .context("Failed to read bundle")?;
+
+    // Each line is "<sha> <ref>"
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(_, name)| name.strip_prefix("refs/heads/"))
+        .map(|branch| branch.to_string())
+        .collect())
+}
+
+/// Fetch a branch from a bundle into a new local branch
+pub fn fetch_bundle_branch(bundle_path: &Path, bundle_branch: &str, branch: &str) -> Result<()> {
+    let path = bundle_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Bundle path contains non-UTF8 characters"))?;
+    let refspec = format!("refs/heads/{}:refs/heads/{}", bundle_branch, branch);
+    Cmd::new("git")
+        .args(&["fetch", "--quiet", path, &refspec])
+        .run()
+        .with_context(|| format!("Failed to fetch '{}' from bundle", bundle_branch))?;
+    Ok(())
+}
+
+/// Apply a `git format-patch` series as commits in a worktree, using a 3-way
+/// merge for hunks that don't apply cleanly
+pub fn am_patches(worktree_path: &Path, patches: &[PathBuf]) -> Result<()> {
+    let patch_args = patches
+        .iter()
+        .map(|patch| {
+            patch
+                .to_str()
+                .ok_or_else(|| anyhow!("Patch path contains non-UTF8 characters"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Cmd::new("git")
+        .workdir(worktree_path)
+        .args(&["am", "--3way"])
+        .args(&patch_args)
+        .run()
+        .context("Failed to apply patches")?;
+    Ok(())
+}
+
+/// Get the remote a branch tracks (`branch.<name>.remote`), if any
+pub fn get_branch_remote(branch: &str) -> Option<String> {
+    Cmd::new("git")
+        .args(&["config", "--get", &format!("branch.{}.remote", branch)])
+        .run_and_capture_stdout()
+        .ok()
+        .filter(|remote| !remote.is_empty() && remote != ".")
+}
+
+/// Retrieve the base branch/commit that a branch was created from
+pub fn get_branch_base(branch: &str) -> Result<String> {
+    let output = Cmd::new("git")
+        .args(&[
+            "config",
+            "--local",
+            &format!("branch.{}.workmux-base", branch),
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to get workmux-base config")?;
+
+    if output.is_empty() {
+        return Err(anyhow!("No workmux-base found for branch '{}'", branch));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CommitSummary, DiffSize, Head, InProgressOperation, RepoContext, WorktreeMatch,
+        create_bundle, exclude_paths, format_patch, in_progress_operation, match_worktree,
+        parse_branch_descriptions, parse_conflicted_names, parse_last_commits,
+        parse_locked_worktrees, parse_owner_from_git_url, parse_prunable_worktrees,
+        parse_shortstat, parse_worktree_list_porcelain, pin_worktree, read_head,
+        refuse_detached_commits, resolve_worktree, set_worktree_config, unreferenced_commits,
+    };
+    use crate::config::GitConfigValue;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_in_progress_operation_reads_git_dir_state() {
+        let main = tempfile::tempdir().unwrap();
+        let git_dir = main.path().join(".git");
+        fs::create_dir_all(git_dir.join("worktrees/feature")).unwrap();
+        assert_eq!(in_progress_operation(main.path()), None);
+
+        fs::write(git_dir.join("MERGE_HEAD"), "abc\n").unwrap();
+        assert_eq!(
+            in_progress_operation(main.path()),
+            Some(InProgressOperation::Merge)
+        );
+
+        // A linked worktree's .git file points at its own git directory
+        let linked = tempfile::tempdir().unwrap();
+        let linked_git_dir = git_dir.join("worktrees/feature");
+        fs::write(
+            linked.path().join(".git"),
+            format!("gitdir: {}\n", linked_git_dir.display()),
+        )
+        .unwrap();
+        assert_eq!(in_progress_operation(linked.path()), None);
+
+        fs::create_dir(linked_git_dir.join("rebase-merge")).unwrap();
+        fs::write(linked_git_dir.join("MERGE_HEAD"), "abc\n").unwrap();
+        assert_eq!(
+            in_progress_operation(linked.path()),
+            Some(InProgressOperation::Rebase)
+        );
+    }
+
+    #[test]
+    fn test_set_worktree_config_keeps_core_bare_to_the_main_worktree() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo.git");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        fs::create_dir(&repo).unwrap();
+        git(&["init", "--bare", "--quiet"]);
+
+        let values = BTreeMap::from([(
+            "user.name".to_string(),
+            GitConfigValue::String("Agent".to_string()),
+        )]);
+        set_worktree_config(&repo, &values).unwrap();
+
+        let shared = fs::read_to_string(repo.join("config")).unwrap();
+        assert!(!shared.contains("bare"), "{}", shared);
+        let main = fs::read_to_string(repo.join("config.worktree")).unwrap();
+        assert!(main.contains("bare = true"), "{}", main);
+        assert!(main.contains("name = Agent"), "{}", main);
+        assert_eq!(git(&["rev-parse", "--is-bare-repository"]), "true");
+    }
+
+    #[test]
+    fn test_detached_commits_are_refused_and_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let review = dir.path().join("review");
+        let git = |cwd: &std::path::Path, args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(cwd)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success()
+        };
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "--quiet", "-b", "main"]);
+        git(
+            &repo,
+            &["commit", "--quiet", "--allow-empty", "-m", "first"],
+        );
+        git(
+            &repo,
+            &["worktree", "add", "--quiet", "--detach", "../review"],
+        );
+
+        refuse_detached_commits(&review).unwrap();
+        assert!(!git(
+            &review,
+            &["commit", "--quiet", "--allow-empty", "-m", "lost"]
+        ));
+        assert_eq!(unreferenced_commits(&review).unwrap(), 0);
+
+        // Only the review worktree's commits are checked
+        assert!(git(
+            &repo,
+            &["commit", "--quiet", "--allow-empty", "-m", "second"]
+        ));
+        // --no-verify gets past the hook, leaving a commit on no branch
+        assert!(git(
+            &review,
+            &[
+                "commit",
+                "--quiet",
+                "--allow-empty",
+                "--no-verify",
+                "-m",
+                "stray"
+            ]
+        ));
+        assert_eq!(unreferenced_commits(&review).unwrap(), 1);
+
+        assert!(git(&review, &["switch", "--quiet", "-c", "kept"]));
+        assert!(git(
+            &review,
+            &["commit", "--quiet", "--allow-empty", "-m", "kept"]
+        ));
+        assert_eq!(unreferenced_commits(&review).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_head_of_main_and_linked_worktrees() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&status.stdout).trim().to_string()
+        };
+        fs::create_dir(&repo).unwrap();
+        git(&["init", "--quiet", "-b", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "-m", "first"]);
+        git(&["worktree", "add", "--quiet", "-b", "feature", "../feature"]);
+        git(&["worktree", "add", "--quiet", "--detach", "../review"]);
+        fs::create_dir(dir.path().join("feature/src")).unwrap();
+
+        let common_dir = fs::canonicalize(repo.join(".git")).unwrap();
+        assert_eq!(
+            read_head(&repo),
+            Some((common_dir.clone(), Head::Branch("main".to_string())))
+        );
+        assert_eq!(
+            read_head(&dir.path().join("feature/src")),
+            Some((common_dir.clone(), Head::Branch("feature".to_string())))
+        );
+        assert_eq!(
+            read_head(&dir.path().join("review")),
+            Some((common_dir, Head::Detached(git(&["rev-parse", "HEAD"]))))
+        );
+        assert_eq!(read_head(dir.path()), None);
+    }
+
+    #[test]
+    fn test_rebasing_worktrees_are_listed_on_their_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let git = |cwd: &std::path::Path, args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(cwd)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "--quiet", "-b", "main"]);
+        git(&repo, &["commit", "--quiet", "--allow-empty", "-m", "base"]);
+        git(
+            &repo,
+            &["worktree", "add", "--quiet", "-b", "feature", "../feature"],
+        );
+        git(
+            &repo,
+            &["worktree", "add", "--quiet", "--detach", "../review"],
+        );
+        let feature = dir.path().join("feature");
+        fs::write(feature.join("file.txt"), "feature\n").unwrap();
+        git(&feature, &["add", "file.txt"]);
+        git(&feature, &["commit", "--quiet", "-m", "feature"]);
+        fs::write(repo.join("file.txt"), "main\n").unwrap();
+        git(&repo, &["add", "file.txt"]);
+        git(&repo, &["commit", "--quiet", "-m", "main"]);
+        // Stops on the conflict with HEAD detached
+        git(&feature, &["rebase", "main"]);
+        assert_eq!(
+            in_progress_operation(&feature),
+            Some(InProgressOperation::Rebase)
+        );
+
+        let listed =
+            parse_worktree_list_porcelain(&git(&repo, &["worktree", "list", "--porcelain"]))
+                .unwrap();
+        let branches: Vec<&str> = listed.iter().map(|(_, branch)| branch.as_str()).collect();
+        assert_eq!(branches, ["main", "feature", "(detached)"]);
+        assert!(matches!(read_head(&feature), Some((_, Head::Branch(b))) if b == "feature"));
+    }
+
+    #[test]
+    fn test_export_branch_commits_as_patches_and_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let out = dir.path().join("out");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        fs::create_dir_all(&out).unwrap();
+        fs::create_dir(&repo).unwrap();
+        git(&["init", "--quiet", "-b", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "-m", "base"]);
+        git(&["switch", "--quiet", "-c", "feature"]);
+        for (file, message) in [("a.txt", "Add a"), ("b.txt", "Add b")] {
+            fs::write(repo.join(file), "content\n").unwrap();
+            git(&["add", file]);
+            git(&["commit", "--quiet", "-m", message]);
+        }
+        git(&["switch", "--quiet", "main"]);
+
+        let patches = format_patch(&repo, "main", "feature", &out).unwrap();
+        let names: Vec<_> = patches
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["0001-Add-a.patch", "0002-Add-b.patch"]);
+        assert!(patches.iter().all(|path| path.starts_with(&out)));
+
+        // The bundle holds only the branch's commits, so it needs the base
+        let bundle = out.join("feature.bundle");
+        create_bundle(&repo, "main", "feature", &bundle).unwrap();
+        let bundle_arg = bundle.to_str().unwrap();
+        let heads = git(&["bundle", "list-heads", bundle_arg]);
+        assert_eq!(
+            heads,
+            format!("{} refs/heads/feature", git(&["rev-parse", "feature"]))
+        );
+        git(&["bundle", "verify", "--quiet", bundle_arg]);
+    }
+
+    #[test]
+    fn test_exclude_paths_adds_each_path_once() {
+        let repo = tempfile::tempdir().unwrap();
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["init", "--quiet"])
+            .status()
+            .unwrap();
+        let exclude = repo.path().join(".git/info/exclude");
+        fs::write(
+            &exclude,
+            "# git ls-files --others --exclude-from=.git/info/exclude\n/target",
+        )
+        .unwrap();
+
+        exclude_paths(repo.path(), &["target/", "node_modules", "web/.cache"]).unwrap();
+        exclude_paths(repo.path(), &["./node_modules"]).unwrap();
+        let content = fs::read_to_string(&exclude).unwrap();
+        assert!(
+            content.ends_with("\n/target\n/node_modules\n/web/.cache\n"),
+            "{}",
+            content
+        );
+
+        // A link in place of a directory is excluded too
+        std::os::unix::fs::symlink("/tmp", repo.path().join("node_modules")).unwrap();
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["status", "--porcelain"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&status.stdout), "");
+    }
+
+    #[test]
+    fn test_repo_context_applies_outside_linked_worktrees() {
+        let root = tempfile::tempdir().unwrap();
+        let work_tree = root.path().join("src");
+        let linked = work_tree.join(".worktrees/feature");
+        fs::create_dir_all(linked.join("nested")).unwrap();
+        fs::write(linked.join(".git"), "gitdir: /elsewhere\n").unwrap();
+        let context = RepoContext {
+            git_dir: Some(root.path().join("repo.git")),
+            work_tree: Some(work_tree.clone()),
+        };
+
+        assert!(context.applies_to(None));
+        assert!(context.applies_to(Some(&work_tree)));
+        assert!(context.applies_to(Some(&work_tree.join(".worktrees"))));
+        assert!(!context.applies_to(Some(&linked)));
+        assert!(!context.applies_to(Some(&linked.join("nested"))));
+        assert!(!context.applies_to(Some(&root.path().join("src__worktrees/feature"))));
+
+        // Commands in a bare repository's worktrees find it from their `.git` file
+        let bare = RepoContext {
+            git_dir: Some(root.path().join("repo.git")),
+            work_tree: None,
+        };
+        assert!(bare.applies_to(None));
+        assert!(!bare.applies_to(Some(&work_tree)));
+    }
+
+    #[test]
+    fn test_parse_prunable_worktrees() {
+        let output = "worktree /repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                      worktree /repo__worktrees/gone\nHEAD def\nbranch refs/heads/gone\n\
+                      prunable gitdir file points to non-existent location\n\n\
+                      worktree /repo__worktrees/live\nHEAD 123\ndetached\n";
+        assert_eq!(
+            parse_prunable_worktrees(output),
+            vec![(
+                PathBuf::from("/repo__worktrees/gone"),
+                "gitdir file points to non-existent location".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_locked_worktrees() {
+        let output = "worktree /repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                      worktree /repo__worktrees/docs\nHEAD def\nbranch refs/heads/docs\n\
+                      locked pinned with workmux\n\n\
+                      worktree /repo__worktrees/usb\nHEAD 456\ndetached\nlocked\n\n\
+                      worktree /repo__worktrees/live\nHEAD 123\nbranch refs/heads/live\n";
+        assert_eq!(
+            parse_locked_worktrees(output),
+            vec![
+                PathBuf::from("/repo__worktrees/docs"),
+                PathBuf::from("/repo__worktrees/usb")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_last_commits() {
+        let output = "main\x00a1b2c3d\x00Ada\x001700000000\x00Initial commit\n\
+                      feat/login\x00e4f5a6b\x00Agent Smith\x001700003600\x00Add login form\n\
+                      broken\x00123\n";
+        let commits = parse_last_commits(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(
+            commits["feat/login"],
+            CommitSummary {
+                sha: "e4f5a6b".to_string(),
+                subject: "Add login form".to_string(),
+                author: "Agent Smith".to_string(),
+                timestamp: 1_700_003_600,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_shortstat() {
+        assert_eq!(
+            parse_shortstat(" 3 files changed, 10 insertions(+), 2 deletions(-)"),
+            DiffSize {
+                files: 3,
+                insertions: 10,
+                deletions: 2,
+            }
+        );
+        assert_eq!(
+            parse_shortstat(" 1 file changed, 1 deletion(-)"),
+            DiffSize {
+                files: 1,
+                insertions: 0,
+                deletions: 1,
+            }
+        );
+        assert_eq!(parse_shortstat(""), DiffSize::default());
+    }
+
+    #[test]
+    fn test_parse_branch_descriptions() {
+        let output = "branch.feat/login.description\nAdd login form\n\0\
+                      branch.v1.2.description\nFirst line\nSecond line\n\0\
+                      branch.empty.description\n\0";
+        let descriptions = parse_branch_descriptions(output);
+        assert_eq!(descriptions.len(), 2);
+        assert_eq!(descriptions["feat/login"], "Add login form");
+        assert_eq!(descriptions["v1.2"], "First line\nSecond line");
+    }
+
+    #[test]
+    fn test_pinned_handle_resolves_past_a_branch_of_the_same_name() {
+        // `workmux rm --all` removes the worktree 'collide' on branch
+        // 'collide-fix' next to another worktree whose branch is 'collide'
+        let worktrees = vec![
+            (
+                PathBuf::from("/p__worktrees/collide"),
+                "collide-fix".to_string(),
+            ),
+            (PathBuf::from("/p__worktrees/other"), "collide".to_string()),
+        ];
+        assert_eq!(
+            match_worktree(&worktrees, "collide", WorktreeMatch::Any).len(),
+            2
+        );
+
+        // Callers that already know the path pin the handle to it
+        pin_worktree("collide", &worktrees[0].0);
+        assert_eq!(
+            resolve_worktree(&worktrees, "collide", WorktreeMatch::Any).unwrap(),
+            worktrees[0]
+        );
+        assert_eq!(
+            resolve_worktree(&worktrees, "collide", WorktreeMatch::Handle).unwrap(),
+            worktrees[0]
+        );
+    }
+
+    #[test]
+    fn test_match_worktree_respects_match_mode() {
+        let worktrees = vec![
+            (PathBuf::from("/p__worktrees/auth"), "fix/login".to_string()),
+            (PathBuf::from("/p__worktrees/web-auth"), "auth".to_string()),
+            (PathBuf::from("/p__worktrees/login"), "login".to_string()),
+        ];
+        let found = |name, by| -> Vec<(PathBuf, WorktreeMatch)> {
+            match_worktree(&worktrees, name, by)
+                .into_iter()
+                .map(|c| (c.path, c.matched_by))
+                .collect()
+        };
+
+        // A handle of one worktree and the branch of another is ambiguous
+        assert_eq!(
+            found("auth", WorktreeMatch::Any),
+            vec![
+                (PathBuf::from("/p__worktrees/auth"), WorktreeMatch::Handle),
+                (
+                    PathBuf::from("/p__worktrees/web-auth"),
+                    WorktreeMatch::Branch
+                ),
+            ]
+        );
+        assert_eq!(
+            found("auth", WorktreeMatch::Handle),
+            vec![(PathBuf::from("/p__worktrees/auth"), WorktreeMatch::Handle)]
+        );
+        assert_eq!(
+            found("auth", WorktreeMatch::Branch),
+            vec![(
+                PathBuf::from("/p__worktrees/web-auth"),
+                WorktreeMatch::Branch
+            )]
+        );
+        // Matching the same worktree both ways is not ambiguous
+        assert_eq!(
+            found("login", WorktreeMatch::Any),
+            vec![(PathBuf::from("/p__worktrees/login"), WorktreeMatch::Handle)]
+        );
+        assert!(found("fix/login", WorktreeMatch::Handle).is_empty());
+    }
+
+    #[test]
+    fn test_parse_repo_owner_https_github_com() {
+        assert_eq!(
+            parse_owner_from_git_url("https://github.com/owner/repo.git"),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_owner_https_github_com_no_git_suffix() {
+        assert_eq!(
+            parse_owner_from_git_url("https://github.com/owner/repo"),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_owner_http_github_com() {
+        assert_eq!(
+            parse_owner_from_git_url("http://github.com/owner/repo.git"),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_owner_ssh_github_com() {
+        assert_eq!(
+            parse_owner_from_git_url("git@github.com:owner/repo.git"),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_owner_ssh_github_com_no_git_suffix() {
+        assert_eq!(
+            parse_owner_from_git_url("git@github.com:owner/repo"),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_owner_https_github_enterprise() {
         assert_eq!(
             parse_owner_from_git_url("https://github.enterprise.com/owner/repo.git"),
             Some("owner")
@@ -919,7 +3220,17 @@ mod tests {
         assert_eq!(parse_owner_from_git_url("file:///local/path/to/repo"), None);
     }
 
-    use super::parse_fork_branch_spec;
+    #[test]
+    fn test_merge_request_head() {
+        assert_eq!(
+            merge_request_head("merge-requests/42").as_deref(),
+            Some("refs/merge-requests/42/head")
+        );
+        assert_eq!(merge_request_head("merge-requests/fix"), None);
+        assert_eq!(merge_request_head("feature/merge-requests/42"), None);
+    }
+
+    use super::{merge_request_head, parse_fork_branch_spec};
 
     #[test]
     fn test_parse_fork_branch_spec_valid() {
@@ -966,4 +3277,11 @@ mod tests {
         // origin/feature should NOT match (no colon)
         assert!(parse_fork_branch_spec("origin/feature").is_none());
     }
+
+    #[test]
+    fn conflicted_names_follow_the_tree_once_each() {
+        let output = "4b825dc642cb6eb9a060e54bf8d69288fbee4904\nsrc/a.rs\nsrc/a.rs\nREADME.md\n";
+        assert_eq!(parse_conflicted_names(output), ["src/a.rs", "README.md"]);
+        assert!(parse_conflicted_names("4b825dc642cb6eb9a060e54bf8d69288fbee4904\n").is_empty());
+    }
 }
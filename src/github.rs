@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::process::Command;
 use tracing::debug;
 
+use crate::offline;
+
 #[derive(Debug, Deserialize)]
 pub struct PrDetails {
     #[serde(rename = "headRefName")]
@@ -15,6 +17,9 @@ pub struct PrDetails {
     pub is_draft: bool,
     pub title: String,
     pub author: Author,
+    /// Branch the PR merges into
+    #[serde(rename = "baseRefName", default)]
+    pub base_ref_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +63,11 @@ struct PrListResult {
 /// Find a PR by its head ref (e.g., "owner:branch" format).
 /// Returns None if no PR is found, or the first matching PR if found.
 pub fn find_pr_by_head_ref(owner: &str, branch: &str) -> Result<Option<PrSummary>> {
+    if offline::is_enabled() {
+        debug!("github:offline, skipping PR lookup by head ref");
+        return Ok(None);
+    }
+
     // gh pr list --head only matches branch name, not owner:branch format
     // So we query by branch and filter by owner in the results
     let output = Command::new("gh")
@@ -116,6 +126,13 @@ pub fn find_pr_by_head_ref(owner: &str, branch: &str) -> Result<Option<PrSummary
 
 /// Fetches pull request details using the GitHub CLI
 pub fn get_pr_details(pr_number: u32) -> Result<PrDetails> {
+    if offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot look up PR #{} in offline mode. Check out the branch by name instead.",
+            pr_number
+        ));
+    }
+
     // Fetch PR details using gh CLI
     // Note: We don't pre-check with 'which' because it doesn't respect test PATH modifications
     let output = Command::new("gh")
@@ -124,7 +141,7 @@ pub fn get_pr_details(pr_number: u32) -> Result<PrDetails> {
             "view",
             &pr_number.to_string(),
             "--json",
-            "headRefName,headRepositoryOwner,state,isDraft,title,author",
+            "headRefName,headRepositoryOwner,state,isDraft,title,author,baseRefName",
         ])
         .output();
 
@@ -159,6 +176,140 @@ pub fn get_pr_details(pr_number: u32) -> Result<PrDetails> {
     Ok(pr_details)
 }
 
+/// Description and review threads of a pull request
+#[derive(Debug, Deserialize)]
+pub struct PrReviews {
+    pub title: String,
+    pub body: String,
+    #[serde(rename = "reviewThreads")]
+    pub review_threads: Nodes<ReviewThread>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Nodes<T> {
+    pub nodes: Vec<T>,
+    /// How many there are, of which `nodes` may hold only the first
+    #[serde(rename = "totalCount", default)]
+    pub total_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewThread {
+    #[serde(rename = "isResolved")]
+    pub is_resolved: bool,
+    #[serde(rename = "isOutdated")]
+    pub is_outdated: bool,
+    pub path: String,
+    /// None once the commented lines are gone from the diff
+    pub line: Option<u32>,
+    #[serde(rename = "originalLine")]
+    pub original_line: Option<u32>,
+    pub comments: Nodes<ReviewComment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewComment {
+    /// None for deleted accounts
+    pub author: Option<Author>,
+    pub body: String,
+}
+
+/// Fetched a page of threads at a time with `gh api --paginate`, which fills
+/// in `$endCursor`
+const PR_REVIEWS_QUERY: &str =
+    "query($owner: String!, $name: String!, $number: Int!, $endCursor: String) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      title
+      body
+      reviewThreads(first: 100, after: $endCursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          isResolved
+          isOutdated
+          path
+          line
+          originalLine
+          comments(first: 100) { totalCount nodes { author { login } body } }
+        }
+      }
+    }
+  }
+}";
+
+/// Fetches a pull request's description and review threads using the GitHub CLI
+pub fn get_pr_reviews(pr_number: u32) -> Result<PrReviews> {
+    if offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot fetch PR #{} reviews in offline mode",
+            pr_number
+        ));
+    }
+
+    // gh fills in {owner} and {repo} from the current repository
+    let output = Command::new("gh")
+        .args([
+            "api",
+            "graphql",
+            "--paginate",
+            "-F",
+            "owner={owner}",
+            "-F",
+            "name={repo}",
+            "-F",
+            &format!("number={}", pr_number),
+            "-f",
+            &format!("query={}", PR_REVIEWS_QUERY),
+            "--jq",
+            ".data.repository.pullRequest",
+        ])
+        .output();
+
+    let output = match output {
+        Ok(out) => out,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("github:gh CLI not found");
+            return Err(anyhow!(
+                "GitHub CLI (gh) is required for --pr. Install from https://cli.github.com"
+            ));
+        }
+        Err(e) => {
+            return Err(e).context("Failed to execute gh command");
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!(pr = pr_number, stderr = %stderr, "github:pr reviews query failed");
+        return Err(anyhow!(
+            "Failed to fetch reviews of PR #{}: {}",
+            pr_number,
+            stderr.trim()
+        ));
+    }
+
+    let json_str = String::from_utf8(output.stdout).context("gh output is not valid UTF-8")?;
+
+    parse_pr_review_pages(&json_str).context("Failed to parse gh JSON output")
+}
+
+/// The pull request of the first page, with the review threads of them all
+fn parse_pr_review_pages(json: &str) -> serde_json::Result<PrReviews> {
+    let mut pages = serde_json::Deserializer::from_str(json).into_iter::<PrReviews>();
+    let mut reviews = match pages.next() {
+        Some(page) => page?,
+        // An empty stream fails to parse the way an empty document would
+        None => return serde_json::from_str(json),
+    };
+    for page in pages {
+        reviews
+            .review_threads
+            .nodes
+            .extend(page?.review_threads.nodes);
+    }
+    Ok(reviews)
+}
+
 /// Internal struct for parsing batch PR list results
 #[derive(Debug, Deserialize)]
 struct PrBatchItem {
@@ -173,6 +324,11 @@ struct PrBatchItem {
 
 /// Fetch all PRs for the current repository.
 pub fn list_prs() -> Result<HashMap<String, PrSummary>> {
+    if offline::is_enabled() {
+        debug!("github:offline, skipping PR list");
+        return Ok(HashMap::new());
+    }
+
     let output = Command::new("gh")
         .args([
             "pr",
@@ -224,3 +380,38 @@ pub fn list_prs() -> Result<HashMap<String, PrSummary>> {
 
     Ok(pr_map)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_mode_answers_without_running_gh() {
+        // No test needs the network, so offline mode can stay on
+        crate::offline::enable();
+        assert!(list_prs().unwrap().is_empty());
+        let error = get_pr_reviews(12).unwrap_err().to_string();
+        assert_eq!(error, "Cannot fetch PR #12 reviews in offline mode");
+    }
+
+    #[test]
+    fn review_threads_are_gathered_from_every_page() {
+        let page = |path: &str| {
+            format!(
+                r#"{{"title":"Fix login","body":"","reviewThreads":{{"pageInfo":{{"hasNextPage":false,"endCursor":null}},"nodes":[{{"isResolved":false,"isOutdated":false,"path":"{}","line":3,"originalLine":3,"comments":{{"totalCount":0,"nodes":[]}}}}]}}}}"#,
+                path
+            )
+        };
+        let json = format!("{}\n{}\n", page("src/a.rs"), page("src/b.rs"));
+        let reviews = parse_pr_review_pages(&json).unwrap();
+        assert_eq!(reviews.title, "Fix login");
+        let paths: Vec<&str> = reviews
+            .review_threads
+            .nodes
+            .iter()
+            .map(|thread| thread.path.as_str())
+            .collect();
+        assert_eq!(paths, ["src/a.rs", "src/b.rs"]);
+        assert!(parse_pr_review_pages("").is_err());
+    }
+}
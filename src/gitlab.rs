@@ -0,0 +1,185 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::process::Command;
+use tracing::debug;
+
+use crate::offline;
+
+#[derive(Debug, Deserialize)]
+pub struct MrDetails {
+    pub source_branch: String,
+    pub source_project_id: u64,
+    pub target_project_id: u64,
+    /// "opened", "closed", "merged" or "locked"
+    pub state: String,
+    #[serde(default)]
+    pub draft: bool,
+    pub title: String,
+    pub author: Author,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Branch the MR merges into
+    #[serde(default)]
+    pub target_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Author {
+    pub username: String,
+}
+
+/// A thread of notes on a merge request
+#[derive(Debug, Deserialize)]
+pub struct Discussion {
+    pub notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Note {
+    pub body: String,
+    pub author: Author,
+    /// Whether the note starts a thread that can be resolved
+    #[serde(default)]
+    pub resolvable: bool,
+    #[serde(default)]
+    pub resolved: bool,
+    /// Set for notes GitLab writes itself (e.g. "added 1 commit")
+    #[serde(default)]
+    pub system: bool,
+    /// Where in the diff the note was left, for diff notes
+    #[serde(default)]
+    pub position: Option<NotePosition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotePosition {
+    pub new_path: Option<String>,
+    pub new_line: Option<u32>,
+    pub old_line: Option<u32>,
+}
+
+impl MrDetails {
+    /// Whether the source branch lives in a fork rather than the target project
+    pub fn is_fork(&self) -> bool {
+        self.source_project_id != self.target_project_id
+    }
+}
+
+/// Fetches merge request details using the GitLab CLI
+pub fn get_mr_details(mr_number: u32) -> Result<MrDetails> {
+    if offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot look up MR !{} in offline mode. Check out the branch by name instead.",
+            mr_number
+        ));
+    }
+
+    let output = Command::new("glab")
+        .args(["mr", "view", &mr_number.to_string(), "--output", "json"])
+        .output();
+
+    let output = match output {
+        Ok(out) => out,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("gitlab:glab CLI not found");
+            return Err(anyhow!(
+                "GitLab CLI (glab) is required for --pr with GitLab. Install from https://gitlab.com/gitlab-org/cli"
+            ));
+        }
+        Err(e) => {
+            return Err(e).context("Failed to execute glab command");
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!(mr = mr_number, stderr = %stderr, "gitlab:mr view failed");
+        return Err(anyhow!(
+            "Failed to fetch MR !{}: {}",
+            mr_number,
+            stderr.trim()
+        ));
+    }
+
+    let json_str = String::from_utf8(output.stdout).context("glab output is not valid UTF-8")?;
+
+    serde_json::from_str(&json_str).context("Failed to parse glab JSON output")
+}
+
+/// Fetches the discussions on a merge request using the GitLab CLI
+pub fn get_mr_discussions(mr_number: u32) -> Result<Vec<Discussion>> {
+    if offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot fetch MR !{} discussions in offline mode",
+            mr_number
+        ));
+    }
+
+    // glab fills in :id from the current repository
+    let endpoint = format!(
+        "projects/:id/merge_requests/{}/discussions?per_page=100",
+        mr_number
+    );
+    let output = Command::new("glab")
+        .args(["api", "--paginate", &endpoint])
+        .output();
+
+    let output = match output {
+        Ok(out) => out,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!("gitlab:glab CLI not found");
+            return Err(anyhow!(
+                "GitLab CLI (glab) is required for --pr with GitLab. Install from https://gitlab.com/gitlab-org/cli"
+            ));
+        }
+        Err(e) => {
+            return Err(e).context("Failed to execute glab command");
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!(mr = mr_number, stderr = %stderr, "gitlab:mr discussions failed");
+        return Err(anyhow!(
+            "Failed to fetch discussions of MR !{}: {}",
+            mr_number,
+            stderr.trim()
+        ));
+    }
+
+    let json_str = String::from_utf8(output.stdout).context("glab output is not valid UTF-8")?;
+
+    parse_discussion_pages(&json_str).context("Failed to parse glab JSON output")
+}
+
+/// The discussions of every page `glab api --paginate` printed, one array
+/// after another
+fn parse_discussion_pages(json: &str) -> serde_json::Result<Vec<Discussion>> {
+    let mut discussions = Vec::new();
+    for page in serde_json::Deserializer::from_str(json).into_iter::<Vec<Discussion>>() {
+        discussions.extend(page?);
+    }
+    Ok(discussions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discussions_are_gathered_from_every_page() {
+        let note = |body: &str| {
+            format!(
+                r#"{{"notes":[{{"body":"{}","author":{{"username":"ada"}}}}]}}"#,
+                body
+            )
+        };
+        let json = format!("[{},{}][{}]", note("first"), note("second"), note("third"));
+        let bodies: Vec<String> = parse_discussion_pages(&json)
+            .unwrap()
+            .into_iter()
+            .map(|discussion| discussion.notes[0].body.clone())
+            .collect();
+        assert_eq!(bodies, ["first", "second", "third"]);
+    }
+}
@@ -0,0 +1,20 @@
+//! The commands `workmux --help` lists, registered by the CLI so that code
+//! below it (e.g. agent instructions) can show them without depending on it.
+
+use std::sync::OnceLock;
+
+/// Lists the name and description of each command
+type Lister = fn() -> Vec<(String, String)>;
+
+static COMMANDS: OnceLock<Lister> = OnceLock::new();
+
+/// Register how to list the commands, from the CLI's definition
+pub fn register(commands: Lister) {
+    let _ = COMMANDS.set(commands);
+}
+
+/// Name and description of each command `workmux --help` lists; none if the
+/// CLI hasn't registered them
+pub fn commands() -> Vec<(String, String)> {
+    COMMANDS.get().map_or_else(Vec::new, |commands| commands())
+}
@@ -0,0 +1,85 @@
+//! Ctrl-C handling while hooks, file operations and multi-step commands run.
+//!
+//! Outside a [`Guard`], Ctrl-C terminates workmux as usual. Inside one, it only
+//! sets a flag: running hooks are killed, file operations stop between files,
+//! and the caller gets an [`Interrupted`] error it can roll back from.
+//! Commands that work through several worktrees check the flag between them.
+//! Pressing Ctrl-C a second time terminates workmux without waiting for that.
+
+use anyhow::{Context, Result, anyhow};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Returned when the user cancels with Ctrl-C inside a [`Guard`]
+#[derive(Debug, thiserror::Error)]
+#[error("Interrupted")]
+pub struct Interrupted;
+
+static FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Number of guards alive; the handler only catches signals while it's above 0
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Catches Ctrl-C (and SIGTERM) for as long as it is alive
+pub struct Guard(());
+
+impl Guard {
+    pub fn new() -> Result<Self> {
+        install()?;
+        GUARDS.fetch_add(1, Ordering::SeqCst);
+        Ok(Self(()))
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        GUARDS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Install the handler once for the whole process. Unregistering it again
+/// would leave the signals ignored rather than restore their default action,
+/// so it stays, and acts as the default itself outside a guard.
+fn install() -> Result<()> {
+    static INSTALLED: OnceLock<Result<(), String>> = OnceLock::new();
+    INSTALLED
+        .get_or_init(|| {
+            for signal in [SIGINT, SIGTERM] {
+                // SAFETY: the handler only touches atomics and calls
+                // `emulate_default_handler`, which is async-signal-safe
+                unsafe { signal_hook::low_level::register(signal, move || on_signal(signal)) }
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        })
+        .clone()
+        .map_err(|e| anyhow!(e))
+        .context("Failed to install Ctrl-C handler")
+}
+
+fn on_signal(signal: i32) {
+    // Guards span whole batches, including steps that never check the flag,
+    // so a second Ctrl-C quits instead of waiting for the next check
+    if GUARDS.load(Ordering::SeqCst) == 0 || FLAG.swap(true, Ordering::SeqCst) {
+        let _ = signal_hook::low_level::emulate_default_handler(signal);
+    }
+}
+
+/// Whether Ctrl-C was pressed inside a guard
+pub fn is_interrupted() -> bool {
+    FLAG.load(Ordering::SeqCst)
+}
+
+/// Fail with [`Interrupted`] if Ctrl-C was pressed
+pub fn check() -> Result<()> {
+    if is_interrupted() {
+        return Err(Interrupted.into());
+    }
+    Ok(())
+}
+
+/// Whether an error (or anything in its chain) is an [`Interrupted`]
+pub fn is_interruption(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<Interrupted>())
+}
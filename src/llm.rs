@@ -5,14 +5,79 @@ use std::process::{Command, Stdio};
 const DEFAULT_SYSTEM_PROMPT: &str = r#"Generate a short, valid git branch name (kebab-case) based on the user's input.
 Output ONLY the branch name."#;
 
+const DESCRIPTION_SYSTEM_PROMPT: &str = r#"Summarize the task in the user's input as one sentence of at most 100 characters, to describe the git branch it is done on.
+Output ONLY the sentence."#;
+
 pub fn generate_branch_name(
     prompt: &str,
     model: Option<&str>,
     system_prompt: Option<&str>,
 ) -> Result<String> {
+    if crate::offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot generate a branch name in offline mode. Provide a branch name instead of --auto-name."
+        ));
+    }
+
     let system = system_prompt.unwrap_or(DEFAULT_SYSTEM_PROMPT);
-    let full_prompt = format!("{}\n\nUser Input:\n{}", system, prompt);
+    let raw = run(&format!("{}\n\nUser Input:\n{}", system, prompt), model)?;
+    let branch_name = sanitize_branch_name(raw.trim());
+
+    if branch_name.is_empty() {
+        return Err(anyhow!("LLM returned empty branch name"));
+    }
+
+    Ok(branch_name)
+}
+
+/// Summarize a prompt into a one-line branch description
+pub fn summarize_prompt(prompt: &str, model: Option<&str>) -> Result<String> {
+    if crate::offline::is_enabled() {
+        return Err(anyhow!("Cannot summarize the prompt in offline mode"));
+    }
+    let raw = run(
+        &format!("{}\n\nUser Input:\n{}", DESCRIPTION_SYSTEM_PROMPT, prompt),
+        model,
+    )?;
+    let summary = raw.trim().trim_matches('`').trim();
+    if summary.is_empty() {
+        return Err(anyhow!("LLM returned an empty summary"));
+    }
+    Ok(summary.lines().next().unwrap_or(summary).to_string())
+}
+
+/// Run a prompt that asks for a summary of a worktree's changes
+pub fn summarize_changes(prompt: &str, model: Option<&str>) -> Result<String> {
+    if crate::offline::is_enabled() {
+        return Err(anyhow!("Cannot summarize changes in offline mode"));
+    }
+    let summary = run(prompt, model)?.trim().to_string();
+    if summary.is_empty() {
+        return Err(anyhow!("LLM returned an empty summary"));
+    }
+    Ok(summary)
+}
+
+/// Run a prompt that asks for an interactive rebase plan
+pub fn suggest_rebase_plan(prompt: &str, model: Option<&str>) -> Result<String> {
+    if crate::offline::is_enabled() {
+        return Err(anyhow!("Cannot suggest a rebase plan in offline mode"));
+    }
+    run(prompt, model)
+}
+
+/// Run a prompt that asks how to split changes into commits
+pub fn suggest_commit_split(prompt: &str, model: Option<&str>) -> Result<String> {
+    if crate::offline::is_enabled() {
+        return Err(anyhow!(
+            "Cannot suggest how to split changes in offline mode"
+        ));
+    }
+    run(prompt, model)
+}
 
+/// Run the `llm` CLI with `full_prompt` on stdin and return its output
+fn run(full_prompt: &str, model: Option<&str>) -> Result<String> {
     let mut cmd = Command::new("llm");
     if let Some(m) = model {
         cmd.args(["-m", m]);
@@ -36,14 +101,7 @@ pub fn generate_branch_name(
         return Err(anyhow!("llm command failed: {}", stderr));
     }
 
-    let raw = String::from_utf8(output.stdout)?;
-    let branch_name = sanitize_branch_name(raw.trim());
-
-    if branch_name.is_empty() {
-        return Err(anyhow!("LLM returned empty branch name"));
-    }
-
-    Ok(branch_name)
+    Ok(String::from_utf8(output.stdout)?)
 }
 
 fn sanitize_branch_name(raw: &str) -> String {
@@ -1,15 +1,27 @@
-use std::fs;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result, anyhow};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{EnvFilter, fmt};
+use tracing_subscriber::{EnvFilter, fmt, reload};
 
 static INIT: OnceLock<()> = OnceLock::new();
 static GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+/// Sets the level of the log printed to stderr, which is off until `-v`
+static CONSOLE_LEVEL: OnceLock<Box<dyn Fn(LevelFilter) + Send + Sync>> = OnceLock::new();
+/// The config's `log_file`, once it has been opened
+static JSON_FILE: OnceLock<Mutex<File>> = OnceLock::new();
 
 pub fn init() -> Result<()> {
     if INIT.get().is_some() {
@@ -22,7 +34,7 @@ pub fn init() -> Result<()> {
 }
 
 fn init_inner() -> Result<()> {
-    let log_path = determine_log_path()?;
+    let log_path = log_path()?;
     if let Some(parent) = log_path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create log directory at {}", parent.display()))?;
@@ -34,42 +46,164 @@ fn init_inner() -> Result<()> {
     let _ = GUARD.set(guard);
 
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (console_filter, console_level) = reload::Layer::new(LevelFilter::OFF);
+    let _ = CONSOLE_LEVEL.set(Box::new(move |level| {
+        let _ = console_level.modify(|filter| *filter = level);
+    }));
 
     tracing_subscriber::registry()
-        .with(env_filter)
         .with(
             fmt::layer()
                 .with_writer(non_blocking)
                 .with_ansi(false)
-                .with_target(false),
+                .with_target(false)
+                .with_filter(env_filter),
         )
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(std::io::stderr().is_terminal())
+                .with_target(false)
+                .without_time()
+                .with_filter(console_filter),
+        )
+        .with(JsonLayer.with_filter(LevelFilter::DEBUG))
         .try_init()
         .context("Failed to initialize tracing subscriber")?;
 
     Ok(())
 }
 
-fn determine_log_path() -> Result<PathBuf> {
+/// Print the log to stderr as well: info with `-v`, debug (including every
+/// command run) with `-vv`, and trace from `-vvv`
+pub fn set_verbosity(verbose: u8) {
+    let level = match verbose {
+        0 => LevelFilter::OFF,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    if let Some(set_level) = CONSOLE_LEVEL.get() {
+        set_level(level);
+    }
+}
+
+/// Also append the log, down to debug, to `path` as one JSON object per line
+pub fn log_json_to(path: &str) -> Result<()> {
+    let path = json_log_path(path)?;
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create log directory at {}", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open log_file '{}'", path.display()))?;
+    let _ = JSON_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// The config's `log_file`, with `~/` expanded
+pub fn json_log_path(path: &str) -> Result<PathBuf> {
+    Ok(match path.strip_prefix("~/") {
+        Some(rest) => home::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(rest),
+        None => PathBuf::from(path),
+    })
+}
+
+/// Writes each event to `JSON_FILE`, if set, as a line of JSON
+struct JsonLayer;
+
+impl<S: Subscriber> Layer<S> for JsonLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let Some(file) = JSON_FILE.get() else {
+            return;
+        };
+        let mut line = json_entry(event);
+        line.push('\n');
+        // A single write per event keeps lines whole when workmux processes
+        // share the file
+        if let Ok(mut file) = file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+fn json_entry(event: &Event<'_>) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let mut fields = JsonFields(Map::new());
+    fields.0.insert("timestamp".to_string(), timestamp.into());
+    fields.0.insert(
+        "level".to_string(),
+        event.metadata().level().as_str().into(),
+    );
+    fields
+        .0
+        .insert("pid".to_string(), std::process::id().into());
+    event.record(&mut fields);
+    Value::Object(fields.0).to_string()
+}
+
+/// Collects an event's fields, keeping numbers and booleans as such
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+}
+
+/// File workmux logs to
+pub fn log_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("workmux.log"))
+}
+
+/// Directory for workmux's own state (log, audit history).
+pub fn state_dir() -> Result<PathBuf> {
     // Check XDG_STATE_HOME environment variable first
     if let Ok(state_home) = std::env::var("XDG_STATE_HOME")
         && !state_home.is_empty()
     {
-        return Ok(PathBuf::from(state_home)
-            .join("workmux")
-            .join("workmux.log"));
+        return Ok(PathBuf::from(state_home).join("workmux"));
     }
 
-    // Fall back to XDG default: ~/.local/state/workmux/workmux.log
+    // Fall back to XDG default: ~/.local/state/workmux
     if let Some(home_dir) = home::home_dir() {
-        return Ok(home_dir
-            .join(".local")
-            .join("state")
-            .join("workmux")
-            .join("workmux.log"));
+        return Ok(home_dir.join(".local").join("state").join("workmux"));
     }
 
     // Fallback to current directory if home cannot be determined
-    Ok(std::env::current_dir()?.join("workmux.log"))
+    Ok(std::env::current_dir()?)
 }
 
 fn split_path(path: &Path) -> Result<(PathBuf, &str)> {
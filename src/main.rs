@@ -1,25 +1,45 @@
+mod activity;
+mod agent;
+mod audit;
 mod claude;
 mod cli;
 mod cmd;
 mod command;
 mod config;
+mod dry_run;
 mod git;
 mod github;
+mod gitlab;
+mod help;
+mod interrupt;
 mod llm;
 mod logger;
+mod manifest;
 mod naming;
+mod offline;
+mod preset;
 mod prompt;
+mod release;
+mod remote;
+mod resume;
+mod retention;
 mod spinner;
+mod state_encryption;
 mod template;
 mod tmux;
 mod workflow;
+mod worktree_state;
 
 use anyhow::Result;
 use tracing::{error, info};
 
 fn main() -> Result<()> {
+    git::take_repo_env();
     logger::init()?;
     info!(args = ?std::env::args().collect::<Vec<_>>(), "workmux start");
+    if let Some(context) = git::repo_context() {
+        info!(?context, "git:repository from GIT_DIR/GIT_WORK_TREE");
+    }
 
     match cli::run() {
         Ok(result) => {
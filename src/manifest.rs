@@ -0,0 +1,181 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, PaneConfig};
+
+/// A declarative list of worktrees to create/open with `workmux apply`.
+///
+/// ```yaml
+/// worktrees:
+///   - branch: feature/auth
+///     base: main
+///     agent: claude
+///     prompt_file: prompts/auth.md
+///     panes:
+///       - command: <agent>
+///         focus: true
+///       - split: horizontal
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub worktrees: Vec<ManifestEntry>,
+}
+
+/// A single worktree in a manifest
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    /// Branch to check out (created if it doesn't exist)
+    pub branch: String,
+
+    /// Override the worktree/window name (same as `add --name`)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Base branch/commit/tag for new branches (defaults to the current branch)
+    #[serde(default)]
+    pub base: Option<String>,
+
+    /// Agent to use for the `<agent>` placeholder
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// Prompt file passed to the agent when the worktree is created.
+    /// Relative paths are resolved from the manifest's directory.
+    #[serde(default)]
+    pub prompt_file: Option<PathBuf>,
+
+    /// Pane layout for this worktree, overriding the configured `panes`
+    #[serde(default, alias = "layout")]
+    pub panes: Option<Vec<PaneConfig>>,
+}
+
+impl Manifest {
+    /// Read and validate a manifest file, resolving relative prompt paths.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest '{}'", path.display()))?;
+        let mut manifest = Self::parse(&contents)
+            .with_context(|| format!("Invalid manifest '{}'", path.display()))?;
+
+        let manifest_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        for entry in &mut manifest.worktrees {
+            if let Some(prompt_file) = entry.prompt_file.take() {
+                entry.prompt_file = Some(if prompt_file.is_absolute() {
+                    prompt_file
+                } else {
+                    manifest_dir.join(prompt_file)
+                });
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Parse manifest YAML and check entries for obvious mistakes.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let manifest: Self = serde_yaml::from_str(contents)?;
+
+        let mut seen = HashSet::new();
+        for (i, entry) in manifest.worktrees.iter().enumerate() {
+            if entry.branch.trim().is_empty() {
+                return Err(anyhow!("Worktree {} has an empty 'branch'", i));
+            }
+            if !seen.insert(entry.branch.as_str()) {
+                return Err(anyhow!(
+                    "Branch '{}' is listed more than once",
+                    entry.branch
+                ));
+            }
+            if let Some(panes) = &entry.panes {
+                config::validate_panes_config(panes)
+                    .with_context(|| format!("Invalid panes for branch '{}'", entry.branch))?;
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_full_entry() {
+        let manifest = Manifest::parse(
+            r#"
+worktrees:
+  - branch: feature/auth
+    name: auth
+    base: main
+    agent: gemini
+    prompt_file: prompts/auth.md
+    layout:
+      - command: <agent>
+        focus: true
+      - split: horizontal
+  - branch: fix/login
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.worktrees.len(), 2);
+        let auth = &manifest.worktrees[0];
+        assert_eq!(auth.branch, "feature/auth");
+        assert_eq!(auth.name.as_deref(), Some("auth"));
+        assert_eq!(auth.base.as_deref(), Some("main"));
+        assert_eq!(auth.agent.as_deref(), Some("gemini"));
+        assert_eq!(auth.prompt_file, Some(PathBuf::from("prompts/auth.md")));
+        assert_eq!(auth.panes.as_ref().map(|p| p.len()), Some(2));
+
+        let login = &manifest.worktrees[1];
+        assert!(login.base.is_none());
+        assert!(login.panes.is_none());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_fields() {
+        let err = Manifest::parse("worktrees:\n  - branch: a\n    bsae: main\n").unwrap_err();
+        assert!(err.to_string().contains("bsae"));
+    }
+
+    #[test]
+    fn parse_rejects_duplicate_branches() {
+        let err = Manifest::parse("worktrees:\n  - branch: a\n  - branch: a\n").unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_panes() {
+        let result = Manifest::parse(
+            "worktrees:\n  - branch: a\n    panes:\n      - command: vim\n      - command: ls\n",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_resolves_prompt_file_relative_to_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("team.yaml");
+        std::fs::write(
+            &path,
+            "worktrees:\n  - branch: a\n    prompt_file: prompts/a.md\n  - branch: b\n    prompt_file: /abs/b.md\n",
+        )
+        .unwrap();
+
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(
+            manifest.worktrees[0].prompt_file,
+            Some(dir.path().join("prompts/a.md"))
+        );
+        assert_eq!(
+            manifest.worktrees[1].prompt_file,
+            Some(PathBuf::from("/abs/b.md"))
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Process-wide offline mode, enabled with the global `--offline` flag.
+//!
+//! When enabled, workmux skips everything that needs the network (git fetches,
+//! GitHub lookups via `gh`, LLM calls) and falls back to local state where it can.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Turn on offline mode for the rest of the process
+pub fn enable() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Whether network operations should be skipped
+pub fn is_enabled() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
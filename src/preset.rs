@@ -0,0 +1,178 @@
+//! Built-in setup for common project types.
+//!
+//! A repository without `post_create` hooks of its own gets the install step of
+//! the preset matching its lockfile or manifest, and, when the project defines
+//! a dev server, a pane running it. Worktrees checked out to review a pull
+//! request run its tests in a pane. Presets are picked by the `preset` option:
+//! `auto` (the default) detects one, `none` turns them off, and a preset name
+//! forces that preset.
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    /// Files in the repository root identifying the project type
+    markers: &'static [&'static str],
+    /// Executable that must be on PATH for the preset to be detected
+    tool: &'static str,
+    /// Installs dependencies, reusing the tool's shared cache or store
+    pub install: &'static str,
+    /// Starts the dev server, if `package.json` has a `dev` script
+    dev: Option<&'static str>,
+    /// Runs the tests, in the test pane of worktrees checked out for review
+    pub test: &'static str,
+}
+
+/// Built-in presets, in detection order
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "node-pnpm",
+        markers: &["pnpm-lock.yaml"],
+        tool: "pnpm",
+        install: "pnpm install --prefer-offline",
+        dev: Some("pnpm run dev"),
+        test: "pnpm test",
+    },
+    Preset {
+        name: "node-npm",
+        markers: &["package-lock.json"],
+        tool: "npm",
+        install: "npm install --prefer-offline",
+        dev: Some("npm run dev"),
+        test: "npm test",
+    },
+    Preset {
+        name: "rust",
+        markers: &["Cargo.toml"],
+        tool: "cargo",
+        install: "cargo fetch",
+        dev: None,
+        test: "cargo test",
+    },
+    Preset {
+        name: "python-uv",
+        markers: &["uv.lock"],
+        tool: "uv",
+        install: "uv sync",
+        dev: None,
+        test: "uv run pytest",
+    },
+    Preset {
+        name: "go",
+        markers: &["go.mod"],
+        tool: "go",
+        install: "go mod download",
+        dev: None,
+        test: "go test ./...",
+    },
+];
+
+impl Preset {
+    /// Command for the dev server pane, when the project has a dev server
+    pub fn dev_command(&self, repo_root: &Path) -> Option<&'static str> {
+        self.dev.filter(|_| has_dev_script(repo_root))
+    }
+
+    fn matches(&self, repo_root: &Path) -> bool {
+        self.markers
+            .iter()
+            .any(|marker| repo_root.join(marker).exists())
+    }
+}
+
+/// The preset selected by the `preset` option for the repository at `repo_root`.
+/// Detection only picks presets whose tool is installed.
+pub fn resolve(setting: Option<&str>, repo_root: &Path) -> Result<Option<&'static Preset>> {
+    resolve_with(setting, repo_root, |tool| which::which(tool).is_ok())
+}
+
+fn resolve_with(
+    setting: Option<&str>,
+    repo_root: &Path,
+    is_installed: impl Fn(&str) -> bool,
+) -> Result<Option<&'static Preset>> {
+    match setting.unwrap_or("auto") {
+        "auto" => Ok(PRESETS
+            .iter()
+            .find(|preset| preset.matches(repo_root) && is_installed(preset.tool))),
+        "none" => Ok(None),
+        name => PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .map(Some)
+            .ok_or_else(|| {
+                let names: Vec<_> = PRESETS.iter().map(|preset| preset.name).collect();
+                anyhow!(
+                    "Unknown preset '{}'. Use auto, none or one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            }),
+    }
+}
+
+fn has_dev_script(repo_root: &Path) -> bool {
+    fs::read_to_string(repo_root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|package| package["scripts"]["dev"].is_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_with;
+    use std::fs;
+
+    #[test]
+    fn detects_the_first_preset_with_a_marker_and_an_installed_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let name = |setting: Option<&str>, installed: &[&str]| {
+            resolve_with(setting, root, |tool| installed.contains(&tool))
+                .unwrap()
+                .map(|preset| preset.name)
+        };
+
+        assert_eq!(name(None, &["pnpm", "cargo"]), None);
+
+        // Without the option set, the detected preset applies
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        assert_eq!(name(None, &["pnpm", "cargo"]), Some("node-pnpm"));
+        assert_eq!(name(Some("auto"), &["cargo"]), Some("rust"));
+        assert_eq!(name(None, &[]), None);
+        // `preset: none` opts out
+        assert_eq!(name(Some("none"), &["pnpm", "cargo"]), None);
+
+        // A named preset applies without detection
+        assert_eq!(name(Some("go"), &[]), Some("go"));
+        let err = resolve_with(Some("ruby"), root, |_| true).unwrap_err();
+        assert!(err.to_string().contains("node-pnpm, node-npm, rust"));
+    }
+
+    #[test]
+    fn dev_pane_needs_a_dev_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let npm = resolve_with(Some("node-npm"), root, |_| true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(npm.dev_command(root), None);
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"build": "vite build"}}"#,
+        )
+        .unwrap();
+        assert_eq!(npm.dev_command(root), None);
+
+        fs::write(root.join("package.json"), r#"{"scripts": {"dev": "vite"}}"#).unwrap();
+        assert_eq!(npm.dev_command(root), Some("npm run dev"));
+
+        let rust = resolve_with(Some("rust"), root, |_| true).unwrap().unwrap();
+        assert_eq!(rust.dev_command(root), None);
+    }
+}
@@ -23,7 +23,10 @@ impl Prompt {
     }
 }
 
+/// Frontmatter of a prompt file. Unknown keys are rejected so a typo like
+/// `for_each:` fails instead of silently creating a single worktree.
 #[derive(Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct PromptMetadata {
     #[serde(default)]
     pub foreach: Option<BTreeMap<String, Vec<String>>>,
@@ -88,7 +91,10 @@ pub fn parse_prompt_document(prompt: &Prompt) -> Result<PromptDocument> {
     let (frontmatter_yaml, body) = split_frontmatter(content);
 
     let meta = if let Some(ref yaml) = frontmatter_yaml {
-        serde_yaml::from_str(yaml).context("Failed to parse YAML frontmatter")?
+        parse_frontmatter(yaml).with_context(|| match prompt {
+            Prompt::FromFile(path) => format!("Invalid frontmatter in {}", path.display()),
+            Prompt::Inline(_) => "Invalid prompt frontmatter".to_string(),
+        })?
     } else {
         PromptMetadata::default()
     };
@@ -99,6 +105,47 @@ pub fn parse_prompt_document(prompt: &Prompt) -> Result<PromptDocument> {
     })
 }
 
+/// Deserialize frontmatter YAML, reporting errors with the offending key and
+/// their line in the prompt file.
+fn parse_frontmatter(yaml: &str) -> Result<PromptMetadata> {
+    serde_yaml::from_str(yaml).map_err(|e| {
+        let message = strip_yaml_locations(&e.to_string());
+        match e.location() {
+            // Count the opening `---` line so the line matches the file
+            Some(location) => anyhow::anyhow!(
+                "line {}, column {}: {}",
+                location.line() + 1,
+                location.column(),
+                message
+            ),
+            None => anyhow::anyhow!(message),
+        }
+    })
+}
+
+/// Remove the " at line N column M" positions serde_yaml puts in its
+/// messages, which count from the start of the frontmatter.
+fn strip_yaml_locations(message: &str) -> String {
+    let mut result = String::new();
+    let mut rest = message;
+    while let Some(idx) = rest.find(" at line ") {
+        result.push_str(&rest[..idx]);
+        let after = &rest[idx + " at line ".len()..];
+        match after
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .strip_prefix(" column ")
+        {
+            Some(column) => rest = column.trim_start_matches(|c: char| c.is_ascii_digit()),
+            None => {
+                result.push_str(" at line ");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
 /// Convert frontmatter foreach (BTreeMap<String, Vec<String>>) to matrix rows.
 /// Validates that all value lists have equal length (zip constraint).
 pub fn foreach_from_frontmatter(
@@ -144,6 +191,54 @@ pub fn foreach_from_frontmatter(
     Ok(rows)
 }
 
+/// Longest prompt used as a branch description as it is
+const DESCRIPTION_MAX_CHARS: usize = 200;
+
+/// A one-line branch description for a prompt. Short prompts are used as they
+/// are; long ones are given to `summarize`, or cut down to their first line
+/// if it returns None. None for an empty prompt.
+pub fn describe(content: &str, summarize: impl FnOnce(&str) -> Option<String>) -> Option<String> {
+    let content = content.trim();
+    let one_line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if one_line.is_empty() {
+        return None;
+    }
+    if one_line.chars().count() <= DESCRIPTION_MAX_CHARS {
+        return Some(one_line);
+    }
+    if let Some(summary) = summarize(content) {
+        return Some(summary);
+    }
+
+    let first_line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    if first_line.chars().count() <= DESCRIPTION_MAX_CHARS {
+        return Some(first_line.to_string());
+    }
+    let cut: String = first_line.chars().take(DESCRIPTION_MAX_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// Longest task title shown in tmux
+const TASK_MAX_CHARS: usize = 40;
+
+/// A short task title for a prompt: its first line, without Markdown heading
+/// marks, cut to fit a tmux status line. None for an empty prompt.
+pub fn task_title(content: &str) -> Option<String> {
+    let first_line = content
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())?;
+    let one_line = first_line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if one_line.chars().count() <= TASK_MAX_CHARS {
+        return Some(one_line);
+    }
+    let cut: String = one_line.chars().take(TASK_MAX_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +337,37 @@ mod tests {
         assert!(doc.meta.foreach.is_none());
     }
 
+    #[test]
+    fn frontmatter_errors_point_at_the_file_line() {
+        let prompt = Prompt::Inline("---\nfor_each:\n  platform: [iOS]\n---\nBody".to_string());
+        let err = format!("{:#}", parse_prompt_document(&prompt).unwrap_err());
+        assert_eq!(
+            err,
+            "Invalid prompt frontmatter: line 2, column 1: unknown field `for_each`, expected `foreach`"
+        );
+
+        let prompt = Prompt::Inline("---\nforeach:\n  platform: iOS\n---\nBody".to_string());
+        let err = format!("{:#}", parse_prompt_document(&prompt).unwrap_err());
+        assert!(
+            err.ends_with(
+                "line 3, column 13: foreach.platform: invalid type: string \"iOS\", expected a sequence"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn yaml_locations_are_stripped_from_messages() {
+        assert_eq!(
+            strip_yaml_locations(
+                "did not find expected ',' at line 3 column 1, while parsing a flow sequence at line 2 column 12"
+            ),
+            "did not find expected ',', while parsing a flow sequence"
+        );
+        assert_eq!(strip_yaml_locations("stuck at line up"), "stuck at line up");
+    }
+
     #[test]
     fn foreach_from_frontmatter_creates_rows() {
         let mut map = BTreeMap::new();
@@ -298,4 +424,41 @@ mod tests {
                 .contains("at least one value")
         );
     }
+
+    #[test]
+    fn describe_keeps_short_prompts_and_shortens_long_ones() {
+        assert_eq!(
+            describe("  Fix the\n  login bug ", |_| None),
+            Some("Fix the login bug".to_string())
+        );
+        assert_eq!(describe(" \n ", |_| None), None);
+
+        let long = format!("Add OAuth login\n\n{}", "Details. ".repeat(40));
+        assert_eq!(
+            describe(&long, |_| None),
+            Some("Add OAuth login".to_string())
+        );
+        assert_eq!(
+            describe(&long, |_| Some("Add OAuth login with Google".to_string())),
+            Some("Add OAuth login with Google".to_string())
+        );
+
+        let one_long_line = "word ".repeat(100);
+        let described = describe(&one_long_line, |_| None).unwrap();
+        assert_eq!(described.chars().count(), 200);
+        assert!(described.ends_with("word…"));
+    }
+
+    #[test]
+    fn task_title_is_the_first_line_cut_short() {
+        assert_eq!(
+            task_title("\n# Fix   auth\n\nThe login form rejects valid tokens."),
+            Some("Fix auth".to_string())
+        );
+        assert_eq!(task_title(" \n#\n"), None);
+
+        let title = task_title(&"word ".repeat(20)).unwrap();
+        assert_eq!(title.chars().count(), 40);
+        assert!(title.ends_with("word…"));
+    }
 }
@@ -0,0 +1,277 @@
+//! Self-update from GitHub releases.
+//!
+//! Releases publish a `workmux-<os>-<arch>.tar.gz` per platform together with
+//! a `.sha256` file. Downloads go through `curl`, and the archive is checked
+//! against the published SHA-256 before the binary replaces the running one.
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tracing::debug;
+
+use crate::offline;
+
+const REPO: &str = "raine/workmux";
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+}
+
+impl Release {
+    /// Version without the leading `v` of the tag
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+}
+
+/// The newest published release
+pub fn latest() -> Result<Release> {
+    if offline::is_enabled() {
+        return Err(anyhow!("Cannot check for updates in offline mode"));
+    }
+    let url = format!("https://api.github.com/repos/{}/releases/latest", REPO);
+    let output = curl(&["-H", "Accept: application/vnd.github+json", &url])?;
+    serde_json::from_slice(&output.stdout).context("Failed to parse GitHub release JSON")
+}
+
+/// Whether `candidate` is a later version than `current`
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    compare_versions(candidate, current) == Ordering::Greater
+}
+
+/// The command to upgrade with instead, if a package manager owns `exe`
+pub fn package_manager(exe: &Path) -> Option<&'static str> {
+    let path = exe.to_string_lossy();
+    if path.contains("/Cellar/") || path.contains("/homebrew/") {
+        Some("brew upgrade workmux")
+    } else if path.contains("/.cargo/bin/") {
+        Some("cargo install workmux")
+    } else {
+        None
+    }
+}
+
+/// Download the release archive for this platform into `dir`, check it
+/// against the published checksum, and return the extracted binary.
+pub fn download(release: &Release, dir: &Path) -> Result<PathBuf> {
+    let asset = asset_name(env::consts::OS, env::consts::ARCH).ok_or_else(|| {
+        anyhow!(
+            "No release binary for {}-{}. Install with 'cargo install workmux' instead.",
+            env::consts::OS,
+            env::consts::ARCH
+        )
+    })?;
+    let base = format!(
+        "https://github.com/{}/releases/download/{}/{}",
+        REPO, release.tag_name, asset
+    );
+    let archive = dir.join(format!("{}.tar.gz", asset));
+    let checksum = dir.join(format!("{}.sha256", asset));
+    curl(&[
+        "-o",
+        &archive.to_string_lossy(),
+        &format!("{}.tar.gz", base),
+    ])?;
+    curl(&[
+        "-o",
+        &checksum.to_string_lossy(),
+        &format!("{}.sha256", base),
+    ])?;
+
+    let published = fs::read_to_string(&checksum)?;
+    let expected = parse_checksum(&published)
+        .ok_or_else(|| anyhow!("Malformed checksum file for {}", asset))?;
+    let actual = sha256(&archive)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "Checksum mismatch for {}.tar.gz: expected {}, got {}",
+            asset,
+            expected,
+            actual
+        ));
+    }
+    debug!(asset, sha256 = %actual, "release:checksum verified");
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(dir)
+        .arg("workmux")
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        return Err(anyhow!("Failed to extract {}.tar.gz", asset));
+    }
+    Ok(dir.join("workmux"))
+}
+
+/// Replace `exe` with `binary`. The new binary is copied next to `exe` first,
+/// so the final rename is atomic and a failed copy leaves `exe` untouched.
+pub fn install(binary: &Path, exe: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let staged = exe.with_file_name(".workmux-upgrade");
+    if let Err(e) = fs::copy(binary, &staged) {
+        let hint = if e.kind() == std::io::ErrorKind::PermissionDenied {
+            " (try again with sudo)"
+        } else {
+            ""
+        };
+        return Err(e).with_context(|| format!("Failed to write to {}{}", staged.display(), hint));
+    }
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    if let Err(e) = fs::rename(&staged, exe) {
+        let _ = fs::remove_file(&staged);
+        return Err(e).with_context(|| format!("Failed to replace {}", exe.display()));
+    }
+    Ok(())
+}
+
+/// Release asset for an `std::env::consts` OS and architecture
+fn asset_name(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("macos", "aarch64") => Some("workmux-darwin-arm64"),
+        ("macos", "x86_64") => Some("workmux-darwin-amd64"),
+        ("linux", "aarch64") => Some("workmux-linux-arm64"),
+        ("linux", "x86_64") => Some("workmux-linux-amd64"),
+        _ => None,
+    }
+}
+
+/// The hash from `shasum -a 256` output (`<hash>  <file>`)
+fn parse_checksum(content: &str) -> Option<&str> {
+    content
+        .split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn sha256(path: &Path) -> Result<String> {
+    let output = Command::new("sha256sum")
+        .arg(path)
+        .output()
+        .or_else(|_| {
+            Command::new("shasum")
+                .arg("-a")
+                .arg("256")
+                .arg(path)
+                .output()
+        })
+        .context("sha256sum or shasum is required to verify the download")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to checksum {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Failed to checksum {}", path.display()))
+}
+
+fn curl(args: &[&str]) -> Result<Output> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--proto", "=https"])
+        .args(args)
+        .output();
+    let output = match output {
+        Ok(out) => out,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow!("curl is required to upgrade workmux"));
+        }
+        Err(e) => return Err(e).context("Failed to execute curl"),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!(args = ?args, stderr = %stderr, "release:curl failed");
+        return Err(anyhow!("Download failed: {}", stderr.trim()));
+    }
+    Ok(output)
+}
+
+/// Compare dotted versions numerically, ignoring pre-release suffixes
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{asset_name, install, is_newer, package_manager, parse_checksum};
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    #[test]
+    fn newer_versions_compare_numerically() {
+        assert!(is_newer("0.1.76", "0.1.75"));
+        assert!(is_newer("0.1.100", "0.1.99"));
+        assert!(is_newer("1.0.0", "0.9.9"));
+        assert!(!is_newer("0.1.75", "0.1.75"));
+        assert!(!is_newer("0.1.74", "0.1.75"));
+        assert!(!is_newer("0.1.75-rc1", "0.1.75"));
+    }
+
+    #[test]
+    fn assets_match_release_artifacts() {
+        assert_eq!(asset_name("linux", "x86_64"), Some("workmux-linux-amd64"));
+        assert_eq!(asset_name("macos", "aarch64"), Some("workmux-darwin-arm64"));
+        assert_eq!(asset_name("windows", "x86_64"), None);
+    }
+
+    #[test]
+    fn checksum_is_read_from_shasum_output() {
+        let hash = "a".repeat(64);
+        let content = format!("{}  workmux-linux-amd64.tar.gz\n", hash);
+        assert_eq!(parse_checksum(&content), Some(hash.as_str()));
+        assert_eq!(parse_checksum("<html>Not Found</html>"), None);
+        assert_eq!(parse_checksum(""), None);
+    }
+
+    #[test]
+    fn package_managed_installs_are_detected() {
+        assert_eq!(
+            package_manager(Path::new("/opt/homebrew/Cellar/workmux/0.1.75/bin/workmux")),
+            Some("brew upgrade workmux")
+        );
+        assert_eq!(
+            package_manager(Path::new("/home/me/.cargo/bin/workmux")),
+            Some("cargo install workmux")
+        );
+        assert_eq!(package_manager(Path::new("/usr/local/bin/workmux")), None);
+    }
+
+    #[test]
+    fn install_replaces_the_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("workmux");
+        let binary = dir.path().join("new");
+        fs::write(&exe, "old").unwrap();
+        fs::write(&binary, "new").unwrap();
+
+        install(&binary, &exe).unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&exe).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+        assert!(!dir.path().join(".workmux-upgrade").exists());
+    }
+}
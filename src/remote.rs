@@ -0,0 +1,265 @@
+//! Remote machine support, enabled with the global `--host` flag.
+//!
+//! When enabled, every git and tmux command (and hook) runs on the remote host
+//! over ssh. All commands share one multiplexed ssh connection, so only the
+//! first one pays for the handshake.
+
+use anyhow::{Context, Result, anyhow};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::debug;
+
+use crate::cmd::{Cmd, shell_quote};
+
+static REMOTE: OnceLock<Remote> = OnceLock::new();
+
+/// Set when a command switched tmux windows on the remote host, so the
+/// caller can attach to the remote session once the command is done.
+static ATTACH_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Keep the shared connection open briefly so back-to-back workmux
+/// invocations reuse it too.
+const CONTROL_PERSIST: &str = "ControlPersist=60s";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Remote {
+    /// ssh destination, usually a `Host` alias from ~/.ssh/config
+    pub host: String,
+    /// Directory on the remote host that commands run in by default
+    pub dir: PathBuf,
+}
+
+impl Remote {
+    /// Parse `alias` or `alias:/path/to/repo`. Without a path, the current
+    /// directory's path is used on the remote host as well.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (host, dir) = match spec.split_once(':') {
+            Some((host, dir)) if !dir.is_empty() => (host, PathBuf::from(dir)),
+            Some((host, _)) => (host, current_dir()?),
+            None => (spec, current_dir()?),
+        };
+        if host.is_empty() {
+            return Err(anyhow!(
+                "Invalid --host '{}'. Expected an ssh host, optionally followed by ':<path>'.",
+                spec
+            ));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            dir,
+        })
+    }
+
+    /// Build an ssh invocation that runs `program args...` on the remote host,
+    /// in `workdir` (or the remote directory) with the given environment.
+    pub fn command(
+        &self,
+        program: &str,
+        args: &[&str],
+        workdir: Option<&Path>,
+        env_vars: &[(&str, &str)],
+        tty: bool,
+    ) -> Command {
+        let dir = workdir.unwrap_or(&self.dir);
+        let dir = dir.to_string_lossy();
+        // Leave a leading ~ unquoted so the remote shell expands it
+        let mut script = match dir.strip_prefix("~/") {
+            Some(rest) => format!("cd ~/{} &&", shell_quote(rest)),
+            None => format!("cd {} &&", shell_quote(&dir)),
+        };
+        if !env_vars.is_empty() {
+            script.push_str(" env");
+            for (key, value) in env_vars {
+                script.push(' ');
+                script.push_str(&shell_quote(&format!("{}={}", key, value)));
+            }
+        }
+        for word in std::iter::once(program).chain(args.iter().copied()) {
+            script.push(' ');
+            script.push_str(&shell_quote(word));
+        }
+
+        let mut cmd = self.ssh(tty);
+        cmd.arg(script);
+        cmd
+    }
+
+    /// Write `content` to `path` on the remote host, creating its directory.
+    pub fn write_file(&self, path: &Path, content: &str) -> Result<()> {
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        let mut child = self
+            .ssh(false)
+            .arg(format!(
+                "mkdir -p {} && cat > {}",
+                shell_quote(&dir.to_string_lossy()),
+                shell_quote(&path.to_string_lossy())
+            ))
+            .stdin(Stdio::piped())
+            .spawn()
+            .context("Failed to start ssh")?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open ssh stdin"))?
+            .write_all(content.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow!(
+                "Failed to write '{}' on {}",
+                path.display(),
+                self.host
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read `path` on the remote host, returning None if it does not exist.
+    pub fn read_file(&self, path: &Path) -> Result<Option<String>> {
+        let output = self
+            .ssh(false)
+            .arg(format!("cat {}", shell_quote(&path.to_string_lossy())))
+            .stderr(Stdio::null())
+            .output()
+            .context("Failed to start ssh")?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    /// Attach the local terminal to the remote tmux session.
+    pub fn attach(&self) -> Result<()> {
+        debug!(host = %self.host, "remote:attach");
+        let status = self
+            .ssh(true)
+            .args(["tmux", "attach-session"])
+            .status()
+            .context("Failed to start ssh")?;
+        if !status.success() {
+            return Err(anyhow!("Failed to attach to tmux on {}", self.host));
+        }
+        Ok(())
+    }
+
+    fn ssh(&self, tty: bool) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args([
+            "-o",
+            "ControlMaster=auto",
+            "-o",
+            "ControlPath=~/.ssh/workmux-%C",
+            "-o",
+            CONTROL_PERSIST,
+        ]);
+        cmd.arg(if tty { "-t" } else { "-T" });
+        cmd.args(["--", self.host.as_str()]);
+        cmd
+    }
+}
+
+/// Run commands on `spec` (see [`Remote::parse`]) for the rest of the process
+pub fn enable(spec: &str) -> Result<()> {
+    let remote = Remote::parse(spec)?;
+    debug!(host = %remote.host, dir = %remote.dir.display(), "remote:enabled");
+    REMOTE
+        .set(remote)
+        .map_err(|_| anyhow!("Remote host is already set"))
+}
+
+/// The remote host commands run on, if `--host` was given
+pub fn get() -> Option<&'static Remote> {
+    REMOTE.get()
+}
+
+/// Remember that the remote tmux should be attached to when the command finishes
+pub fn request_attach() {
+    if REMOTE.get().is_some() {
+        ATTACH_REQUESTED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Attach to the remote tmux if a command switched windows there.
+/// Scripts without a terminal are left alone.
+pub fn attach_if_requested() -> Result<()> {
+    match REMOTE.get() {
+        Some(remote) if ATTACH_REQUESTED.load(Ordering::Relaxed) && io::stdin().is_terminal() => {
+            remote.attach()
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `path` exists, on the remote host with `--host`
+pub fn path_exists(path: &Path) -> bool {
+    match REMOTE.get() {
+        Some(_) => Cmd::new("test")
+            .args(&["-e", &path.to_string_lossy()])
+            .run()
+            .is_ok(),
+        None => path.exists(),
+    }
+}
+
+/// Whether the directory `path` has nothing in it, on the remote host with
+/// `--host`. False if it can't be read.
+pub fn dir_is_empty(path: &Path) -> bool {
+    match REMOTE.get() {
+        Some(_) => Cmd::new("ls")
+            .args(&["-A", &path.to_string_lossy()])
+            .run_and_capture_stdout()
+            .is_ok_and(|entries| entries.is_empty()),
+        None => std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()),
+    }
+}
+
+fn current_dir() -> Result<PathBuf> {
+    std::env::current_dir().context("Failed to determine the current directory")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_host_with_and_without_path() {
+        let remote = Remote::parse("build:/srv/app").unwrap();
+        assert_eq!(remote.host, "build");
+        assert_eq!(remote.dir, PathBuf::from("/srv/app"));
+
+        let remote = Remote::parse("build").unwrap();
+        assert_eq!(remote.dir, std::env::current_dir().unwrap());
+
+        assert!(Remote::parse(":/srv/app").is_err());
+    }
+
+    #[test]
+    fn command_quotes_arguments_for_remote_shell() {
+        let remote = Remote::parse("build:/srv/my app").unwrap();
+        let cmd = remote.command(
+            "git",
+            &["commit", "-m", "it's done"],
+            None,
+            &[("WM_HANDLE", "feat")],
+            false,
+        );
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(args[args.len() - 2], "build");
+        assert_eq!(
+            args[args.len() - 1],
+            r#"cd '/srv/my app' && env WM_HANDLE=feat git commit -m 'it'\''s done'"#
+        );
+
+        let remote = Remote::parse("build:~/src/app").unwrap();
+        let cmd = remote.command("tmux", &["has-session"], None, &[], false);
+        let script = cmd
+            .get_args()
+            .last()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(script, "cd ~/src/app && tmux has-session");
+    }
+}
@@ -0,0 +1,88 @@
+//! Progress of a multi-step command stopped by Ctrl-C.
+//!
+//! `resume.json` in the state directory records which steps finished and which
+//! did not, so that running the same command again in the same repository can
+//! skip the finished ones. Only the most recent interrupted command is kept.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::{audit, dry_run, git, logger};
+
+const RESUME_FILE: &str = "resume.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumeState {
+    /// The workmux invocation that was interrupted
+    pub command: String,
+    /// Main worktree of the repository it ran in
+    pub repo: Option<PathBuf>,
+    /// Steps that finished, in order
+    pub completed: Vec<String>,
+    /// Steps that did not run, or were cancelled and rolled back
+    pub remaining: Vec<String>,
+}
+
+/// Save progress of the current command, returning where it was written.
+/// Failing to write is logged, not returned.
+pub fn save(completed: &[String], remaining: &[String]) -> Option<PathBuf> {
+    let state = ResumeState {
+        command: audit::invocation(),
+        repo: git::get_main_worktree_root().ok(),
+        completed: completed.to_vec(),
+        remaining: remaining.to_vec(),
+    };
+    match write(&state) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            warn!(error = %e, "resume:failed to save progress");
+            None
+        }
+    }
+}
+
+/// Saved progress of an earlier, interrupted run of this same command in this
+/// same repository, if there is one.
+pub fn load_for_current_command() -> Option<ResumeState> {
+    let content = fs::read_to_string(state_path().ok()?).ok()?;
+    let state: ResumeState = serde_json::from_str(&content).ok()?;
+    let repo = git::get_main_worktree_root().ok();
+    (state.command == audit::invocation() && state.repo == repo).then_some(state)
+}
+
+/// Forget saved progress once the command it belongs to has finished.
+pub fn clear() {
+    if let Ok(path) = state_path()
+        && !dry_run::skip(format_args!("rm -f {}", path.display()))
+        && let Err(e) = fs::remove_file(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!(error = %e, path = %path.display(), "resume:failed to remove saved progress");
+    }
+}
+
+/// The file progress is saved in, if some is
+pub fn saved_path() -> Option<PathBuf> {
+    state_path().ok().filter(|path| path.exists())
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(logger::state_dir()?.join(RESUME_FILE))
+}
+
+fn write(state: &ResumeState) -> Result<PathBuf> {
+    let path = state_path()?;
+    if dry_run::skip(format_args!("write {}", path.display())) {
+        return Ok(path);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create state directory {}", parent.display()))?;
+    }
+    let content = serde_json::to_string_pretty(state)?;
+    fs::write(&path, content).with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,291 @@
+//! Retention of what builds up as workmux is used.
+//!
+//! `retention` in the config sets how long workmux keeps entries of its logs,
+//! worktree directories `remove` renamed to trash but couldn't delete (held
+//! by a shell, for example), and the progress an interrupted command saved to
+//! resume from. Commands enforce it at most once a day as they start, and
+//! `workmux gc` does it on demand.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+use crate::command::history::format_timestamp;
+use crate::config::Config;
+use crate::workflow::WorkflowContext;
+use crate::{activity, audit, dry_run, git, logger, remote, resume};
+
+/// Prefix of the name `remove` renames a worktree directory to before
+/// deleting it, followed by the handle and a Unix timestamp
+pub const TRASH_PREFIX: &str = ".workmux_trash_";
+
+/// File in the state directory whose modification time records the last
+/// time retention was enforced
+const STAMP_FILE: &str = "retention.stamp";
+
+const ENFORCE_EVERY: Duration = Duration::from_secs(86_400);
+
+/// What enforcing retention removed
+#[derive(Debug, Default)]
+pub struct Collected {
+    /// Log entries removed, over all logs
+    pub log_entries: usize,
+    pub trash_dirs: Vec<PathBuf>,
+    /// Whether saved progress of an interrupted command was removed
+    pub checkpoint: bool,
+}
+
+/// Enforce retention if it wasn't in the last day. Failures are logged, not
+/// returned, so they never stop the command that is starting.
+pub fn enforce_if_due(config: &Config) {
+    if dry_run::is_enabled() {
+        return;
+    }
+    let Ok(stamp) = logger::state_dir().map(|dir| dir.join(STAMP_FILE)) else {
+        return;
+    };
+    let due = fs::metadata(&stamp)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age >= ENFORCE_EVERY);
+    if !due {
+        return;
+    }
+    // Stamped first, so a failure isn't retried by every command
+    if let Err(e) = fs::write(&stamp, "") {
+        warn!(error = %e, "retention:failed to write stamp");
+        return;
+    }
+    match enforce(config) {
+        Ok(collected) => debug!(
+            log_entries = collected.log_entries,
+            trash_dirs = collected.trash_dirs.len(),
+            checkpoint = collected.checkpoint,
+            "retention:enforced"
+        ),
+        Err(e) => warn!(error = %e, "retention:failed to enforce"),
+    }
+}
+
+/// Remove whatever is older than the config keeps it
+pub fn enforce(config: &Config) -> Result<Collected> {
+    let retention = &config.retention;
+    let mut collected = Collected::default();
+    if let Some(period) = retention.logs() {
+        let cutoff = cutoff(period);
+        for path in [audit::log_path()?, activity::log_path()?] {
+            collected.log_entries += prune_json_log(&path, cutoff)?;
+        }
+        if let Some(path) = &config.log_file {
+            collected.log_entries += prune_json_log(&logger::json_log_path(path)?, cutoff)?;
+        }
+        collected.log_entries += prune_text_log(&logger::log_path()?, cutoff)?;
+    }
+    if let Some(period) = retention.trash() {
+        collected.trash_dirs = prune_trash(config, cutoff(period))?;
+    }
+    if let Some(period) = retention.checkpoints() {
+        collected.checkpoint = prune_checkpoint(period)?;
+    }
+    Ok(collected)
+}
+
+/// Unix timestamp of `period` ago
+fn cutoff(period: Duration) -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .saturating_sub(period.as_secs())
+}
+
+/// Drop the lines of a log of JSON entries whose `timestamp` is before
+/// `cutoff`, returning how many. Lines without one are kept.
+fn prune_json_log(path: &Path, cutoff: u64) -> Result<usize> {
+    rewrite_log(path, |content| {
+        let kept: Vec<&str> = content
+            .lines()
+            .filter(|line| {
+                serde_json::from_str::<serde_json::Value>(line)
+                    .ok()
+                    .and_then(|entry| entry.get("timestamp")?.as_f64())
+                    .is_none_or(|timestamp| timestamp >= cutoff as f64)
+            })
+            .collect();
+        (content.lines().count() - kept.len(), kept)
+    })
+}
+
+/// Drop the events of workmux's own log from before `cutoff`, returning how
+/// many. Each event starts with an RFC 3339 time in UTC, which sorts as text;
+/// lines that don't (a multi-line message) go with the event before them.
+fn prune_text_log(path: &Path, cutoff: u64) -> Result<usize> {
+    let cutoff = format_timestamp(cutoff).replace(' ', "T");
+    rewrite_log(path, |content| {
+        let mut removed = 0;
+        let mut keep = true;
+        let kept = content
+            .lines()
+            .filter(|line| {
+                if let Some(time) = event_time(line) {
+                    keep = time >= cutoff.as_str();
+                    removed += usize::from(!keep);
+                }
+                keep
+            })
+            .collect();
+        (removed, kept)
+    })
+}
+
+/// The `YYYY-MM-DDTHH:MM:SS` an event's line starts with
+fn event_time(line: &str) -> Option<&str> {
+    let time = line.get(..19)?;
+    let bytes = time.as_bytes();
+    (bytes[4] == b'-' && bytes[10] == b'T' && bytes[..4].iter().all(u8::is_ascii_digit))
+        .then_some(time)
+}
+
+/// Replace the content of the log at `path` with the lines `prune` keeps, if
+/// it removed any. The file is rewritten in place, so processes appending to
+/// it keep doing so.
+fn rewrite_log(path: &Path, prune: impl FnOnce(&str) -> (usize, Vec<&str>)) -> Result<usize> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let (removed, kept) = prune(&content);
+    if removed == 0
+        || dry_run::skip(format_args!(
+            "prune {} ({} entries)",
+            path.display(),
+            removed
+        ))
+    {
+        return Ok(removed);
+    }
+    let mut content = kept.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(removed)
+}
+
+/// Delete the trash directories older than `cutoff` next to the worktrees of
+/// the current repository, returning them
+fn prune_trash(config: &Config, cutoff: u64) -> Result<Vec<PathBuf>> {
+    // Trash is next to the worktrees, which --host puts on the other machine
+    if remote::get().is_some() || !git::is_git_repo()? {
+        return Ok(Vec::new());
+    }
+    let main = git::get_main_worktree_root()?;
+    let mut dirs: BTreeSet<PathBuf> = git::list_worktrees()?
+        .into_iter()
+        .filter(|(path, _)| *path != main)
+        .filter_map(|(path, _)| Some(path.parent()?.to_path_buf()))
+        .collect();
+    // The worktree directory, even when no worktrees are left in it
+    if let Ok(base) = WorkflowContext::new(config.clone()).and_then(|c| c.worktree_base_dir()) {
+        dirs.insert(base);
+    }
+
+    let mut removed = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if trash_time(&path).is_none_or(|time| time >= cutoff) {
+                continue;
+            }
+            if !dry_run::skip(format_args!("rm -rf {}", path.display())) {
+                fs::remove_dir_all(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// When the trash directory at `path` was made, from its name, or None if it
+/// isn't one
+fn trash_time(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?.strip_prefix(TRASH_PREFIX)?;
+    name.rsplit_once('_')?.1.parse().ok()
+}
+
+/// Forget saved progress last written more than `period` ago
+fn prune_checkpoint(period: Duration) -> Result<bool> {
+    let Some(path) = resume::saved_path() else {
+        return Ok(false);
+    };
+    let age = fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if age.is_none_or(|age| age < period) {
+        return Ok(false);
+    }
+    if !dry_run::skip(format_args!("rm -f {}", path.display())) {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prunes_entries_before_the_cutoff() {
+        let dir = std::env::temp_dir().join(format!("workmux-retention-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let json = dir.join("audit.log");
+        fs::write(
+            &json,
+            "{\"timestamp\":100,\"target\":\"a\"}\nnot json\n{\"timestamp\":300.5,\"target\":\"b\"}\n",
+        )
+        .unwrap();
+        assert_eq!(prune_json_log(&json, 200).unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(&json).unwrap(),
+            "not json\n{\"timestamp\":300.5,\"target\":\"b\"}\n"
+        );
+
+        // 1_700_000_000 is 2023-11-14T22:13:20Z
+        let text = dir.join("workmux.log");
+        fs::write(
+            &text,
+            "2023-11-14T22:13:19.5Z  INFO old\ncontinued\n2023-11-14T22:13:20.1Z  INFO new\nmore\n",
+        )
+        .unwrap();
+        assert_eq!(prune_text_log(&text, 1_700_000_000).unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(&text).unwrap(),
+            "2023-11-14T22:13:20.1Z  INFO new\nmore\n"
+        );
+        assert_eq!(prune_text_log(&dir.join("missing.log"), 0).unwrap(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trash_time_reads_the_name() {
+        let time = |name: &str| trash_time(Path::new(name));
+        assert_eq!(
+            time("/w/.workmux_trash_fix_login_1700000000"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(time("/w/fix_login_1700000000"), None);
+        assert_eq!(time("/w/.workmux_trash_fix"), None);
+    }
+}
@@ -1,5 +1,5 @@
 # Dynamic worktree handle completion (directory names)
-# Used for open/remove/merge/path - these accept handles or branch names
+# Used for commands that take worktrees - these accept handles or branch names
 _workmux_handles() {
     workmux _complete-handles 2>/dev/null
 }
@@ -9,6 +9,11 @@ _workmux_git_branches() {
     workmux _complete-git-branches 2>/dev/null
 }
 
+# Local branches, checked out ones included, for add --base and merge --into
+_workmux_local_branches() {
+    workmux _complete-local-branches 2>/dev/null
+}
+
 # Wrapper that adds dynamic completion
 _workmux_dynamic() {
     local cur prev words cword
@@ -28,10 +33,10 @@ _workmux_dynamic() {
     if [[ ${cword} -ge 2 ]]; then
         local cmd="${words[1]}"
         case "$cmd" in
-            merge)
-                # Handle --into flag (takes worktree handle)
-                if [[ "$prev" == "--into" ]]; then
-                    COMPREPLY=($(compgen -W "$(_workmux_handles)" -- "$cur"))
+            @HANDLE_COMMANDS@)
+                # Handle --into flag of merge (takes a branch)
+                if [[ "$cmd" == "merge" && "$prev" == "--into" ]]; then
+                    COMPREPLY=($(compgen -W "$(_workmux_local_branches)" -- "$cur"))
                     return
                 fi
                 # Positional arg: handles
@@ -40,18 +45,11 @@ _workmux_dynamic() {
                     return
                 fi
                 ;;
-            open|remove|rm|path)
-                # Positional arg: handles
-                if [[ "$cur" != -* ]]; then
-                    COMPREPLY=($(compgen -W "$(_workmux_handles)" -- "$cur"))
-                    return
-                fi
-                ;;
             add)
                 # Handle flags that take specific argument types
                 case "$prev" in
-                    --base|-b)
-                        COMPREPLY=($(compgen -W "$(_workmux_git_branches)" -- "$cur"))
+                    --base)
+                        COMPREPLY=($(compgen -W "$(_workmux_local_branches)" -- "$cur"))
                         return
                         ;;
                     --prompt-file|-P)
@@ -1,5 +1,5 @@
 # Dynamic worktree handle completion (directory names)
-# Used for open/remove/merge/path - these accept handles or branch names
+# Used for commands that take worktrees - these accept handles or branch names
 function __workmux_handles
     workmux _complete-handles 2>/dev/null
 end
@@ -9,8 +9,15 @@ function __workmux_git_branches
     workmux _complete-git-branches 2>/dev/null
 end
 
+# Local branches, checked out ones included, for add --base and merge --into
+function __workmux_local_branches
+    workmux _complete-local-branches 2>/dev/null
+end
+
 # Add dynamic completions for commands that take worktree handles or branch names
 # (handles are the primary identifier shown in completions)
-complete -c workmux -n '__fish_seen_subcommand_from open remove rm path merge' -f -a '(__workmux_handles)'
+complete -c workmux -n '__fish_seen_subcommand_from @HANDLE_COMMANDS@' -f -a '(__workmux_handles)'
 # Add dynamic completions for add command (uses git branches)
 complete -c workmux -n '__fish_seen_subcommand_from add' -f -a '(__workmux_git_branches)'
+complete -c workmux -n '__fish_seen_subcommand_from add' -l base -f -a '(__workmux_local_branches)'
+complete -c workmux -n '__fish_seen_subcommand_from merge' -l into -f -a '(__workmux_local_branches)'
@@ -0,0 +1,32 @@
+#!/bin/sh
+# Give a review worktree its own hooks directory, whose pre-commit hook
+# refuses commits on a detached HEAD. The repository's hooks are linked in
+# beside it, and its own pre-commit runs once a branch is checked out.
+# Prints the directory, for core.hooksPath.
+set -eu
+
+hooks=$(git rev-parse --path-format=absolute --git-path hooks)
+ours="$(git rev-parse --path-format=absolute --git-dir)/workmux-hooks"
+mkdir -p "$ours"
+
+for hook in "$hooks"/*; do
+  name=${hook##*/}
+  if [ -f "$hook" ] && [ "$name" != pre-commit ]; then
+    ln -sf "$hook" "$ours/$name"
+  fi
+done
+
+cat >"$ours/pre-commit" <<EOF
+#!/bin/sh
+if ! git symbolic-ref -q HEAD >/dev/null; then
+  echo "This worktree is for reviewing a pull request, and commits on its detached HEAD would be lost." >&2
+  echo "Check out a branch to commit on first: git switch -c <name>" >&2
+  exit 1
+fi
+if [ -x '$hooks/pre-commit' ]; then
+  exec '$hooks/pre-commit' "\$@"
+fi
+EOF
+chmod +x "$ours/pre-commit"
+
+printf '%s' "$ours"
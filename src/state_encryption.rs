@@ -0,0 +1,359 @@
+//! Encryption of the sensitive parts of worktree state with `age`.
+//!
+//! With `state_encryption.recipients` set, the prompt and the captured agent
+//! output in `.git/workmux/<handle>.json` are written as ASCII-armored age
+//! ciphertext. Writing only needs the recipients; reading needs the identity,
+//! which `workmux state unlock` reads from a key file or the system keyring
+//! and keeps in `$XDG_RUNTIME_DIR` until `workmux state lock` (or a reboot,
+//! as that directory is a tmpfs). Without `$XDG_RUNTIME_DIR`, as on macOS, it
+//! is kept in a directory of the user's in the temporary directory instead.
+
+use anyhow::{Context, Result, anyhow};
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+use crate::config::{Config, StateEncryptionConfig};
+use crate::dry_run;
+
+/// First line of age's ASCII armor
+const ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Shown in place of a value that can't be decrypted
+const LOCKED_PLACEHOLDER: &str = "(encrypted; run 'workmux state unlock' to show)";
+
+/// Service and account the identity is stored under in the system keyring
+const KEYRING_SERVICE: &str = "workmux";
+const KEYRING_ACCOUNT: &str = "state-identity";
+
+const SESSION_FILE: &str = "state-identity";
+
+/// Whether `value` is age ciphertext
+pub fn is_encrypted(value: &str) -> bool {
+    value.trim_start().starts_with(ARMOR_HEADER)
+}
+
+/// `value` encrypted to the configured recipients, or unchanged when
+/// encryption is off or it already is encrypted
+pub fn encrypt(value: &str, config: &StateEncryptionConfig) -> Result<String> {
+    let recipients = config.recipients();
+    if recipients.is_empty() || is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let mut args = vec!["--armor".to_string()];
+    for recipient in recipients {
+        args.push("--recipient".to_string());
+        args.push(recipient.clone());
+    }
+    age(&args, value.as_bytes()).context("Failed to encrypt worktree state")
+}
+
+/// Encryption settings of `config`, if it turns encryption on
+pub fn settings(config: &Config) -> Option<&StateEncryptionConfig> {
+    config
+        .state_encryption
+        .as_ref()
+        .filter(|config| !config.recipients().is_empty())
+}
+
+/// `value` in plain text: decrypted with the unlocked identity if it is
+/// encrypted, or a placeholder while the state is locked
+pub fn reveal(value: &str) -> Cow<'_, str> {
+    if !is_encrypted(value) {
+        return Cow::Borrowed(value);
+    }
+    match decrypt(value) {
+        Ok(plain) => Cow::Owned(plain),
+        Err(e) => {
+            debug!(error = %e, "state_encryption:cannot decrypt");
+            Cow::Borrowed(LOCKED_PLACEHOLDER)
+        }
+    }
+}
+
+/// `value` in plain text, decrypted if it is encrypted, failing while the
+/// state is locked
+pub fn decrypted(value: &str) -> Result<String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    decrypt(value).context("Run 'workmux state unlock' to decrypt it")
+}
+
+fn decrypt(value: &str) -> Result<String> {
+    let identity = session_file();
+    if !identity.exists() {
+        return Err(anyhow!("the state is locked"));
+    }
+    let identity = identity.to_string_lossy().to_string();
+    age(
+        &["--decrypt".into(), "--identity".into(), identity],
+        value.as_bytes(),
+    )
+}
+
+/// Run `age` with `input` on stdin, returning its output
+fn age(args: &[String], input: &[u8]) -> Result<String> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run 'age'. Is it installed? (https://age-encryption.org)")?;
+    // Written from a thread, as age may fill its stdout before it has read
+    // all of its input
+    let stdin = child.stdin.take();
+    let (written, output) = std::thread::scope(|scope| {
+        let writer = stdin.map(|mut stdin| scope.spawn(move || stdin.write_all(input)));
+        let output = child.wait_with_output();
+        let written = writer.map_or(Ok(()), |writer| {
+            writer
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("writer panicked")))
+        });
+        (written, output)
+    });
+    let output = output.context("Failed to wait for 'age'")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "age failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written.context("Failed to write to 'age'")?;
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Read the identity from where the config says and keep it for this
+/// session, so encrypted state can be shown
+pub fn unlock(config: &StateEncryptionConfig) -> Result<PathBuf> {
+    let source = config.identity.as_deref().ok_or_else(|| {
+        anyhow!("Set state_encryption.identity to a key file or 'keyring' to unlock")
+    })?;
+    let identity = if source == "keyring" {
+        read_keyring()?
+    } else {
+        read_identity_file(source)?
+    };
+    if !identity.contains("AGE-SECRET-KEY-") && !identity.contains("PRIVATE KEY") {
+        return Err(anyhow!("{} does not hold an age or SSH identity", source));
+    }
+
+    let path = session_file();
+    if dry_run::skip(format_args!("write {}", path.display())) {
+        return Ok(path);
+    }
+    let dir = path.parent().unwrap_or(&path);
+    create_private_dir(dir)?;
+    write_private(&path, &identity)?;
+    Ok(path)
+}
+
+/// Forget the unlocked identity. Returns whether there was one.
+pub fn lock() -> Result<bool> {
+    let path = session_file();
+    if !path.exists() {
+        return Ok(false);
+    }
+    if dry_run::skip(format_args!("rm -f {}", path.display())) {
+        return Ok(true);
+    }
+    fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(true)
+}
+
+pub fn is_unlocked() -> bool {
+    session_file().exists()
+}
+
+/// A key file, decrypted first if it is passphrase-protected, in which case
+/// age asks for the passphrase on the terminal
+fn read_identity_file(source: &str) -> Result<String> {
+    let path = match source.strip_prefix("~/") {
+        Some(rest) => home::home_dir()
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(rest),
+        None => PathBuf::from(source),
+    };
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read identity {}", path.display()))?;
+    if !is_encrypted(&content) {
+        return Ok(content);
+    }
+    let output = Command::new("age")
+        .arg("--decrypt")
+        .arg(&path)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run 'age'. Is it installed? (https://age-encryption.org)")?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to decrypt identity {}", path.display()));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The identity stored in the macOS keychain or the Secret Service, which
+/// are on this machine even with --host
+fn read_keyring() -> Result<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args(["find-generic-password", "-s", KEYRING_SERVICE])
+            .args(["-a", KEYRING_ACCOUNT, "-w"])
+            .output()
+            .context("Failed to run 'security'")?
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", KEYRING_SERVICE])
+            .args(["account", KEYRING_ACCOUNT])
+            .output()
+            .context("Failed to run 'secret-tool'. Is libsecret-tools installed?")?
+    };
+    let identity = String::from_utf8(output.stdout)?;
+    if !output.status.success() || identity.trim().is_empty() {
+        return Err(anyhow!(
+            "No identity in the keyring under service '{}', account '{}'",
+            KEYRING_SERVICE,
+            KEYRING_ACCOUNT
+        ));
+    }
+    Ok(identity)
+}
+
+/// Where the unlocked identity is kept
+fn session_file() -> PathBuf {
+    session_file_in(std::env::var_os("XDG_RUNTIME_DIR"))
+}
+
+/// The user's runtime directory `runtime_dir`, which is private and cleared
+/// on logout or reboot, or else a private directory of the user's in the
+/// temporary directory
+fn session_file_in(runtime_dir: Option<OsString>) -> PathBuf {
+    let dir = match runtime_dir.filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("workmux"),
+        None => std::env::temp_dir().join(format!("workmux-{}", user_id())),
+    };
+    dir.join(SESSION_FILE)
+}
+
+#[cfg(unix)]
+fn user_id() -> String {
+    unsafe { libc::getuid() }.to_string()
+}
+
+#[cfg(not(unix))]
+fn user_id() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
+
+/// Create `dir` readable by the user only. One that exists already must be
+/// theirs, as a shared temporary directory lets anyone create it first.
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let meta =
+        fs::symlink_metadata(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    if !meta.is_dir() || meta.uid() != unsafe { libc::getuid() } {
+        return Err(anyhow!(
+            "{} is not a directory of yours, so the identity can't be kept there",
+            dir.display()
+        ));
+    }
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+        .with_context(|| format!("Failed to restrict {}", dir.display()))
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_values_are_left_alone() {
+        let armored =
+            "-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----\n";
+        assert!(is_encrypted(armored));
+        assert!(!is_encrypted("Fix the login page"));
+
+        // Without recipients nothing is encrypted, and age isn't needed
+        let off = StateEncryptionConfig::default();
+        let config = Config {
+            state_encryption: Some(off.clone()),
+            ..Config::default()
+        };
+        assert!(settings(&config).is_none());
+        assert_eq!(
+            encrypt("Fix the login page", &off).unwrap(),
+            "Fix the login page"
+        );
+        assert_eq!(reveal("Fix the login page"), "Fix the login page");
+    }
+
+    #[test]
+    fn identity_is_kept_privately_without_a_runtime_dir() {
+        let runtime = tempfile::tempdir().unwrap();
+        assert_eq!(
+            session_file_in(Some(runtime.path().into())),
+            runtime.path().join("workmux").join(SESSION_FILE)
+        );
+
+        // As on macOS, where XDG_RUNTIME_DIR isn't set
+        let fallback = session_file_in(None);
+        assert_eq!(fallback, session_file_in(Some(OsString::new())));
+        let dir = fallback.parent().unwrap();
+        assert_eq!(dir.parent(), Some(std::env::temp_dir().as_path()));
+        assert_eq!(
+            dir.file_name().unwrap().to_string_lossy(),
+            format!("workmux-{}", user_id())
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let private = runtime.path().join("workmux-private");
+            fs::create_dir(&private).unwrap();
+            fs::set_permissions(&private, fs::Permissions::from_mode(0o755)).unwrap();
+            create_private_dir(&private).unwrap();
+            let mode = fs::metadata(&private).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+
+            // A link planted in its place is refused
+            let planted = runtime.path().join("workmux-planted");
+            std::os::unix::fs::symlink(&private, &planted).unwrap();
+            assert!(create_private_dir(&planted).is_err());
+        }
+    }
+}
@@ -5,7 +5,14 @@ use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
 /// Reserved template variable names that cannot be used in foreach
-const RESERVED_TEMPLATE_KEYS: &[&str] = &["base_name", "agent", "num", "index", "foreach_vars"];
+const RESERVED_TEMPLATE_KEYS: &[&str] =
+    &["base_name", "agent", "num", "index", "foreach_vars", "role"];
+
+/// Variable name exposed to templates for the PR's context with `--with-context`
+pub const PR_CONTEXT_VAR: &str = "pr_context";
+
+/// Variable name exposed to templates for the branch a PR merges into, with `--review`
+pub const PR_BASE_VAR: &str = "pr_base";
 
 #[derive(Debug, Clone)]
 pub struct WorktreeSpec {
@@ -31,40 +38,122 @@ pub fn render_prompt_body(body: &str, env: &TemplateEnv, context: &JsonValue) ->
         .context("Failed to render prompt template")
 }
 
-/// Validate that all variables used in the template exist in the provided context.
-/// Returns an error listing missing variables and available ones if validation fails.
-pub fn validate_template_variables(
+/// Variables workmux itself defines for pane and hook commands. A `{{ }}`
+/// using one of them is always rendered, and fails when the worktree
+/// doesn't have it (such as `num` without `--count`).
+const COMMAND_VARIABLES: &[&str] = &[
+    "branch",
+    "handle",
+    "worktree_path",
+    "project_root",
+    "target_branch",
+    "base_name",
+    "agent",
+    "num",
+    "index",
+    "foreach_vars",
+    "role",
+    PR_BASE_VAR,
+    PR_CONTEXT_VAR,
+];
+
+/// Render the workmux variables in a pane or hook command with `context`.
+/// Only `{{ }}` expressions that use workmux variables, or ones `context`
+/// defines, are rendered; others, such as `docker ps --format '{{.Names}}'`,
+/// are left for the command itself, as is shell syntax like `${#files[@]}`.
+pub fn render_command(command: &str, context: &JsonValue) -> Result<String> {
+    if !command.contains("{{") {
+        return Ok(command.to_string());
+    }
+    let env = create_template_env();
+    let mut rendered = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let end = start + len + 2;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(
+            &render_expression(&env, &rest[start..end], context)
+                .with_context(|| format!("Failed to render command '{}'", command))?,
+        );
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Render a path from config with `context`. Unlike commands, paths have
+/// no templates of their own, so every variable they use must be defined.
+pub fn render_path(path: &str, context: &JsonValue) -> Result<String> {
+    if !path.contains("{{") {
+        return Ok(path.to_string());
+    }
+    let env = create_template_env();
+    validate_template_variables(&env, path, context)
+        .with_context(|| format!("Failed to render path '{}'", path))?;
+    env.render_str(path, context)
+        .with_context(|| format!("Failed to render path '{}'", path))
+}
+
+/// Render one `{{ }}` expression of a command, or return it as it is if it
+/// isn't about workmux variables
+fn render_expression(env: &TemplateEnv, expression: &str, context: &JsonValue) -> Result<String> {
+    let Ok(tmpl) = env.template_from_str(expression) else {
+        return Ok(expression.to_string());
+    };
+    let available = context_keys(context);
+    let variables = tmpl.undeclared_variables(false);
+    let ours = !variables.is_empty()
+        && variables.iter().all(|var| {
+            available.contains(var.as_str()) || COMMAND_VARIABLES.contains(&var.as_str())
+        });
+    if !ours {
+        return Ok(expression.to_string());
+    }
+    validate_template_variables(env, expression, context)?;
+    env.render_str(expression, context)
+        .context("Failed to render template")
+}
+
+/// Variables used in the template that the provided context doesn't define, sorted.
+pub fn missing_template_variables(
     env: &TemplateEnv,
     template_str: &str,
     context: &JsonValue,
-) -> Result<()> {
+) -> Result<Vec<String>> {
     let tmpl = env
         .template_from_str(template_str)
         .context("Failed to parse template syntax")?;
 
-    let required_vars = tmpl.undeclared_variables(true);
+    let available_vars = context_keys(context);
+    let mut missing_vars: Vec<String> = tmpl
+        .undeclared_variables(true)
+        .into_iter()
+        .filter(|req| !available_vars.contains(req.as_str()))
+        .collect();
+    missing_vars.sort();
 
-    let available_vars: HashSet<&str> = match context {
-        JsonValue::Object(map) => map.keys().map(|k| k.as_str()).collect(),
-        _ => HashSet::new(),
-    };
+    Ok(missing_vars)
+}
 
-    let missing_vars: Vec<&str> = required_vars
-        .iter()
-        .map(|s| s.as_str())
-        .filter(|req| !available_vars.contains(req))
-        .collect();
+/// Validate that all variables used in the template exist in the provided context.
+/// Returns an error listing missing variables and available ones if validation fails.
+pub fn validate_template_variables(
+    env: &TemplateEnv,
+    template_str: &str,
+    context: &JsonValue,
+) -> Result<()> {
+    let missing_vars = missing_template_variables(env, template_str, context)?;
 
     if !missing_vars.is_empty() {
-        let mut missing_sorted = missing_vars.clone();
-        missing_sorted.sort();
-
-        let mut available_sorted: Vec<&str> = available_vars.into_iter().collect();
+        let mut available_sorted: Vec<&str> = context_keys(context).into_iter().collect();
         available_sorted.sort();
 
         return Err(anyhow!(
             "Template uses undefined variables: {}\nAvailable variables: {}",
-            missing_sorted.join(", "),
+            missing_vars.join(", "),
             available_sorted.join(", ")
         ));
     }
@@ -72,6 +161,42 @@ pub fn validate_template_variables(
     Ok(())
 }
 
+/// Add user-supplied variables to a template context. Variables the context
+/// already defines keep their value.
+pub fn add_template_variables(context: &mut JsonValue, vars: &BTreeMap<String, String>) {
+    if let JsonValue::Object(map) = context {
+        for (key, value) in vars {
+            map.entry(key.clone())
+                .or_insert_with(|| JsonValue::String(value.clone()));
+        }
+    }
+}
+
+/// Parse a `--var name=value` argument.
+pub fn parse_template_var(input: &str) -> Result<(String, String), String> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("invalid variable '{}': use the format name=value", input))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!(
+            "invalid variable '{}': name cannot be empty",
+            input
+        ));
+    }
+    if RESERVED_TEMPLATE_KEYS.contains(&key) {
+        return Err(format!("'{}' is a reserved template variable", key));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn context_keys(context: &JsonValue) -> HashSet<&str> {
+    match context {
+        JsonValue::Object(map) => map.keys().map(|k| k.as_str()).collect(),
+        _ => HashSet::new(),
+    }
+}
+
 pub fn generate_worktree_specs(
     base_name: &str,
     agents: &[String],
@@ -204,6 +329,20 @@ fn agent_display_name(agent: &str) -> String {
         .to_string()
 }
 
+/// Extend a spec's template context for one member of an agent team, setting
+/// `role` and replacing `agent` with the member's agent.
+pub fn team_member_context(context: &JsonValue, role: &str, agent: &str) -> JsonValue {
+    let mut context = context.clone();
+    if let JsonValue::Object(map) = &mut context {
+        map.insert("role".to_string(), JsonValue::String(role.to_string()));
+        map.insert(
+            "agent".to_string(),
+            JsonValue::String(agent_display_name(agent)),
+        );
+    }
+    context
+}
+
 fn build_template_context(
     base_name: &str,
     agent: &Option<String>,
@@ -465,6 +604,23 @@ mod tests {
         assert_eq!(specs[1].template_context["index"], 2);
     }
 
+    #[test]
+    fn team_member_context_sets_role_and_agent() {
+        let env = create_test_env();
+        let specs = generate_worktree_specs("auth", &[], None, None, &env, "{{ base_name }}")
+            .expect("specs");
+
+        let context =
+            team_member_context(&specs[0].template_context, "reviewer", "/usr/bin/gemini");
+        let rendered = render_prompt_body(
+            "{{ base_name }}: {% if role == 'reviewer' %}review{% else %}build{% endif %} ({{ agent }})",
+            &env,
+            &context,
+        )
+        .expect("render");
+        assert_eq!(rendered, "auth: review (gemini)");
+    }
+
     #[test]
     fn render_prompt_template_inline_renders_variables() {
         let env = create_test_env();
@@ -698,6 +854,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn supplied_variables_fill_in_missing_ones() {
+        let env = create_test_env();
+        let mut map = JsonMap::new();
+        map.insert("ticket".to_string(), JsonValue::String("foo".to_string()));
+        let mut context = JsonValue::Object(map);
+        let body = "Fix {{ ticket }} in {{ service }} ({{ priority }})";
+
+        assert_eq!(
+            missing_template_variables(&env, body, &context).unwrap(),
+            vec!["priority", "service"]
+        );
+
+        let vars = BTreeMap::from([
+            ("service".to_string(), "billing".to_string()),
+            ("ticket".to_string(), "ignored".to_string()),
+        ]);
+        add_template_variables(&mut context, &vars);
+        assert_eq!(
+            missing_template_variables(&env, body, &context).unwrap(),
+            vec!["priority"]
+        );
+        assert_eq!(context["ticket"], "foo");
+        assert_eq!(context["service"], "billing");
+    }
+
+    #[test]
+    fn parse_template_var_splits_on_first_equals() {
+        assert_eq!(
+            parse_template_var("query=a=b").unwrap(),
+            ("query".to_string(), "a=b".to_string())
+        );
+        assert_eq!(
+            parse_template_var("empty=").unwrap(),
+            ("empty".to_string(), String::new())
+        );
+        assert!(parse_template_var("novalue").is_err());
+        assert!(parse_template_var("=x").is_err());
+        assert!(parse_template_var("agent=x").is_err());
+    }
+
     #[test]
     fn validate_template_variables_handles_filters() {
         let env = create_test_env();
@@ -801,6 +998,47 @@ mod tests {
         assert!(err_msg.contains("missing2"));
     }
 
+    #[test]
+    fn render_command_only_renders_commands_with_variables() {
+        let context = serde_json::json!({"handle": "login", "num": 2});
+        assert_eq!(
+            render_command("echo ${#files[@]} {#x}", &context).unwrap(),
+            "echo ${#files[@]} {#x}"
+        );
+        assert_eq!(
+            render_command(
+                "npm run dev -- --port 300{{ num }} # {{ handle }}",
+                &context
+            )
+            .unwrap(),
+            "npm run dev -- --port 3002 # login"
+        );
+        let err = render_command("echo {{ index }}", &context).unwrap_err();
+        assert!(format!("{:#}", err).contains("index"));
+    }
+
+    #[test]
+    fn render_command_leaves_other_templates_to_the_command() {
+        let context = serde_json::json!({"handle": "login", "region": "eu"});
+        assert_eq!(
+            render_command(
+                "docker ps --filter name={{ handle }} --format '{{.Names}} {{ .Status }}'",
+                &context
+            )
+            .unwrap(),
+            "docker ps --filter name=login --format '{{.Names}} {{ .Status }}'"
+        );
+        // Only `--var` values and workmux's own variables are filled in
+        assert_eq!(
+            render_command("helm --set r={{ region }},z={{ zone }} {{ x", &context).unwrap(),
+            "helm --set r=eu,z={{ zone }} {{ x"
+        );
+        assert_eq!(
+            render_command("{{ handle | upper }}-{{ \"{{\" }}", &context).unwrap(),
+            "LOGIN-{{ \"{{\" }}"
+        );
+    }
+
     // Helper function for tests
     fn render_prompt_template(
         prompt: &Prompt,
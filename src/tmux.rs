@@ -2,29 +2,75 @@ use anyhow::{Context, Result, anyhow};
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, trace, warn};
 
-use crate::cmd::Cmd;
-use crate::config::{PaneConfig, SplitDirection};
+use crate::cmd::{self, Cmd};
+use crate::config::{PaneConfig, SplitDirection, TmuxLayout};
+use crate::{dry_run, remote};
+
+/// Whether each worktree opens in a session of its own (`tmux.layout:
+/// sessions`). The session and its first window share the name a window
+/// would have, so lookups by name work the same in both layouts.
+static SESSIONS: AtomicBool = AtomicBool::new(false);
+
+/// Use `layout` for the rest of the process
+pub fn set_layout(layout: TmuxLayout) {
+    SESSIONS.store(layout == TmuxLayout::Sessions, Ordering::Relaxed);
+}
+
+/// Whether worktrees open as sessions rather than windows
+pub fn uses_sessions() -> bool {
+    SESSIONS.load(Ordering::Relaxed)
+}
+
+/// Target for the worktree window named `full_name`: the window in the
+/// current session, or the current window of the session of that name
+pub fn window_target(full_name: &str) -> String {
+    if uses_sessions() {
+        format!("={}:", full_name)
+    } else {
+        format!("={}", full_name)
+    }
+}
 
 /// Helper function to add prefix to window name
 pub fn prefixed(prefix: &str, window_name: &str) -> String {
     format!("{}{}", prefix, window_name)
 }
 
-/// Get all tmux window names in a single call
+/// Get all tmux window names in a single call, or the session names when
+/// worktrees have sessions of their own
 pub fn get_all_window_names() -> Result<HashSet<String>> {
     // tmux list-windows may exit with error if no windows exist
-    let windows = Cmd::new("tmux")
-        .args(&["list-windows", "-F", "#{window_name}"])
-        .run_and_capture_stdout()
-        .unwrap_or_default(); // Return empty string if command fails
+    let windows = list_names().unwrap_or_default();
 
     Ok(windows.lines().map(String::from).collect())
 }
 
+/// Names of the windows, or of the sessions, worktrees open in. A session
+/// stays the worktree's even if its first window is renamed.
+fn list_names() -> Result<String> {
+    if uses_sessions() {
+        return Cmd::new("tmux")
+            .args(&["list-sessions", "-F", "#{session_name}"])
+            .run_and_capture_stdout();
+    }
+    list_windows("#{window_name}")
+}
+
+/// `list-windows` with `format`: the current session's windows, or those of
+/// every session when worktrees have sessions of their own
+fn list_windows(format: &str) -> Result<String> {
+    let mut cmd = Cmd::new("tmux").arg("list-windows");
+    if uses_sessions() {
+        cmd = cmd.arg("-a");
+    }
+    cmd.args(&["-F", format]).run_and_capture_stdout()
+}
+
 /// Filter a list of window names, returning only those that still exist.
 /// Used by the worker pool to track which windows are still active.
 pub fn filter_active_windows(windows: &[String]) -> Result<Vec<String>> {
@@ -46,6 +92,10 @@ pub fn is_running() -> Result<bool> {
 /// Returns the window ID (e.g. @1) to be used as a target for inserting new windows.
 /// Uses window IDs rather than names for stability.
 pub fn find_last_window_with_prefix(prefix: &str) -> Result<Option<String>> {
+    // Sessions have nothing to group with
+    if uses_sessions() {
+        return Ok(None);
+    }
     // tmux list-windows outputs in index order, so the last match is the highest index.
     let output = Cmd::new("tmux")
         .args(&["list-windows", "-F", "#{window_id} #{window_name}"])
@@ -66,6 +116,45 @@ pub fn find_last_window_with_prefix(prefix: &str) -> Result<Option<String>> {
     Ok(last_match)
 }
 
+/// Find the window before or after `full_name` (by index, wrapping around).
+/// Returns the window ID (e.g. @3), or None if there is no other window.
+/// With a session per worktree, the session before or after (e.g. $3).
+pub fn find_adjacent_window(full_name: &str, forward: bool) -> Result<Option<String>> {
+    let args: &[&str] = if uses_sessions() {
+        &["list-sessions", "-F", "#{session_id} #{session_name}"]
+    } else {
+        &["list-windows", "-F", "#{window_id} #{window_name}"]
+    };
+    let output = Cmd::new("tmux")
+        .args(args)
+        .run_and_capture_stdout()
+        .unwrap_or_default();
+
+    Ok(adjacent_window_id(&output, full_name, forward))
+}
+
+/// Pick the neighbour of `full_name` from `list-windows` output ("@id name" per line)
+fn adjacent_window_id(output: &str, full_name: &str, forward: bool) -> Option<String> {
+    let windows: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .collect();
+
+    if windows.len() < 2 {
+        return None;
+    }
+
+    let pos = windows.iter().position(|(_, name)| *name == full_name)?;
+    let len = windows.len();
+    let adjacent = if forward {
+        (pos + 1) % len
+    } else {
+        (pos + len - 1) % len
+    };
+
+    Some(windows[adjacent].0.to_string())
+}
+
 /// Check if a tmux window with the given name exists
 pub fn window_exists(prefix: &str, window_name: &str) -> Result<bool> {
     let prefixed_name = prefixed(prefix, window_name);
@@ -74,9 +163,7 @@ pub fn window_exists(prefix: &str, window_name: &str) -> Result<bool> {
 
 /// Check if a window exists by its full name (including prefix)
 pub fn window_exists_by_full_name(full_name: &str) -> Result<bool> {
-    let windows = Cmd::new("tmux")
-        .args(&["list-windows", "-F", "#{window_name}"])
-        .run_and_capture_stdout();
+    let windows = list_names();
 
     match windows {
         Ok(output) => Ok(output.lines().any(|line| line == full_name)),
@@ -84,10 +171,37 @@ pub fn window_exists_by_full_name(full_name: &str) -> Result<bool> {
     }
 }
 
-/// Return the tmux window name for the current pane, if any
+/// Get the ID of the active pane in a window
+pub fn active_pane_id(prefix: &str, window_name: &str) -> Result<String> {
+    let target = window_target(&prefixed(prefix, window_name));
+    let output = Cmd::new("tmux")
+        .args(&[
+            "list-panes",
+            "-t",
+            &target,
+            "-F",
+            "#{pane_active} #{pane_id}",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to list panes")?;
+
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("1 "))
+        .map(|id| id.to_string())
+        .ok_or_else(|| anyhow!("No active pane in window '{}'", target))
+}
+
+/// Return the tmux window name for the current pane, if any, or its session
+/// name when worktrees have sessions of their own
 pub fn current_window_name() -> Result<Option<String>> {
+    let format = if uses_sessions() {
+        "#{session_name}"
+    } else {
+        "#{window_name}"
+    };
     match Cmd::new("tmux")
-        .args(&["display-message", "-p", "#{window_name}"])
+        .args(&["display-message", "-p", format])
         .run_and_capture_stdout()
     {
         Ok(name) => Ok(Some(name.trim().to_string())),
@@ -95,6 +209,116 @@ pub fn current_window_name() -> Result<Option<String>> {
     }
 }
 
+/// ID of the pane this process runs in, if inside tmux
+pub fn current_pane_id() -> Option<String> {
+    std::env::var("TMUX_PANE").ok().filter(|id| !id.is_empty())
+}
+
+/// IDs and foreground commands of every pane in the window containing `pane_id`
+pub fn window_panes(pane_id: &str) -> Result<Vec<(String, String)>> {
+    let output = Cmd::new("tmux")
+        .args(&[
+            "list-panes",
+            "-t",
+            pane_id,
+            "-F",
+            "#{pane_id}\t#{pane_current_command}",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to list panes")?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(id, command)| (id.to_string(), command.to_string()))
+        .collect())
+}
+
+/// Pane option marking the panes workmux started an agent in
+const AGENT_PANE_OPTION: &str = "@workmux_agent";
+
+/// Panes of the window `target` that run an agent: those workmux started
+/// one in, or, in windows set up before it marked them, those whose
+/// foreground command is `agent_command`
+pub fn agent_panes(target: &str, agent_command: Option<&str>) -> Result<Vec<String>> {
+    let format = format!(
+        "#{{pane_id}}\t#{{{}}}\t#{{pane_current_command}}",
+        AGENT_PANE_OPTION
+    );
+    let output = Cmd::new("tmux")
+        .args(&["list-panes", "-t", target, "-F", &format])
+        .run_and_capture_stdout()
+        .context("Failed to list panes")?;
+    Ok(parse_agent_panes(&output, agent_command))
+}
+
+fn parse_agent_panes(output: &str, agent_command: Option<&str>) -> Vec<String> {
+    let panes: Vec<(&str, bool, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some((
+                fields.next()?,
+                fields.next()? == "1",
+                fields.next().unwrap_or_default(),
+            ))
+        })
+        .collect();
+    let marked = panes.iter().any(|(_, marked, _)| *marked);
+    panes
+        .into_iter()
+        .filter(|(_, is_marked, command)| {
+            if marked {
+                *is_marked
+            } else {
+                agent_command.is_some_and(|agent| {
+                    !command.is_empty() && crate::config::is_agent_command(command, agent)
+                })
+            }
+        })
+        .map(|(id, _, _)| id.to_string())
+        .collect()
+}
+
+/// Record whether the pane runs an agent, for [`agent_panes`]
+fn mark_agent_pane(pane_id: &str, is_agent: bool) -> Result<()> {
+    let args: &[&str] = if is_agent {
+        &["set-option", "-p", "-t", pane_id, AGENT_PANE_OPTION, "1"]
+    } else {
+        &["set-option", "-up", "-t", pane_id, AGENT_PANE_OPTION]
+    };
+    Cmd::new("tmux")
+        .args(args)
+        .run()
+        .context("Failed to mark the agent pane")?;
+    Ok(())
+}
+
+/// Rename the window containing `pane_id`, and its session too when each
+/// worktree has a session of its own
+pub fn rename_window(pane_id: &str, prefix: &str, window_name: &str) -> Result<()> {
+    let name = prefixed(prefix, window_name);
+    Cmd::new("tmux")
+        .args(&["rename-window", "-t", pane_id, &name])
+        .run()
+        .context("Failed to rename tmux window")?;
+    if uses_sessions() {
+        Cmd::new("tmux")
+            .args(&["rename-session", "-t", pane_id, &name])
+            .run()
+            .context("Failed to rename tmux session")?;
+    }
+    Ok(())
+}
+
+/// ID of the window (e.g. `@3`) that currently contains the pane
+pub fn pane_window_id(pane_id: &str) -> Result<String> {
+    Cmd::new("tmux")
+        .args(&["display-message", "-p", "-t", pane_id, "#{window_id}"])
+        .run_and_capture_stdout()
+        .context("Failed to get the pane's window")
+}
+
 /// Get the current foreground command for a pane
 pub fn get_pane_current_command(pane_id: &str) -> Result<String> {
     let output = Cmd::new("tmux")
@@ -225,12 +449,139 @@ fn clear_pane_status(pane_id: &str) {
         .run();
 }
 
+/// A workmux-managed tmux window, as shown in `workmux menu`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkmuxWindow {
+    /// Window ID (e.g., @3), stable across renames
+    pub window_id: String,
+    /// Window name without the prefix
+    pub handle: String,
+    /// Working directory of the window's active pane
+    pub path: PathBuf,
+    /// Window status icon (if set)
+    pub status: Option<String>,
+    /// Pane with an agent status set, if any
+    pub agent_pane: Option<String>,
+}
+
+/// List windows whose name starts with `prefix`, across all sessions.
+pub fn list_workmux_windows(prefix: &str) -> Result<Vec<WorkmuxWindow>> {
+    // Some tmux versions replace tabs in formats with underscores, so use `|`
+    // and put the path, the only field that may contain one, last
+    let format = "#{window_id}|#{pane_id}|#{pane_active}|#{@workmux_status}|#{@workmux_pane_status}|#{window_name}|#{pane_current_path}";
+    let output = Cmd::new("tmux")
+        .args(&["list-panes", "-a", "-F", format])
+        .run_and_capture_stdout()
+        .context("Failed to list tmux panes")?;
+
+    let mut windows: Vec<WorkmuxWindow> = Vec::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.splitn(7, '|').collect();
+        let [
+            window_id,
+            pane_id,
+            pane_active,
+            window_status,
+            pane_status,
+            window_name,
+            path,
+        ] = parts[..]
+        else {
+            continue;
+        };
+        let Some(handle) = window_name.strip_prefix(prefix) else {
+            continue;
+        };
+
+        // Panes of a window are listed together, so only the last entry can match
+        let window = match windows.last_mut() {
+            Some(window) if window.window_id == window_id => window,
+            _ => {
+                windows.push(WorkmuxWindow {
+                    window_id: window_id.to_string(),
+                    handle: handle.to_string(),
+                    path: PathBuf::from(path),
+                    status: (!window_status.is_empty()).then(|| window_status.to_string()),
+                    agent_pane: None,
+                });
+                windows.last_mut().unwrap()
+            }
+        };
+        if pane_active == "1" {
+            window.path = PathBuf::from(path);
+        }
+        if !pane_status.is_empty() && window.agent_pane.is_none() {
+            window.agent_pane = Some(pane_id.to_string());
+        }
+    }
+
+    Ok(windows)
+}
+
+/// An entry in a tmux `display-menu`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuItem {
+    /// A selectable entry running a tmux command
+    Action {
+        label: String,
+        key: String,
+        command: String,
+    },
+    /// A greyed-out line that can't be selected
+    Disabled(String),
+    Separator,
+}
+
+/// Arguments for `display-menu`, after the command name itself
+fn menu_args(title: &str, items: &[MenuItem]) -> Vec<String> {
+    let mut args = vec!["-T".to_string(), title.to_string()];
+    for item in items {
+        match item {
+            MenuItem::Action {
+                label,
+                key,
+                command,
+            } => args.extend([label.clone(), key.clone(), command.clone()]),
+            MenuItem::Disabled(label) => {
+                args.extend([format!("-{}", label), String::new(), String::new()])
+            }
+            MenuItem::Separator => args.push(String::new()),
+        }
+    }
+    args
+}
+
+/// A `display-menu` command line, for opening a menu from another menu's item
+pub fn menu_command(title: &str, items: &[MenuItem]) -> String {
+    std::iter::once("display-menu".to_string())
+        .chain(
+            menu_args(title, items)
+                .iter()
+                .map(|arg| cmd::shell_quote(arg)),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Show a menu on the current tmux client
+pub fn display_menu(title: &str, items: &[MenuItem]) -> Result<()> {
+    let args = menu_args(title, items);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Cmd::new("tmux")
+        .arg("display-menu")
+        .args(&args)
+        .run()
+        .context("Failed to display tmux menu")?;
+    Ok(())
+}
+
 /// Switch the tmux client to a specific pane
 pub fn switch_to_pane(pane_id: &str) -> Result<()> {
     Cmd::new("tmux")
         .args(&["switch-client", "-t", pane_id])
         .run()
         .context("Failed to switch to pane")?;
+    remote::request_attach();
     Ok(())
 }
 
@@ -274,6 +625,10 @@ pub fn create_window(
         .to_str()
         .ok_or_else(|| anyhow!("Working directory path contains non-UTF8 characters"))?;
 
+    if uses_sessions() {
+        return create_session(&prefixed_name, working_dir_str, detached);
+    }
+
     let mut cmd = Cmd::new("tmux").arg("new-window");
     if detached {
         cmd = cmd.arg("-d");
@@ -301,6 +656,60 @@ pub fn create_window(
     Ok(pane_id.trim().to_string())
 }
 
+/// Create a session for a worktree, its window named like the session, and
+/// switch to it unless `detached`. Returns the ID of its pane.
+fn create_session(name: &str, working_dir: &str, detached: bool) -> Result<String> {
+    // Always detached, as new-session can't attach from inside tmux
+    let pane_id = Cmd::new("tmux")
+        .args(&[
+            "new-session",
+            "-d",
+            "-s",
+            name,
+            "-n",
+            name,
+            "-c",
+            working_dir,
+            "-P",
+            "-F",
+            "#{pane_id}",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to create tmux session and get pane ID")?;
+    // The session is there either way; without a client to switch (e.g. run
+    // from a script inside tmux) it is left to attach to
+    if !detached && let Err(e) = switch_client(&format!("={}", name)) {
+        warn!(session = name, error = %e, "tmux:failed to switch to new session");
+        eprintln!(
+            "Warning: could not switch to session '{}'. Attach with: tmux attach -t '={}'",
+            name, name
+        );
+    }
+    Ok(pane_id.trim().to_string())
+}
+
+/// Switch the client to `target`. Outside tmux, or inside it with no client
+/// attached (e.g. from a script), there is no client to switch, so this is
+/// left to attaching.
+fn switch_client(target: &str) -> Result<()> {
+    if std::env::var_os("TMUX").is_some() && has_client() {
+        Cmd::new("tmux")
+            .args(&["switch-client", "-t", target])
+            .run()
+            .context("Failed to switch tmux session")?;
+    }
+    remote::request_attach();
+    Ok(())
+}
+
+/// Whether a client is attached to the session workmux runs in
+fn has_client() -> bool {
+    Cmd::new("tmux")
+        .args(&["display-message", "-p", "#{client_tty}"])
+        .run_and_capture_stdout()
+        .is_ok_and(|tty| !tty.is_empty())
+}
+
 /// Select a specific pane by its ID
 pub fn select_pane(pane_id: &str) -> Result<()> {
     Cmd::new("tmux")
@@ -311,15 +720,39 @@ pub fn select_pane(pane_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Arrange the panes of the window containing `pane_id` in `layout`, a
+/// preset name or a layout string from [`window_layout`]
+pub fn apply_pane_layout(pane_id: &str, layout: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["select-layout", "-t", pane_id, layout])
+        .run()
+        .with_context(|| format!("Failed to apply pane layout '{}'", layout))?;
+    Ok(())
+}
+
+/// The layout string of the window containing `pane_id`, which
+/// [`apply_pane_layout`] restores on a window with as many panes
+pub fn window_layout(pane_id: &str) -> Result<String> {
+    let output = Cmd::new("tmux")
+        .args(&["display-message", "-p", "-t", pane_id, "#{window_layout}"])
+        .run_and_capture_stdout()
+        .context("Failed to read the window layout")?;
+    Ok(output.trim().to_string())
+}
+
 /// Select a specific window
 pub fn select_window(prefix: &str, window_name: &str) -> Result<()> {
     let prefixed_name = prefixed(prefix, window_name);
+    if uses_sessions() {
+        return switch_client(&format!("={}", prefixed_name));
+    }
     let target = format!("={}", prefixed_name);
 
     Cmd::new("tmux")
         .args(&["select-window", "-t", &target])
         .run()
         .context("Failed to select window")?;
+    remote::request_attach();
 
     Ok(())
 }
@@ -329,13 +762,43 @@ pub fn kill_window_by_full_name(full_name: &str) -> Result<()> {
     let target = format!("={}", full_name);
 
     Cmd::new("tmux")
-        .args(&["kill-window", "-t", &target])
+        .args(&[close_command(), "-t", &target])
         .run()
         .context("Failed to kill tmux window")?;
 
     Ok(())
 }
 
+/// The tmux command that closes a worktree's window, or its whole session
+pub fn close_command() -> &'static str {
+    if uses_sessions() {
+        "kill-session"
+    } else {
+        "kill-window"
+    }
+}
+
+/// Kill a tmux window by its ID (e.g. @3), or the worktree session it is
+/// in when worktrees have sessions of their own
+pub fn kill_window(window_id: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&[close_command(), "-t", window_id])
+        .run()
+        .context("Failed to kill tmux window")?;
+
+    Ok(())
+}
+
+/// Kill a tmux pane by its ID
+pub fn kill_pane(pane_id: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["kill-pane", "-t", pane_id])
+        .run()
+        .context("Failed to kill tmux pane")?;
+
+    Ok(())
+}
+
 /// Execute a shell script via tmux run-shell
 pub fn run_shell(script: &str) -> Result<()> {
     Cmd::new("tmux")
@@ -345,6 +808,404 @@ pub fn run_shell(script: &str) -> Result<()> {
     Ok(())
 }
 
+/// Run `script` in the background whenever `event` fires for the window
+/// containing `pane_id`. Replaces any hook workmux set before for that event.
+pub fn set_window_hook(pane_id: &str, event: &str, script: &str) -> Result<()> {
+    let command = format!("run-shell -b '{}'", script.replace('\'', r#"'\''"#));
+    let mut args = vec!["set-hook", "-w", "-t", pane_id, event, &command];
+    // A window's hook hides the global one for the same event, so run the
+    // commands of the global hook after ours
+    let global = global_hook_commands(event);
+    for command in &global {
+        args.extend([";", "set-hook", "-aw", "-t", pane_id, event, command]);
+    }
+    Cmd::new("tmux")
+        .args(&args)
+        .run()
+        .with_context(|| format!("Failed to set tmux hook '{}'", event))?;
+    Ok(())
+}
+
+/// The commands of the global hook for window `event`, as set in tmux.conf
+pub fn global_hook_commands(event: &str) -> Vec<String> {
+    Cmd::new("tmux")
+        .args(&["show-hooks", "-gw"])
+        .run_and_capture_stdout()
+        .map(|output| hook_commands(&output, event))
+        .unwrap_or_default()
+}
+
+/// The commands of the hook for window `event` set on `window_id` itself
+pub fn window_hook_commands(window_id: &str, event: &str) -> Vec<String> {
+    Cmd::new("tmux")
+        .args(&["show-hooks", "-w", "-t", window_id])
+        .run_and_capture_stdout()
+        .map(|output| hook_commands(&output, event))
+        .unwrap_or_default()
+}
+
+/// The commands listed for `event` in `show-hooks` output, whose lines look
+/// like `pane-focus-in[0] run-shell true`
+fn hook_commands(output: &str, event: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(name, _)| name.split_once('[').is_some_and(|(name, _)| name == event))
+        .map(|(_, command)| command.to_string())
+        .collect()
+}
+
+/// Add `commands` after the hook for `event` set on `window_id`
+pub fn append_window_hook(window_id: &str, event: &str, commands: &[String]) -> Result<()> {
+    let mut args: Vec<&str> = Vec::new();
+    for command in commands {
+        if !args.is_empty() {
+            args.push(";");
+        }
+        args.extend(["set-hook", "-aw", "-t", window_id, event, command]);
+    }
+    if args.is_empty() {
+        return Ok(());
+    }
+    Cmd::new("tmux")
+        .args(&args)
+        .run()
+        .with_context(|| format!("Failed to add to tmux hook '{}'", event))?;
+    Ok(())
+}
+
+/// Remove the hook for `event` set on `window_id`
+pub fn unset_window_hook(window_id: &str, event: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["set-hook", "-uw", "-t", window_id, event])
+        .run()
+        .with_context(|| format!("Failed to remove tmux hook '{}'", event))?;
+    Ok(())
+}
+
+/// Window option recording which worktree a workmux window belongs to, named
+/// after the worktree's handle (`@workmux_worktree_<handle>`) and holding its
+/// path. Unlike the window's name, index or active pane, it stays with the
+/// window when the window is renamed, moved or renumbered, and when panes
+/// move between windows.
+const WORKTREE_OPTION_PREFIX: &str = "@workmux_worktree_";
+
+/// Window option holding the short title of the worktree's task, for window
+/// names and status lines
+const TASK_OPTION: &str = "@workmux_task";
+
+/// Window option set while the window is focused, so focus moving between
+/// its panes doesn't run the `on_enter`/`on_leave` hooks again
+pub const FOCUSED_OPTION: &str = "@workmux_focused";
+
+/// Window options workmux sets on its windows besides the worktree tag. Status
+/// formats refer to them by name, so they aren't namespaced per handle.
+const WINDOW_OPTIONS: [&str; 4] = [
+    "@workmux_status",
+    "@workmux_status_ts",
+    TASK_OPTION,
+    FOCUSED_OPTION,
+];
+
+/// Window hooks workmux installs on its windows
+pub const WINDOW_HOOKS: [&str; 2] = ["pane-focus-in", "pane-focus-out"];
+
+/// Window formats workmux adds the agent status to
+pub const STATUS_FORMAT_OPTIONS: [&str; 2] =
+    ["window-status-format", "window-status-current-format"];
+
+/// The worktree a window is tagged with
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorktreeTag {
+    /// The handle the tag option is named after
+    pub handle: String,
+    pub path: PathBuf,
+}
+
+/// Name of the option tagging a window with the worktree `handle`, with the
+/// characters tmux formats treat specially replaced
+fn worktree_option(handle: &str) -> String {
+    let handle: String = handle
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", WORKTREE_OPTION_PREFIX, handle)
+}
+
+/// Format expanding to the path a window is tagged with as the window of the
+/// worktree `handle`, or to nothing
+pub fn worktree_tag_format(handle: &str) -> String {
+    format!("#{{{}}}", worktree_option(handle))
+}
+
+/// The form of a worktree's path windows are tagged with
+pub fn tag_path(worktree_path: &Path) -> PathBuf {
+    worktree_path
+        .canonicalize()
+        .unwrap_or_else(|_| worktree_path.to_path_buf())
+}
+
+/// Mark the window containing `pane_id` as the window of the worktree
+/// `handle` at `worktree_path`
+pub fn tag_window(pane_id: &str, handle: &str, worktree_path: &Path) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&[
+            "set-option",
+            "-w",
+            "-t",
+            pane_id,
+            &worktree_option(handle),
+            &tag_path(worktree_path).to_string_lossy(),
+        ])
+        .run()
+        .context("Failed to tag tmux window with its worktree")?;
+    Ok(())
+}
+
+/// Show `task` as the task of the window containing `pane_id`
+pub fn set_window_task(pane_id: &str, task: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["set-option", "-w", "-t", pane_id, TASK_OPTION, task])
+        .run()
+        .context("Failed to set the tmux window's task")?;
+    Ok(())
+}
+
+/// IDs of the windows, in any session
+fn open_window_ids() -> Result<Vec<String>> {
+    Ok(Cmd::new("tmux")
+        .args(&["list-windows", "-a", "-F", "#{window_id}"])
+        .run_and_capture_stdout()
+        .context("Failed to list tmux windows")?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// Tagged windows, in any session, by ID, with the worktree each belongs to.
+/// As tag options are named after handles, the windows' options are read
+/// whole, in a single tmux call.
+pub fn tagged_worktrees() -> Result<Vec<(String, WorktreeTag)>> {
+    let window_ids = open_window_ids()?;
+    let mut args = Vec::new();
+    for window_id in &window_ids {
+        if !args.is_empty() {
+            args.push(";");
+        }
+        args.extend([
+            "display-message",
+            "-p",
+            "-t",
+            window_id,
+            "#{window_id}",
+            ";",
+            "show-options",
+            "-w",
+            "-t",
+            window_id,
+        ]);
+    }
+    if args.is_empty() {
+        return Ok(Vec::new());
+    }
+    let output = Cmd::new("tmux")
+        .args(&args)
+        .run_and_capture_stdout()
+        .context("Failed to read tmux window options")?;
+    Ok(parse_worktree_tags(&output))
+}
+
+/// The worktree tags in the output of a `display-message` of each window's ID
+/// followed by the window's `show-options`
+fn parse_worktree_tags(output: &str) -> Vec<(String, WorktreeTag)> {
+    let mut window_id = None;
+    let mut tags = Vec::new();
+    for line in output.lines() {
+        // Options are shown with their value; window IDs stand alone
+        let Some((option, value)) = line.split_once(' ') else {
+            window_id = Some(line);
+            continue;
+        };
+        if let Some(window_id) = window_id
+            && let Some(handle) = option.strip_prefix(WORKTREE_OPTION_PREFIX)
+        {
+            let path = option_value(value);
+            if !path.is_empty() {
+                tags.push((
+                    window_id.to_string(),
+                    WorktreeTag {
+                        handle: handle.to_string(),
+                        path: PathBuf::from(path),
+                    },
+                ));
+            }
+        }
+    }
+    tags
+}
+
+/// An option's value as `show-options` prints it: quoted when it has spaces or
+/// characters special to tmux, with those escaped by a backslash
+fn option_value(shown: &str) -> String {
+    let unquoted = shown
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(shown);
+    let mut value = String::with_capacity(unquoted.len());
+    let mut chars = unquoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            c => value.push(c),
+        }
+    }
+    value
+}
+
+/// IDs of the windows, in any session, tagged as belonging to the worktree
+/// `handle` at `worktree_path`
+pub fn windows_for_worktree(handle: &str, worktree_path: &Path) -> Result<Vec<String>> {
+    let format = format!("#{{window_id}}|{}", worktree_tag_format(handle));
+    let output = Cmd::new("tmux")
+        .args(&["list-windows", "-a", "-F", &format])
+        .run_and_capture_stdout()
+        .context("Failed to list tmux windows")?;
+    Ok(windows_tagged(&output, &tag_path(worktree_path)))
+}
+
+/// IDs of the windows tagged with `path` in `window_id|tag` lines
+fn windows_tagged(output: &str, path: &Path) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('|'))
+        .filter(|(_, tagged)| Path::new(tagged) == path)
+        .map(|(window_id, _)| window_id.to_string())
+        .collect()
+}
+
+/// Remove the status, worktree tag, task and focus hooks workmux set on the
+/// windows of the worktree `handle` that are still open, turning them back
+/// into plain windows, in one tmux call. Best-effort.
+pub fn clear_window_state(window_ids: &[String], handle: &str) {
+    let open = open_window_ids().unwrap_or_default();
+    let tag = worktree_option(handle);
+    let mut args = Vec::new();
+    for window_id in window_ids.iter().filter(|id| open.contains(id)) {
+        for option in WINDOW_OPTIONS.iter().copied().chain([tag.as_str()]) {
+            args.extend([";", "set-option", "-uw", "-t", window_id, option]);
+        }
+        for hook in WINDOW_HOOKS {
+            args.extend([";", "set-hook", "-uw", "-t", window_id, hook]);
+        }
+    }
+    if let Some((_, args)) = args.split_first() {
+        let _ = Cmd::new("tmux").args(args).run();
+    }
+}
+
+/// A window, in any session, with what workmux may have set on it
+#[derive(Debug, Clone)]
+pub struct MarkedWindow {
+    pub window_id: String,
+    /// `session:window`, for showing
+    pub name: String,
+    pub worktree: Option<WorktreeTag>,
+    pub status: Option<String>,
+    /// Whether a pane of the window reports an agent status
+    pub agent_pane: bool,
+}
+
+/// Every window in any session, with its workmux tag and status
+pub fn marked_windows() -> Result<Vec<MarkedWindow>> {
+    let windows = Cmd::new("tmux")
+        .args(&[
+            "list-windows",
+            "-a",
+            "-F",
+            "#{window_id}|#{@workmux_status}|#{session_name}:#{window_name}",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to list tmux windows")?;
+    let mut tags = tagged_worktrees()?;
+    let agent_windows: HashSet<String> = Cmd::new("tmux")
+        .args(&[
+            "list-panes",
+            "-a",
+            "-F",
+            "#{window_id}|#{@workmux_pane_status}",
+        ])
+        .run_and_capture_stdout()
+        .context("Failed to list tmux panes")?
+        .lines()
+        .filter_map(|line| line.split_once('|'))
+        .filter(|(_, status)| !status.is_empty())
+        .map(|(window_id, _)| window_id.to_string())
+        .collect();
+    Ok(windows
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '|');
+            let window_id = fields.next()?.to_string();
+            let status = fields.next().filter(|f| !f.is_empty()).map(String::from);
+            let worktree = tags
+                .iter()
+                .position(|(tagged, _)| *tagged == window_id)
+                .map(|i| tags.swap_remove(i).1);
+            Some(MarkedWindow {
+                agent_pane: agent_windows.contains(&window_id),
+                name: fields.next()?.to_string(),
+                window_id,
+                worktree,
+                status,
+            })
+        })
+        .collect())
+}
+
+/// Remove the agent status shown on a window
+pub fn clear_window_status(window_id: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&[
+            "set-option",
+            "-uw",
+            "-t",
+            window_id,
+            "@workmux_status",
+            ";",
+            "set-option",
+            "-uw",
+            "-t",
+            window_id,
+            "@workmux_status_ts",
+        ])
+        .run()
+        .context("Failed to clear the tmux window's status")?;
+    Ok(())
+}
+
+/// Whether tmux reports focus changes, which the focus hooks depend on
+pub fn focus_events_enabled() -> bool {
+    Cmd::new("tmux")
+        .args(&["show-option", "-sv", "focus-events"])
+        .run_and_capture_stdout()
+        .is_ok_and(|value| value.trim() == "on")
+}
+
+/// Turn on focus reporting, which the pane-focus-in/out hooks depend on
+pub fn enable_focus_events() -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["set-option", "-s", "focus-events", "on"])
+        .run()
+        .context("Failed to enable tmux focus events")?;
+    Ok(())
+}
+
 /// Schedule a tmux window to be killed after a short delay. This is useful when
 /// the current command is running inside the window that needs to close.
 pub fn schedule_window_close_by_full_name(full_name: &str, delay: Duration) -> Result<()> {
@@ -352,9 +1213,16 @@ pub fn schedule_window_close_by_full_name(full_name: &str, delay: Duration) -> R
     // Shell-escape the target with = inside quotes to handle spaces in window names
     let target = format!("={}", full_name);
     let escaped_target = format!("'{}'", target.replace('\'', r#"'\''"#));
+    // Closing the client's session would detach it, so move it first
+    let switch = if uses_sessions() {
+        "tmux switch-client -l >/dev/null 2>&1; "
+    } else {
+        ""
+    };
     let script = format!(
-        "sleep {delay}; tmux kill-window -t {target} >/dev/null 2>&1",
+        "sleep {delay}; {switch}tmux {close} -t {target} >/dev/null 2>&1",
         delay = delay_secs,
+        close = close_command(),
         target = escaped_target
     );
 
@@ -362,7 +1230,7 @@ pub fn schedule_window_close_by_full_name(full_name: &str, delay: Duration) -> R
 }
 
 /// Get the default shell configured in tmux
-fn get_default_shell() -> Result<String> {
+pub fn get_default_shell() -> Result<String> {
     let output = Cmd::new("tmux")
         .args(&["show-option", "-gqv", "default-shell"])
         .run_and_capture_stdout()?;
@@ -395,6 +1263,13 @@ fn detect_shell_type(shell: &str) -> ShellType {
     }
 }
 
+/// Whether a pane's foreground command is an interactive shell that a typed
+/// `cd` can be sent to
+pub fn is_shell_command(command: &str) -> bool {
+    let command = command.trim_start_matches('-');
+    detect_shell_type(command) != ShellType::Other || matches!(command, "fish" | "tcsh" | "csh")
+}
+
 /// Check if a shell is POSIX-compatible (supports `$(...)` syntax)
 fn is_posix_shell(shell: &str) -> bool {
     matches!(detect_shell_type(shell), ShellType::Posix)
@@ -432,11 +1307,14 @@ impl PaneHandshake {
         let pid = std::process::id();
         let channel = format!("wm_ready_{}_{}", pid, nanos);
 
-        // Lock the channel (ensures we don't miss the signal)
-        Cmd::new("tmux")
-            .args(&["wait-for", "-L", &channel])
-            .run()
-            .context("Failed to initialize wait channel")?;
+        // Lock the channel (ensures we don't miss the signal). No shell
+        // starts in a dry run, so there is nothing to wait for.
+        if !dry_run::is_enabled() {
+            Cmd::new("tmux")
+                .args(&["wait-for", "-L", &channel])
+                .run()
+                .context("Failed to initialize wait channel")?;
+        }
 
         Ok(Self { channel })
     }
@@ -488,10 +1366,14 @@ impl PaneHandshake {
     /// Uses a polling loop with timeout to prevent indefinite hangs if the pane
     /// fails to start.
     fn wait(self) -> Result<()> {
+        if dry_run::is_enabled() {
+            return Ok(());
+        }
         debug!(channel = %self.channel, "tmux:handshake start");
 
-        let mut child = std::process::Command::new("tmux")
-            .args(["wait-for", "-L", &self.channel])
+        let mut child = Cmd::new("tmux")
+            .args(&["wait-for", "-L", &self.channel])
+            .into_command(false)
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
             .spawn()
@@ -670,6 +1552,24 @@ pub struct PaneSetupResult {
 pub struct PaneSetupOptions<'a> {
     pub run_commands: bool,
     pub prompt_file_path: Option<&'a Path>,
+    /// Prompt files for individual panes (by index), passed to that pane's own command
+    pub pane_prompt_paths: &'a [Option<PathBuf>],
+}
+
+impl PaneSetupOptions<'_> {
+    /// The prompt file and agent to use for a pane. Panes with their own prompt
+    /// (agent team members) pass it to their own command.
+    fn prompt_for<'a>(
+        &'a self,
+        pane_idx: usize,
+        command: &'a str,
+        effective_agent: Option<&'a str>,
+    ) -> (Option<&'a Path>, Option<&'a str>) {
+        match self.pane_prompt_paths.get(pane_idx) {
+            Some(Some(path)) => (Some(path.as_path()), Some(command)),
+            _ => (self.prompt_file_path, effective_agent),
+        }
+    }
 }
 
 /// Setup panes in a window according to configuration
@@ -689,7 +1589,10 @@ pub fn setup_panes(
 
     let mut focus_pane_id: Option<String> = None;
     let mut pane_ids: Vec<String> = vec![initial_pane_id.to_string()];
-    let effective_agent = task_agent.or(config.agent.as_deref());
+    let effective_agent = task_agent
+        .or(config.agent.as_deref())
+        .map(|agent| crate::agent::command(config, agent));
+    let effective_agent = effective_agent.as_deref();
     let shell = get_default_shell()?;
 
     // Handle the first pane (initial pane from window creation)
@@ -702,13 +1605,8 @@ pub fn setup_panes(
 
         let adjusted_command = if pane_options.run_commands {
             command_to_run.as_ref().map(|cmd| {
-                adjust_command(
-                    cmd,
-                    pane_options.prompt_file_path,
-                    working_dir,
-                    effective_agent,
-                    &shell,
-                )
+                let (prompt_path, agent) = pane_options.prompt_for(0, cmd, effective_agent);
+                adjust_command(cmd, prompt_path, working_dir, agent, &shell, config)
             })
         } else {
             None
@@ -722,6 +1620,10 @@ pub fn setup_panes(
             respawn_pane(initial_pane_id, working_dir, Some(&wrapper))?;
             handshake.wait()?;
             send_keys(initial_pane_id, cmd_str)?;
+            mark_agent_pane(
+                initial_pane_id,
+                runs_agent(0, pane_config, &pane_options, effective_agent),
+            )?;
         }
         if pane_config.focus {
             focus_pane_id = Some(initial_pane_id.to_string());
@@ -729,7 +1631,7 @@ pub fn setup_panes(
     }
 
     // Create additional panes by splitting
-    for pane_config in panes.iter().skip(1) {
+    for (pane_idx, pane_config) in panes.iter().enumerate().skip(1) {
         if let Some(ref direction) = pane_config.split {
             // Determine which pane to split based on logical index, then get its ID
             let target_pane_idx = pane_config.target.unwrap_or(pane_ids.len() - 1);
@@ -745,13 +1647,9 @@ pub fn setup_panes(
 
             let adjusted_command = if pane_options.run_commands {
                 command_to_run.as_ref().map(|cmd| {
-                    adjust_command(
-                        cmd,
-                        pane_options.prompt_file_path,
-                        working_dir,
-                        effective_agent,
-                        &shell,
-                    )
+                    let (prompt_path, agent) =
+                        pane_options.prompt_for(pane_idx, cmd, effective_agent);
+                    adjust_command(cmd, prompt_path, working_dir, agent, &shell, config)
                 })
             } else {
                 None
@@ -773,6 +1671,9 @@ pub fn setup_panes(
 
                 handshake.wait()?;
                 send_keys(&pane_id, cmd_str)?;
+                if runs_agent(pane_idx, pane_config, &pane_options, effective_agent) {
+                    mark_agent_pane(&pane_id, true)?;
+                }
                 pane_id
             } else {
                 split_pane_with_command(
@@ -798,20 +1699,54 @@ pub fn setup_panes(
     })
 }
 
+/// Whether the pane at `pane_idx` runs an agent: `<agent>`, the agent's own
+/// command, or a command given a prompt of its own (an agent team member)
+fn runs_agent(
+    pane_idx: usize,
+    pane_config: &PaneConfig,
+    pane_options: &PaneSetupOptions<'_>,
+    effective_agent: Option<&str>,
+) -> bool {
+    let Some(command) = pane_config.command.as_deref() else {
+        return false;
+    };
+    matches!(pane_options.pane_prompt_paths.get(pane_idx), Some(Some(_)))
+        || command.trim_start().starts_with("<agent>")
+        || effective_agent.is_some_and(|agent| crate::config::is_agent_command(command, agent))
+}
+
+/// The command a pane runs: an agent command gets the prompt file, if there
+/// is one, and agents whose status is tracked by their exit are wrapped to
+/// report it.
 fn adjust_command<'a>(
     command: &'a str,
     prompt_file_path: Option<&Path>,
     working_dir: &Path,
     effective_agent: Option<&str>,
     shell: &str,
+    config: &crate::config::Config,
 ) -> Cow<'a, str> {
+    let mut adjusted = Cow::Borrowed(command);
     if let Some(prompt_path) = prompt_file_path
-        && let Some(rewritten) =
-            rewrite_agent_command(command, prompt_path, working_dir, effective_agent, shell)
+        && let Some(rewritten) = rewrite_agent_command(
+            command,
+            prompt_path,
+            working_dir,
+            effective_agent,
+            shell,
+            config,
+        )
     {
-        return Cow::Owned(rewritten);
+        adjusted = Cow::Owned(rewritten);
     }
-    Cow::Borrowed(command)
+    let tracked_by_exit = effective_agent
+        .filter(|agent| crate::config::is_agent_command(command, agent))
+        .and_then(|_| crate::agent::for_command(config, command))
+        .is_some_and(|agent| agent.status == Some(crate::config::StatusTracking::Exit));
+    if tracked_by_exit {
+        adjusted = Cow::Owned(crate::agent::track_until_exit(&adjusted));
+    }
+    adjusted
 }
 
 /// Rewrites an agent command to inject a prompt file's contents.
@@ -824,9 +1759,9 @@ fn adjust_command<'a>(
 /// Only rewrites commands that match the configured agent. For instance, if the config
 /// specifies "gemini" as the agent, a "claude" command won't be rewritten.
 ///
-/// Special handling:
-/// - gemini: Adds `-i` flag for interactive mode after the prompt
-/// - Other agents (claude, codex, etc.): Just passes the prompt as first argument
+/// How the prompt is passed comes from the agent's `agents` definition or
+/// built-in profile in `agent.rs`, e.g. `-i` for gemini, `--message-file` for
+/// aider, and `--` for claude or agents without either.
 ///
 /// For non-POSIX shells (nushell, fish, pwsh), the command is wrapped in `sh -c '...'`
 /// to ensure the `$(cat ...)` command substitution works correctly.
@@ -841,6 +1776,7 @@ fn rewrite_agent_command(
     working_dir: &Path,
     effective_agent: Option<&str>,
     shell: &str,
+    config: &crate::config::Config,
 ) -> Option<String> {
     let agent_command = effective_agent?;
     let trimmed_command = command.trim();
@@ -877,18 +1813,10 @@ fn rewrite_agent_command(
         inner_cmd.push_str(rest);
     }
 
-    // Add the prompt argument (agent-specific handling)
-    let pane_stem_str = pane_stem.and_then(|s| s.to_str());
-    if pane_stem_str == Some("gemini") {
-        // gemini uses -i flag with the prompt as its argument
-        inner_cmd.push_str(&format!(" -i \"$(cat {})\"", prompt_path));
-    } else if pane_stem_str == Some("opencode") {
-        // opencode uses --prompt flag for interactive TUI with initial prompt
-        inner_cmd.push_str(&format!(" --prompt \"$(cat {})\"", prompt_path));
-    } else {
-        // Other agents use -- separator
-        inner_cmd.push_str(&format!(" -- \"$(cat {})\"", prompt_path));
-    }
+    // Add the prompt the way the agent expects it
+    let agent = crate::agent::for_command(config, trimmed_command)
+        .unwrap_or_else(|| crate::agent::Agent::unknown(pane_token));
+    inner_cmd.push_str(&agent.prompt_args(&prompt_path));
 
     // For POSIX shells (bash, zsh, sh, etc.), use the command directly.
     // For non-POSIX shells (nushell, fish, pwsh), wrap in sh -c '...' to ensure
@@ -912,8 +1840,9 @@ const WORKMUX_STATUS_FORMAT: &str = "#{?@workmux_status, #{@workmux_status},}";
 /// Sets format per-window to avoid affecting non-workmux windows or other sessions.
 /// Uses pane target to set on the correct window (not the focused one).
 pub fn ensure_status_format(pane: &str) -> Result<()> {
-    update_format_option(pane, "window-status-format")?;
-    update_format_option(pane, "window-status-current-format")?;
+    for option in STATUS_FORMAT_OPTIONS {
+        update_format_option(pane, option)?;
+    }
     Ok(())
 }
 
@@ -985,6 +1914,32 @@ pub fn wait_until_windows_closed(full_window_names: &[String]) -> Result<()> {
     }
 }
 
+/// The format workmux set on `window_id` for `option` and the one it would
+/// set now, when they differ: the global format changed since, and the window
+/// keeps showing the old one
+pub fn stale_status_format(window_id: &str, option: &str) -> Option<(String, String)> {
+    let window = Cmd::new("tmux")
+        .args(&["show-option", "-wv", "-t", window_id, option])
+        .run_and_capture_stdout()
+        .ok()
+        .filter(|format| format.contains(WORKMUX_STATUS_FORMAT))?;
+    let global = Cmd::new("tmux")
+        .args(&["show-option", "-gv", option])
+        .run_and_capture_stdout()
+        .ok()?;
+    let current = inject_status_format(&global);
+    (window != current).then_some((window, current))
+}
+
+/// Show agent status in the global format for `option` on `window_id`
+pub fn reset_status_format(window_id: &str, option: &str) -> Result<()> {
+    Cmd::new("tmux")
+        .args(&["set-option", "-uw", "-t", window_id, option])
+        .run()
+        .with_context(|| format!("Failed to unset {}", option))?;
+    update_format_option(window_id, option)
+}
+
 /// Injects workmux status format into an existing format string.
 /// Inserts before window_flags if present, otherwise appends to end.
 fn inject_status_format(format: &str) -> String {
@@ -1009,10 +1964,66 @@ fn inject_status_format(format: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn agent_panes_are_the_marked_ones() {
+        let output = "%1\t1\tnode\n%2\t\tzsh\n%3\t1\tclaude\n";
+        assert_eq!(parse_agent_panes(output, Some("claude")), ["%1", "%3"]);
+
+        // Windows set up before panes were marked go by the running command
+        let output = "%1\t\tclaude\n%2\t\tzsh\n%3\t\tclaude\n";
+        assert_eq!(
+            parse_agent_panes(output, Some("claude --verbose")),
+            ["%1", "%3"]
+        );
+        assert!(parse_agent_panes(output, None).is_empty());
+        assert!(parse_agent_panes("", Some("claude")).is_empty());
+    }
+
+    #[test]
+    fn panes_run_an_agent_by_placeholder_command_or_own_prompt() {
+        let pane = |command: Option<&str>| PaneConfig {
+            command: command.map(String::from),
+            focus: false,
+            split: None,
+            size: None,
+            percentage: None,
+            target: None,
+        };
+        let prompts = [None, Some(PathBuf::from("/tmp/reviewer.md"))];
+        let options = PaneSetupOptions {
+            run_commands: true,
+            prompt_file_path: None,
+            pane_prompt_paths: &prompts,
+        };
+        let agent = Some("claude");
+        assert!(runs_agent(0, &pane(Some("<agent>")), &options, agent));
+        assert!(runs_agent(
+            0,
+            &pane(Some("claude --resume")),
+            &options,
+            agent
+        ));
+        assert!(!runs_agent(0, &pane(Some("npm run dev")), &options, agent));
+        assert!(!runs_agent(0, &pane(None), &options, agent));
+        // A team member runs its own agent with its own prompt
+        assert!(runs_agent(1, &pane(Some("codex")), &options, agent));
+    }
+    use crate::config::Config;
     use std::path::PathBuf;
 
     // --- is_posix_shell tests ---
 
+    #[test]
+    fn test_is_shell_command() {
+        assert!(is_shell_command("zsh"));
+        assert!(is_shell_command("-bash"));
+        assert!(is_shell_command("fish"));
+        assert!(is_shell_command("nu"));
+        assert!(!is_shell_command("nvim"));
+        assert!(!is_shell_command("claude"));
+    }
+
     #[test]
     fn test_is_posix_shell_bash() {
         assert!(is_posix_shell("/bin/bash"));
@@ -1055,6 +2066,7 @@ mod tests {
             &working_dir,
             Some("claude"),
             "/bin/zsh",
+            &Config::default(),
         );
         // POSIX shell: no wrapper, prefixed with space to prevent history
         assert_eq!(result, Some(" claude -- \"$(cat PROMPT.md)\"".to_string()));
@@ -1071,6 +2083,7 @@ mod tests {
             &working_dir,
             Some("gemini"),
             "/bin/bash",
+            &Config::default(),
         );
         assert_eq!(result, Some(" gemini -i \"$(cat PROMPT.md)\"".to_string()));
     }
@@ -1086,6 +2099,7 @@ mod tests {
             &working_dir,
             Some("opencode"),
             "/bin/zsh",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1104,6 +2118,7 @@ mod tests {
             &working_dir,
             Some("claude"),
             "/bin/bash",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1124,6 +2139,7 @@ mod tests {
             &working_dir,
             Some("claude"),
             "/opt/homebrew/bin/nu",
+            &Config::default(),
         );
         // Non-POSIX shell: wrap in sh -c, prefixed with space
         assert_eq!(
@@ -1143,6 +2159,7 @@ mod tests {
             &working_dir,
             Some("gemini"),
             "/usr/bin/fish",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1162,6 +2179,7 @@ mod tests {
             &working_dir,
             Some("/path/with'quote/claude"),
             "/opt/homebrew/bin/nu",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1183,6 +2201,7 @@ mod tests {
             &working_dir,
             Some("gemini"),
             "/bin/zsh",
+            &Config::default(),
         );
         assert_eq!(result, None);
     }
@@ -1192,8 +2211,14 @@ mod tests {
         let prompt_file = PathBuf::from("/tmp/worktree/PROMPT.md");
         let working_dir = PathBuf::from("/tmp/worktree");
 
-        let result =
-            rewrite_agent_command("", &prompt_file, &working_dir, Some("claude"), "/bin/zsh");
+        let result = rewrite_agent_command(
+            "",
+            &prompt_file,
+            &working_dir,
+            Some("claude"),
+            "/bin/zsh",
+            &Config::default(),
+        );
         assert_eq!(result, None);
     }
 
@@ -1208,6 +2233,7 @@ mod tests {
             &working_dir,
             Some("/usr/local/bin/claude"),
             "/bin/zsh",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1226,6 +2252,7 @@ mod tests {
             &working_dir,
             Some("unknown-agent"),
             "/bin/bash",
+            &Config::default(),
         );
         assert_eq!(
             result,
@@ -1233,6 +2260,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_adjust_command_follows_agent_definitions() {
+        let prompt_file = PathBuf::from("/tmp/worktree/PROMPT.md");
+        let working_dir = PathBuf::from("/tmp/worktree");
+        let config: Config = serde_yaml::from_str(
+            "agents:\n  fast:\n    command: codex --full-auto\n    prompt: stdin\n",
+        )
+        .unwrap();
+
+        let adjust = |command: &str, agent: &str, prompt: Option<&Path>| {
+            adjust_command(
+                command,
+                prompt,
+                &working_dir,
+                Some(agent),
+                "/bin/zsh",
+                &config,
+            )
+            .into_owned()
+        };
+        assert_eq!(
+            adjust("codex --full-auto", "codex --full-auto", Some(&prompt_file)),
+            " codex --full-auto < PROMPT.md"
+        );
+        // Aider's status follows its command, which exits once the message is done
+        assert_eq!(
+            adjust("aider", "aider", Some(&prompt_file)),
+            " workmux set-window-status working; aider --message-file PROMPT.md; workmux set-window-status done"
+        );
+        assert_eq!(
+            adjust("aider", "aider", None),
+            " workmux set-window-status working; aider; workmux set-window-status done"
+        );
+        assert_eq!(adjust("npm run dev", "aider", None), "npm run dev");
+    }
+
     // --- inject_status_format tests ---
 
     #[test]
@@ -1283,4 +2346,124 @@ mod tests {
             "#I:#W#{?@workmux_status, #{@workmux_status},}#{window_flags}"
         );
     }
+
+    // --- adjacent_window_id tests ---
+
+    const WINDOWS: &str = "@1 main\n@4 wm-feature\n@7 wm-bugfix";
+
+    #[test]
+    fn test_adjacent_window_id_previous() {
+        assert_eq!(
+            adjacent_window_id(WINDOWS, "wm-feature", false),
+            Some("@1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_adjacent_window_id_next() {
+        assert_eq!(
+            adjacent_window_id(WINDOWS, "wm-feature", true),
+            Some("@7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_adjacent_window_id_wraps_around() {
+        assert_eq!(
+            adjacent_window_id(WINDOWS, "wm-bugfix", true),
+            Some("@1".to_string())
+        );
+        assert_eq!(
+            adjacent_window_id(WINDOWS, "main", false),
+            Some("@7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_adjacent_window_id_single_window() {
+        assert_eq!(
+            adjacent_window_id("@1 wm-feature", "wm-feature", true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_adjacent_window_id_unknown_window() {
+        assert_eq!(adjacent_window_id(WINDOWS, "wm-missing", true), None);
+    }
+
+    #[test]
+    fn test_hook_commands_by_event() {
+        let output = "client-focus-in\npane-focus-in[0] run-shell -b \"cd /w || exit 1\"\npane-focus-in[1] run-shell true\npane-focus-out[0] run-shell false\n";
+        assert_eq!(
+            hook_commands(output, "pane-focus-in"),
+            vec!["run-shell -b \"cd /w || exit 1\"", "run-shell true"]
+        );
+        assert_eq!(
+            hook_commands(output, "pane-focus-out"),
+            vec!["run-shell false"]
+        );
+        assert!(hook_commands(output, "client-focus-in").is_empty());
+    }
+
+    #[test]
+    fn test_worktree_tags_are_read_from_window_options() {
+        let output = "@1\n\
+                      @workmux_status 🤖\n\
+                      @workmux_worktree_api /w/api\n\
+                      @2\n\
+                      automatic-rename off\n\
+                      @3\n\
+                      @workmux_worktree_fix_auth \"/w/my tree/fix \\\"auth\\\"\"\n\
+                      @workmux_worktree_old \"\"\n";
+        assert_eq!(
+            parse_worktree_tags(output),
+            vec![
+                (
+                    "@1".to_string(),
+                    WorktreeTag {
+                        handle: "api".to_string(),
+                        path: PathBuf::from("/w/api"),
+                    }
+                ),
+                (
+                    "@3".to_string(),
+                    WorktreeTag {
+                        handle: "fix_auth".to_string(),
+                        path: PathBuf::from("/w/my tree/fix \"auth\""),
+                    }
+                ),
+            ]
+        );
+        assert!(parse_worktree_tags("").is_empty());
+    }
+
+    #[test]
+    fn test_option_values_are_unescaped() {
+        assert_eq!(option_value("/w/api"), "/w/api");
+        assert_eq!(option_value("\\~/w"), "~/w");
+        assert_eq!(option_value("/w/a\\\\b"), "/w/a\\b");
+        assert_eq!(option_value("\"/w/\\$x y\""), "/w/$x y");
+        assert_eq!(option_value("\"\""), "");
+    }
+
+    #[test]
+    fn test_worktree_options_are_named_after_handles() {
+        assert_eq!(
+            worktree_option("fix-auth.2"),
+            "@workmux_worktree_fix-auth.2"
+        );
+        assert_eq!(worktree_option("a}b#c"), "@workmux_worktree_a_b_c");
+        assert_eq!(worktree_tag_format("api"), "#{@workmux_worktree_api}");
+    }
+
+    #[test]
+    fn test_windows_tagged_with_a_worktree() {
+        let output = "@1|/w/api\n@2|\n@3|/w/web\n@4|/w/api\n";
+        assert_eq!(
+            windows_tagged(output, Path::new("/w/api")),
+            vec!["@1", "@4"]
+        );
+        assert!(windows_tagged(output, Path::new("/w/gone")).is_empty());
+    }
 }
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::config::Config;
+use crate::manifest::{Manifest, ManifestEntry};
+use crate::prompt::Prompt;
+use crate::{git, naming, tmux};
+
+use super::context::WorkflowContext;
+use super::types::{ApplyAction, ApplyStep, CreateArgs, CreateResult, SetupOptions};
+use super::{create, open};
+
+/// Compare a manifest against the existing worktrees and tmux windows.
+pub fn plan(manifest: &Manifest) -> Result<Vec<ApplyStep>> {
+    manifest
+        .worktrees
+        .iter()
+        .map(|entry| {
+            let config = entry_config(entry)?;
+
+            let (handle, action) = if git::worktree_exists(&entry.branch)? {
+                // Use the existing directory name, which may differ from what the
+                // current naming config would derive (e.g. created with --name)
+                let path = git::get_worktree_path(&entry.branch)?;
+                let handle = path
+                    .file_name()
+                    .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+                    .to_string_lossy()
+                    .to_string();
+                let action = if tmux::window_exists(config.window_prefix(), &handle)? {
+                    ApplyAction::Unchanged
+                } else {
+                    ApplyAction::Open
+                };
+                (handle, action)
+            } else {
+                if let Some(prompt_file) = &entry.prompt_file
+                    && !prompt_file.is_file()
+                {
+                    return Err(anyhow!(
+                        "Prompt file '{}' for branch '{}' does not exist",
+                        prompt_file.display(),
+                        entry.branch
+                    ));
+                }
+                let handle = naming::derive_handle(&entry.branch, entry.name.as_deref(), &config)?;
+                (handle, ApplyAction::Create)
+            };
+
+            Ok(ApplyStep {
+                entry: entry.clone(),
+                handle,
+                action,
+            })
+        })
+        .collect()
+}
+
+/// Worktrees that exist but aren't listed in the manifest, as (path, branch) pairs.
+/// The main worktree, the main branch and detached worktrees are never included.
+pub fn unlisted_worktrees(
+    manifest: &Manifest,
+    context: &WorkflowContext,
+) -> Result<Vec<(PathBuf, String)>> {
+    Ok(unlisted(
+        git::list_worktrees()?,
+        manifest,
+        &context.main_worktree_root,
+        &context.main_branch,
+    ))
+}
+
+/// Of `worktrees`, the ones [`unlisted_worktrees`] returns
+fn unlisted(
+    worktrees: Vec<(PathBuf, String)>,
+    manifest: &Manifest,
+    main_worktree_root: &Path,
+    main_branch: &str,
+) -> Vec<(PathBuf, String)> {
+    let listed: HashSet<&str> = manifest
+        .worktrees
+        .iter()
+        .map(|entry| entry.branch.as_str())
+        .collect();
+
+    worktrees
+        .into_iter()
+        .filter(|(path, branch)| {
+            path != main_worktree_root
+                && branch != main_branch
+                && branch != "(detached)"
+                && !listed.contains(branch.as_str())
+        })
+        .collect()
+}
+
+/// Execute a planned step. Windows are opened in the background so applying a
+/// manifest doesn't jump between windows. Returns None for unchanged entries.
+pub fn apply(step: &ApplyStep) -> Result<Option<CreateResult>> {
+    let entry = &step.entry;
+    info!(
+        branch = entry.branch,
+        handle = step.handle,
+        action = ?step.action,
+        "apply:step"
+    );
+
+    match step.action {
+        ApplyAction::Unchanged => Ok(None),
+        ApplyAction::Open => {
+            let context = WorkflowContext::new(entry_config(entry)?)?;
+            let mut options = SetupOptions::new(false, false, true);
+            options.focus_window = false;
+            open(&step.handle, &context, options, false)
+                .with_context(|| format!("Failed to open worktree for '{}'", entry.branch))
+                .map(Some)
+        }
+        ApplyAction::Create => {
+            let context = WorkflowContext::new(entry_config(entry)?)?;
+            let mut options = SetupOptions::new(true, true, true);
+            options.focus_window = false;
+            let prompt = entry.prompt_file.clone().map(Prompt::FromFile);
+            create(
+                &context,
+                CreateArgs {
+                    branch_name: &entry.branch,
+                    handle: &step.handle,
+                    base_branch: entry.base.as_deref(),
+                    remote_branch: None,
+                    prompt: prompt.as_ref(),
+                    options,
+                    agent: entry.agent.as_deref(),
+                    pr: None,
+                    review: false,
+                    detach: false,
+                },
+            )
+            .with_context(|| format!("Failed to create worktree for '{}'", entry.branch))
+            .map(Some)
+        }
+    }
+}
+
+/// Load the config for a manifest entry, applying its agent and pane overrides.
+fn entry_config(entry: &ManifestEntry) -> Result<Config> {
+    let mut config = Config::load(entry.agent.as_deref())?;
+    if let Some(panes) = &entry.panes {
+        config.panes = Some(panes.clone().into());
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worktrees_missing_from_the_manifest_are_unlisted() {
+        let manifest = Manifest::parse("worktrees:\n  - branch: feat/login\n").unwrap();
+        let worktree = |dir: &str, branch: &str| (PathBuf::from(dir), branch.to_string());
+        let worktrees = vec![
+            worktree("/src/app", "main"),
+            worktree("/src/app__worktrees/login", "feat/login"),
+            worktree("/src/app__worktrees/search", "feat/search"),
+            worktree("/src/app__worktrees/review", "(detached)"),
+            // The main branch is never pruned, wherever it is checked out
+            worktree("/src/app__worktrees/hotfix", "main"),
+        ];
+        assert_eq!(
+            unlisted(worktrees, &manifest, Path::new("/src/app"), "main"),
+            [worktree("/src/app__worktrees/search", "feat/search")]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::cmd::Cmd;
+use crate::git;
+use crate::worktree_state::{self, WorktreeState};
+
+use super::context::WorkflowContext;
+use super::create;
+use super::types::{CreateArgs, CreateResult, SetupOptions};
+
+/// Name of the directory in the repository's git directory that archives
+/// are written to by default
+const ARCHIVE_DIR: &str = "workmux/archives";
+
+const BUNDLE_FILE: &str = "branch.bundle";
+const PATCH_FILE: &str = "uncommitted.patch";
+const META_FILE: &str = "workmux.json";
+
+/// What an archive records about the worktree it was made from, besides its
+/// commits and uncommitted changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveMeta {
+    pub handle: String,
+    pub branch: String,
+    /// Commit the branch left its base at, which the bundle builds on
+    pub base_commit: String,
+    /// Number of commits on the branch since `base_commit`, in the bundle
+    pub commits: usize,
+    /// Whether the archive holds a patch of uncommitted changes
+    pub uncommitted: bool,
+    /// Unix timestamp of when the worktree was archived
+    pub archived_at: u64,
+    /// The worktree's recorded state, restored with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<WorktreeState>,
+}
+
+/// Where archives are written when no output file is given
+pub fn archive_dir() -> Result<PathBuf> {
+    Ok(git::get_git_common_dir()?.join(ARCHIVE_DIR))
+}
+
+/// Archives in [`archive_dir`], by name
+pub fn list_archives() -> Result<Vec<PathBuf>> {
+    let dir = archive_dir()?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut archives: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tar"))
+        .collect();
+    archives.sort();
+    Ok(archives)
+}
+
+/// The archive `name` refers to: a file, or the handle of a worktree
+/// archived to [`archive_dir`]
+pub fn resolve_archive(name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    let archived = archive_dir()?.join(format!("{}.tar", name));
+    if archived.is_file() {
+        return Ok(archived);
+    }
+    Err(anyhow!(
+        "No archive '{}'. Give a file, or the handle of a worktree archived with 'workmux archive'.",
+        name
+    ))
+}
+
+/// Write the commits, uncommitted changes and state of the worktree `handle`
+/// to a tar archive at `out`. The worktree itself is left in place.
+pub fn archive(handle: &str, out: &Path, context: &WorkflowContext) -> Result<ArchiveMeta> {
+    let (worktree_path, branch) = git::find_worktree(handle)?;
+    if worktree_path == context.main_worktree_root {
+        return Err(anyhow!("The main worktree can't be archived"));
+    }
+    if branch == "(detached)" {
+        return Err(anyhow!(
+            "Worktree '{}' has no branch to archive. Run 'workmux remove {}' instead.",
+            handle,
+            handle
+        ));
+    }
+    if let Some(operation) = git::in_progress_operation(&worktree_path) {
+        return Err(anyhow!(
+            "Worktree '{}' is in the middle of a {}. Finish it, or run '{}', before archiving.",
+            handle,
+            operation.label(),
+            operation.abort_command()
+        ));
+    }
+    info!(handle, branch, out = %out.display(), "archive:start");
+
+    let state = worktree_state::load(handle)?;
+    let base =
+        worktree_state::base_branch(handle, &branch).unwrap_or_else(|| context.main_branch.clone());
+    let base_commit = git::get_merge_base_in_worktree(&worktree_path, &base)?;
+    let commits = git::get_commits_from_base(&worktree_path, &base_commit)?.len();
+
+    let staging = staging_dir(handle)?;
+    let result = (|| {
+        let mut files = vec![META_FILE];
+        if commits > 0 {
+            git::create_bundle(
+                &worktree_path,
+                &base_commit,
+                &branch,
+                &staging.join(BUNDLE_FILE),
+            )?;
+            files.push(BUNDLE_FILE);
+        }
+        let patch = git::uncommitted_patch(&worktree_path)?;
+        if !patch.is_empty() {
+            fs::write(staging.join(PATCH_FILE), &patch)
+                .context("Failed to write the uncommitted changes")?;
+            files.push(PATCH_FILE);
+        }
+
+        let meta = ArchiveMeta {
+            handle: handle.to_string(),
+            branch: branch.clone(),
+            base_commit: base_commit.clone(),
+            commits,
+            uncommitted: !patch.is_empty(),
+            archived_at: now(),
+            state,
+        };
+        fs::write(
+            staging.join(META_FILE),
+            serde_json::to_string_pretty(&meta)?,
+        )
+        .context("Failed to write the archive's metadata")?;
+
+        if let Some(dir) = out.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+        }
+        tar(
+            &[
+                "-cf",
+                &out.to_string_lossy(),
+                "-C",
+                &staging.to_string_lossy(),
+            ],
+            &files,
+        )?;
+        Ok(meta)
+    })();
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Recreate the worktree archived in `archive`: its branch with the
+/// commits, the uncommitted changes on top, and its recorded state
+pub fn restore(
+    archive: &Path,
+    context: &WorkflowContext,
+    options: SetupOptions,
+) -> Result<(ArchiveMeta, CreateResult)> {
+    let staging = staging_dir("restore")?;
+    let result = (|| {
+        tar(
+            &[
+                "-xf",
+                &archive.to_string_lossy(),
+                "-C",
+                &staging.to_string_lossy(),
+            ],
+            &[],
+        )
+        .with_context(|| format!("Failed to unpack '{}'", archive.display()))?;
+        let meta: ArchiveMeta = serde_json::from_str(
+            &fs::read_to_string(staging.join(META_FILE))
+                .with_context(|| format!("'{}' is not a workmux archive", archive.display()))?,
+        )
+        .with_context(|| format!("Failed to read the metadata of '{}'", archive.display()))?;
+        info!(handle = meta.handle, branch = meta.branch, "restore:start");
+
+        if git::branch_exists(&meta.branch)? {
+            return Err(anyhow!(
+                "Branch '{}' already exists. Delete or rename it before restoring.",
+                meta.branch
+            ));
+        }
+        let base = if meta.commits > 0 {
+            git::fetch_bundle_branch(&staging.join(BUNDLE_FILE), &meta.branch, &meta.branch)?;
+            None
+        } else {
+            Some(meta.base_commit.as_str())
+        };
+
+        let handle = meta.handle.clone();
+        let result = create(
+            context,
+            CreateArgs {
+                branch_name: &meta.branch,
+                handle: &handle,
+                base_branch: base,
+                remote_branch: None,
+                prompt: None,
+                options,
+                agent: None,
+                pr: None,
+                review: false,
+                detach: false,
+            },
+        )?;
+
+        if let Some(state) = &meta.state {
+            worktree_state::save(&handle, state, &context.config)
+                .context("Failed to restore the worktree's state")?;
+        }
+        if meta.uncommitted {
+            git::apply_patch(&result.worktree_path, &staging.join(PATCH_FILE), false)
+                .with_context(|| {
+                    format!(
+                        "Restored the branch at '{}', but its uncommitted changes did not apply",
+                        result.worktree_path.display()
+                    )
+                })?;
+        }
+        Ok((meta, result))
+    })();
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// An empty directory to assemble or unpack an archive in
+fn staging_dir(name: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("workmux-archive-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    Ok(dir)
+}
+
+fn tar(args: &[&str], files: &[&str]) -> Result<()> {
+    Cmd::new("tar")
+        .args(args)
+        .args(files)
+        .run()
+        .context("Failed to run tar")?;
+    Ok(())
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_keeps_the_state_it_was_given() {
+        let meta = ArchiveMeta {
+            handle: "login".to_string(),
+            branch: "feature/login".to_string(),
+            base_commit: "1a2b3c4".to_string(),
+            commits: 2,
+            uncommitted: true,
+            archived_at: 1_700_000_000,
+            state: Some(WorktreeState {
+                prompt: Some("Fix the login form".to_string()),
+                ..WorktreeState::new("feature/login")
+            }),
+        };
+        let json = serde_json::to_string(&meta).unwrap();
+        assert_eq!(serde_json::from_str::<ArchiveMeta>(&json).unwrap(), meta);
+
+        // Worktrees without recorded state archive without one
+        let bare: ArchiveMeta = serde_json::from_str(
+            r#"{"handle":"x","branch":"x","base_commit":"abc","commits":0,"uncommitted":false,"archived_at":0}"#,
+        )
+        .unwrap();
+        assert!(bare.state.is_none());
+    }
+}
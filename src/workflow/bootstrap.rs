@@ -0,0 +1,201 @@
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::cmd::shell_quote;
+use crate::config::{BootstrapConfig, SplitDirection};
+use crate::{template, tmux};
+
+use super::context::expand_home;
+use super::hooks::HookEnv;
+
+/// Share of the window the bootstrap pane takes
+const PANE_PERCENTAGE: u8 = 25;
+
+/// The directory `cache` paths are linked into: `cache_dir` with `{{ repo }}`
+/// filled in, or `~/.cache/workmux/<repo>`
+pub fn cache_dir(bootstrap: &BootstrapConfig, repo_root: &Path) -> Result<PathBuf> {
+    let repo = repo_root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("Could not determine project name"))?;
+    let Some(dir) = &bootstrap.cache_dir else {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home::home_dir().map(|home| home.join(".cache")))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?;
+        return Ok(cache_home.join("workmux").join(repo));
+    };
+    let dir = template::render_path(dir, &serde_json::json!({ "repo": repo }))
+        .with_context(|| format!("Invalid bootstrap.cache_dir '{}'", dir))?;
+    Ok(repo_root.join(expand_home(&dir)?))
+}
+
+/// Link each `cache` path of the worktree to the same path in `cache_dir`,
+/// creating it there first. A path the worktree already has is left alone,
+/// unless it is an empty directory or a link.
+pub fn link_cache(worktree_path: &Path, cache_dir: &Path, paths: &[String]) -> Result<()> {
+    for path in paths {
+        let relative = Path::new(path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|part| matches!(part, Component::ParentDir))
+        {
+            return Err(anyhow!(
+                "bootstrap.cache path '{}' must be inside the worktree",
+                path
+            ));
+        }
+        let shared = cache_dir.join(relative);
+        fs::create_dir_all(&shared)
+            .with_context(|| format!("Failed to create cache directory {:?}", shared))?;
+
+        let dest = worktree_path.join(relative);
+        if let Ok(metadata) = dest.symlink_metadata() {
+            let is_empty_dir = metadata.is_dir()
+                && fs::read_dir(&dest).is_ok_and(|mut entries| entries.next().is_none());
+            if metadata.is_symlink() {
+                fs::remove_file(&dest)?;
+            } else if is_empty_dir {
+                fs::remove_dir(&dest)?;
+            } else {
+                warn!(path = path, "bootstrap:cache path exists, not linked");
+                eprintln!(
+                    "Not sharing '{}': the worktree already has it (e.g. it is tracked)",
+                    path
+                );
+                continue;
+            }
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&shared, &dest)
+            .with_context(|| format!("Failed to link {:?} to {:?}", dest, shared))?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_dir(&shared, &dest)
+            .with_context(|| format!("Failed to link {:?} to {:?}", dest, shared))?;
+        debug!(path = path, shared = %shared.display(), "bootstrap:cache linked");
+    }
+    Ok(())
+}
+
+/// Start the bootstrap commands in a pane split off `pane_id`, returning the
+/// new pane. It closes once every command succeeds, and otherwise stays open
+/// at a shell showing what failed.
+pub fn start(
+    pane_id: &str,
+    worktree_path: &Path,
+    commands: &[String],
+    env: &HookEnv,
+    cache_dir: Option<&Path>,
+) -> Result<String> {
+    let commands = commands
+        .iter()
+        .map(|command| env.render(command))
+        .collect::<Result<Vec<_>>>()
+        .context("Failed to render bootstrap commands")?;
+    let mut vars: Vec<(&str, String)> = env
+        .vars()
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    if let Some(dir) = cache_dir {
+        vars.push(("WM_CACHE_DIR", dir.to_string_lossy().to_string()));
+    }
+    let script = script(&commands, &vars);
+    let pane = tmux::split_pane_with_command(
+        pane_id,
+        &SplitDirection::Vertical,
+        worktree_path,
+        None,
+        Some(PANE_PERCENTAGE),
+        Some(&format!("sh -c {}", shell_quote(&script))),
+    )
+    .context("Failed to open the bootstrap pane")?;
+    info!(pane = pane, commands = commands.len(), "bootstrap:started");
+    Ok(pane)
+}
+
+/// Shell script running `commands` in order, numbering each as it starts
+fn script(commands: &[String], vars: &[(&str, String)]) -> String {
+    let mut lines = Vec::new();
+    for (key, value) in vars {
+        lines.push(format!("export {}={}", key, shell_quote(value)));
+    }
+    for (idx, command) in commands.iter().enumerate() {
+        lines.push(format!(
+            "printf '\\033[1m[%s/%s] %s\\033[0m\\n' {} {} {}",
+            idx + 1,
+            commands.len(),
+            shell_quote(command)
+        ));
+        lines.push(format!(
+            "( {} ) || {{ printf '\\n✗ Bootstrap failed: %s\\n' {}; exec \"${{SHELL:-sh}}\"; }}",
+            command,
+            shell_quote(command)
+        ));
+    }
+    lines.push("printf '\\n✓ Bootstrap finished\\n'".to_string());
+    lines.push("sleep 2".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_numbers_commands_and_stops_at_a_failure() {
+        let commands = [
+            "echo one".to_string(),
+            "exit 3".to_string(),
+            "touch never".to_string(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let script = script(&commands, &[("WM_CACHE_DIR", "/tmp/cache dir".to_string())]);
+        // A failure hands over to $SHELL, here one that exits at once
+        let output = std::process::Command::new("sh")
+            .args(["-c", &script])
+            .current_dir(dir.path())
+            .env("SHELL", "true")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("[1/3] echo one\u{1b}[0m\none\n"));
+        assert!(stdout.contains("[2/3] exit 3"));
+        assert!(stdout.contains("✗ Bootstrap failed: exit 3"));
+        assert!(!stdout.contains("[3/3]"));
+        assert!(!dir.path().join("never").exists());
+        assert!(script.starts_with("export WM_CACHE_DIR='/tmp/cache dir'\n"));
+    }
+
+    #[test]
+    fn link_cache_shares_paths_but_keeps_what_the_worktree_has() {
+        let worktree = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        fs::create_dir(worktree.path().join("target")).unwrap();
+        fs::create_dir(worktree.path().join("vendor")).unwrap();
+        fs::write(worktree.path().join("vendor/lib.rs"), "").unwrap();
+        let paths = [
+            "target".to_string(),
+            "vendor".to_string(),
+            "web/.cache".to_string(),
+        ];
+
+        link_cache(worktree.path(), cache.path(), &paths).unwrap();
+        assert_eq!(
+            fs::read_link(worktree.path().join("target")).unwrap(),
+            cache.path().join("target")
+        );
+        assert!(cache.path().join("web/.cache").is_dir());
+        assert!(worktree.path().join("web/.cache").is_symlink());
+        assert!(!worktree.path().join("vendor").is_symlink());
+
+        assert!(link_cache(worktree.path(), cache.path(), &["../up".to_string()]).is_err());
+    }
+}
@@ -4,10 +4,13 @@ use std::path::Path;
 use std::time::SystemTime;
 use std::{thread, time::Duration};
 
-use crate::{cmd, git, tmux};
+use crate::audit::{self, AuditAction};
+use crate::config::AfterClose;
+use crate::{cmd, config, dry_run, git, interrupt, remote, retention, tmux, worktree_state};
 use tracing::{debug, info, warn};
 
 use super::context::WorkflowContext;
+use super::hooks::{self, HookEnv};
 use super::types::CleanupResult;
 
 const WINDOW_CLOSE_DELAY_MS: u64 = 300;
@@ -35,6 +38,15 @@ fn remove_dir_contents(path: &Path) {
     }
 }
 
+/// Whether a failed rename means the source is a mount point: Linux reports
+/// EBUSY, others report a cross-device rename.
+fn is_mount_point_error(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::ResourceBusy | std::io::ErrorKind::CrossesDevices
+    )
+}
+
 /// Find all tmux windows matching the base handle pattern (including duplicates).
 /// Matches: {prefix}{handle} and {prefix}{handle}-{N}
 fn find_matching_windows(prefix: &str, handle: &str) -> Result<Vec<String>> {
@@ -101,6 +113,36 @@ pub fn cleanup(
         None
     };
     let running_inside_target_window = current_matching_window.is_some();
+    // Looked up while the worktree path still exists, as windows are tagged
+    // with its canonical form
+    let tagged_windows = if tmux_running {
+        tmux::windows_for_worktree(handle, worktree_path).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    // Hooks from the template the worktree was created with, if any. Read
+    // before the branch, which records the template, is deleted.
+    let branch_config = context.config.for_branch(branch_name);
+    let when_target = config::WhenTarget {
+        branch: branch_name,
+        agent: context.config.agent.as_deref(),
+        worktree_path,
+    };
+    let hook_env = HookEnv::new(
+        handle,
+        branch_name,
+        worktree_path,
+        &context.main_worktree_root,
+    );
+    // The worktree is gone by then, so they run in the main worktree
+    let post_remove = config::hooks_for(
+        &branch_config.post_remove,
+        &config::WhenTarget {
+            worktree_path: &context.main_worktree_root,
+            ..when_target
+        },
+    );
 
     let mut result = CleanupResult {
         tmux_window_killed: false,
@@ -114,36 +156,25 @@ pub fn cleanup(
     let perform_fs_git_cleanup = |result: &mut CleanupResult| -> Result<()> {
         // Run pre-remove hooks before removing the worktree directory.
         // Skip if the worktree directory doesn't exist (e.g., user manually deleted it).
-        if worktree_path.exists() {
-            if let Some(pre_remove_hooks) = &context.config.pre_remove {
+        if remote::path_exists(worktree_path) {
+            let pre_remove_hooks = config::hooks_for(&branch_config.pre_remove, &when_target);
+            if !pre_remove_hooks.is_empty() {
                 info!(
                     branch = branch_name,
                     count = pre_remove_hooks.len(),
                     "cleanup:running pre-remove hooks"
                 );
-                // Resolve absolute paths for environment variables.
-                // canonicalize() ensures symlinks are resolved and paths are absolute.
-                let abs_worktree_path = worktree_path
-                    .canonicalize()
-                    .unwrap_or_else(|_| worktree_path.to_path_buf());
-                let abs_project_root = context
-                    .main_worktree_root
-                    .canonicalize()
-                    .unwrap_or_else(|_| context.main_worktree_root.clone());
-                let worktree_path_str = abs_worktree_path.to_string_lossy();
-                let project_root_str = abs_project_root.to_string_lossy();
-                let hook_env = [
-                    ("WORKMUX_HANDLE", handle),
-                    ("WM_HANDLE", handle),
-                    ("WM_WORKTREE_PATH", worktree_path_str.as_ref()),
-                    ("WM_PROJECT_ROOT", project_root_str.as_ref()),
-                ];
-                for command in pre_remove_hooks {
+                for hook in pre_remove_hooks {
                     // Run the hook with the worktree path as the working directory.
                     // This allows for relative paths like `node_modules` in the command.
-                    cmd::shell_command_with_env(command, worktree_path, &hook_env).with_context(
-                        || format!("Failed to run pre-remove command: '{}'", command),
-                    )?;
+                    cmd::shell_command_with_env(
+                        &hook_env.render(hook.command())?,
+                        worktree_path,
+                        &hook_env.vars(),
+                        Some(&hook_env.input("pre_remove")),
+                        hook.timeout(),
+                    )
+                    .with_context(|| format!("Failed to run pre-remove command: '{}'", hook))?;
                 }
             }
         } else {
@@ -153,6 +184,9 @@ pub fn cleanup(
             );
         }
 
+        // Stop before anything is removed if Ctrl-C came during the hooks
+        interrupt::check()?;
+
         // Track the trash path for best-effort deletion at the end
         let mut trash_path: Option<std::path::PathBuf> = None;
 
@@ -161,7 +195,10 @@ pub fn cleanup(
         // still has it as CWD (the shell's CWD moves with the rename).
         // This fixes a race condition where running `workmux remove` from inside the
         // target tmux window could leave the directory behind.
-        if worktree_path.exists() {
+        let worktree_exists = remote::path_exists(worktree_path);
+        if worktree_exists && dry_run::skip(format_args!("rm -rf {}", worktree_path.display())) {
+            result.worktree_removed = true;
+        } else if worktree_exists {
             let parent = worktree_path.parent().unwrap_or_else(|| Path::new("."));
             let dir_name = worktree_path
                 .file_name()
@@ -173,7 +210,8 @@ pub fn cleanup(
                 .unwrap_or_default()
                 .as_secs();
             let trash_name = format!(
-                ".workmux_trash_{}_{}",
+                "{}{}_{}",
+                retention::TRASH_PREFIX,
                 dir_name.to_string_lossy(),
                 timestamp
             );
@@ -185,29 +223,73 @@ pub fn cleanup(
                 "cleanup:renaming worktree to trash"
             );
 
-            std::fs::rename(worktree_path, &target_trash_path).with_context(|| {
-                format!(
-                    "Failed to rename worktree directory to trash location '{}'. \
-                    Please close any terminals or editors using this directory and try again.",
-                    target_trash_path.display()
-                )
-            })?;
-
-            trash_path = Some(target_trash_path);
+            let renamed = if remote::get().is_some() {
+                // The worktree is on the remote host
+                cmd::Cmd::new("mv")
+                    .args(&[
+                        &worktree_path.to_string_lossy(),
+                        &target_trash_path.to_string_lossy(),
+                    ])
+                    .run()
+                    .map(|_| ())
+                    .map_err(std::io::Error::other)
+            } else {
+                std::fs::rename(worktree_path, &target_trash_path)
+            };
+            match renamed {
+                Ok(()) => trash_path = Some(target_trash_path),
+                // A worktree directory that is itself a mount point (e.g. a scratch
+                // disk mounted at the worktree path) can't be renamed away, so
+                // empty it where it is. Removing `.git` is enough for the prune below.
+                Err(e) if is_mount_point_error(&e) => {
+                    info!(
+                        path = %worktree_path.display(),
+                        error = %e,
+                        "cleanup:worktree is a mount point, deleting in place"
+                    );
+                    remove_dir_contents(worktree_path);
+                    if let Err(e) = std::fs::remove_dir(worktree_path) {
+                        warn!(
+                            path = %worktree_path.display(),
+                            error = %e,
+                            "cleanup:left empty mount point behind"
+                        );
+                    }
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to rename worktree directory to trash location '{}'. \
+                            Please close any terminals or editors using this directory and try again.",
+                            target_trash_path.display()
+                        )
+                    });
+                }
+            }
             result.worktree_removed = true;
+            audit::record(
+                AuditAction::WorktreeRemoved,
+                &worktree_path.to_string_lossy(),
+                None,
+            );
             info!(branch = branch_name, path = %worktree_path.display(), "cleanup:worktree directory removed");
         }
 
         // Clean up prompt files (handles both legacy fixed names and timestamped names)
         // Matches: workmux-prompt-{name}.md and workmux-prompt-{name}-{timestamp}.md
+        // With --host they were written on the remote host, whose temp files
+        // are its own to clean up
         let temp_dir = std::env::temp_dir();
         let prefix = format!("workmux-prompt-{}", branch_name);
-        if let Ok(entries) = std::fs::read_dir(&temp_dir) {
+        if remote::get().is_none()
+            && let Ok(entries) = std::fs::read_dir(&temp_dir)
+        {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if let Some(filename) = path.file_name().and_then(|n| n.to_str())
                     && filename.starts_with(&prefix)
                     && filename.ends_with(".md")
+                    && !dry_run::skip(format_args!("rm -f {}", path.display()))
                 {
                     if let Err(e) = std::fs::remove_file(&path) {
                         warn!(path = %path.display(), error = %e, "cleanup:failed to remove prompt file");
@@ -218,22 +300,37 @@ pub fn cleanup(
             }
         }
 
+        worktree_state::remove(handle);
+
         // 2. Prune worktrees to clean up git's metadata.
         // Git will see the original path as missing since we renamed it.
         git::prune_worktrees().context("Failed to prune worktrees")?;
         debug!("cleanup:git worktrees pruned");
 
-        // 3. Delete the local branch (unless keeping it).
-        if !keep_branch {
+        // 3. Delete the local branch (unless keeping it, or there is none,
+        // as for a detached review worktree).
+        if !keep_branch && branch_name != "(detached)" {
+            // Remember the commit so the branch can be recreated from the audit log
+            let commit = git::get_branch_commit(branch_name).ok();
             git::delete_branch(branch_name, force).context("Failed to delete local branch")?;
             result.local_branch_deleted = true;
+            audit::record(AuditAction::BranchDeleted, branch_name, commit.as_deref());
             info!(branch = branch_name, "cleanup:local branch deleted");
         }
 
         // 4. Best-effort deletion of the trash directory.
         // If the shell is inside this directory, remove_dir_all on the root might fail
         // immediately. Clearing children first ensures we reclaim the space.
-        if let Some(tp) = trash_path {
+        if let Some(tp) = &trash_path
+            && remote::get().is_some()
+        {
+            if let Err(e) = cmd::Cmd::new("rm")
+                .args(&["-rf", &tp.to_string_lossy()])
+                .run()
+            {
+                warn!(path = %tp.display(), error = %e, "cleanup:failed to remove trash directory");
+            }
+        } else if let Some(tp) = trash_path {
             // First, aggressively clear contents to reclaim disk space
             remove_dir_contents(&tp);
 
@@ -271,6 +368,7 @@ pub fn cleanup(
                         warn!(window = window, error = %e, "cleanup:failed to kill duplicate window");
                     } else {
                         killed_count += 1;
+                        audit::record(AuditAction::WindowKilled, window, None);
                         debug!(window = window, "cleanup:killed duplicate window");
                     }
                 }
@@ -295,6 +393,7 @@ pub fn cleanup(
                     warn!(window = window, error = %e, "cleanup:failed to kill window");
                 } else {
                     killed_count += 1;
+                    audit::record(AuditAction::WindowKilled, window, None);
                     debug!(window = window, "cleanup:killed window");
                 }
             }
@@ -306,10 +405,16 @@ pub fn cleanup(
                     "cleanup:killed all matching windows"
                 );
 
-                // Poll to confirm windows are gone before proceeding
+                // Poll to confirm windows are gone before proceeding (in a dry
+                // run they never go)
                 const MAX_RETRIES: u32 = 20;
                 const RETRY_DELAY: Duration = Duration::from_millis(50);
-                for _ in 0..MAX_RETRIES {
+                let retries = if dry_run::is_enabled() {
+                    0
+                } else {
+                    MAX_RETRIES
+                };
+                for _ in 0..retries {
                     let remaining = find_matching_windows(&context.prefix, handle)?;
                     if remaining.is_empty() {
                         break;
@@ -322,10 +427,32 @@ pub fn cleanup(
         perform_fs_git_cleanup(&mut result)?;
     }
 
+    // The worktree is gone either way, so a failing hook is only reported
+    if let Err(e) = hooks::run(
+        "post-remove",
+        &post_remove,
+        &context.main_worktree_root,
+        &hook_env,
+    ) {
+        warn!(branch = branch_name, error = %e, "cleanup:post-remove hook failed");
+        eprintln!("Warning: {:#}", e);
+    }
+
+    // A window of this worktree that no longer carries its name (e.g. renamed by
+    // hand) survives the kills above; strip workmux's status and hooks from it
+    // so they don't outlive the worktree. Windows already closed are skipped.
+    if !tagged_windows.is_empty() {
+        debug!(
+            windows = ?tagged_windows,
+            "cleanup:clearing workmux state from windows"
+        );
+        tmux::clear_window_state(&tagged_windows, handle);
+    }
+
     Ok(result)
 }
 
-/// Navigate to the target branch window and close the source window.
+/// Navigate according to `after_close` and close the source window.
 /// Handles both cases: running inside the source window (async) and outside (sync).
 /// `target_window_name` is the tmux window name of the merge target.
 /// `source_handle` is the tmux window name of the branch being merged/removed.
@@ -333,68 +460,53 @@ pub fn navigate_to_target_and_close(
     prefix: &str,
     target_window_name: &str,
     source_handle: &str,
+    after_close: AfterClose,
     cleanup_result: &CleanupResult,
 ) -> Result<()> {
-    /// Helper function to shell-escape strings for safe inclusion in shell commands
-    fn shell_escape(s: &str) -> String {
-        format!("'{}'", s.replace('\'', r#"'\''"#))
-    }
-
-    // Check if target window exists
-    if !tmux::is_running()? || !tmux::window_exists(prefix, target_window_name)? {
-        // If target window doesn't exist, still need to close source window if running inside it
-        if let Some(ref window_to_close) = cleanup_result.window_to_close_later {
-            let delay = Duration::from_millis(WINDOW_CLOSE_DELAY_MS);
-            let delay_secs = format!("{:.3}", delay.as_secs_f64());
-            let source_spec = format!("={}", window_to_close);
-            let source_escaped = shell_escape(&source_spec);
-            let script = format!(
-                "sleep {delay}; tmux kill-window -t {source} >/dev/null 2>&1",
-                delay = delay_secs,
-                source = source_escaped,
-            );
-            match tmux::run_shell(&script) {
-                Ok(_) => info!(window = window_to_close, "cleanup:scheduled window close"),
-                Err(e) => warn!(
-                    window = window_to_close,
-                    error = %e,
-                    "cleanup:failed to schedule window close",
-                ),
-            }
-        }
-        return Ok(());
-    }
+    let tmux_running = tmux::is_running()?;
+    let target_exists = tmux_running && tmux::window_exists(prefix, target_window_name)?;
 
     if let Some(ref window_to_close) = cleanup_result.window_to_close_later {
         // Running inside a matching window: schedule both navigation and kill together
-        let delay = Duration::from_millis(WINDOW_CLOSE_DELAY_MS);
-        let delay_secs = format!("{:.3}", delay.as_secs_f64());
-        let target_spec = format!("={}", tmux::prefixed(prefix, target_window_name));
-        let source_spec = format!("={}", window_to_close);
-        let target_escaped = shell_escape(&target_spec);
-        let source_escaped = shell_escape(&source_spec);
-        let script = format!(
-            "sleep {delay}; tmux select-window -t {target} >/dev/null 2>&1; tmux kill-window -t {source} >/dev/null 2>&1",
-            delay = delay_secs,
-            target = target_escaped,
-            source = source_escaped,
+        let navigate_to = if !tmux_running {
+            None
+        } else {
+            match after_close {
+                AfterClose::Main if target_exists => {
+                    Some(format!("={}", tmux::prefixed(prefix, target_window_name)))
+                }
+                AfterClose::Main | AfterClose::Stay => None,
+                AfterClose::Previous => tmux::find_adjacent_window(window_to_close, false)?,
+                AfterClose::Next => tmux::find_adjacent_window(window_to_close, true)?,
+            }
+        };
+
+        let script = close_window_script(
+            Duration::from_millis(WINDOW_CLOSE_DELAY_MS),
+            navigate_to.as_deref(),
+            &format!("={}", window_to_close),
+            tmux::uses_sessions(),
         );
 
         match tmux::run_shell(&script) {
-            Ok(_) => info!(
-                window = window_to_close,
-                target = target_window_name,
-                "cleanup:scheduled navigation to target and window close"
-            ),
+            Ok(_) => {
+                audit::record(AuditAction::WindowKilled, window_to_close, None);
+                info!(
+                    window = window_to_close,
+                    target = ?navigate_to,
+                    "cleanup:scheduled navigation and window close"
+                )
+            }
             Err(e) => warn!(
                 window = window_to_close,
                 error = %e,
                 "cleanup:failed to schedule navigation and window close",
             ),
         }
-    } else if !cleanup_result.tmux_window_killed {
-        // Running outside and windows weren't killed yet (shouldn't happen normally)
-        // but handle it for completeness
+    } else if target_exists && after_close == AfterClose::Main && !cleanup_result.tmux_window_killed
+    {
+        // Running outside the source window. Only `main` moves focus here; the other
+        // policies leave the user in the window they ran the command from.
         tmux::select_window(prefix, target_window_name)?;
         info!(
             handle = source_handle,
@@ -405,3 +517,115 @@ pub fn navigate_to_target_and_close(
 
     Ok(())
 }
+
+/// Build the deferred script that optionally selects `navigate_to` and then kills
+/// `source`. Both are tmux target specs (e.g. `=wm-main` or `@3`). With
+/// `sessions`, they are sessions, and the client switches to the last session
+/// if there's nowhere else to go, rather than detaching with the one closed.
+fn close_window_script(
+    delay: Duration,
+    navigate_to: Option<&str>,
+    source: &str,
+    sessions: bool,
+) -> String {
+    /// Helper function to shell-escape strings for safe inclusion in shell commands
+    fn shell_escape(s: &str) -> String {
+        format!("'{}'", s.replace('\'', r#"'\''"#))
+    }
+
+    let (select, kill) = if sessions {
+        ("switch-client", "kill-session")
+    } else {
+        ("select-window", "kill-window")
+    };
+    let mut script = format!("sleep {:.3}; ", delay.as_secs_f64());
+    match navigate_to {
+        Some(target) => script.push_str(&format!(
+            "tmux {} -t {} >/dev/null 2>&1; ",
+            select,
+            shell_escape(target)
+        )),
+        None if sessions => script.push_str("tmux switch-client -l >/dev/null 2>&1; "),
+        None => {}
+    }
+    script.push_str(&format!(
+        "tmux {} -t {} >/dev/null 2>&1",
+        kill,
+        shell_escape(source)
+    ));
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renaming_a_mount_point_is_recognized() {
+        let repo = tempfile::tempdir().unwrap();
+
+        // Only the errors a mount point gives lead to deleting in place
+        for kind in [
+            std::io::ErrorKind::ResourceBusy,
+            std::io::ErrorKind::CrossesDevices,
+        ] {
+            assert!(is_mount_point_error(&std::io::Error::from(kind)));
+        }
+        let missing = std::fs::rename(repo.path().join("gone"), repo.path().join("trash"));
+        assert!(!is_mount_point_error(&missing.unwrap_err()));
+
+        // Linux refuses to rename a mount point, such as /proc
+        #[cfg(target_os = "linux")]
+        {
+            let busy = std::fs::rename("/proc", repo.path().join("trash"));
+            assert!(is_mount_point_error(&busy.unwrap_err()));
+        }
+    }
+
+    #[test]
+    fn test_close_window_script_with_navigation() {
+        let script = close_window_script(
+            Duration::from_millis(300),
+            Some("=wm-main"),
+            "=wm-feature",
+            false,
+        );
+        assert_eq!(
+            script,
+            "sleep 0.300; tmux select-window -t '=wm-main' >/dev/null 2>&1; \
+             tmux kill-window -t '=wm-feature' >/dev/null 2>&1"
+        );
+    }
+
+    #[test]
+    fn test_close_window_script_without_navigation() {
+        let script = close_window_script(Duration::from_millis(300), None, "=wm-feature", false);
+        assert_eq!(
+            script,
+            "sleep 0.300; tmux kill-window -t '=wm-feature' >/dev/null 2>&1"
+        );
+    }
+
+    #[test]
+    fn test_close_window_script_escapes_quotes() {
+        let script = close_window_script(Duration::from_millis(300), None, "=it's", false);
+        assert!(script.ends_with(r#"-t '=it'\''s' >/dev/null 2>&1"#));
+    }
+
+    #[test]
+    fn test_close_window_script_for_sessions() {
+        let script =
+            close_window_script(Duration::from_millis(300), Some("$2"), "=wm-feature", true);
+        assert_eq!(
+            script,
+            "sleep 0.300; tmux switch-client -t '$2' >/dev/null 2>&1; \
+             tmux kill-session -t '=wm-feature' >/dev/null 2>&1"
+        );
+        let script = close_window_script(Duration::from_millis(300), None, "=wm-feature", true);
+        assert_eq!(
+            script,
+            "sleep 0.300; tmux switch-client -l >/dev/null 2>&1; \
+             tmux kill-session -t '=wm-feature' >/dev/null 2>&1"
+        );
+    }
+}
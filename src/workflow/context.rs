@@ -1,7 +1,8 @@
 use anyhow::{Context, Result, anyhow};
 use std::path::PathBuf;
 
-use crate::{config, git, tmux};
+use crate::config::WorktreeDir;
+use crate::{config, git, remote, template, tmux};
 use tracing::debug;
 
 /// Shared context for workflow operations
@@ -32,7 +33,8 @@ impl WorkflowContext {
         let main_branch = if let Some(ref branch) = config.main_branch {
             branch.clone()
         } else {
-            git::get_default_branch().context("Failed to determine the main branch")?
+            git::get_default_branch(config.default_remote())
+                .context("Failed to determine the main branch")?
         };
 
         let prefix = config.window_prefix().to_string();
@@ -69,7 +71,13 @@ impl WorkflowContext {
     /// This is necessary for destructive operations (merge, remove) to prevent
     /// "Unable to read current working directory" errors when the command is run
     /// from within a worktree that is about to be deleted.
+    ///
+    /// With `--host` the worktrees are on the remote host, and commands run
+    /// there in their own directory, so the local one is left alone.
     pub fn chdir_to_main_worktree(&self) -> Result<()> {
+        if remote::get().is_some() {
+            return Ok(());
+        }
         debug!(
             safe_cwd = %self.main_worktree_root.display(),
             "workflow_context:changing to main worktree"
@@ -81,4 +89,97 @@ impl WorkflowContext {
             )
         })
     }
+
+    /// Directory new worktrees are created in, from `worktree_dir` in the
+    /// config (`<project>__worktrees` next to the main worktree by default)
+    ///
+    /// Based on the main worktree (not the current one) so paths are the same
+    /// when running from inside a worktree.
+    pub fn worktree_base_dir(&self) -> Result<PathBuf> {
+        let project_name = self
+            .main_worktree_root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("Could not determine project name"))?;
+        match &self.config.worktree_dir {
+            None | Some(WorktreeDir::Sibling) => Ok(self
+                .main_worktree_root
+                .parent()
+                .ok_or_else(|| anyhow!("Could not determine parent directory"))?
+                .join(format!("{}__worktrees", project_name))),
+            // An absolute path is used as is
+            Some(WorktreeDir::Subdir(dir) | WorktreeDir::Path(dir)) => {
+                Ok(self.main_worktree_root.join(dir))
+            }
+            Some(WorktreeDir::Global(path)) => global_base_dir(path, project_name),
+        }
+    }
+}
+
+/// Stands in for the handle when rendering a `global:` path, to find the
+/// directory the handles go in
+const HANDLE_PLACEHOLDER: &str = "workmux-handle";
+
+/// The directory of a `global:` path template: everything before
+/// `{{ handle }}`, or the whole path if it doesn't use the handle
+fn global_base_dir(path: &str, project_name: &str) -> Result<PathBuf> {
+    let context = serde_json::json!({ "repo": project_name, "handle": HANDLE_PLACEHOLDER });
+    let rendered = template::render_path(path, &context)
+        .with_context(|| format!("Invalid worktree_dir 'global:{}'", path))?;
+    let rendered = PathBuf::from(expand_home(&rendered)?);
+
+    let uses_handle = template::missing_template_variables(
+        &template::create_template_env(),
+        path,
+        &serde_json::json!({ "repo": project_name }),
+    )?
+    .iter()
+    .any(|var| var == "handle");
+    if !uses_handle {
+        return Ok(rendered);
+    }
+    match rendered.file_name().and_then(|name| name.to_str()) {
+        Some(HANDLE_PLACEHOLDER) => Ok(rendered.parent().unwrap_or(&rendered).to_path_buf()),
+        _ => Err(anyhow!(
+            "Invalid worktree_dir 'global:{}': {{{{ handle }}}} must be the last part of the path",
+            path
+        )),
+    }
+}
+
+/// `path` with a leading `~` replaced by the home directory
+pub(super) fn expand_home(path: &str) -> Result<String> {
+    let Some(rest) = path.strip_prefix('~') else {
+        return Ok(path.to_string());
+    };
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return Ok(path.to_string());
+    }
+    if remote::get().is_some() {
+        return Err(anyhow!(
+            "worktree_dir '{}' starts with ~, which can't be expanded with --host; use an absolute path",
+            path
+        ));
+    }
+    let home = home::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    Ok(format!("{}{}", home.display(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_worktree_dir_is_the_path_before_the_handle() {
+        assert_eq!(
+            global_base_dir("/wt/{{ repo }}/{{ handle }}", "app").unwrap(),
+            PathBuf::from("/wt/app")
+        );
+        assert_eq!(
+            global_base_dir("/wt/{{ repo }}", "app").unwrap(),
+            PathBuf::from("/wt/app")
+        );
+        assert!(global_base_dir("/wt/{{ handle }}/{{ repo }}", "app").is_err());
+        assert!(global_base_dir("/wt/{{ project }}", "app").is_err());
+    }
 }
@@ -1,12 +1,23 @@
 use anyhow::{Context, Result, anyhow};
-use std::path::Path;
-
-use crate::{git, spinner, tmux};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::cmd::Cmd;
+use crate::config::{self, BranchDescription, Config, FetchPolicy, FetchTrigger};
+use crate::prompt::{self, Prompt};
+use crate::worktree_state::{self, WorktreeState};
+use crate::{dry_run, git, interrupt, llm, offline, remote, spinner, template, tmux};
 use tracing::{debug, info, warn};
 
 /// Check if a path is registered as a git worktree.
 /// Uses canonicalize() to handle symlinks, case sensitivity, and relative paths.
 fn is_registered_worktree(path: &Path) -> Result<bool> {
+    // With --host the paths are on the remote host, where git reports them
+    if remote::get().is_some() {
+        return Ok(git::list_worktrees()?
+            .iter()
+            .any(|(wt_path, _)| wt_path == path));
+    }
     // Canonicalize the input path for reliable comparison
     let abs_path = match std::fs::canonicalize(path) {
         Ok(p) => p,
@@ -30,8 +41,22 @@ fn is_registered_worktree(path: &Path) -> Result<bool> {
 
 use super::cleanup;
 use super::context::WorkflowContext;
+use super::hooks::{self, HookEnv};
+use super::ignore::{IGNORE_FILE, WorkmuxIgnore};
 use super::setup;
-use super::types::{CreateArgs, CreateResult, SetupOptions};
+use super::types::{ChangesTarget, CreateArgs, CreateResult, SetupOptions};
+
+/// Held while a worktree being created checks and writes the repository.
+/// Worktrees created in parallel (`add --parallel`) take turns at these steps,
+/// since git locks the config and refs they write, and run their hooks and
+/// set up their windows at the same time.
+static GIT_TURN: Mutex<()> = Mutex::new(());
+
+fn git_turn() -> MutexGuard<'static, ()> {
+    GIT_TURN
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
 /// Create a new worktree with tmux window and panes
 pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResult> {
@@ -43,6 +68,9 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         prompt,
         options,
         agent,
+        pr,
+        review,
+        detach,
     } = args;
 
     info!(
@@ -55,11 +83,12 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
 
     // Validate pane config before any other operations
     if let Some(panes) = &context.config.panes {
-        crate::config::validate_panes_config(panes)?;
+        panes.validate()?;
     }
 
     // Pre-flight checks
     context.ensure_tmux_running()?;
+    let turn = git_turn();
 
     // Check tmux window using handle (the display name)
     if tmux::window_exists(&context.prefix, handle)? {
@@ -71,7 +100,7 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
     }
 
     // Check if branch already has a worktree
-    if git::worktree_exists(branch_name)? {
+    if !detach && git::worktree_exists(branch_name)? {
         return Err(anyhow!(
             "A worktree for branch '{}' already exists. Use 'workmux open {}' to open it.",
             branch_name,
@@ -79,15 +108,16 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         ));
     }
 
-    // Auto-detect: create branch if it doesn't exist
-    let branch_exists = git::branch_exists(branch_name)?;
+    // Auto-detect: create branch if it doesn't exist. A detached worktree
+    // has none of its own, so a local branch of the same name doesn't matter.
+    let branch_exists = !detach && git::branch_exists(branch_name)?;
     if branch_exists && remote_branch.is_some() {
         return Err(anyhow!(
             "Branch '{}' already exists. Remove '--remote' or pick a different branch name.",
             branch_name
         ));
     }
-    let create_new = !branch_exists;
+    let create_new = !branch_exists && !detach;
     let mut track_upstream = false;
     debug!(
         branch = branch_name,
@@ -104,11 +134,28 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
                 git::list_remotes()?
             ));
         }
-        spinner::with_spinner(&format!("Fetching from '{}'", spec.remote), || {
-            git::fetch_remote(&spec.remote)
-        })
-        .with_context(|| format!("Failed to fetch from remote '{}'", spec.remote))?;
         let remote_ref = format!("{}/{}", spec.remote, spec.branch);
+        // A recent fetch doesn't help if this remote (e.g. a new fork remote) was never
+        // fetched, so the interval policy still fetches when the ref is missing.
+        let fetch = context.config.should_fetch(FetchTrigger::Add)
+            || (context.config.fetch == Some(FetchPolicy::Interval)
+                && !git::branch_exists(&remote_ref)?);
+        if offline::is_enabled() {
+            eprintln!(
+                "Offline: not fetching from '{}', using existing remote-tracking refs",
+                spec.remote
+            );
+        } else if fetch {
+            spinner::with_spinner(&format!("Fetching from '{}'", spec.remote), || {
+                git::fetch_remote(&spec.remote)
+            })
+            .with_context(|| format!("Failed to fetch from remote '{}'", spec.remote))?;
+        } else {
+            debug!(
+                remote = spec.remote,
+                "create:skipping fetch per fetch policy"
+            );
+        }
         if !git::branch_exists(&remote_ref)? {
             return Err(anyhow!(
                 "Remote branch '{}' was not found. Double-check the name or fetch it manually.",
@@ -140,36 +187,12 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         None
     };
 
-    // Determine worktree path: use config.worktree_dir or default to <project>__worktrees pattern
-    // Always use main_worktree_root (not repo_root) to ensure consistent paths even when
-    // running from inside an existing worktree.
-    let base_dir = if let Some(ref worktree_dir) = context.config.worktree_dir {
-        let path = Path::new(worktree_dir);
-        if path.is_absolute() {
-            // Use absolute path as-is
-            path.to_path_buf()
-        } else {
-            // Relative path: resolve from main worktree root
-            context.main_worktree_root.join(path)
-        }
-    } else {
-        // Default behavior: <main_worktree_root>/../<project_name>__worktrees
-        let project_name = context
-            .main_worktree_root
-            .file_name()
-            .and_then(|n| n.to_str())
-            .ok_or_else(|| anyhow!("Could not determine project name"))?;
-        context
-            .main_worktree_root
-            .parent()
-            .ok_or_else(|| anyhow!("Could not determine parent directory"))?
-            .join(format!("{}__worktrees", project_name))
-    };
+    let base_dir = context.worktree_base_dir()?;
     // Use handle for the worktree directory name (not branch_name)
     let worktree_path = base_dir.join(handle);
 
     // Check if path already exists (handle collision detection)
-    if worktree_path.exists() {
+    if remote::path_exists(&worktree_path) {
         // Check if this is an orphan directory (exists on disk but not registered with git).
         // This can happen when cleanup renames a worktree but a background process (build tool,
         // file watcher, shell prompt) recreates the directory structure using stale $PWD.
@@ -184,7 +207,7 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
 
         // Safety check: if the directory contains a .git file/folder, it might be a
         // corrupted worktree or a manual clone. Don't auto-delete to prevent data loss.
-        if worktree_path.join(".git").exists() {
+        if remote::path_exists(&worktree_path.join(".git")) {
             return Err(anyhow!(
                 "Directory '{}' exists and contains a .git resource, but is not registered.\n\
                  This looks like a repository or worktree with corrupted metadata.\n\
@@ -193,23 +216,72 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
             ));
         }
 
-        // It's an orphan directory (not registered with git) - safe to remove.
-        // This typically happens when cleanup renames a worktree but a background process
-        // (build tool, file watcher) recreates files using stale $PWD paths.
-        // Since it's not a registered worktree, any files are just build artifacts.
+        // git checks out into an empty directory, so leave one be. It may be a
+        // mount point (e.g. a scratch disk mounted for the worktree), which
+        // can't be removed anyway.
+        let is_empty = remote::dir_is_empty(&worktree_path);
+
+        if !is_empty && !dry_run::skip(format_args!("rm -rf {}", worktree_path.display())) {
+            // It's an orphan directory (not registered with git) - safe to remove.
+            // This typically happens when cleanup renames a worktree but a background process
+            // (build tool, file watcher) recreates files using stale $PWD paths.
+            // Since it's not a registered worktree, any files are just build artifacts.
+            info!(
+                path = %worktree_path.display(),
+                "create:removing orphan directory from previous cleanup"
+            );
+            let removed = if remote::get().is_some() {
+                Cmd::new("rm")
+                    .args(&["-rf", &worktree_path.to_string_lossy()])
+                    .run()
+                    .map(|_| ())
+            } else {
+                std::fs::remove_dir_all(&worktree_path).map_err(Into::into)
+            };
+            removed.with_context(|| {
+                format!(
+                    "Failed to remove orphan directory '{}'. Please remove it manually.",
+                    worktree_path.display()
+                )
+            })?;
+        }
+    }
+
+    drop(turn);
+
+    // Last chance to stop before anything is created. The worktree doesn't
+    // exist yet, so hooks run in the main worktree and `when` checks it.
+    let pre_create = config::hooks_for(
+        &context.config.pre_create,
+        &config::WhenTarget {
+            branch: branch_name,
+            agent: agent.or(context.config.agent.as_deref()),
+            worktree_path: &context.main_worktree_root,
+        },
+    );
+    if options.run_hooks && !pre_create.is_empty() {
+        let hook_env = HookEnv::new(
+            handle,
+            branch_name,
+            &worktree_path,
+            &context.main_worktree_root,
+        )
+        .with_template_context(options.template_context.as_ref());
+        hooks::run(
+            "pre-create",
+            &pre_create,
+            &context.main_worktree_root,
+            &hook_env,
+        )?;
         info!(
-            path = %worktree_path.display(),
-            "create:removing orphan directory from previous cleanup"
+            branch = branch_name,
+            total = pre_create.len(),
+            "create:pre-create hooks complete"
         );
-        std::fs::remove_dir_all(&worktree_path).with_context(|| {
-            format!(
-                "Failed to remove orphan directory '{}'. Please remove it manually.",
-                worktree_path.display()
-            )
-        })?;
     }
 
     // Create worktree
+    let turn = git_turn();
     info!(
         branch = branch_name,
         path = %worktree_path.display(),
@@ -218,17 +290,38 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         "create:creating worktree"
     );
 
-    git::create_worktree(
-        &worktree_path,
-        branch_name,
-        create_new,
-        base_branch_for_creation.as_deref(),
-        track_upstream,
-    )
+    let sparse = context.config.sparse.as_deref().unwrap_or_default();
+    if detach {
+        let commit = base_branch_for_creation.as_deref().ok_or_else(|| {
+            anyhow!("Nothing to check out: a detached worktree needs a remote branch")
+        })?;
+        git::create_detached_worktree(&worktree_path, commit, sparse)
+    } else {
+        git::create_worktree(
+            &worktree_path,
+            branch_name,
+            create_new,
+            base_branch_for_creation.as_deref(),
+            track_upstream,
+            sparse,
+        )
+    }
     .context("Failed to create git worktree")?;
 
+    // A merge request's head isn't a branch of the remote, so pulls need the
+    // ref it was fetched from
+    if let Some(remote_spec) = remote_branch.filter(|_| create_new)
+        && let Some(merge_ref) = git::parse_remote_branch_spec(remote_spec)
+            .ok()
+            .and_then(|spec| git::merge_request_head(&spec.branch))
+    {
+        git::set_branch_merge_ref(branch_name, &merge_ref)?;
+    }
+
     // Store the base branch in git config for future reference (used during removal checks)
-    if let Some(ref base) = base_branch_for_creation {
+    if let Some(ref base) = base_branch_for_creation
+        && !detach
+    {
         git::set_branch_base(branch_name, base).with_context(|| {
             format!(
                 "Failed to store base branch '{}' for branch '{}'",
@@ -242,6 +335,79 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         );
     }
 
+    // Remember the template so merge and remove run its hooks too
+    if let Some(template) = &context.config.applied_template
+        && !detach
+    {
+        git::set_branch_template(branch_name, template)?;
+    }
+
+    if let Some(prompt) = prompt
+        && !detach
+    {
+        describe_branch(&context.config, branch_name, prompt);
+    }
+
+    // Record what the worktree was created from for `workmux status`
+    let prompt_content = prompt.and_then(|p| p.read_content().ok());
+    let prompt_file = match (&prompt_content, &context.config.save_prompt) {
+        (Some(content), Some(relative_path)) => {
+            match setup::save_prompt(&worktree_path, relative_path, content, &context.config)
+                .and_then(|()| {
+                    // It belongs to this worktree's task, not to the branch
+                    git::exclude_paths(&worktree_path, &[relative_path])
+                        .context("Failed to exclude the saved prompt from git")
+                }) {
+                Ok(()) => Some(relative_path.clone()),
+                Err(e) => {
+                    warn!(handle, error = %e, "create:failed to save prompt in worktree");
+                    eprintln!("Warning: {:#}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let state = WorktreeState {
+        base: base_branch_for_creation.clone(),
+        task: options
+            .task
+            .clone()
+            .or_else(|| prompt_content.as_deref().and_then(prompt::task_title)),
+        prompt: prompt_content,
+        prompt_file,
+        vars: options.template_context.clone().map(|mut context| {
+            // The PR's context is only meant for the prompt, and can be long
+            if let serde_json::Value::Object(map) = &mut context {
+                map.remove(template::PR_CONTEXT_VAR);
+                map.remove("foreach_vars");
+            }
+            context
+        }),
+        agent: agent
+            .or(context.config.agent.as_deref())
+            .map(str::to_string),
+        pr,
+        review,
+        ..WorktreeState::new(branch_name)
+    };
+    if let Err(e) = worktree_state::save(handle, &state, &context.config) {
+        warn!(handle, error = %e, "create:failed to record worktree state");
+    }
+
+    // Apply per-worktree git config before hooks run, so they see it too
+    if let Some(values) = context.config.git_config.as_ref().filter(|v| !v.is_empty()) {
+        git::set_worktree_config(&worktree_path, values)
+            .context("Failed to apply git_config to the new worktree")?;
+        debug!(count = values.len(), "create:applied worktree git config");
+    }
+    // Commits on a review worktree's detached HEAD would be on no branch
+    if detach {
+        git::refuse_detached_commits(&worktree_path)
+            .context("Failed to make the review worktree read-only")?;
+    }
+    drop(turn);
+
     // Setup the rest of the environment (tmux, files, hooks)
     let prompt_file_path = if let Some(p) = prompt {
         Some(setup::write_prompt_file(branch_name, p)?)
@@ -254,14 +420,25 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
         prompt_file_path,
         ..options
     };
-    let mut result = setup::setup_environment(
+    let mut result = match setup::setup_environment(
         branch_name,
         handle,
         &worktree_path,
         &context.config,
         &options_with_prompt,
         agent,
-    )?;
+    ) {
+        Ok(result) => result,
+        // Ctrl-C during file operations or hooks: leave nothing half-created
+        Err(e) if interrupt::is_interruption(&e) => {
+            roll_back_creation(&worktree_path, branch_name, create_new);
+            return Err(e.context(format!(
+                "Cancelled: removed the partially created worktree '{}'",
+                handle
+            )));
+        }
+        Err(e) => return Err(e),
+    };
     result.base_branch = base_branch_for_creation.clone();
     info!(
         branch = branch_name,
@@ -272,18 +449,89 @@ pub fn create(context: &WorkflowContext, args: CreateArgs) -> Result<CreateResul
     Ok(result)
 }
 
-/// Create a new worktree and move uncommitted changes from the current worktree into it.
+/// Store the prompt, or a summary of it, as the branch description, unless
+/// the branch already has one. Failures are logged, not returned.
+fn describe_branch(config: &Config, branch_name: &str, prompt: &Prompt) {
+    let mode = config.branch_description();
+    if mode == BranchDescription::Off || git::branch_descriptions().contains_key(branch_name) {
+        return;
+    }
+    let Ok(content) = prompt.read_content() else {
+        return;
+    };
+    let model = config.auto_name.as_ref().and_then(|c| c.model.as_deref());
+    let can_summarize = mode == BranchDescription::Summarize
+        && !offline::is_enabled()
+        && which::which("llm").is_ok();
+    let summarize = |text: &str| {
+        if !can_summarize {
+            return None;
+        }
+        spinner::with_spinner("Summarizing prompt for the branch description", || {
+            llm::summarize_prompt(text, model)
+        })
+        .inspect_err(|e| warn!(error = %e, "create:failed to summarize prompt"))
+        .ok()
+    };
+    let Some(description) = prompt::describe(&content, summarize) else {
+        return;
+    };
+    match git::set_branch_description(branch_name, &description) {
+        Ok(()) => debug!(branch = branch_name, "create:stored branch description"),
+        Err(e) => warn!(error = %e, "create:failed to store branch description"),
+    }
+}
+
+/// Undo a cancelled worktree creation: remove the worktree and, if it was
+/// created along with the worktree, the branch. Best-effort.
+fn roll_back_creation(worktree_path: &Path, branch_name: &str, created_branch: bool) {
+    info!(path = %worktree_path.display(), branch = branch_name, "create:rolling back");
+    if let Err(e) = git::remove_worktree(worktree_path) {
+        warn!(error = %e, "create:failed to remove worktree during rollback");
+    }
+    if created_branch && let Err(e) = git::delete_branch(branch_name, true) {
+        warn!(error = %e, "create:failed to delete branch during rollback");
+    }
+}
+
+/// Untracked paths of the worktree at `root`, split into those to move and
+/// those `ignore` keeps behind. A directory holding nothing tracked is one
+/// path, and is only gone through file by file when it isn't ignored whole.
+fn partition_untracked(
+    root: &Path,
+    ignore: &WorkmuxIgnore,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let (mut movable, mut ignored) = (Vec::new(), Vec::new());
+    for (path, is_dir) in git::list_untracked_entries(root)? {
+        if ignore.is_ignored(&path, is_dir) {
+            ignored.push(path);
+        } else if is_dir && !ignore.is_empty() {
+            for file in git::list_untracked_files_in(root, &path)? {
+                if ignore.is_ignored(&file, false) {
+                    ignored.push(file);
+                } else {
+                    movable.push(file);
+                }
+            }
+        } else {
+            movable.push(path);
+        }
+    }
+    Ok((movable, ignored))
+}
+
+/// Create new worktrees and move uncommitted changes from the current worktree
+/// into them. Each target gets its own copy of the changes; if any worktree
+/// can't be created or take the changes, the ones already created are removed.
 pub fn create_with_changes(
-    branch_name: &str,
-    handle: &str,
+    targets: &[ChangesTarget],
     include_untracked: bool,
     patch: bool,
-    context: &WorkflowContext,
     options: SetupOptions,
-) -> Result<CreateResult> {
+) -> Result<Vec<CreateResult>> {
+    let branches: Vec<&str> = targets.iter().map(|t| t.branch_name.as_str()).collect();
     info!(
-        branch = branch_name,
-        handle = handle,
+        branches = ?branches,
         include_untracked,
         patch,
         "create_with_changes:start"
@@ -293,101 +541,169 @@ pub fn create_with_changes(
     let original_worktree_path = std::env::current_dir()
         .context("Failed to get current working directory to rescue changes from")?;
 
+    // Untracked files matched by .workmuxignore stay behind
+    let (movable_untracked, ignored_untracked) = if include_untracked && !patch {
+        let root = git::get_repo_root()?;
+        let ignore = WorkmuxIgnore::load(&git::get_main_worktree_root()?)?;
+        partition_untracked(&root, &ignore)?
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
     // Check for changes based on the include_untracked flag
     let has_tracked_changes = git::has_tracked_changes(&original_worktree_path)?;
-    let has_movable_untracked =
-        include_untracked && git::has_untracked_files(&original_worktree_path)?;
+    let has_movable_untracked = include_untracked
+        && if patch {
+            git::has_untracked_files(&original_worktree_path)?
+        } else {
+            !movable_untracked.is_empty()
+        };
 
     if !has_tracked_changes && !has_movable_untracked {
         return Err(anyhow!(
             "No uncommitted changes to move. Use 'workmux add {}' to create a clean worktree.",
-            branch_name
+            branches.join(" ")
         ));
     }
 
-    if git::branch_exists(branch_name)? {
-        return Err(anyhow!("Branch '{}' already exists.", branch_name));
+    for branch_name in &branches {
+        if git::branch_exists(branch_name)? {
+            return Err(anyhow!("Branch '{}' already exists.", branch_name));
+        }
     }
 
     // 1. Stash changes
-    let stash_message = format!("workmux: moving changes to {}", branch_name);
-    git::stash_push(&stash_message, include_untracked, patch)
+    let stash_message = format!("workmux: moving changes to {}", branches.join(", "));
+    git::stash_push(&stash_message, include_untracked, patch, &ignored_untracked)
         .context("Failed to stash current changes")?;
-    info!(branch = branch_name, "create_with_changes: changes stashed");
-
-    // 2. Create new worktree
-    let create_result = match create(
-        context,
-        CreateArgs {
-            branch_name,
-            handle,
-            base_branch: None,
-            remote_branch: None,
-            prompt: None,
-            options,
-            agent: None,
-        },
-    ) {
-        Ok(result) => result,
-        Err(e) => {
-            warn!(error = %e, "create_with_changes: worktree creation failed, popping stash");
-            // Best effort to restore the stash - if this fails, user still has stash@{0}
-            let _ = git::stash_pop(&original_worktree_path);
-            return Err(e).context(
-                "Failed to create new worktree. Stashed changes have been restored if possible.",
-            );
-        }
-    };
-
-    let new_worktree_path = &create_result.worktree_path;
-    info!(
-        path = %new_worktree_path.display(),
-        "create_with_changes: worktree created"
-    );
+    if !ignored_untracked.is_empty() {
+        println!(
+            "Leaving {} untracked path(s) matched by {} behind",
+            ignored_untracked.len(),
+            IGNORE_FILE
+        );
+    }
+    info!(branches = ?branches, "create_with_changes: changes stashed");
+
+    let mut created: Vec<(&ChangesTarget, CreateResult)> = Vec::new();
+    for target in targets {
+        // 2. Create new worktree
+        let create_result = match create(
+            &target.context,
+            CreateArgs {
+                branch_name: &target.branch_name,
+                handle: &target.handle,
+                base_branch: None,
+                remote_branch: None,
+                prompt: None,
+                options: options.clone(),
+                agent: None,
+                pr: None,
+                review: false,
+                detach: false,
+            },
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(error = %e, "create_with_changes: worktree creation failed, popping stash");
+                roll_back_changes_targets(&created)?;
+                // Best effort to restore the stash - if this fails, user still has stash@{0}
+                let _ = git::stash_pop(&original_worktree_path);
+                return Err(e).context(
+                    "Failed to create new worktree. Stashed changes have been restored if possible.",
+                );
+            }
+        };
 
-    // 3. Apply stash in new worktree
-    match git::stash_pop(new_worktree_path) {
-        Ok(_) => {
-            // 4. Success: Clean up original worktree
-            info!("create_with_changes: stash applied successfully, cleaning original worktree");
-            git::reset_hard(&original_worktree_path)?;
+        info!(
+            path = %create_result.worktree_path.display(),
+            "create_with_changes: worktree created"
+        );
 
-            info!(
-                branch = branch_name,
-                "create_with_changes: completed successfully"
-            );
-            Ok(create_result)
-        }
-        Err(e) => {
+        // 3. Apply stash in new worktree, keeping it for the next one
+        let applied = git::stash_apply(&create_result.worktree_path);
+        created.push((target, create_result));
+        if let Err(e) = applied {
             // 5. Failure: Rollback
             warn!(error = %e, "create_with_changes: failed to apply stash, rolling back");
-
-            let cleanup_result = cleanup::cleanup(
-                context,
-                branch_name,
-                handle,
-                &create_result.worktree_path,
-                true,  // force
-                false, // keep_branch
-            )
-            .context(
-                "Rollback failed: could not clean up the new worktree. Please do so manually.",
-            )?;
-
-            // Handle tmux window navigation/closing based on whether we're inside the source window
-            cleanup::navigate_to_target_and_close(
-                &context.prefix,
-                &context.main_branch,
-                handle,
-                &cleanup_result,
-            )?;
-
-            Err(anyhow!(
+            roll_back_changes_targets(&created)?;
+            return Err(anyhow!(
                 "Could not apply changes to '{}', likely due to conflicts.\n\n\
                 The new worktree has been removed.\n\
                 Your changes are safe in the latest stash. Run 'git stash pop' manually to resolve.",
-                branch_name
-            ))
+                target.branch_name
+            ));
+        }
+    }
+
+    // 4. Success: Clean up original worktree
+    info!("create_with_changes: stash applied successfully, cleaning original worktree");
+    git::stash_drop()?;
+    git::reset_hard(&original_worktree_path)?;
+
+    info!(branches = ?branches, "create_with_changes: completed successfully");
+    Ok(created.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Remove the worktrees and windows `create_with_changes` created before failing
+fn roll_back_changes_targets(created: &[(&ChangesTarget, CreateResult)]) -> Result<()> {
+    for (target, result) in created {
+        let context = &target.context;
+        let cleanup_result = cleanup::cleanup(
+            context,
+            &target.branch_name,
+            &target.handle,
+            &result.worktree_path,
+            true,  // force
+            false, // keep_branch
+        )
+        .context("Rollback failed: could not clean up the new worktree. Please do so manually.")?;
+
+        // Handle tmux window navigation/closing based on whether we're inside the source window
+        cleanup::navigate_to_target_and_close(
+            &context.prefix,
+            &context.main_branch,
+            &target.handle,
+            context.config.after_close.unwrap_or_default(),
+            &cleanup_result,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn ignored_untracked_directories_stay_behind_whole() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = repo.path();
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["init", "--quiet"])
+            .status()
+            .unwrap();
+        fs::write(root.join(IGNORE_FILE), "data/\n*.log\n").unwrap();
+        for file in ["data/a/1.bin", "data/2.bin", "src/main.rs", "src/build.log"] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
         }
+        let ignore = WorkmuxIgnore::load(root).unwrap();
+
+        let (mut movable, mut ignored) = partition_untracked(root, &ignore).unwrap();
+        movable.sort();
+        ignored.sort();
+        assert_eq!(
+            movable,
+            [PathBuf::from(IGNORE_FILE), PathBuf::from("src/main.rs")]
+        );
+        assert_eq!(
+            ignored,
+            [PathBuf::from("data"), PathBuf::from("src/build.log")]
+        );
     }
 }
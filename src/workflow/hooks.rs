@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use tracing::info;
+
+use crate::config::HookCommand;
+use crate::{cmd, template, worktree_state};
+
+/// What hooks run for a worktree are told about it: environment variables,
+/// and a JSON document on stdin for scripts that need more
+pub struct HookEnv {
+    handle: String,
+    branch: String,
+    worktree_path: String,
+    project_root: String,
+    base: Option<String>,
+    agent: Option<String>,
+    pr: Option<u32>,
+    prompt_file: Option<String>,
+    target_branch: Option<String>,
+    /// Template variables of the worktree (`num`, `--foreach` values, `--var`s)
+    template_context: Option<serde_json::Value>,
+}
+
+impl HookEnv {
+    /// Paths are passed on absolute, with symlinks resolved where they exist.
+    /// The base, agent, PR, saved prompt and template variables come from the
+    /// worktree's recorded state.
+    pub fn new(handle: &str, branch: &str, worktree_path: &Path, project_root: &Path) -> Self {
+        let state = worktree_state::load(handle).ok().flatten();
+        Self {
+            handle: handle.to_string(),
+            branch: branch.to_string(),
+            worktree_path: absolute(worktree_path),
+            project_root: absolute(project_root),
+            base: worktree_state::base_branch(handle, branch),
+            agent: state.as_ref().and_then(|state| state.agent.clone()),
+            pr: state.as_ref().and_then(|state| state.pr),
+            prompt_file: state
+                .as_ref()
+                .and_then(|state| state.prompt_file.as_ref())
+                .map(|file| absolute(&worktree_path.join(file))),
+            target_branch: None,
+            template_context: state.and_then(|state| state.vars),
+        }
+    }
+
+    /// Template variables of a worktree being created, before they are
+    /// recorded in its state
+    pub fn with_template_context(mut self, context: Option<&serde_json::Value>) -> Self {
+        if let Some(context) = context {
+            self.template_context = Some(context.clone());
+        }
+        self
+    }
+
+    /// The file the agent's prompt was written to, for hooks of a new worktree
+    pub fn with_prompt_file(mut self, path: Option<&Path>) -> Self {
+        if let Some(path) = path {
+            self.prompt_file = Some(absolute(path));
+        }
+        self
+    }
+
+    /// The branch being merged into, also passed as `WM_TARGET_BRANCH`
+    pub fn with_target_branch(mut self, branch: &str) -> Self {
+        self.target_branch = Some(branch.to_string());
+        self
+    }
+
+    pub fn vars(&self) -> Vec<(&str, &str)> {
+        let mut vars = vec![
+            ("WORKMUX_HANDLE", self.handle.as_str()),
+            ("WM_HANDLE", &self.handle),
+            ("WM_BRANCH_NAME", &self.branch),
+            ("WM_WORKTREE_PATH", &self.worktree_path),
+            ("WM_PROJECT_ROOT", &self.project_root),
+        ];
+        if let Some(target_branch) = &self.target_branch {
+            vars.push(("WM_TARGET_BRANCH", target_branch));
+        }
+        vars
+    }
+
+    /// `command` with `{{ branch }}`, `{{ handle }}`, `{{ worktree_path }}`,
+    /// `{{ agent }}` and any template variables filled in
+    pub fn render(&self, command: &str) -> Result<String> {
+        let mut context = self
+            .template_context
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({}));
+        if let serde_json::Value::Object(map) = &mut context {
+            map.insert("branch".to_string(), self.branch.clone().into());
+            map.insert("handle".to_string(), self.handle.clone().into());
+            map.insert(
+                "worktree_path".to_string(),
+                self.worktree_path.clone().into(),
+            );
+            map.insert("project_root".to_string(), self.project_root.clone().into());
+            // A worktree created for a named agent has it among its variables
+            if let Some(agent) = &self.agent
+                && map.get("agent").is_none_or(|value| value.is_null())
+            {
+                map.insert("agent".to_string(), agent.clone().into());
+            }
+            if let Some(target_branch) = &self.target_branch {
+                map.insert("target_branch".to_string(), target_branch.clone().into());
+            }
+        }
+        template::render_command(command, &context)
+    }
+
+    /// The JSON document piped to hooks of `phase` (e.g. `post_create`).
+    /// Every key is present, null when it doesn't apply.
+    pub fn input(&self, phase: &str) -> String {
+        serde_json::json!({
+            "hook": phase,
+            "handle": self.handle,
+            "branch": self.branch,
+            "worktree_path": self.worktree_path,
+            "project_root": self.project_root,
+            "base": self.base,
+            "agent": self.agent,
+            "prompt_file": self.prompt_file,
+            "pr": self.pr,
+            "target_branch": self.target_branch,
+        })
+        .to_string()
+    }
+}
+
+fn absolute(path: &Path) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Run the hooks of `phase` (e.g. `post-merge`) in `workdir`, one after the
+/// other, stopping at the first that fails
+pub fn run(phase: &str, hooks: &[&HookCommand], workdir: &Path, env: &HookEnv) -> Result<()> {
+    let input = env.input(&phase.replace('-', "_"));
+    for (idx, hook) in hooks.iter().enumerate() {
+        info!(command = %hook, "Running {} hook {}/{}", phase, idx + 1, hooks.len());
+        cmd::shell_command_with_env(
+            &env.render(hook.command())?,
+            workdir,
+            &env.vars(),
+            Some(&input),
+            hook.timeout(),
+        )
+        .with_context(|| format!("Failed to run {} command: '{}'", phase, hook))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_passes_the_environment_and_stops_at_a_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = HookEnv::new("login", "feature/login", dir.path(), dir.path())
+            .with_target_branch("main");
+        let hooks = [
+            HookCommand::Command(
+                "echo \"$WM_HANDLE $WM_BRANCH_NAME $WM_TARGET_BRANCH\" > out".to_string(),
+            ),
+            HookCommand::Command("cat > input.json".to_string()),
+            HookCommand::Command("exit 3".to_string()),
+            HookCommand::Command("touch never".to_string()),
+        ];
+        let hooks: Vec<&HookCommand> = hooks.iter().collect();
+
+        let error = run("post-merge", &hooks, dir.path(), &env).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Failed to run post-merge command: 'exit 3'")
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("out")).unwrap(),
+            "login feature/login main\n"
+        );
+        let input: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("input.json")).unwrap())
+                .unwrap();
+        assert_eq!(input["hook"], "post_merge");
+        assert_eq!(input["branch"], "feature/login");
+        assert_eq!(input["target_branch"], "main");
+        assert!(input["prompt_file"].is_null());
+        assert!(!dir.path().join("never").exists());
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Component, Path};
+
+/// File in the repository root listing paths workmux never brings into a new
+/// worktree, in gitignore syntax
+pub const IGNORE_FILE: &str = ".workmuxignore";
+
+/// The rules of a `.workmuxignore`. Unlike `.gitignore`, they only decide
+/// what `add --with-changes` moves and `files.copy`/`files.symlink` bring
+/// along; git sees the files as usual.
+#[derive(Debug, Default)]
+pub struct WorkmuxIgnore {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    glob: glob::Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole path from the root, rather than any name
+    anchored: bool,
+}
+
+impl WorkmuxIgnore {
+    /// The rules in `root`'s `.workmuxignore`, none if it doesn't have one
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(IGNORE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let pattern = line.strip_prefix('/').unwrap_or(line);
+            let glob = glob::Pattern::new(pattern)
+                .with_context(|| format!("Invalid {} pattern '{}'", IGNORE_FILE, line))?;
+            rules.push(Rule {
+                glob,
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether `relative_path` is ignored, itself or through a directory it
+    /// is in. As with gitignore, a file in an ignored directory can't be
+    /// brought back with `!`.
+    pub fn is_ignored(&self, relative_path: &Path, is_dir: bool) -> bool {
+        let parts: Vec<&str> = relative_path
+            .components()
+            .filter_map(|part| match part {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        (1..=parts.len()).any(|len| {
+            let path = parts[..len].join("/");
+            self.matches(&path, parts[len - 1], len < parts.len() || is_dir)
+        })
+    }
+
+    /// Whether the last rule matching `path` ignores it
+    fn matches(&self, path: &str, name: &str, is_dir: bool) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && if rule.anchored {
+                        rule.glob.matches_with(path, options)
+                    } else {
+                        rule.glob.matches_with(name, options)
+                    }
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_gitignore_syntax() {
+        let ignore = WorkmuxIgnore::parse(
+            "# large local files\n*.mp4\n!keep.mp4\ndata/\n/build\nassets/**/*.psd\n",
+        )
+        .unwrap();
+        let ignored = |path: &str, is_dir| ignore.is_ignored(Path::new(path), is_dir);
+
+        assert!(ignored("clip.mp4", false));
+        assert!(ignored("media/raw/clip.mp4", false));
+        assert!(!ignored("keep.mp4", false));
+        assert!(ignored("data", true));
+        assert!(!ignored("data", false));
+        assert!(ignored("data/train.csv", false));
+        assert!(ignored("src/data/train.csv", false));
+        assert!(ignored("build/out.bin", false));
+        assert!(!ignored("src/build/out.bin", false));
+        assert!(ignored("assets/ui/icons/logo.psd", false));
+        assert!(!ignored("assets/logo.png", false));
+        assert!(!ignored("src/main.rs", false));
+        assert!(WorkmuxIgnore::default().is_empty());
+    }
+}
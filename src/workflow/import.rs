@@ -0,0 +1,178 @@
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::{git, naming};
+
+use super::context::WorkflowContext;
+use super::create;
+use super::types::{CreateArgs, CreateResult, ImportSource, SetupOptions};
+
+/// Work out what kind of export `path` is: a bundle, a directory of `.patch`
+/// files (as written by `workmux export`), or a single patch/mbox file.
+pub fn detect_source(path: &Path) -> Result<ImportSource> {
+    if path.is_dir() {
+        let mut patches: Vec<_> = fs::read_dir(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "patch"))
+            .collect();
+        if patches.is_empty() {
+            return Err(anyhow!("No .patch files found in '{}'", path.display()));
+        }
+        // format-patch numbers files (0001-..., 0002-...), so name order is apply order
+        patches.sort();
+        return Ok(ImportSource::Patches(patches));
+    }
+
+    if !path.is_file() {
+        return Err(anyhow!("'{}' does not exist", path.display()));
+    }
+
+    if git::is_bundle(path) {
+        Ok(ImportSource::Bundle(path.to_path_buf()))
+    } else {
+        Ok(ImportSource::Patches(vec![path.to_path_buf()]))
+    }
+}
+
+/// Create a worktree holding the imported work. Bundles are fetched into a new
+/// branch; patch series are applied as commits on a branch created from `base`.
+pub fn import_work(
+    context: &WorkflowContext,
+    source: &ImportSource,
+    branch: Option<&str>,
+    base: Option<&str>,
+    options: SetupOptions,
+) -> Result<CreateResult> {
+    let branch_name = match source {
+        ImportSource::Bundle(bundle) => {
+            if base.is_some() {
+                return Err(anyhow!(
+                    "--base only applies to patch imports; a bundle carries its own history"
+                ));
+            }
+            let bundle_branch = pick_bundle_branch(bundle, branch)?;
+            let branch_name = branch.unwrap_or(&bundle_branch).to_string();
+            ensure_new_branch(&branch_name)?;
+            git::fetch_bundle_branch(bundle, &bundle_branch, &branch_name)?;
+            branch_name
+        }
+        ImportSource::Patches(_) => {
+            let branch_name = branch
+                .ok_or_else(|| anyhow!("--branch is required when importing patches"))?
+                .to_string();
+            ensure_new_branch(&branch_name)?;
+            branch_name
+        }
+    };
+
+    info!(branch = branch_name, source = ?source, "import:start");
+
+    let handle = naming::derive_handle(&branch_name, None, &context.config)?;
+    let result = create(
+        context,
+        CreateArgs {
+            branch_name: &branch_name,
+            handle: &handle,
+            base_branch: base,
+            remote_branch: None,
+            prompt: None,
+            options,
+            agent: None,
+            pr: None,
+            review: false,
+            detach: false,
+        },
+    )?;
+
+    if let ImportSource::Patches(patches) = source {
+        git::am_patches(&result.worktree_path, patches).with_context(|| {
+            format!(
+                "Created worktree at '{}', but the patches did not apply cleanly. \
+                 Resolve the conflicts there and run 'git am --continue', or 'git am --abort' to give up.",
+                result.worktree_path.display()
+            )
+        })?;
+        info!(
+            branch = branch_name,
+            count = patches.len(),
+            "import:patches applied"
+        );
+    }
+
+    Ok(result)
+}
+
+/// Choose which branch to take from a bundle: the only one, or the one named by `branch`.
+fn pick_bundle_branch(bundle: &Path, branch: Option<&str>) -> Result<String> {
+    let branches = git::get_bundle_branches(bundle)?;
+    match branches.as_slice() {
+        [] => Err(anyhow!(
+            "Bundle '{}' contains no branches",
+            bundle.display()
+        )),
+        [only] => Ok(only.clone()),
+        _ => branch
+            .filter(|name| branches.iter().any(|b| b == name))
+            .map(|name| name.to_string())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Bundle contains several branches ({}). Pick one with --branch.",
+                    branches.join(", ")
+                )
+            }),
+    }
+}
+
+fn ensure_new_branch(branch: &str) -> Result<()> {
+    if git::branch_exists(branch)? {
+        return Err(anyhow!(
+            "Branch '{}' already exists. Pick another name with --branch.",
+            branch
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_source_orders_patch_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["0002-second.patch", "0001-first.patch", "notes.txt"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let source = detect_source(dir.path()).unwrap();
+        assert_eq!(
+            source,
+            ImportSource::Patches(vec![
+                dir.path().join("0001-first.patch"),
+                dir.path().join("0002-second.patch"),
+            ])
+        );
+    }
+
+    #[test]
+    fn detect_source_recognizes_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("work.bundle");
+        fs::write(&bundle, "# v2 git bundle\nabc refs/heads/x\n").unwrap();
+        let mbox = dir.path().join("work.mbox");
+        fs::write(&mbox, "From abc Mon Sep 17 00:00:00 2001\n").unwrap();
+
+        assert_eq!(
+            detect_source(&bundle).unwrap(),
+            ImportSource::Bundle(bundle.clone())
+        );
+        assert_eq!(
+            detect_source(&mbox).unwrap(),
+            ImportSource::Patches(vec![mbox.clone()])
+        );
+        assert!(detect_source(&dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::command::set_window_status::SetWindowStatusCommand;
+use crate::{agent, dry_run, git, help};
+
+const START_MARKER: &str = "<!-- workmux:start -->";
+const END_MARKER: &str = "<!-- workmux:end -->";
+
+/// Files agents read project instructions from. Claude reads the first, other
+/// agents the second.
+const INSTRUCTION_FILES: [&str; 2] = ["CLAUDE.md", "AGENTS.md"];
+
+/// Claude's untracked companion to CLAUDE.md, which takes the section in its
+/// place so the shared file is left alone
+const CLAUDE_LOCAL_FILE: &str = "CLAUDE.local.md";
+
+/// The section telling an agent how to use workmux from the worktree of
+/// `branch`, listing `commands` as `workmux --help` does
+pub fn section(branch: &str, commands: &[(String, String)]) -> String {
+    let statuses: Vec<String> = SetWindowStatusCommand::value_variants()
+        .iter()
+        .filter(|status| **status != SetWindowStatusCommand::Clear)
+        .filter_map(|status| status.to_possible_value())
+        .map(|value| {
+            format!(
+                "- `workmux set-window-status {}`: {}",
+                value.get_name(),
+                value
+                    .get_help()
+                    .map(|help| help.to_string())
+                    .unwrap_or_default()
+            )
+        })
+        .collect();
+    let commands: Vec<String> = commands
+        .iter()
+        .map(|(name, about)| format!("- `workmux {}`: {}", name, about))
+        .collect();
+    format!(
+        "{START_MARKER}\n\
+         ## workmux\n\
+         \n\
+         This directory is a git worktree of branch `{branch}`, created by workmux, which \
+         runs it in its own tmux window. Run `workmux <command> --help` for a command's options.\n\
+         \n\
+         ### Reporting status\n\
+         \n\
+         Unless your hooks already do, show what you are doing in the tmux window list:\n\
+         \n\
+         {statuses}\n\
+         \n\
+         ### Starting a sibling worktree\n\
+         \n\
+         To hand a separate task to another agent, run \
+         `workmux add <new-branch> --base {branch} --background --prompt \"<task>\"`. It \
+         gets its own worktree, branch and window, and an agent started with the prompt.\n\
+         \n\
+         ### Commands\n\
+         \n\
+         {commands}\n\
+         {END_MARKER}\n",
+        statuses = statuses.join("\n"),
+        commands = commands.join("\n"),
+    )
+}
+
+/// `content` with its workmux section replaced by `section`, or with
+/// `section` appended if it has none
+pub fn with_section(content: &str, section: &str) -> String {
+    if let Some(start) = content.find(START_MARKER)
+        && let Some(end) = content[start..].find(END_MARKER)
+    {
+        let end = start + end + END_MARKER.len();
+        let rest = content[end..].strip_prefix('\n').unwrap_or(&content[end..]);
+        return format!("{}{}{}", &content[..start], section, rest);
+    }
+    if content.is_empty() {
+        return section.to_string();
+    }
+    let separator = if content.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}", content, separator, section)
+}
+
+/// Add the workmux section for each agent file the worktree has (CLAUDE.md,
+/// AGENTS.md), or, when it has neither, for the one `agent` reads. Claude's
+/// goes to CLAUDE.local.md. Files git tracks are left alone, and the ones
+/// created are excluded from git, so the section is never committed.
+pub fn write(worktree_path: &Path, branch: &str, agent: Option<&str>) -> Result<()> {
+    let mut files: Vec<&str> = INSTRUCTION_FILES
+        .into_iter()
+        .filter(|file| worktree_path.join(file).exists())
+        .collect();
+    if files.is_empty() {
+        let reads_claude_md = agent
+            .and_then(agent::name)
+            .is_none_or(|name| name == "claude");
+        files.push(INSTRUCTION_FILES[if reads_claude_md { 0 } else { 1 }]);
+    }
+
+    let section = section(branch, &help::commands());
+    for file in files.into_iter().map(local_file) {
+        if git::is_tracked(worktree_path, file) {
+            eprintln!(
+                "Skipping workmux instructions in {}: it is tracked by git",
+                file
+            );
+            continue;
+        }
+        let path = worktree_path.join(file);
+        if dry_run::skip(format_args!(
+            "add workmux instructions to {}",
+            path.display()
+        )) {
+            continue;
+        }
+        let mut created = false;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                created = true;
+                String::new()
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read '{}'", path.display()));
+            }
+        };
+        fs::write(&path, with_section(&content, &section))
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        // An untracked file that was already there is the user's to manage
+        if created || file == CLAUDE_LOCAL_FILE {
+            git::exclude_paths(worktree_path, &[file])?;
+        }
+        info!(branch, file, "instructions:written");
+    }
+    Ok(())
+}
+
+/// The untracked file the section for agent file `file` is written to
+fn local_file(file: &str) -> &str {
+    if file == INSTRUCTION_FILES[0] {
+        CLAUDE_LOCAL_FILE
+    } else {
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_lists_commands_and_statuses() {
+        let commands = vec![(
+            "add".to_string(),
+            "Create a new worktree and tmux window".to_string(),
+        )];
+        let section = section("feature/auth", &commands);
+        assert!(section.starts_with(START_MARKER) && section.ends_with(&format!("{END_MARKER}\n")));
+        assert!(section.contains("- `workmux add`: Create a new worktree and tmux window\n"));
+        assert!(section.contains("- `workmux set-window-status waiting`: "));
+        assert!(!section.contains("set-window-status clear"));
+        assert!(section.contains("--base feature/auth --background"));
+    }
+
+    #[test]
+    fn with_section_replaces_the_previous_section() {
+        let section = format!("{START_MARKER}\nnew\n{END_MARKER}\n");
+        assert_eq!(with_section("", &section), section);
+        assert_eq!(
+            with_section("# Project", &section),
+            format!("# Project\n\n{section}")
+        );
+        let old = format!("# Project\n\n{START_MARKER}\nold\n{END_MARKER}\n\nMore notes\n");
+        assert_eq!(
+            with_section(&old, &section),
+            format!("# Project\n\n{section}\nMore notes\n")
+        );
+    }
+
+    #[test]
+    fn write_leaves_tracked_files_alone() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(repo.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "--quiet"]);
+        fs::write(repo.path().join("CLAUDE.md"), "# Project\n").unwrap();
+        fs::write(repo.path().join("AGENTS.md"), "# Agents\n").unwrap();
+        git(&["add", "CLAUDE.md", "AGENTS.md"]);
+
+        write(repo.path(), "feature/auth", None).unwrap();
+        write(repo.path(), "feature/auth", None).unwrap();
+
+        // Claude gets its section beside CLAUDE.md; AGENTS.md has no such file
+        assert_eq!(
+            fs::read_to_string(repo.path().join("CLAUDE.md")).unwrap(),
+            "# Project\n"
+        );
+        assert_eq!(
+            fs::read_to_string(repo.path().join("AGENTS.md")).unwrap(),
+            "# Agents\n"
+        );
+        let local = fs::read_to_string(repo.path().join(CLAUDE_LOCAL_FILE)).unwrap();
+        assert!(local.starts_with(START_MARKER) && local.matches(START_MARKER).count() == 1);
+
+        let status = git(&["status", "--porcelain", "--untracked-files=all"]);
+        assert_eq!(
+            String::from_utf8_lossy(&status.stdout),
+            "A  AGENTS.md\nA  CLAUDE.md\n"
+        );
+    }
+}
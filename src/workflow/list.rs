@@ -1,11 +1,18 @@
 use anyhow::{Result, anyhow};
 
+use crate::worktree_state::{self, WaitingReason};
 use crate::{config, git, github, spinner, tmux};
 
-use super::types::WorktreeInfo;
+use super::types::{WorktreeInfo, WorktreeStatus};
 
-/// List all worktrees with their status
-pub fn list(config: &config::Config, fetch_pr_status: bool) -> Result<Vec<WorktreeInfo>> {
+/// List all worktrees with their status. `with_status` also checks each
+/// worktree for uncommitted changes and counts commits ahead of and behind
+/// the main branch.
+pub fn list(
+    config: &config::Config,
+    fetch_pr_status: bool,
+    with_status: bool,
+) -> Result<Vec<WorktreeInfo>> {
     if !git::is_git_repo()? {
         return Err(anyhow!("Not in a git repository"));
     }
@@ -24,13 +31,13 @@ pub fn list(config: &config::Config, fetch_pr_status: bool) -> Result<Vec<Worktr
     };
 
     // Get the main branch for unmerged checks
-    let main_branch = git::get_default_branch().ok();
+    let main_branch = git::get_default_branch(config.default_remote()).ok();
 
     // Get all unmerged branches in one go for efficiency
     // Prefer checking against remote tracking branch for more accurate results
     let unmerged_branches = main_branch
         .as_deref()
-        .and_then(|main| git::get_merge_base(main).ok())
+        .and_then(|main| git::get_merge_base(main, config.default_remote()).ok())
         .and_then(|base| git::get_unmerged_branches(&base).ok())
         .unwrap_or_default(); // Use an empty set on failure
 
@@ -43,6 +50,9 @@ pub fn list(config: &config::Config, fetch_pr_status: bool) -> Result<Vec<Worktr
         std::collections::HashMap::new()
     };
 
+    let mut descriptions = git::branch_descriptions();
+    let mut last_commits = git::last_commits();
+
     let prefix = config.window_prefix();
     let worktrees: Vec<WorktreeInfo> = worktrees_data
         .into_iter()
@@ -72,15 +82,56 @@ pub fn list(config: &config::Config, fetch_pr_status: bool) -> Result<Vec<Worktr
             // Lookup PR info from batch fetch
             let pr_info = pr_map.get(&branch).cloned();
 
+            let operation = git::in_progress_operation(&path);
+            let pinned = worktree_state::is_pinned(&handle);
+
+            let description = descriptions.remove(&branch);
+            let last_commit = last_commits.remove(&branch);
+            let status = with_status.then(|| WorktreeStatus {
+                dirty: git::has_uncommitted_changes(&path).unwrap_or(false),
+                ahead_behind: main_branch
+                    .as_deref()
+                    .filter(|_| branch != "(detached)")
+                    .and_then(|main| git::ahead_behind(main, &branch).ok()),
+            });
+
             WorktreeInfo {
                 branch,
+                handle,
                 path,
+                window: prefixed_window_name,
                 has_tmux,
                 has_unmerged,
+                operation,
+                pinned,
                 pr_info,
+                description,
+                last_commit,
+                status,
             }
         })
         .collect();
 
     Ok(worktrees)
 }
+
+/// Worktrees whose window shows an agent waiting for input, with what the
+/// agent's pane showed when it started waiting, if that was recorded.
+pub fn waiting(config: &config::Config) -> Result<Vec<(String, Option<WaitingReason>)>> {
+    if !tmux::is_running().unwrap_or(false) {
+        return Ok(Vec::new());
+    }
+    let waiting_icon = config.status_icons.waiting();
+    Ok(tmux::list_workmux_windows(config.window_prefix())?
+        .into_iter()
+        .filter(|window| window.status.as_deref() == Some(waiting_icon))
+        .map(|window| {
+            let reason = worktree_state::load(&window.handle)
+                .ok()
+                .flatten()
+                .and_then(|state| state.waiting)
+                .map(WaitingReason::revealed);
+            (window.handle, reason)
+        })
+        .collect())
+}
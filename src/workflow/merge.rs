@@ -1,11 +1,53 @@
 use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
 
-use crate::{cmd, git};
-use tracing::{debug, info};
+use crate::config::{MergeStrategy, PaneConfig, SplitDirection};
+use crate::prompt::Prompt;
+use crate::worktree_state::{self, PendingMerge, WorktreeState};
+use crate::{cmd, config, git, interrupt, template, tmux};
+use tracing::{debug, info, warn};
 
 use super::cleanup;
 use super::context::WorkflowContext;
-use super::types::MergeResult;
+use super::hooks::{self, HookEnv};
+use super::setup::write_prompt_file;
+use super::types::{MergeConflicts, MergePreflight, MergeResult};
+
+/// Gather what merging `name` into the target branch would do, without
+/// changing anything
+pub fn merge_preflight(
+    name: &str,
+    into_branch: Option<&str>,
+    context: &WorkflowContext,
+) -> Result<MergePreflight> {
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    refuse_review(handle_of(&worktree_path)?)?;
+    let target = into_branch.unwrap_or(&context.main_branch).to_string();
+    Ok(MergePreflight {
+        commits: git::get_commit_subjects(&target, &branch)?,
+        diff_stat: git::diff_stat(&target, &branch)?,
+        conflicts: git::predict_conflicts(&target, &branch),
+        has_staged: git::has_staged_changes(&worktree_path)?,
+        has_unstaged: git::has_unstaged_changes(&worktree_path)?,
+        has_untracked: git::has_untracked_files(&worktree_path)?,
+        branch,
+        target,
+        worktree_path,
+    })
+}
+
+/// A review worktree checks out someone else's pull request, which is merged
+/// where it was opened
+fn refuse_review(handle: &str) -> Result<()> {
+    if worktree_state::is_review(handle) {
+        return Err(anyhow!(
+            "Worktree '{}' is for reviewing a pull request and can't be merged. Run 'workmux remove {}' when done with it.",
+            handle,
+            handle
+        ));
+    }
+    Ok(())
+}
 
 /// Merge a branch into the target branch and clean up
 #[allow(clippy::too_many_arguments)]
@@ -13,8 +55,7 @@ pub fn merge(
     name: &str,
     into_branch: Option<&str>,
     ignore_uncommitted: bool,
-    rebase: bool,
-    squash: bool,
+    strategy: MergeStrategy,
     keep: bool,
     no_verify: bool,
     context: &WorkflowContext,
@@ -23,8 +64,7 @@ pub fn merge(
         name = name,
         into = into_branch,
         ignore_uncommitted,
-        rebase,
-        squash,
+        ?strategy,
         keep,
         no_verify,
         "merge:start"
@@ -35,19 +75,10 @@ pub fn merge(
     context.chdir_to_main_worktree()?;
 
     // Smart resolution: try handle first, then branch name
-    let (worktree_path, branch_to_merge) = git::find_worktree(name)
-        .with_context(|| format!("No worktree found with name '{}'", name))?;
+    let (worktree_path, branch_to_merge) = git::find_worktree(name)?;
 
     // The handle is the basename of the worktree directory (used for tmux operations)
-    let handle = worktree_path
-        .file_name()
-        .and_then(std::ffi::OsStr::to_str)
-        .ok_or_else(|| {
-            anyhow!(
-                "Could not derive handle from worktree path: {}",
-                worktree_path.display()
-            )
-        })?;
+    let handle = handle_of(&worktree_path)?;
 
     debug!(
         name = name,
@@ -57,51 +88,42 @@ pub fn merge(
         "merge:worktree resolved"
     );
 
+    if let Some((_, pending)) = load_pending(handle)? {
+        return Err(anyhow!(
+            "A merge of '{}' is stopped on conflicts. Run 'workmux merge --continue' to finish it, or 'workmux merge --abort' to undo it.",
+            pending.branch
+        ));
+    }
+
+    refuse_review(handle)?;
+
+    if !keep && worktree_state::is_pinned(handle) {
+        return Err(anyhow!(
+            "Worktree '{}' is pinned. Merge it with --keep, or run 'workmux unpin {}' first.",
+            handle,
+            handle
+        ));
+    }
+
     let target_branch = into_branch.unwrap_or(&context.main_branch);
+    let (target_worktree_path, target_window_name) = target_worktree(context, target_branch)?;
 
-    // Resolve the worktree path and window handle for the TARGET branch.
-    // If the target branch is the configured main branch, we use the main worktree root
-    // and the main branch name as the window handle (standard workmux convention).
-    // Otherwise, we check if the target branch has a dedicated worktree.
-    // If it doesn't, we fallback to using the main worktree root but switch it to the target branch.
-    let (target_worktree_path, target_window_name) = if target_branch == context.main_branch {
-        (
-            context.main_worktree_root.clone(),
-            context.main_branch.clone(),
-        )
-    } else {
-        match git::get_worktree_path(target_branch) {
-            Ok(path) => {
-                // Check if the target is checked out in the main worktree.
-                // In that case, use the main branch name as the window handle
-                // (main worktree window is named after main_branch, not directory).
-                if path == context.main_worktree_root {
-                    (path, context.main_branch.clone())
-                } else {
-                    // Target has its own dedicated worktree. Use its directory name as the handle.
-                    let handle = path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .ok_or_else(|| anyhow!("Invalid worktree path for target branch"))?
-                        .to_string();
-                    (path, handle)
-                }
-            }
-            Err(_) => {
-                // Target branch exists but is not checked out in any worktree.
-                // We will use the main worktree to perform the merge.
-                // The target window remains the main window (since that's where we are merging).
-                debug!(
-                    target = target_branch,
-                    "merge:target branch has no worktree, using main worktree"
-                );
-                (
-                    context.main_worktree_root.clone(),
-                    context.main_branch.clone(),
-                )
-            }
+    // Merging from or into a worktree stopped mid-rebase (or mid-merge, etc.)
+    // would build on a half-finished state
+    for (path, branch) in [
+        (&worktree_path, branch_to_merge.as_str()),
+        (&target_worktree_path, target_branch),
+    ] {
+        if let Some(operation) = git::in_progress_operation(path) {
+            return Err(anyhow!(
+                "Worktree for '{}' is in the middle of a {}. Finish it, or run '{}' in {}, before merging.",
+                branch,
+                operation.label(),
+                operation.abort_command(),
+                path.display()
+            ));
         }
-    };
+    }
 
     // Handle changes in the source worktree
     // Only check for unstaged/untracked when worktree will be deleted (!keep)
@@ -159,59 +181,85 @@ pub fn merge(
 
     // Run pre-merge hooks after all validations pass but before any merge operations begin.
     // Skip hooks if --no-verify flag is passed.
-    if !no_verify
-        && let Some(hooks) = &context.config.pre_merge
-        && !hooks.is_empty()
-    {
+    // Hooks from the template the worktree was created with, if any
+    let branch_config = context.config.for_branch(&branch_to_merge);
+    let hooks = config::hooks_for(
+        &branch_config.pre_merge,
+        &config::WhenTarget {
+            branch: &branch_to_merge,
+            agent: context.config.agent.as_deref(),
+            worktree_path: &worktree_path,
+        },
+    );
+    if !no_verify && !hooks.is_empty() {
         info!(count = hooks.len(), "merge:running pre-merge hooks");
 
-        let abs_worktree_path = worktree_path
-            .canonicalize()
-            .unwrap_or_else(|_| worktree_path.clone());
-        let abs_project_root = context
-            .main_worktree_root
-            .canonicalize()
-            .unwrap_or_else(|_| context.main_worktree_root.clone());
-        let worktree_path_str = abs_worktree_path.to_string_lossy();
-        let project_root_str = abs_project_root.to_string_lossy();
-
-        let hook_env = [
-            ("WORKMUX_HANDLE", handle),
-            ("WM_BRANCH_NAME", branch_to_merge.as_str()),
-            ("WM_TARGET_BRANCH", target_branch),
-            ("WM_WORKTREE_PATH", worktree_path_str.as_ref()),
-            ("WM_PROJECT_ROOT", project_root_str.as_ref()),
-            ("WM_HANDLE", handle),
-        ];
-
-        for command in hooks {
-            cmd::shell_command_with_env(command, &worktree_path, &hook_env)
-                .with_context(|| format!("Pre-merge hook failed: '{}'", command))?;
+        let hook_env = HookEnv::new(
+            handle,
+            &branch_to_merge,
+            &worktree_path,
+            &context.main_worktree_root,
+        )
+        .with_target_branch(target_branch);
+        let input = hook_env.input("pre_merge");
+
+        for hook in hooks {
+            cmd::shell_command_with_env(
+                &hook_env.render(hook.command())?,
+                &worktree_path,
+                &hook_env.vars(),
+                Some(&input),
+                hook.timeout(),
+            )
+            .with_context(|| format!("Pre-merge hook failed: '{}'", hook))?;
         }
     }
 
-    // Helper closure to generate the error message for merge conflicts
-    let conflict_err = |branch: &str| -> anyhow::Error {
-        let retry_cmd = if into_branch.is_some() {
-            format!("workmux merge {} --into {}", branch, target_branch)
-        } else {
-            format!("workmux merge {}", branch)
-        };
-        anyhow!(
-            "Merge failed due to conflicts. Target worktree kept clean.\n\n\
-            To resolve, update your branch in worktree at {}:\n\
-              git rebase {}  (recommended)\n\
-            Or:\n\
-              git merge {}\n\n\
-            After resolving conflicts, retry: {}",
-            worktree_path.display(),
-            target_branch,
+    // Last point at which stopping leaves both branches untouched
+    interrupt::check().context("Stopped before merging; nothing was changed")?;
+
+    // Rendered up front so a broken template fails before anything changes.
+    // Rebase and ff-only never make a commit to put it on.
+    let message = match (&context.config.merge_message, strategy) {
+        (Some(template), MergeStrategy::Merge | MergeStrategy::Squash) => Some(render_message(
+            template,
+            &branch_to_merge,
             target_branch,
-            retry_cmd
-        )
+            handle,
+        )?),
+        _ => None,
     };
 
-    if rebase {
+    let failed = |e: anyhow::Error| {
+        e.context(format!(
+            "Failed to merge '{}' into '{}'. Target worktree kept clean.",
+            branch_to_merge, target_branch
+        ))
+    };
+
+    // A merge stopped on conflicts is left for `merge --continue`: a rebase
+    // in the branch's worktree, a merge or squash in the target's. Other
+    // failures leave the target as it was.
+    let pending = PendingMerge {
+        branch: branch_to_merge.clone(),
+        target: target_branch.to_string(),
+        strategy,
+        keep,
+        message: message.clone(),
+        pane: None,
+    };
+    let pause = |conflicted_path: &Path, window: &str| {
+        pause_on_conflicts(
+            context,
+            &branch_to_merge,
+            handle,
+            conflicted_path,
+            window,
+            pending.clone(),
+            had_staged_changes,
+        )
+    };
+    if strategy == MergeStrategy::Rebase {
         // Rebase the feature branch on top of target inside its own worktree.
         // This is where conflicts will be detected.
         println!(
@@ -223,76 +271,608 @@ pub fn merge(
             base = target_branch,
             "merge:rebase start"
         );
-        git::rebase_branch_onto_base(&worktree_path, target_branch).with_context(|| {
-            format!(
-                "Rebase failed, likely due to conflicts.\n\n\
-                Please resolve them manually inside the worktree at '{}'.\n\
-                Then, run 'git rebase --continue' to proceed or 'git rebase --abort' to cancel.",
-                worktree_path.display()
-            )
-        })?;
+        if let Err(e) = git::rebase_branch_onto_base(&worktree_path, target_branch) {
+            if git::in_progress_operation(&worktree_path).is_some() {
+                return pause(&worktree_path, handle);
+            }
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to rebase '{}' onto '{}'",
+                    branch_to_merge, target_branch
+                )
+            });
+        }
 
         // After a successful rebase, merge into target. This will be a fast-forward.
         git::merge_in_worktree(&target_worktree_path, &branch_to_merge)
             .context("Failed to merge rebased branch. This should have been a fast-forward.")?;
         info!(branch = %branch_to_merge, "merge:fast-forward complete");
-    } else if squash {
+    } else if strategy == MergeStrategy::FfOnly {
+        // Fails without touching the target when the branches have diverged
+        git::merge_ff_only_in_worktree(&target_worktree_path, &branch_to_merge).map_err(|e| {
+            info!(branch = %branch_to_merge, error = %e, "merge:fast-forward refused");
+            anyhow!(
+                "Cannot fast-forward '{}' to '{}': the branches have diverged. Target worktree kept clean.\n\n\
+                Rebase the branch first and retry, or merge with 'workmux merge {} --rebase'.",
+                target_branch,
+                branch_to_merge,
+                branch_to_merge
+            )
+        })?;
+        info!(branch = %branch_to_merge, "merge:fast-forward complete");
+    } else if strategy == MergeStrategy::Squash {
         // Perform the squash merge. This stages all changes from the feature branch but does not commit.
         if let Err(e) = git::merge_squash_in_worktree(&target_worktree_path, &branch_to_merge) {
+            if !git::get_conflicted_files(&target_worktree_path)
+                .unwrap_or_default()
+                .is_empty()
+            {
+                return pause(&target_worktree_path, &target_window_name);
+            }
             info!(branch = %branch_to_merge, error = %e, "merge:squash merge failed, resetting target worktree");
             // Best effort to reset; ignore failure as the user message is the priority.
             let _ = git::reset_hard(&target_worktree_path);
-            return Err(conflict_err(&branch_to_merge));
+            return Err(failed(e));
         }
 
-        // Prompt the user to provide a commit message for the squashed changes.
-        println!("Staged squashed changes. Please provide a commit message in your editor.");
-        git::commit_with_editor(&target_worktree_path)
-            .context("Failed to commit squashed changes. You may need to commit them manually.")?;
+        commit_squash(&target_worktree_path, message.as_deref())?;
         info!(branch = %branch_to_merge, "merge:squash merge committed");
     } else {
         // Default merge commit workflow
-        if let Err(e) = git::merge_in_worktree(&target_worktree_path, &branch_to_merge) {
+        let merged = match &message {
+            Some(message) => git::merge_with_message_in_worktree(
+                &target_worktree_path,
+                &branch_to_merge,
+                message,
+            ),
+            None => git::merge_in_worktree(&target_worktree_path, &branch_to_merge),
+        };
+        if let Err(e) = merged {
+            if git::in_progress_operation(&target_worktree_path)
+                == Some(git::InProgressOperation::Merge)
+            {
+                return pause(&target_worktree_path, &target_window_name);
+            }
             info!(branch = %branch_to_merge, error = %e, "merge:standard merge failed, aborting merge in target worktree");
             // Best effort to abort; ignore failure as the user message is the priority.
             let _ = git::abort_merge_in_worktree(&target_worktree_path);
-            return Err(conflict_err(&branch_to_merge));
+            return Err(failed(e));
         }
         info!(branch = %branch_to_merge, "merge:standard merge complete");
     }
 
+    finish(
+        context,
+        &branch_to_merge,
+        target_branch,
+        handle,
+        &worktree_path,
+        &target_worktree_path,
+        &target_window_name,
+        had_staged_changes,
+        keep,
+    )
+}
+
+/// Finish a merge stopped on conflicts, once they are resolved, and clean up
+/// as the merge would have
+pub fn continue_merge(name: &str, context: &WorkflowContext) -> Result<MergeResult> {
+    info!(name = name, "merge:continue");
+    context.chdir_to_main_worktree()?;
+
+    let (worktree_path, _) = git::find_worktree(name)?;
+    let handle = handle_of(&worktree_path)?;
+    let (mut state, pending) = load_pending(handle)?
+        .ok_or_else(|| anyhow!("No merge of '{}' is stopped on conflicts", name))?;
+    let branch = pending.branch.clone();
+    let (target_worktree_path, target_window_name) = target_worktree(context, &pending.target)?;
+
+    let conflicted_path = if pending.strategy == MergeStrategy::Rebase {
+        &worktree_path
+    } else {
+        &target_worktree_path
+    };
+    let remaining = git::get_conflicted_files(conflicted_path)?;
+    if !remaining.is_empty() {
+        return Err(anyhow!(
+            "Conflicts remain in {}: {}\n\n\
+            Resolve them and stage them with 'git add', then run 'workmux merge --continue' again.",
+            conflicted_path.display(),
+            remaining.join(", ")
+        ));
+    }
+
+    // Each step is skipped if it was already done by hand
+    match pending.strategy {
+        MergeStrategy::Rebase => {
+            if git::in_progress_operation(&worktree_path) == Some(git::InProgressOperation::Rebase)
+            {
+                git::continue_rebase_in_worktree(&worktree_path).with_context(|| {
+                    format!(
+                        "The rebase stopped again, likely on conflicts in a later commit.\n\n\
+                        Resolve them in '{}', then run 'workmux merge --continue' again.",
+                        worktree_path.display()
+                    )
+                })?;
+            }
+            git::merge_in_worktree(&target_worktree_path, &branch)
+                .context("Failed to merge rebased branch. This should have been a fast-forward.")?;
+        }
+        MergeStrategy::Merge => {
+            if git::in_progress_operation(&target_worktree_path)
+                == Some(git::InProgressOperation::Merge)
+            {
+                git::conclude_merge_in_worktree(&target_worktree_path)?;
+            }
+        }
+        MergeStrategy::Squash => {
+            if git::has_staged_changes(&target_worktree_path)? {
+                commit_squash(&target_worktree_path, pending.message.as_deref())?;
+            }
+        }
+        // Never stops on conflicts
+        MergeStrategy::FfOnly => {}
+    }
+    info!(branch = %branch, "merge:continued merge complete");
+
+    close_conflict_pane(&pending);
+    state.merge = None;
+    worktree_state::save(handle, &state, &context.config)?;
+
+    finish(
+        context,
+        &branch,
+        &pending.target,
+        handle,
+        &worktree_path,
+        &target_worktree_path,
+        &target_window_name,
+        false,
+        pending.keep,
+    )
+}
+
+/// Undo a merge stopped on conflicts, leaving both branches as they were.
+/// Returns the branch that was being merged.
+pub fn abort_merge(name: &str, context: &WorkflowContext) -> Result<String> {
+    info!(name = name, "merge:abort");
+    context.chdir_to_main_worktree()?;
+
+    let (worktree_path, _) = git::find_worktree(name)?;
+    let handle = handle_of(&worktree_path)?;
+    let (mut state, pending) = load_pending(handle)?
+        .ok_or_else(|| anyhow!("No merge of '{}' is stopped on conflicts", name))?;
+    let (target_worktree_path, _) = target_worktree(context, &pending.target)?;
+
+    match pending.strategy {
+        MergeStrategy::Rebase => {
+            if git::in_progress_operation(&worktree_path) == Some(git::InProgressOperation::Rebase)
+            {
+                git::abort_rebase_in_worktree(&worktree_path)?;
+            }
+        }
+        MergeStrategy::Merge => {
+            if git::in_progress_operation(&target_worktree_path)
+                == Some(git::InProgressOperation::Merge)
+            {
+                git::abort_merge_in_worktree(&target_worktree_path)?;
+            }
+        }
+        // The target had no uncommitted changes before the squash
+        MergeStrategy::Squash => git::reset_hard(&target_worktree_path)?,
+        MergeStrategy::FfOnly => {}
+    }
+
+    close_conflict_pane(&pending);
+    state.merge = None;
+    worktree_state::save(handle, &state, &context.config)?;
+    Ok(pending.branch)
+}
+
+/// Clean up after a completed merge, unless it keeps the worktree
+#[allow(clippy::too_many_arguments)]
+fn finish(
+    context: &WorkflowContext,
+    branch_to_merge: &str,
+    target_branch: &str,
+    handle: &str,
+    worktree_path: &Path,
+    target_worktree_path: &Path,
+    target_window_name: &str,
+    had_staged_changes: bool,
+    keep: bool,
+) -> Result<MergeResult> {
+    let result = |cleanup_interrupted| MergeResult {
+        branch_merged: branch_to_merge.to_string(),
+        main_branch: target_branch.to_string(),
+        had_staged_changes,
+        cleanup_interrupted,
+        kept: keep,
+        conflicts: None,
+    };
+
+    // Read before cleanup, which removes the worktree and its branch
+    let branch_config = context.config.for_branch(branch_to_merge);
+    let hook_env = HookEnv::new(
+        handle,
+        branch_to_merge,
+        worktree_path,
+        &context.main_worktree_root,
+    )
+    .with_target_branch(target_branch);
+    let post_merge = config::hooks_for(
+        &branch_config.post_merge,
+        &config::WhenTarget {
+            branch: branch_to_merge,
+            agent: context.config.agent.as_deref(),
+            worktree_path: target_worktree_path,
+        },
+    );
+    // The merge is done either way, so a failing hook is only reported
+    let run_post_merge = || {
+        if let Err(e) = hooks::run("post-merge", &post_merge, target_worktree_path, &hook_env) {
+            warn!(branch = %branch_to_merge, error = %e, "merge:post-merge hook failed");
+            eprintln!("Warning: {:#}", e);
+        }
+    };
+
     // Skip cleanup if --keep flag is used
     if keep {
         info!(branch = %branch_to_merge, "merge:skipping cleanup (--keep)");
-        return Ok(MergeResult {
-            branch_merged: branch_to_merge,
-            main_branch: target_branch.to_string(),
-            had_staged_changes,
-        });
+        run_post_merge();
+        return Ok(result(false));
+    }
+
+    // The merge is done; on Ctrl-C, leave cleanup for `workmux rm`
+    if interrupt::is_interrupted() {
+        info!(branch = %branch_to_merge, "merge:interrupted before cleanup");
+        return Ok(result(true));
     }
 
     // Always force cleanup after a successful merge
     info!(branch = %branch_to_merge, "merge:cleanup start");
-    let cleanup_result = cleanup::cleanup(
+    let cleanup_result = match cleanup::cleanup(
         context,
-        &branch_to_merge,
+        branch_to_merge,
         handle,
-        &worktree_path,
+        worktree_path,
         true,
         false, // keep_branch: always delete when merging
-    )?;
+    ) {
+        Ok(result) => result,
+        Err(e) if interrupt::is_interrupted() => {
+            info!(branch = %branch_to_merge, error = %e, "merge:cleanup interrupted");
+            return Ok(result(true));
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Before the source window closes, in case workmux runs in it
+    run_post_merge();
 
-    // Navigate to the target branch window and close the source window
+    // Navigate per `after_close` (target branch window by default) and close the source window
     cleanup::navigate_to_target_and_close(
         &context.prefix,
-        &target_window_name,
+        target_window_name,
         handle,
+        context.config.after_close.unwrap_or_default(),
         &cleanup_result,
     )?;
 
+    Ok(result(false))
+}
+
+/// The worktree directory name, used as the window handle
+fn handle_of(worktree_path: &Path) -> Result<&str> {
+    worktree_path
+        .file_name()
+        .and_then(std::ffi::OsStr::to_str)
+        .ok_or_else(|| {
+            anyhow!(
+                "Could not derive handle from worktree path: {}",
+                worktree_path.display()
+            )
+        })
+}
+
+/// Resolve the worktree path and window handle for the TARGET branch.
+/// If the target branch is the configured main branch, we use the main worktree root
+/// and the main branch name as the window handle (standard workmux convention).
+/// Otherwise, we check if the target branch has a dedicated worktree.
+/// If it doesn't, we fallback to using the main worktree root but switch it to the target branch.
+fn target_worktree(context: &WorkflowContext, target_branch: &str) -> Result<(PathBuf, String)> {
+    if target_branch == context.main_branch {
+        return Ok((
+            context.main_worktree_root.clone(),
+            context.main_branch.clone(),
+        ));
+    }
+    match git::get_worktree_path(target_branch) {
+        Ok(path) => {
+            // Check if the target is checked out in the main worktree.
+            // In that case, use the main branch name as the window handle
+            // (main worktree window is named after main_branch, not directory).
+            if path == context.main_worktree_root {
+                Ok((path, context.main_branch.clone()))
+            } else {
+                // Target has its own dedicated worktree. Use its directory name as the handle.
+                let handle = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .ok_or_else(|| anyhow!("Invalid worktree path for target branch"))?
+                    .to_string();
+                Ok((path, handle))
+            }
+        }
+        Err(_) => {
+            // Target branch exists but is not checked out in any worktree.
+            // We will use the main worktree to perform the merge.
+            // The target window remains the main window (since that's where we are merging).
+            debug!(
+                target = target_branch,
+                "merge:target branch has no worktree, using main worktree"
+            );
+            Ok((
+                context.main_worktree_root.clone(),
+                context.main_branch.clone(),
+            ))
+        }
+    }
+}
+
+/// The worktree's state and the merge of it stopped on conflicts, if any
+fn load_pending(handle: &str) -> Result<Option<(WorktreeState, PendingMerge)>> {
+    Ok(worktree_state::load(handle)?
+        .and_then(|state| state.merge.clone().map(|pending| (state, pending))))
+}
+
+/// Commit squashed changes with the configured message, else ask for one in the editor
+fn commit_squash(target_worktree_path: &Path, message: Option<&str>) -> Result<()> {
+    match message {
+        Some(message) => git::commit_with_message(target_worktree_path, message),
+        None => {
+            println!("Staged squashed changes. Please provide a commit message in your editor.");
+            git::commit_with_editor(target_worktree_path)
+        }
+    }
+    .context("Failed to commit squashed changes. You may need to commit them manually.")
+}
+
+/// Leave the merge stopped on conflicts, open `conflict_command` beside them
+/// if configured, and record how to finish it
+fn pause_on_conflicts(
+    context: &WorkflowContext,
+    branch: &str,
+    handle: &str,
+    conflicted_path: &Path,
+    window: &str,
+    mut pending: PendingMerge,
+    had_staged_changes: bool,
+) -> Result<MergeResult> {
+    let files = git::get_conflicted_files(conflicted_path).unwrap_or_default();
+    info!(branch, files = files.len(), "merge:paused on conflicts");
+
+    // The merge can be finished without the pane, so failing to open it
+    // doesn't fail the merge
+    if context.config.conflict_command.is_some() {
+        match open_conflict_pane(
+            context,
+            branch,
+            handle,
+            conflicted_path,
+            window,
+            &pending,
+            &files,
+        ) {
+            Ok(pane) => pending.pane = Some(pane),
+            Err(e) => warn!(error = %e, "merge:failed to open conflict pane"),
+        }
+    }
+
+    let pane_opened = pending.pane.is_some();
+    let target = pending.target.clone();
+    let mut state = worktree_state::load(handle)?.unwrap_or_else(|| WorktreeState {
+        branch: branch.to_string(),
+        ..WorktreeState::default()
+    });
+    state.merge = Some(pending);
+    worktree_state::save(handle, &state, &context.config)
+        .context("Failed to record the stopped merge")?;
+
     Ok(MergeResult {
-        branch_merged: branch_to_merge,
-        main_branch: target_branch.to_string(),
+        branch_merged: branch.to_string(),
+        main_branch: target,
         had_staged_changes,
+        cleanup_interrupted: false,
+        kept: true,
+        conflicts: Some(MergeConflicts {
+            path: conflicted_path.to_path_buf(),
+            files,
+            pane_opened,
+        }),
     })
 }
+
+/// Split the conflicted worktree's window (or open a `<handle>-conflicts`
+/// window) running `conflict_command`, returning the new pane
+fn open_conflict_pane(
+    context: &WorkflowContext,
+    branch: &str,
+    handle: &str,
+    conflicted_path: &Path,
+    window: &str,
+    pending: &PendingMerge,
+    files: &[String],
+) -> Result<String> {
+    if !tmux::is_running()? {
+        return Err(anyhow!("tmux is not running"));
+    }
+    let command = context
+        .config
+        .conflict_command
+        .clone()
+        .ok_or_else(|| anyhow!("No conflict_command configured"))?;
+
+    let prompt = conflict_prompt(
+        branch,
+        &pending.target,
+        pending.strategy == MergeStrategy::Rebase,
+        files,
+    );
+    let prompt_file_path =
+        write_prompt_file(&format!("{}-conflicts", handle), &Prompt::Inline(prompt))?;
+
+    let conflict_pane = PaneConfig {
+        command: Some(command),
+        focus: true,
+        split: None,
+        size: None,
+        percentage: None,
+        target: None,
+    };
+    let (initial_pane_id, panes) = if tmux::window_exists(&context.prefix, window)? {
+        // An empty first pane leaves the window's active pane untouched
+        let existing_pane = PaneConfig {
+            command: None,
+            focus: false,
+            ..conflict_pane.clone()
+        };
+        let split_pane = PaneConfig {
+            split: Some(SplitDirection::Horizontal),
+            ..conflict_pane
+        };
+        (
+            tmux::active_pane_id(&context.prefix, window)?,
+            vec![existing_pane, split_pane],
+        )
+    } else {
+        let pane_id = tmux::create_window(
+            &context.prefix,
+            &format!("{}-conflicts", handle),
+            conflicted_path,
+            /* detached: */ false,
+            None,
+        )
+        .context("Failed to create tmux window")?;
+        (pane_id, vec![conflict_pane])
+    };
+
+    let result = tmux::setup_panes(
+        &initial_pane_id,
+        &panes,
+        conflicted_path,
+        tmux::PaneSetupOptions {
+            run_commands: true,
+            prompt_file_path: Some(&prompt_file_path),
+            pane_prompt_paths: &[],
+        },
+        &context.config,
+        None,
+    )?;
+    Ok(result.focus_pane_id)
+}
+
+/// Close the pane opened for a stopped merge, if it is still there
+fn close_conflict_pane(pending: &PendingMerge) {
+    if let Some(pane) = &pending.pane
+        && let Err(e) = tmux::kill_pane(pane)
+    {
+        debug!(pane, error = %e, "merge:conflict pane already closed");
+    }
+}
+
+/// Build the prompt handed to an agent run as `conflict_command`
+fn conflict_prompt(branch: &str, target: &str, rebase: bool, files: &[String]) -> String {
+    let operation = if rebase {
+        format!("Rebasing `{}` onto `{}`", branch, target)
+    } else {
+        format!("Merging `{}` into `{}`", branch, target)
+    };
+    let mut prompt = format!("{} stopped on conflicts in this worktree.\n\n", operation);
+    if !files.is_empty() {
+        prompt.push_str("Conflicted files:\n");
+        for file in files {
+            prompt.push_str(&format!("- {}\n", file));
+        }
+        prompt.push('\n');
+    }
+    prompt.push_str(
+        "Resolve each conflict so that the intent of both sides is kept, remove the \
+         conflict markers, and stage the files with `git add`. Do not commit, continue \
+         or abort the rebase or merge yourself: once everything is staged, say so, and \
+         `workmux merge --continue` will finish it.\n",
+    );
+    prompt
+}
+
+/// Render the `merge_message` template for merging `branch` into `target`
+fn render_message(template: &str, branch: &str, target: &str, handle: &str) -> Result<String> {
+    let commits = git::get_commit_subjects(target, branch)?;
+    render_message_with(template, branch, target, handle, &commits)
+}
+
+/// [`render_message`] given the subjects of the commits being merged
+fn render_message_with(
+    template: &str,
+    branch: &str,
+    target: &str,
+    handle: &str,
+    commits: &[String],
+) -> Result<String> {
+    let context = serde_json::json!({
+        "branch": branch,
+        "target": target,
+        "handle": handle,
+        "commits": commits,
+    });
+    let env = template::create_template_env();
+    template::validate_template_variables(&env, template, &context)
+        .context("Invalid merge_message")?;
+    let message = env
+        .render_str(template, &context)
+        .context("Failed to render merge_message")?;
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(anyhow!("merge_message rendered an empty commit message"));
+    }
+    Ok(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conflict_prompt, render_message_with};
+
+    #[test]
+    fn merge_message_renders_each_variable() {
+        let commits = ["Add login form".to_string(), "Fix typo".to_string()];
+        let template = "Merge {{ branch }} ({{ handle }}) into {{ target }}\n\n\
+                        {% for commit in commits %}- {{ commit }}\n{% endfor %}";
+        assert_eq!(
+            render_message_with(template, "feature/login", "main", "login", &commits).unwrap(),
+            "Merge feature/login (login) into main\n\n- Add login form\n- Fix typo"
+        );
+
+        let error = |template| {
+            render_message_with(template, "feature/login", "main", "login", &commits)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error("{{ ticket }}"), "Invalid merge_message");
+        assert_eq!(
+            error("{% if false %}x{% endif %}\n"),
+            "merge_message rendered an empty commit message"
+        );
+    }
+
+    #[test]
+    fn conflict_prompt_lists_files_and_how_to_finish() {
+        let prompt = conflict_prompt("auth", "main", true, &["src/lib.rs".to_string()]);
+        assert!(prompt.starts_with("Rebasing `auth` onto `main` stopped on conflicts"));
+        assert!(prompt.contains("Conflicted files:\n- src/lib.rs\n"));
+        assert!(prompt.contains("`workmux merge --continue`"));
+
+        let prompt = conflict_prompt("auth", "main", false, &[]);
+        assert!(prompt.starts_with("Merging `auth` into `main`"));
+        assert!(!prompt.contains("Conflicted files"));
+    }
+}
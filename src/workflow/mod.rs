@@ -1,24 +1,57 @@
 // Module declarations
+mod apply;
+mod archive;
+mod bootstrap;
 mod cleanup;
 mod context;
 mod create;
+mod hooks;
+mod ignore;
+mod import;
+mod instructions;
 mod list;
 mod merge;
 mod open;
+mod patch;
 pub mod pr;
 pub mod prompt_loader;
+mod prune;
 mod remove;
+mod review;
 mod setup;
+mod split;
+mod summarize;
+mod switch;
+mod tidy;
 pub mod types;
+mod untracked;
+mod which;
 
 // Public API re-exports
+pub use apply::{apply, plan, unlisted_worktrees};
+pub use archive::{archive, archive_dir, list_archives, resolve_archive, restore};
 pub use create::{create, create_with_changes};
-pub use list::list;
-pub use merge::merge;
-pub use open::open;
+pub use hooks::HookEnv;
+pub use import::{detect_source, import_work};
+pub use list::{list, waiting};
+pub use merge::{abort_merge, continue_merge, merge, merge_preflight};
+pub use open::{open, open_here};
+pub use patch::{apply_patch, check_patch};
+pub use prune::{apply_prune, plan_prune};
 pub use remove::remove;
-pub use setup::write_prompt_file;
+pub use review::review;
+pub use setup::{save_prompt, write_prompt_file};
+pub use split::{apply_split, plan_split};
+pub use summarize::summarize;
+pub use switch::{best_match, fuzzy_rank, switch_candidates};
+pub use tidy::tidy;
+pub use untracked::measure_untracked;
+pub use which::which;
 
 // Re-export commonly used types for convenience
 pub use context::WorkflowContext;
-pub use types::{CreateArgs, SetupOptions};
+pub use types::{
+    ApplyAction, ApplyStep, ChangesTarget, CreateArgs, FileFilter, ImportSource, MergePreflight,
+    MergeResult, NameMatch, PatchOutcome, ReviewArgs, SetupOptions, SplitSource, SwitchCandidate,
+    WorktreeInfo,
+};
@@ -1,12 +1,14 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Result, anyhow};
 use regex::Regex;
+use std::path::Path;
 
-use crate::{git, tmux};
+use crate::{config, git, tmux};
 use tracing::info;
 
 use super::context::WorkflowContext;
+use super::hooks::{self, HookEnv};
 use super::setup;
-use super::types::{CreateResult, SetupOptions};
+use super::types::{AdoptResult, CreateResult, SetupOptions};
 
 /// Open a tmux window for an existing worktree
 pub fn open(
@@ -25,7 +27,7 @@ pub fn open(
 
     // Validate pane config before any other operations
     if let Some(panes) = &context.config.panes {
-        crate::config::validate_panes_config(panes)?;
+        panes.validate()?;
     }
 
     // Pre-flight checks
@@ -33,12 +35,7 @@ pub fn open(
 
     // This command requires the worktree to already exist
     // Smart resolution: try handle first, then branch name
-    let (worktree_path, branch_name) = git::find_worktree(name).with_context(|| {
-        format!(
-            "No worktree found with name '{}'. Use 'workmux list' to see available worktrees.",
-            name
-        )
-    })?;
+    let (worktree_path, branch_name) = git::find_worktree(name)?;
 
     // Derive base handle from the worktree path (in case user provided branch name)
     let base_handle = worktree_path
@@ -75,6 +72,8 @@ pub fn open(
         base_handle
     };
 
+    run_pre_open_hooks(context, &handle, &branch_name, &worktree_path)?;
+
     // Setup the environment
     let result = setup::setup_environment(
         &branch_name,
@@ -94,6 +93,76 @@ pub fn open(
     Ok(result)
 }
 
+/// Turn the current tmux window into the window for an existing worktree
+pub fn open_here(
+    name: &str,
+    context: &WorkflowContext,
+    options: SetupOptions,
+) -> Result<AdoptResult> {
+    info!(name = name, "open_here:start");
+
+    let pane_id = tmux::current_pane_id()
+        .ok_or_else(|| anyhow!("--here must be run from inside a tmux window"))?;
+    let (worktree_path, branch_name) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    // Another window already belongs to this worktree; two windows with the
+    // same name would confuse every later lookup
+    let full_name = tmux::prefixed(&context.prefix, &handle);
+    if tmux::current_window_name()?.as_deref() != Some(full_name.as_str())
+        && tmux::window_exists(&context.prefix, &handle)?
+    {
+        return Err(anyhow!(
+            "Window '{}' already exists. Run 'workmux open {}' to switch to it.",
+            full_name,
+            handle
+        ));
+    }
+
+    run_pre_open_hooks(context, &handle, &branch_name, &worktree_path)?;
+
+    setup::adopt_window(
+        &branch_name,
+        &handle,
+        &worktree_path,
+        &pane_id,
+        &context.config,
+        &options,
+    )
+}
+
+/// Run the `pre_open` hooks in the worktree
+fn run_pre_open_hooks(
+    context: &WorkflowContext,
+    handle: &str,
+    branch_name: &str,
+    worktree_path: &Path,
+) -> Result<()> {
+    let branch_config = context.config.for_branch(branch_name);
+    let hooks = config::hooks_for(
+        &branch_config.pre_open,
+        &config::WhenTarget {
+            branch: branch_name,
+            agent: context.config.agent.as_deref(),
+            worktree_path,
+        },
+    );
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let hook_env = HookEnv::new(
+        handle,
+        branch_name,
+        worktree_path,
+        &context.main_worktree_root,
+    );
+    hooks::run("pre-open", &hooks, worktree_path, &hook_env)
+}
+
 /// Find a unique handle by appending a suffix if necessary.
 ///
 /// If `base_handle` is "my-feature" and windows exist for:
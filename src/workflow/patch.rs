@@ -0,0 +1,170 @@
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::git;
+
+use super::types::{PatchOutcome, PatchResult};
+
+/// Pull the unified diff out of `content`, dropping a surrounding markdown code
+/// fence if present (agents often wrap their patches in one). Lines keep
+/// their endings, as CRLF files only patch with the CRs in place.
+pub fn extract_patch(content: &str) -> Result<String> {
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    if let Some(open) = lines.iter().position(|line| line.starts_with("```")) {
+        let close = lines[open + 1..]
+            .iter()
+            .position(|line| line.starts_with("```"))
+            .map(|i| open + 1 + i)
+            .ok_or_else(|| anyhow!("Patch has an unterminated code fence"))?;
+        lines = lines[open + 1..close].to_vec();
+    }
+
+    let is_diff = lines.iter().any(|line| line.starts_with("diff --git "))
+        || (lines.iter().any(|line| line.starts_with("+++ "))
+            && lines.iter().any(|line| line.starts_with("@@ ")));
+    if !is_diff {
+        return Err(anyhow!(
+            "Input is not a unified diff (expected 'diff --git' or '---'/'+++' headers with '@@' hunks)"
+        ));
+    }
+
+    // git apply rejects patches whose last line has no newline. It gets the
+    // one the line before it ends with.
+    let mut patch = lines.concat();
+    if !patch.ends_with('\n') {
+        let crlf = lines.len() > 1 && lines[lines.len() - 2].ends_with("\r\n");
+        patch.push_str(if crlf { "\r\n" } else { "\n" });
+    }
+    Ok(patch)
+}
+
+/// Resolve a worktree by name, returning its path and handle.
+fn resolve_worktree(name: &str) -> Result<(PathBuf, String)> {
+    let (worktree_path, _) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    Ok((worktree_path, handle))
+}
+
+/// Write the patch next to other workmux temp files so git can read it.
+fn write_patch_file(handle: &str, patch: &str) -> Result<PathBuf> {
+    let patch_path = std::env::temp_dir().join(format!("workmux-patch-{}.diff", handle));
+    fs::write(&patch_path, patch)
+        .with_context(|| format!("Failed to write patch file '{}'", patch_path.display()))?;
+    Ok(patch_path)
+}
+
+/// Check whether a patch applies cleanly to a worktree without changing it.
+/// Returns the files the patch touches and whether it applies cleanly.
+pub fn check_patch(name: &str, content: &str) -> Result<(Vec<String>, bool)> {
+    let (worktree_path, handle) = resolve_worktree(name)?;
+    let patch_path = write_patch_file(&handle, &extract_patch(content)?)?;
+
+    let result = patch_summary(&worktree_path, &patch_path).and_then(|files| {
+        let clean = git::patch_applies(&worktree_path, &patch_path)?;
+        Ok((files, clean))
+    });
+
+    let _ = fs::remove_file(&patch_path);
+    result
+}
+
+/// Apply a patch to a worktree. Patches that don't apply cleanly fall back to a
+/// 3-way merge, which leaves conflict markers in files it can't reconcile.
+pub fn apply_patch(name: &str, content: &str) -> Result<PatchResult> {
+    let (worktree_path, handle) = resolve_worktree(name)?;
+    let patch_path = write_patch_file(&handle, &extract_patch(content)?)?;
+
+    let result = apply_patch_file(&worktree_path, &patch_path);
+
+    let _ = fs::remove_file(&patch_path);
+    let (files, outcome) = result?;
+
+    info!(
+        handle = %handle,
+        files = files.len(),
+        outcome = ?outcome,
+        "apply_patch:completed"
+    );
+
+    Ok(PatchResult {
+        worktree_path,
+        files,
+        outcome,
+    })
+}
+
+fn patch_summary(worktree_path: &Path, patch_path: &Path) -> Result<Vec<String>> {
+    git::get_patch_files(worktree_path, patch_path).context("Invalid patch")
+}
+
+fn apply_patch_file(
+    worktree_path: &Path,
+    patch_path: &Path,
+) -> Result<(Vec<String>, PatchOutcome)> {
+    let files = patch_summary(worktree_path, patch_path)?;
+
+    if git::patch_applies(worktree_path, patch_path)? {
+        git::apply_patch(worktree_path, patch_path, false)?;
+        return Ok((files, PatchOutcome::Clean));
+    }
+
+    info!(path = %worktree_path.display(), "apply_patch:falling back to 3-way merge");
+    match git::apply_patch(worktree_path, patch_path, true) {
+        Ok(()) => Ok((files, PatchOutcome::Merged)),
+        Err(err) => {
+            // A failed 3-way apply that left conflicts is still a partial success
+            let conflicts = git::get_conflicted_files(worktree_path)?;
+            if conflicts.is_empty() {
+                Err(err.context("Patch does not apply, even with a 3-way merge"))
+            } else {
+                Ok((files, PatchOutcome::Conflicts(conflicts)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract_patch;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                        --- a/src/lib.rs\n\
+                        +++ b/src/lib.rs\n\
+                        @@ -1 +1 @@\n\
+                        -old\n\
+                        +new";
+
+    #[test]
+    fn extract_patch_accepts_plain_diff_and_adds_newline() {
+        assert_eq!(extract_patch(DIFF).unwrap(), format!("{}\n", DIFF));
+    }
+
+    #[test]
+    fn extract_patch_strips_markdown_fence() {
+        let wrapped = format!("Here is the fix:\n\n```diff\n{}\n```\nDone.", DIFF);
+        assert_eq!(extract_patch(&wrapped).unwrap(), format!("{}\n", DIFF));
+    }
+
+    #[test]
+    fn extract_patch_keeps_crlf_line_endings() {
+        let crlf = DIFF.replace('\n', "\r\n");
+        assert_eq!(extract_patch(&crlf).unwrap(), format!("{}\r\n", crlf));
+
+        let wrapped = format!("```diff\r\n{}\r\n```\r\n", crlf);
+        assert_eq!(extract_patch(&wrapped).unwrap(), format!("{}\r\n", crlf));
+    }
+
+    #[test]
+    fn extract_patch_rejects_non_diffs() {
+        assert!(extract_patch("").is_err());
+        assert!(extract_patch("just some text\n+++ not a hunk\n").is_err());
+        assert!(extract_patch("```diff\n--- a\n+++ b\n@@ -1 +1 @@\n").is_err());
+    }
+}
@@ -3,13 +3,316 @@
 //! This module extracts domain logic for resolving pull requests and fork branches
 //! from the command layer, making it reusable and testable.
 
-use crate::{git, github, spinner};
+use crate::config::{PrConfig, PrProvider};
+use crate::{git, github, gitlab, spinner};
 use anyhow::{Context, Result, anyhow};
+use git_url_parse::GitUrl;
 
 /// Result of resolving a PR checkout.
 pub struct PrCheckoutResult {
     pub local_branch: String,
     pub remote_branch: String,
+    pub title: String,
+    /// Remote-tracking ref of the branch the PR merges into, if known
+    pub base_branch: Option<String>,
+}
+
+/// Code host a PR number is resolved on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+    GitHub,
+    GitLab,
+}
+
+impl Provider {
+    /// How the host refers to a change request, e.g. "PR #12" or "MR !12"
+    pub fn label(self, number: u32) -> String {
+        match self {
+            Provider::GitHub => format!("PR #{}", number),
+            Provider::GitLab => format!("MR !{}", number),
+        }
+    }
+}
+
+/// Pick the provider from the `pr` config, detecting it from the remote's URL
+/// when set to auto (the default).
+pub fn provider(config: Option<&PrConfig>, remote: &str) -> Provider {
+    let default = PrConfig::default();
+    let config = config.unwrap_or(&default);
+    match config.provider() {
+        PrProvider::Github => Provider::GitHub,
+        PrProvider::Gitlab => Provider::GitLab,
+        PrProvider::Auto => git::get_remote_url(remote)
+            .map(|url| detect_provider(&url, config.gitlab_hosts()))
+            .unwrap_or(Provider::GitHub),
+    }
+}
+
+/// GitLab if the URL's host looks like GitLab or is a configured GitLab host,
+/// otherwise GitHub
+fn detect_provider(url: &str, gitlab_hosts: &[String]) -> Provider {
+    let host = GitUrl::parse(url)
+        .ok()
+        .and_then(|url| url.host().map(str::to_lowercase));
+    match host {
+        Some(host)
+            if host.contains("gitlab")
+                || gitlab_hosts.iter().any(|h| h.eq_ignore_ascii_case(&host)) =>
+        {
+            Provider::GitLab
+        }
+        _ => Provider::GitHub,
+    }
+}
+
+/// Whether a pull or merge request is still open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeState {
+    Open,
+    Merged,
+    Closed,
+}
+
+impl ChangeState {
+    /// From GitHub's (`OPEN`, `MERGED`, `CLOSED`) or GitLab's (`opened`,
+    /// `merged`, `closed`, `locked`) state
+    fn parse(state: &str) -> Self {
+        match state.to_lowercase().as_str() {
+            "merged" => Self::Merged,
+            "closed" => Self::Closed,
+            _ => Self::Open,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Merged => "merged",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+/// Look up whether a pull or merge request was merged or closed
+pub fn change_state(provider: Provider, number: u32) -> Result<ChangeState> {
+    let state = match provider {
+        Provider::GitHub => github::get_pr_details(number)?.state,
+        Provider::GitLab => gitlab::get_mr_details(number)?.state,
+    };
+    Ok(ChangeState::parse(&state))
+}
+
+/// A pull or merge request's description and unresolved review comments,
+/// handed to the agent by `add --pr --with-context`
+#[derive(Debug, PartialEq)]
+pub struct ReviewContext {
+    pub title: String,
+    pub description: String,
+    pub threads: Vec<ReviewThread>,
+}
+
+/// Comments on one place in the diff, or on the change as a whole
+#[derive(Debug, PartialEq)]
+pub struct ReviewThread {
+    /// `path:line`, for threads on the diff
+    pub location: Option<String>,
+    /// The commented lines have changed since
+    pub outdated: bool,
+    /// Author and text of each comment, oldest first
+    pub comments: Vec<(String, String)>,
+}
+
+impl ReviewContext {
+    /// Markdown for the prompt, headed by the change's label, e.g. "PR #12"
+    pub fn render(&self, label: &str) -> String {
+        let mut out = format!("# {}: {}\n\n", label, self.title);
+        let description = self.description.trim();
+        if description.is_empty() {
+            out.push_str("(No description.)\n");
+        } else {
+            out.push_str(description);
+            out.push('\n');
+        }
+
+        out.push_str("\n## Unresolved review comments\n");
+        if self.threads.is_empty() {
+            out.push_str("\nNone.\n");
+        }
+        for thread in &self.threads {
+            let heading = match &thread.location {
+                Some(location) => format!("`{}`", location),
+                None => "General".to_string(),
+            };
+            let outdated = if thread.outdated { " (outdated)" } else { "" };
+            out.push_str(&format!("\n### {}{}\n", heading, outdated));
+            for (author, body) in &thread.comments {
+                out.push_str(&format!("\n**{}** wrote:\n\n", author));
+                for line in body.trim().lines() {
+                    if line.is_empty() {
+                        out.push_str(">\n");
+                    } else {
+                        out.push_str(&format!("> {}\n", line));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Fetch a pull or merge request's description and unresolved review threads
+pub fn review_context(provider: Provider, number: u32) -> Result<ReviewContext> {
+    match provider {
+        Provider::GitHub => {
+            let pr = github::get_pr_reviews(number)?;
+            let truncated = pr
+                .review_threads
+                .nodes
+                .iter()
+                .filter(|thread| {
+                    !thread.is_resolved && thread.comments.total_count > thread.comments.nodes.len()
+                })
+                .count();
+            if truncated > 0 {
+                eprintln!(
+                    "Warning: {} review thread(s) of PR #{} have over 100 comments; only the first 100 of each are included",
+                    truncated, number
+                );
+            }
+            let threads = pr
+                .review_threads
+                .nodes
+                .into_iter()
+                .filter(|thread| !thread.is_resolved)
+                .map(|thread| ReviewThread {
+                    location: Some(match thread.line.or(thread.original_line) {
+                        Some(line) => format!("{}:{}", thread.path, line),
+                        None => thread.path,
+                    }),
+                    outdated: thread.is_outdated,
+                    comments: thread
+                        .comments
+                        .nodes
+                        .into_iter()
+                        .map(|comment| {
+                            let author = comment
+                                .author
+                                .map_or_else(|| "ghost".to_string(), |author| author.login);
+                            (author, comment.body)
+                        })
+                        .collect(),
+                })
+                .collect();
+            Ok(ReviewContext {
+                title: pr.title,
+                description: pr.body,
+                threads,
+            })
+        }
+        Provider::GitLab => {
+            let mr = gitlab::get_mr_details(number)?;
+            let threads = gitlab::get_mr_discussions(number)?
+                .into_iter()
+                .filter(|discussion| {
+                    discussion
+                        .notes
+                        .iter()
+                        .any(|note| note.resolvable && !note.resolved)
+                })
+                .map(|discussion| {
+                    let location = discussion
+                        .notes
+                        .first()
+                        .and_then(|note| note.position.as_ref())
+                        .and_then(|position| {
+                            let path = position.new_path.as_ref()?;
+                            Some(match position.new_line.or(position.old_line) {
+                                Some(line) => format!("{}:{}", path, line),
+                                None => path.clone(),
+                            })
+                        });
+                    ReviewThread {
+                        location,
+                        outdated: false,
+                        comments: discussion
+                            .notes
+                            .into_iter()
+                            .filter(|note| !note.system)
+                            .map(|note| (note.author.username, note.body))
+                            .collect(),
+                    }
+                })
+                .collect();
+            Ok(ReviewContext {
+                title: mr.title,
+                description: mr.description.unwrap_or_default(),
+                threads,
+            })
+        }
+    }
+}
+
+/// A pull or merge request, as needed to check out its branch
+struct ChangeRequest {
+    title: String,
+    author: String,
+    branch: String,
+    /// Branch it merges into, if the host reported it
+    base: Option<String>,
+    state: String,
+    is_open: bool,
+    is_draft: bool,
+    /// Owner of the fork the branch lives in, if it isn't the base repository
+    /// and the host reports it
+    fork_owner: Option<String>,
+    /// Whether the branch lives in a fork
+    fork: bool,
+}
+
+fn fetch_change_request(provider: Provider, number: u32, remote: &str) -> Result<ChangeRequest> {
+    match provider {
+        Provider::GitHub => {
+            let pr = github::get_pr_details(number)?;
+
+            // Determine if this is a fork PR
+            let current_repo_owner = git::get_repo_owner(remote).with_context(|| {
+                format!(
+                    "Failed to determine repository owner from '{}' remote",
+                    remote
+                )
+            })?;
+            let fork_owner = pr
+                .is_fork(&current_repo_owner)
+                .then(|| pr.head_repository_owner.login.clone());
+
+            Ok(ChangeRequest {
+                is_open: pr.state == "OPEN",
+                title: pr.title,
+                author: pr.author.login,
+                branch: pr.head_ref_name,
+                base: pr.base_ref_name,
+                state: pr.state,
+                is_draft: pr.is_draft,
+                fork: fork_owner.is_some(),
+                fork_owner,
+            })
+        }
+        Provider::GitLab => {
+            let mr = gitlab::get_mr_details(number)?;
+            let fork = mr.is_fork();
+            Ok(ChangeRequest {
+                is_open: mr.state == "opened",
+                title: mr.title,
+                author: mr.author.username,
+                branch: mr.source_branch,
+                base: mr.target_branch,
+                state: mr.state.to_uppercase(),
+                is_draft: mr.draft,
+                fork_owner: None,
+                fork,
+            })
+        }
+    }
 }
 
 /// Resolve a PR reference and prepare for checkout.
@@ -19,51 +322,63 @@ pub struct PrCheckoutResult {
 pub fn resolve_pr_ref(
     pr_number: u32,
     custom_branch_name: Option<&str>,
+    remote: &str,
+    provider: Provider,
 ) -> Result<PrCheckoutResult> {
-    let pr_details = spinner::with_spinner(&format!("Fetching PR #{}", pr_number), || {
-        github::get_pr_details(pr_number)
+    let label = provider.label(pr_number);
+    let pr_details = spinner::with_spinner(&format!("Fetching {}", label), || {
+        fetch_change_request(provider, pr_number, remote)
     })
-    .with_context(|| format!("Failed to fetch details for PR #{}", pr_number))?;
+    .with_context(|| format!("Failed to fetch details for {}", label))?;
 
     // Display PR information
-    println!("PR #{}: {}", pr_number, pr_details.title);
-    println!("Author: {}", pr_details.author.login);
-    println!("Branch: {}", pr_details.head_ref_name);
+    println!("{}: {}", label, pr_details.title);
+    println!("Author: {}", pr_details.author);
+    println!("Branch: {}", pr_details.branch);
 
     // Warn about PR state
-    if pr_details.state != "OPEN" {
+    if !pr_details.is_open {
         eprintln!(
-            "⚠️  Warning: PR #{} is {}. Proceeding with checkout...",
-            pr_number, pr_details.state
+            "⚠️  Warning: {} is {}. Proceeding with checkout...",
+            label, pr_details.state
         );
     }
     if pr_details.is_draft {
-        eprintln!("⚠️  Warning: PR #{} is a DRAFT.", pr_number);
+        eprintln!("⚠️  Warning: {} is a DRAFT.", label);
     }
 
     // Determine local branch name (match gh pr checkout behavior)
     let local_branch = custom_branch_name
         .map(String::from)
-        .unwrap_or_else(|| pr_details.head_ref_name.clone());
+        .unwrap_or_else(|| pr_details.branch.clone());
 
-    // Determine if this is a fork PR and ensure remote exists
-    let current_repo_owner =
-        git::get_repo_owner().context("Failed to determine repository owner from origin remote")?;
-
-    let remote_name = if pr_details.is_fork(&current_repo_owner) {
-        let fork_owner = &pr_details.head_repository_owner.login;
-        git::ensure_fork_remote(fork_owner)?
-    } else {
-        "origin".to_string()
+    // Note: We do not fetch here, except for merge requests from forks. The
+    // `create` workflow handles fetching the remote branch to ensure the
+    // worktree base is up to date.
+    let remote_branch = match &pr_details.fork_owner {
+        Some(fork_owner) => format!(
+            "{}/{}",
+            git::ensure_fork_remote(fork_owner, remote)?,
+            pr_details.branch
+        ),
+        // GitLab doesn't say where the fork is, but the target project keeps
+        // a copy of the merge request's head
+        None if pr_details.fork => spinner::with_spinner(&format!("Fetching {}", label), || {
+            git::fetch_merge_request(remote, pr_number)
+        })?,
+        None => format!("{}/{}", remote, pr_details.branch),
     };
-
-    // Note: We do not fetch here. The `create` workflow handles fetching
-    // the remote branch to ensure the worktree base is up to date.
-    let remote_branch = format!("{}/{}", remote_name, pr_details.head_ref_name);
+    // The base lives in the base repository, even for PRs from forks
+    let base_branch = pr_details
+        .base
+        .or_else(|| git::get_default_branch(remote).ok())
+        .map(|base| format!("{}/{}", remote, base));
 
     Ok(PrCheckoutResult {
         local_branch,
         remote_branch,
+        title: pr_details.title,
+        base_branch,
     })
 }
 
@@ -76,7 +391,10 @@ pub struct ForkBranchResult {
 /// Resolve a fork branch specified as "owner:branch".
 ///
 /// Sets up the fork remote and optionally displays associated PR info.
-pub fn resolve_fork_branch(fork_spec: &git::ForkBranchSpec) -> Result<ForkBranchResult> {
+pub fn resolve_fork_branch(
+    fork_spec: &git::ForkBranchSpec,
+    remote: &str,
+) -> Result<ForkBranchResult> {
     // Try to find an associated PR and display info (optional, non-blocking)
     if let Ok(Some(pr)) = github::find_pr_by_head_ref(&fork_spec.owner, &fork_spec.branch) {
         let state_suffix = match pr.state.as_str() {
@@ -90,7 +408,7 @@ pub fn resolve_fork_branch(fork_spec: &git::ForkBranchSpec) -> Result<ForkBranch
     }
 
     // Ensure the fork remote exists
-    let remote_name = git::ensure_fork_remote(&fork_spec.owner)?;
+    let remote_name = git::ensure_fork_remote(&fork_spec.owner, remote)?;
 
     // Note: We do not fetch or verify the branch exists here.
     // The `create` workflow will perform the fetch and fail if the branch is missing.
@@ -105,10 +423,12 @@ pub fn resolve_fork_branch(fork_spec: &git::ForkBranchSpec) -> Result<ForkBranch
 /// Detect if a branch name refers to a remote branch and extract the base name.
 ///
 /// Handles both "remote/branch" format and "owner:branch" (GitHub fork) format.
+/// `remote` is the upstream remote that fork remotes are derived from.
 /// Returns (remote_branch, template_base_name).
 pub fn detect_remote_branch(
     branch_name: &str,
     base: Option<&str>,
+    remote: &str,
 ) -> Result<(Option<String>, String)> {
     // 1. Check for owner:branch syntax (GitHub fork format, e.g., "someuser:feature-a")
     if let Some(fork_spec) = git::parse_fork_branch_spec(branch_name) {
@@ -121,7 +441,7 @@ pub fn detect_remote_branch(
             ));
         }
 
-        let result = resolve_fork_branch(&fork_spec)?;
+        let result = resolve_fork_branch(&fork_spec, remote)?;
         return Ok((Some(result.remote_ref), result.template_base_name));
     }
 
@@ -152,3 +472,83 @@ pub fn detect_remote_branch(
         Ok((None, branch_name.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeState, Provider, ReviewContext, ReviewThread, detect_provider};
+
+    #[test]
+    fn review_context_renders_threads_as_quotes() {
+        let context = ReviewContext {
+            title: "Add login".to_string(),
+            description: "Adds a login form.\n".to_string(),
+            threads: vec![
+                ReviewThread {
+                    location: Some("src/auth.rs:12".to_string()),
+                    outdated: true,
+                    comments: vec![
+                        (
+                            "alice".to_string(),
+                            "Handle the error.\n\nIt can fail.".to_string(),
+                        ),
+                        ("bob".to_string(), "Agreed".to_string()),
+                    ],
+                },
+                ReviewThread {
+                    location: None,
+                    outdated: false,
+                    comments: vec![("carol".to_string(), "Add tests".to_string())],
+                },
+            ],
+        };
+        assert_eq!(
+            context.render("PR #7"),
+            "# PR #7: Add login\n\nAdds a login form.\n\n## Unresolved review comments\n\n\
+             ### `src/auth.rs:12` (outdated)\n\n**alice** wrote:\n\n> Handle the error.\n>\n> It can fail.\n\n\
+             **bob** wrote:\n\n> Agreed\n\n### General\n\n**carol** wrote:\n\n> Add tests\n"
+        );
+
+        let empty = ReviewContext {
+            title: "Fix".to_string(),
+            description: String::new(),
+            threads: Vec::new(),
+        };
+        assert_eq!(
+            empty.render("MR !3"),
+            "# MR !3: Fix\n\n(No description.)\n\n## Unresolved review comments\n\nNone.\n"
+        );
+    }
+
+    #[test]
+    fn github_and_gitlab_states_are_understood() {
+        assert_eq!(ChangeState::parse("OPEN"), ChangeState::Open);
+        assert_eq!(ChangeState::parse("MERGED"), ChangeState::Merged);
+        assert_eq!(ChangeState::parse("closed"), ChangeState::Closed);
+        assert_eq!(ChangeState::parse("locked"), ChangeState::Open);
+    }
+
+    #[test]
+    fn provider_is_detected_from_the_remote_host() {
+        let hosts = vec!["git.example.com".to_string()];
+        assert_eq!(
+            detect_provider("git@github.com:owner/repo.git", &hosts),
+            Provider::GitHub
+        );
+        assert_eq!(
+            detect_provider("https://gitlab.com/group/repo.git", &hosts),
+            Provider::GitLab
+        );
+        assert_eq!(
+            detect_provider("git@gitlab.corp.net:group/repo.git", &[]),
+            Provider::GitLab
+        );
+        assert_eq!(
+            detect_provider("ssh://git@Git.Example.com:2222/group/repo.git", &hosts),
+            Provider::GitLab
+        );
+        assert_eq!(
+            detect_provider("git@git.example.com:group/repo.git", &[]),
+            Provider::GitHub
+        );
+    }
+}
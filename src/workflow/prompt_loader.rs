@@ -18,15 +18,7 @@ pub struct PromptLoadArgs<'a> {
 /// Load a prompt from the provided arguments (editor, inline, or file).
 pub fn load_prompt(args: &PromptLoadArgs) -> Result<Option<Prompt>> {
     if args.prompt_editor {
-        let mut builder = Builder::new();
-        builder.suffix(".md");
-        let editor_content = edit::edit_with_builder("", &builder)
-            .context("Failed to open editor or read content")?;
-        let trimmed = editor_content.trim();
-        if trimmed.is_empty() {
-            return Err(anyhow!("Aborting: prompt is empty"));
-        }
-        Ok(Some(Prompt::Inline(trimmed.to_string())))
+        Ok(Some(Prompt::Inline(edit_prompt("")?)))
     } else {
         Ok(match (args.prompt_inline, args.prompt_file) {
             (Some(inline), None) => Some(Prompt::Inline(inline.to_string())),
@@ -37,6 +29,19 @@ pub fn load_prompt(args: &PromptLoadArgs) -> Result<Option<Prompt>> {
     }
 }
 
+/// Open the editor on `initial` and return the prompt written, trimmed.
+pub fn edit_prompt(initial: &str) -> Result<String> {
+    let mut builder = Builder::new();
+    builder.suffix(".md");
+    let editor_content = edit::edit_with_builder(initial, &builder)
+        .context("Failed to open editor or read content")?;
+    let trimmed = editor_content.trim();
+    if trimmed.is_empty() {
+        return Err(anyhow!("Aborting: prompt is empty"));
+    }
+    Ok(trimmed.to_string())
+}
+
 /// Parse a prompt with optional frontmatter extraction.
 ///
 /// Returns a PromptDocument with parsed metadata and body.
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::{git, remote, tmux};
+
+use super::context::WorkflowContext;
+use super::types::{LeftoverDirectory, PrunePlan};
+
+/// Suffix Linux adds to the working directory of a process whose directory
+/// was removed, which tmux passes on as the pane's path
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// `path` as given and, if it exists, with symlinks resolved, since tmux and
+/// git may report either
+fn forms(path: &Path) -> Vec<PathBuf> {
+    let mut forms = vec![path.to_path_buf()];
+    if let Ok(canonical) = path.canonicalize()
+        && canonical != path
+    {
+        forms.push(canonical);
+    }
+    forms
+}
+
+/// The worktree directory a window's `path` is in: the entry of one of
+/// `base_dirs` containing it, or None if it isn't below any of them
+fn worktree_dir_of(path: &Path, base_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let path = path
+        .to_str()
+        .and_then(|path| path.strip_suffix(DELETED_SUFFIX))
+        .map_or(path, Path::new);
+    base_dirs.iter().find_map(|base| {
+        let entry = path.strip_prefix(base).ok()?.components().next()?;
+        Some(base.join(entry))
+    })
+}
+
+/// Find what's left over from worktrees removed without workmux: windows of
+/// worktrees that are gone, worktrees git still lists though their directory
+/// is gone, and directories in the worktree directory that aren't worktrees.
+pub fn plan_prune(context: &WorkflowContext) -> Result<PrunePlan> {
+    let stale_worktrees = git::prunable_worktrees()?;
+    let live: Vec<PathBuf> = git::list_worktrees()?
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| !stale_worktrees.iter().any(|(stale, _)| stale == path))
+        .flat_map(|path| forms(&path))
+        .collect();
+    let base_dir = context.worktree_base_dir()?;
+    let base_dirs = forms(&base_dir);
+
+    // Windows are matched to worktrees by the path they were tagged with, or
+    // else their active pane's directory
+    let mut windows = Vec::new();
+    if tmux::is_running()? {
+        let tagged = tmux::tagged_worktrees()?;
+        for window in tmux::list_workmux_windows(&context.prefix)? {
+            let path = tagged
+                .iter()
+                .find(|(window_id, _)| *window_id == window.window_id)
+                .map_or(&window.path, |(_, tag)| &tag.path);
+            if let Some(dir) = worktree_dir_of(path, &base_dirs)
+                && !live.contains(&dir)
+            {
+                windows.push(window);
+            }
+        }
+    }
+
+    let mut directories = Vec::new();
+    let directories_unchecked = if remote::get().is_some() {
+        Some("not supported with --host".to_string())
+    } else if base_dirs
+        .iter()
+        .any(|base| context.main_worktree_root.starts_with(base))
+    {
+        // Other projects may live next to the main worktree
+        Some(format!(
+            "{} also contains the main worktree",
+            base_dir.display()
+        ))
+    } else {
+        if let Ok(entries) = fs::read_dir(&base_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.is_dir() || forms(&path).iter().any(|form| live.contains(form)) {
+                    continue;
+                }
+                // An empty directory may be a mount point kept for a worktree
+                let is_empty = fs::read_dir(&path)
+                    .map(|mut entries| entries.next().is_none())
+                    .unwrap_or(true);
+                if is_empty {
+                    continue;
+                }
+                let skip_reason = path
+                    .join(".git")
+                    .exists()
+                    .then(|| "contains .git, so may be a repository".to_string());
+                directories.push(LeftoverDirectory { path, skip_reason });
+            }
+        }
+        directories.sort_by(|a, b| a.path.cmp(&b.path));
+        None
+    };
+
+    info!(
+        windows = windows.len(),
+        stale_worktrees = stale_worktrees.len(),
+        directories = directories.len(),
+        "prune:plan"
+    );
+    Ok(PrunePlan {
+        windows,
+        stale_worktrees,
+        directories,
+        directories_unchecked,
+    })
+}
+
+/// Close the plan's windows, prune git's worktree metadata, and delete the
+/// directories not marked as kept. The window workmux runs in, if it's one
+/// of them, closes once workmux exits.
+pub fn apply_prune(plan: &PrunePlan) -> Result<()> {
+    let current_window = tmux::current_pane_id().and_then(|pane| tmux::pane_window_id(&pane).ok());
+    for window in &plan.windows {
+        let result = if current_window.as_ref() == Some(&window.window_id) {
+            tmux::run_shell(&format!(
+                "sleep 0.3; tmux {} -t {} >/dev/null 2>&1",
+                tmux::close_command(),
+                window.window_id
+            ))
+        } else {
+            tmux::kill_window(&window.window_id)
+        };
+        if let Err(e) = result {
+            warn!(window = window.window_id, error = %e, "prune:failed to close window");
+        }
+    }
+
+    if !plan.stale_worktrees.is_empty() {
+        git::prune_worktrees()?;
+    }
+
+    for directory in plan.directories.iter().filter(|d| d.skip_reason.is_none()) {
+        fs::remove_dir_all(&directory.path)
+            .with_context(|| format!("Failed to remove '{}'", directory.path.display()))?;
+    }
+
+    info!(
+        windows = plan.windows.len(),
+        stale_worktrees = plan.stale_worktrees.len(),
+        "prune:applied"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_map_to_the_worktree_directory_they_are_in() {
+        let base_dirs = [PathBuf::from("/src/app__worktrees")];
+        assert_eq!(
+            worktree_dir_of(Path::new("/src/app__worktrees/login/src"), &base_dirs),
+            Some(PathBuf::from("/src/app__worktrees/login"))
+        );
+        assert_eq!(
+            worktree_dir_of(Path::new("/src/app__worktrees/login (deleted)"), &base_dirs),
+            Some(PathBuf::from("/src/app__worktrees/login"))
+        );
+        assert_eq!(
+            worktree_dir_of(Path::new("/src/app__worktrees"), &base_dirs),
+            None
+        );
+        assert_eq!(worktree_dir_of(Path::new("/src/app"), &base_dirs), None);
+    }
+}
@@ -1,6 +1,6 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Result, anyhow};
 
-use crate::git;
+use crate::{git, worktree_state};
 use tracing::{debug, info};
 
 use super::cleanup;
@@ -18,8 +18,7 @@ pub fn remove(
 
     // Get worktree path and branch - this also validates that the worktree exists
     // Smart resolution: try handle first, then branch name
-    let (worktree_path, branch_name) = git::find_worktree(handle)
-        .with_context(|| format!("No worktree found with name '{}'", handle))?;
+    let (worktree_path, branch_name) = git::find_worktree(handle)?;
     debug!(handle = handle, branch = branch_name, path = %worktree_path.display(), "remove:worktree resolved");
 
     // Safety Check: Prevent deleting the main worktree itself, regardless of branch.
@@ -57,6 +56,16 @@ pub fn remove(
         ));
     }
 
+    if let Some(dir) = worktree_path.file_name().and_then(|n| n.to_str())
+        && worktree_state::is_pinned(dir)
+    {
+        return Err(anyhow!(
+            "Worktree '{}' is pinned. Run 'workmux unpin {}' first.",
+            dir,
+            dir
+        ));
+    }
+
     if worktree_path.exists() && git::has_uncommitted_changes(&worktree_path)? && !force {
         return Err(anyhow!(
             "Worktree has uncommitted changes. Use --force to delete anyway."
@@ -75,11 +84,12 @@ pub fn remove(
         keep_branch,
     )?;
 
-    // Navigate to the main branch window and close the source window
+    // Navigate per `after_close` (main branch window by default) and close the source window
     cleanup::navigate_to_target_and_close(
         &context.prefix,
         &context.main_branch,
         handle,
+        context.config.after_close.unwrap_or_default(),
         &cleanup_result,
     )?;
 
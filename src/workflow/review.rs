@@ -0,0 +1,211 @@
+use anyhow::{Context, Result, anyhow};
+use tracing::info;
+
+use crate::config::{PaneConfig, ReviewTarget, SplitDirection};
+use crate::prompt::Prompt;
+use crate::{agent, git, tmux, worktree_state};
+
+use super::context::WorkflowContext;
+use super::setup::write_prompt_file;
+use super::types::{ReviewArgs, ReviewResult};
+
+/// Diffs larger than this are truncated so the prompt still fits on a command line
+const MAX_DIFF_BYTES: usize = 100_000;
+
+/// Cut a diff down to `MAX_DIFF_BYTES`, returning whether it was cut
+pub(super) fn truncate_diff(diff: &str) -> (&str, bool) {
+    if diff.len() <= MAX_DIFF_BYTES {
+        return (diff, false);
+    }
+    let mut end = MAX_DIFF_BYTES;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&diff[..end], true)
+}
+
+/// Build the prompt handed to the reviewer agent.
+pub fn review_prompt(branch: &str, base: &str, diff: &str, output_file: &str) -> String {
+    let (diff, truncated) = truncate_diff(diff);
+
+    let mut prompt = format!(
+        "Review the changes on branch `{branch}` against `{base}`.\n\
+         \n\
+         Look for bugs, missing edge cases, unclear code and missing tests. \
+         Do not modify any files other than `{output_file}`.\n\
+         \n\
+         Write your findings to `{output_file}` in the current directory, grouped by \
+         file, with the most important issues first. If you find nothing worth \
+         changing, say so in that file.\n\
+         \n\
+         ```diff\n{diff}\n```\n"
+    );
+    if truncated {
+        prompt.push_str(&format!(
+            "\nThe diff was truncated. Run `git diff $(git merge-base {base} HEAD)` to see the rest.\n"
+        ));
+    }
+    prompt
+}
+
+/// Launch a reviewer agent for a worktree's changes.
+///
+/// The reviewer either splits the worktree's tmux window or gets its own
+/// `<handle>-review` window, and receives the branch's diff against its base.
+pub fn review(name: &str, context: &WorkflowContext, args: ReviewArgs) -> Result<ReviewResult> {
+    context.ensure_tmux_running()?;
+
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    // The window is named after the directory, even when looked up by branch
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    let review_config = context.config.review.clone().unwrap_or_default();
+    let base = match args.base {
+        Some(base) => base.to_string(),
+        None => worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone()),
+    };
+    let output_file = args.output.unwrap_or(review_config.output()).to_string();
+
+    let diff = git::get_diff_from_base(&worktree_path, &base)?;
+    if diff.is_empty() {
+        return Err(anyhow!(
+            "Branch '{}' has no changes against '{}' to review",
+            branch,
+            base
+        ));
+    }
+
+    let agent = args
+        .agent
+        .map(str::to_string)
+        .or(review_config.agent.clone())
+        .map(|agent| agent::command(&context.config, &agent))
+        .or(context.config.agent.clone())
+        .unwrap_or_else(|| "claude".to_string());
+    let target = args.target.or(review_config.open_in).unwrap_or_default();
+
+    let prompt = review_prompt(&branch, &base, &diff, &output_file);
+    let prompt_file_path =
+        write_prompt_file(&format!("{}-review", handle), &Prompt::Inline(prompt))?;
+
+    info!(
+        handle = %handle,
+        branch = branch,
+        base = base,
+        agent = agent,
+        target = ?target,
+        "review:launch"
+    );
+
+    let reviewer_pane = PaneConfig {
+        command: Some(agent.clone()),
+        focus: true,
+        split: None,
+        size: None,
+        percentage: None,
+        target: None,
+    };
+
+    // Both layouts go through setup_panes so the prompt is injected the same way
+    // as for regular agent panes
+    let (initial_pane_id, panes) = match target {
+        ReviewTarget::Pane => {
+            if !tmux::window_exists(&context.prefix, &handle)? {
+                return Err(anyhow!(
+                    "No tmux window for '{}'. Run 'workmux open {}' first or use --window.",
+                    handle,
+                    handle
+                ));
+            }
+            // An empty first pane leaves the worktree's active pane untouched
+            let existing_pane = PaneConfig {
+                command: None,
+                focus: false,
+                split: None,
+                size: None,
+                percentage: None,
+                target: None,
+            };
+            let split_pane = PaneConfig {
+                split: Some(review_config.split.unwrap_or(SplitDirection::Horizontal)),
+                ..reviewer_pane
+            };
+            (
+                tmux::active_pane_id(&context.prefix, &handle)?,
+                vec![existing_pane, split_pane],
+            )
+        }
+        ReviewTarget::Window => {
+            let window_name = format!("{}-review", handle);
+            if tmux::window_exists(&context.prefix, &window_name)? {
+                return Err(anyhow!(
+                    "A review window for '{}' is already open. Close it before starting another review.",
+                    handle
+                ));
+            }
+            let pane_id = tmux::create_window(
+                &context.prefix,
+                &window_name,
+                &worktree_path,
+                /* detached: */ false,
+                None,
+            )
+            .context("Failed to create tmux window")?;
+            (pane_id, vec![reviewer_pane])
+        }
+    };
+
+    let pane_setup_result = tmux::setup_panes(
+        &initial_pane_id,
+        &panes,
+        &worktree_path,
+        tmux::PaneSetupOptions {
+            run_commands: true,
+            prompt_file_path: Some(&prompt_file_path),
+            pane_prompt_paths: &[],
+        },
+        &context.config,
+        Some(&agent),
+    )
+    .context("Failed to start reviewer agent")?;
+
+    if target == ReviewTarget::Pane {
+        tmux::select_window(&context.prefix, &handle)?;
+    }
+    tmux::select_pane(&pane_setup_result.focus_pane_id)?;
+
+    Ok(ReviewResult {
+        branch,
+        base,
+        agent,
+        output_path: worktree_path.join(output_file),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_prompt_includes_diff_and_output_file() {
+        let prompt = review_prompt("feature/auth", "main", "+fn login() {}", "REVIEW.md");
+        assert!(prompt.contains("`feature/auth` against `main`"));
+        assert!(prompt.contains("```diff\n+fn login() {}\n```"));
+        assert!(prompt.contains("Write your findings to `REVIEW.md`"));
+        assert!(!prompt.contains("truncated"));
+    }
+
+    #[test]
+    fn review_prompt_truncates_large_diffs() {
+        let diff = "é".repeat(MAX_DIFF_BYTES);
+        let prompt = review_prompt("feature", "main", &diff, "REVIEW.md");
+        assert!(prompt.len() < diff.len());
+        assert!(prompt.contains("The diff was truncated"));
+        assert!(prompt.contains("git merge-base main HEAD"));
+    }
+}
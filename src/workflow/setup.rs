@@ -2,13 +2,20 @@ use anyhow::{Context, Result, anyhow};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::{cmd, config, git, prompt::Prompt, tmux};
-use tracing::{debug, info};
+use crate::{
+    agent, cmd, config, dry_run, git, interrupt, prompt::Prompt, remote, template, tmux,
+    worktree_state,
+};
+use tracing::{debug, info, warn};
 
 use fs_extra::dir as fs_dir;
 use fs_extra::file as fs_file;
 
-use super::types::CreateResult;
+use super::bootstrap;
+use super::hooks::HookEnv;
+use super::ignore::WorkmuxIgnore;
+use super::instructions;
+use super::types::{AdoptResult, CreateResult, FileFilter};
 
 /// Sets up the tmux window, files, and hooks for a worktree.
 /// This is the shared logic between `create` and `open`.
@@ -36,56 +43,18 @@ pub fn setup_environment(
         run_file_ops = options.run_file_ops,
         "setup_environment:start"
     );
+    // An agent given by name runs its defined command
+    let agent = agent.map(|agent| agent::command(config, agent));
+    let agent = agent.as_deref();
     let prefix = config.window_prefix();
-    // Use main worktree root for file operations since source files live there
-    let repo_root = git::get_main_worktree_root()?;
-
-    // Perform file operations (copy and symlink) if requested
-    if options.run_file_ops {
-        handle_file_operations(&repo_root, worktree_path, &config.files)
-            .context("Failed to perform file operations")?;
-        debug!(
-            branch = branch_name,
-            "setup_environment:file operations applied"
-        );
-    }
-
-    // Run post-create hooks before opening tmux so the new window appears "ready"
-    let mut hooks_run = 0;
-    if options.run_hooks
-        && let Some(post_create) = &config.post_create
-        && !post_create.is_empty()
-    {
-        hooks_run = post_create.len();
-        // Resolve absolute paths for environment variables.
-        // canonicalize() ensures symlinks are resolved and paths are absolute.
-        let abs_worktree_path = worktree_path
-            .canonicalize()
-            .unwrap_or_else(|_| worktree_path.to_path_buf());
-        let abs_project_root = repo_root
-            .canonicalize()
-            .unwrap_or_else(|_| repo_root.clone());
-        let worktree_path_str = abs_worktree_path.to_string_lossy();
-        let project_root_str = abs_project_root.to_string_lossy();
-        let hook_env = [
-            ("WORKMUX_HANDLE", handle),
-            ("WM_HANDLE", handle),
-            ("WM_WORKTREE_PATH", worktree_path_str.as_ref()),
-            ("WM_PROJECT_ROOT", project_root_str.as_ref()),
-        ];
-        for (idx, command) in post_create.iter().enumerate() {
-            info!(branch = branch_name, step = idx + 1, total = hooks_run, command = %command, "setup_environment:hook start");
-            info!(command = %command, "Running post-create hook {}/{}", idx + 1, hooks_run);
-            cmd::shell_command_with_env(command, worktree_path, &hook_env)
-                .with_context(|| format!("Failed to run post-create command: '{}'", command))?;
-            info!(branch = branch_name, step = idx + 1, total = hooks_run, command = %command, "setup_environment:hook complete");
-        }
-        info!(
-            branch = branch_name,
-            total = hooks_run,
-            "setup_environment:hooks complete"
-        );
-    }
+    let (hooks_run, hook_env) = prepare_worktree(
+        branch_name,
+        handle,
+        worktree_path,
+        config,
+        options,
+        agent.or(config.agent.as_deref()),
+    )?;
 
     // Find the last workmux-managed window to insert the new one after.
     // This keeps worktree windows grouped together instead of appending at the end.
@@ -108,15 +77,33 @@ pub fn setup_environment(
         pane_id = %initial_pane_id,
         "setup_environment:tmux window created"
     );
+    tmux::tag_window(&initial_pane_id, handle, worktree_path)?;
+    show_task(&initial_pane_id, handle);
+
+    if options.run_hooks {
+        install_focus_hooks(&initial_pane_id, worktree_path, &hook_env, config)
+            .context("Failed to install focus hooks")?;
+    }
 
     // Setup panes
-    let panes = config.panes.as_deref().unwrap_or(&[]);
-    let resolved_panes = resolve_pane_configuration(panes, agent);
+    let mut panes = config.panes.as_deref().unwrap_or_default().to_vec();
+    for pane in &mut panes {
+        if let Some(command) = &pane.command {
+            pane.command = Some(hook_env.render(command).context("Failed to setup panes")?);
+        }
+    }
+    let resolved_panes = resolve_pane_configuration(&panes, agent);
 
     // Validate that prompt will be consumed if one was provided
     if options.prompt_file_path.is_some() {
         validate_prompt_consumption(&resolved_panes, agent, config, options)?;
     }
+    if !options.run_pane_commands && options.pane_prompt_paths.iter().any(Option::is_some) {
+        return Err(anyhow!(
+            "Team prompts provided but pane commands are disabled (--no-pane-cmds). \
+             The prompts would be ignored."
+        ));
+    }
 
     let pane_setup_result = tmux::setup_panes(
         &initial_pane_id,
@@ -125,6 +112,7 @@ pub fn setup_environment(
         tmux::PaneSetupOptions {
             run_commands: options.run_pane_commands,
             prompt_file_path: options.prompt_file_path.as_deref(),
+            pane_prompt_paths: &options.pane_prompt_paths,
         },
         config,
         agent,
@@ -135,6 +123,32 @@ pub fn setup_environment(
         focus_id = %pane_setup_result.focus_pane_id,
         "setup_environment:panes configured"
     );
+    restore_layout(&pane_setup_result.focus_pane_id, worktree_path, config);
+
+    let bootstrap = &config.bootstrap;
+    if options.run_hooks && !bootstrap.commands().is_empty() {
+        let cache_dir = if remote::get().is_none()
+            && (!bootstrap.cache().is_empty() || bootstrap.cache_dir.is_some())
+        {
+            Some(bootstrap::cache_dir(
+                bootstrap,
+                &git::get_main_worktree_root()?,
+            )?)
+        } else {
+            None
+        };
+        bootstrap::start(
+            &initial_pane_id,
+            worktree_path,
+            bootstrap.commands(),
+            &hook_env,
+            cache_dir.as_deref(),
+        )?;
+        // The split takes focus; hand it back to the configured pane
+        if !options.focus_window {
+            tmux::select_pane(&pane_setup_result.focus_pane_id)?;
+        }
+    }
 
     // Focus the configured pane and optionally switch to the window
     if options.focus_window {
@@ -155,6 +169,301 @@ pub fn setup_environment(
     })
 }
 
+/// Turn the tmux window containing `pane_id` into the worktree's window instead
+/// of creating one: rename it, `cd` its shell panes into the worktree, and apply
+/// the workmux status format. Panes running other programs are left alone.
+pub fn adopt_window(
+    branch_name: &str,
+    handle: &str,
+    worktree_path: &Path,
+    pane_id: &str,
+    config: &config::Config,
+    options: &super::types::SetupOptions,
+) -> Result<AdoptResult> {
+    debug!(
+        branch = branch_name,
+        handle = handle,
+        pane_id = pane_id,
+        path = %worktree_path.display(),
+        "adopt_window:start"
+    );
+    let (hooks_run, hook_env) = prepare_worktree(
+        branch_name,
+        handle,
+        worktree_path,
+        config,
+        options,
+        config.agent.as_deref(),
+    )?;
+
+    tmux::rename_window(pane_id, config.window_prefix(), handle)?;
+    tmux::tag_window(pane_id, handle, worktree_path)?;
+    show_task(pane_id, handle);
+
+    let cd = format!("cd {}", cmd::shell_quote(&worktree_path.to_string_lossy()));
+    let mut skipped_panes = Vec::new();
+    for (id, command) in tmux::window_panes(pane_id)? {
+        // Our own pane runs workmux now; its shell reads the `cd` once we exit
+        if id == pane_id || tmux::is_shell_command(&command) {
+            tmux::send_keys(&id, &cd)?;
+        } else {
+            skipped_panes.push((id, command));
+        }
+    }
+
+    if options.run_hooks {
+        install_focus_hooks(pane_id, worktree_path, &hook_env, config)
+            .context("Failed to install focus hooks")?;
+    }
+    if config.status_format.unwrap_or(true) {
+        tmux::ensure_status_format(pane_id)?;
+    }
+    info!(
+        branch = branch_name,
+        handle = handle,
+        skipped = skipped_panes.len(),
+        "adopt_window:completed"
+    );
+
+    Ok(AdoptResult {
+        worktree_path: worktree_path.to_path_buf(),
+        post_create_hooks_run: hooks_run,
+        skipped_panes,
+    })
+}
+
+/// Apply file operations and run post-create hooks, as requested by `options`.
+/// `agent` is what `when: agent` conditions match.
+/// Returns the number of hooks run and the environment they ran with.
+fn prepare_worktree(
+    branch_name: &str,
+    handle: &str,
+    worktree_path: &Path,
+    config: &config::Config,
+    options: &super::types::SetupOptions,
+    agent: Option<&str>,
+) -> Result<(usize, HookEnv)> {
+    // Ctrl-C from here on cancels cleanly instead of killing workmux mid-copy
+    let _guard = interrupt::Guard::new()?;
+
+    // Use main worktree root for file operations since source files live there
+    let repo_root = git::get_main_worktree_root()?;
+
+    // What `when` conditions on file operations and hooks are checked against
+    let target = config::WhenTarget {
+        branch: branch_name,
+        agent,
+        worktree_path,
+    };
+
+    // Perform file operations (copy and symlink) if requested
+    if options.run_file_ops && dry_run::is_enabled() {
+        let files = config.files.for_worktree(&target);
+        for op in files.copy.iter().flatten() {
+            dry_run::skip(format_args!("copy {} into the worktree", op.pattern()));
+        }
+        for op in files.symlink.iter().flatten() {
+            dry_run::skip(format_args!("symlink {} into the worktree", op.pattern()));
+        }
+        for op in files.render.iter().flatten() {
+            dry_run::skip(format_args!(
+                "render {} to {}",
+                op.template,
+                op.destination()
+            ));
+        }
+        for path in config.bootstrap.cache() {
+            dry_run::skip(format_args!("link {} to the shared cache", path));
+        }
+    } else if options.run_file_ops && remote::get().is_some() {
+        if file_ops_configured(&config.files) || !config.bootstrap.cache().is_empty() {
+            eprintln!("Skipping file copy/symlink operations: not supported with --host");
+        }
+    } else if options.run_file_ops {
+        let files = config.files.for_worktree(&target);
+        handle_file_operations(&repo_root, worktree_path, &files, &options.file_filter)
+            .context("Failed to perform file operations")?;
+        if !config.bootstrap.cache().is_empty() {
+            let cache_dir = bootstrap::cache_dir(&config.bootstrap, &repo_root)?;
+            bootstrap::link_cache(worktree_path, &cache_dir, config.bootstrap.cache())
+                .context("Failed to link shared cache paths")?;
+            // Ignore patterns like `target/` only match directories, not links
+            let paths: Vec<&str> = config
+                .bootstrap
+                .cache()
+                .iter()
+                .map(String::as_str)
+                .collect();
+            git::exclude_paths(worktree_path, &paths)
+                .context("Failed to exclude shared cache paths from git")?;
+        }
+        if let Some(ops) = files.render.as_ref().filter(|ops| !ops.is_empty()) {
+            let context = render_context(branch_name, handle, options)?;
+            render_files(
+                &repo_root,
+                worktree_path,
+                ops,
+                &options.file_filter,
+                &context,
+            )
+            .context("Failed to render files")?;
+        }
+        debug!(
+            branch = branch_name,
+            "setup_environment:file operations applied"
+        );
+    }
+
+    if config.agent_instructions.unwrap_or(false) {
+        if remote::get().is_some() {
+            eprintln!("Skipping agent instructions: not supported with --host");
+        } else {
+            instructions::write(worktree_path, branch_name, agent)
+                .context("Failed to add workmux instructions for the agent")?;
+        }
+    }
+
+    let hook_env = HookEnv::new(handle, branch_name, worktree_path, &repo_root)
+        .with_prompt_file(options.prompt_file_path.as_deref())
+        .with_template_context(options.template_context.as_ref());
+
+    // Run post-create hooks before opening tmux so the new window appears "ready"
+    let mut hooks_run = 0;
+    let post_create = config::hooks_for(&config.post_create, &target);
+    if options.run_hooks && !post_create.is_empty() {
+        hooks_run = post_create.len();
+        for (idx, hook) in post_create.iter().enumerate() {
+            info!(branch = branch_name, step = idx + 1, total = hooks_run, command = %hook, "setup_environment:hook start");
+            info!(command = %hook, "Running post-create hook {}/{}", idx + 1, hooks_run);
+            cmd::shell_command_with_env(
+                &hook_env.render(hook.command())?,
+                worktree_path,
+                &hook_env.vars(),
+                Some(&hook_env.input("post_create")),
+                hook.timeout(),
+            )
+            .with_context(|| format!("Failed to run post-create command: '{}'", hook))?;
+            info!(branch = branch_name, step = idx + 1, total = hooks_run, command = %hook, "setup_environment:hook complete");
+        }
+        info!(
+            branch = branch_name,
+            total = hooks_run,
+            "setup_environment:hooks complete"
+        );
+    }
+
+    Ok((hooks_run, hook_env))
+}
+
+/// Arrange the panes of the window containing `pane_id` in the layout saved
+/// for the worktree, else the configured one. Best-effort: a saved layout no
+/// longer fits once the number of panes changes.
+fn restore_layout(pane_id: &str, worktree_path: &Path, config: &config::Config) {
+    let saved = worktree_state::load_for_path(worktree_path).and_then(|state| state.layout);
+    let configured = config.panes.as_ref().and_then(|panes| panes.layout.clone());
+    for layout in saved.into_iter().chain(configured) {
+        match tmux::apply_pane_layout(pane_id, &layout) {
+            Ok(()) => return,
+            Err(e) => warn!(layout, error = %e, "setup:failed to apply pane layout"),
+        }
+    }
+}
+
+/// Set the task recorded for the worktree `handle` as the `@workmux_task` of
+/// the window containing `pane_id`. Best-effort.
+fn show_task(pane_id: &str, handle: &str) {
+    let task = worktree_state::load(handle)
+        .ok()
+        .flatten()
+        .and_then(|state| state.task);
+    if let Some(task) = task
+        && let Err(e) = tmux::set_window_task(pane_id, &task)
+    {
+        warn!(handle, error = %e, "setup:failed to show task");
+    }
+}
+
+/// Install tmux hooks that run the `on_enter`/`on_leave` commands when the
+/// window gains or loses focus.
+fn install_focus_hooks(
+    pane_id: &str,
+    worktree_path: &Path,
+    env: &HookEnv,
+    config: &config::Config,
+) -> Result<()> {
+    let configured =
+        |commands: &Option<Vec<String>>| commands.as_ref().filter(|c| !c.is_empty()).cloned();
+    let on_enter = configured(&config.on_enter);
+    let on_leave = configured(&config.on_leave);
+    // Leaving resets what entering records, so it's hooked for either
+    let hooks = [
+        ("pane-focus-in", on_enter.clone()),
+        ("pane-focus-out", on_leave.or(on_enter.map(|_| Vec::new()))),
+    ];
+    if hooks.iter().all(|(_, commands)| commands.is_none()) {
+        return Ok(());
+    }
+    tmux::enable_focus_events()?;
+    let window_id = tmux::pane_window_id(pane_id)?;
+    for (event, commands) in hooks {
+        let Some(commands) = commands else {
+            continue;
+        };
+        let commands = commands
+            .iter()
+            .map(|command| env.render(command))
+            .collect::<Result<Vec<_>>>()?;
+        let script = focus_hook_script(event, &window_id, &commands, worktree_path, &env.vars());
+        tmux::set_window_hook(pane_id, event, &script)?;
+        debug!(
+            event,
+            count = commands.len(),
+            "setup_environment:focus hook installed"
+        );
+    }
+    Ok(())
+}
+
+/// Build the shell script for a focus hook. It ends early when focus only
+/// moved between panes of the window: entering a window already marked
+/// focused, or leaving one that is still the active window. Commands go on
+/// separate lines so ones ending in `&` still parse.
+fn focus_hook_script(
+    event: &str,
+    window_id: &str,
+    commands: &[String],
+    worktree_path: &Path,
+    env_vars: &[(&str, &str)],
+) -> String {
+    let tmux = |args: &str| format!("tmux {} -t {} {}", args, window_id, tmux::FOCUSED_OPTION);
+    let mut script = if event == "pane-focus-in" {
+        format!(
+            "[ \"$({})\" = 1 ] && exit 0\n{} 1\n",
+            tmux("show-options -wqv"),
+            tmux("set-option -w")
+        )
+    } else {
+        // `##` keeps tmux from expanding the format before the shell runs
+        format!(
+            "[ \"$(tmux display-message -p -t {} '##{{window_active}}')\" = 1 ] && exit 0\n{}\n",
+            window_id,
+            tmux("set-option -wu")
+        )
+    };
+    script.push_str(&format!(
+        "cd {} || exit 1\nexport",
+        cmd::shell_quote(&worktree_path.to_string_lossy())
+    ));
+    for (key, value) in env_vars {
+        script.push_str(&format!(" {}={}", key, cmd::shell_quote(value)));
+    }
+    for command in commands {
+        script.push('\n');
+        script.push_str(command);
+    }
+    script
+}
+
 pub fn resolve_pane_configuration(
     original_panes: &[config::PaneConfig],
     agent: Option<&str>,
@@ -192,11 +501,208 @@ pub fn resolve_pane_configuration(
     }]
 }
 
+fn file_ops_configured(file_config: &config::FileConfig) -> bool {
+    file_config.copy.as_ref().is_some_and(|v| !v.is_empty())
+        || file_config.symlink.as_ref().is_some_and(|v| !v.is_empty())
+        || file_config.render.as_ref().is_some_and(|v| !v.is_empty())
+}
+
+/// Variables for `files.render` templates: the worktree's template variables
+/// (`agent`, `num`, `--foreach` values, `--var`s), its branch and handle, and
+/// its prompt along with a one-line summary of it
+fn render_context(
+    branch_name: &str,
+    handle: &str,
+    options: &super::types::SetupOptions,
+) -> Result<serde_json::Value> {
+    let prompt = options
+        .prompt_file_path
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read prompt file '{}'", path.display()))
+        })
+        .transpose()?;
+    let summary = prompt
+        .as_deref()
+        .and_then(|prompt| crate::prompt::describe(prompt, |_| None));
+
+    let mut context = options
+        .template_context
+        .clone()
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let serde_json::Value::Object(map) = &mut context {
+        map.insert("branch".to_string(), branch_name.into());
+        map.insert("handle".to_string(), handle.into());
+        map.insert("prompt".to_string(), prompt.unwrap_or_default().into());
+        map.insert(
+            "prompt_summary".to_string(),
+            summary.unwrap_or_default().into(),
+        );
+    }
+    Ok(context)
+}
+
+/// Render each `files.render` template from the repository into the worktree.
+/// Destinations must be files git doesn't track, and are excluded from git, so
+/// the rendered copies aren't committed.
+pub fn render_files(
+    repo_root: &Path,
+    worktree_path: &Path,
+    ops: &[config::RenderOp],
+    filter: &FileFilter,
+    context: &serde_json::Value,
+) -> Result<()> {
+    let env = template::create_template_env();
+    for op in ops {
+        let destination = Path::new(op.destination());
+        if destination.is_absolute()
+            || destination
+                .components()
+                .any(|part| matches!(part, std::path::Component::ParentDir))
+        {
+            return Err(anyhow!(
+                "Render destination '{}' must be a path inside the worktree",
+                op.destination()
+            ));
+        }
+        if !filter.allows(&op.template, destination) {
+            debug!(template = op.template, "file_operations:render skipped");
+            continue;
+        }
+        if git::is_tracked(worktree_path, op.destination()) {
+            return Err(anyhow!(
+                "Render destination '{}' is tracked by git. Render to a file the repository doesn't have.",
+                op.destination()
+            ));
+        }
+
+        let source = repo_root.join(&op.template);
+        let body = fs::read_to_string(&source)
+            .with_context(|| format!("Failed to read template '{}'", source.display()))?;
+        template::validate_template_variables(&env, &body, context)
+            .with_context(|| format!("Invalid template '{}'", op.template))?;
+        let rendered = env
+            .render_str(&body, context)
+            .with_context(|| format!("Failed to render '{}'", op.template))?;
+
+        let dest_path = worktree_path.join(destination);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create parent directory for {:?}", dest_path)
+            })?;
+        }
+        fs::write(&dest_path, rendered)
+            .with_context(|| format!("Failed to write '{}'", dest_path.display()))?;
+        git::exclude_paths(worktree_path, &[op.destination()])?;
+        debug!(
+            template = op.template,
+            destination = op.destination(),
+            "file_operations:rendered"
+        );
+    }
+    Ok(())
+}
+
+/// Whether two existing paths are on the same filesystem (mount or drive)
+#[cfg(unix)]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => true,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    a.components().next() == b.components().next()
+}
+
+/// What file operations leave out: `files.exclude` and `.workmuxignore`
+struct Excludes {
+    globs: Vec<glob::Pattern>,
+    ignore: WorkmuxIgnore,
+}
+
+impl Excludes {
+    fn new(globs: &[String], repo_root: &Path) -> Result<Self> {
+        let globs = globs
+            .iter()
+            .map(|glob| {
+                glob::Pattern::new(glob)
+                    .with_context(|| format!("Invalid files.exclude pattern '{}'", glob))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            globs,
+            ignore: WorkmuxIgnore::load(repo_root)?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.globs.is_empty() && self.ignore.is_empty()
+    }
+
+    /// Whether `relative_path` is left out: a `files.exclude` glob matches
+    /// the path from the repository root, or its file name, or
+    /// `.workmuxignore` ignores it
+    fn contains(&self, relative_path: &Path, is_dir: bool) -> bool {
+        self.globs.iter().any(|glob| {
+            glob.matches_path(relative_path)
+                || relative_path
+                    .file_name()
+                    .is_some_and(|name| glob.matches(&name.to_string_lossy()))
+        }) || self.ignore.is_ignored(relative_path, is_dir)
+    }
+}
+
+/// Copy the directory `source` to `dest` one entry at a time, leaving out the
+/// excluded ones. `relative_path` is the directory's path in the repository.
+fn copy_dir_excluding(
+    source: &Path,
+    dest: &Path,
+    relative_path: &Path,
+    excludes: &Excludes,
+) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| format!("Failed to create directory {:?}", dest))?;
+    for entry in fs::read_dir(source)? {
+        interrupt::check()?;
+        let entry = entry?;
+        let relative_path = relative_path.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if excludes.contains(&relative_path, file_type.is_dir()) {
+            debug!(path = %relative_path.display(), "file_operations:copy excluded");
+            continue;
+        }
+        let source_path = entry.path();
+        let dest_path = dest.join(entry.file_name());
+        // Symlinks are copied as links
+        #[cfg(unix)]
+        if file_type.is_symlink() {
+            let target = fs::read_link(&source_path)?;
+            let _ = fs::remove_file(&dest_path);
+            std::os::unix::fs::symlink(&target, &dest_path)
+                .with_context(|| format!("Failed to copy symlink {:?}", source_path))?;
+            continue;
+        }
+        if file_type.is_dir() {
+            copy_dir_excluding(&source_path, &dest_path, &relative_path, excludes)?;
+        } else {
+            fs::copy(&source_path, &dest_path).with_context(|| {
+                format!("Failed to copy file {:?} to {:?}", source_path, dest_path)
+            })?;
+        }
+    }
+    Ok(())
+}
+
 /// Performs copy and symlink operations from the repo root to the worktree
 pub fn handle_file_operations(
     repo_root: &Path,
     worktree_path: &Path,
     file_config: &config::FileConfig,
+    filter: &FileFilter,
 ) -> Result<()> {
     debug!(
         repo = %repo_root.display(),
@@ -213,14 +719,21 @@ pub fn handle_file_operations(
         )
     })?;
 
+    let excludes = Excludes::new(
+        file_config.exclude.as_deref().unwrap_or_default(),
+        repo_root,
+    )?;
+
     let mut copy_count = 0;
     let mut symlink_count = 0;
 
     // Handle copies
     if let Some(copy_patterns) = &file_config.copy {
-        for pattern in copy_patterns {
+        for op in copy_patterns {
+            let pattern = op.pattern();
             let full_pattern = repo_root.join(pattern).to_string_lossy().to_string();
             for entry in glob::glob(&full_pattern)? {
+                interrupt::check()?;
                 let source_path = entry?;
 
                 // Validate that the resolved source path stays within the repository root
@@ -242,6 +755,12 @@ pub fn handle_file_operations(
                         repo_root.display()
                     )
                 })?;
+                if !filter.allows(pattern, relative_path)
+                    || excludes.contains(relative_path, source_path.is_dir())
+                {
+                    debug!(path = %relative_path.display(), "file_operations:copy skipped");
+                    continue;
+                }
                 let dest_path = worktree_path.join(relative_path);
 
                 if source_path.is_dir() {
@@ -249,6 +768,11 @@ pub fn handle_file_operations(
                     if let Some(parent) = dest_path.parent() {
                         fs::create_dir_all(parent)?;
                     }
+                    if !excludes.is_empty() {
+                        copy_dir_excluding(&source_path, &dest_path, relative_path, &excludes)?;
+                        copy_count += 1;
+                        continue;
+                    }
                     // Use fs_extra::dir::copy which handles recursion and symlinks correctly
                     let mut dir_options = fs_dir::CopyOptions::new();
                     dir_options.overwrite = true;
@@ -280,9 +804,16 @@ pub fn handle_file_operations(
 
     // Handle symlinks
     if let Some(symlink_patterns) = &file_config.symlink {
-        for pattern in symlink_patterns {
+        // Relative links survive the repo and worktree moving together. A worktree
+        // on another filesystem (worktree_dir on a scratch disk, another drive)
+        // moves independently, and there may be no relative path at all.
+        let link_relatively = same_filesystem(repo_root, worktree_path);
+
+        for op in symlink_patterns {
+            let pattern = op.pattern();
             let full_pattern = repo_root.join(pattern).to_string_lossy().to_string();
             for entry in glob::glob(&full_pattern)? {
+                interrupt::check()?;
                 let source_path = entry?;
 
                 // Validate that the resolved source path is within the repository root
@@ -298,6 +829,12 @@ pub fn handle_file_operations(
                 }
 
                 let relative_path = source_path.strip_prefix(repo_root)?;
+                if !filter.allows(pattern, relative_path)
+                    || excludes.contains(relative_path, source_path.is_dir())
+                {
+                    debug!(path = %relative_path.display(), "file_operations:symlink skipped");
+                    continue;
+                }
                 let dest_path = worktree_path.join(relative_path);
 
                 if let Some(parent) = dest_path.parent() {
@@ -314,8 +851,12 @@ pub fn handle_file_operations(
                     )
                 })?;
 
-                let relative_source = pathdiff::diff_paths(&source_path, dest_parent)
-                    .ok_or_else(|| anyhow!("Could not create relative path for symlink"))?;
+                let relative_source = if link_relatively {
+                    pathdiff::diff_paths(&source_path, dest_parent)
+                        .ok_or_else(|| anyhow!("Could not create relative path for symlink"))?
+                } else {
+                    source_path.clone()
+                };
 
                 // Remove existing file/symlink at destination to avoid errors
                 // IMPORTANT: Use symlink_metadata to avoid following symlinks
@@ -379,16 +920,354 @@ pub fn write_prompt_file(branch_name: &str, prompt: &Prompt) -> Result<PathBuf>
 
     // Write to temp directory instead of the worktree to avoid polluting git status
     let prompt_filename = format!("workmux-prompt-{}.md", branch_name);
+    if dry_run::skip(format_args!("write the prompt to {}", prompt_filename)) {
+        return Ok(std::env::temp_dir().join(prompt_filename));
+    }
+    if let Some(remote) = remote::get() {
+        // The agent reads the prompt on the remote host, whose temp dir may differ
+        let prompt_path = Path::new("/tmp").join(prompt_filename);
+        remote.write_file(&prompt_path, &content)?;
+        return Ok(prompt_path);
+    }
     let prompt_path = std::env::temp_dir().join(prompt_filename);
     fs::write(&prompt_path, content)
         .with_context(|| format!("Failed to write prompt file '{}'", prompt_path.display()))?;
     Ok(prompt_path)
 }
 
+/// Write a new worktree's prompt to `relative_path` in it, for `save_prompt`.
+/// The file is plain text, so it is refused while worktree state is encrypted.
+pub fn save_prompt(
+    worktree_path: &Path,
+    relative_path: &str,
+    content: &str,
+    config: &config::Config,
+) -> Result<()> {
+    if config.encrypts_state() {
+        return Err(anyhow!(
+            "Not saving the prompt to '{}': it would be in plain text while state_encryption is on",
+            relative_path
+        ));
+    }
+    let relative = Path::new(relative_path);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|part| matches!(part, std::path::Component::ParentDir))
+    {
+        return Err(anyhow!(
+            "save_prompt '{}' must be a path inside the worktree",
+            relative_path
+        ));
+    }
+    let path = worktree_path.join(relative);
+    if dry_run::skip(format_args!("write the prompt to {}", path.display())) {
+        return Ok(());
+    }
+    if let Some(remote) = remote::get() {
+        return remote.write_file(&path, content);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    }
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write prompt file '{}'", path.display()))
+}
+
+/// Validates that a prompt will actually be consumed by an agent pane.
+///
+/// This prevents the case where a user provides `-p "some prompt"` but no pane
+/// is configured to run an agent that would receive it.
+fn validate_prompt_consumption(
+    panes: &[config::PaneConfig],
+    cli_agent: Option<&str>,
+    config: &config::Config,
+    options: &super::types::SetupOptions,
+) -> Result<()> {
+    if !options.run_pane_commands {
+        return Err(anyhow!(
+            "Prompt provided (-p/-P/-e) but pane commands are disabled (--no-pane-cmds). \
+             The prompt would be ignored."
+        ));
+    }
+
+    let effective_agent = cli_agent.or(config.agent.as_deref());
+
+    let Some(agent_cmd) = effective_agent else {
+        return Err(anyhow!(
+            "Prompt provided but no agent is configured to consume it. \
+             Set 'agent' in config or use -a/--agent flag."
+        ));
+    };
+
+    let consumes_prompt = panes.iter().any(|pane| {
+        pane.command
+            .as_deref()
+            .map(|cmd| config::is_agent_command(cmd, agent_cmd))
+            .unwrap_or(false)
+    });
+
+    if !consumes_prompt {
+        let commands: Vec<_> = panes
+            .iter()
+            .map(|p| p.command.as_deref().unwrap_or("<shell>"))
+            .collect();
+
+        return Err(anyhow!(
+            "Prompt provided, but no pane is configured to run the agent '{}'.\n\
+             Resolved pane commands: {:?}\n\
+             Ensure your panes config includes '<agent>' or runs the configured agent.",
+            agent_cmd,
+            commands
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn file_filter_matches_patterns_paths_and_file_names() {
+        let skip = FileFilter::new(&["node_modules".to_string()], &[]).unwrap();
+        assert!(!skip.allows(
+            "packages/*/node_modules",
+            Path::new("packages/a/node_modules")
+        ));
+        assert!(skip.allows(".env", Path::new(".env")));
+
+        let only = FileFilter::new(&[], &[".env*".to_string()]).unwrap();
+        assert!(only.allows("config/*", Path::new("config/.env.local")));
+        assert!(!only.allows("config/*", Path::new("config/app.yaml")));
+
+        let by_pattern = FileFilter::new(&["config/*".to_string()], &[]).unwrap();
+        assert!(!by_pattern.allows("config/*", Path::new("config/app.yaml")));
+        assert!(by_pattern.allows(".env", Path::new(".env")));
+
+        assert!(FileFilter::new(&["[".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn render_files_writes_templates_into_the_worktree() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktree = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(worktree.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "--quiet"]);
+        fs::create_dir(repo.path().join(".workmux")).unwrap();
+        fs::write(
+            repo.path().join(".workmux/AGENTS.md.j2"),
+            "Branch {{ branch }}: {{ prompt_summary }} ({{ platform }})\n",
+        )
+        .unwrap();
+        let op = |to: &str| config::RenderOp {
+            template: ".workmux/AGENTS.md.j2".to_string(),
+            to: Some(to.to_string()),
+            when: None,
+        };
+        let context = serde_json::json!({
+            "branch": "fix-login",
+            "prompt_summary": "Fix the login form",
+            "platform": "ios",
+        });
+
+        render_files(
+            repo.path(),
+            worktree.path(),
+            &[op("docs/AGENTS.md")],
+            &FileFilter::default(),
+            &context,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(worktree.path().join("docs/AGENTS.md")).unwrap(),
+            "Branch fix-login: Fix the login form (ios)\n"
+        );
+        // The rendered copy is left out of git
+        let status = git(&["status", "--porcelain", "--untracked-files=all"]);
+        assert_eq!(String::from_utf8_lossy(&status.stdout), "");
+
+        let error = |ops: &[config::RenderOp], context: &serde_json::Value| {
+            render_files(
+                repo.path(),
+                worktree.path(),
+                ops,
+                &FileFilter::default(),
+                context,
+            )
+            .unwrap_err()
+        };
+        assert!(
+            error(&[op("../AGENTS.md")], &context)
+                .to_string()
+                .contains("must be a path inside the worktree")
+        );
+        let missing = format!(
+            "{:#}",
+            error(&[op("AGENTS.md")], &serde_json::json!({"branch": "x"}))
+        );
+        assert!(missing.contains("undefined variables: platform, prompt_summary"));
+
+        // A file the repository has is never overwritten
+        fs::write(worktree.path().join("AGENTS.md"), "# Agents\n").unwrap();
+        git(&["add", "AGENTS.md"]);
+        assert!(
+            error(&[op("AGENTS.md")], &context)
+                .to_string()
+                .contains("is tracked by git")
+        );
+        assert_eq!(
+            fs::read_to_string(worktree.path().join("AGENTS.md")).unwrap(),
+            "# Agents\n"
+        );
+    }
+
+    #[test]
+    fn save_prompt_writes_inside_the_worktree() {
+        let worktree = tempfile::tempdir().unwrap();
+        let config = config::Config::default();
+        save_prompt(
+            worktree.path(),
+            ".workmux/prompt.md",
+            "Fix the login form\n",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(worktree.path().join(".workmux/prompt.md")).unwrap(),
+            "Fix the login form\n"
+        );
+        for outside in ["../prompt.md", "/tmp/prompt.md"] {
+            assert!(save_prompt(worktree.path(), outside, "x", &config).is_err());
+        }
+
+        // Encrypted state stays out of plain-text files
+        let encrypted: config::Config =
+            serde_yaml::from_str("state_encryption:\n  recipients: [age1xyz]\n").unwrap();
+        assert!(save_prompt(worktree.path(), "secret.md", "x", &encrypted).is_err());
+        assert!(!worktree.path().join("secret.md").exists());
+    }
+
+    #[test]
+    fn file_operations_leave_out_excluded_paths() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktree = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join("config/certs")).unwrap();
+        for file in [
+            ".env",
+            ".env.example",
+            "config/app.yaml",
+            "config/certs/dev.key",
+        ] {
+            fs::write(repo.path().join(file), file).unwrap();
+        }
+        let files: config::FileConfig =
+            serde_yaml::from_str("copy: [\".env*\", config]\nexclude: [.env.example, \"*.key\"]\n")
+                .unwrap();
+
+        handle_file_operations(repo.path(), worktree.path(), &files, &FileFilter::default())
+            .unwrap();
+        assert!(worktree.path().join(".env").exists());
+        assert!(worktree.path().join("config/app.yaml").exists());
+        assert!(worktree.path().join("config/certs").is_dir());
+        assert!(!worktree.path().join(".env.example").exists());
+        assert!(!worktree.path().join("config/certs/dev.key").exists());
+    }
+
+    #[test]
+    fn file_operations_leave_out_workmuxignore_paths() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktree = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join("fixtures/datasets")).unwrap();
+        for file in [
+            "fixtures/small.json",
+            "fixtures/datasets/big.csv",
+            "demo.mp4",
+        ] {
+            fs::write(repo.path().join(file), file).unwrap();
+        }
+        fs::write(repo.path().join(".workmuxignore"), "datasets/\n*.mp4\n").unwrap();
+        let files: config::FileConfig =
+            serde_yaml::from_str("copy: [fixtures]\nsymlink: [\"*.mp4\"]\n").unwrap();
+
+        handle_file_operations(repo.path(), worktree.path(), &files, &FileFilter::default())
+            .unwrap();
+        assert!(worktree.path().join("fixtures/small.json").exists());
+        assert!(!worktree.path().join("fixtures/datasets").exists());
+        assert!(!worktree.path().join("demo.mp4").exists());
+    }
+
+    #[test]
+    fn symlinks_are_absolute_only_across_filesystems() {
+        let repo = tempfile::tempdir().unwrap();
+        let worktree = tempfile::tempdir().unwrap();
+        fs::write(repo.path().join(".env"), "KEY=1\n").unwrap();
+        let files: config::FileConfig = serde_yaml::from_str("symlink: [.env]\n").unwrap();
+
+        assert!(same_filesystem(repo.path(), worktree.path()));
+        handle_file_operations(repo.path(), worktree.path(), &files, &FileFilter::default())
+            .unwrap();
+        let link = fs::read_link(worktree.path().join(".env")).unwrap();
+        assert!(link.is_relative(), "{}", link.display());
+        assert_eq!(
+            fs::read_to_string(worktree.path().join(".env")).unwrap(),
+            "KEY=1\n"
+        );
+
+        // A worktree on a scratch disk links to where the repo actually is
+        #[cfg(target_os = "linux")]
+        assert!(!same_filesystem(repo.path(), Path::new("/proc")));
+        let Some(scratch) = tempfile::tempdir_in("/dev/shm")
+            .ok()
+            .filter(|dir| !same_filesystem(repo.path(), dir.path()))
+        else {
+            return;
+        };
+        handle_file_operations(repo.path(), scratch.path(), &files, &FileFilter::default())
+            .unwrap();
+        assert_eq!(
+            fs::read_link(scratch.path().join(".env")).unwrap(),
+            repo.path().join(".env")
+        );
+    }
+
+    #[test]
+    fn focus_hook_script_exports_env_and_runs_each_command() {
+        let script = focus_hook_script(
+            "pane-focus-in",
+            "@3",
+            &["watcher --pause &".to_string(), "echo done".to_string()],
+            Path::new("/work/my feature"),
+            &[("WM_HANDLE", "my-feature")],
+        );
+        assert_eq!(
+            script.split_once("cd ").unwrap().1,
+            "'/work/my feature' || exit 1\nexport WM_HANDLE=my-feature\nwatcher --pause &\necho done"
+        );
+    }
+
+    #[test]
+    fn focus_hooks_skip_moves_between_panes_of_the_window() {
+        let enter = focus_hook_script("pane-focus-in", "@3", &[], Path::new("/w"), &[]);
+        assert!(enter.starts_with(
+            "[ \"$(tmux show-options -wqv -t @3 @workmux_focused)\" = 1 ] && exit 0\n\
+             tmux set-option -w -t @3 @workmux_focused 1\ncd /w"
+        ));
+        let leave = focus_hook_script("pane-focus-out", "@3", &[], Path::new("/w"), &[]);
+        assert!(leave.starts_with(
+            "[ \"$(tmux display-message -p -t @3 '##{window_active}')\" = 1 ] && exit 0\n\
+             tmux set-option -wu -t @3 @workmux_focused\ncd /w"
+        ));
+    }
+
     #[test]
     fn resolve_pane_configuration_no_agent_returns_original() {
         let original_panes = vec![config::PaneConfig {
@@ -485,7 +1364,11 @@ mod tests {
             run_file_ops: true,
             run_pane_commands,
             prompt_file_path: Some(std::path::PathBuf::from("/tmp/prompt.md")),
+            pane_prompt_paths: Vec::new(),
             focus_window: true,
+            file_filter: Default::default(),
+            template_context: None,
+            task: None,
         }
     }
 
@@ -648,54 +1531,3 @@ mod tests {
         assert!(result.is_ok());
     }
 }
-
-/// Validates that a prompt will actually be consumed by an agent pane.
-///
-/// This prevents the case where a user provides `-p "some prompt"` but no pane
-/// is configured to run an agent that would receive it.
-fn validate_prompt_consumption(
-    panes: &[config::PaneConfig],
-    cli_agent: Option<&str>,
-    config: &config::Config,
-    options: &super::types::SetupOptions,
-) -> Result<()> {
-    if !options.run_pane_commands {
-        return Err(anyhow!(
-            "Prompt provided (-p/-P/-e) but pane commands are disabled (--no-pane-cmds). \
-             The prompt would be ignored."
-        ));
-    }
-
-    let effective_agent = cli_agent.or(config.agent.as_deref());
-
-    let Some(agent_cmd) = effective_agent else {
-        return Err(anyhow!(
-            "Prompt provided but no agent is configured to consume it. \
-             Set 'agent' in config or use -a/--agent flag."
-        ));
-    };
-
-    let consumes_prompt = panes.iter().any(|pane| {
-        pane.command
-            .as_deref()
-            .map(|cmd| config::is_agent_command(cmd, agent_cmd))
-            .unwrap_or(false)
-    });
-
-    if !consumes_prompt {
-        let commands: Vec<_> = panes
-            .iter()
-            .map(|p| p.command.as_deref().unwrap_or("<shell>"))
-            .collect();
-
-        return Err(anyhow!(
-            "Prompt provided, but no pane is configured to run the agent '{}'.\n\
-             Resolved pane commands: {:?}\n\
-             Ensure your panes config includes '<agent>' or runs the configured agent.",
-            agent_cmd,
-            commands
-        ));
-    }
-
-    Ok(())
-}
@@ -0,0 +1,310 @@
+use anyhow::{Context, Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::{git, llm, spinner, worktree_state};
+
+use super::context::WorkflowContext;
+use super::review::truncate_diff;
+use super::types::{CommitGroup, SplitPlan, SplitSource};
+
+/// Directory levels that name a group, e.g. `src/command` for `src/command/add.rs`
+const GROUP_DEPTH: usize = 2;
+
+/// Group files by their directory, up to [`GROUP_DEPTH`] levels deep, naming
+/// each commit with `message` and the directory
+pub fn group_by_directory(files: &[String], message: impl Fn(&str) -> String) -> Vec<CommitGroup> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for file in files {
+        let parent = Path::new(file).parent().unwrap_or(Path::new(""));
+        let dir = parent
+            .components()
+            .take(GROUP_DEPTH)
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        groups.entry(dir).or_default().push(file.clone());
+    }
+    groups
+        .into_iter()
+        .map(|(dir, files)| CommitGroup {
+            message: message(if dir.is_empty() {
+                "top-level files"
+            } else {
+                &dir
+            }),
+            files,
+        })
+        .collect()
+}
+
+/// Build the prompt asking how to split changes into commits.
+pub fn split_prompt(branch: &str, subject: Option<&str>, files: &[String], diff: &str) -> String {
+    let (diff, truncated) = truncate_diff(diff);
+    let mut prompt = format!(
+        "Split the changes below, on branch `{branch}`, into commits that each make one \
+         logical change, such as a feature, a fix, a refactoring, or the tests or docs for \
+         one of them. Keep related files together, and prefer a few coherent commits over \
+         many small ones. Put every file in exactly one commit.\n\
+         \n\
+         Output ONLY the commits, in the order to make them, each as a `## <commit subject>` \
+         line followed by its files, one per line, exactly as listed below.\n"
+    );
+    if let Some(subject) = subject {
+        prompt.push_str(&format!(
+            "\nThe changes were committed together as: {}\n",
+            subject
+        ));
+    }
+    prompt.push_str(&format!(
+        "\nFiles:\n{}\n\nDiff (new files are not shown):\n```diff\n{}\n```\n",
+        files.join("\n"),
+        diff
+    ));
+    if truncated {
+        prompt.push_str("\nThe diff was truncated; group the rest by the file names.\n");
+    }
+    prompt
+}
+
+/// Check the split suggested for `files`: every file in exactly one commit,
+/// and no commit without files or a message.
+pub fn parse_groups(output: &str, files: &[String]) -> Result<Vec<CommitGroup>> {
+    let mut groups: Vec<CommitGroup> = Vec::new();
+    let mut seen = vec![false; files.len()];
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("```") {
+            continue;
+        }
+        if let Some(message) = line.strip_prefix('#') {
+            let message = message.trim_start_matches('#').trim();
+            if message.is_empty() {
+                return Err(anyhow!("a commit has no message"));
+            }
+            groups.push(CommitGroup {
+                message: message.to_string(),
+                files: Vec::new(),
+            });
+            continue;
+        }
+        let file = line.trim_start_matches("- ").trim_matches('`');
+        let index = files
+            .iter()
+            .position(|known| known == file)
+            .ok_or_else(|| anyhow!("unknown file '{}'", file))?;
+        if std::mem::replace(&mut seen[index], true) {
+            return Err(anyhow!("'{}' is in two commits", file));
+        }
+        groups
+            .last_mut()
+            .ok_or_else(|| anyhow!("'{}' is not under a commit", file))?
+            .files
+            .push(file.to_string());
+    }
+    if let Some(group) = groups.iter().find(|group| group.files.is_empty()) {
+        return Err(anyhow!("commit '{}' has no files", group.message));
+    }
+    if let Some(index) = seen.iter().position(|seen| !seen) {
+        return Err(anyhow!("'{}' is missing", files[index]));
+    }
+    Ok(groups)
+}
+
+/// Work out how to split a worktree's changes into commits.
+///
+/// Splits the uncommitted changes if there are any, else the branch's last
+/// commit, as long as the branch's base doesn't have it. With `suggest`, the
+/// `llm` CLI groups the files; a split that doesn't check out is reported
+/// and grouped by directory instead.
+pub fn plan_split(
+    name: &str,
+    context: &WorkflowContext,
+    suggest: bool,
+    model: Option<&str>,
+) -> Result<SplitPlan> {
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+
+    if let Some(operation) = git::in_progress_operation(&worktree_path) {
+        return Err(anyhow!(
+            "Worktree for '{}' is in the middle of a {}. Finish it, or run '{}' in {}, first.",
+            branch,
+            operation.label(),
+            operation.abort_command(),
+            worktree_path.display()
+        ));
+    }
+
+    let (source, from) = if git::has_uncommitted_changes(&worktree_path)? {
+        (SplitSource::Uncommitted, "HEAD")
+    } else {
+        let base = worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone());
+        let onto = git::get_merge_base_in_worktree(&worktree_path, &base)?;
+        let (hash, subject) = git::get_commits_from_base(&worktree_path, &onto)?
+            .pop()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Nothing to split: '{}' has no uncommitted changes or commits since '{}'",
+                    branch,
+                    base
+                )
+            })?;
+        if git::get_parent_count(&worktree_path, "HEAD")? != 1 {
+            return Err(anyhow!("The last commit on '{}' is a merge", branch));
+        }
+        (SplitSource::LastCommit { hash, subject }, "HEAD~1")
+    };
+    let subject = match &source {
+        SplitSource::LastCommit { subject, .. } => Some(subject.as_str()),
+        SplitSource::Uncommitted => None,
+    };
+    let files = git::get_changed_files(&worktree_path, from)?;
+    info!(branch = branch, ?source, files = files.len(), "split:plan");
+
+    let by_directory = || {
+        group_by_directory(&files, |dir| match subject {
+            Some(subject) => format!("{} ({})", subject, dir),
+            None => format!("Update {}", dir),
+        })
+    };
+    let mut plan_error = None;
+    let groups = if suggest {
+        let model = model.or_else(|| {
+            context
+                .config
+                .auto_name
+                .as_ref()
+                .and_then(|c| c.model.as_deref())
+        });
+        let diff = git::get_diff_since(&worktree_path, from)?;
+        let prompt = split_prompt(&branch, subject, &files, &diff);
+        let suggested = spinner::with_spinner(
+            &format!("Suggesting how to split {} file(s)", files.len()),
+            || llm::suggest_commit_split(&prompt, model),
+        )
+        .and_then(|output| parse_groups(&output, &files));
+        match suggested {
+            Ok(groups) => groups,
+            Err(e) => {
+                warn!(branch = branch, error = %e, "split:suggested split not used");
+                plan_error = Some(format!("{:#}", e));
+                by_directory()
+            }
+        }
+    } else {
+        by_directory()
+    };
+
+    Ok(SplitPlan {
+        worktree_path,
+        branch,
+        source,
+        groups,
+        plan_error,
+    })
+}
+
+/// Make the commits of a split, undoing the split commit first. Stops at the
+/// first commit that fails, leaving the rest of the changes uncommitted.
+pub fn apply_split(plan: &SplitPlan) -> Result<()> {
+    let path = &plan.worktree_path;
+    // Staged or not, every change is staged again group by group
+    let reset_to = match plan.source {
+        SplitSource::Uncommitted => "HEAD",
+        SplitSource::LastCommit { .. } => "HEAD~1",
+    };
+    git::reset_mixed(path, reset_to)?;
+
+    for (made, group) in plan.groups.iter().enumerate() {
+        git::stage_paths(path, &group.files)
+            .and_then(|()| git::commit_with_message(path, &group.message))
+            .with_context(|| {
+                format!(
+                    "Failed to commit '{}' after {} of {} commit(s); the rest of the changes are left uncommitted",
+                    group.message,
+                    made,
+                    plan.groups.len()
+                )
+            })?;
+    }
+    info!(
+        branch = plan.branch,
+        commits = plan.groups.len(),
+        "split:applied"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn files_are_grouped_two_directories_deep() {
+        let files = paths(&[
+            "README.md",
+            "src/command/add.rs",
+            "src/command/merge.rs",
+            "src/workflow/pr/github.rs",
+            "src/workflow/pr.rs",
+        ]);
+        let groups = group_by_directory(&files, |dir| format!("Update {}", dir));
+        assert_eq!(
+            groups,
+            vec![
+                CommitGroup {
+                    message: "Update top-level files".to_string(),
+                    files: paths(&["README.md"]),
+                },
+                CommitGroup {
+                    message: "Update src/command".to_string(),
+                    files: paths(&["src/command/add.rs", "src/command/merge.rs"]),
+                },
+                CommitGroup {
+                    message: "Update src/workflow".to_string(),
+                    files: paths(&["src/workflow/pr/github.rs", "src/workflow/pr.rs"]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_groups_accepts_only_complete_splits() {
+        let files = paths(&["README.md", "src/auth.rs", "tests/auth.rs"]);
+        let groups = parse_groups(
+            "```\n## Add login\nsrc/auth.rs\n- `tests/auth.rs`\n\n## Document login\nREADME.md\n```",
+            &files,
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].message, "Add login");
+        assert_eq!(groups[0].files, paths(&["src/auth.rs", "tests/auth.rs"]));
+
+        let error = |output: &str| parse_groups(output, &files).unwrap_err().to_string();
+        assert_eq!(
+            error("## A\nsrc/auth.rs\nREADME.md"),
+            "'tests/auth.rs' is missing"
+        );
+        assert_eq!(error("README.md"), "'README.md' is not under a commit");
+        assert_eq!(
+            error("## A\nREADME.md\n## B\nREADME.md"),
+            "'README.md' is in two commits"
+        );
+        assert_eq!(error("## A\nsrc/main.rs"), "unknown file 'src/main.rs'");
+        assert_eq!(
+            error("## A\n## B\nREADME.md\nsrc/auth.rs\ntests/auth.rs"),
+            "commit 'A' has no files"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{Result, anyhow};
+use tracing::info;
+
+use crate::{git, llm, spinner, worktree_state};
+
+use super::context::WorkflowContext;
+use super::review::truncate_diff;
+use super::types::ChangeSummary;
+
+/// Build the prompt asking for a summary of a branch's commits and diff.
+pub fn summary_prompt(branch: &str, base: &str, log: &str, diff: &str) -> String {
+    let (diff, truncated) = truncate_diff(diff);
+    let log = if log.trim().is_empty() {
+        "(no commits yet, only uncommitted changes)"
+    } else {
+        log.trim()
+    };
+
+    let mut prompt = format!(
+        "Summarize what was changed on branch `{branch}` since it diverged from `{base}`, \
+         for a developer returning to it after a break.\n\
+         \n\
+         Start with one sentence on the overall change, then list the main changes as \
+         short bullet points, grouped by area. Mention anything that looks unfinished, \
+         such as TODOs, failing code or uncommitted work. Be concise and output only \
+         the summary.\n\
+         \n\
+         Commits:\n{log}\n\
+         \n\
+         Diff, including uncommitted changes:\n```diff\n{diff}\n```\n"
+    );
+    if truncated {
+        prompt.push_str("\nThe diff was truncated; summarize the part shown and say so.\n");
+    }
+    prompt
+}
+
+/// Summarize a worktree's changes against its base with the `llm` CLI.
+pub fn summarize(
+    name: &str,
+    context: &WorkflowContext,
+    base: Option<&str>,
+    model: Option<&str>,
+) -> Result<ChangeSummary> {
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone()),
+    };
+
+    let diff = git::get_diff_from_base(&worktree_path, &base)?;
+    if diff.is_empty() {
+        return Err(anyhow!(
+            "Branch '{}' has no changes against '{}' to summarize",
+            branch,
+            base
+        ));
+    }
+    let log = git::get_log_from_base(&worktree_path, &base)?;
+
+    let model = model.or_else(|| {
+        context
+            .config
+            .auto_name
+            .as_ref()
+            .and_then(|c| c.model.as_deref())
+    });
+    info!(branch = branch, base = base, model = ?model, "summarize:start");
+
+    let prompt = summary_prompt(&branch, &base, &log, &diff);
+    let summary = spinner::with_spinner(&format!("Summarizing '{}'", branch), || {
+        llm::summarize_changes(&prompt, model)
+    })?;
+
+    Ok(ChangeSummary {
+        branch,
+        base,
+        summary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_prompt_includes_commits_and_diff() {
+        let prompt = summary_prompt(
+            "feature/auth",
+            "main",
+            "abc123 Add login form\n",
+            "+fn login() {}",
+        );
+        assert!(prompt.contains("branch `feature/auth` since it diverged from `main`"));
+        assert!(prompt.contains("Commits:\nabc123 Add login form\n"));
+        assert!(prompt.contains("```diff\n+fn login() {}\n```"));
+        assert!(!prompt.contains("truncated"));
+    }
+
+    #[test]
+    fn summary_prompt_notes_uncommitted_only_changes() {
+        let prompt = summary_prompt("feature", "main", "", "+x");
+        assert!(prompt.contains("(no commits yet, only uncommitted changes)"));
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use std::cmp::Reverse;
+
+use crate::{git, tmux};
+
+use super::context::WorkflowContext;
+use super::types::SwitchCandidate;
+
+/// Worktrees `workmux switch` can jump to: every worktree except the main
+/// one, noting which already have a tmux window.
+pub fn switch_candidates(context: &WorkflowContext) -> Result<Vec<SwitchCandidate>> {
+    let main_root = git::get_main_worktree_root().ok();
+    let windows = tmux::get_all_window_names()?;
+
+    let mut candidates: Vec<SwitchCandidate> = git::list_worktrees()?
+        .into_iter()
+        .filter(|(path, _)| main_root.as_ref() != Some(path))
+        .filter_map(|(path, branch)| {
+            let handle = path.file_name()?.to_string_lossy().to_string();
+            let has_window = windows.contains(&tmux::prefixed(&context.prefix, &handle));
+            Some(SwitchCandidate {
+                handle,
+                branch,
+                has_window,
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.handle.cmp(&b.handle));
+    Ok(candidates)
+}
+
+/// Candidates matching `query` against their handle or branch, best match
+/// first. Ties go to worktrees with an open window.
+pub fn fuzzy_rank<'a>(query: &str, candidates: &'a [SwitchCandidate]) -> Vec<&'a SwitchCandidate> {
+    scored(query, candidates)
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// The best-ranked candidate, if it scores higher than the next one
+pub fn best_match<'a>(
+    query: &str,
+    candidates: &'a [SwitchCandidate],
+) -> Option<&'a SwitchCandidate> {
+    match scored(query, candidates).as_slice() {
+        [(_, only)] => Some(*only),
+        [(first_score, first), (second_score, _), ..] => {
+            (first_score > second_score).then_some(*first)
+        }
+        [] => None,
+    }
+}
+
+fn scored<'a>(query: &str, candidates: &'a [SwitchCandidate]) -> Vec<(i64, &'a SwitchCandidate)> {
+    let mut scored: Vec<(i64, &SwitchCandidate)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let score =
+                fuzzy_score(query, &candidate.handle).max(fuzzy_score(query, &candidate.branch))?;
+            Some((score, candidate))
+        })
+        .collect();
+    scored.sort_by_key(|(score, candidate)| (Reverse(*score), !candidate.has_window));
+    scored
+}
+
+/// Score `text` against `query` when the query's characters appear in it in
+/// order (case-insensitive). Exact and substring matches score highest, then
+/// matches on consecutive characters and at word starts (after `/`, `-`, `_`,
+/// `.`); gaps and longer texts cost a little.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query = query.to_lowercase();
+    let text_lower = text.to_lowercase();
+    if query.is_empty() {
+        return Some(0);
+    }
+    if text_lower == query {
+        return Some(10_000);
+    }
+
+    let text_chars: Vec<char> = text_lower.chars().collect();
+    let mut score = 0i64;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.chars() {
+        let found = text_chars[pos..].iter().position(|&c| c == q)? + pos;
+        score += 10;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 15;
+        } else if let Some(p) = previous {
+            score -= (found - p - 1).min(10) as i64;
+        }
+        if found == 0 || matches!(text_chars[found - 1], '/' | '-' | '_' | '.') {
+            score += 20;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+
+    if let Some(idx) = text_lower.find(&query) {
+        score += 100;
+        if idx == 0 {
+            score += 50;
+        }
+    }
+    Some(score - text_chars.len() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(handle: &str, branch: &str, has_window: bool) -> SwitchCandidate {
+        SwitchCandidate {
+            handle: handle.to_string(),
+            branch: branch.to_string(),
+            has_window,
+        }
+    }
+
+    fn handles(ranked: Vec<&SwitchCandidate>) -> Vec<&str> {
+        ranked.iter().map(|c| c.handle.as_str()).collect()
+    }
+
+    #[test]
+    fn matches_characters_in_order_across_handles_and_branches() {
+        let candidates = [
+            candidate("user-auth", "feature/user-auth", false),
+            candidate("billing", "fix/invoice-totals", false),
+            candidate("docs", "docs", false),
+        ];
+        assert_eq!(handles(fuzzy_rank("uath", &candidates)), ["user-auth"]);
+        assert_eq!(handles(fuzzy_rank("invtot", &candidates)), ["billing"]);
+        assert_eq!(handles(fuzzy_rank("xyz", &candidates)), Vec::<&str>::new());
+        assert_eq!(fuzzy_rank("", &candidates).len(), 3);
+    }
+
+    #[test]
+    fn closer_matches_rank_first() {
+        let candidates = [
+            candidate("api-tests", "api-tests", false),
+            candidate("api", "api", false),
+            candidate("rapid-ui", "rapid-ui", false),
+        ];
+        assert_eq!(
+            handles(fuzzy_rank("api", &candidates)),
+            ["api", "api-tests", "rapid-ui"]
+        );
+        assert_eq!(best_match("api", &candidates).unwrap().handle, "api");
+    }
+
+    #[test]
+    fn ties_prefer_open_windows_but_are_not_a_best_match() {
+        let candidates = [
+            candidate("auth-a", "auth-a", false),
+            candidate("auth-b", "auth-b", true),
+        ];
+        assert_eq!(
+            handles(fuzzy_rank("auth", &candidates)),
+            ["auth-b", "auth-a"]
+        );
+        assert!(best_match("auth", &candidates).is_none());
+        assert_eq!(best_match("authb", &candidates).unwrap().handle, "auth-b");
+    }
+}
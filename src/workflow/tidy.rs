@@ -0,0 +1,285 @@
+use anyhow::{Context, Result, anyhow};
+use tracing::{info, warn};
+
+use crate::cmd::shell_quote;
+use crate::config::{PaneConfig, SplitDirection};
+use crate::prompt::Prompt;
+use crate::{git, llm, spinner, tmux, worktree_state};
+
+use super::context::WorkflowContext;
+use super::setup::write_prompt_file;
+use super::types::TidyResult;
+
+/// Rebase commands a suggested plan may use. Dropping, editing and running
+/// commands are left to the user.
+const PLAN_COMMANDS: [(&str, &str); 4] = [
+    ("pick", "p"),
+    ("reword", "r"),
+    ("squash", "s"),
+    ("fixup", "f"),
+];
+
+/// Build the prompt asking for a plan to tidy a branch's commits.
+pub fn plan_prompt(branch: &str, log: &str) -> String {
+    format!(
+        "Suggest how to tidy the commits on branch `{branch}` before it is opened as a pull \
+         request, as the todo list of an interactive rebase.\n\
+         \n\
+         Fold commits that fix, continue or undo an earlier commit into it with `fixup`, or \
+         with `squash` when their message is worth keeping. Mark commits whose message does \
+         not describe their change with `reword`. Keep the rest as `pick`. List every commit \
+         exactly once, and only move a commit to put it right after the one it fixes. Do not \
+         use `drop`, `edit` or `exec`.\n\
+         \n\
+         Output ONLY the todo list, one `<command> <hash> <subject>` line per commit.\n\
+         \n\
+         Commits, oldest first, with the files they change:\n{log}\n"
+    )
+}
+
+/// Check the todo list suggested for `commits` and return it in git's form.
+///
+/// Every commit must appear once, with a command from [`PLAN_COMMANDS`], and
+/// the first can't be folded into a previous one.
+pub fn parse_plan(output: &str, commits: &[(String, String)]) -> Result<String> {
+    let mut todo = Vec::new();
+    let mut seen = vec![false; commits.len()];
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("```") {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let (Some(command), Some(hash)) = (words.next(), words.next()) else {
+            return Err(anyhow!("unexpected line '{}'", line));
+        };
+        let command = PLAN_COMMANDS
+            .iter()
+            .find(|(long, short)| command == *long || command == *short)
+            .map(|(long, _)| *long)
+            .ok_or_else(|| anyhow!("unsupported command '{}'", command))?;
+        let index = commits
+            .iter()
+            .position(|(ours, _)| ours.starts_with(hash) || hash.starts_with(ours.as_str()))
+            .filter(|_| hash.len() >= 4)
+            .ok_or_else(|| anyhow!("unknown commit '{}'", hash))?;
+        if std::mem::replace(&mut seen[index], true) {
+            return Err(anyhow!("commit '{}' is listed twice", hash));
+        }
+        if todo.is_empty() && matches!(command, "squash" | "fixup") {
+            return Err(anyhow!("the first commit can't be a {}", command));
+        }
+        let (hash, subject) = &commits[index];
+        todo.push(format!("{} {} {}", command, hash, subject));
+    }
+    if let Some(index) = seen.iter().position(|seen| !seen) {
+        return Err(anyhow!("commit '{}' is missing", commits[index].0));
+    }
+    Ok(todo.join("\n") + "\n")
+}
+
+/// Open an interactive rebase of a worktree's branch against the point it
+/// diverged from its base, in a new pane of the worktree's window.
+///
+/// With `suggest`, the `llm` CLI proposes the todo list the rebase starts
+/// with; a plan that doesn't check out is reported and left out.
+pub fn tidy(
+    name: &str,
+    context: &WorkflowContext,
+    base: Option<&str>,
+    suggest: bool,
+    model: Option<&str>,
+) -> Result<TidyResult> {
+    context.ensure_tmux_running()?;
+
+    let (worktree_path, branch) = git::find_worktree(name)?;
+    let handle = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => worktree_state::base_branch(&handle, &branch)
+            .unwrap_or_else(|| context.main_branch.clone()),
+    };
+
+    if let Some(operation) = git::in_progress_operation(&worktree_path) {
+        return Err(anyhow!(
+            "Worktree for '{}' is in the middle of a {}. Finish it, or run '{}' in {}, first.",
+            branch,
+            operation.label(),
+            operation.abort_command(),
+            worktree_path.display()
+        ));
+    }
+    if git::has_tracked_changes(&worktree_path)? {
+        return Err(anyhow!(
+            "Worktree for '{}' has uncommitted changes. Commit or stash them before tidying.",
+            branch
+        ));
+    }
+    if !tmux::window_exists(&context.prefix, &handle)? {
+        return Err(anyhow!(
+            "No tmux window for '{}'. Run 'workmux open {}' first.",
+            handle,
+            handle
+        ));
+    }
+
+    // Against the fork point, so the rebase only rewrites the branch's own
+    // commits rather than also moving it onto a newer base
+    let onto = git::get_merge_base_in_worktree(&worktree_path, &base)?;
+    let commits = git::get_commits_from_base(&worktree_path, &onto)?;
+    if commits.is_empty() {
+        return Err(anyhow!(
+            "Branch '{}' has no commits since '{}' to tidy",
+            branch,
+            base
+        ));
+    }
+
+    let mut plan = None;
+    let mut plan_error = None;
+    if suggest {
+        let model = model.or_else(|| {
+            context
+                .config
+                .auto_name
+                .as_ref()
+                .and_then(|c| c.model.as_deref())
+        });
+        let log = git::get_log_stat_from_base(&worktree_path, &onto)?;
+        let prompt = plan_prompt(&branch, &log);
+        let suggested = spinner::with_spinner(
+            &format!("Suggesting a plan for {} commit(s)", commits.len()),
+            || llm::suggest_rebase_plan(&prompt, model),
+        )
+        .and_then(|output| parse_plan(&output, &commits));
+        match suggested {
+            Ok(todo) => plan = Some(todo),
+            Err(e) => {
+                warn!(branch = branch, error = %e, "tidy:suggested plan not used");
+                plan_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    let mut command = format!("git rebase -i {} && exit", onto);
+    let mut plan_path = None;
+    if let Some(todo) = &plan {
+        // The sequence editor moves the plan into the todo file, then opens
+        // the usual editor to review it
+        let path = write_prompt_file(&format!("{}-tidy", handle), &Prompt::Inline(todo.clone()))?;
+        let editor = format!(
+            "sh -c 'cp \"$0\" \"$1\" && rm -f \"$0\" && exec $(git var GIT_EDITOR) \"$1\"' {}",
+            shell_quote(&path.to_string_lossy())
+        );
+        plan_path = Some(path);
+        command = format!(
+            "env GIT_SEQUENCE_EDITOR={} {}",
+            shell_quote(&editor),
+            command
+        );
+    }
+
+    info!(
+        handle = %handle,
+        branch = branch,
+        base = base,
+        commits = commits.len(),
+        planned = plan.is_some(),
+        "tidy:launch"
+    );
+
+    // An empty first pane leaves the worktree's active pane untouched
+    let existing_pane = PaneConfig {
+        command: None,
+        focus: false,
+        split: None,
+        size: None,
+        percentage: None,
+        target: None,
+    };
+    let rebase_pane = PaneConfig {
+        command: Some(command),
+        focus: true,
+        split: Some(SplitDirection::Horizontal),
+        ..existing_pane.clone()
+    };
+    let result = tmux::active_pane_id(&context.prefix, &handle)
+        .and_then(|pane_id| {
+            tmux::setup_panes(
+                &pane_id,
+                &[existing_pane, rebase_pane],
+                &worktree_path,
+                tmux::PaneSetupOptions {
+                    run_commands: true,
+                    prompt_file_path: None,
+                    pane_prompt_paths: &[],
+                },
+                &context.config,
+                None,
+            )
+        })
+        .context("Failed to open the rebase pane");
+    // Without a rebase to start, nothing removes the plan
+    if result.is_err()
+        && let Some(path) = &plan_path
+    {
+        let _ = std::fs::remove_file(path);
+    }
+    let result = result?;
+    tmux::select_window(&context.prefix, &handle)?;
+    tmux::select_pane(&result.focus_pane_id)?;
+
+    Ok(TidyResult {
+        branch,
+        base,
+        commits: commits.len(),
+        plan,
+        plan_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plan_accepts_only_complete_plans() {
+        let commits: Vec<(String, String)> = [
+            ("a1b2c3d", "Add login"),
+            ("e4f5a6b", "fix typo"),
+            ("c7d8e9f", "wip"),
+        ]
+        .iter()
+        .map(|(hash, subject)| (hash.to_string(), subject.to_string()))
+        .collect();
+
+        let output = "```\npick a1b2c3d Add login\nf e4f5a6b fix typo\nreword c7d8e9f0 wip\n```\n";
+        assert_eq!(
+            parse_plan(output, &commits).unwrap(),
+            "pick a1b2c3d Add login\nfixup e4f5a6b fix typo\nreword c7d8e9f wip\n"
+        );
+
+        let error = |output: &str| parse_plan(output, &commits).unwrap_err().to_string();
+        assert_eq!(
+            error("pick a1b2c3d\nfixup e4f5a6b"),
+            "commit 'c7d8e9f' is missing"
+        );
+        assert_eq!(
+            error("fixup a1b2c3d\npick e4f5a6b\npick c7d8e9f"),
+            "the first commit can't be a fixup"
+        );
+        assert_eq!(
+            error("pick a1b2c3d\ndrop e4f5a6b\npick c7d8e9f"),
+            "unsupported command 'drop'"
+        );
+        assert_eq!(
+            error("pick a1b2c3d\npick a1b2\npick c7d8e9f"),
+            "commit 'a1b2' is listed twice"
+        );
+        assert_eq!(error("pick 123456 x"), "unknown commit '123456'");
+    }
+}
@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 
+use crate::config::{ReviewTarget, WorktreeNaming};
+use crate::git::{CommitSummary, InProgressOperation};
 use crate::github::PrSummary;
+use crate::manifest::ManifestEntry;
 use crate::prompt::Prompt;
+use crate::tmux::WorkmuxWindow;
+
+use super::context::WorkflowContext;
 
 /// Arguments for creating a worktree
 pub struct CreateArgs<'a> {
@@ -12,6 +19,20 @@ pub struct CreateArgs<'a> {
     pub prompt: Option<&'a Prompt>,
     pub options: SetupOptions,
     pub agent: Option<&'a str>,
+    /// Pull or merge request the branch was checked out from
+    pub pr: Option<u32>,
+    /// Checked out to review the pull request, not to work on it
+    pub review: bool,
+    /// Check out `remote_branch` detached, without a local branch
+    pub detach: bool,
+}
+
+/// A worktree for `create_with_changes` to create and copy the changes into
+pub struct ChangesTarget {
+    pub branch_name: String,
+    pub handle: String,
+    /// Context with the config of the worktree's agent
+    pub context: WorkflowContext,
 }
 
 /// Result of creating a worktree
@@ -24,11 +45,196 @@ pub struct CreateResult {
     pub did_switch: bool,
 }
 
+/// Result of turning the current tmux window into a worktree's window
+pub struct AdoptResult {
+    pub worktree_path: PathBuf,
+    pub post_create_hooks_run: usize,
+    /// Panes left in their directory because they run something other than a
+    /// shell, with that command
+    pub skipped_panes: Vec<(String, String)>,
+}
+
+/// What `workmux apply` will do for a manifest entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApplyAction {
+    /// No worktree exists for the branch yet
+    Create,
+    /// The worktree exists but has no tmux window
+    Open,
+    /// The worktree and its window already exist
+    Unchanged,
+}
+
+/// A planned action for a single manifest entry
+pub struct ApplyStep {
+    pub entry: ManifestEntry,
+    pub handle: String,
+    pub action: ApplyAction,
+}
+
+/// Overrides for `workmux review-with-agent`; unset values fall back to the `review` config
+#[derive(Default)]
+pub struct ReviewArgs<'a> {
+    pub agent: Option<&'a str>,
+    pub base: Option<&'a str>,
+    pub output: Option<&'a str>,
+    pub target: Option<ReviewTarget>,
+}
+
+/// Result of launching a reviewer agent
+pub struct ReviewResult {
+    pub branch: String,
+    pub base: String,
+    pub agent: String,
+    /// Where the reviewer was asked to write its findings
+    pub output_path: PathBuf,
+}
+
+/// A worktree `workmux switch` can jump to
+#[derive(Debug, Clone)]
+pub struct SwitchCandidate {
+    pub handle: String,
+    pub branch: String,
+    /// Whether the worktree's tmux window is open
+    pub has_window: bool,
+}
+
+/// A summary of a worktree's changes from `workmux summarize`
+pub struct ChangeSummary {
+    pub branch: String,
+    pub base: String,
+    pub summary: String,
+}
+
+/// Result of opening an interactive rebase with `workmux tidy`
+pub struct TidyResult {
+    pub branch: String,
+    pub base: String,
+    /// Commits on the branch since it diverged from the base
+    pub commits: usize,
+    /// The suggested todo list the rebase starts with
+    pub plan: Option<String>,
+    /// Why a suggested plan was left out
+    pub plan_error: Option<String>,
+}
+
+/// The changes `workmux split-commits` splits
+#[derive(Debug, PartialEq)]
+pub enum SplitSource {
+    /// Uncommitted changes, untracked files included
+    Uncommitted,
+    /// The branch's last commit, undone before committing its parts
+    LastCommit { hash: String, subject: String },
+}
+
+/// One commit of a split
+#[derive(Debug, PartialEq)]
+pub struct CommitGroup {
+    pub message: String,
+    /// Paths relative to the worktree root
+    pub files: Vec<String>,
+}
+
+/// How `workmux split-commits` would split a worktree's changes
+pub struct SplitPlan {
+    pub worktree_path: PathBuf,
+    pub branch: String,
+    pub source: SplitSource,
+    /// Commits to make, in order
+    pub groups: Vec<CommitGroup>,
+    /// Why a suggested split was left out for the grouping by directory
+    pub plan_error: Option<String>,
+}
+
+/// A directory in the worktree directory that isn't a worktree
+pub struct LeftoverDirectory {
+    pub path: PathBuf,
+    /// Why the directory is kept (None if it will be removed)
+    pub skip_reason: Option<String>,
+}
+
+/// What `workmux prune` would clean up
+pub struct PrunePlan {
+    /// Workmux windows whose worktree is gone
+    pub windows: Vec<WorkmuxWindow>,
+    /// Worktrees git still lists, with the reason git gives for pruning them
+    pub stale_worktrees: Vec<(PathBuf, String)>,
+    /// Directories left in the worktree directory
+    pub directories: Vec<LeftoverDirectory>,
+    /// Why the worktree directory wasn't checked for leftovers, if it wasn't
+    pub directories_unchecked: Option<String>,
+}
+
+impl PrunePlan {
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty() && self.stale_worktrees.is_empty() && self.directories.is_empty()
+    }
+}
+
+/// How a patch was applied by `workmux apply-patch`
+#[derive(Debug, PartialEq)]
+pub enum PatchOutcome {
+    /// The patch applied cleanly
+    Clean,
+    /// The patch needed a 3-way merge, which resolved without conflicts
+    Merged,
+    /// The 3-way merge left conflict markers in these files
+    Conflicts(Vec<String>),
+}
+
+/// Result of applying a patch to a worktree
+pub struct PatchResult {
+    pub worktree_path: PathBuf,
+    /// Files touched by the patch
+    pub files: Vec<String>,
+    pub outcome: PatchOutcome,
+}
+
+/// Work to bring into a new worktree with `workmux import-work`
+#[derive(Debug, PartialEq)]
+pub enum ImportSource {
+    /// A `git bundle` file
+    Bundle(PathBuf),
+    /// A `git format-patch` series, in the order to apply it
+    Patches(Vec<PathBuf>),
+}
+
+/// What a merge is about to do, reported before it runs
+pub struct MergePreflight {
+    pub branch: String,
+    pub target: String,
+    pub worktree_path: PathBuf,
+    /// Subjects of the commits to merge, oldest first
+    pub commits: Vec<String>,
+    /// `git diff --stat` of the files the branch changed
+    pub diff_stat: String,
+    /// Files expected to conflict, None if git can't tell
+    pub conflicts: Option<Vec<String>>,
+    pub has_staged: bool,
+    pub has_unstaged: bool,
+    pub has_untracked: bool,
+}
+
 /// Result of merging a worktree
 pub struct MergeResult {
     pub branch_merged: String,
     pub main_branch: String,
     pub had_staged_changes: bool,
+    /// Ctrl-C stopped the merge after merging but before cleaning up
+    pub cleanup_interrupted: bool,
+    /// The worktree, window and branch were kept (`--keep`)
+    pub kept: bool,
+    /// Set when the merge stopped on conflicts, for `merge --continue`
+    pub conflicts: Option<MergeConflicts>,
+}
+
+/// Conflicts a merge stopped on
+pub struct MergeConflicts {
+    /// Worktree the conflicts are in
+    pub path: PathBuf,
+    pub files: Vec<String>,
+    /// Whether `conflict_command` was opened beside them
+    pub pane_opened: bool,
 }
 
 /// Result of removing a worktree
@@ -36,6 +242,14 @@ pub struct RemoveResult {
     pub branch_removed: String,
 }
 
+/// Untracked files that removing a worktree would delete
+pub struct UntrackedSize {
+    /// Files and directories at least the configured minimum size, largest first
+    pub large: Vec<(PathBuf, u64)>,
+    /// Size of all untracked files, including the ones not listed
+    pub total: u64,
+}
+
 /// Result of cleanup operations
 pub struct CleanupResult {
     pub tmux_window_killed: bool,
@@ -52,8 +266,59 @@ pub struct SetupOptions {
     pub run_file_ops: bool,
     pub run_pane_commands: bool,
     pub prompt_file_path: Option<PathBuf>,
+    /// Per-pane prompt files (by pane index) for agent teams, used instead of
+    /// `prompt_file_path` for those panes
+    pub pane_prompt_paths: Vec<Option<PathBuf>>,
     /// If true, switch to the new tmux window when done; if false, leave it in the background.
     pub focus_window: bool,
+    /// `--skip-file`/`--only-file` globs narrowing the configured file operations
+    pub file_filter: FileFilter,
+    /// Template variables of the worktree being created, for `files.render`
+    pub template_context: Option<serde_json::Value>,
+    /// Task title of the worktree being created; the prompt's first line is
+    /// used without one
+    pub task: Option<String>,
+}
+
+/// Narrows file operations to some of the configured ones. A glob selects an
+/// operation when it equals the configured pattern or matches the path being
+/// copied or linked, either whole or by file name.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    skip: Vec<glob::Pattern>,
+    only: Vec<glob::Pattern>,
+}
+
+impl FileFilter {
+    pub fn new(skip: &[String], only: &[String]) -> Result<Self> {
+        let parse = |globs: &[String], flag: &str| {
+            globs
+                .iter()
+                .map(|glob| {
+                    glob::Pattern::new(glob)
+                        .with_context(|| format!("Invalid {} pattern '{}'", flag, glob))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            skip: parse(skip, "--skip-file")?,
+            only: parse(only, "--only-file")?,
+        })
+    }
+
+    /// Whether to run the operation configured as `pattern` on `relative_path`
+    pub fn allows(&self, pattern: &str, relative_path: &Path) -> bool {
+        let selects = |globs: &[glob::Pattern]| {
+            globs.iter().any(|glob| {
+                glob.as_str() == pattern
+                    || glob.matches_path(relative_path)
+                    || relative_path
+                        .file_name()
+                        .is_some_and(|name| glob.matches(&name.to_string_lossy()))
+            })
+        };
+        !selects(&self.skip) && (self.only.is_empty() || selects(&self.only))
+    }
 }
 
 impl SetupOptions {
@@ -65,7 +330,11 @@ impl SetupOptions {
             run_file_ops: true,
             run_pane_commands: true,
             prompt_file_path: None,
+            pane_prompt_paths: Vec::new(),
             focus_window: true,
+            file_filter: FileFilter::default(),
+            template_context: None,
+            task: None,
         }
     }
 
@@ -76,7 +345,11 @@ impl SetupOptions {
             run_file_ops,
             run_pane_commands,
             prompt_file_path: None,
+            pane_prompt_paths: Vec::new(),
             focus_window: true,
+            file_filter: FileFilter::default(),
+            template_context: None,
+            task: None,
         }
     }
 
@@ -93,7 +366,11 @@ impl SetupOptions {
             run_file_ops,
             run_pane_commands,
             prompt_file_path,
+            pane_prompt_paths: Vec::new(),
             focus_window: true,
+            file_filter: FileFilter::default(),
+            template_context: None,
+            task: None,
         }
     }
 }
@@ -101,8 +378,101 @@ impl SetupOptions {
 /// List all worktrees with their status
 pub struct WorktreeInfo {
     pub branch: String,
+    /// The worktree's directory name
+    pub handle: String,
     pub path: PathBuf,
+    /// The worktree's tmux window name, with the window prefix
+    pub window: String,
     pub has_tmux: bool,
     pub has_unmerged: bool,
+    /// A rebase, merge, etc. left unfinished in the worktree
+    pub operation: Option<InProgressOperation>,
+    /// Pinned with `workmux pin`
+    pub pinned: bool,
     pub pr_info: Option<PrSummary>,
+    /// `branch.<name>.description`
+    pub description: Option<String>,
+    /// The branch's newest commit. None for detached worktrees
+    pub last_commit: Option<CommitSummary>,
+    /// Only gathered when asked for, as it runs git in every worktree
+    pub status: Option<WorktreeStatus>,
+}
+
+/// Working tree and commit status of a listed worktree
+pub struct WorktreeStatus {
+    /// Uncommitted changes or untracked files
+    pub dirty: bool,
+    /// Commits on the branch that the main branch lacks, and vice versa.
+    /// None without a main branch to compare to.
+    pub ahead_behind: Option<(usize, usize)>,
+}
+
+/// How a name given on the command line matched a worktree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameMatch {
+    /// The worktree's directory name (handle) equals the name
+    Handle,
+    /// The branch checked out in the worktree equals the name
+    Branch,
+    /// The handle starts with or contains the name (`handle_matching`)
+    Partial,
+}
+
+/// The handle `workmux add` would derive for a branch, step by step
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandleDerivation {
+    pub naming: WorktreeNaming,
+    /// Branch name after applying the naming strategy
+    pub named: String,
+    pub prefix: Option<String>,
+    /// Final handle after the prefix and slugification, if it is valid
+    pub handle: Option<String>,
+}
+
+/// Everything that went into resolving a name, for `workmux which`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameResolution {
+    pub name: String,
+    /// Worktrees whose directory name is the name, in `git worktree list` order
+    pub handle_matches: Vec<(PathBuf, String)>,
+    /// Worktree that has the name checked out as its branch
+    pub branch_match: Option<(PathBuf, String)>,
+    /// Worktrees whose handle partially matches the name, when nothing matches
+    /// exactly and `handle_matching` allows it
+    pub partial_matches: Vec<(PathBuf, String)>,
+    pub derivation: HandleDerivation,
+    /// Worktree whose directory is the derived handle, if different from the name
+    pub derived_match: Option<(PathBuf, String)>,
+}
+
+impl NameResolution {
+    /// Whether the name matches more than one worktree, which commands refuse
+    /// to guess between
+    pub fn is_ambiguous(&self) -> bool {
+        let branch_elsewhere = self.branch_match.as_ref().is_some_and(|(path, _)| {
+            !self
+                .handle_matches
+                .iter()
+                .any(|(handle_path, _)| handle_path == path)
+        });
+        self.handle_matches.len() > 1
+            || (!self.handle_matches.is_empty() && branch_elsewhere)
+            || self.partial_matches.len() > 1
+    }
+
+    /// The worktree commands like `open` pick, unless the name is ambiguous
+    pub fn resolved(&self) -> Option<(&PathBuf, &String, NameMatch)> {
+        if self.is_ambiguous() {
+            return None;
+        }
+        if let Some((path, branch)) = self.handle_matches.first() {
+            return Some((path, branch, NameMatch::Handle));
+        }
+        if let Some((path, branch)) = &self.branch_match {
+            return Some((path, branch, NameMatch::Branch));
+        }
+        self.partial_matches
+            .first()
+            .map(|(path, branch)| (path, branch, NameMatch::Partial))
+    }
 }
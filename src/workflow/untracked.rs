@@ -0,0 +1,45 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+use crate::git;
+
+use super::types::UntrackedSize;
+
+/// Measure the untracked files (ignored ones included) in a worktree, listing
+/// those at least `min_size` bytes. Symlinks count as their own size, not their
+/// target's, since removing the worktree leaves the target alone.
+pub fn measure_untracked(worktree_path: &Path, min_size: u64) -> Result<UntrackedSize> {
+    let mut large = Vec::new();
+    let mut total = 0;
+    for entry in git::list_untracked_paths(worktree_path)? {
+        let size = disk_size(&worktree_path.join(&entry));
+        total += size;
+        if size >= min_size {
+            large.push((entry, size));
+        }
+    }
+    large.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    debug!(path = %worktree_path.display(), total, listed = large.len(), "untracked:measured");
+    Ok(UntrackedSize { large, total })
+}
+
+/// Apparent size of a file, or of everything below a directory. Unreadable
+/// entries count as empty.
+fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::{git, naming};
+
+use super::types::{HandleDerivation, NameResolution};
+
+/// Explain how `name` resolves to a worktree, using the same rules as
+/// `git::find_worktree`: a handle (directory name) or branch match, then a
+/// partial handle match if `handle_matching` allows, with names matching
+/// several worktrees reported as ambiguous.
+pub fn which(name: &str, config: &Config) -> Result<NameResolution> {
+    let worktrees = git::list_worktrees()?;
+    Ok(resolve(name, &worktrees, config))
+}
+
+fn resolve(name: &str, worktrees: &[(PathBuf, String)], config: &Config) -> NameResolution {
+    let by_handle = |handle: &str| {
+        worktrees
+            .iter()
+            .filter(|(path, _)| path.file_name().is_some_and(|dir| dir == handle))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let derivation = derive(name, config);
+    let derived_match = derivation
+        .handle
+        .as_deref()
+        .filter(|handle| *handle != name)
+        .and_then(|handle| by_handle(handle).into_iter().next());
+
+    let handle_matches = by_handle(name);
+    let branch_match = worktrees.iter().find(|(_, branch)| branch == name).cloned();
+    let partial_matches = if handle_matches.is_empty() && branch_match.is_none() {
+        git::match_handle_partially(worktrees, name, config.handle_matching())
+    } else {
+        Vec::new()
+    };
+
+    NameResolution {
+        name: name.to_string(),
+        handle_matches,
+        branch_match,
+        partial_matches,
+        derivation,
+        derived_match,
+    }
+}
+
+fn derive(branch: &str, config: &Config) -> HandleDerivation {
+    HandleDerivation {
+        naming: config.worktree_naming.clone(),
+        named: config.worktree_naming.derive_name(branch),
+        prefix: config.worktree_prefix.clone(),
+        handle: naming::derive_handle(branch, None, config).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HandleMatching, WorktreeNaming};
+    use crate::workflow::types::NameMatch;
+
+    fn worktrees() -> Vec<(PathBuf, String)> {
+        vec![
+            (PathBuf::from("/p"), "main".to_string()),
+            (PathBuf::from("/p__worktrees/auth"), "fix/login".to_string()),
+            (PathBuf::from("/p__worktrees/web-login"), "auth".to_string()),
+        ]
+    }
+
+    #[test]
+    fn handle_and_branch_of_different_worktrees_is_ambiguous() {
+        let resolution = resolve("auth", &worktrees(), &Config::default());
+        assert!(resolution.is_ambiguous());
+        assert!(resolution.resolved().is_none());
+        assert_eq!(
+            resolution.branch_match.unwrap().0,
+            PathBuf::from("/p__worktrees/web-login")
+        );
+
+        let resolution = resolve("fix/login", &worktrees(), &Config::default());
+        let (path, _, matched) = resolution.resolved().unwrap();
+        assert_eq!(path, &PathBuf::from("/p__worktrees/auth"));
+        assert_eq!(matched, NameMatch::Branch);
+    }
+
+    #[test]
+    fn partial_handle_matches_only_when_enabled_and_unique() {
+        let worktrees = vec![
+            (
+                PathBuf::from("/p__worktrees/web-feature-auth-oauth"),
+                "a".to_string(),
+            ),
+            (
+                PathBuf::from("/p__worktrees/web-feature-login"),
+                "b".to_string(),
+            ),
+            (PathBuf::from("/p__worktrees/api-login"), "c".to_string()),
+            (PathBuf::from("/p__worktrees/login-page"), "d".to_string()),
+        ];
+        let with = |handle_matching| Config {
+            handle_matching: Some(handle_matching),
+            ..Config::default()
+        };
+
+        assert!(
+            resolve("auth", &worktrees, &Config::default())
+                .resolved()
+                .is_none()
+        );
+        assert!(
+            resolve("auth", &worktrees, &with(HandleMatching::Prefix))
+                .resolved()
+                .is_none()
+        );
+
+        let resolution = resolve("auth", &worktrees, &with(HandleMatching::Substring));
+        let (path, _, matched) = resolution.resolved().unwrap();
+        assert_eq!(path, &PathBuf::from("/p__worktrees/web-feature-auth-oauth"));
+        assert_eq!(matched, NameMatch::Partial);
+
+        // A prefix match wins over handles that merely contain the name
+        let resolution = resolve("login", &worktrees, &with(HandleMatching::Substring));
+        let (path, _, _) = resolution.resolved().unwrap();
+        assert_eq!(path, &PathBuf::from("/p__worktrees/login-page"));
+
+        let resolution = resolve("web-feature", &worktrees, &with(HandleMatching::Prefix));
+        assert!(resolution.is_ambiguous());
+        let resolution = resolve("feature", &worktrees, &with(HandleMatching::Substring));
+        assert!(resolution.is_ambiguous());
+    }
+
+    #[test]
+    fn derived_handle_applies_naming_and_prefix() {
+        let config = Config {
+            worktree_naming: WorktreeNaming::Basename,
+            worktree_prefix: Some("web-".to_string()),
+            ..Config::default()
+        };
+        let resolution = resolve("prj/Login", &worktrees(), &config);
+        assert!(resolution.resolved().is_none());
+        assert_eq!(resolution.derivation.named, "Login");
+        assert_eq!(resolution.derivation.handle.as_deref(), Some("web-login"));
+        assert_eq!(
+            resolution.derived_match.unwrap().0,
+            PathBuf::from("/p__worktrees/web-login")
+        );
+    }
+}
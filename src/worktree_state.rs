@@ -0,0 +1,527 @@
+//! Metadata recorded for each worktree workmux creates.
+//!
+//! `workmux/<handle>.json` in the repository's common git directory (usually
+//! `.git/workmux/`) records what the worktree was created from: its base, the
+//! prompt and agent it started with, the PR it checks out, and a short title
+//! of its task. It is written when the worktree is created and removed
+//! together with the worktree. While its agent waits for input, it also holds
+//! what the agent's pane showed, while a merge of it waits on conflicts, how
+//! to finish that merge, and once its pane layout is saved, that layout.
+//!
+//! With `state_encryption` configured, the prompt and the agent's output are
+//! written encrypted (see [`crate::state_encryption`]).
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::cmd::Cmd;
+use crate::config::{Config, MergeStrategy};
+use crate::{dry_run, git, remote, state_encryption};
+
+const STATE_DIR: &str = "workmux";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorktreeState {
+    pub branch: String,
+    /// Branch or commit the branch was created from, if workmux created it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// The prompt the agent was started with, possibly encrypted (see
+    /// [`WorktreeState::revealed`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Where in the worktree, relative to its root, the prompt was written
+    /// with `save_prompt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Unix timestamp of the worktree's creation, if workmux created it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Pull or merge request checked out with `add --pr`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr: Option<u32>,
+    /// Checked out with `add --pr --review`, to review the pull request
+    /// rather than work on it. Opens with `pr.review_panes`, and isn't merged.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub review: bool,
+    /// Short title of the task, shown in tmux as `@workmux_task`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Template variables the worktree was created with (`num`, `--foreach`
+    /// values, `--var`s), for pane and hook commands run later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vars: Option<serde_json::Value>,
+    /// Set while the worktree's agent is waiting for input. Its output may
+    /// be encrypted, see [`WaitingReason::revealed`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting: Option<WaitingReason>,
+    /// Set while a `workmux merge` of the worktree is stopped on conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge: Option<PendingMerge>,
+    /// Set by `workmux pin`, which also locks the worktree in git
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+    /// tmux layout saved with `workmux layout save`, restored over
+    /// `panes.layout` when the worktree's window is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+}
+
+/// A merge stopped on conflicts, finished by `merge --continue`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingMerge {
+    /// Branch being merged, which a stopped rebase leaves checked out detached
+    pub branch: String,
+    /// Branch being merged into
+    pub target: String,
+    pub strategy: MergeStrategy,
+    /// Keep the worktree, window and branch once merged
+    #[serde(default)]
+    pub keep: bool,
+    /// Rendered `merge_message`, for the squash commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Pane running `conflict_command`, closed once the merge is done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pane: Option<String>,
+}
+
+/// What an agent's pane showed when it started waiting for input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaitingReason {
+    /// Unix timestamp of when the agent started waiting
+    pub since: u64,
+    /// The last lines of the pane, without colors
+    pub output: String,
+}
+
+impl WaitingReason {
+    pub fn new(output: String) -> Self {
+        Self {
+            since: now(),
+            output,
+        }
+    }
+
+    /// This reason with its output decrypted, or a placeholder for it while
+    /// the state is locked
+    pub fn revealed(self) -> Self {
+        Self {
+            output: state_encryption::reveal(&self.output).into_owned(),
+            ..self
+        }
+    }
+
+    /// The line most likely to say what the agent is asking: the last
+    /// question, else the last line with text, without box drawing
+    pub fn summary(&self) -> Option<&str> {
+        let lines: Vec<&str> = self
+            .output
+            .lines()
+            .map(|line| line.trim_matches(|c: char| c.is_whitespace() || BOX_CHARS.contains(c)))
+            .filter(|line| !line.is_empty())
+            .collect();
+        lines
+            .iter()
+            .rev()
+            .find(|line| line.ends_with('?'))
+            .or(lines.last())
+            .copied()
+    }
+}
+
+/// Border characters agents draw around prompts
+const BOX_CHARS: &str = "│─╭╮╰╯┌┐└┘├┤┬┴┼";
+
+impl WorktreeState {
+    /// State of a worktree being created now
+    pub fn new(branch: &str) -> Self {
+        Self {
+            branch: branch.to_string(),
+            created_at: Some(now()),
+            ..Self::default()
+        }
+    }
+
+    /// This state with the prompt and agent output decrypted, or
+    /// placeholders for them while the state is locked
+    pub fn revealed(self) -> Self {
+        Self {
+            prompt: self
+                .prompt
+                .map(|prompt| state_encryption::reveal(&prompt).into_owned()),
+            waiting: self.waiting.map(WaitingReason::revealed),
+            ..self
+        }
+    }
+
+    /// Whether any field is stored in plain text that encryption would cover
+    fn has_plain_secrets(&self) -> bool {
+        self.prompt
+            .iter()
+            .chain(self.waiting.iter().map(|waiting| &waiting.output))
+            .any(|value| !value.is_empty() && !state_encryption::is_encrypted(value))
+    }
+}
+
+/// Record the state of the worktree `handle`, replacing any earlier record,
+/// encrypted as `config` says
+pub fn save(handle: &str, state: &WorktreeState, config: &Config) -> Result<()> {
+    write(
+        &state_dir()?.join(format!("{}.json", handle)),
+        state,
+        config,
+    )
+}
+
+fn write(path: &Path, state: &WorktreeState, config: &Config) -> Result<()> {
+    let dir = path.parent().unwrap_or(path);
+    let content = match encrypted(state, config)? {
+        Some(state) => serde_json::to_string_pretty(&state)?,
+        None => serde_json::to_string_pretty(state)?,
+    };
+    if dry_run::skip(format_args!("write {}", path.display())) {
+        return Ok(());
+    }
+    if let Some(remote) = remote::get() {
+        Cmd::new("mkdir")
+            .args(&["-p", &dir.to_string_lossy()])
+            .run()
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        return remote.write_file(path, &content);
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    // Readers see the old state or the new one, never part of it
+    let staged = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&staged, content)
+        .and_then(|()| fs::rename(&staged, path))
+        .with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+/// `state` with its prompt and agent output encrypted, if `config` turns
+/// encryption on and they aren't already
+fn encrypted(state: &WorktreeState, config: &Config) -> Result<Option<WorktreeState>> {
+    if !state.has_plain_secrets() {
+        return Ok(None);
+    }
+    let Some(settings) = state_encryption::settings(config) else {
+        return Ok(None);
+    };
+    let mut state = state.clone();
+    if let Some(prompt) = &state.prompt {
+        state.prompt = Some(state_encryption::encrypt(prompt, settings)?);
+    }
+    if let Some(waiting) = &mut state.waiting {
+        waiting.output = state_encryption::encrypt(&waiting.output, settings)?;
+    }
+    Ok(Some(state))
+}
+
+/// The recorded state of the worktree `handle`, or None for worktrees
+/// workmux didn't create (or created before it recorded state).
+pub fn load(handle: &str) -> Result<Option<WorktreeState>> {
+    read(&state_dir()?.join(format!("{}.json", handle)))
+}
+
+/// The recorded state of the worktree containing `path`, in any repository
+pub fn load_for_path(path: &Path) -> Option<WorktreeState> {
+    read(&state_file_for_path(path)?).ok().flatten()
+}
+
+/// Record or forget what the agent of the worktree containing `path` is
+/// waiting for. A worktree without state gets one, without a creation time.
+/// Nothing is written if the agent shows what it showed before.
+pub fn set_waiting(path: &Path, waiting: Option<WaitingReason>, config: &Config) -> Result<()> {
+    let file = state_file_for_path(path)
+        .ok_or_else(|| anyhow!("{} is not in a git worktree", path.display()))?;
+    // Status hooks of the worktree's agents run concurrently, and other
+    // commands update the rest of the state
+    let _lock = lock(&file)?;
+    let mut state = match read(&file)? {
+        Some(state) => state,
+        None if waiting.is_none() => return Ok(()),
+        None => WorktreeState {
+            branch: Cmd::new("git")
+                .workdir(path)
+                .args(&["branch", "--show-current"])
+                .run_and_capture_stdout()?,
+            ..WorktreeState::default()
+        },
+    };
+    let unchanged = match (&state.waiting, &waiting) {
+        (None, None) => true,
+        (Some(old), Some(new)) => state_encryption::reveal(&old.output) == new.output,
+        _ => false,
+    };
+    if unchanged {
+        return Ok(());
+    }
+    state.waiting = waiting;
+    write(&file, &state, config)
+}
+
+/// Hold an exclusive lock for the state file at `path` until the returned
+/// file is dropped. State on a remote host isn't locked.
+fn lock(path: &Path) -> Result<Option<fs::File>> {
+    if remote::get().is_some() || dry_run::is_enabled() {
+        return Ok(None);
+    }
+    let dir = path.parent().unwrap_or(path);
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let lock_path = path.with_extension("lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open '{}'", lock_path.display()))?;
+    // SAFETY: flock only acts on the descriptor, which `file` keeps open
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to lock '{}'", lock_path.display()));
+    }
+    Ok(Some(file))
+}
+
+/// Rewrite the state of the worktree `handle` so encryption covers it,
+/// returning whether it had anything in plain text
+pub fn encrypt_existing(handle: &str, config: &Config) -> Result<bool> {
+    match load(handle)? {
+        Some(state) if state.has_plain_secrets() => {
+            save(handle, &state, config)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Whether the worktree `handle` is pinned, which keeps it out of batch
+/// removals and syncs
+pub fn is_pinned(handle: &str) -> bool {
+    load(handle)
+        .ok()
+        .flatten()
+        .is_some_and(|state| state.pinned)
+}
+
+/// Whether the worktree `handle` was checked out with `add --pr --review`
+pub fn is_review(handle: &str) -> bool {
+    load(handle)
+        .ok()
+        .flatten()
+        .is_some_and(|state| state.review)
+}
+
+/// Record whether the worktree `handle` of `branch` is pinned. A worktree
+/// without state gets one, without a creation time.
+pub fn set_pinned(handle: &str, branch: &str, pinned: bool, config: &Config) -> Result<()> {
+    let mut state = match load(handle)? {
+        Some(state) => state,
+        None if !pinned => return Ok(()),
+        None => WorktreeState {
+            branch: branch.to_string(),
+            ..WorktreeState::default()
+        },
+    };
+    state.pinned = pinned;
+    save(handle, &state, config)
+}
+
+/// Record the pane layout to restore for the worktree `handle` of `branch`,
+/// or forget it with None. A worktree without state gets one, without a
+/// creation time.
+pub fn set_layout(
+    handle: &str,
+    branch: &str,
+    layout: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    let mut state = match load(handle)? {
+        Some(state) => state,
+        None if layout.is_none() => return Ok(()),
+        None => WorktreeState {
+            branch: branch.to_string(),
+            ..WorktreeState::default()
+        },
+    };
+    state.layout = layout;
+    save(handle, &state, config)
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// State file of the worktree containing `path`
+fn state_file_for_path(path: &Path) -> Option<PathBuf> {
+    let output = Cmd::new("git")
+        .workdir(path)
+        .args(&[
+            "rev-parse",
+            "--path-format=absolute",
+            "--show-toplevel",
+            "--git-common-dir",
+        ])
+        .run_and_capture_stdout()
+        .ok()?;
+    let mut lines = output.lines();
+    let root = PathBuf::from(lines.next()?);
+    let common_dir = PathBuf::from(lines.next()?);
+    let handle = root.file_name()?.to_string_lossy();
+    Some(common_dir.join(STATE_DIR).join(format!("{}.json", handle)))
+}
+
+fn read(path: &Path) -> Result<Option<WorktreeState>> {
+    let content = match remote::get() {
+        Some(remote) => remote.read_file(path)?,
+        None => match fs::read_to_string(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read '{}'", path.display()));
+            }
+        },
+    };
+    content
+        .map(|content| {
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid worktree state in '{}'", path.display()))
+        })
+        .transpose()
+}
+
+/// Forget the state of a removed worktree. Failing to is logged, not returned.
+pub fn remove(handle: &str) {
+    let result = state_dir().and_then(|dir| {
+        let path = dir.join(format!("{}.json", handle));
+        if dry_run::skip(format_args!("remove {}", path.display())) {
+            return Ok(());
+        }
+        if remote::get().is_some() {
+            Cmd::new("rm")
+                .args(&["-f", &path.to_string_lossy()])
+                .run()?;
+            return Ok(());
+        }
+        // The lock file goes too, as nothing updates a removed worktree's state
+        for file in [path.clone(), path.with_extension("lock")] {
+            match fs::remove_file(&file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to remove '{}'", file.display()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!(handle, error = %e, "worktree_state:failed to remove state file");
+    }
+}
+
+/// The base the worktree's branch was created from: the recorded one, else
+/// the one in git config, for worktrees created before state was recorded.
+pub fn base_branch(handle: &str, branch: &str) -> Option<String> {
+    load(handle)
+        .ok()
+        .flatten()
+        .and_then(|state| state.base)
+        .or_else(|| git::get_branch_base(branch).ok())
+}
+
+fn state_dir() -> Result<PathBuf> {
+    Ok(git::get_git_common_dir()?.join(STATE_DIR))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, WaitingReason, WorktreeState, read, set_waiting, state_file_for_path};
+
+    #[test]
+    fn optional_fields_are_left_out() {
+        let state = WorktreeState {
+            branch: "feature".to_string(),
+            base: Some("main".to_string()),
+            created_at: Some(1_700_000_000),
+            ..WorktreeState::default()
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(
+            json,
+            r#"{"branch":"feature","base":"main","created_at":1700000000}"#
+        );
+        assert_eq!(serde_json::from_str::<WorktreeState>(&json).unwrap(), state);
+    }
+
+    #[test]
+    fn pinned_is_written_only_when_set() {
+        let state = WorktreeState {
+            branch: "docs".to_string(),
+            pinned: true,
+            ..WorktreeState::default()
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(json, r#"{"branch":"docs","pinned":true}"#);
+        assert_eq!(serde_json::from_str::<WorktreeState>(&json).unwrap(), state);
+    }
+
+    #[test]
+    fn waiting_summary_prefers_the_question() {
+        let reason = |output: &str| WaitingReason {
+            since: 0,
+            output: output.to_string(),
+        };
+        let prompt = reason(
+            "╭──────────────╮\n│ Bash command │\n│ rm -rf build │\n│ Do you want to proceed? │\n│ ❯ 1. Yes │\n│   2. No │\n╰──────────────╯\n  Esc to cancel\n\n",
+        );
+        assert_eq!(prompt.summary(), Some("Do you want to proceed?"));
+        assert_eq!(
+            reason("Build failed\nShould I retry?\n> \n").summary(),
+            Some("Should I retry?")
+        );
+        assert_eq!(reason("done\n> waiting\n").summary(), Some("> waiting"));
+        assert_eq!(reason("\n  \n").summary(), None);
+    }
+
+    #[test]
+    fn set_waiting_keeps_the_first_time_the_same_output_was_shown() {
+        let repo = tempfile::tempdir().unwrap();
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["init", "--quiet"])
+            .status()
+            .unwrap();
+        let file = state_file_for_path(repo.path()).unwrap();
+        let waiting = |since: u64, output: &str| WaitingReason {
+            since,
+            output: output.to_string(),
+        };
+        let recorded = || read(&file).unwrap().unwrap().waiting;
+        let config = Config::default();
+
+        set_waiting(repo.path(), Some(waiting(10, "Proceed?")), &config).unwrap();
+        set_waiting(repo.path(), Some(waiting(20, "Proceed?")), &config).unwrap();
+        assert_eq!(recorded(), Some(waiting(10, "Proceed?")));
+
+        set_waiting(repo.path(), Some(waiting(30, "Overwrite?")), &config).unwrap();
+        assert_eq!(recorded(), Some(waiting(30, "Overwrite?")));
+        set_waiting(repo.path(), None, &config).unwrap();
+        assert_eq!(recorded(), None);
+    }
+}
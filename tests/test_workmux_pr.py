@@ -283,3 +283,46 @@ def test_add_pr_fails_when_worktree_exists(
     assert (
         "already exists" in result.stderr.lower() or "worktree" in result.stderr.lower()
     )
+
+
+def test_add_pr_review_checks_out_detached(
+    isolated_tmux_server, workmux_exe_path, remote_repo_path
+):
+    """Test that --review checks out the PR without creating a local branch"""
+    env = isolated_tmux_server
+    repo_path = env.tmp_path
+    setup_git_repo(repo_path, env.env)
+
+    setup_pr_remote_and_branch(env, repo_path, remote_repo_path, "feature-branch")
+
+    pr_data = {
+        "headRefName": "feature-branch",
+        "headRepositoryOwner": {"login": "testowner"},
+        "state": "OPEN",
+        "isDraft": False,
+        "title": "Add new feature",
+        "author": {"login": "contributor"},
+        "baseRefName": "main",
+    }
+    install_fake_gh_cli(env, pr_number=123, json_response=pr_data)
+
+    run_workmux_command(env, workmux_exe_path, repo_path, "add --pr 123 --review")
+
+    worktree_path = get_worktree_path(repo_path, "feature-branch")
+    assert worktree_path.exists()
+
+    branches = env.run_command(
+        ["git", "branch", "--list", "feature-branch"], cwd=repo_path
+    )
+    assert branches.stdout.strip() == ""
+
+    head = env.run_command(
+        ["git", "symbolic-ref", "-q", "HEAD"], cwd=worktree_path, check=False
+    )
+    assert head.returncode != 0
+
+    # A review worktree is removed, not merged
+    result = run_workmux_command(
+        env, workmux_exe_path, repo_path, "merge feature-branch", expect_fail=True
+    )
+    assert "reviewing a pull request" in result.stderr